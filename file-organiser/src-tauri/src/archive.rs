@@ -0,0 +1,357 @@
+//! Zip archive inspection, extraction and creation
+//!
+//! Lecture materials often arrive as a single `.zip`. This module lists an archive's
+//! entries (used to classify it by what's inside) and extracts it, rejecting any entry
+//! whose name would land outside the destination folder (zip-slip). It also compresses
+//! whole folders for end-of-term cleanup.
+
+use crate::CommandError;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// Refuse to extract archives that expand beyond this (guards against zip bombs)
+const MAX_EXTRACTED_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// A single entry in an archive listing
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub is_dir: bool,
+}
+
+fn open(path: &Path) -> Result<ZipArchive<File>, CommandError> {
+    let file = File::open(path)?;
+    ZipArchive::new(file).map_err(|e| CommandError::InvalidPath(format!("Not a readable zip archive: {}", e)))
+}
+
+/// List every entry in a zip archive
+pub fn list_entries(path: &Path) -> Result<Vec<ArchiveEntry>, CommandError> {
+    let mut archive = open(path)?;
+    let mut entries = Vec::with_capacity(archive.len());
+
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| CommandError::IoError(format!("Failed to read archive entry: {}", e)))?;
+        entries.push(ArchiveEntry {
+            name: entry.name().to_string(),
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            is_dir: entry.is_dir(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Resolve an entry name to a path inside dest_dir, rejecting anything that escapes it
+///
+/// Same rules as `validate_path`: no `..` components, and no absolute paths or drive prefixes.
+pub fn safe_entry_path(dest_dir: &Path, entry_name: &str) -> Result<PathBuf, CommandError> {
+    let relative = Path::new(entry_name);
+    let mut resolved = dest_dir.to_path_buf();
+
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(CommandError::PathTraversal)
+            }
+        }
+    }
+
+    // Backslashes aren't separators on Unix, but zips made on Windows may still use them
+    if entry_name.contains("..\\") || entry_name.starts_with('\\') {
+        return Err(CommandError::PathTraversal);
+    }
+
+    Ok(resolved)
+}
+
+/// Extract an archive into dest_dir, returning the paths of the extracted files
+///
+/// Every entry is checked before anything is written: unsafe names, existing files and
+/// oversized archives abort the extraction without touching the destination.
+pub fn extract(path: &Path, dest_dir: &Path) -> Result<Vec<PathBuf>, CommandError> {
+    let mut archive = open(path)?;
+    let mut planned = Vec::with_capacity(archive.len());
+    let mut total_size = 0u64;
+
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| CommandError::IoError(format!("Failed to read archive entry: {}", e)))?;
+        let target = safe_entry_path(dest_dir, entry.name())?;
+        if !entry.is_dir() && target.exists() {
+            return Err(CommandError::DuplicateExists(target.display().to_string()));
+        }
+        total_size = total_size.saturating_add(entry.size());
+        planned.push((i, target, entry.is_dir()));
+    }
+
+    if total_size > MAX_EXTRACTED_BYTES {
+        return Err(CommandError::IoError(format!(
+            "Archive expands to {} bytes, more than the {} byte limit",
+            total_size, MAX_EXTRACTED_BYTES
+        )));
+    }
+
+    let mut extracted = Vec::new();
+    for (i, target, is_dir) in planned {
+        if is_dir {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| CommandError::IoError(format!("Failed to read archive entry: {}", e)))?;
+        let mut out = File::create(&target)?;
+        io::copy(&mut entry, &mut out)?;
+        extracted.push(target);
+    }
+
+    Ok(extracted)
+}
+
+/// Compress a folder (recursively) into a new zip file, returning the number of files added
+///
+/// Entry names are relative to the folder and always use `/`. The zip is re-opened and its
+/// file count checked before returning, so callers can safely remove the originals afterwards.
+/// A partially written zip is deleted on failure.
+pub fn create(folder: &Path, dest_zip: &Path) -> Result<usize, CommandError> {
+    if dest_zip.exists() {
+        return Err(CommandError::DuplicateExists(dest_zip.display().to_string()));
+    }
+    if dest_zip.starts_with(folder) {
+        return Err(CommandError::InvalidPath(
+            "The archive can't be saved inside the folder being archived".to_string(),
+        ));
+    }
+
+    let result = write_folder(folder, dest_zip).and_then(|count| {
+        let written = list_entries(dest_zip)?.iter().filter(|e| !e.is_dir).count();
+        if written != count {
+            return Err(CommandError::IoError(format!(
+                "Archive verification failed: expected {} files, found {}",
+                count, written
+            )));
+        }
+        Ok(count)
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(dest_zip);
+    }
+    result
+}
+
+fn write_folder(folder: &Path, dest_zip: &Path) -> Result<usize, CommandError> {
+    let mut writer = ZipWriter::new(File::create(dest_zip)?);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    let mut count = 0;
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            let relative = path
+                .strip_prefix(folder)
+                .map_err(|_| CommandError::InvalidPath(path.display().to_string()))?;
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if path.is_dir() {
+                writer
+                    .add_directory(name, options)
+                    .map_err(|e| CommandError::IoError(format!("Failed to write archive: {}", e)))?;
+                pending.push(path);
+            } else if path.is_file() {
+                writer
+                    .start_file(name, options)
+                    .map_err(|e| CommandError::IoError(format!("Failed to write archive: {}", e)))?;
+                io::copy(&mut File::open(&path)?, &mut writer)?;
+                count += 1;
+            }
+        }
+    }
+
+    writer
+        .finish()
+        .map_err(|e| CommandError::IoError(format!("Failed to finish archive: {}", e)))?;
+    Ok(count)
+}
+
+/// Summarize an archive's contents as text for the classifier
+pub fn describe_for_classification(entries: &[ArchiveEntry], max_names: usize) -> String {
+    let files: Vec<&str> = entries
+        .iter()
+        .filter(|e| !e.is_dir)
+        .map(|e| e.name.as_str())
+        .collect();
+
+    let mut text = format!("Zip archive containing {} files:\n", files.len());
+    for name in files.iter().take(max_names) {
+        text.push_str(name);
+        text.push('\n');
+    }
+    if files.len() > max_names {
+        text.push_str(&format!("... and {} more\n", files.len() - max_names));
+    }
+    text
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn make_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, contents) in entries {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_list_entries() {
+        let tmp = std::env::temp_dir().join("fileorg_test_archive_list");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let zip_path = tmp.join("week3.zip");
+        make_zip(&zip_path, &[("lecture3.pdf", "pdf"), ("slides/intro.pptx", "pptx")]);
+
+        let entries = list_entries(&zip_path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "lecture3.pdf");
+        assert_eq!(entries[0].size, 3);
+        assert_eq!(entries[1].name, "slides/intro.pptx");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_extract_writes_files() {
+        let tmp = std::env::temp_dir().join("fileorg_test_archive_extract");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let zip_path = tmp.join("week3.zip");
+        make_zip(&zip_path, &[("lecture3.pdf", "pdf"), ("slides/intro.pptx", "pptx")]);
+
+        let dest = tmp.join("out");
+        let extracted = extract(&zip_path, &dest).unwrap();
+        assert_eq!(extracted.len(), 2);
+        assert_eq!(fs::read_to_string(dest.join("slides").join("intro.pptx")).unwrap(), "pptx");
+
+        // Extracting again would overwrite, so it's refused
+        assert!(matches!(extract(&zip_path, &dest).unwrap_err(), CommandError::DuplicateExists(_)));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_extract_rejects_zip_slip() {
+        let tmp = std::env::temp_dir().join("fileorg_test_archive_slip");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let zip_path = tmp.join("evil.zip");
+        make_zip(&zip_path, &[("ok.txt", "fine"), ("../escaped.txt", "bad")]);
+
+        let dest = tmp.join("out");
+        assert!(matches!(extract(&zip_path, &dest).unwrap_err(), CommandError::PathTraversal));
+        assert!(!tmp.join("escaped.txt").exists());
+        assert!(!dest.join("ok.txt").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_safe_entry_path() {
+        let dest = Path::new("/tmp/out");
+        assert_eq!(safe_entry_path(dest, "a/b.txt").unwrap(), dest.join("a").join("b.txt"));
+        assert_eq!(safe_entry_path(dest, "./a.txt").unwrap(), dest.join("a.txt"));
+        assert!(safe_entry_path(dest, "../a.txt").is_err());
+        assert!(safe_entry_path(dest, "a/../../b.txt").is_err());
+        assert!(safe_entry_path(dest, "/etc/passwd").is_err());
+        assert!(safe_entry_path(dest, "..\\windows\\system.ini").is_err());
+    }
+
+    #[test]
+    fn test_describe_for_classification() {
+        let entries: Vec<ArchiveEntry> = ["a.pdf", "b.pdf", "c.pdf"]
+            .iter()
+            .map(|n| ArchiveEntry {
+                name: n.to_string(),
+                size: 1,
+                compressed_size: 1,
+                is_dir: false,
+            })
+            .collect();
+
+        let text = describe_for_classification(&entries, 2);
+        assert!(text.starts_with("Zip archive containing 3 files"));
+        assert!(text.contains("a.pdf\nb.pdf\n"));
+        assert!(text.contains("... and 1 more"));
+    }
+
+    #[test]
+    fn test_create_roundtrip() {
+        let tmp = std::env::temp_dir().join("fileorg_test_archive_create");
+        let _ = fs::remove_dir_all(&tmp);
+        let course = tmp.join("Year1").join("Physics");
+        fs::create_dir_all(course.join("Week1")).unwrap();
+        fs::write(course.join("syllabus.pdf"), "syllabus").unwrap();
+        fs::write(course.join("Week1").join("lecture1.pdf"), "lecture").unwrap();
+
+        let zip_path = tmp.join("Physics.zip");
+        assert_eq!(create(&course, &zip_path).unwrap(), 2);
+
+        let out = tmp.join("restored");
+        extract(&zip_path, &out).unwrap();
+        assert_eq!(fs::read_to_string(out.join("syllabus.pdf")).unwrap(), "syllabus");
+        assert_eq!(fs::read_to_string(out.join("Week1").join("lecture1.pdf")).unwrap(), "lecture");
+
+        // Refuses to overwrite an existing archive
+        assert!(matches!(create(&course, &zip_path).unwrap_err(), CommandError::DuplicateExists(_)));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_create_rejects_zip_inside_folder() {
+        let tmp = std::env::temp_dir().join("fileorg_test_archive_inside");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(tmp.join("a.txt"), "a").unwrap();
+
+        let result = create(&tmp, &tmp.join("self.zip"));
+        assert!(matches!(result.unwrap_err(), CommandError::InvalidPath(_)));
+        assert!(!tmp.join("self.zip").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,139 @@
+//! Tamper-evident audit log of file operations
+//!
+//! Every move, rename, replace, copy, trash, undo and redo is appended to the `audit_log`
+//! table, never updated or deleted. Each entry's hash covers its own fields and the
+//! previous entry's hash, so editing, removing or reordering any entry breaks the chain
+//! from that point on. Removing entries from the end can't be detected from the table
+//! alone; compare `head_hash` with a copy kept elsewhere for that.
+
+use serde::{Deserialize, Serialize};
+
+/// The "previous hash" of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One recorded operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String, // "move", "rename", "replace", "copy", "trash", "undo", "redo"
+    pub before_path: String,
+    pub after_path: Option<String>, // None when the file left the filesystem (trash)
+    pub created_at: i64,            // Unix timestamp ms
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// What checking the chain found
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditVerification {
+    pub ok: bool,
+    pub entries: usize,
+    pub head_hash: String,        // hash of the newest entry (GENESIS_HASH when empty)
+    pub broken_at: Option<i64>,   // id of the first entry that doesn't check out
+    pub problem: Option<String>,
+}
+
+/// The hash of an entry, chained onto `prev_hash`
+///
+/// Fields are length-prefixed so no two different entries hash the same input.
+pub fn entry_hash(
+    id: i64,
+    prev_hash: &str,
+    action: &str,
+    before_path: &str,
+    after_path: Option<&str>,
+    created_at: i64,
+) -> String {
+    let mut hasher = blake3::Hasher::new();
+    let fields = [
+        id.to_string(),
+        prev_hash.to_string(),
+        action.to_string(),
+        before_path.to_string(),
+        after_path.map_or_else(|| "-".to_string(), |p| format!("+{}", p)),
+        created_at.to_string(),
+    ];
+    for field in &fields {
+        hasher.update(&(field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Check the chain of `entries` (oldest first)
+pub fn verify(entries: &[AuditEntry]) -> AuditVerification {
+    let mut prev_hash = GENESIS_HASH;
+    let mut prev_id = 0;
+    for entry in entries {
+        let problem = if entry.id != prev_id + 1 {
+            Some(format!("Entries {} to {} are missing", prev_id + 1, entry.id - 1))
+        } else if entry.prev_hash != prev_hash {
+            Some("Doesn't follow on from the entry before it".to_string())
+        } else if entry.hash
+            != entry_hash(entry.id, &entry.prev_hash, &entry.action, &entry.before_path, entry.after_path.as_deref(), entry.created_at)
+        {
+            Some("Its contents were changed after it was recorded".to_string())
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            return AuditVerification {
+                ok: false,
+                entries: entries.len(),
+                head_hash: entries.last().map_or(GENESIS_HASH, |e| &e.hash).to_string(),
+                broken_at: Some(entry.id),
+                problem: Some(problem),
+            };
+        }
+        prev_hash = &entry.hash;
+        prev_id = entry.id;
+    }
+    AuditVerification {
+        ok: true,
+        entries: entries.len(),
+        head_hash: prev_hash.to_string(),
+        broken_at: None,
+        problem: None,
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_audit_chain() {
+        let tmp = std::env::temp_dir().join("fileorg_test_audit");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let db = Database::new(tmp.join("test.db")).unwrap();
+
+        assert!(verify(&db.get_audit_log().unwrap()).ok, "an empty log verifies");
+        db.append_audit("move", "/Downloads/a.pdf", Some("/Uni/ML/a.pdf"), 1000).unwrap();
+        db.append_audit("rename", "/Uni/ML/a.pdf", Some("/Uni/ML/Lecture_1.pdf"), 2000).unwrap();
+        let last = db.append_audit("trash", "/Downloads/b.exe", None, 3000).unwrap();
+
+        let entries = db.get_audit_log().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        let verification = verify(&entries);
+        assert!(verification.ok);
+        assert_eq!(verification.head_hash, last.hash);
+
+        // Rewriting where a file went
+        let mut edited = entries.clone();
+        edited[1].after_path = Some("/Elsewhere/a.pdf".to_string());
+        assert_eq!(verify(&edited).broken_at, Some(2));
+
+        // Removing an entry from the middle
+        let removed = vec![entries[0].clone(), entries[2].clone()];
+        assert_eq!(verify(&removed).broken_at, Some(3));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,187 @@
+//! Type-bucket classification (no AI)
+//!
+//! A zero-cost fallback for when there's no API budget left: files are sorted into broad
+//! buckets (Documents, Images, Archives, Installers, Media) based on the magic-byte
+//! detector, so a messy Downloads folder still gets tidied without any API calls.
+
+use crate::classifier::Classification;
+use crate::filetype::{self, FileType};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How files without a rule or cached folder are classified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassificationMode {
+    #[default]
+    Ai,
+    Ocr, // like Ai, but images are read with local OCR and never sent for vision
+    Buckets,
+}
+
+impl ClassificationMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ai" => Some(ClassificationMode::Ai),
+            "ocr" => Some(ClassificationMode::Ocr),
+            "buckets" => Some(ClassificationMode::Buckets),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClassificationMode::Ai => "ai",
+            ClassificationMode::Ocr => "ocr",
+            ClassificationMode::Buckets => "buckets",
+        }
+    }
+}
+
+/// Broad file-type buckets, each mapped to a folder of the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Documents,
+    Images,
+    Archives,
+    Installers,
+    Media,
+}
+
+impl Bucket {
+    pub fn folder_name(&self) -> &'static str {
+        match self {
+            Bucket::Documents => "Documents",
+            Bucket::Images => "Images",
+            Bucket::Archives => "Archives",
+            Bucket::Installers => "Installers",
+            Bucket::Media => "Media",
+        }
+    }
+}
+
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "csv", "xls", "xlsx", "ods", "ppt", "pptx",
+    "odp", "epub", "tex", "pages", "key", "numbers",
+];
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst"];
+const INSTALLER_EXTENSIONS: &[&str] = &["exe", "msi", "dmg", "pkg", "deb", "rpm", "appimage", "apk"];
+
+/// Which bucket a detected file type belongs in (None for types we don't recognize)
+pub fn bucket_for(file_type: &FileType) -> Option<Bucket> {
+    let ext = file_type.extension.as_str();
+    let mime = file_type.mime_type.as_str();
+
+    if INSTALLER_EXTENSIONS.contains(&ext) || mime == "application/vnd.microsoft.portable-executable" {
+        Some(Bucket::Installers)
+    } else if DOCUMENT_EXTENSIONS.contains(&ext) {
+        Some(Bucket::Documents)
+    } else if ARCHIVE_EXTENSIONS.contains(&ext) {
+        Some(Bucket::Archives)
+    } else if mime.starts_with("image/") {
+        Some(Bucket::Images)
+    } else if mime.starts_with("video/") || mime.starts_with("audio/") {
+        Some(Bucket::Media)
+    } else {
+        None
+    }
+}
+
+/// Classify a file into `<base_folder>/<Bucket>` from its detected type
+pub fn classify(path: &Path, base_folder: &Path) -> Classification {
+    let file_type = filetype::detect(path);
+    let label = if file_type.extension.is_empty() {
+        "unknown".to_string()
+    } else {
+        file_type.extension.to_uppercase()
+    };
+
+    match bucket_for(&file_type) {
+        Some(bucket) => Classification {
+            is_relevant: true,
+            suggested_folder: base_folder.join(bucket.folder_name()).to_string_lossy().to_string(),
+            // The name can lie; the content signature can't
+            confidence: if file_type.from_content { 1.0 } else { 0.8 },
+            reasoning: format!(
+                "{} file ({}) goes in {}",
+                label,
+                if file_type.from_content { "detected from content" } else { "by extension" },
+                bucket.folder_name()
+            ),
+            suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
+        },
+        None => Classification {
+            is_relevant: true,
+            suggested_folder: "__UNSORTED__".to_string(),
+            confidence: 0.0,
+            reasoning: format!("No bucket for {} files", label),
+            suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
+        },
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn file_type(extension: &str, mime_type: &str) -> FileType {
+        FileType {
+            extension: extension.to_string(),
+            mime_type: mime_type.to_string(),
+            from_content: true,
+        }
+    }
+
+    #[test]
+    fn test_bucket_for() {
+        assert_eq!(bucket_for(&file_type("pdf", "application/pdf")), Some(Bucket::Documents));
+        assert_eq!(bucket_for(&file_type("png", "image/png")), Some(Bucket::Images));
+        assert_eq!(bucket_for(&file_type("zip", "application/zip")), Some(Bucket::Archives));
+        assert_eq!(
+            bucket_for(&file_type("exe", "application/vnd.microsoft.portable-executable")),
+            Some(Bucket::Installers)
+        );
+        assert_eq!(bucket_for(&file_type("mp4", "video/mp4")), Some(Bucket::Media));
+        assert_eq!(bucket_for(&file_type("mp3", "audio/mpeg")), Some(Bucket::Media));
+        assert_eq!(bucket_for(&file_type("", "application/octet-stream")), None);
+    }
+
+    #[test]
+    fn test_classify_uses_content() {
+        let tmp = std::env::temp_dir().join("fileorg_test_buckets");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        // A PDF saved with a misleading name still lands in Documents
+        let pdf = tmp.join("download.bin");
+        fs::write(&pdf, b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj\n").unwrap();
+        let result = classify(&pdf, &tmp);
+        assert_eq!(result.suggested_folder, tmp.join("Documents").to_string_lossy());
+        assert_eq!(result.confidence, 1.0);
+
+        let blob = tmp.join("blob");
+        fs::write(&blob, [0u8, 159, 146, 150]).unwrap();
+        assert_eq!(classify(&blob, &tmp).suggested_folder, "__UNSORTED__");
+
+        assert_eq!(ClassificationMode::parse("buckets"), Some(ClassificationMode::Buckets));
+        assert_eq!(ClassificationMode::Ai.as_str(), "ai");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
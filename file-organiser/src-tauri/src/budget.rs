@@ -0,0 +1,308 @@
+//! Monthly API spend budget
+//!
+//! Chat completions and embeddings report the tokens they used. Each call is priced by its
+//! model (see PRICES) and recorded in the `api_usage` table, and this month's total is kept
+//! in memory. Once it passes `downgrade_at` of the limit, paid classification requests switch
+//! to the cheaper `downgrade_model`; once it reaches the limit, only the free tiers of the
+//! fallback chain (Ollama, a local endpoint, the offline keyword classifier) are asked until
+//! the month is over. Reaching the limit is reported once a month.
+//!
+//! Calls to this computer or the local network (Ollama, a local endpoint) never count against
+//! the budget. Any other model missing from the price list is charged at UNKNOWN_MODEL_PRICE,
+//! so a new or renamed model can't spend past the limit unnoticed.
+//!
+//! Repair requests (sent when a model's reply isn't valid JSON) are recorded too, marked with
+//! whether they worked, so the table also shows how reliable each model is.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+
+pub const DEFAULT_DOWNGRADE_MODEL: &str = "gpt-4o-mini";
+pub const DEFAULT_DOWNGRADE_AT: f64 = 0.8;
+
+// US dollars per million tokens (input, output), as listed by the providers. Matched by
+// prefix in this order, so dated versions ("gpt-4o-2024-08-06") find their model.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-4", 30.00, 60.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-1.5-flash", 0.075, 0.30),
+    ("text-embedding-3-small", 0.02, 0.0),
+    ("text-embedding-3-large", 0.13, 0.0),
+];
+
+// What a paid model missing from PRICES is charged: as much as the priciest listed one
+pub const UNKNOWN_MODEL_PRICE: (f64, f64) = (30.00, 60.00);
+
+/// The user's monthly limit and what happens on the way to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetSettings {
+    pub monthly_limit_usd: f64, // 0: no limit
+    pub downgrade_model: String,
+    pub downgrade_at: f64, // share of the limit after which the cheaper model is used
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        BudgetSettings {
+            monthly_limit_usd: 0.0,
+            downgrade_model: DEFAULT_DOWNGRADE_MODEL.to_string(),
+            downgrade_at: DEFAULT_DOWNGRADE_AT,
+        }
+    }
+}
+
+impl BudgetSettings {
+    /// Check the budget before it's saved
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.monthly_limit_usd.is_finite() || self.monthly_limit_usd < 0.0 {
+            return Err(format!("Monthly limit must be 0 or more (got {})", self.monthly_limit_usd));
+        }
+        if !(0.0..=1.0).contains(&self.downgrade_at) {
+            return Err(format!("Downgrade point must be between 0 and 1 (got {})", self.downgrade_at));
+        }
+        if price(&self.downgrade_model).is_none() {
+            return Err(format!("No price is known for \"{}\"", self.downgrade_model));
+        }
+        Ok(())
+    }
+}
+
+/// Where this month's spending stands against the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetState {
+    Normal,
+    Downgraded, // past downgrade_at: paid tiers use the cheaper model
+    Exceeded,   // at the limit: only free tiers are asked
+}
+
+/// The budget, this month's spending and its effect, for the settings screen and events
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    pub settings: BudgetSettings,
+    pub month: String, // e.g. "2026-10"
+    pub spent_usd: f64,
+    pub state: BudgetState,
+}
+
+/// One priced API call, as stored in the `api_usage` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiUsage {
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+    pub created_at: i64, // Unix timestamp ms
+    #[serde(default)]
+    pub repaired: Option<bool>, // for repair requests: whether the reply could then be read
+}
+
+/// How often a model's replies had to be repaired, from the `api_usage` table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelReliability {
+    pub model: String,
+    pub calls: u64,    // ordinary requests
+    pub repairs: u64,  // replies that weren't valid JSON and were sent back
+    pub repaired: u64, // repairs that then returned valid JSON
+}
+
+struct Ledger {
+    month: String,
+    spent: f64,
+    reported: bool, // reaching the limit was already announced this month
+}
+
+type RecordHook = Box<dyn Fn(ApiUsage) + Send + Sync>;
+type ExceededHook = Box<dyn Fn(BudgetStatus) + Send + Sync>;
+
+static SETTINGS: Mutex<Option<BudgetSettings>> = Mutex::new(None);
+static LEDGER: Mutex<Option<Ledger>> = Mutex::new(None);
+static RECORDER: OnceLock<RecordHook> = OnceLock::new();
+static EXCEEDED_LISTENER: OnceLock<ExceededHook> = OnceLock::new();
+
+/// Save each priced call with `record` (called once the database is open)
+pub fn set_recorder(record: impl Fn(ApiUsage) + Send + Sync + 'static) {
+    let _ = RECORDER.set(Box::new(record));
+}
+
+/// Tell `listener` when this month's spending reaches the limit
+pub fn set_exceeded_listener(listener: impl Fn(BudgetStatus) + Send + Sync + 'static) {
+    let _ = EXCEEDED_LISTENER.set(Box::new(listener));
+}
+
+/// The listed price of `model` in dollars per million tokens (input, output)
+pub fn price(model: &str) -> Option<(f64, f64)> {
+    // Proxies often prefix the vendor, e.g. "openai/gpt-4o-mini"
+    let model = model.rsplit('/').next().unwrap_or(model).to_ascii_lowercase();
+    PRICES
+        .iter()
+        .find(|(name, _, _)| model.starts_with(name))
+        .map(|&(_, input, output)| (input, output))
+}
+
+/// What a paid call to `model` with these token counts cost, in dollars
+pub fn cost(model: &str, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+    let (input, output) = price(model).unwrap_or(UNKNOWN_MODEL_PRICE);
+    (prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1_000_000.0
+}
+
+/// Whether `cheap` costs less per token than `model` (false when either price is unknown)
+pub fn is_cheaper(cheap: &str, model: &str) -> bool {
+    match (price(cheap), price(model)) {
+        (Some((cheap_in, cheap_out)), Some((model_in, model_out))) => cheap_in + cheap_out < model_in + model_out,
+        _ => false,
+    }
+}
+
+/// The current month in local time, e.g. "2026-10"
+pub fn current_month() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+/// When the current month began (local time), as a Unix timestamp in ms
+pub fn month_start_ms() -> i64 {
+    use chrono::{Datelike, TimeZone};
+    let now = chrono::Local::now();
+    chrono::Local
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .earliest()
+        .map_or(0, |start| start.timestamp_millis())
+}
+
+fn state_for(settings: &BudgetSettings, spent: f64) -> BudgetState {
+    let limit = settings.monthly_limit_usd;
+    if limit <= 0.0 {
+        BudgetState::Normal
+    } else if spent >= limit {
+        BudgetState::Exceeded
+    } else if spent >= limit * settings.downgrade_at {
+        BudgetState::Downgraded
+    } else {
+        BudgetState::Normal
+    }
+}
+
+/// Use `settings` from now on
+pub fn set_settings(settings: BudgetSettings) {
+    *SETTINGS.lock().unwrap_or_else(|e| e.into_inner()) = Some(settings);
+}
+
+pub fn settings() -> BudgetSettings {
+    SETTINGS.lock().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// Start the month's total from what the database recorded (called at startup)
+pub fn set_spent_this_month(spent: f64) {
+    let reported = state_for(&settings(), spent) == BudgetState::Exceeded;
+    *LEDGER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Ledger { month: current_month(), spent, reported });
+}
+
+/// This month's spending so far, in dollars
+pub fn spent_this_month() -> f64 {
+    let month = current_month();
+    LEDGER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .filter(|ledger| ledger.month == month)
+        .map_or(0.0, |ledger| ledger.spent)
+}
+
+pub fn state() -> BudgetState {
+    state_for(&settings(), spent_this_month())
+}
+
+pub fn status() -> BudgetStatus {
+    let spent_usd = spent_this_month();
+    let settings = settings();
+    BudgetStatus {
+        state: state_for(&settings, spent_usd),
+        settings,
+        month: current_month(),
+        spent_usd,
+    }
+}
+
+/// Count a call's tokens against the budget and save it (`repaired` is set for repair requests)
+///
+/// `paid` is false for calls that stayed on this computer or the local network; they're
+/// saved at no cost.
+pub fn record(model: &str, paid: bool, prompt_tokens: u64, completion_tokens: u64, repaired: Option<bool>) {
+    let usage = ApiUsage {
+        model: model.to_string(),
+        prompt_tokens,
+        completion_tokens,
+        cost_usd: if paid { cost(model, prompt_tokens, completion_tokens) } else { 0.0 },
+        created_at: chrono::Utc::now().timestamp_millis(),
+        repaired,
+    };
+
+    let just_exceeded = {
+        let mut ledger = LEDGER.lock().unwrap_or_else(|e| e.into_inner());
+        let month = current_month();
+        let ledger = match ledger.as_mut() {
+            Some(ledger) if ledger.month == month => ledger,
+            // A new month (or nothing loaded yet) starts from zero
+            _ => ledger.insert(Ledger { month, spent: 0.0, reported: false }),
+        };
+        ledger.spent += usage.cost_usd;
+        let exceeded = state_for(&settings(), ledger.spent) == BudgetState::Exceeded;
+        let first = exceeded && !ledger.reported;
+        ledger.reported |= exceeded;
+        first
+    };
+
+    if let Some(record) = RECORDER.get() {
+        record(usage);
+    }
+    if just_exceeded {
+        println!("[BUDGET] Monthly limit reached, only free classifiers will be used");
+        if let Some(listener) = EXCEEDED_LISTENER.get() {
+            listener(status());
+        }
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prices_and_costs() {
+        assert_eq!(price("gpt-4o-mini-2024-07-18"), Some((0.15, 0.60)));
+        assert_eq!(price("gpt-4o-2024-08-06"), Some((2.50, 10.00)));
+        assert_eq!(price("openai/GPT-4o"), Some((2.50, 10.00)));
+        assert_eq!(price("llama3.2"), None);
+        assert!((cost("gpt-4o", 1_000_000, 100_000) - 3.50).abs() < 1e-9);
+        assert!(cost("llava", 1_000_000, 1_000_000) > 0.0, "unlisted paid models aren't free");
+        assert!((cost("o3-pro", 1_000_000, 0) - UNKNOWN_MODEL_PRICE.0).abs() < 1e-9);
+        assert!(is_cheaper("gpt-4o-mini", "gpt-4o"));
+        assert!(!is_cheaper("gpt-4o-mini", "gpt-4.1-nano"));
+        assert!(!is_cheaper("gpt-4o-mini", "llama3.2"));
+    }
+
+    #[test]
+    fn test_budget_states() {
+        let budget = BudgetSettings { monthly_limit_usd: 5.0, ..BudgetSettings::default() };
+        assert_eq!(state_for(&budget, 1.0), BudgetState::Normal);
+        assert_eq!(state_for(&budget, 4.0), BudgetState::Downgraded);
+        assert_eq!(state_for(&budget, 5.0), BudgetState::Exceeded);
+        assert_eq!(state_for(&BudgetSettings::default(), 1_000.0), BudgetState::Normal); // no limit
+
+        assert!(budget.validate().is_ok());
+        assert!(BudgetSettings { monthly_limit_usd: -1.0, ..budget.clone() }.validate().is_err());
+        assert!(BudgetSettings { downgrade_at: 1.5, ..budget.clone() }.validate().is_err());
+        assert!(BudgetSettings { downgrade_model: "llama3.2".to_string(), ..budget }.validate().is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Saved ChatGPT conversations
+//!
+//! Conversations saved from ChatGPT (the page saved as .html, or .md from copying it or an
+//! export extension) are named "ChatGPT.html" or after a vague chat title, and most of
+//! their text is the assistant's long answers. Exports are recognised by their speaker
+//! markers, and the chat title plus the user's own questions become the text they're
+//! classified on. They then go into an "AI notes" subfolder of the course they're about.
+
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+// Subfolder of the course folder that conversations go into (empty: the course folder itself)
+pub const DEFAULT_SUBFOLDER: &str = "AI notes";
+
+// Saved chat pages carry a lot of markup; the questions are near the top
+const MAX_READ_BYTES: u64 = 2 * 1024 * 1024;
+
+// Questions kept for classification, and how much of each
+const MAX_QUESTIONS: usize = 5;
+const MAX_QUESTION_CHARS: usize = 300;
+
+// Speaker labels used by copy-paste and common export extensions
+const USER_LABELS: &[&str] = &["you", "user", "me", "prompt", "question"];
+const ASSISTANT_LABELS: &[&str] = &["chatgpt", "assistant", "gpt", "gpt-4", "gpt-4o", "answer", "response"];
+
+static SUBFOLDER: Mutex<Option<String>> = Mutex::new(None);
+
+/// Put conversations into `subfolder` of their course (empty: straight into the course folder)
+pub fn set_subfolder(subfolder: String) {
+    *SUBFOLDER.lock().unwrap_or_else(|e| e.into_inner()) = Some(subfolder);
+}
+
+pub fn subfolder() -> String {
+    SUBFOLDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SUBFOLDER.to_string())
+}
+
+/// Check a subfolder name: a single folder name, no path separators
+pub fn validate_subfolder(subfolder: &str) -> Result<String, String> {
+    let subfolder = subfolder.trim();
+    if subfolder.contains(['/', '\\']) || subfolder == "." || subfolder == ".." {
+        return Err("The AI notes subfolder must be a single folder name".to_string());
+    }
+    Ok(subfolder.to_string())
+}
+
+/// A recognised conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatExport {
+    pub title: Option<String>,
+    pub questions: Vec<String>, // the user's messages, in order
+}
+
+impl ChatExport {
+    /// What the AI classifies the conversation on: its title and the questions asked
+    pub fn classification_text(&self) -> String {
+        let mut text = String::from("ChatGPT conversation");
+        if let Some(title) = &self.title {
+            text.push_str(&format!(": {}", title));
+        }
+        text.push_str("\nQuestions the student asked:");
+        for question in self.questions.iter().take(MAX_QUESTIONS) {
+            let question: String = question.chars().take(MAX_QUESTION_CHARS).collect();
+            text.push_str(&format!("\n- {}", question));
+        }
+        text
+    }
+}
+
+/// Read `path` as a ChatGPT conversation (None if it isn't one)
+pub fn read(path: &Path, extension: &str) -> Option<ChatExport> {
+    if !matches!(extension, "md" | "txt" | "html" | "htm") || crate::metadata::skip_content(path) {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.take(MAX_READ_BYTES).read_to_end(&mut bytes).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    match extension {
+        "html" | "htm" => parse_html(&text),
+        _ => parse_markdown(&text),
+    }
+}
+
+/// The speaker a line introduces, and any message text on the same line
+/// ("**You:** what is...", "### ChatGPT", "User:")
+fn speaker(line: &str) -> Option<(bool, &str)> {
+    let line = line.trim_start_matches(['#', '>', ' ', '\t']);
+    let line = line.trim_start_matches('*');
+    let end = line.find([':', '*']).unwrap_or(line.len());
+    let label = line[..end].trim().to_lowercase();
+    if label.is_empty() || label.len() > 10 {
+        return None;
+    }
+    let rest = line[end..].trim_start_matches([':', '*']).trim();
+    if USER_LABELS.contains(&label.as_str()) {
+        Some((true, rest))
+    } else if ASSISTANT_LABELS.contains(&label.as_str()) {
+        Some((false, rest))
+    } else {
+        None
+    }
+}
+
+fn parse_markdown(text: &str) -> Option<ChatExport> {
+    let mut title = None;
+    let mut questions: Vec<String> = Vec::new();
+    let mut saw_assistant = false;
+    let mut in_question = false;
+
+    for line in text.lines() {
+        match speaker(line) {
+            Some((true, rest)) => {
+                questions.push(rest.to_string());
+                in_question = true;
+            }
+            Some((false, _)) => {
+                saw_assistant = true;
+                in_question = false;
+            }
+            None if in_question => {
+                let question = questions.last_mut().expect("in a question");
+                if !line.trim().is_empty() {
+                    if !question.is_empty() {
+                        question.push(' ');
+                    }
+                    question.push_str(line.trim());
+                }
+            }
+            None => {
+                if title.is_none() && questions.is_empty() {
+                    title = line.strip_prefix("# ").map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+                }
+            }
+        }
+    }
+
+    questions.retain(|q| !q.is_empty());
+    (saw_assistant && !questions.is_empty()).then_some(ChatExport { title, questions })
+}
+
+/// Text with tags removed and whitespace collapsed
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text.replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&#39;", "'");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_html(html: &str) -> Option<ChatExport> {
+    const ROLE: &str = "data-message-author-role=\"";
+    let title = html
+        .find("<title>")
+        .and_then(|start| {
+            let rest = &html[start + "<title>".len()..];
+            rest.find("</title>").map(|end| strip_tags(&rest[..end]))
+        })
+        .map(|t| t.trim_end_matches(" - ChatGPT").trim_start_matches("ChatGPT - ").trim().to_string())
+        .filter(|t| !t.is_empty() && t != "ChatGPT");
+
+    // Each message is marked with its author; a user message runs until the next marker
+    let mut questions = Vec::new();
+    let mut saw_assistant = false;
+    let mut rest = html;
+    while let Some(at) = rest.find(ROLE) {
+        rest = &rest[at + ROLE.len()..];
+        let role = &rest[..rest.find('"').unwrap_or(0)];
+        let body_start = rest.find('>').map_or(0, |i| i + 1);
+        let body_end = rest[body_start..].find(ROLE).map_or(rest.len(), |i| body_start + i);
+        // Back up to the start of the tag holding the next marker
+        let body_end = rest[body_start..body_end].rfind('<').map_or(body_end, |i| body_start + i);
+        match role {
+            "user" => {
+                let question = strip_tags(&rest[body_start..body_end]);
+                if !question.is_empty() {
+                    questions.push(question);
+                }
+            }
+            "assistant" => saw_assistant = true,
+            _ => {}
+        }
+    }
+
+    (saw_assistant && !questions.is_empty()).then_some(ChatExport { title, questions })
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_markdown_export() {
+        let md = "# Eigenvalues of a symmetric matrix\n\n**You:** Why are the eigenvalues of a symmetric\nmatrix real?\n\n**ChatGPT:** Because A equals its transpose...\n\n### User\nShow it for 2x2\n\n### Assistant\nLet A = ...\n";
+        let chat = parse_markdown(md).expect("a conversation");
+        assert_eq!(chat.title.as_deref(), Some("Eigenvalues of a symmetric matrix"));
+        assert_eq!(chat.questions, vec!["Why are the eigenvalues of a symmetric matrix real?", "Show it for 2x2"]);
+        assert!(chat.classification_text().contains("Questions the student asked:\n- Why are the eigenvalues"));
+
+        // Ordinary notes that happen to mention a label aren't conversations
+        assert_eq!(parse_markdown("# Notes\nPrompt engineering is the art of...\nUser: admin\n"), None);
+    }
+
+    #[test]
+    fn test_parse_html_export() {
+        let html = r#"<html><head><title>Fourier series help - ChatGPT</title></head><body>
+            <div data-message-author-role="user"><div class="whitespace-pre-wrap">What is a Fourier series &amp; why use it?</div></div>
+            <div data-message-author-role="assistant"><p>A Fourier series...</p></div>
+            <div data-message-author-role="user"><p>Example with a square wave</p></div>
+            </body></html>"#;
+        let chat = parse_html(html).expect("a conversation");
+        assert_eq!(chat.title.as_deref(), Some("Fourier series help"));
+        assert_eq!(chat.questions, vec!["What is a Fourier series & why use it?", "Example with a square wave"]);
+
+        assert_eq!(parse_html("<html><title>ChatGPT</title><p>Pricing</p></html>"), None);
+    }
+
+    #[test]
+    fn test_validate_subfolder() {
+        assert_eq!(validate_subfolder(" AI notes ").unwrap(), "AI notes");
+        assert_eq!(validate_subfolder("").unwrap(), "");
+        assert!(validate_subfolder("AI/notes").is_err());
+        assert!(validate_subfolder("..").is_err());
+    }
+}
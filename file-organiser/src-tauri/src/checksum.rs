@@ -0,0 +1,182 @@
+//! Content checksums used to verify that moves never corrupt or mix up files
+//!
+//! A plain rename within a volume can't change a file's contents, so it isn't checked.
+//! When a rename isn't possible (e.g. moving to another drive), the file is hashed with
+//! blake3 and copied, the copy is verified, and only then is the original deleted. The
+//! copy keeps the original's timestamps and attributes, so organized files don't all show
+//! the day they were moved.
+
+use crate::CommandError;
+use std::fs::{self, File};
+use std::io::{self, BufReader, ErrorKind};
+use std::path::Path;
+
+/// Hash a file's contents, returning the blake3 digest as hex
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Check that a file is still the one that was moved there, before moving it back
+///
+/// The size recorded at move time (if any) is compared first, so a file that was edited
+/// or replaced since is usually caught without reading it.
+pub fn verify(path: &Path, expected_hash: &str, expected_size: Option<u64>) -> Result<(), CommandError> {
+    let changed = || CommandError::ChangedSinceMove(path.display().to_string());
+    if expected_size.is_some_and(|size| size != fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX)) {
+        return Err(changed());
+    }
+    if hash_file(path)? != expected_hash {
+        return Err(changed());
+    }
+    Ok(())
+}
+
+/// Move a file, verifying its contents arrived intact when it had to be copied
+///
+/// A same-volume rename is atomic and never reads the file. Across filesystems it falls
+/// back to copy + verify + delete, removing the original only once the copy has been
+/// verified; the content hash computed for that is returned.
+pub fn move_verified(from: &Path, to: &Path) -> Result<Option<String>, CommandError> {
+    move_file(from, to, None)
+}
+
+/// `move_verified` for a file whose hash was just checked, so a cross-device copy is
+/// verified against it without reading the original again
+pub fn move_with_hash(from: &Path, to: &Path, hash: &str) -> Result<(), CommandError> {
+    move_file(from, to, Some(hash)).map(|_| ())
+}
+
+fn move_file(from: &Path, to: &Path, known_hash: Option<&str>) -> Result<Option<String>, CommandError> {
+    let _op = crate::shutdown::begin_operation()?;
+
+    match fs::rename(from, to) {
+        Ok(()) => Ok(None),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            println!("[CHECKSUM] Cross-device move, copying {} -> {}", from.display(), to.display());
+            let hash = match known_hash {
+                Some(hash) => hash.to_string(),
+                None => hash_file(from)?,
+            };
+            copy_then_delete(from, to, &hash)?;
+            Ok(Some(hash))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Copy `from` to `to` with its timestamps and attributes, check the copy against `hash`,
+/// and only then delete `from`
+fn copy_then_delete(from: &Path, to: &Path, hash: &str) -> Result<(), CommandError> {
+    crate::fileop::copy_with_progress(from, to)?;
+    if hash_file(to)? != hash {
+        let _ = fs::remove_file(to);
+        return Err(CommandError::ChecksumMismatch(format!(
+            "Copy of {} did not match the original; the original was kept",
+            from.display()
+        )));
+    }
+    // The contents are what matter; a filesystem that can't take the dates doesn't stop the move
+    if let Err(e) = crate::fileop::copy_attributes(from, to) {
+        eprintln!("[CHECKSUM] Could not keep timestamps of {}: {}", from.display(), e);
+    }
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file_matches_contents() {
+        let tmp = std::env::temp_dir().join("fileorg_test_checksum_hash");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(tmp.join("a.txt"), "same").unwrap();
+        fs::write(tmp.join("b.txt"), "same").unwrap();
+        fs::write(tmp.join("c.txt"), "different").unwrap();
+
+        let a = hash_file(&tmp.join("a.txt")).unwrap();
+        assert_eq!(a, hash_file(&tmp.join("b.txt")).unwrap());
+        assert_ne!(a, hash_file(&tmp.join("c.txt")).unwrap());
+        assert_eq!(a, blake3::hash(b"same").to_hex().to_string());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_move_verified_renames_without_hashing() {
+        let tmp = std::env::temp_dir().join("fileorg_test_checksum_move");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("dest")).unwrap();
+        let from = tmp.join("notes.pdf");
+        let to = tmp.join("dest").join("notes.pdf");
+        fs::write(&from, "lecture notes").unwrap();
+
+        // Same volume: a rename, so there's nothing to verify and no hash
+        assert_eq!(move_verified(&from, &to).unwrap(), None);
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "lecture notes");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_copy_then_delete_keeps_modified_time() {
+        let tmp = std::env::temp_dir().join("fileorg_test_checksum_copy_delete");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("dest")).unwrap();
+        let from = tmp.join("lecture1.pdf");
+        let to = tmp.join("dest").join("lecture1.pdf");
+        fs::write(&from, "slides").unwrap();
+        let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        File::options().write(true).open(&from).unwrap().set_modified(modified).unwrap();
+        #[cfg(unix)]
+        let tagged = xattr::set(&from, "user.fileorg.test", b"Red").is_ok(); // not every filesystem allows it
+
+        let hash = hash_file(&from).unwrap();
+        copy_then_delete(&from, &to, &hash).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "slides");
+        assert_eq!(fs::metadata(&to).unwrap().modified().unwrap(), modified);
+        #[cfg(unix)]
+        if tagged {
+            assert_eq!(xattr::get(&to, "user.fileorg.test").unwrap().as_deref(), Some(&b"Red"[..]));
+        }
+
+        // A copy that doesn't match is removed and the original kept
+        fs::write(&from, "slides").unwrap();
+        fs::remove_file(&to).unwrap();
+        assert!(matches!(copy_then_delete(&from, &to, "not-the-hash"), Err(CommandError::ChecksumMismatch(_))));
+        assert!(from.exists());
+        assert!(!to.exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_verify_detects_changed_file() {
+        let tmp = std::env::temp_dir().join("fileorg_test_checksum_verify");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let path = tmp.join("notes.txt");
+        fs::write(&path, "original").unwrap();
+        let hash = hash_file(&path).unwrap();
+
+        assert!(verify(&path, &hash, Some(8)).is_ok());
+        assert!(verify(&path, &hash, None).is_ok());
+        // Same size, different contents: only the hash catches it
+        fs::write(&path, "Original").unwrap();
+        assert!(matches!(verify(&path, &hash, Some(8)).unwrap_err(), CommandError::ChangedSinceMove(_)));
+        fs::write(&path, "edited").unwrap();
+        assert!(matches!(verify(&path, &hash, Some(8)).unwrap_err(), CommandError::ChangedSinceMove(_)));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
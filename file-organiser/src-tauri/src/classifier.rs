@@ -1,10 +1,15 @@
 // AI-powered file classification using OpenAI GPT
 
+use crate::budget::BudgetState;
+use crate::folder_match;
 use base64::Engine;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 #[derive(Debug, Error)]
 pub enum ClassifierError {
@@ -40,6 +45,9 @@ pub enum ClassifierError {
 
     #[error("OCR extraction failed: {0}")]
     OcrExtract(String),
+
+    #[error("Cloud AI is turned off (local-only mode); {0} is not on this computer or network")]
+    CloudDisabled(String),
 }
 
 // Convert ClassifierError to String for Tauri command compatibility
@@ -50,22 +58,753 @@ impl From<ClassifierError> for String {
 }
 
 const API_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 600;
 const MAX_IMAGE_SIZE_BYTES: u64 = 20 * 1024 * 1024; // 20 MB
 const MIN_API_INTERVAL_MS: u64 = 500; // Minimum 500ms between API calls
 
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 3;
+pub const MAX_CONCURRENT_REQUESTS: usize = 8;
+
 /// Simple rate limiter to prevent rapid-fire API calls
 static LAST_API_CALL: Mutex<Option<Instant>> = Mutex::new(None);
 
-fn rate_limit() {
-    let mut last = LAST_API_CALL.lock().unwrap_or_else(|e| e.into_inner());
-    if let Some(prev) = *last {
-        let elapsed = prev.elapsed();
-        let min_interval = Duration::from_millis(MIN_API_INTERVAL_MS);
-        if elapsed < min_interval {
-            std::thread::sleep(min_interval - elapsed);
+static MAX_CONCURRENT: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONCURRENT_REQUESTS);
+
+// Permits for requests in flight; replaced when the limit changes (requests holding a permit
+// from the old one finish normally)
+static REQUEST_SLOTS: Mutex<Option<Arc<Semaphore>>> = Mutex::new(None);
+
+/// Allow up to `limit` requests in flight at once from now on
+pub fn set_max_concurrent_requests(limit: usize) {
+    let limit = limit.clamp(1, MAX_CONCURRENT_REQUESTS);
+    MAX_CONCURRENT.store(limit, Ordering::Relaxed);
+    *REQUEST_SLOTS.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(Semaphore::new(limit)));
+}
+
+pub fn max_concurrent_requests() -> usize {
+    MAX_CONCURRENT.load(Ordering::Relaxed)
+}
+
+/// Wait for a free request slot, then for the rate limiter
+///
+/// Requests may overlap (up to the concurrency limit) but still start at least
+/// MIN_API_INTERVAL_MS apart. Hold the permit until the response has arrived.
+async fn request_slot() -> OwnedSemaphorePermit {
+    let slots = REQUEST_SLOTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| Arc::new(Semaphore::new(max_concurrent_requests())))
+        .clone();
+    let permit = slots.acquire_owned().await.expect("request slots are never closed");
+
+    // Reserve the next start time, then wait for it outside the lock
+    let wait = {
+        let mut last = LAST_API_CALL.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let start = last.map_or(now, |prev| (prev + Duration::from_millis(MIN_API_INTERVAL_MS)).max(now));
+        *last = Some(start);
+        start - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+    permit
+}
+
+/// Which chat completions API classification requests go to
+///
+/// Ollama serves an OpenAI-compatible API, so only the address and model names change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    #[default]
+    OpenAi,
+    Ollama, // local server at OLLAMA_HOST (default localhost:11434); models from OLLAMA_MODEL / OLLAMA_VISION_MODEL
+    Mock,   // offline keyword rules (see mock_classifier), for development and demos
+}
+
+impl std::str::FromStr for Provider {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "openai" => Ok(Provider::OpenAi),
+            "ollama" => Ok(Provider::Ollama),
+            "mock" => Ok(Provider::Mock),
+            other => Err(format!("Unknown provider \"{}\" (expected openai, ollama or mock)", other)),
+        }
+    }
+}
+
+impl Provider {
+    /// Whether requests need the user's API key
+    pub fn needs_api_key(self) -> bool {
+        self == Provider::OpenAi
+    }
+
+    /// The name the provider is stored and parsed by
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "openai",
+            Provider::Ollama => "ollama",
+            Provider::Mock => "mock",
+        }
+    }
+}
+
+static PROVIDER: Mutex<Provider> = Mutex::new(Provider::OpenAi);
+
+/// Send classification requests to `provider` from now on
+pub fn set_provider(provider: Provider) {
+    *PROVIDER.lock().unwrap_or_else(|e| e.into_inner()) = provider;
+}
+
+pub fn provider() -> Provider {
+    *PROVIDER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Where OpenAI-provider requests go instead of api.openai.com (a LiteLLM proxy, LM Studio,
+/// vLLM or a corporate gateway), with any extra headers it wants
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointOverride {
+    pub base_url: Option<String>, // e.g. "http://localhost:4000/v1"; None: api.openai.com
+    pub headers: BTreeMap<String, String>,
+    pub text_model: Option<String>, // for servers that don't know the OpenAI model names
+    pub vision_model: Option<String>,
+}
+
+impl EndpointOverride {
+    /// Check the override before it's saved
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(base_url) = &self.base_url {
+            let url = reqwest::Url::parse(base_url).map_err(|e| format!("Invalid base URL \"{}\": {}", base_url, e))?;
+            if url.scheme() != "http" && url.scheme() != "https" {
+                return Err(format!("Base URL must start with http:// or https:// (got {})", base_url));
+            }
+        }
+        for (name, value) in &self.headers {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("Invalid header name \"{}\"", name))?;
+            reqwest::header::HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid value for header \"{}\"", name))?;
+        }
+        Ok(())
+    }
+
+    /// The override with blank fields cleared, so they fall back to the defaults
+    pub fn normalized(self) -> Self {
+        let filled = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        EndpointOverride {
+            base_url: filled(self.base_url),
+            headers: self
+                .headers
+                .into_iter()
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .filter(|(name, _)| !name.is_empty())
+                .collect(),
+            text_model: filled(self.text_model),
+            vision_model: filled(self.vision_model),
+        }
+    }
+}
+
+static ENDPOINT_OVERRIDE: Mutex<EndpointOverride> = Mutex::new(EndpointOverride {
+    base_url: None,
+    headers: BTreeMap::new(),
+    text_model: None,
+    vision_model: None,
+});
+
+/// Send OpenAI-provider requests through `endpoint` from now on
+pub fn set_endpoint_override(endpoint: EndpointOverride) {
+    *ENDPOINT_OVERRIDE.lock().unwrap_or_else(|e| e.into_inner()) = endpoint;
+}
+
+pub fn endpoint_override() -> EndpointOverride {
+    ENDPOINT_OVERRIDE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// What a request sends, which decides the model it goes to (see `ModelRoutes`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Filename, // the filename alone (also topic names)
+    Content,  // the filename with extracted text
+    Vision,   // an image
+}
+
+/// The model each kind of request goes to on the OpenAI provider (api.openai.com or a
+/// custom endpoint), e.g. gpt-4o-mini for filenames and a Gemini Flash model behind a
+/// LiteLLM proxy for images. Unset kinds use the endpoint's text or vision model; a model
+/// named in a fallback step still wins for text requests. Ollama keeps OLLAMA_MODEL and
+/// OLLAMA_VISION_MODEL.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelRoutes {
+    pub filename: Option<String>,
+    pub content: Option<String>,
+    pub vision: Option<String>,
+}
+
+impl ModelRoutes {
+    /// The routes with blank models cleared, so those kinds fall back to the defaults
+    pub fn normalized(self) -> Self {
+        let filled = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        ModelRoutes {
+            filename: filled(self.filename),
+            content: filled(self.content),
+            vision: filled(self.vision),
+        }
+    }
+
+    fn route(&self, kind: CallKind) -> Option<&String> {
+        match kind {
+            CallKind::Filename => self.filename.as_ref(),
+            CallKind::Content => self.content.as_ref(),
+            CallKind::Vision => self.vision.as_ref(),
+        }
+    }
+}
+
+static MODEL_ROUTES: Mutex<ModelRoutes> = Mutex::new(ModelRoutes { filename: None, content: None, vision: None });
+
+/// Send each kind of request to the model in `routes` from now on
+pub fn set_model_routes(routes: ModelRoutes) {
+    *MODEL_ROUTES.lock().unwrap_or_else(|e| e.into_inner()) = routes;
+}
+
+pub fn model_routes() -> ModelRoutes {
+    MODEL_ROUTES.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Whether requests need the user's API key: not when some tier can answer without one
+/// (Ollama, the mock provider, or a custom endpoint such as LM Studio)
+pub fn needs_api_key() -> bool {
+    tiers().iter().all(FallbackTier::needs_api_key)
+}
+
+/// One step of the fallback chain: a provider and, optionally, the text model to ask
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FallbackTier {
+    pub provider: Provider,
+    #[serde(default)]
+    pub model: Option<String>, // None: the provider's default text model
+}
+
+impl FallbackTier {
+    fn endpoint(&self) -> Endpoint {
+        self.endpoint_for(CallKind::Filename)
+    }
+
+    /// The endpoint with the model for `kind`: the step's own model for text, then the
+    /// route for that kind, then the provider's default
+    fn endpoint_for(&self, kind: CallKind) -> Endpoint {
+        let routes = match self.provider {
+            Provider::OpenAi => model_routes(),
+            _ => ModelRoutes::default(),
+        };
+        self.route(endpoint(self.provider), &routes, kind)
+    }
+
+    fn route(&self, mut endpoint: Endpoint, routes: &ModelRoutes, kind: CallKind) -> Endpoint {
+        if let Some(model) = routes.route(kind) {
+            *endpoint.model_for_mut(kind) = model.clone();
+        }
+        if let (Some(model), false) = (&self.model, kind == CallKind::Vision) {
+            endpoint.text_model = model.clone();
+        }
+        endpoint
+    }
+
+    fn needs_api_key(&self) -> bool {
+        self.provider.needs_api_key() && endpoint_override().base_url.is_none()
+    }
+
+    /// Whether requests to this tier count against the spend budget: any that leave this
+    /// computer and local network, priced or not
+    fn is_paid(&self) -> bool {
+        self.provider != Provider::Mock && !is_local_url(&self.endpoint().url)
+    }
+
+    /// Which tier answered, e.g. "openai/gpt-4o-mini"
+    pub fn label(&self) -> String {
+        self.label_with(&self.endpoint(), CallKind::Filename)
+    }
+
+    /// The label for a `kind` request sent to `endpoint`
+    fn label_with(&self, endpoint: &Endpoint, kind: CallKind) -> String {
+        match self.provider {
+            Provider::Mock => "mock".to_string(),
+            provider => format!("{}/{}", provider.as_str(), endpoint.model_for(kind)),
+        }
+    }
+}
+
+pub const MAX_FALLBACK_TIERS: usize = 6;
+
+static FALLBACK_CHAIN: Mutex<Vec<FallbackTier>> = Mutex::new(Vec::new());
+
+/// Walk `chain` in order when a request can't be answered from now on (empty: only the
+/// chosen provider is asked)
+pub fn set_fallback_chain(chain: Vec<FallbackTier>) {
+    *FALLBACK_CHAIN.lock().unwrap_or_else(|e| e.into_inner()) = chain;
+}
+
+pub fn fallback_chain() -> Vec<FallbackTier> {
+    FALLBACK_CHAIN.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Check a fallback chain before it's saved
+pub fn validate_fallback_chain(chain: &[FallbackTier]) -> Result<(), String> {
+    if chain.len() > MAX_FALLBACK_TIERS {
+        return Err(format!("At most {} fallback steps are allowed", MAX_FALLBACK_TIERS));
+    }
+    if chain.iter().any(|tier| tier.model.as_ref().is_some_and(|m| m.trim().is_empty())) {
+        return Err("Fallback model names can't be blank".to_string());
+    }
+    Ok(())
+}
+
+/// The tiers a request walks: the fallback chain, or just the chosen provider
+fn tiers() -> Vec<FallbackTier> {
+    let chain = fallback_chain();
+    if chain.is_empty() {
+        vec![FallbackTier { provider: provider(), model: None }]
+    } else {
+        chain
+    }
+}
+
+/// The tiers a classification walks under this month's budget: paid tiers are left out
+/// once it's reached, when the offline keyword classifier answers if no free tier is left
+fn budgeted_tiers(state: BudgetState) -> Vec<FallbackTier> {
+    let tiers = tiers();
+    if state != BudgetState::Exceeded {
+        return tiers;
+    }
+    let free: Vec<FallbackTier> = tiers.into_iter().filter(|tier| !tier.is_paid()).collect();
+    if free.is_empty() {
+        vec![FallbackTier { provider: Provider::Mock, model: None }]
+    } else {
+        free
+    }
+}
+
+/// A tier's endpoint for a `kind` request, switched to the cheaper model once spending
+/// nears the limit
+fn budgeted_endpoint(tier: &FallbackTier, state: BudgetState, kind: CallKind) -> Endpoint {
+    let mut endpoint = tier.endpoint_for(kind);
+    let cheap = crate::budget::settings().downgrade_model;
+    if state == BudgetState::Downgraded && tier.is_paid() && crate::budget::is_cheaper(&cheap, endpoint.model_for(kind)) {
+        *endpoint.model_for_mut(kind) = cheap;
+    }
+    endpoint
+}
+
+/// The tier asked first, for requests that aren't classifications (topic names, key checks)
+fn primary_tier() -> FallbackTier {
+    tiers().remove(0)
+}
+
+/// Whether an error means the next tier should be tried (this one can't be reached, isn't
+/// authorized or is out of quota) rather than the request itself being bad
+fn should_fall_back(err: &ClassifierError) -> bool {
+    match err {
+        ClassifierError::MissingApiKey | ClassifierError::CloudDisabled(_) => true,
+        ClassifierError::ApiStatus(status) => {
+            matches!(status.as_u16(), 401 | 402 | 403 | 404 | 408 | 429) || status.is_server_error()
+        }
+        ClassifierError::HttpClient(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+        _ => false,
+    }
+}
+
+static PRIVACY_MODE: AtomicBool = AtomicBool::new(false);
+
+/// How long each kind of request may run before it's abandoned, in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    pub text_secs: u64,   // filename and content classification, topic names, embeddings
+    pub vision_secs: u64, // image uploads
+    pub ocr_secs: u64,    // the local Tesseract run
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        DEFAULT_TIMEOUTS
+    }
+}
+
+const DEFAULT_TIMEOUTS: Timeouts = Timeouts {
+    text_secs: API_TIMEOUT_SECS,
+    vision_secs: API_TIMEOUT_SECS * 2, // vision needs more time
+    ocr_secs: API_TIMEOUT_SECS,
+};
+
+impl Timeouts {
+    /// Check the timeouts before they're saved
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, secs) in [("Text", self.text_secs), ("Vision", self.vision_secs), ("OCR", self.ocr_secs)] {
+            if secs == 0 || secs > MAX_TIMEOUT_SECS {
+                return Err(format!("{} timeout must be between 1 and {} seconds (got {})", name, MAX_TIMEOUT_SECS, secs));
+            }
         }
+        Ok(())
     }
-    *last = Some(Instant::now());
+}
+
+static TIMEOUTS: Mutex<Timeouts> = Mutex::new(DEFAULT_TIMEOUTS);
+
+/// Use `timeouts` for requests from now on
+pub fn set_timeouts(timeouts: Timeouts) {
+    *TIMEOUTS.lock().unwrap_or_else(|e| e.into_inner()) = timeouts;
+}
+
+pub fn timeouts() -> Timeouts {
+    *TIMEOUTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Prompts are trimmed to this many tokens, leaving gpt-3.5-turbo's 16k context room for the reply
+pub const DEFAULT_PROMPT_TOKEN_BUDGET: usize = 12_000;
+pub const MIN_PROMPT_TOKEN_BUDGET: usize = 1_000; // the instructions and a folder list need about this much
+
+static PROMPT_TOKEN_BUDGET: AtomicUsize = AtomicUsize::new(DEFAULT_PROMPT_TOKEN_BUDGET);
+
+/// Trim prompts to `tokens` from now on
+pub fn set_prompt_token_budget(tokens: usize) {
+    PROMPT_TOKEN_BUDGET.store(tokens.max(MIN_PROMPT_TOKEN_BUDGET), Ordering::Relaxed);
+}
+
+pub fn prompt_token_budget() -> usize {
+    PROMPT_TOKEN_BUDGET.load(Ordering::Relaxed)
+}
+
+// Share of the prompt budget the past-outcome examples may take, as a divisor
+const EXAMPLES_BUDGET_DIVISOR: usize = 4;
+
+/// Tokens the past-outcome examples may take before the prompt is built
+pub fn examples_token_budget() -> usize {
+    prompt_token_budget() / EXAMPLES_BUDGET_DIVISOR
+}
+
+/// The most relevant of `candidates` (scored lines) that fit in `budget` tokens together
+///
+/// Lines are taken greedily by score (ties keep the order given), skipping any that no
+/// longer fit, so a long line doesn't crowd out several short relevant ones. They come back
+/// least relevant first: the most relevant end up closest to the question, and are the
+/// last to be dropped if the whole prompt is still too long (see `fit_prompt`).
+pub fn pack_by_relevance(mut candidates: Vec<(f32, String)>, budget: usize) -> Vec<String> {
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut packed: Vec<String> = Vec::new();
+    let mut used = 0;
+    for (_, line) in candidates {
+        let tokens = count_tokens(&line) + 1; // and its newline
+        if used + tokens > budget || packed.contains(&line) {
+            continue;
+        }
+        used += tokens;
+        packed.push(line);
+    }
+    packed.reverse();
+    packed
+}
+
+/// The cl100k tokenizer GPT-3.5 and GPT-4 use (None if it couldn't be loaded)
+fn tokenizer() -> Option<&'static tiktoken_rs::CoreBPE> {
+    static TOKENIZER: OnceLock<Option<tiktoken_rs::CoreBPE>> = OnceLock::new();
+    TOKENIZER.get_or_init(|| tiktoken_rs::cl100k_base().ok()).as_ref()
+}
+
+/// How many tokens `text` takes (about 4 characters a token if the tokenizer is unavailable)
+fn count_tokens(text: &str) -> usize {
+    match tokenizer() {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        None => text.chars().count().div_ceil(4),
+    }
+}
+
+/// The start of `text` that fits in `tokens`
+fn truncate_to_tokens(text: &str, tokens: usize) -> String {
+    match tokenizer() {
+        Some(bpe) => bpe.split_by_token_ordinary_iter(text).take(tokens).map_while(Result::ok).collect(),
+        None => text.chars().take(tokens * 4).collect(),
+    }
+}
+
+/// Keep real filenames out of prompts from now on (see `redact_filename`)
+pub fn set_privacy_mode(enabled: bool) {
+    PRIVACY_MODE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn privacy_mode() -> bool {
+    PRIVACY_MODE.load(Ordering::Relaxed)
+}
+
+type PromptHook = Box<dyn Fn(&str, &str) + Send + Sync>;
+
+// Told the filename and prompt of each text classification that got an answer
+static PROMPT_RECORDER: OnceLock<PromptHook> = OnceLock::new();
+
+/// Keep the prompts files were classified with using `record` (called once the database is
+/// open), so corrections can later be exported with them as fine-tuning examples
+pub fn set_prompt_recorder(record: impl Fn(&str, &str) + Send + Sync + 'static) {
+    let _ = PROMPT_RECORDER.set(Box::new(record));
+}
+
+/// The filename-only prompt `filename` would be classified with among `folders`, for
+/// corrections whose original prompt wasn't kept
+pub fn filename_prompt(filename: &str, folders: &[String]) -> String {
+    build_prompt(filename, folders, &[], PromptMode::FilenameOnly, privacy_mode()).text
+}
+
+// Filenames already in a candidate folder that are ranked for the prompt, and the tokens
+// the ones listed with the folder may take (about five names)
+pub const SIBLING_CANDIDATES: usize = 20;
+pub const SIBLING_TOKEN_BUDGET: usize = 40;
+
+static SIBLING_CONTEXT: AtomicBool = AtomicBool::new(false);
+
+/// List a few filenames already in each folder alongside it in prompts
+pub fn set_sibling_context(enabled: bool) {
+    SIBLING_CONTEXT.store(enabled, Ordering::Relaxed);
+}
+
+pub fn sibling_context() -> bool {
+    SIBLING_CONTEXT.load(Ordering::Relaxed)
+}
+
+// Subfolders of one course folder listed in the prompt
+pub const SUBFOLDER_LIMIT: usize = 30;
+// How sure the model must be of a subfolder before files are put in it
+pub const SUBFOLDER_MIN_CONFIDENCE: f32 = 0.7;
+
+static SUGGEST_SUBFOLDERS: AtomicBool = AtomicBool::new(false);
+static SUBFOLDERS: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
+
+/// Ask for a subfolder of the chosen folder too (one of those given to `set_subfolders`)
+pub fn set_suggest_subfolders(enabled: bool) {
+    SUGGEST_SUBFOLDERS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn suggest_subfolders() -> bool {
+    SUGGEST_SUBFOLDERS.load(Ordering::Relaxed)
+}
+
+/// Remember the subfolders (relative paths) found in `folder`, to list in prompts and to
+/// check the model's subfolder against
+pub fn set_subfolders(folder: &str, subfolders: Vec<String>) {
+    SUBFOLDERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(folder.to_string(), subfolders);
+}
+
+/// The subfolders last found in `folder`
+fn subfolders_of(folder: &str) -> Vec<String> {
+    SUBFOLDERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|known| known.get(folder).cloned())
+        .unwrap_or_default()
+}
+
+/// A stand-in for a filename that may contain personal details, e.g. "file_3f9a1c0b.pdf"
+///
+/// Only the extension survives. The same name always maps to the same stand-in, so past
+/// corrections still line up with the files they were about.
+pub fn redact_filename(filename: &str) -> String {
+    let hash = blake3::hash(filename.as_bytes()).to_hex();
+    let stand_in = format!("file_{}", &hash[..8]);
+    match std::path::Path::new(filename).extension() {
+        Some(ext) => format!("{}.{}", stand_in, ext.to_string_lossy().to_lowercase()),
+        None => stand_in,
+    }
+}
+
+/// A correction-history line with its quoted filename (if any) redacted
+fn redact_correction(line: &str) -> String {
+    let quoted = line
+        .strip_prefix('"')
+        .and_then(|rest| rest.find("\" →").map(|end| (&rest[..end], &rest[end..])));
+    match quoted {
+        Some((filename, rest)) => format!("\"{}{}", redact_filename(filename), rest),
+        None => line.to_string(),
+    }
+}
+
+static LOCAL_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Refuse every request that would leave this computer or local network from now on
+pub fn set_local_only(enabled: bool) {
+    LOCAL_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn local_only() -> bool {
+    LOCAL_ONLY.load(Ordering::Relaxed)
+}
+
+/// Whether the AI can be used at all: not when local-only mode is on and the provider is a cloud one
+pub fn ai_available() -> bool {
+    !local_only() || tiers().iter().any(|tier| tier.provider == Provider::Mock || is_local_url(&tier.endpoint().url))
+}
+
+/// Whether `url` points at this computer or a private network address (e.g. an Ollama server)
+pub fn is_local_url(url: &str) -> bool {
+    let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']').trim_end_matches('.');
+    match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => is_local_ip(ip),
+        Err(_) => host == "localhost" || host.ends_with(".localhost") || host.ends_with(".local"),
+    }
+}
+
+/// Whether `ip` is this computer or on a private network (IPv4-mapped IPv6 addresses included)
+pub fn is_local_ip(ip: std::net::IpAddr) -> bool {
+    match ip {
+        std::net::IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified(),
+        std::net::IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_local_ip(ip.into()),
+            // fc00::/7 is unique local, fe80::/10 link-local
+            None => {
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.segments()[0] & 0xfe00 == 0xfc00
+                    || ip.segments()[0] & 0xffc0 == 0xfe80
+            }
+        },
+    }
+}
+
+// A redirect mustn't cross between local and other hosts: that would take a request past
+// local-only mode, or from a public page onto this computer or network
+fn same_side_redirects() -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(|attempt| {
+        let from_local = attempt.previous().first().is_some_and(|url| is_local_url(url.as_str()));
+        if attempt.previous().len() > 10 {
+            attempt.error("too many redirects")
+        } else if from_local != is_local_url(attempt.url().as_str()) {
+            attempt.stop()
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// The HTTP client for a request to `url`
+///
+/// Every request in this module (and the embedding model download) goes through here or
+/// `download_client`, so local-only mode is enforced no matter which command or pipeline asked.
+pub fn http_client(url: &str, timeout: Duration) -> Result<reqwest::Client, ClassifierError> {
+    if local_only() && !is_local_url(url) {
+        return Err(ClassifierError::CloudDisabled(url.to_string()));
+    }
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(same_side_redirects())
+        .build()
+        .map_err(ClassifierError::HttpClient)
+}
+
+/// The HTTP client for a large download from `url`, which can take longer than any request
+///
+/// As `http_client`, but the configured text timeout only limits connecting; the caller
+/// limits each read with it (see `timeouts`).
+pub fn download_client(url: &str) -> Result<reqwest::Client, ClassifierError> {
+    if local_only() && !is_local_url(url) {
+        return Err(ClassifierError::CloudDisabled(url.to_string()));
+    }
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(timeouts().text_secs))
+        .redirect(same_side_redirects())
+        .build()
+        .map_err(ClassifierError::HttpClient)
+}
+
+/// Chat completions URL, extra headers and the text and vision model names for a provider
+struct Endpoint {
+    url: String,
+    headers: BTreeMap<String, String>,
+    text_model: String,
+    vision_model: String,
+}
+
+impl Endpoint {
+    /// The model a `kind` request is sent to
+    fn model_for(&self, kind: CallKind) -> &str {
+        match kind {
+            CallKind::Vision => &self.vision_model,
+            CallKind::Filename | CallKind::Content => &self.text_model,
+        }
+    }
+
+    fn model_for_mut(&mut self, kind: CallKind) -> &mut String {
+        match kind {
+            CallKind::Vision => &mut self.vision_model,
+            CallKind::Filename | CallKind::Content => &mut self.text_model,
+        }
+    }
+
+    /// Start a POST to the chat completions URL with the auth and extra headers set
+    fn post(&self, client: &reqwest::Client, api_key: &str) -> reqwest::RequestBuilder {
+        self.with_headers(client.post(&self.url), api_key)
+    }
+
+    fn with_headers(&self, mut request: reqwest::RequestBuilder, api_key: &str) -> reqwest::RequestBuilder {
+        if !api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request
+    }
+}
+
+fn endpoint(provider: Provider) -> Endpoint {
+    let env = |name: &str, default: &str| std::env::var(name).ok().filter(|v| !v.is_empty()).unwrap_or_else(|| default.to_string());
+    match provider {
+        Provider::OpenAi => {
+            let custom = endpoint_override();
+            let url = match custom.base_url.as_deref().map(|url| url.trim_end_matches('/')) {
+                Some(url) if url.ends_with("/chat/completions") => url.to_string(),
+                Some(url) => format!("{}/chat/completions", url),
+                None => "https://api.openai.com/v1/chat/completions".to_string(),
+            };
+            Endpoint {
+                url,
+                headers: custom.headers,
+                text_model: custom.text_model.unwrap_or_else(|| "gpt-3.5-turbo".to_string()),
+                vision_model: custom.vision_model.unwrap_or_else(|| "gpt-4o".to_string()),
+            }
+        }
+        Provider::Ollama => {
+            let host = env("OLLAMA_HOST", "http://localhost:11434");
+            let host = if host.contains("://") { host } else { format!("http://{}", host) };
+            Endpoint {
+                url: format!("{}/v1/chat/completions", host.trim_end_matches('/')),
+                headers: BTreeMap::new(),
+                text_model: env("OLLAMA_MODEL", "llama3.2"),
+                vision_model: env("OLLAMA_VISION_MODEL", "llava"),
+            }
+        }
+        // Never contacted: requests are answered before they're sent
+        Provider::Mock => Endpoint {
+            url: "http://localhost/mock".to_string(),
+            headers: BTreeMap::new(),
+            text_model: "mock".to_string(),
+            vision_model: "mock".to_string(),
+        },
+    }
+}
+
+/// Whether a request can't be made because the provider needs a key and there isn't one
+fn missing_api_key(api_key: &str) -> bool {
+    api_key.is_empty() && needs_api_key()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +814,31 @@ pub struct Classification {
     pub confidence: f32,
     pub reasoning: String,
     pub suggested_filename: Option<String>,
+    #[serde(default)]
+    pub tier: Option<String>, // which provider and model answered, e.g. "openai/gpt-4o-mini"
+    #[serde(default)]
+    pub truncation: Option<PromptTruncation>, // set when the prompt was cut to fit the token budget
+    #[serde(default)]
+    pub calibrated_confidence: Option<f32>, // chance the suggestion is right, judged from past outcomes
+    #[serde(default)]
+    pub suggested_subfolder: Option<String>, // relative to suggested_folder; one that already exists
+    #[serde(default)]
+    pub subfolder_confidence: Option<f32>, // the model's confidence in the subfolder alone
+    #[serde(default)]
+    pub explanation: Option<Explanation>, // the evidence behind `reasoning`, if the model gave any
+}
+
+// How many keywords or content clues an explanation keeps
+const EXPLANATION_LIMIT: usize = 5;
+
+/// Why a folder was chosen, split into the signals the UI lists ("Matched: 'gradient descent',
+/// similar to past correction ...")
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Explanation {
+    pub keywords: Vec<String>,              // words from the filename or content that point to the folder
+    pub content_clues: Vec<String>,         // short phrases about what the content shows
+    pub similar_correction: Option<String>, // filename of the past correction that was followed
 }
 
 // --- Request types for text-only (GPT-3.5) ---
@@ -128,6 +892,18 @@ struct ImageUrlData {
 #[derive(Deserialize)]
 struct OpenAIResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+struct TokenUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
 }
 
 #[derive(Deserialize)]
@@ -140,6 +916,32 @@ struct ResponseMessage {
     content: String,
 }
 
+#[derive(Deserialize)]
+struct TopicNameResponse {
+    folder: String,
+}
+
+// --- Request/response types for embeddings ---
+
+#[derive(Serialize)]
+struct EmbeddingRequest {
+    model: String,
+    input: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 #[derive(Deserialize)]
 struct GptResponse {
     is_relevant: Option<bool>,
@@ -147,23 +949,196 @@ struct GptResponse {
     confidence: f32,
     reasoning: String,
     suggested_filename: Option<String>,
+    subfolder: Option<String>,
+    subfolder_confidence: Option<f32>,
+    signals: Option<serde_json::Value>, // read leniently: a malformed one shouldn't cost a repair
 }
 
+#[derive(Clone)]
 enum PromptMode {
     FilenameOnly,
-    Vision,
+    Vision(Option<String>), // photo metadata (capture date, device) if any
     TextContent(String), // extracted text snippet
 }
 
-/// Build the classification prompt
-fn build_prompt(
+// Separates a folder path from its description in the prompt's folder list
+pub const FOLDER_DESCRIPTION_SEPARATOR: &str = " :: ";
+
+/// A folder as listed in the prompt: its path, then its description if it has one
+pub fn describe_folder(folder: &str, description: Option<&str>) -> String {
+    match description.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(description) => format!("{}{}{}", folder, FOLDER_DESCRIPTION_SEPARATOR, description),
+        None => folder.to_string(),
+    }
+}
+
+/// A folder's description followed by the other names the user gave it,
+/// e.g. "COMP4702; also called Machine Learning, ML course"
+pub fn describe_with_aliases(description: Option<&str>, aliases: &[&str]) -> Option<String> {
+    let description = description.map(str::trim).filter(|d| !d.is_empty());
+    if aliases.is_empty() {
+        return description.map(str::to_string);
+    }
+    let names = format!("also called {}", aliases.join(", "));
+    Some(match description {
+        Some(description) => format!("{}; {}", description, names),
+        None => names,
+    })
+}
+
+/// A folder's description followed by some of the files already in it,
+/// e.g. "COMP4702 Machine Learning; has files like PS1.pdf, PS2.pdf"
+pub fn describe_with_siblings(description: Option<&str>, siblings: &[String]) -> Option<String> {
+    let description = description.map(str::trim).filter(|d| !d.is_empty());
+    if siblings.is_empty() {
+        return description.map(str::to_string);
+    }
+    let files = format!("has files like {}", siblings.join(", "));
+    Some(match description {
+        Some(description) => format!("{}; {}", description, files),
+        None => files,
+    })
+}
+
+/// The prompt's list of each folder's subfolders, the extra JSON fields and the rule for
+/// them (all empty when no folder has subfolders to choose from)
+fn subfolder_prompt(subfolders: &[(&str, Vec<String>)]) -> (String, &'static str, &'static str) {
+    if subfolders.is_empty() {
+        return (String::new(), "", "");
+    }
+    let lines: Vec<String> = subfolders
+        .iter()
+        .map(|(folder, subfolders)| format!("{}: {}", folder, subfolders.join(" | ")))
+        .collect();
+    (
+        format!("\n\nSubfolders inside these folders:\n{}", lines.join("\n")),
+        ",\n  \"subfolder\": \"Lectures\",\n  \"subfolder_confidence\": 0.8",
+        "\n- subfolder: OPTIONAL. If the chosen folder has subfolders listed above, the one this file belongs in, copied exactly from that folder's list. Set it to null if none fits or the folder has no subfolders listed. Never invent a subfolder\n- subfolder_confidence: how sure you are of the subfolder alone (0-1), separate from confidence",
+    )
+}
+
+/// The path part of a folder the model copied from the list, description and all
+fn strip_folder_description(folder: &str) -> &str {
+    folder.split(FOLDER_DESCRIPTION_SEPARATOR).next().unwrap_or(folder).trim_end()
+}
+
+/// A classification prompt, and what was cut from it to fit the token budget
+struct Prompt {
+    text: String,
+    truncation: Option<PromptTruncation>,
+}
+
+/// What was left out of a prompt that would have gone over the token budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTruncation {
+    pub budget: usize,          // tokens
+    pub original_tokens: usize, // what the whole prompt would have taken
+    pub corrections_dropped: usize, // oldest first
+    pub content_tokens_dropped: usize, // from the end of the extracted text
+}
+
+/// Build the classification prompt, trimmed to the token budget
+fn build_prompt(
+    filename: &str,
+    available_folders: &[String],
+    correction_history: &[String],
+    mode: PromptMode,
+    redact_filenames: bool,
+) -> Prompt {
+    fit_prompt(filename, available_folders, correction_history, mode, redact_filenames, prompt_token_budget())
+}
+
+/// Build the classification prompt in at most `budget` tokens
+///
+/// When the whole prompt doesn't fit, the oldest corrections are dropped (they may use up to
+/// half the room left after the instructions and folder list) and the extracted text is cut
+/// to whatever remains.
+fn fit_prompt(
     filename: &str,
     available_folders: &[String],
     correction_history: &[String],
     mode: PromptMode,
+    redact_filenames: bool,
+    budget: usize,
+) -> Prompt {
+    let full = render_prompt(filename, available_folders, correction_history, &mode, redact_filenames);
+    let original_tokens = count_tokens(&full);
+    if original_tokens <= budget {
+        return Prompt { text: full, truncation: None };
+    }
+
+    // Room left once everything but the corrections and content is in (one blank correction
+    // keeps the corrections heading in the count)
+    let content = match &mode {
+        PromptMode::TextContent(text) => text.as_str(),
+        _ => "",
+    };
+    let bare_mode = match &mode {
+        PromptMode::TextContent(_) => PromptMode::TextContent(String::new()),
+        other => other.clone(),
+    };
+    let heading = if correction_history.is_empty() { Vec::new() } else { vec![String::new()] };
+    let mut room = budget.saturating_sub(count_tokens(&render_prompt(filename, available_folders, &heading, &bare_mode, redact_filenames)));
+
+    let corrections_room = if content.is_empty() { room } else { room / 2 };
+    let mut kept: Vec<String> = Vec::new();
+    let mut used = 0;
+    for line in correction_history.iter().rev() {
+        let tokens = count_tokens(line) + 1; // and its newline
+        if used + tokens > corrections_room {
+            break;
+        }
+        used += tokens;
+        kept.push(line.clone());
+    }
+    kept.reverse();
+    room -= used;
+
+    let content_tokens = count_tokens(content);
+    let mode = match mode {
+        PromptMode::TextContent(text) => PromptMode::TextContent(truncate_to_tokens(&text, room)),
+        other => other,
+    };
+    let truncation = PromptTruncation {
+        budget,
+        original_tokens,
+        corrections_dropped: correction_history.len() - kept.len(),
+        content_tokens_dropped: content_tokens.saturating_sub(room),
+    };
+    eprintln!(
+        "[CLASSIFIER] Prompt over budget ({} > {} tokens): dropped {} correction(s) and {} content token(s)",
+        original_tokens, budget, truncation.corrections_dropped, truncation.content_tokens_dropped
+    );
+    Prompt {
+        text: render_prompt(filename, available_folders, &kept, &mode, redact_filenames),
+        truncation: Some(truncation),
+    }
+}
+
+/// Render the classification prompt in full
+///
+/// With `redact_filenames` (privacy mode) the filename, and the filenames in past
+/// corrections, are replaced by stand-ins that keep only the extension.
+fn render_prompt(
+    filename: &str,
+    available_folders: &[String],
+    correction_history: &[String],
+    mode: &PromptMode,
+    redact_filenames: bool,
 ) -> String {
     let corrections_section = if correction_history.is_empty() {
         String::new()
+    } else if redact_filenames {
+        let examples: Vec<String> = correction_history.iter().map(|line| redact_correction(line)).collect();
+        format!(
+            r#"
+
+Learn from these past corrections by the user:
+{}
+
+Use these examples to improve your accuracy."#,
+            examples.join("\n")
+        )
     } else {
         let examples = correction_history.join("\n");
         format!(
@@ -177,10 +1152,13 @@ Use these examples to improve your accuracy. If a similar filename appears, appl
         )
     };
 
-    let (content_instruction, content_section) = match &mode {
-        PromptMode::Vision => (
+    let (content_instruction, content_section) = match mode {
+        PromptMode::Vision(photo_info) => (
             "Look at the image content to understand what this file is about. Use the visual content (text, formulas, diagrams, code, lecture slides, handwritten notes) to determine the subject matter, NOT just the filename. IMPORTANT: Screenshots of lecture notes, textbook pages, slides, formulas, code, academic websites, or any educational content ARE relevant coursework material — treat them the same as a PDF or document about that subject.".to_string(),
-            String::new(),
+            photo_info
+                .as_ref()
+                .map(|info| format!("\n{} (use the date to match the term or week it belongs to)", info))
+                .unwrap_or_default(),
         ),
         PromptMode::TextContent(text) => (
             "IMPORTANT: Classify this file based PRIMARILY on the extracted text content below, NOT the filename. The filename may be generic (like 'PS2.pdf' or 'notes.pdf') but the actual content reveals the subject. Look for subject-specific keywords, course names, topics, formulas, or terminology in the extracted text to determine the correct folder.".to_string(),
@@ -192,13 +1170,43 @@ Use these examples to improve your accuracy. If a similar filename appears, appl
         ),
     };
 
+    let (filename, filename_rule) = if redact_filenames {
+        (
+            redact_filename(filename),
+            "- suggested_filename: OPTIONAL. The real filename is hidden for privacy, so only include one if the content clearly shows what the file is. Keep the same file extension. Use underscores between words, max 80 characters",
+        )
+    } else {
+        (
+            filename.to_string(),
+            "- suggested_filename: OPTIONAL. Only include if the current filename is genuinely uninformative (e.g. IMG_*, screenshot*, random strings, numbered files like document(1).pdf). If the filename is already descriptive, omit this field or set it to null. Keep the same file extension. Use underscores between words, max 80 characters",
+        )
+    };
+
+    let subfolders: Vec<(&str, Vec<String>)> = if suggest_subfolders() {
+        available_folders
+            .iter()
+            .map(|folder| strip_folder_description(folder))
+            .map(|folder| (folder, subfolders_of(folder)))
+            .filter(|(_, subfolders)| !subfolders.is_empty())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let (subfolders_section, subfolder_fields, subfolder_rule) = subfolder_prompt(&subfolders);
+
+    let descriptions_note = if available_folders.iter().any(|f| f.contains(FOLDER_DESCRIPTION_SEPARATOR)) {
+        "\n(Text after \"::\" describes the folder, e.g. its full course name, other names for it or files already in it. It is not part of the path. Files named like ones already in a folder usually belong there.)"
+    } else {
+        ""
+    };
+
     format!(
         r#"You are a file organization assistant for a student. {content_instruction}
 
 Filename: {filename}{content_section}
 
 Available course folders:
-{folders}
+{folders}{descriptions_note}{subfolders_section}
 
 Respond with ONLY a JSON object in this format:
 {{
@@ -206,7 +1214,8 @@ Respond with ONLY a JSON object in this format:
   "folder": "suggested folder path",
   "confidence": 0.95,
   "reasoning": "brief explanation",
-  "suggested_filename": "Better_Name.pdf"
+  "signals": {{"keywords": ["gradient descent"], "content_clues": ["derivation of the loss gradient"], "similar_correction": null}},
+  "suggested_filename": "Better_Name.pdf"{subfolder_fields}
 }}
 
 Rules:
@@ -217,18 +1226,24 @@ Rules:
 - confidence should be 0-1 (1 = very confident)
 - Consider file extension, name patterns, and common use cases
 - Be concise in reasoning
-- suggested_filename: OPTIONAL. Only include if the current filename is genuinely uninformative (e.g. IMG_*, screenshot*, random strings, numbered files like document(1).pdf). If the filename is already descriptive, omit this field or set it to null. Keep the same file extension. Use underscores between words, max 80 characters{corrections}"#,
+- signals: the evidence for the folder. keywords are words copied exactly from the filename or content; content_clues are a few words each on what the content shows (empty if you only have the filename); similar_correction is the filename of a past correction below that you followed, otherwise null. At most 5 keywords and 5 clues
+{filename_rule}{subfolder_rule}{corrections}"#,
         content_instruction = content_instruction,
         filename = filename,
         content_section = content_section,
         folders = available_folders.join("\n"),
+        descriptions_note = descriptions_note,
+        subfolders_section = subfolders_section,
+        subfolder_fields = subfolder_fields,
+        subfolder_rule = subfolder_rule,
+        filename_rule = filename_rule,
         corrections = corrections_section
     )
 }
 
-/// Parse the GPT response JSON into a Classification
-fn parse_response(content: &str) -> Result<Classification, ClassifierError> {
-    let json_str = if content.contains("```json") {
+/// Strip a ```json fence (if any) from a GPT reply
+fn extract_json(content: &str) -> &str {
+    if content.contains("```json") {
         content
             .split("```json")
             .nth(1)
@@ -243,7 +1258,12 @@ fn parse_response(content: &str) -> Result<Classification, ClassifierError> {
             .trim()
     } else {
         content.trim()
-    };
+    }
+}
+
+/// Parse the GPT response JSON into a Classification
+fn parse_response(content: &str) -> Result<Classification, ClassifierError> {
+    let json_str = extract_json(content);
 
     let gpt_response: GptResponse = serde_json::from_str(json_str)
         .map_err(|e| ClassifierError::ParseJson {
@@ -253,18 +1273,134 @@ fn parse_response(content: &str) -> Result<Classification, ClassifierError> {
 
     // Clamp confidence to [0.0, 1.0] range
     let confidence = gpt_response.confidence.clamp(0.0, 1.0);
+    let subfolder = gpt_response.subfolder.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
 
     Ok(Classification {
         is_relevant: gpt_response.is_relevant.unwrap_or(false),
-        suggested_folder: gpt_response.folder,
+        suggested_folder: strip_folder_description(&gpt_response.folder).to_string(),
         confidence,
         reasoning: gpt_response.reasoning,
-        suggested_filename: gpt_response.suggested_filename,
+        // The AI's names can contain characters the filesystem rejects ("Lecture 3: Intro.pdf")
+        suggested_filename: gpt_response.suggested_filename.and_then(|n| crate::filename::sanitize(&n)),
+        tier: None,
+        truncation: None,
+        calibrated_confidence: None,
+        subfolder_confidence: subfolder.is_some().then(|| gpt_response.subfolder_confidence.unwrap_or(0.0).clamp(0.0, 1.0)),
+        suggested_subfolder: subfolder,
+        explanation: gpt_response.signals.as_ref().and_then(parse_signals),
     })
 }
 
-/// Handle an OpenAI API response: check status, parse JSON, extract classification
-async fn handle_api_response(response: reqwest::Response) -> Result<Classification, ClassifierError> {
+/// The model's `signals` object as an Explanation, accepting a lone string where a list was
+/// asked for; None when it names nothing
+fn parse_signals(signals: &serde_json::Value) -> Option<Explanation> {
+    fn texts(value: Option<&serde_json::Value>) -> Vec<String> {
+        let values = match value {
+            Some(serde_json::Value::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+            None => Vec::new(),
+        };
+        let mut texts: Vec<String> = Vec::new();
+        for text in values.into_iter().filter_map(serde_json::Value::as_str) {
+            let text = text.trim().trim_matches(['"', '\'']).trim();
+            if !text.is_empty() && !texts.iter().any(|t| t.eq_ignore_ascii_case(text)) {
+                texts.push(text.to_string());
+            }
+        }
+        texts.truncate(EXPLANATION_LIMIT);
+        texts
+    }
+
+    let explanation = Explanation {
+        keywords: texts(signals.get("keywords")),
+        content_clues: texts(signals.get("content_clues")),
+        similar_correction: texts(signals.get("similar_correction"))
+            .into_iter()
+            .find(|name| !name.eq_ignore_ascii_case("null") && !name.eq_ignore_ascii_case("none")),
+    };
+    (explanation != Explanation::default()).then_some(explanation)
+}
+
+// Sent back to a model whose reply couldn't be parsed, followed by that reply
+const REPAIR_PROMPT: &str = r#"Your previous reply could not be read as JSON. Return ONLY a valid JSON object matching this schema, with no other text:
+{
+  "is_relevant": boolean,
+  "folder": string,
+  "confidence": number from 0 to 1,
+  "reasoning": string,
+  "signals": {"keywords": [string], "content_clues": [string], "similar_correction": string or null},
+  "suggested_filename": string or null
+}
+
+Your previous reply:
+"#;
+
+/// Handle an OpenAI API response: check status, parse JSON, extract classification.
+/// A reply that isn't valid JSON gets one repair request before the error is returned.
+async fn handle_api_response(
+    response: reqwest::Response,
+    endpoint: &Endpoint,
+    kind: CallKind,
+    api_key: &str,
+) -> Result<Classification, ClassifierError> {
+    let reply = read_chat_response(response).await?;
+    let model = reply.model.as_deref().unwrap_or(endpoint.model_for(kind));
+    record_usage(&endpoint.url, model, reply.usage, None);
+    match parse_response(&reply.content) {
+        Err(e @ ClassifierError::ParseJson { .. }) => {
+            let model = model.to_string();
+            repair_response(endpoint, &model, api_key, &reply.content).await.ok_or(e)
+        }
+        result => result,
+    }
+}
+
+/// Ask `model` to rewrite its unparseable `content` as the JSON the prompt asked for.
+/// The attempt is recorded against the model whether it works or not, so the usage table
+/// shows how often each model needs repairing.
+async fn repair_response(endpoint: &Endpoint, model: &str, api_key: &str, content: &str) -> Option<Classification> {
+    println!("[CLASSIFIER] Reply from {} wasn't valid JSON, asking it to repair it", model);
+    let prompt = format!("{}{}", REPAIR_PROMPT, content);
+    let reply = match post_text_request(endpoint, model, api_key, prompt, timeouts().text_secs).await {
+        Ok(response) => read_chat_response(response).await.ok(),
+        Err(_) => None,
+    };
+    let classification = reply.as_ref().and_then(|reply| parse_response(&reply.content).ok());
+    // Counted even when the server doesn't report usage, so every repair shows up
+    let usage = reply.and_then(|reply| reply.usage).unwrap_or_default();
+    record_usage(&endpoint.url, model, Some(usage), Some(classification.is_some()));
+    if classification.is_none() {
+        eprintln!("[CLASSIFIER] Repair request to {} didn't return valid JSON either", model);
+    }
+    classification
+}
+
+/// Count a call to `url` against the budget; `repaired` is set for repair requests
+fn record_usage(url: &str, model: &str, usage: Option<TokenUsage>, repaired: Option<bool>) {
+    if let Some(usage) = usage {
+        crate::budget::record(model, !is_local_url(url), usage.prompt_tokens, usage.completion_tokens, repaired);
+    }
+}
+
+/// A chat response's first choice, and the model and tokens the server reported
+struct ChatReply {
+    content: String,
+    model: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+/// Check an OpenAI chat response's status and return the first choice's text
+async fn response_content(response: reqwest::Response) -> Result<String, ClassifierError> {
+    let url = response.url().to_string();
+    let reply = read_chat_response(response).await?;
+    if let Some(model) = &reply.model {
+        record_usage(&url, model, reply.usage, None);
+    }
+    Ok(reply.content)
+}
+
+/// Check an OpenAI chat response's status and read the first choice without recording it
+async fn read_chat_response(response: reqwest::Response) -> Result<ChatReply, ClassifierError> {
     if !response.status().is_success() {
         let status = response.status();
         let _error_text = response.text().await.unwrap_or_default();
@@ -275,22 +1411,169 @@ async fn handle_api_response(response: reqwest::Response) -> Result<Classificati
         .json()
         .await
         .map_err(ClassifierError::HttpClient)?;
+    let content = api_response
+        .choices
+        .into_iter()
+        .next()
+        .map(|choice| choice.message.content)
+        .ok_or(ClassifierError::NoChoices)?;
+    Ok(ChatReply { content, model: api_response.model, usage: api_response.usage })
+}
+
+/// What's being classified, for the mock tier (which answers without reading the prompt) and
+/// for checking the answer against what the model was shown
+struct Subject<'a> {
+    kind: CallKind,
+    filename: &'a str,
+    text: Option<&'a str>,
+    folders: &'a [String],
+    corrections: &'a [String],
+}
+
+/// Ask each tier in turn until one answers, moving on when a tier can't be reached, isn't
+/// authorized or is out of quota, and note which tier gave the answer
+async fn classify_with_fallback<F, Fut>(
+    api_key: &str,
+    subject: Subject<'_>,
+    request: F,
+) -> Result<Classification, ClassifierError>
+where
+    F: Fn(Endpoint) -> Fut,
+    Fut: std::future::Future<Output = Result<Classification, ClassifierError>>,
+{
+    let budget = crate::budget::state();
+    let mut tiers = budgeted_tiers(budget).into_iter().peekable();
+    while let Some(tier) = tiers.next() {
+        let endpoint = budgeted_endpoint(&tier, budget, subject.kind);
+        let label = tier.label_with(&endpoint, subject.kind);
+        let result = if tier.provider == Provider::Mock {
+            Ok(crate::mock_classifier::classify(subject.filename, subject.text, subject.folders))
+        } else if api_key.is_empty() && tier.needs_api_key() {
+            Err(ClassifierError::MissingApiKey)
+        } else {
+            request(endpoint).await
+        };
+        match result {
+            Ok(classification) => {
+                return Ok(Classification {
+                    tier: Some(label),
+                    ..ground_explanation(clamp_subfolder(clamp_folder(classification, subject.folders), subfolders_of), &subject)
+                })
+            }
+            Err(e) if should_fall_back(&e) && tiers.peek().is_some() => {
+                eprintln!("[CLASSIFIER] {} failed ({}), trying the next fallback", label, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(ClassifierError::MissingApiKey) // only reached with no tiers at all
+}
+
+/// Point `classification` at the listed folder its answer means (models change the casing,
+/// shorten paths or invent subfolders), or leave it unsorted when it means none of `folders`
+fn clamp_folder(classification: Classification, folders: &[String]) -> Classification {
+    let answer = classification.suggested_folder.as_str();
+    if !classification.is_relevant || answer.is_empty() || answer == folder_match::UNSORTED || folders.is_empty() {
+        return classification;
+    }
+    let paths: Vec<&str> = folders.iter().map(|folder| strip_folder_description(folder)).collect();
+    match folder_match::resolve(answer, &paths, &folder_match::aliases()) {
+        Some(folder) if folder == answer => classification,
+        Some(folder) => {
+            println!("[CLASSIFIER] Answered \"{}\", using the listed folder \"{}\"", answer, folder);
+            Classification { suggested_folder: folder.to_string(), ..classification }
+        }
+        None => {
+            println!("[CLASSIFIER] Answered \"{}\", which isn't one of the folders; leaving it unsorted", answer);
+            let reasoning = format!("{} (suggested \"{}\", which isn't one of your folders)", classification.reasoning, answer);
+            Classification { suggested_folder: folder_match::UNSORTED.to_string(), reasoning, ..classification }
+        }
+    }
+}
+
+/// Drop the parts of the model's explanation it didn't get from the file: keywords that aren't
+/// in the filename or text (an image's can't be checked) and corrections it wasn't shown
+fn ground_explanation(mut classification: Classification, subject: &Subject) -> Classification {
+    // Compared as words, so "gradient descent" is found in "gradient_descent.pdf"
+    fn words(text: &str) -> String {
+        let text: String = text.chars().map(|c| if c.is_alphanumeric() { c } else { ' ' }).collect();
+        format!(" {} ", text.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" "))
+    }
 
-    if api_response.choices.is_empty() {
-        return Err(ClassifierError::NoChoices);
+    let Some(mut explanation) = classification.explanation.take() else {
+        return classification;
+    };
+    if subject.kind != CallKind::Vision {
+        let seen = words(&format!("{} {}", subject.filename, subject.text.unwrap_or_default()));
+        explanation.keywords.retain(|keyword| seen.contains(&words(keyword)));
     }
+    explanation.similar_correction = explanation.similar_correction.and_then(|name| {
+        subject
+            .corrections
+            .iter()
+            .filter_map(|line| crate::insights::example_filename(line))
+            .find(|shown| shown.eq_ignore_ascii_case(&name))
+            .map(str::to_string)
+    });
+    classification.explanation = (explanation != Explanation::default()).then_some(explanation);
+    classification
+}
 
-    parse_response(&api_response.choices[0].message.content)
+/// Keep the model's subfolder only if it's one of those found in the chosen folder (matched
+/// loosely, like the folder itself), spelt as it is on disk
+fn clamp_subfolder(classification: Classification, subfolders_of: impl Fn(&str) -> Vec<String>) -> Classification {
+    let Some(answer) = classification.suggested_subfolder.as_deref() else {
+        return classification;
+    };
+    let folder = classification.suggested_folder.as_str();
+    let known = if classification.is_relevant && !folder.is_empty() && folder != folder_match::UNSORTED {
+        subfolders_of(folder)
+    } else {
+        Vec::new()
+    };
+    let known: Vec<&str> = known.iter().map(String::as_str).collect();
+    match folder_match::resolve(answer, &known, &[]) {
+        Some(subfolder) => Classification { suggested_subfolder: Some(subfolder.to_string()), ..classification },
+        None => {
+            println!("[CLASSIFIER] Subfolder \"{}\" isn't in \"{}\", ignoring it", answer, folder);
+            Classification { suggested_subfolder: None, subfolder_confidence: None, ..classification }
+        }
+    }
 }
 
-/// Send a text-based request to the OpenAI chat completions API and parse the response
+/// Send a text-based classification request, falling back through the tiers, and parse the response
 async fn send_text_request(
     api_key: &str,
-    prompt: String,
+    prompt: Prompt,
+    subject: Subject<'_>,
     timeout_secs: u64,
 ) -> Result<Classification, ClassifierError> {
+    let kind = subject.kind;
+    let filename = subject.filename.to_string();
+    let classification = classify_with_fallback(api_key, subject, |endpoint| {
+        let prompt = prompt.text.clone();
+        async move {
+            let response = post_text_request(&endpoint, &endpoint.text_model, api_key, prompt, timeout_secs).await?;
+            handle_api_response(response, &endpoint, kind, api_key).await
+        }
+    })
+    .await?;
+    if let Some(record) = PROMPT_RECORDER.get() {
+        record(&filename, &prompt.text);
+    }
+    Ok(Classification { truncation: prompt.truncation, ..classification })
+}
+
+/// POST a single-message prompt to `model` on a chat completions API
+async fn post_text_request(
+    endpoint: &Endpoint,
+    model: &str,
+    api_key: &str,
+    prompt: String,
+    timeout_secs: u64,
+) -> Result<reqwest::Response, ClassifierError> {
     let request = TextRequest {
-        model: "gpt-3.5-turbo".to_string(),
+        model: model.to_string(),
         messages: vec![TextMessage {
             role: "user".to_string(),
             content: prompt,
@@ -298,43 +1581,44 @@ async fn send_text_request(
         temperature: 0.3,
     };
 
-    rate_limit();
+    let client = http_client(&endpoint.url, Duration::from_secs(timeout_secs))?;
+    let _slot = request_slot().await;
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs))
-        .build()
-        .map_err(ClassifierError::HttpClient)?;
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
+    endpoint
+        .post(&client, api_key)
         .header("Content-Type", "application/json")
         .json(&request)
         .send()
         .await
-        .map_err(ClassifierError::HttpClient)?;
-
-    handle_api_response(response).await
+        .map_err(ClassifierError::HttpClient)
 }
 
-/// Classify a file using filename only (GPT-3.5-turbo)
+/// Classify a file using filename only (the filename route; GPT-3.5-turbo by default)
 pub async fn classify_file(
     api_key: String,
     filename: String,
     available_folders: Vec<String>,
     correction_history: Vec<String>,
 ) -> Result<Classification, String> {
-    if api_key.is_empty() {
+    if missing_api_key(&api_key) {
         return Err(ClassifierError::MissingApiKey.to_string());
     }
 
-    let prompt = build_prompt(&filename, &available_folders, &correction_history, PromptMode::FilenameOnly);
+    let prompt = build_prompt(&filename, &available_folders, &correction_history, PromptMode::FilenameOnly, privacy_mode());
+    let subject = Subject {
+        kind: CallKind::Filename,
+        filename: &filename,
+        text: None,
+        folders: &available_folders,
+        corrections: &correction_history,
+    };
 
-    send_text_request(&api_key, prompt, API_TIMEOUT_SECS).await.map_err(|e| e.to_string())
+    send_text_request(&api_key, prompt, subject, timeouts().text_secs).await.map_err(|e| e.to_string())
 }
 
-/// Classify an image file using GPT-4o vision
+/// Classify an image file using a vision model (the vision route; GPT-4o by default)
 ///
-/// Reads the image, base64-encodes it, and sends it to GPT-4o
+/// Reads the image, base64-encodes it, and sends it to the vision model
 /// so the AI can see the actual content (math, text, diagrams, etc.)
 pub async fn classify_image_file(
     api_key: String,
@@ -355,7 +1639,7 @@ async fn classify_image_file_impl(
     available_folders: Vec<String>,
     correction_history: Vec<String>,
 ) -> Result<Classification, ClassifierError> {
-    if api_key.is_empty() {
+    if missing_api_key(&api_key) {
         return Err(ClassifierError::MissingApiKey);
     }
 
@@ -375,8 +1659,8 @@ async fn classify_image_file_impl(
 
     let base64_data = base64::engine::general_purpose::STANDARD.encode(&image_bytes);
 
-    // Determine MIME type from extension
-    let mime_type = match file_path.rsplit('.').next().unwrap_or("").to_lowercase().as_str() {
+    // Determine MIME type from the file's content (the extension may be missing or wrong)
+    let mime_type = match crate::filetype::detect(std::path::Path::new(&file_path)).extension.as_str() {
         "png" => "image/png",
         "jpg" | "jpeg" => "image/jpeg",
         "gif" => "image/gif",
@@ -385,44 +1669,55 @@ async fn classify_image_file_impl(
         _ => "image/png", // fallback
     };
 
-    let prompt = build_prompt(&filename, &available_folders, &correction_history, PromptMode::Vision);
+    let photo_info = crate::metadata::read_exif(std::path::Path::new(&file_path)).and_then(|e| e.summary());
+    let prompt = build_prompt(&filename, &available_folders, &correction_history, PromptMode::Vision(photo_info), privacy_mode());
 
     let data_url = format!("data:{};base64,{}", mime_type, base64_data);
-
-    let request = VisionRequest {
-        model: "gpt-4o".to_string(),
-        messages: vec![VisionMessage {
-            role: "user".to_string(),
-            content: vec![
-                VisionContent::Text { text: prompt },
-                VisionContent::ImageUrl {
-                    image_url: ImageUrlData {
-                        url: data_url,
-                        detail: "low".to_string(), // low detail to reduce cost
-                    },
-                },
-            ],
-        }],
-        temperature: 0.3,
-        max_tokens: 300,
+    let subject = Subject {
+        kind: CallKind::Vision,
+        filename: &filename,
+        text: None,
+        folders: &available_folders,
+        corrections: &correction_history,
     };
 
-    rate_limit();
-
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(API_TIMEOUT_SECS * 2)) // Vision needs more time
-        .build()
-        .map_err(ClassifierError::HttpClient)?;
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(ClassifierError::HttpClient)?;
-
-    handle_api_response(response).await
+    let vision_secs = timeouts().vision_secs;
+    let classification = classify_with_fallback(&api_key, subject, |endpoint| {
+        let request = VisionRequest {
+            model: endpoint.vision_model.clone(),
+            messages: vec![VisionMessage {
+                role: "user".to_string(),
+                content: vec![
+                    VisionContent::Text { text: prompt.text.clone() },
+                    VisionContent::ImageUrl {
+                        image_url: ImageUrlData {
+                            url: data_url.clone(),
+                            detail: "low".to_string(), // low detail to reduce cost
+                        },
+                    },
+                ],
+            }],
+            temperature: 0.3,
+            max_tokens: 300,
+        };
+        let api_key = &api_key;
+        async move {
+            let client = http_client(&endpoint.url, Duration::from_secs(vision_secs))?;
+            let _slot = request_slot().await;
+
+            let response = endpoint
+                .post(&client, api_key)
+                .header("Content-Type", "application/json")
+                .json(&request)
+                .send()
+                .await
+                .map_err(ClassifierError::HttpClient)?;
+
+            handle_api_response(response, &endpoint, CallKind::Vision, api_key).await
+        }
+    })
+    .await?;
+    Ok(Classification { truncation: prompt.truncation, ..classification })
 }
 
 /// Extract text from a PDF file (first ~500 chars)
@@ -539,7 +1834,7 @@ mod tests {
     fn test_build_prompt_filename_only() {
         let folders = vec!["ML".to_string(), "Physics".to_string()];
         let corrections: Vec<String> = vec![];
-        let prompt = build_prompt("lecture_notes.pdf", &folders, &corrections, PromptMode::FilenameOnly);
+        let prompt = build_prompt("lecture_notes.pdf", &folders, &corrections, PromptMode::FilenameOnly, false).text;
 
         assert!(prompt.contains("Filename: lecture_notes.pdf"));
         assert!(prompt.contains("ML\nPhysics"));
@@ -552,7 +1847,7 @@ mod tests {
     fn test_build_prompt_vision_mode() {
         let folders = vec!["OR".to_string()];
         let corrections: Vec<String> = vec![];
-        let prompt = build_prompt("screenshot.png", &folders, &corrections, PromptMode::Vision);
+        let prompt = build_prompt("screenshot.png", &folders, &corrections, PromptMode::Vision(None), false).text;
 
         assert!(prompt.contains("Look at the image content"));
         assert!(prompt.contains("Filename: screenshot.png"));
@@ -564,7 +1859,7 @@ mod tests {
         let folders = vec!["Math".to_string()];
         let corrections: Vec<String> = vec![];
         let text = "Integration by parts formula...".to_string();
-        let prompt = build_prompt("tutorial3.pdf", &folders, &corrections, PromptMode::TextContent(text));
+        let prompt = build_prompt("tutorial3.pdf", &folders, &corrections, PromptMode::TextContent(text), false).text;
 
         assert!(prompt.contains("Extracted text content"));
         assert!(prompt.contains("Integration by parts formula"));
@@ -578,7 +1873,7 @@ mod tests {
         let corrections = vec![
             "\"romer_model.pdf\" → AI suggested ML, but user moved to Econ".to_string(),
         ];
-        let prompt = build_prompt("test.pdf", &folders, &corrections, PromptMode::FilenameOnly);
+        let prompt = build_prompt("test.pdf", &folders, &corrections, PromptMode::FilenameOnly, false).text;
 
         assert!(prompt.contains("Learn from these past corrections"));
         assert!(prompt.contains("romer_model.pdf"));
@@ -588,7 +1883,7 @@ mod tests {
     fn test_build_prompt_no_corrections() {
         let folders = vec!["ML".to_string()];
         let corrections: Vec<String> = vec![];
-        let prompt = build_prompt("test.pdf", &folders, &corrections, PromptMode::FilenameOnly);
+        let prompt = build_prompt("test.pdf", &folders, &corrections, PromptMode::FilenameOnly, false).text;
 
         assert!(!prompt.contains("Learn from these past corrections"));
     }
@@ -597,7 +1892,7 @@ mod tests {
     fn test_build_prompt_empty_folders() {
         let folders: Vec<String> = vec![];
         let corrections: Vec<String> = vec![];
-        let prompt = build_prompt("test.pdf", &folders, &corrections, PromptMode::FilenameOnly);
+        let prompt = build_prompt("test.pdf", &folders, &corrections, PromptMode::FilenameOnly, false).text;
 
         assert!(prompt.contains("Available course folders:"));
         // Should still have the section, just empty
@@ -608,11 +1903,193 @@ mod tests {
     fn test_build_prompt_special_characters_in_filename() {
         let folders = vec!["ML".to_string()];
         let corrections: Vec<String> = vec![];
-        let prompt = build_prompt("lecture (2) [final].pdf", &folders, &corrections, PromptMode::FilenameOnly);
+        let prompt = build_prompt("lecture (2) [final].pdf", &folders, &corrections, PromptMode::FilenameOnly, false).text;
 
         assert!(prompt.contains("Filename: lecture (2) [final].pdf"));
     }
 
+    #[test]
+    fn test_build_prompt_redacts_filenames() {
+        let folders = vec!["ML".to_string()];
+        let corrections = vec![
+            "[Folder accuracy stats: ML: 50% accuracy (1/2)]".to_string(),
+            "\"Jane_Doe_payslip.pdf\" → AI suggested ML, but user moved to Finance".to_string(),
+        ];
+        let text = "Gradient descent converges when...".to_string();
+        let prompt = build_prompt("Jane_Doe_transcript.PDF", &folders, &corrections, PromptMode::TextContent(text), true).text;
+
+        assert!(!prompt.contains("Jane_Doe"));
+        assert!(prompt.contains(&format!("Filename: {}", redact_filename("Jane_Doe_transcript.PDF"))));
+        assert!(prompt.contains("Gradient descent converges"));
+        assert!(prompt.contains(&format!("\"{}\" → AI suggested ML, but user moved to Finance", redact_filename("Jane_Doe_payslip.pdf"))));
+        assert!(prompt.contains("[Folder accuracy stats: ML: 50% accuracy (1/2)]"));
+        assert!(prompt.contains("real filename is hidden"));
+    }
+
+    #[test]
+    fn test_redact_filename() {
+        let redacted = redact_filename("Jane Doe - passport scan.JPG");
+        assert!(redacted.starts_with("file_") && redacted.ends_with(".jpg"), "{}", redacted);
+        assert_eq!(redacted.len(), "file_".len() + 8 + ".jpg".len());
+        assert_eq!(redact_filename("Jane Doe - passport scan.JPG"), redacted, "stable across calls");
+        assert_ne!(redact_filename("John Doe - passport scan.JPG"), redacted);
+        assert!(!redact_filename("Makefile").contains('.'));
+    }
+
+    #[test]
+    fn test_build_prompt_folder_descriptions() {
+        let folders = vec![
+            describe_folder("C:\\Uni\\ML", Some("COMP4702 Machine Learning")),
+            describe_folder("C:\\Uni\\Stats", Some("  ")),
+        ];
+        assert_eq!(folders[1], "C:\\Uni\\Stats");
+        let prompt = build_prompt("test.pdf", &folders, &[], PromptMode::FilenameOnly, false).text;
+        assert!(prompt.contains("C:\\Uni\\ML :: COMP4702 Machine Learning\nC:\\Uni\\Stats"));
+        assert!(prompt.contains("It is not part of the path"));
+
+        // Files already in a folder are listed after its description
+        let siblings = vec!["PS1.pdf".to_string(), "PS2.pdf".to_string()];
+        assert_eq!(
+            describe_with_siblings(Some("COMP4702 Machine Learning"), &siblings).as_deref(),
+            Some("COMP4702 Machine Learning; has files like PS1.pdf, PS2.pdf")
+        );
+        assert_eq!(describe_with_siblings(Some(" "), &siblings).as_deref(), Some("has files like PS1.pdf, PS2.pdf"));
+        assert_eq!(describe_with_siblings(None, &[]), None);
+
+        // Aliases come after the description
+        assert_eq!(
+            describe_with_aliases(Some("COMP4702"), &["Machine Learning", "ML course"]).as_deref(),
+            Some("COMP4702; also called Machine Learning, ML course")
+        );
+        assert_eq!(describe_with_aliases(None, &["Operations Research"]).as_deref(), Some("also called Operations Research"));
+        assert_eq!(describe_with_aliases(Some("COMP4702"), &[]).as_deref(), Some("COMP4702"));
+
+        // A description the model copied along with the path is dropped
+        let content = r#"{"is_relevant": true, "folder": "C:\\Uni\\ML :: COMP4702 Machine Learning", "confidence": 0.9, "reasoning": "ML"}"#;
+        assert_eq!(parse_response(content).unwrap().suggested_folder, "C:\\Uni\\ML");
+    }
+
+    #[test]
+    fn test_clamp_folder_to_listed_folders() {
+        let folders = vec!["C:\\Uni\\ML :: COMP4702 Machine Learning".to_string(), "C:\\Uni\\Stats".to_string()];
+        let answer = |folder: &str| {
+            parse_response(&format!(r#"{{"is_relevant": true, "folder": {:?}, "confidence": 0.9, "reasoning": "ML"}}"#, folder)).unwrap()
+        };
+
+        assert_eq!(clamp_folder(answer("c:/uni/ml/"), &folders).suggested_folder, "C:\\Uni\\ML");
+        assert_eq!(clamp_folder(answer("C:\\Uni\\ML\\Week 3"), &folders).suggested_folder, "C:\\Uni\\ML");
+        assert_eq!(clamp_folder(answer("Stats"), &folders).suggested_folder, "C:\\Uni\\Stats");
+
+        let invented = clamp_folder(answer("C:\\Uni\\Quantum"), &folders);
+        assert_eq!(invented.suggested_folder, "__UNSORTED__");
+        assert!(invented.reasoning.contains("isn't one of your folders"));
+
+        // Irrelevant files and unsorted answers are left alone
+        assert_eq!(clamp_folder(answer("__UNSORTED__"), &folders).suggested_folder, "__UNSORTED__");
+        assert_eq!(clamp_folder(answer("C:\\Uni\\Quantum"), &[]).suggested_folder, "C:\\Uni\\Quantum");
+    }
+
+    #[test]
+    fn test_parse_and_ground_explanation() {
+        let content = r#"{"is_relevant": true, "folder": "ML", "confidence": 0.9, "reasoning": "ML",
+            "signals": {"keywords": ["Gradient Descent", "gradient descent", " ", "backprop"], "content_clues": "loss curve plot",
+                        "similar_correction": "\"ps3_romer.pdf\""}}"#;
+        let parsed = parse_response(content).unwrap();
+        let explanation = parsed.explanation.clone().unwrap();
+        assert_eq!(explanation.keywords, vec!["Gradient Descent", "backprop"]);
+        assert_eq!(explanation.content_clues, vec!["loss curve plot"]);
+        assert_eq!(explanation.similar_correction.as_deref(), Some("ps3_romer.pdf"));
+
+        // Only what's in the file, and corrections that were shown
+        let folders = vec!["ML".to_string()];
+        let corrections = vec!["\"PS3_Romer.pdf\" → ML (corrected)".to_string()];
+        let subject = |kind, text| Subject { kind, filename: "week3.pdf", text, folders: &folders, corrections: &corrections };
+        let grounded = ground_explanation(parsed.clone(), &subject(CallKind::Content, Some("We apply gradient_descent to...")));
+        let grounded = grounded.explanation.unwrap();
+        assert_eq!(grounded.keywords, vec!["Gradient Descent"]);
+        assert_eq!(grounded.similar_correction.as_deref(), Some("PS3_Romer.pdf"));
+        let image = ground_explanation(parsed.clone(), &subject(CallKind::Vision, None)).explanation.unwrap();
+        assert_eq!(image.keywords.len(), 2, "an image's keywords can't be checked");
+        let unshown = Subject { corrections: &[], ..subject(CallKind::Filename, None) };
+        assert_eq!(ground_explanation(parsed, &unshown).explanation.unwrap().similar_correction, None);
+
+        for signals in [r#""signals": {"keywords": [], "similar_correction": "null"}"#, r#""signals": "n/a""#] {
+            let content = format!(r#"{{"is_relevant": true, "folder": "ML", "confidence": 0.9, "reasoning": "ML", {}}}"#, signals);
+            assert_eq!(parse_response(&content).unwrap().explanation, None);
+        }
+    }
+
+    #[test]
+    fn test_subfolder_suggestions() {
+        let (section, fields, rule) = subfolder_prompt(&[("C:\\Uni\\ML", vec!["Lectures".to_string(), "Problem Sets".to_string()])]);
+        assert!(section.contains("C:\\Uni\\ML: Lectures | Problem Sets"));
+        assert!(fields.contains("\"subfolder_confidence\""));
+        assert!(rule.contains("Never invent a subfolder"));
+        assert_eq!(subfolder_prompt(&[]), (String::new(), "", ""));
+
+        let content = r#"{"is_relevant": true, "folder": "C:\\Uni\\ML", "confidence": 0.9, "reasoning": "ML", "subfolder": "problem sets", "subfolder_confidence": 1.4}"#;
+        let classification = parse_response(content).unwrap();
+        assert_eq!(classification.suggested_subfolder.as_deref(), Some("problem sets"));
+        assert_eq!(classification.subfolder_confidence, Some(1.0));
+
+        let subfolders = |folder: &str| match folder {
+            "C:\\Uni\\ML" => vec!["Lectures".to_string(), "Problem Sets".to_string()],
+            _ => Vec::new(),
+        };
+        // Matched to the subfolder's real spelling
+        let clamped = clamp_subfolder(classification.clone(), subfolders);
+        assert_eq!(clamped.suggested_subfolder.as_deref(), Some("Problem Sets"));
+        assert_eq!(clamped.subfolder_confidence, Some(1.0));
+
+        // A subfolder that doesn't exist is dropped, with its confidence
+        let invented = Classification { suggested_subfolder: Some("Exams".to_string()), ..classification.clone() };
+        let clamped = clamp_subfolder(invented, subfolders);
+        assert_eq!(clamped.suggested_subfolder, None);
+        assert_eq!(clamped.subfolder_confidence, None);
+
+        let no_subfolder = parse_response(r#"{"is_relevant": true, "folder": "ML", "confidence": 0.9, "reasoning": "ML", "subfolder": " "}"#).unwrap();
+        assert_eq!((no_subfolder.suggested_subfolder, no_subfolder.subfolder_confidence), (None, None));
+    }
+
+    #[test]
+    fn test_fit_prompt_to_token_budget() {
+        let folders = vec!["Physics".to_string(), "Math".to_string()];
+        let corrections: Vec<String> = (0..200).map(|i| format!("\"notes_{}.pdf\" → Physics (correct)", i)).collect();
+        let text = "quantum harmonic oscillator eigenstates ".repeat(1000);
+
+        let fitted = fit_prompt("notes.pdf", &folders, &corrections, PromptMode::TextContent(text.clone()), false, 1500);
+        let truncation = fitted.truncation.expect("over budget");
+        assert!(count_tokens(&fitted.text) <= 1500, "fits: {} tokens", count_tokens(&fitted.text));
+        assert!(truncation.original_tokens > 1500);
+        assert!(truncation.corrections_dropped > 0 && truncation.corrections_dropped < 200);
+        assert!(truncation.content_tokens_dropped > 0);
+        assert!(fitted.text.contains("notes_199.pdf"), "newest corrections are kept");
+        assert!(!fitted.text.contains("\"notes_0.pdf\""));
+        assert!(fitted.text.contains("quantum harmonic"));
+
+        let small = fit_prompt("notes.pdf", &folders, &corrections[..2], PromptMode::FilenameOnly, false, 1500);
+        assert!(small.truncation.is_none());
+        assert!(small.text.contains("notes_1.pdf"));
+    }
+
+    #[test]
+    fn test_pack_by_relevance() {
+        let long = "lecture notes ".repeat(40);
+        let candidates = vec![
+            (0.1, "unrelated.pdf".to_string()),
+            (0.9, "romer_growth.pdf".to_string()),
+            (0.8, long.clone()),
+            (0.5, "solow_model.pdf".to_string()),
+            (0.9, "romer_growth.pdf".to_string()),
+        ];
+        let budget = count_tokens("romer_growth.pdf") + count_tokens("solow_model.pdf") + 2;
+
+        // The long line doesn't fit, so the next most relevant one takes its place
+        let packed = pack_by_relevance(candidates.clone(), budget);
+        assert_eq!(packed, vec!["solow_model.pdf", "romer_growth.pdf"]);
+        assert_eq!(pack_by_relevance(candidates, 0), Vec::<String>::new());
+    }
+
     // --- Classification struct tests ---
 
     #[test]
@@ -623,6 +2100,12 @@ mod tests {
             confidence: 0.9,
             reasoning: "test".to_string(),
             suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
         };
         let json = serde_json::to_string(&c).unwrap();
         assert!(json.contains("\"is_relevant\":true"));
@@ -646,10 +2129,123 @@ mod tests {
             confidence: 0.85,
             reasoning: "physics material".to_string(),
             suggested_filename: Some("Physics_Lecture_Notes.pdf".to_string()),
+            tier: Some("openai/gpt-3.5-turbo".to_string()),
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
         };
         let c2 = c.clone();
         assert_eq!(c.suggested_folder, c2.suggested_folder);
         assert_eq!(c.confidence, c2.confidence);
+        assert_eq!(c.tier, c2.tier);
+    }
+
+    #[tokio::test]
+    async fn test_request_slots_limit_concurrency() {
+        set_max_concurrent_requests(2);
+        let first = request_slot().await;
+        let _second = request_slot().await;
+        let third = tokio::time::timeout(Duration::from_millis(50), request_slot()).await;
+        assert!(third.is_err(), "a third request waits for a free slot");
+
+        drop(first);
+        let started = Instant::now();
+        let _third = request_slot().await;
+        assert!(started.elapsed() < Duration::from_millis(MIN_API_INTERVAL_MS * 2), "and starts once one is freed");
+
+        // A new limit applies from the next request; slots taken under the old one don't count
+        let free_slots = || REQUEST_SLOTS.lock().unwrap().as_ref().map(|slots| slots.available_permits());
+        set_max_concurrent_requests(0);
+        assert_eq!(max_concurrent_requests(), 1, "clamped to at least one");
+        assert_eq!(free_slots(), Some(1));
+        drop(_second);
+        assert_eq!(free_slots(), Some(1));
+        let only = request_slot().await;
+        assert_eq!(free_slots(), Some(0));
+        drop(only);
+
+        set_max_concurrent_requests(100);
+        assert_eq!(max_concurrent_requests(), MAX_CONCURRENT_REQUESTS);
+        assert_eq!(free_slots(), Some(MAX_CONCURRENT_REQUESTS));
+        set_max_concurrent_requests(DEFAULT_MAX_CONCURRENT_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_requests_start_apart() {
+        let first = request_slot().await;
+        let first_started = Instant::now();
+        drop(first);
+        let _second = request_slot().await;
+        // Less a little for the first task being woken late
+        assert!(first_started.elapsed() >= Duration::from_millis(MIN_API_INTERVAL_MS - 50));
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let saved: Timeouts = serde_json::from_str(r#"{"vision_secs": 90}"#).unwrap();
+        assert_eq!(saved, Timeouts { vision_secs: 90, ..Timeouts::default() }, "missing fields keep their defaults");
+        assert_eq!(saved.text_secs, 30);
+        assert!(saved.validate().is_ok());
+        assert!(Timeouts { ocr_secs: 0, ..saved }.validate().is_err());
+        assert!(Timeouts { vision_secs: MAX_TIMEOUT_SECS + 1, ..saved }.validate().is_err());
+    }
+
+    #[test]
+    fn test_fallback_tiers() {
+        assert!(should_fall_back(&ClassifierError::MissingApiKey));
+        assert!(should_fall_back(&ClassifierError::ApiStatus(reqwest::StatusCode::UNAUTHORIZED)));
+        assert!(should_fall_back(&ClassifierError::ApiStatus(reqwest::StatusCode::TOO_MANY_REQUESTS)));
+        assert!(should_fall_back(&ClassifierError::ApiStatus(reqwest::StatusCode::BAD_GATEWAY)));
+        assert!(!should_fall_back(&ClassifierError::ApiStatus(reqwest::StatusCode::BAD_REQUEST)));
+
+        let mini = FallbackTier { provider: Provider::OpenAi, model: Some("gpt-4o-mini".to_string()) };
+        let mock = FallbackTier { provider: Provider::Mock, model: None };
+        assert_eq!(mini.label(), "openai/gpt-4o-mini");
+        assert_eq!(mock.label(), "mock");
+        assert!(!mock.needs_api_key());
+
+        assert!(validate_fallback_chain(&[mini.clone(), mock]).is_ok());
+        assert!(validate_fallback_chain(&vec![mini; MAX_FALLBACK_TIERS + 1]).is_err());
+        assert!(validate_fallback_chain(&[FallbackTier { provider: Provider::Ollama, model: Some(" ".to_string()) }]).is_err());
+    }
+
+    #[test]
+    fn test_unpriced_cloud_models_are_paid() {
+        // Not in the price list, but sent to api.openai.com, so it counts against the budget
+        let unlisted = FallbackTier { provider: Provider::OpenAi, model: Some("o3-pro".to_string()) };
+        assert!(crate::budget::price("o3-pro").is_none());
+        assert!(unlisted.is_paid());
+        assert!(budgeted_tiers(BudgetState::Exceeded).iter().all(|tier| !tier.is_paid()));
+
+        assert!(!FallbackTier { provider: Provider::Ollama, model: None }.is_paid(), "local servers are free");
+        assert!(!FallbackTier { provider: Provider::Mock, model: None }.is_paid());
+    }
+
+    #[test]
+    fn test_model_routes() {
+        let routes = ModelRoutes {
+            filename: Some("gpt-4o-mini".to_string()),
+            content: None,
+            vision: Some(" gemini-2.0-flash ".to_string()),
+        }
+        .normalized();
+        let openai = FallbackTier { provider: Provider::OpenAi, model: None };
+        let route = |tier: &FallbackTier, kind| {
+            let routed = tier.route(endpoint(Provider::OpenAi), &routes, kind);
+            routed.model_for(kind).to_string()
+        };
+        assert_eq!(route(&openai, CallKind::Filename), "gpt-4o-mini");
+        assert_eq!(route(&openai, CallKind::Content), endpoint(Provider::OpenAi).text_model); // not routed
+        assert_eq!(route(&openai, CallKind::Vision), "gemini-2.0-flash");
+
+        // A fallback step's own model still wins for text, but not for images
+        let pinned = FallbackTier { provider: Provider::OpenAi, model: Some("gpt-4.1-nano".to_string()) };
+        assert_eq!(route(&pinned, CallKind::Filename), "gpt-4.1-nano");
+        assert_eq!(route(&pinned, CallKind::Vision), "gemini-2.0-flash");
+
+        assert_eq!(ModelRoutes { content: Some("  ".to_string()), ..ModelRoutes::default() }.normalized(), ModelRoutes::default());
     }
 
     // --- Edge case: GPT returns weird formats ---
@@ -690,12 +2286,78 @@ mod tests {
         let result = parse_response(content).unwrap();
         assert_eq!(result.suggested_folder, "C:\\Users\\student\\Year2\\ML");
     }
+
+    // --- Provider tests ---
+
+    #[test]
+    fn test_provider_from_str() {
+        assert_eq!("ollama".parse::<Provider>(), Ok(Provider::Ollama));
+        assert_eq!("OpenAI".parse::<Provider>(), Ok(Provider::OpenAi));
+        assert!("claude".parse::<Provider>().is_err());
+        assert!(!Provider::Ollama.needs_api_key());
+        assert_eq!(endpoint(Provider::OpenAi).url, "https://api.openai.com/v1/chat/completions");
+    }
+
+    #[test]
+    fn test_endpoint_override() {
+        let mut custom = EndpointOverride {
+            base_url: Some(" http://localhost:4000/v1/ ".to_string()),
+            headers: BTreeMap::from([("X-Team".to_string(), " research ".to_string())]),
+            text_model: Some("  ".to_string()),
+            vision_model: None,
+        }
+        .normalized();
+        assert!(custom.validate().is_ok());
+        assert_eq!(custom.base_url.as_deref(), Some("http://localhost:4000/v1/"));
+        assert_eq!(custom.headers["X-Team"], "research");
+        assert_eq!(custom.text_model, None, "blank model falls back to the default");
+
+        custom.headers.insert("Bad Header".to_string(), "x".to_string());
+        assert!(custom.validate().is_err());
+        assert!(EndpointOverride { base_url: Some("ftp://gateway".to_string()), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_embeddings_url_follows_endpoint() {
+        assert_eq!(embeddings_url("https://api.openai.com/v1/chat/completions"), "https://api.openai.com/v1/embeddings");
+        assert_eq!(embeddings_url("http://localhost:4000/v1/chat/completions"), "http://localhost:4000/v1/embeddings");
+    }
+
+    #[test]
+    fn test_is_local_url() {
+        assert!(is_local_url("http://localhost:11434/v1/chat/completions"));
+        assert!(is_local_url("http://127.0.0.1:11434/v1/models"));
+        assert!(is_local_url("http://[::1]:11434/v1/models"));
+        assert!(is_local_url("http://192.168.1.20:11434/v1/chat/completions"));
+        assert!(is_local_url("http://gpu-box.local:11434/v1/chat/completions"));
+        assert!(is_local_url("http://0.0.0.0:8080/"));
+        assert!(is_local_url("http://[fd12::1]/"));
+        assert!(is_local_url("http://[fe80::1]/"));
+        assert!(is_local_url("http://[::ffff:127.0.0.1]/"));
+        assert!(is_local_url("http://admin.localhost/"));
+
+        assert!(!is_local_url("https://api.openai.com/v1/chat/completions"));
+        assert!(!is_local_url("http://8.8.8.8/v1/models"));
+        assert!(!is_local_url("not a url"));
+    }
 }
 
 /// Extract text from an image using Tesseract OCR
 ///
-/// Returns the extracted text (first ~500 chars), or an error if OCR fails
+/// Returns the extracted text (first ~500 chars), or an error if OCR fails or takes longer
+/// than the OCR timeout (the Tesseract run is then left to finish on its own).
 pub fn extract_image_text(file_path: &str) -> Result<String, String> {
+    let secs = timeouts().ocr_secs;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let path = file_path.to_string();
+    std::thread::spawn(move || {
+        let _ = tx.send(run_tesseract(&path));
+    });
+    rx.recv_timeout(Duration::from_secs(secs))
+        .map_err(|_| ClassifierError::OcrExtract(format!("timed out after {} seconds", secs)).to_string())?
+}
+
+fn run_tesseract(file_path: &str) -> Result<String, String> {
     let img = rusty_tesseract::Image::from_path(file_path)
         .map_err(|e| ClassifierError::OcrLoad(e.to_string()).to_string())?;
 
@@ -719,7 +2381,7 @@ pub fn extract_image_text(file_path: &str) -> Result<String, String> {
     Ok(cleaned)
 }
 
-/// Classify a file using extracted text content + filename (GPT-3.5-turbo)
+/// Classify a file using extracted text content + filename (the content route; GPT-3.5-turbo by default)
 ///
 /// Used as a second pass when filename-only classification has low confidence
 pub async fn classify_with_text_content(
@@ -729,7 +2391,7 @@ pub async fn classify_with_text_content(
     available_folders: Vec<String>,
     correction_history: Vec<String>,
 ) -> Result<Classification, String> {
-    if api_key.is_empty() {
+    if missing_api_key(&api_key) {
         return Err(ClassifierError::MissingApiKey.to_string());
     }
 
@@ -737,8 +2399,131 @@ pub async fn classify_with_text_content(
         &filename,
         &available_folders,
         &correction_history,
-        PromptMode::TextContent(text_content),
+        PromptMode::TextContent(text_content.clone()),
+        privacy_mode(),
+    );
+    let subject = Subject {
+        kind: CallKind::Content,
+        filename: &filename,
+        text: Some(&text_content),
+        folders: &available_folders,
+        corrections: &correction_history,
+    };
+
+    send_text_request(&api_key, prompt, subject, timeouts().text_secs).await.map_err(|e| e.to_string())
+}
+
+/// The embeddings URL on the same server as a chat completions URL
+fn embeddings_url(chat_url: &str) -> String {
+    format!("{}/embeddings", chat_url.trim_end_matches("/chat/completions"))
+}
+
+/// Embed short texts (filenames plus a content snippet) with text-embedding-3-small
+///
+/// Sent to the OpenAI endpoint, or the custom one with its headers if set. Returns one
+/// vector per input, in input order.
+pub async fn embed_texts(api_key: String, texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+    if primary_tier().provider == Provider::Mock {
+        return Ok(crate::mock_classifier::embed(&texts));
+    }
+    if api_key.is_empty() {
+        return Err(ClassifierError::MissingApiKey.to_string());
+    }
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let request = EmbeddingRequest {
+        model: "text-embedding-3-small".to_string(),
+        input: texts,
+    };
+
+    let endpoint = endpoint(Provider::OpenAi);
+    let url = embeddings_url(&endpoint.url);
+    let client = http_client(&url, Duration::from_secs(timeouts().text_secs))?;
+    let _slot = request_slot().await;
+
+    let response = endpoint
+        .with_headers(client.post(&url), &api_key)
+        .header("Content-Type", "application/json")
+        .json(&request)
+        .send()
+        .await
+        .map_err(ClassifierError::HttpClient)?;
+
+    if !response.status().is_success() {
+        return Err(ClassifierError::ApiStatus(response.status()).to_string());
+    }
+    let mut embeddings: EmbeddingResponse = response
+        .json()
+        .await
+        .map_err(ClassifierError::HttpClient)?;
+    if let Some(usage) = &embeddings.usage {
+        crate::budget::record(&request.model, !is_local_url(&url), usage.prompt_tokens, 0, None);
+    }
+    embeddings.data.sort_by_key(|d| d.index);
+
+    Ok(embeddings.data.into_iter().map(|d| d.embedding).collect())
+}
+
+/// Ask GPT-3.5 for a short folder name covering a group of related files
+///
+/// Used to name clusters of unsorted files; the existing folders are listed so it
+/// doesn't propose one that's already there.
+pub async fn suggest_topic_name(
+    api_key: String,
+    filenames: Vec<String>,
+    existing_folders: Vec<String>,
+) -> Result<String, String> {
+    if missing_api_key(&api_key) {
+        return Err(ClassifierError::MissingApiKey.to_string());
+    }
+    let tier = primary_tier();
+    if tier.provider == Provider::Mock {
+        return Ok(crate::mock_classifier::topic_name(&filenames, &existing_folders));
+    }
+
+    let prompt = format!(
+        r#"These files don't fit any of the user's existing folders, but seem to share a topic:
+{files}
+
+Existing folders (do not reuse these):
+{folders}
+
+Suggest ONE short folder name (1-4 words, Title Case, e.g. "Linear Algebra" or "Internship Applications") that would hold all of these files.
+
+Respond ONLY with valid JSON in this exact format:
+{{"folder": "Folder Name"}}"#,
+        files = filenames.join("\n"),
+        folders = existing_folders.join("\n"),
     );
 
-    send_text_request(&api_key, prompt, API_TIMEOUT_SECS).await.map_err(|e| e.to_string())
+    let endpoint = tier.endpoint();
+    let response = post_text_request(&endpoint, &endpoint.text_model, &api_key, prompt, timeouts().text_secs).await?;
+    let content = response_content(response).await?;
+    let json_str = extract_json(&content);
+    let parsed: TopicNameResponse = serde_json::from_str(json_str).map_err(|e| ClassifierError::ParseJson {
+        source: e,
+        content: json_str.to_string(),
+    })?;
+    Ok(parsed.folder)
+}
+
+/// Check an API key against the provider without spending tokens (lists the models)
+///
+/// Returns the HTTP status the provider answered with; network failures are errors.
+pub async fn verify_api_key(api_key: &str) -> Result<reqwest::StatusCode, String> {
+    let tier = primary_tier();
+    if tier.provider == Provider::Mock {
+        return Ok(reqwest::StatusCode::OK);
+    }
+    let endpoint = tier.endpoint();
+    let models_url = endpoint.url.replace("/chat/completions", "/models");
+    let client = http_client(&models_url, Duration::from_secs(10))?;
+    let response = endpoint
+        .with_headers(client.get(&models_url), api_key)
+        .send()
+        .await
+        .map_err(|e| ClassifierError::HttpClient(e).to_string())?;
+    Ok(response.status())
 }
@@ -0,0 +1,178 @@
+//! Command-line mode
+//!
+//! `file-organiser organize <folder>` runs the same pipeline as the app (rules, correction
+//! cache, classification, conflict policy, activity log) without opening a window, so it
+//! can run over SSH or from cron. It shares the app's database, so rules, corrections and
+//! the API key set up in the app apply here too. Any other arguments start the app as usual.
+//! (Windows release builds have no console, so output there only shows when redirected.)
+
+use crate::classifier::Provider;
+use crate::planner::OrganizationPlan;
+
+// Must match "identifier" in tauri.conf.json, which names the app's data folder
+const APP_IDENTIFIER: &str = "com.rongq.file-organiser";
+
+pub const USAGE: &str = "Usage: file-organiser organize <folder> [options]
+
+Sorts the files in <folder> into your destination folders, then exits.
+
+Options:
+  --dry-run            Show where each file would go without moving anything
+  --provider <name>    openai (default), ollama (uses OLLAMA_HOST and OLLAMA_MODEL) or mock (offline)
+  --into <folder>      A destination folder; repeat for each one. Defaults to the
+                       folders set up in the app
+  --min-confidence <n> Leave files the AI is less sure about than this (0-1) in place
+  --portable           Use the database in the data folder next to the program
+  -h, --help           Show this message
+
+The OpenAI key is read from OPENAI_API_KEY, or the one saved in the app.";
+
+/// What the command line asked for
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Organize(OrganizeArgs),
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OrganizeArgs {
+    pub folder: String,
+    pub dry_run: bool,
+    pub provider: Option<Provider>,
+    pub destinations: Vec<String>,
+    pub min_confidence: Option<f32>,
+}
+
+/// Parse the arguments after the program name
+///
+/// Returns None when they aren't a CLI command, so the app should start normally
+/// (launchers and the OS can pass arguments of their own).
+pub fn parse_args(args: &[String]) -> Option<Result<CliCommand, String>> {
+    let (command, rest) = args.split_first()?;
+    match command.as_str() {
+        "organize" | "organise" => Some(parse_organize(rest)),
+        "help" | "-h" | "--help" => Some(Ok(CliCommand::Help)),
+        _ => None,
+    }
+}
+
+fn parse_organize(args: &[String]) -> Result<CliCommand, String> {
+    let mut parsed = OrganizeArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "--dry-run" | "-n" => parsed.dry_run = true,
+            "--provider" => parsed.provider = Some(value("--provider")?.parse()?),
+            "--into" => parsed.destinations.push(value("--into")?),
+            "--min-confidence" => {
+                let raw = value("--min-confidence")?;
+                let confidence: f32 = raw.parse().map_err(|_| format!("Invalid confidence: {}", raw))?;
+                if !(0.0..=1.0).contains(&confidence) {
+                    return Err("--min-confidence must be between 0 and 1".to_string());
+                }
+                parsed.min_confidence = Some(confidence);
+            }
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            crate::data_dir::PORTABLE_FLAG => {} // read by data_dir when finding the database
+            flag if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag)),
+            folder if parsed.folder.is_empty() => parsed.folder = folder.to_string(),
+            extra => return Err(format!("Unexpected argument: {}", extra)),
+        }
+    }
+    if parsed.folder.is_empty() {
+        return Err("Which folder should be organized?".to_string());
+    }
+    Ok(CliCommand::Organize(parsed))
+}
+
+/// Where the app's database is: where Tauri puts it for the GUI, unless portable mode or a
+/// relocation moved it (see data_dir.rs)
+pub fn data_location() -> Option<crate::data_dir::DataLocation> {
+    Some(crate::data_dir::resolve(dirs::data_dir()?.join(APP_IDENTIFIER)))
+}
+
+/// A dry run's proposed moves and skipped files, one per line
+pub fn plan_report(plan: &OrganizationPlan) -> String {
+    let mut lines: Vec<String> = plan
+        .moves
+        .iter()
+        .map(|m| {
+            let name = m.suggested_filename.as_deref().unwrap_or(&m.filename);
+            let renamed = if name != m.filename { format!(" (as {})", name) } else { String::new() };
+            format!("would move {} → {}{} [{}, {:.0}%]", m.filename, m.dest_folder, renamed, m.origin, m.confidence * 100.0)
+        })
+        .collect();
+    lines.extend(plan.skipped.iter().map(|s| format!("would skip {}: {}", s.filename, s.reason)));
+    lines.push(format!("{} to move, {} to leave in place", plan.moves.len(), plan.skipped.len()));
+    lines.join("\n")
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::{PlanSkip, PlannedMove};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&args("")), None);
+        assert_eq!(parse_args(&args("-psn_0_12345")), None);
+        assert_eq!(parse_args(&args("--help")), Some(Ok(CliCommand::Help)));
+        assert_eq!(parse_args(&args("organize -h")), Some(Ok(CliCommand::Help)));
+
+        let parsed = parse_args(&args("organize /home/sam/Downloads --dry-run --provider ollama --into /Uni/ML --into /Uni/Econ"));
+        assert_eq!(
+            parsed,
+            Some(Ok(CliCommand::Organize(OrganizeArgs {
+                folder: "/home/sam/Downloads".to_string(),
+                dry_run: true,
+                provider: Some(Provider::Ollama),
+                destinations: vec!["/Uni/ML".to_string(), "/Uni/Econ".to_string()],
+                min_confidence: None,
+            })))
+        );
+
+        assert!(matches!(parse_args(&args("organize")), Some(Err(_))));
+        assert!(matches!(parse_args(&args("organize /x --provider")), Some(Err(_))));
+        assert!(matches!(parse_args(&args("organize /x --provider gemini")), Some(Err(_))));
+        assert!(matches!(parse_args(&args("organize /x --min-confidence 80")), Some(Err(_))));
+        assert!(matches!(parse_args(&args("organize /x /y")), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_plan_report() {
+        let plan = OrganizationPlan {
+            id: "plan-1".to_string(),
+            folder: "/Downloads".to_string(),
+            created_at: 0,
+            moves: vec![PlannedMove {
+                source_path: "/Downloads/scan01.pdf".to_string(),
+                filename: "scan01.pdf".to_string(),
+                dest_folder: "/Uni/Econ".to_string(),
+                confidence: 0.92,
+                reasoning: "Problem set".to_string(),
+                suggested_filename: Some("Econ_PS2.pdf".to_string()),
+                origin: "ai".to_string(),
+            }],
+            skipped: vec![PlanSkip {
+                source_path: "/Downloads/cat.jpg".to_string(),
+                filename: "cat.jpg".to_string(),
+                reason: "No matching folder".to_string(),
+            }],
+            trash: Vec::new(),
+        };
+        assert_eq!(
+            plan_report(&plan),
+            "would move scan01.pdf → /Uni/Econ (as Econ_PS2.pdf) [ai, 92%]\n\
+             would skip cat.jpg: No matching folder\n\
+             1 to move, 1 to leave in place"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+//! Where the database and app trash live
+//!
+//! Normally that's the OS app data folder. In portable mode, turned on by a `portable` file
+//! next to the executable or by starting the app with `--portable`, it's a `data` folder
+//! beside the executable instead, so the app can run from a USB stick and leave nothing
+//! on the computer.
+//!
+//! Either location can be redirected to a folder of the user's choosing with
+//! `relocate_database`. The chosen folder is recorded in `data_location.json` in the
+//! original location and is used from the next start; the old database is removed once
+//! the app has started with the new one. `use_existing_database` points the app at a
+//! database already in a folder instead, e.g. one another computer keeps in a synced folder.
+//!
+//! A database inside a folder a sync client uploads (Dropbox, OneDrive, Google Drive,
+//! iCloud...) is opened in sync-safe mode (see `db::StorageMode`). SQLite's own locks don't
+//! travel between computers, so a `.lock` file beside the database records which computer
+//! has it open, and a second computer refuses to open it until the first has quit.
+
+use crate::db::{Database, StorageMode};
+use crate::CommandError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const DB_FILE_NAME: &str = "file_organiser.db";
+pub const PORTABLE_MARKER: &str = "portable";
+pub const PORTABLE_FLAG: &str = "--portable";
+pub const PORTABLE_DATA_DIR: &str = "data";
+const LOCATION_FILE: &str = "data_location.json";
+const LOCK_SUFFIX: &str = ".lock";
+
+// Folders sync clients create, matched against each part of a path (case-insensitively,
+// allowing suffixes like "OneDrive - University" or "Dropbox (Personal)")
+const SYNCED_FOLDERS: [(&str, &str); 10] = [
+    ("dropbox", "Dropbox"),
+    ("onedrive", "OneDrive"),
+    ("google drive", "Google Drive"),
+    ("my drive", "Google Drive"),
+    ("icloud drive", "iCloud Drive"),
+    ("mobile documents", "iCloud Drive"), // ~/Library/Mobile Documents on macOS
+    ("pcloud drive", "pCloud"),
+    ("nextcloud", "Nextcloud"),
+    ("owncloud", "ownCloud"),
+    ("box", "Box"),
+];
+
+// SQLite keeps recent writes and locks beside the database in these
+const SIDE_FILE_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+static CURRENT: OnceLock<DataLocation> = OnceLock::new();
+
+/// What `data_location.json` holds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct LocationFile {
+    data_dir: PathBuf,
+    #[serde(default)]
+    moved_from: Option<PathBuf>, // database to remove once the new one has opened
+}
+
+/// Where the app keeps its data, and why
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataLocation {
+    pub data_dir: PathBuf,
+    pub database: PathBuf,
+    pub base_dir: PathBuf, // app data folder, or the portable folder; holds data_location.json
+    pub portable: bool,
+    pub relocated: bool, // data_dir was chosen by the user
+    pub synced: Option<String>, // sync client whose folder holds data_dir
+}
+
+/// Who has a synced database open, as written to its `.lock` file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LockFile {
+    host: String,
+    pid: u32,
+    since: i64, // Unix timestamp ms
+}
+
+/// The portable data folder, if portable mode is on
+pub fn portable_dir() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let flagged = std::env::args().skip(1).any(|arg| arg == PORTABLE_FLAG);
+    (flagged || exe_dir.join(PORTABLE_MARKER).is_file()).then(|| exe_dir.join(PORTABLE_DATA_DIR))
+}
+
+/// Work out where data lives, given the OS app data folder
+pub fn resolve(app_data_dir: PathBuf) -> DataLocation {
+    match portable_dir() {
+        Some(dir) => resolve_in(dir, true),
+        None => resolve_in(app_data_dir, false),
+    }
+}
+
+fn resolve_in(base_dir: PathBuf, portable: bool) -> DataLocation {
+    let chosen = read_location_file(&base_dir).map(|location| location.data_dir);
+    let data_dir = chosen.clone().unwrap_or_else(|| base_dir.clone());
+    DataLocation {
+        database: data_dir.join(DB_FILE_NAME),
+        synced: sync_provider(&data_dir).map(str::to_string),
+        data_dir,
+        base_dir,
+        portable,
+        relocated: chosen.is_some(),
+    }
+}
+
+fn read_location_file(base_dir: &Path) -> Option<LocationFile> {
+    let text = fs::read_to_string(base_dir.join(LOCATION_FILE)).ok()?;
+    match serde_json::from_str::<LocationFile>(&text) {
+        Ok(location) if location.data_dir.is_absolute() => Some(location),
+        _ => {
+            eprintln!("[DATA] Ignoring unreadable {}", base_dir.join(LOCATION_FILE).display());
+            None
+        }
+    }
+}
+
+/// The sync client whose folder `path` is in, if any
+pub fn sync_provider(path: &Path) -> Option<&'static str> {
+    path.components().find_map(|component| {
+        let name = component.as_os_str().to_string_lossy().to_lowercase();
+        SYNCED_FOLDERS.iter().find_map(|(folder, provider)| {
+            let matches = name == *folder
+                || name.strip_prefix(folder).is_some_and(|rest| rest.starts_with(" -") || rest.starts_with(" ("));
+            matches.then_some(*provider)
+        })
+    })
+}
+
+/// How the database in `location` should be opened
+pub fn storage_mode(location: &DataLocation) -> StorageMode {
+    match location.synced {
+        Some(_) => StorageMode::Synced,
+        None => StorageMode::Local,
+    }
+}
+
+/// Record where data should live from the next start (removing the record when that's
+/// the base folder itself)
+fn write_location_file(base_dir: &Path, location: &LocationFile) -> Result<(), CommandError> {
+    let path = base_dir.join(LOCATION_FILE);
+    if location.data_dir == base_dir && location.moved_from.is_none() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(base_dir)?;
+    let json = serde_json::to_string_pretty(location).map_err(|e| CommandError::IoError(e.to_string()))?;
+    // Written aside and renamed, so a crash can't leave half a file pointing nowhere
+    let tmp = base_dir.join(format!("{}.tmp", LOCATION_FILE));
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+fn lock_path(database: &Path) -> PathBuf {
+    let mut name = database.as_os_str().to_owned();
+    name.push(LOCK_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// This computer's name, as shown to the other computer sharing a synced database
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .ok()
+        .or_else(|| {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "another computer".to_string())
+}
+
+fn read_lock(database: &Path) -> Option<LockFile> {
+    serde_json::from_str(&fs::read_to_string(lock_path(database)).ok()?).ok()
+}
+
+/// Refuse a synced database another computer has open, otherwise mark it as open here
+///
+/// A lock left by this computer (say after a crash, or by the command line while the app
+/// runs) doesn't count: SQLite's own locks work between programs on one computer.
+pub fn acquire_lock(location: &DataLocation) -> Result<(), String> {
+    if location.synced.is_none() {
+        return Ok(());
+    }
+    let host = host_name();
+    if let Some(lock) = read_lock(&location.database).filter(|lock| lock.host != host) {
+        let since = chrono::DateTime::from_timestamp_millis(lock.since)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        return Err(format!(
+            "The database in {} is open on {} (since {}). Quit the app there and let {} finish syncing; \
+             if that computer crashed, delete {}",
+            location.data_dir.display(),
+            lock.host,
+            since,
+            location.synced.as_deref().unwrap_or_default(),
+            lock_path(&location.database).display()
+        ));
+    }
+    let lock = LockFile { host, pid: std::process::id(), since: chrono::Utc::now().timestamp_millis() };
+    let json = serde_json::to_string(&lock).map_err(|e| e.to_string())?;
+    fs::write(lock_path(&location.database), json).map_err(|e| format!("Could not lock the database: {}", e))
+}
+
+/// Remove this process's lock once the database is flushed (at quit)
+pub fn release_lock(location: &DataLocation) {
+    if read_lock(&location.database).is_some_and(|lock| lock.pid == std::process::id() && lock.host == host_name()) {
+        let _ = fs::remove_file(lock_path(&location.database));
+    }
+}
+
+/// Copies a sync client made when two computers changed the database at once
+/// (e.g. "file_organiser (Sam's conflicted copy).db", "file_organiser-LAPTOP.db")
+pub fn conflicted_copies(location: &DataLocation) -> Vec<PathBuf> {
+    let stem = DB_FILE_NAME.trim_end_matches(".db");
+    let Ok(entries) = fs::read_dir(&location.data_dir) else {
+        return Vec::new();
+    };
+    let mut copies: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            name != DB_FILE_NAME && name.starts_with(stem) && name.ends_with(".db")
+        })
+        .collect();
+    copies.sort();
+    copies
+}
+
+/// Remember the location the app started with (called once at startup)
+pub fn init(location: DataLocation) {
+    let _ = CURRENT.set(location);
+}
+
+/// The location the app started with
+pub fn current() -> Result<&'static DataLocation, CommandError> {
+    CURRENT
+        .get()
+        .ok_or_else(|| CommandError::IoError("App data folder is not available".to_string()))
+}
+
+/// After a relocation, remove the old database now that the new one has opened
+pub fn finish_relocation(location: &DataLocation) {
+    let Some(recorded) = read_location_file(&location.base_dir) else {
+        return;
+    };
+    let Some(old) = recorded.moved_from.as_deref().filter(|old| *old != location.database) else {
+        return;
+    };
+    for path in database_files(old) {
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("[DATA] Could not remove old database file {}: {}", path.display(), e);
+            }
+        }
+    }
+    println!("[DATA] Removed the old database at {}", old.display());
+    let _ = write_location_file(&location.base_dir, &LocationFile { moved_from: None, ..recorded });
+}
+
+/// A database file and the files SQLite keeps beside it
+fn database_files(database: &Path) -> Vec<PathBuf> {
+    let mut files = vec![database.to_path_buf()];
+    files.extend(SIDE_FILE_SUFFIXES.iter().map(|suffix| {
+        let mut name = database.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    }));
+    files
+}
+
+/// Check that the database can be moved into `new_dir`; an existing database there is
+/// never overwritten
+pub fn check_target(from: &DataLocation, new_dir: &Path) -> Result<(), CommandError> {
+    if !new_dir.is_absolute() {
+        return Err(CommandError::InvalidPath("Choose a full folder path for the database".to_string()));
+    }
+    if new_dir == from.data_dir {
+        return Err(CommandError::InvalidPath("The database is already in that folder".to_string()));
+    }
+    let target = new_dir.join(DB_FILE_NAME);
+    if target.exists() {
+        return Err(CommandError::InvalidPath(format!(
+            "{} already has a database; move or delete it first",
+            new_dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Copy the open database into `new_dir` and make that folder the data location from
+/// the next start, returning the new location
+///
+/// The copy is a consistent snapshot (`VACUUM INTO`) checked with SQLite's integrity check
+/// before anything is recorded, so a failed move leaves the app on its current database.
+pub fn relocate(db: &Database, from: &DataLocation, new_dir: &Path) -> Result<DataLocation, CommandError> {
+    check_target(from, new_dir)?;
+    fs::create_dir_all(new_dir)?;
+
+    let target = new_dir.join(DB_FILE_NAME);
+    db.copy_to(&target)?;
+    let copy_ok = Database::open(target.clone(), storage_mode(&resolve_at(new_dir, from)))
+        .and_then(|copy| copy.check_integrity())
+        .map(|messages| messages == ["ok"])
+        .unwrap_or(false);
+    if !copy_ok {
+        for path in database_files(&target) {
+            let _ = fs::remove_file(path);
+        }
+        return Err(CommandError::IoError(format!(
+            "The copy in {} could not be verified; the database was not moved",
+            new_dir.display()
+        )));
+    }
+
+    write_location_file(
+        &from.base_dir,
+        &LocationFile {
+            data_dir: new_dir.to_path_buf(),
+            moved_from: Some(from.database.clone()),
+        },
+    )?;
+    Ok(resolve_in(from.base_dir.clone(), from.portable))
+}
+
+/// What `from` would look like with its data in `dir`
+fn resolve_at(dir: &Path, from: &DataLocation) -> DataLocation {
+    DataLocation {
+        data_dir: dir.to_path_buf(),
+        database: dir.join(DB_FILE_NAME),
+        synced: sync_provider(dir).map(str::to_string),
+        relocated: true,
+        ..from.clone()
+    }
+}
+
+/// Use the database already in `dir` from the next start, leaving the current one as it is
+///
+/// For pointing a second computer at a database kept in a synced folder. The database must
+/// pass SQLite's integrity check and, if synced, not be open on another computer.
+pub fn use_existing(from: &DataLocation, dir: &Path) -> Result<DataLocation, CommandError> {
+    if !dir.is_absolute() {
+        return Err(CommandError::InvalidPath("Choose a full folder path for the database".to_string()));
+    }
+    if dir == from.data_dir {
+        return Err(CommandError::InvalidPath("The app already uses the database in that folder".to_string()));
+    }
+    let target = resolve_at(dir, from);
+    if !target.database.is_file() {
+        return Err(CommandError::FileNotFound(target.database.display().to_string()));
+    }
+    if Database::check_file_integrity(&target.database)? != ["ok"] {
+        return Err(CommandError::IoError(format!(
+            "The database in {} failed its integrity check",
+            dir.display()
+        )));
+    }
+    if let Some(lock) = read_lock(&target.database).filter(|lock| lock.host != host_name()) {
+        return Err(CommandError::InvalidPath(format!(
+            "The database in {} is open on {}; quit the app there first",
+            dir.display(),
+            lock.host
+        )));
+    }
+
+    write_location_file(&from.base_dir, &LocationFile { data_dir: dir.to_path_buf(), moved_from: None })?;
+    Ok(resolve_in(from.base_dir.clone(), from.portable))
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relocate_database() {
+        let tmp = std::env::temp_dir().join("fileorg_test_data_dir_relocate");
+        let _ = fs::remove_dir_all(&tmp);
+        let base = tmp.join("appdata");
+        let usb = tmp.join("usb");
+
+        let location = resolve_in(base.clone(), false);
+        assert_eq!(location.database, base.join(DB_FILE_NAME));
+        assert!(!location.relocated);
+        let db = Database::new(location.database.clone()).unwrap();
+        db.set_setting("privacy_mode", "true").unwrap();
+
+        let moved = relocate(&db, &location, &usb).unwrap();
+        assert_eq!(moved.database, usb.join(DB_FILE_NAME));
+        assert!(moved.relocated);
+        assert_eq!(resolve_in(base.clone(), false), moved);
+        assert!(relocate(&db, &moved, &usb).is_err()); // already there
+        drop(db);
+
+        // The next start opens the copy and clears away the old database
+        let copy = Database::new(moved.database.clone()).unwrap();
+        assert_eq!(copy.get_setting("privacy_mode").unwrap().as_deref(), Some("true"));
+        finish_relocation(&moved);
+        assert!(!location.database.exists());
+        assert_eq!(read_location_file(&base).unwrap().moved_from, None);
+
+        // Moving back to the base folder removes the record, but never overwrites a database
+        fs::write(base.join(DB_FILE_NAME), "someone else's").unwrap();
+        assert!(relocate(&copy, &moved, &base).is_err());
+        fs::remove_file(base.join(DB_FILE_NAME)).unwrap();
+        let back = relocate(&copy, &moved, &base).unwrap();
+        assert_eq!(back.database, base.join(DB_FILE_NAME));
+        drop(copy);
+        finish_relocation(&back);
+        assert!(!moved.database.exists());
+        assert!(!base.join(LOCATION_FILE).exists());
+        assert!(!resolve_in(base.clone(), false).relocated);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_sync_provider() {
+        assert_eq!(sync_provider(Path::new("/Users/sam/Dropbox/Apps/organiser")), Some("Dropbox"));
+        assert_eq!(sync_provider(Path::new("/mnt/c/Users/sam/OneDrive - University/data")), Some("OneDrive"));
+        assert_eq!(sync_provider(Path::new("/home/sam/Dropbox (Personal)")), Some("Dropbox"));
+        assert_eq!(sync_provider(Path::new("/Users/sam/Library/Mobile Documents/com~apple~CloudDocs")), Some("iCloud Drive"));
+        assert_eq!(sync_provider(Path::new("/home/sam/Documents/Boxes")), None);
+        assert_eq!(sync_provider(Path::new("/home/sam/.local/share/com.rongq.file-organiser")), None);
+    }
+
+    #[test]
+    fn test_synced_database_lock_and_existing_database() {
+        let tmp = std::env::temp_dir().join("fileorg_test_data_dir_synced");
+        let _ = fs::remove_dir_all(&tmp);
+        let base = tmp.join("appdata");
+        let synced = tmp.join("Dropbox").join("organiser");
+        fs::create_dir_all(&synced).unwrap();
+
+        // A database another computer left in the synced folder
+        let shared = Database::open(synced.join(DB_FILE_NAME), StorageMode::Synced).unwrap();
+        shared.set_setting("privacy_mode", "true").unwrap();
+        drop(shared);
+        fs::write(synced.join("file_organiser (Sam's conflicted copy).db"), "").unwrap();
+        let other = LockFile { host: "SAMS-LAPTOP".to_string(), pid: 1, since: 0 };
+        fs::write(lock_path(&synced.join(DB_FILE_NAME)), serde_json::to_string(&other).unwrap()).unwrap();
+
+        let location = resolve_in(base.clone(), false);
+        assert!(use_existing(&location, &synced).is_err()); // open on the laptop
+        fs::remove_file(lock_path(&synced.join(DB_FILE_NAME))).unwrap();
+        let moved = use_existing(&location, &synced).unwrap();
+        assert_eq!(moved.synced.as_deref(), Some("Dropbox"));
+        assert_eq!(storage_mode(&moved), StorageMode::Synced);
+        assert_eq!(conflicted_copies(&moved), vec![synced.join("file_organiser (Sam's conflicted copy).db")]);
+
+        acquire_lock(&moved).unwrap();
+        assert!(lock_path(&moved.database).exists());
+        acquire_lock(&moved).unwrap(); // this computer's own lock doesn't block it
+        release_lock(&moved);
+        assert!(!lock_path(&moved.database).exists());
+
+        fs::write(lock_path(&moved.database), serde_json::to_string(&other).unwrap()).unwrap();
+        assert!(acquire_lock(&moved).unwrap_err().contains("SAMS-LAPTOP"));
+        // Not synced: no lock file is needed
+        assert!(acquire_lock(&location).is_ok());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,12 +1,23 @@
 //! Database module for SQLite persistence
 //!
 //! Handles corrections and activity log storage with automatic schema creation
-//! and data limits enforcement.
-
+//! and data limits enforcement. Commands reach the database through `DbWorker`,
+//! a dedicated thread, so queries never run on UI-facing command threads.
+
+use crate::audit::{self, AuditEntry};
+use crate::budget::{ApiUsage, ModelReliability};
+use crate::file_index::{IndexSummary, IndexedFile};
+use crate::folder_match::FolderAlias;
+use crate::scan::FileEntry;
+use crate::setup_profile::ProfileRule;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 // ============================================================
@@ -45,6 +56,22 @@ pub struct Rule {
     pub pattern: String,
     pub target_folder: String,
     pub created_at: i64,
+    #[serde(default)]
+    pub profile_id: Option<i64>, // None: applies to every watched folder
+}
+
+/// A watched folder with its own destination folders, rules, mode and threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchProfile {
+    pub id: Option<i64>,
+    pub name: String,
+    pub watch_path: String,
+    pub folders: Vec<String>, // destination folders (full paths)
+    pub mode: String,         // "ai", "ocr" or "buckets"
+    pub min_confidence: f64,  // auto-move threshold
+    pub created_at: i64,      // Unix timestamp ms
+    #[serde(default)]
+    pub excluded: Vec<String>, // subfolders the watcher and scans skip (see `profiles::is_excluded`)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +81,8 @@ pub struct Correction {
     pub ai_suggested: String,
     pub user_chose: String,
     pub correction_type: String,
+    #[serde(default)]
+    pub confidence: Option<f32>, // the AI's own confidence in its suggestion, if it was recorded
     pub created_at: i64, // Unix timestamp ms
 }
 
@@ -62,12 +91,216 @@ pub struct ActivityEntry {
     pub id: Option<i64>,
     pub filename: String,
     pub original_filename: Option<String>,
+    /// Full path the file was moved from, kept so the move can be redone after an undo
+    pub source_path: Option<String>,
+    /// Organize session (batch) this move belongs to, if any
+    pub session_id: Option<i64>,
+    /// blake3 hash of the file at its destination, checked again before undoing the move
+    pub content_hash: Option<String>,
+    /// Size in bytes at the destination, a quick first check before the hash
+    #[serde(default)]
+    pub content_size: Option<u64>,
+    /// How sure the classifier was of the destination, when it picked it
+    #[serde(default)]
+    pub confidence: Option<f32>,
     pub from_folder: String,
     pub to_folder: String,
     pub undone: bool,
     pub created_at: i64, // Unix timestamp ms
 }
 
+/// A file the app moved out of a watched folder, for time-saved stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandledFile {
+    pub detected_at: Option<i64>, // when it was first seen in the folder, if it was
+    pub moved_at: i64,            // Unix timestamp ms
+}
+
+/// A group of moves performed together (e.g. one batch organize run)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: i64,
+    pub label: String,
+    pub created_at: i64, // Unix timestamp ms
+    pub ended_at: Option<i64>,
+    pub undone: bool,
+    pub file_count: i64,
+    pub pending_items: i64, // planned moves not carried out yet (interrupted run)
+}
+
+pub const ITEM_PENDING: &str = "pending";
+pub const ITEM_DONE: &str = "done";
+pub const ITEM_SKIPPED: &str = "skipped"; // the destination already had the file
+pub const ITEM_FAILED: &str = "failed";
+pub const ITEM_DEFERRED: &str = "deferred"; // waiting for its destination drive to be plugged back in
+
+/// One planned move of a session and how far it got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionItem {
+    pub id: i64,
+    pub session_id: i64,
+    pub source_path: String,
+    pub planned: serde_json::Value, // the PlannedMove
+    pub policy: String,             // conflict policy the session runs with
+    pub status: String,             // ITEM_PENDING, ITEM_DONE, ITEM_SKIPPED, ITEM_FAILED or ITEM_DEFERRED
+    pub error: Option<String>,
+    pub updated_at: i64, // Unix timestamp ms
+}
+
+/// Per-destination-folder classification and move preferences
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderPref {
+    pub folder: String, // full path of the destination folder
+    pub never_suggest: bool,
+    pub require_confirm: bool,
+    pub naming_template: Option<String>, // e.g. "{date}_{name}"
+    #[serde(default)]
+    pub description: Option<String>, // e.g. the full course name, shown to the classifier
+    pub updated_at: i64, // Unix timestamp ms
+}
+
+/// A file parked in the quarantine ("Needs Review") folder, and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineItem {
+    pub id: Option<i64>,
+    pub path: String, // current location inside the quarantine folder
+    pub filename: String,
+    pub original_folder: String,
+    pub reason: String, // "unsorted" or "low_confidence"
+    pub confidence: f64,
+    pub suggested_folder: Option<String>,
+    pub created_at: i64, // Unix timestamp ms
+}
+
+/// A file held in the app-managed trash until its retention period runs out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedFile {
+    pub id: Option<i64>,
+    pub original_path: String,
+    pub trash_path: String,
+    pub size: i64,
+    pub deleted_at: i64, // Unix timestamp ms
+    pub expires_at: i64, // Unix timestamp ms; purged after this
+}
+
+/// Where an organized file came from and why it was put where it is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub path: String,          // where the file is now
+    pub original_path: String, // where it was before it was first organized
+    pub organized_at: i64,     // Unix timestamp ms of the latest move
+    pub reasoning: Option<String>,
+}
+
+/// A detected file waiting for review, kept so it survives a restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub detected_at: i64, // Unix timestamp ms
+    pub classification: Option<serde_json::Value>, // the AI's answer, once it has one
+}
+
+/// A recorded file operation in the undo/redo journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: Option<i64>,
+    pub op_type: String, // "move", "rename", "replace", "copy", "trash"
+    pub before_path: String,
+    pub after_path: Option<String>, // None for trash
+    pub status: String, // "done", "undone", "discarded"
+    pub created_at: i64, // Unix timestamp ms
+    pub updated_at: i64, // Unix timestamp ms of the last undo/redo
+    #[serde(default)]
+    pub content_hash: Option<String>, // blake3 of a copy, so undo only deletes an unchanged copy
+}
+
+/// Map a corrections row (selected in column order) to a Correction
+fn correction_from_row(row: &rusqlite::Row) -> rusqlite::Result<Correction> {
+    Ok(Correction {
+        id: Some(row.get(0)?),
+        filename: row.get(1)?,
+        ai_suggested: row.get(2)?,
+        user_chose: row.get(3)?,
+        correction_type: row.get(4)?,
+        confidence: row.get::<_, Option<f64>>(6)?.map(|c| c as f32),
+        created_at: row.get(5)?,
+    })
+}
+
+/// Map an activity_log row (selected in column order) to an ActivityEntry
+fn activity_from_row(row: &rusqlite::Row) -> rusqlite::Result<ActivityEntry> {
+    Ok(ActivityEntry {
+        id: Some(row.get(0)?),
+        filename: row.get(1)?,
+        original_filename: row.get(2)?,
+        source_path: row.get(3)?,
+        session_id: row.get(4)?,
+        from_folder: row.get(5)?,
+        to_folder: row.get(6)?,
+        undone: row.get::<_, i32>(7)? != 0,
+        created_at: row.get(8)?,
+        content_hash: row.get(9)?,
+        content_size: row.get::<_, Option<i64>>(10)?.map(|size| size as u64),
+        confidence: row.get::<_, Option<f64>>(11)?.map(|confidence| confidence as f32),
+    })
+}
+
+/// Map a session_items row (selected in column order) to a SessionItem
+fn session_item_from_row(row: &rusqlite::Row) -> rusqlite::Result<SessionItem> {
+    let planned: String = row.get(3)?;
+    Ok(SessionItem {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        source_path: row.get(2)?,
+        planned: serde_json::from_str(&planned).unwrap_or(serde_json::Value::Null),
+        policy: row.get(4)?,
+        status: row.get(5)?,
+        error: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// Map an operations row (selected in column order) to an Operation
+fn operation_from_row(row: &rusqlite::Row) -> rusqlite::Result<Operation> {
+    Ok(Operation {
+        id: Some(row.get(0)?),
+        op_type: row.get(1)?,
+        before_path: row.get(2)?,
+        after_path: row.get(3)?,
+        status: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        content_hash: row.get(7)?,
+    })
+}
+
+/// Map a file_index row (selected in column order) to an IndexedFile
+fn indexed_file_from_row(row: &rusqlite::Row) -> rusqlite::Result<IndexedFile> {
+    Ok(IndexedFile {
+        path: row.get(0)?,
+        folder: row.get(1)?,
+        size: row.get::<_, i64>(2)? as u64,
+        modified: row.get::<_, i64>(3)? as u64,
+        hash: row.get(4)?,
+        status: row.get(5)?,
+        dest_folder: row.get(6)?,
+        suggested_filename: row.get(7)?,
+        confidence: row.get::<_, Option<f64>>(8)?.map(|c| c as f32),
+        reasoning: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+/// Current Unix timestamp in milliseconds
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
 // ============================================================
 // DATABASE MANAGER
 // ============================================================
@@ -80,10 +313,187 @@ pub struct Database {
 // Limits matching frontend constants
 const MAX_CORRECTIONS: usize = 50;
 const MAX_ACTIVITY_LOG: usize = 100;
+// Moves past MAX_ACTIVITY_LOG are archived rather than deleted, so a session can still be
+// undone as a whole; only the oldest beyond this many are deleted
+const MAX_ACTIVITY_HISTORY: usize = 20_000;
+const MAX_OPERATIONS: usize = 200;
+const MAX_PROMPTS: usize = 500;
+// Corrections past MAX_CORRECTIONS are archived: left out of prompts but kept for the
+// fine-tuning export, up to this many
+const MAX_CORRECTION_HISTORY: usize = 20_000;
+
+// Detections of files that never got organized are dropped after this long
+const MAX_DETECTION_AGE_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+// Handled files are kept long enough for yearly totals
+const MAX_HANDLED_FILE_AGE_MS: i64 = 400 * 24 * 60 * 60 * 1000;
+
+// How long a query waits on a lock held by another connection before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+// How often the worker folds the WAL file back into the main database
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How the database file is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageMode {
+    Local,
+    // Inside a folder a sync client uploads (Dropbox, OneDrive...). Those copy files one at a
+    // time whenever they change, so a WAL file synced apart from its database, or a file caught
+    // mid-write, corrupts the copy on the other computer. Everything is kept in the one file
+    // instead (rollback journal), and each commit is flushed to disk before it returns.
+    Synced,
+}
+
+/// Result of a `run_maintenance` pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub integrity_ok: bool,
+    pub integrity_messages: Vec<String>,
+    pub size_before: i64, // bytes
+    pub size_after: i64,  // bytes
+}
+
+/// How many rows `remap_paths` pointed at the new location, per table
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RemapReport {
+    pub activity: usize,
+    pub rules: usize,
+    pub folder_prefs: usize,
+    pub file_index: usize,
+}
+
+/// SQL that's true when `column` is the path ?1 or inside it (whole components only)
+fn under_prefix(column: &str) -> String {
+    format!("({0} = ?1 OR substr({0}, 1, length(?1) + 1) IN (?1 || '/', ?1 || '\\'))", column)
+}
+
+/// SQL for `column` with the prefix ?1 replaced by ?2, or unchanged when it isn't under ?1
+fn remapped(column: &str) -> String {
+    format!("{0} = CASE WHEN {1} THEN ?2 || substr({0}, length(?1) + 1) ELSE {0} END", column, under_prefix(column))
+}
+
+/// Remap `columns` of `table` from ?1 to ?2; a row that would clash with one already at the
+/// new path is dropped instead, as the path it names no longer exists
+fn remap_table(tx: &rusqlite::Transaction, table: &str, columns: &[&str], prefix: (&str, &str)) -> Result<usize, DbError> {
+    let set: Vec<String> = columns.iter().map(|column| remapped(column)).collect();
+    let matches: Vec<String> = columns.iter().map(|column| under_prefix(column)).collect();
+    let updated = tx.execute(
+        &format!("UPDATE OR IGNORE {} SET {} WHERE {}", table, set.join(", "), matches.join(" OR ")),
+        params![prefix.0, prefix.1],
+    )?;
+    tx.execute(&format!("DELETE FROM {} WHERE {}", table, matches.join(" OR ")), params![prefix.0])?;
+    Ok(updated)
+}
+
+/// Insert or replace a file_index row (takes a transaction too, through deref)
+fn insert_indexed_file(conn: &Connection, file: &IndexedFile) -> Result<(), DbError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO file_index
+         (path, folder, size, modified, hash, status, dest_folder, suggested_filename, confidence, reasoning, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            file.path,
+            file.folder,
+            file.size as i64,
+            file.modified as i64,
+            file.hash,
+            file.status,
+            file.dest_folder,
+            file.suggested_filename,
+            file.confidence.map(f64::from),
+            file.reasoning,
+            file.updated_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// Insert a watch profile, or update it if it has an id (takes a transaction too, through deref)
+fn upsert_watch_profile(conn: &Connection, profile: &WatchProfile) -> Result<i64, DbError> {
+    let folders = serde_json::to_string(&profile.folders).map_err(|e| DbError::InsertFailed(e.to_string()))?;
+    let excluded = serde_json::to_string(&profile.excluded).map_err(|e| DbError::InsertFailed(e.to_string()))?;
+
+    match profile.id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE watch_profiles SET name = ?1, watch_path = ?2, folders = ?3, mode = ?4, min_confidence = ?5,
+                 excluded = ?6 WHERE id = ?7",
+                params![profile.name, profile.watch_path, folders, profile.mode, profile.min_confidence, excluded, id],
+            )?;
+            if updated == 0 {
+                return Err(DbError::UpdateFailed(format!("No watch profile with id {}", id)));
+            }
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO watch_profiles (name, watch_path, folders, mode, min_confidence, created_at, excluded)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![profile.name, profile.watch_path, folders, profile.mode, profile.min_confidence, now_ms(), excluded],
+            )
+            .map_err(|e| DbError::InsertFailed(e.to_string()))?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Add a classification rule (takes a transaction too, through deref)
+fn insert_rule(conn: &Connection, pattern: &str, target_folder: &str, profile_id: Option<i64>) -> Result<i64, DbError> {
+    conn.execute(
+        "INSERT INTO rules (pattern, target_folder, created_at, profile_id) VALUES (?1, ?2, ?3, ?4)",
+        params![pattern, target_folder, now_ms(), profile_id],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Create or replace a folder's preferences (takes a transaction too, through deref)
+fn save_folder_pref(conn: &Connection, pref: &FolderPref) -> Result<(), DbError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO folder_prefs
+         (folder, never_suggest, require_confirm, naming_template, description, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            pref.folder,
+            pref.never_suggest as i32,
+            pref.require_confirm as i32,
+            pref.naming_template,
+            pref.description,
+            now_ms()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Remember when a file arrived (re-detecting a replaced file restarts its clock)
+fn record_detection(conn: &Connection, path: &str, detected_at: i64) -> Result<(), DbError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO file_detections (path, detected_at) VALUES (?1, ?2)",
+        params![path, detected_at],
+    )?;
+    Ok(())
+}
+
+/// Check whether a table already has a column (used by additive migrations)
+fn column_exists(conn: &Connection, table: &str, column: &str) -> bool {
+    conn.prepare(&format!("PRAGMA table_info({})", table))
+        .and_then(|mut stmt| {
+            let names: Vec<String> = stmt
+                .query_map([], |row| row.get::<_, String>(1))?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(names.iter().any(|n| n == column))
+        })
+        .unwrap_or(false)
+}
 
 impl Database {
-    /// Initialize database at the given path, creating tables if needed
+    /// Initialize a local database at the given path, creating tables if needed
+    #[cfg(test)]
     pub fn new(db_path: PathBuf) -> Result<Self, DbError> {
+        Self::open(db_path, StorageMode::Local)
+    }
+
+    /// Initialize database at the given path with the journaling suited to where it is
+    pub fn open(db_path: PathBuf, mode: StorageMode) -> Result<Self, DbError> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)
@@ -93,8 +503,16 @@ impl Database {
         let conn = Connection::open(&db_path)
             .map_err(|e| DbError::InitFailed(e.to_string()))?;
 
-        // Enable WAL mode for better concurrency
-        conn.execute_batch("PRAGMA journal_mode = WAL;")
+        // Enable WAL mode for better concurrency, unless a sync client would split the files
+        let pragmas = match mode {
+            StorageMode::Local => "PRAGMA journal_mode = WAL;",
+            StorageMode::Synced => "PRAGMA journal_mode = DELETE; PRAGMA synchronous = FULL;",
+        };
+        conn.execute_batch(pragmas)
+            .map_err(|e| DbError::InitFailed(e.to_string()))?;
+
+        // Wait for competing writers instead of failing with "database is locked"
+        conn.busy_timeout(BUSY_TIMEOUT)
             .map_err(|e| DbError::InitFailed(e.to_string()))?;
 
         let db = Database {
@@ -147,27 +565,281 @@ impl Database {
                 target_folder TEXT NOT NULL,
                 created_at INTEGER NOT NULL
             );
+
+            CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                label TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                ended_at INTEGER,
+                undone INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                op_type TEXT NOT NULL,
+                before_path TEXT NOT NULL,
+                after_path TEXT,
+                status TEXT NOT NULL DEFAULT 'done',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_operations_status
+                ON operations(status, updated_at DESC);
+
+            CREATE TABLE IF NOT EXISTS folder_prefs (
+                folder TEXT PRIMARY KEY,
+                never_suggest INTEGER NOT NULL DEFAULT 0,
+                require_confirm INTEGER NOT NULL DEFAULT 0,
+                naming_template TEXT,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS folder_aliases (
+                alias TEXT PRIMARY KEY COLLATE NOCASE,
+                folder TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS deleted_files (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                original_path TEXT NOT NULL,
+                trash_path TEXT NOT NULL UNIQUE,
+                size INTEGER NOT NULL DEFAULT 0,
+                deleted_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_deleted_files_expires
+                ON deleted_files(expires_at);
+
+            CREATE TABLE IF NOT EXISTS quarantine_items (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL UNIQUE,
+                filename TEXT NOT NULL,
+                original_folder TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                confidence REAL NOT NULL DEFAULT 0,
+                suggested_folder TEXT,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY,
+                action TEXT NOT NULL,
+                before_path TEXT NOT NULL,
+                after_path TEXT,
+                created_at INTEGER NOT NULL,
+                prev_hash TEXT NOT NULL,
+                hash TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS file_index (
+                path TEXT PRIMARY KEY,
+                folder TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT,
+                status TEXT NOT NULL DEFAULT 'new',
+                dest_folder TEXT,
+                suggested_filename TEXT,
+                confidence REAL,
+                reasoning TEXT,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_file_index_folder
+                ON file_index(folder, status);
+
+            CREATE TABLE IF NOT EXISTS file_detections (
+                path TEXT PRIMARY KEY,
+                detected_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS handled_files (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                detected_at INTEGER,
+                moved_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_handled_files_moved_at
+                ON handled_files(moved_at);
+
+            CREATE TABLE IF NOT EXISTS watch_profiles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                watch_path TEXT NOT NULL UNIQUE,
+                folders TEXT NOT NULL DEFAULT '[]',
+                mode TEXT NOT NULL DEFAULT 'ai',
+                min_confidence REAL NOT NULL DEFAULT 0.9,
+                created_at INTEGER NOT NULL,
+                excluded TEXT NOT NULL DEFAULT '[]'
+            );
+
+            CREATE TABLE IF NOT EXISTS provenance (
+                path TEXT PRIMARY KEY,
+                original_path TEXT NOT NULL,
+                organized_at INTEGER NOT NULL,
+                reasoning TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS pending_files (
+                path TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                detected_at INTEGER NOT NULL,
+                classification TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS session_items (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL,
+                source_path TEXT NOT NULL,
+                planned TEXT NOT NULL,
+                policy TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                error TEXT,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_session_items_session ON session_items(session_id, status);
+
+            CREATE TABLE IF NOT EXISTS api_usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                model TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                cost_usd REAL NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_api_usage_created ON api_usage(created_at);
+
+            CREATE TABLE IF NOT EXISTS classification_prompts (
+                filename TEXT PRIMARY KEY,
+                prompt TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
         ",
         )
         .map_err(|e| DbError::InitFailed(e.to_string()))?;
 
-        // Migration: add original_filename column for smart rename tracking
-        let has_column: bool = conn
-            .prepare("PRAGMA table_info(activity_log)")
-            .and_then(|mut stmt| {
-                let names: Vec<String> = stmt
-                    .query_map([], |row| row.get::<_, String>(1))?
-                    .filter_map(|r| r.ok())
-                    .collect();
-                Ok(names.contains(&"original_filename".to_string()))
-            })
-            .unwrap_or(false);
+        // Migration: collapse duplicate corrections (left by re-run imports) before
+        // adding the uniqueness key that keeps them out from now on
+        let has_unique_key: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = 'idx_corrections_unique'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|e| DbError::InitFailed(e.to_string()))?
+            > 0;
+        if !has_unique_key {
+            conn.execute_batch(
+                "DELETE FROM corrections WHERE id NOT IN (
+                     SELECT MIN(id) FROM corrections
+                     GROUP BY filename, ai_suggested, user_chose, created_at
+                 );
+                 CREATE UNIQUE INDEX idx_corrections_unique
+                     ON corrections(filename, ai_suggested, user_chose, created_at);",
+            )
+            .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
 
-        if !has_column {
+        // Migration: add original_filename column for smart rename tracking
+        if !column_exists(&conn, "activity_log", "original_filename") {
             conn.execute_batch("ALTER TABLE activity_log ADD COLUMN original_filename TEXT;")
                 .map_err(|e| DbError::InitFailed(e.to_string()))?;
         }
 
+        // Migration: add source_path column so undone moves can be redone
+        if !column_exists(&conn, "activity_log", "source_path") {
+            conn.execute_batch("ALTER TABLE activity_log ADD COLUMN source_path TEXT;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add content_hash column so undo can verify the file wasn't changed
+        if !column_exists(&conn, "activity_log", "content_hash") {
+            conn.execute_batch("ALTER TABLE activity_log ADD COLUMN content_hash TEXT;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add content_size column, checked along with the hash before an undo
+        if !column_exists(&conn, "activity_log", "content_size") {
+            conn.execute_batch("ALTER TABLE activity_log ADD COLUMN content_size INTEGER;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add confidence column, the classifier's confidence in the destination
+        if !column_exists(&conn, "activity_log", "confidence") {
+            conn.execute_batch("ALTER TABLE activity_log ADD COLUMN confidence REAL;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add session_id column to group moves from one organize run
+        if !column_exists(&conn, "activity_log", "session_id") {
+            conn.execute_batch(
+                "ALTER TABLE activity_log ADD COLUMN session_id INTEGER;
+                 CREATE INDEX IF NOT EXISTS idx_activity_session ON activity_log(session_id);",
+            )
+            .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add profile_id column so rules can belong to one watch profile
+        if !column_exists(&conn, "rules", "profile_id") {
+            conn.execute_batch("ALTER TABLE rules ADD COLUMN profile_id INTEGER;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add description column so folders can carry their real course name
+        if !column_exists(&conn, "folder_prefs", "description") {
+            conn.execute_batch("ALTER TABLE folder_prefs ADD COLUMN description TEXT;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add confidence column so outcomes can calibrate the AI's confidence
+        if !column_exists(&conn, "corrections", "confidence") {
+            conn.execute_batch("ALTER TABLE corrections ADD COLUMN confidence REAL;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add repaired column so repair requests can be counted per model
+        if !column_exists(&conn, "api_usage", "repaired") {
+            conn.execute_batch("ALTER TABLE api_usage ADD COLUMN repaired INTEGER;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add excluded column so watch profiles can skip subfolders
+        if !column_exists(&conn, "watch_profiles", "excluded") {
+            conn.execute_batch("ALTER TABLE watch_profiles ADD COLUMN excluded TEXT NOT NULL DEFAULT '[]';")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: archive corrections past MAX_CORRECTIONS instead of deleting them
+        if !column_exists(&conn, "corrections", "archived") {
+            conn.execute_batch("ALTER TABLE corrections ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: archive activity past MAX_ACTIVITY_LOG instead of deleting it
+        if !column_exists(&conn, "activity_log", "archived") {
+            conn.execute_batch("ALTER TABLE activity_log ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: count each session's moves, so an undo can tell when some are missing
+        // (existing sessions start from the moves still in the log)
+        if !column_exists(&conn, "sessions", "moves") {
+            conn.execute_batch(
+                "ALTER TABLE sessions ADD COLUMN moves INTEGER NOT NULL DEFAULT 0;
+                 UPDATE sessions SET moves = (SELECT COUNT(*) FROM activity_log a WHERE a.session_id = sessions.id);",
+            )
+            .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
+        // Migration: add content_hash column so undoing a copy can check it wasn't edited
+        if !column_exists(&conn, "operations", "content_hash") {
+            conn.execute_batch("ALTER TABLE operations ADD COLUMN content_hash TEXT;")
+                .map_err(|e| DbError::InitFailed(e.to_string()))?;
+        }
+
         Ok(())
     }
 
@@ -176,53 +848,72 @@ impl Database {
     // --------------------------------------------------------
 
     /// Add a correction, enforcing the max limit
+    ///
+    /// An identical correction (same filename, suggestion, choice and timestamp) is not
+    /// inserted twice; the existing row's id is returned instead.
     pub fn add_correction(&self, correction: Correction) -> Result<i64, DbError> {
         let conn = self.conn.lock().unwrap();
 
-        conn.execute(
-            "INSERT INTO corrections (filename, ai_suggested, user_chose, correction_type, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO corrections (filename, ai_suggested, user_chose, correction_type, confidence, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
                 correction.filename,
                 correction.ai_suggested,
                 correction.user_chose,
                 correction.correction_type,
+                correction.confidence.map(f64::from),
                 correction.created_at,
             ],
         )?;
 
-        let id = conn.last_insert_rowid();
+        let id = if inserted > 0 {
+            conn.last_insert_rowid()
+        } else {
+            conn.query_row(
+                "SELECT id FROM corrections
+                 WHERE filename = ?1 AND ai_suggested = ?2 AND user_chose = ?3 AND created_at = ?4",
+                params![
+                    correction.filename,
+                    correction.ai_suggested,
+                    correction.user_chose,
+                    correction.created_at,
+                ],
+                |row| row.get(0),
+            )?
+        };
 
-        // Enforce max limit - delete oldest entries beyond the limit
-        conn.execute(
-            "DELETE FROM corrections WHERE id NOT IN (
-                SELECT id FROM corrections ORDER BY created_at DESC LIMIT ?1
-            )",
-            params![MAX_CORRECTIONS],
-        )?;
+        enforce_corrections_limit(&conn)?;
 
         Ok(id)
     }
 
-    /// Get all corrections (newest first)
+    /// Get the newest MAX_CORRECTIONS corrections, the ones prompts learn from (newest first)
     pub fn get_corrections(&self) -> Result<Vec<Correction>, DbError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, filename, ai_suggested, user_chose, correction_type, created_at
+            "SELECT id, filename, ai_suggested, user_chose, correction_type, created_at, confidence
+             FROM corrections WHERE archived = 0 ORDER BY created_at DESC",
+        )?;
+
+        let corrections = stmt
+            .query_map([], correction_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(corrections)
+    }
+
+    /// Get every correction still kept, archived ones included (newest first), for the
+    /// fine-tuning export
+    pub fn get_correction_history(&self) -> Result<Vec<Correction>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, ai_suggested, user_chose, correction_type, created_at, confidence
              FROM corrections ORDER BY created_at DESC",
         )?;
 
         let corrections = stmt
-            .query_map([], |row| {
-                Ok(Correction {
-                    id: Some(row.get(0)?),
-                    filename: row.get(1)?,
-                    ai_suggested: row.get(2)?,
-                    user_chose: row.get(3)?,
-                    correction_type: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            })?
+            .query_map([], correction_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(corrections)
@@ -235,36 +926,86 @@ impl Database {
         Ok(())
     }
 
+    // --------------------------------------------------------
+    // CLASSIFICATION PROMPTS
+    // --------------------------------------------------------
+
+    /// Keep the prompt a file was last classified with, replacing any earlier one
+    pub fn save_prompt(&self, filename: &str, prompt: &str, created_at: i64) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO classification_prompts (filename, prompt, created_at) VALUES (?1, ?2, ?3)",
+            params![filename, prompt, created_at],
+        )?;
+        conn.execute(
+            "DELETE FROM classification_prompts WHERE filename NOT IN (
+                SELECT filename FROM classification_prompts ORDER BY created_at DESC LIMIT ?1
+            )",
+            params![MAX_PROMPTS],
+        )?;
+        Ok(())
+    }
+
+    /// The stored prompts by filename
+    pub fn get_prompts(&self) -> Result<HashMap<String, String>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT filename, prompt FROM classification_prompts")?;
+        let prompts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, String>, _>>()?;
+        Ok(prompts)
+    }
+
     // --------------------------------------------------------
     // ACTIVITY LOG
     // --------------------------------------------------------
 
     /// Add an activity entry, enforcing the max limit
+    ///
+    /// Entries past MAX_ACTIVITY_LOG are archived: they drop out of the activity log but
+    /// still count for their session (see `get_session_activity`).
     pub fn add_activity(&self, entry: ActivityEntry) -> Result<i64, DbError> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
-        conn.execute(
-            "INSERT INTO activity_log (filename, original_filename, from_folder, to_folder, undone, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        tx.execute(
+            "INSERT INTO activity_log
+             (filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at, content_hash, content_size, confidence)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 entry.filename,
                 entry.original_filename,
+                entry.source_path,
+                entry.session_id,
                 entry.from_folder,
                 entry.to_folder,
                 entry.undone as i32,
                 entry.created_at,
+                entry.content_hash,
+                entry.content_size.map(|size| size as i64),
+                entry.confidence.map(f64::from),
             ],
         )?;
 
-        let id = conn.last_insert_rowid();
+        let id = tx.last_insert_rowid();
+        if let Some(session_id) = entry.session_id {
+            tx.execute("UPDATE sessions SET moves = moves + 1 WHERE id = ?1", params![session_id])?;
+        }
 
         // Enforce max limit
-        conn.execute(
-            "DELETE FROM activity_log WHERE id NOT IN (
+        tx.execute(
+            "UPDATE activity_log SET archived = 1 WHERE archived = 0 AND id NOT IN (
                 SELECT id FROM activity_log ORDER BY created_at DESC LIMIT ?1
             )",
             params![MAX_ACTIVITY_LOG],
         )?;
+        tx.execute(
+            "DELETE FROM activity_log WHERE id NOT IN (
+                SELECT id FROM activity_log ORDER BY created_at DESC LIMIT ?1
+            )",
+            params![MAX_ACTIVITY_HISTORY],
+        )?;
+        tx.commit()?;
 
         Ok(id)
     }
@@ -273,146 +1014,1181 @@ impl Database {
     pub fn get_activity_log(&self) -> Result<Vec<ActivityEntry>, DbError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, filename, original_filename, from_folder, to_folder, undone, created_at
-             FROM activity_log ORDER BY created_at DESC",
+            "SELECT id, filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at, content_hash, content_size, confidence
+             FROM activity_log WHERE archived = 0 ORDER BY created_at DESC, id DESC",
         )?;
 
         let entries = stmt
-            .query_map([], |row| {
-                Ok(ActivityEntry {
-                    id: Some(row.get(0)?),
-                    filename: row.get(1)?,
-                    original_filename: row.get(2)?,
-                    from_folder: row.get(3)?,
-                    to_folder: row.get(4)?,
-                    undone: row.get::<_, i32>(5)? != 0,
-                    created_at: row.get(6)?,
-                })
-            })?
+            .query_map([], activity_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(entries)
     }
 
-    /// Mark an activity entry as undone by timestamp
-    pub fn mark_activity_undone(&self, timestamp: i64) -> Result<bool, DbError> {
+    /// Get every move still kept, archived ones included (newest first), for exports that
+    /// cover more than the activity log shows
+    pub fn get_activity_history(&self) -> Result<Vec<ActivityEntry>, DbError> {
         let conn = self.conn.lock().unwrap();
-        let updated = conn.execute(
-            "UPDATE activity_log SET undone = 1 WHERE created_at = ?1",
-            params![timestamp],
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at, content_hash, content_size, confidence
+             FROM activity_log ORDER BY created_at DESC, id DESC",
         )?;
-        Ok(updated > 0)
+
+        let entries = stmt
+            .query_map([], activity_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
     }
 
-    /// Clear all activity entries
-    pub fn clear_activity_log(&self) -> Result<(), DbError> {
+    /// Get a single activity entry by id
+    pub fn get_activity(&self, id: i64) -> Result<Option<ActivityEntry>, DbError> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM activity_log", [])?;
-        Ok(())
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at, content_hash, content_size, confidence
+             FROM activity_log WHERE id = ?1",
+        )?;
+        match stmt.query_row(params![id], activity_from_row) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(DbError::QueryFailed(e.to_string())),
+        }
     }
 
-    // --------------------------------------------------------
-    // MIGRATION FROM LOCALSTORAGE
-    // --------------------------------------------------------
-
+    /// The newest move, not undone, that left a file at `path`
+    pub fn find_activity_at(&self, path: &str) -> Result<Option<ActivityEntry>, DbError> {
+        let path = Path::new(path);
+        let (Some(folder), Some(filename)) = (path.parent(), path.file_name()) else {
+            return Ok(None);
+        };
+        let folder = folder.to_string_lossy();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at, content_hash, content_size, confidence
+             FROM activity_log
+             WHERE undone = 0 AND filename = ?1 AND rtrim(to_folder, '/\\') = ?2
+             ORDER BY created_at DESC, id DESC LIMIT 1",
+        )?;
+        match stmt.query_row(params![filename.to_string_lossy(), folder.trim_end_matches(['/', '\\'])], activity_from_row) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(DbError::QueryFailed(e.to_string())),
+        }
+    }
+
+    /// Mark an activity entry as undone by id
+    pub fn mark_activity_undone(&self, id: i64) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE activity_log SET undone = 1 WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Clear all activity entries
+    pub fn clear_activity_log(&self) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM activity_log", [])?;
+        Ok(())
+    }
+
+    // --------------------------------------------------------
+    // SESSIONS
+    // --------------------------------------------------------
+
+    /// Start a new organize session, returning its id
+    pub fn create_session(&self, label: &str) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sessions (label, created_at) VALUES (?1, ?2)",
+            params![label, now_ms()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Mark a session as finished
+    pub fn end_session(&self, id: i64) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE sessions SET ended_at = ?1 WHERE id = ?2 AND ended_at IS NULL",
+            params![now_ms(), id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Get all sessions with their file counts (newest first)
+    pub fn get_sessions(&self) -> Result<Vec<Session>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.label, s.created_at, s.ended_at, s.undone,
+                    (SELECT COUNT(*) FROM activity_log a WHERE a.session_id = s.id),
+                    (SELECT COUNT(*) FROM session_items i WHERE i.session_id = s.id AND i.status = 'pending')
+             FROM sessions s ORDER BY s.created_at DESC, s.id DESC",
+        )?;
+
+        let sessions = stmt
+            .query_map([], |row| {
+                Ok(Session {
+                    id: row.get(0)?,
+                    label: row.get(1)?,
+                    created_at: row.get(2)?,
+                    ended_at: row.get(3)?,
+                    undone: row.get::<_, i32>(4)? != 0,
+                    file_count: row.get(5)?,
+                    pending_items: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sessions)
+    }
+
+    /// Get all activity entries belonging to a session (newest first), archived ones too
+    pub fn get_session_activity(&self, session_id: i64) -> Result<Vec<ActivityEntry>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at, content_hash, content_size, confidence
+             FROM activity_log WHERE session_id = ?1 ORDER BY created_at DESC, id DESC",
+        )?;
+
+        let entries = stmt
+            .query_map(params![session_id], activity_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// How many moves a session made (0 for an unknown session); fewer entries in
+    /// `get_session_activity` means some were deleted from the history
+    pub fn get_session_move_count(&self, session_id: i64) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        match conn.query_row("SELECT moves FROM sessions WHERE id = ?1", params![session_id], |row| row.get(0)) {
+            Ok(moves) => Ok(moves),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+            Err(e) => Err(DbError::QueryFailed(e.to_string())),
+        }
+    }
+
+    /// Mark a session and all of its activity entries as undone in one transaction
+    pub fn mark_session_undone(&self, session_id: i64) -> Result<bool, DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE activity_log SET undone = 1 WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        let updated = tx.execute(
+            "UPDATE sessions SET undone = 1 WHERE id = ?1",
+            params![session_id],
+        )?;
+        tx.commit()?;
+        Ok(updated > 0)
+    }
+
+    /// Record a session's planned moves as pending, returning their ids in order
+    pub fn add_session_items(
+        &self,
+        session_id: i64,
+        items: &[(String, serde_json::Value)],
+        policy: &str,
+    ) -> Result<Vec<i64>, DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = now_ms();
+        let mut ids = Vec::with_capacity(items.len());
+        for (source_path, planned) in items {
+            tx.execute(
+                "INSERT INTO session_items (session_id, source_path, planned, policy, status, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![session_id, source_path, planned.to_string(), policy, ITEM_PENDING, now],
+            )?;
+            ids.push(tx.last_insert_rowid());
+        }
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    /// Record how a session item went; returns false if there's no such item
+    pub fn set_session_item_status(&self, id: i64, status: &str, error: Option<&str>) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE session_items SET status = ?2, error = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, status, error, now_ms()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Get a session's planned moves in the order they were planned
+    pub fn get_session_items(&self, session_id: i64) -> Result<Vec<SessionItem>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, source_path, planned, policy, status, error, updated_at
+             FROM session_items WHERE session_id = ?1 ORDER BY id",
+        )?;
+        let items = stmt
+            .query_map(params![session_id], session_item_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    /// Get the moves of every session parked until their destination drive is back, oldest first
+    pub fn get_deferred_items(&self) -> Result<Vec<SessionItem>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, source_path, planned, policy, status, error, updated_at
+             FROM session_items WHERE status = ?1 ORDER BY id",
+        )?;
+        let items = stmt
+            .query_map(params![ITEM_DEFERRED], session_item_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    // --------------------------------------------------------
+    // MIGRATION FROM LOCALSTORAGE
+    // --------------------------------------------------------
+
     // --------------------------------------------------------
     // SETTINGS
     // --------------------------------------------------------
 
-    /// Store a setting value
-    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), DbError> {
+    /// Store a setting value
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Store several setting values in one transaction
+    pub fn set_settings(&self, values: &[(String, String)]) -> Result<(), DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (key, value) in values {
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Save an imported setup in one transaction, so a failure leaves none of it saved:
+    /// settings, watch profiles (updated if they have an id), rules (tied to their watch
+    /// profile by the folder it watches) and folder preferences
+    pub fn save_setup(
+        &self,
+        settings: &[(String, String)],
+        watch_profiles: &[WatchProfile],
+        rules: &[ProfileRule],
+        folder_prefs: &[FolderPref],
+    ) -> Result<(), DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (key, value) in settings {
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+        }
+        for profile in watch_profiles {
+            upsert_watch_profile(&tx, profile)?;
+        }
+        for rule in rules {
+            let profile_id = match &rule.watch_path {
+                Some(path) => Some(tx.query_row(
+                    "SELECT id FROM watch_profiles WHERE watch_path = ?1 ORDER BY id LIMIT 1",
+                    params![path],
+                    |row| row.get::<_, i64>(0),
+                )?),
+                None => None,
+            };
+            insert_rule(&tx, &rule.pattern, &rule.target_folder, profile_id)?;
+        }
+        for pref in folder_prefs {
+            save_folder_pref(&tx, pref)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Retrieve a setting value
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+        match stmt.query_row(params![key], |row| row.get(0)) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(DbError::QueryFailed(e.to_string())),
+        }
+    }
+
+    // --------------------------------------------------------
+    // RULES
+    // --------------------------------------------------------
+
+    /// Add a classification rule, optionally limited to one watch profile (None: every folder)
+    pub fn add_rule(&self, pattern: &str, target_folder: &str, profile_id: Option<i64>) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        insert_rule(&conn, pattern, target_folder, profile_id)
+    }
+
+    /// Get all classification rules
+    pub fn get_rules(&self) -> Result<Vec<Rule>, DbError> {
+        self.query_rules(
+            "SELECT id, pattern, target_folder, created_at, profile_id FROM rules ORDER BY created_at ASC",
+            [],
+        )
+    }
+
+    /// Get the rules that apply to a watch profile: its own rules first, then the global ones
+    ///
+    /// With `None`, only the global rules are returned.
+    pub fn get_rules_for_profile(&self, profile_id: Option<i64>) -> Result<Vec<Rule>, DbError> {
+        self.query_rules(
+            "SELECT id, pattern, target_folder, created_at, profile_id FROM rules
+             WHERE profile_id IS NULL OR profile_id = ?1
+             ORDER BY profile_id IS NULL, created_at ASC",
+            params![profile_id],
+        )
+    }
+
+    fn query_rules(&self, sql: &str, args: impl rusqlite::Params) -> Result<Vec<Rule>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+
+        let rules = stmt
+            .query_map(args, |row| {
+                Ok(Rule {
+                    id: Some(row.get(0)?),
+                    pattern: row.get(1)?,
+                    target_folder: row.get(2)?,
+                    created_at: row.get(3)?,
+                    profile_id: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(rules)
+    }
+
+    /// Delete a rule by id
+    pub fn delete_rule(&self, id: i64) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM rules WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    // --------------------------------------------------------
+    // WATCH PROFILES
+    // --------------------------------------------------------
+
+    /// Insert a watch profile, or update it if it has an id; returns the id
+    pub fn save_watch_profile(&self, profile: &WatchProfile) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        upsert_watch_profile(&conn, profile)
+    }
+
+    /// Get all watch profiles, oldest first
+    pub fn get_watch_profiles(&self) -> Result<Vec<WatchProfile>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, watch_path, folders, mode, min_confidence, created_at, excluded
+             FROM watch_profiles ORDER BY created_at ASC, id ASC",
+        )?;
+
+        let profiles = stmt
+            .query_map([], |row| {
+                let folders: String = row.get(3)?;
+                let excluded: String = row.get(7)?;
+                Ok(WatchProfile {
+                    id: Some(row.get(0)?),
+                    name: row.get(1)?,
+                    watch_path: row.get(2)?,
+                    folders: serde_json::from_str(&folders).unwrap_or_default(),
+                    mode: row.get(4)?,
+                    min_confidence: row.get(5)?,
+                    created_at: row.get(6)?,
+                    excluded: serde_json::from_str(&excluded).unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(profiles)
+    }
+
+    /// Delete a watch profile and the rules that belong to it
+    pub fn delete_watch_profile(&self, id: i64) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM rules WHERE profile_id = ?1", params![id])?;
+        let deleted = conn.execute("DELETE FROM watch_profiles WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    // --------------------------------------------------------
+    // FOLDER PREFERENCES
+    // --------------------------------------------------------
+
+    /// Create or replace the preferences for a destination folder
+    pub fn set_folder_pref(&self, pref: FolderPref) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        save_folder_pref(&conn, &pref)
+    }
+
+    /// Set a folder's description, keeping its other preferences
+    pub fn set_folder_description(&self, folder: &str, description: Option<&str>) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO folder_prefs (folder, description, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(folder) DO UPDATE SET description = ?2, updated_at = ?3",
+            params![folder, description, now_ms()],
+        )?;
+        Ok(())
+    }
+
+    /// Get preferences for all folders that have any set
+    pub fn get_folder_prefs(&self) -> Result<Vec<FolderPref>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT folder, never_suggest, require_confirm, naming_template, description, updated_at
+             FROM folder_prefs ORDER BY folder ASC",
+        )?;
+
+        let prefs = stmt
+            .query_map([], |row| {
+                Ok(FolderPref {
+                    folder: row.get(0)?,
+                    never_suggest: row.get::<_, i32>(1)? != 0,
+                    require_confirm: row.get::<_, i32>(2)? != 0,
+                    naming_template: row.get(3)?,
+                    description: row.get(4)?,
+                    updated_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(prefs)
+    }
+
+    /// Remove all preferences for a folder (back to defaults)
+    pub fn delete_folder_pref(&self, folder: &str) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM folder_prefs WHERE folder = ?1", params![folder])?;
+        Ok(deleted > 0)
+    }
+
+    /// Replace all folder aliases (aliases differing only in case are kept once, the last wins)
+    pub fn set_folder_aliases(&self, aliases: &[FolderAlias]) -> Result<(), DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM folder_aliases", [])?;
+        for alias in aliases {
+            tx.execute(
+                "INSERT OR REPLACE INTO folder_aliases (alias, folder) VALUES (?1, ?2)",
+                params![alias.alias, alias.folder],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get all folder aliases, by folder
+    pub fn get_folder_aliases(&self) -> Result<Vec<FolderAlias>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT alias, folder FROM folder_aliases ORDER BY folder ASC, alias ASC")?;
+        let aliases = stmt
+            .query_map([], |row| Ok(FolderAlias { alias: row.get(0)?, folder: row.get(1)? }))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(aliases)
+    }
+
+    // --------------------------------------------------------
+    // QUARANTINE
+    // --------------------------------------------------------
+
+    /// Record a file moved into quarantine (replacing any stale entry for the same path)
+    pub fn add_quarantine_item(&self, item: QuarantineItem) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO quarantine_items
+             (path, filename, original_folder, reason, confidence, suggested_folder, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                item.path,
+                item.filename,
+                item.original_folder,
+                item.reason,
+                item.confidence,
+                item.suggested_folder,
+                item.created_at
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get all quarantined files, newest first
+    pub fn get_quarantine_items(&self) -> Result<Vec<QuarantineItem>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, path, filename, original_folder, reason, confidence, suggested_folder, created_at
+             FROM quarantine_items ORDER BY created_at DESC, id DESC",
+        )?;
+
+        let items = stmt
+            .query_map([], |row| {
+                Ok(QuarantineItem {
+                    id: Some(row.get(0)?),
+                    path: row.get(1)?,
+                    filename: row.get(2)?,
+                    original_folder: row.get(3)?,
+                    reason: row.get(4)?,
+                    confidence: row.get(5)?,
+                    suggested_folder: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    /// Forget a quarantined file (it was re-classified, moved or deleted)
+    pub fn remove_quarantine_item(&self, path: &str) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM quarantine_items WHERE path = ?1", params![path])?;
+        Ok(deleted > 0)
+    }
+
+    // --------------------------------------------------------
+    // APP TRASH (SAFE DELETE)
+    // --------------------------------------------------------
+
+    /// Record a file moved into the app-managed trash
+    pub fn add_deleted_file(&self, file: DeletedFile) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO deleted_files (original_path, trash_path, size, deleted_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![file.original_path, file.trash_path, file.size, file.deleted_at, file.expires_at],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get files in the app trash, most recently deleted first.
+    /// With `expired_before`, only those whose retention ended before that time.
+    pub fn get_deleted_files(&self, expired_before: Option<i64>) -> Result<Vec<DeletedFile>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, original_path, trash_path, size, deleted_at, expires_at
+             FROM deleted_files
+             WHERE ?1 IS NULL OR expires_at <= ?1
+             ORDER BY deleted_at DESC, id DESC",
+        )?;
+
+        let files = stmt
+            .query_map(params![expired_before], |row| {
+                Ok(DeletedFile {
+                    id: Some(row.get(0)?),
+                    original_path: row.get(1)?,
+                    trash_path: row.get(2)?,
+                    size: row.get(3)?,
+                    deleted_at: row.get(4)?,
+                    expires_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(files)
+    }
+
+    /// Forget a file in the app trash (purged, restored or gone)
+    pub fn remove_deleted_file(&self, id: i64) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM deleted_files WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    // --------------------------------------------------------
+    // PROVENANCE
+    // --------------------------------------------------------
+
+    /// Record that the file at `before` was organized to `after`
+    ///
+    /// A file moved again keeps its first original path, and its earlier reasoning if none
+    /// is given this time.
+    pub fn record_provenance(
+        &self,
+        before: &str,
+        after: &str,
+        organized_at: i64,
+        reasoning: Option<&str>,
+    ) -> Result<Provenance, DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let earlier: Option<(String, Option<String>)> = match tx.query_row(
+            "SELECT original_path, reasoning FROM provenance WHERE path = ?1",
+            params![before],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(earlier) => Some(earlier),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(DbError::QueryFailed(e.to_string())),
+        };
+        tx.execute("DELETE FROM provenance WHERE path = ?1", params![before])?;
+
+        let (original_path, earlier_reasoning) = earlier.unwrap_or_else(|| (before.to_string(), None));
+        let provenance = Provenance {
+            path: after.to_string(),
+            original_path,
+            organized_at,
+            reasoning: reasoning.map(str::to_string).or(earlier_reasoning),
+        };
+        tx.execute(
+            "INSERT OR REPLACE INTO provenance (path, original_path, organized_at, reasoning)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![provenance.path, provenance.original_path, provenance.organized_at, provenance.reasoning],
+        )?;
+        tx.commit()?;
+        Ok(provenance)
+    }
+
+    /// Get the provenance recorded for the file now at `path`
+    pub fn get_provenance(&self, path: &str) -> Result<Option<Provenance>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn.query_row(
+            "SELECT path, original_path, organized_at, reasoning FROM provenance WHERE path = ?1",
+            params![path],
+            |row| {
+                Ok(Provenance {
+                    path: row.get(0)?,
+                    original_path: row.get(1)?,
+                    organized_at: row.get(2)?,
+                    reasoning: row.get(3)?,
+                })
+            },
+        );
+        match row {
+            Ok(provenance) => Ok(Some(provenance)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(DbError::QueryFailed(e.to_string())),
+        }
+    }
+
+    // --------------------------------------------------------
+    // PENDING QUEUE
+    // --------------------------------------------------------
+
+    /// Queue a detected file for review. A file detected again (e.g. downloaded over
+    /// the old copy) starts over without its old classification.
+    pub fn add_pending_file(&self, file: &PendingFile) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        let classification = file.classification.as_ref().map(|c| c.to_string());
+        conn.execute(
+            "INSERT OR REPLACE INTO pending_files (path, name, size, detected_at, classification)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![file.path, file.name, file.size as i64, file.detected_at, classification],
+        )?;
+        Ok(())
+    }
+
+    /// Keep the classification of a queued file; returns false if it isn't queued
+    pub fn set_pending_classification(&self, path: &str, classification: &serde_json::Value) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE pending_files SET classification = ?2 WHERE path = ?1",
+            params![path, classification.to_string()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Follow a queued file to its new name after a rename
+    pub fn rename_pending_file(&self, before: &str, after: &str, name: &str) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE OR REPLACE pending_files SET path = ?2, name = ?3 WHERE path = ?1",
+            params![before, after, name],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Take a file off the queue; returns false if it wasn't queued
+    pub fn remove_pending_file(&self, path: &str) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM pending_files WHERE path = ?1", params![path])?;
+        Ok(deleted > 0)
+    }
+
+    /// Get all queued files, oldest first
+    pub fn get_pending_files(&self) -> Result<Vec<PendingFile>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT path, name, size, detected_at, classification FROM pending_files ORDER BY detected_at, path",
+        )?;
+        let files = stmt
+            .query_map([], |row| {
+                let classification: Option<String> = row.get(4)?;
+                Ok(PendingFile {
+                    path: row.get(0)?,
+                    name: row.get(1)?,
+                    size: row.get::<_, i64>(2)? as u64,
+                    detected_at: row.get(3)?,
+                    classification: classification.and_then(|c| serde_json::from_str(&c).ok()),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    // --------------------------------------------------------
+    // OPERATIONS JOURNAL
+    // --------------------------------------------------------
+
+    /// Record a completed file operation.
+    ///
+    /// Any operations that were undone but not redone are discarded, so the
+    /// redo stack behaves like an editor's: a new action clears it.
+    pub fn add_operation(&self, op: Operation) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "UPDATE operations SET status = 'discarded' WHERE status = 'undone'",
+            [],
+        )?;
+
+        conn.execute(
+            "INSERT INTO operations (op_type, before_path, after_path, status, created_at, updated_at, content_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                op.op_type,
+                op.before_path,
+                op.after_path,
+                op.status,
+                op.created_at,
+                op.updated_at,
+                op.content_hash,
+            ],
+        )?;
+
+        let id = conn.last_insert_rowid();
+
+        // Enforce max limit
+        conn.execute(
+            "DELETE FROM operations WHERE id NOT IN (
+                SELECT id FROM operations ORDER BY id DESC LIMIT ?1
+            )",
+            params![MAX_OPERATIONS],
+        )?;
+
+        Ok(id)
+    }
+
+    /// Get a single operation by id
+    pub fn get_operation(&self, id: i64) -> Result<Option<Operation>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, op_type, before_path, after_path, status, created_at, updated_at, content_hash
+             FROM operations WHERE id = ?1",
+        )?;
+        match stmt.query_row(params![id], operation_from_row) {
+            Ok(op) => Ok(Some(op)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(DbError::QueryFailed(e.to_string())),
+        }
+    }
+
+    /// Get the most recent operations (newest first)
+    pub fn get_operations(&self, limit: usize) -> Result<Vec<Operation>, DbError> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
-            params![key, value],
+        let mut stmt = conn.prepare(
+            "SELECT id, op_type, before_path, after_path, status, created_at, updated_at, content_hash
+             FROM operations ORDER BY id DESC LIMIT ?1",
         )?;
-        Ok(())
+
+        let ops = stmt
+            .query_map(params![limit], operation_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ops)
     }
 
-    /// Retrieve a setting value
-    pub fn get_setting(&self, key: &str) -> Result<Option<String>, DbError> {
+    /// Paths the assistant sent to the system recycle bin (journaled "trash" operations)
+    pub fn trashed_paths(&self) -> Result<std::collections::HashSet<String>, DbError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
-        match stmt.query_row(params![key], |row| row.get(0)) {
-            Ok(value) => Ok(Some(value)),
+        let mut stmt = conn.prepare("SELECT before_path FROM operations WHERE op_type = 'trash'")?;
+        let paths = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(paths)
+    }
+
+    /// Get the top of the undo stack (latest "done") or redo stack (most recently "undone")
+    ///
+    /// Operations without an "after" path (trashing) can't be undone, so they're skipped
+    /// rather than blocking the ones before them.
+    pub fn last_operation(&self, status: &str) -> Result<Option<Operation>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, op_type, before_path, after_path, status, created_at, updated_at, content_hash
+             FROM operations WHERE status = ?1 AND after_path IS NOT NULL
+             ORDER BY updated_at DESC, id DESC LIMIT 1",
+        )?;
+        match stmt.query_row(params![status], operation_from_row) {
+            Ok(op) => Ok(Some(op)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(DbError::QueryFailed(e.to_string())),
         }
     }
 
+    /// Update an operation's status after it was undone or redone
+    pub fn set_operation_status(&self, id: i64, status: &str) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE operations SET status = ?1, updated_at = ?2 WHERE id = ?3",
+            params![status, now_ms(), id],
+        )?;
+        Ok(updated > 0)
+    }
+
     // --------------------------------------------------------
-    // RULES
+    // AUDIT LOG
     // --------------------------------------------------------
 
-    /// Add a classification rule
-    pub fn add_rule(&self, pattern: &str, target_folder: &str) -> Result<i64, DbError> {
-        let conn = self.conn.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
+    /// Append an entry to the audit log, chained onto the newest one
+    ///
+    /// Unlike the operations journal, the audit log is never trimmed or updated.
+    pub fn append_audit(
+        &self,
+        action: &str,
+        before_path: &str,
+        after_path: Option<&str>,
+        created_at: i64,
+    ) -> Result<AuditEntry, DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let (prev_id, prev_hash) = match tx.query_row(
+            "SELECT id, hash FROM audit_log ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        ) {
+            Ok(last) => last,
+            Err(rusqlite::Error::QueryReturnedNoRows) => (0, audit::GENESIS_HASH.to_string()),
+            Err(e) => return Err(e.into()),
+        };
 
-        conn.execute(
-            "INSERT INTO rules (pattern, target_folder, created_at) VALUES (?1, ?2, ?3)",
-            params![pattern, target_folder, now],
+        let id = prev_id + 1;
+        let hash = audit::entry_hash(id, &prev_hash, action, before_path, after_path, created_at);
+        tx.execute(
+            "INSERT INTO audit_log (id, action, before_path, after_path, created_at, prev_hash, hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![id, action, before_path, after_path, created_at, prev_hash, hash],
         )?;
-
-        Ok(conn.last_insert_rowid())
+        tx.commit()?;
+
+        Ok(AuditEntry {
+            id,
+            action: action.to_string(),
+            before_path: before_path.to_string(),
+            after_path: after_path.map(str::to_string),
+            created_at,
+            prev_hash,
+            hash,
+        })
     }
 
-    /// Get all classification rules
-    pub fn get_rules(&self) -> Result<Vec<Rule>, DbError> {
+    /// Get the whole audit log, oldest first
+    pub fn get_audit_log(&self) -> Result<Vec<AuditEntry>, DbError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, pattern, target_folder, created_at FROM rules ORDER BY created_at ASC",
+            "SELECT id, action, before_path, after_path, created_at, prev_hash, hash
+             FROM audit_log ORDER BY id",
         )?;
-
-        let rules = stmt
+        let entries = stmt
             .query_map([], |row| {
-                Ok(Rule {
-                    id: Some(row.get(0)?),
-                    pattern: row.get(1)?,
-                    target_folder: row.get(2)?,
-                    created_at: row.get(3)?,
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    action: row.get(1)?,
+                    before_path: row.get(2)?,
+                    after_path: row.get(3)?,
+                    created_at: row.get(4)?,
+                    prev_hash: row.get(5)?,
+                    hash: row.get(6)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
 
-        Ok(rules)
+    // --------------------------------------------------------
+    // FILE INDEX
+    // --------------------------------------------------------
+
+    /// Bring the index for `folder` in line with a full listing of it
+    ///
+    /// New and changed files are (re)indexed as unclassified, unchanged ones keep what's
+    /// known about them, and files no longer listed are dropped. Returns how many rows
+    /// were added or reset.
+    pub fn sync_file_index(&self, folder: &str, files: &[FileEntry]) -> Result<usize, DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let known: HashMap<String, (u64, u64)> = {
+            let mut stmt = tx.prepare("SELECT path, size, modified FROM file_index WHERE folder = ?1")?;
+            let rows = stmt.query_map(params![folder], |row| {
+                Ok((row.get::<_, String>(0)?, (row.get::<_, i64>(1)? as u64, row.get::<_, i64>(2)? as u64)))
+            })?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let now = now_ms();
+        let mut changed = 0;
+        for file in files {
+            if known.get(&file.path) == Some(&(file.size, file.modified)) {
+                continue;
+            }
+            insert_indexed_file(&tx, &IndexedFile::new(folder, file, now))?;
+            record_detection(&tx, &file.path, now)?;
+            changed += 1;
+        }
+
+        let listed: std::collections::HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        for path in known.keys().filter(|path| !listed.contains(path.as_str())) {
+            tx.execute("DELETE FROM file_index WHERE path = ?1", params![path])?;
+        }
+        tx.commit()?;
+        Ok(changed)
     }
 
-    /// Delete a rule by id
-    pub fn delete_rule(&self, id: i64) -> Result<bool, DbError> {
+    /// Index one file (e.g. just detected by the watcher), keeping what's known if it's unchanged
+    pub fn index_file(&self, folder: &str, file: &FileEntry) -> Result<(), DbError> {
         let conn = self.conn.lock().unwrap();
-        let deleted = conn.execute("DELETE FROM rules WHERE id = ?1", params![id])?;
+        let unchanged = conn
+            .query_row(
+                "SELECT COUNT(*) FROM file_index WHERE path = ?1 AND size = ?2 AND modified = ?3",
+                params![file.path, file.size as i64, file.modified as i64],
+                |row| row.get::<_, i64>(0),
+            )?
+            > 0;
+        if !unchanged {
+            let now = now_ms();
+            insert_indexed_file(&conn, &IndexedFile::new(folder, file, now))?;
+            record_detection(&conn, &file.path, now)?;
+        }
+        Ok(())
+    }
+
+    /// Store a file's index row as given (e.g. with its classification filled in)
+    pub fn save_indexed_file(&self, file: &IndexedFile) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        insert_indexed_file(&conn, file)
+    }
+
+    /// Forget a file that was moved away or deleted
+    pub fn remove_indexed_file(&self, path: &str) -> Result<bool, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM file_index WHERE path = ?1", params![path])?;
         Ok(deleted > 0)
     }
 
+    /// Get the index rows for `folder`, keyed by path
+    pub fn get_file_index(&self, folder: &str) -> Result<HashMap<String, IndexedFile>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT path, folder, size, modified, hash, status, dest_folder, suggested_filename,
+                    confidence, reasoning, updated_at
+             FROM file_index WHERE folder = ?1",
+        )?;
+        let files = stmt
+            .query_map(params![folder], indexed_file_from_row)?
+            .map(|file| file.map(|f| (f.path.clone(), f)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(files)
+    }
+
+    /// Names of up to `limit` indexed files inside `folder` (at any depth), newest first
+    pub fn indexed_names_in(&self, folder: &str, limit: usize) -> Result<Vec<String>, DbError> {
+        let folder = folder.trim_end_matches(['/', '\\']);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT path FROM file_index
+             WHERE substr(path, 1, length(?1) + 1) IN (?1 || '/', ?1 || '\\')
+             ORDER BY modified DESC LIMIT ?2",
+        )?;
+        let paths = stmt
+            .query_map(params![folder, limit as i64], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(paths
+            .iter()
+            .filter_map(|path| std::path::Path::new(path).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect())
+    }
+
+    /// Every indexed path, with when its row was last updated
+    pub fn get_indexed_paths(&self) -> Result<Vec<(String, i64)>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path, updated_at FROM file_index ORDER BY path")?;
+        let paths = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(paths)
+    }
+
+    /// Count the indexed files in `folder` without touching the filesystem
+    pub fn file_index_summary(&self, folder: &str) -> Result<IndexSummary, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let (unsorted, unclassified, total_size) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(status = 'new'), 0), COALESCE(SUM(size), 0)
+             FROM file_index WHERE folder = ?1",
+            params![folder],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+        )?;
+        Ok(IndexSummary {
+            folder: folder.to_string(),
+            unsorted: unsorted as usize,
+            unclassified: unclassified as usize,
+            total_size: total_size as u64,
+        })
+    }
+
+    // --------------------------------------------------------
+    // API USAGE
+    // --------------------------------------------------------
+
+    /// Record the tokens and cost of one API call
+    pub fn add_api_usage(&self, usage: &ApiUsage) -> Result<i64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO api_usage (model, prompt_tokens, completion_tokens, cost_usd, created_at, repaired)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                usage.model,
+                usage.prompt_tokens as i64,
+                usage.completion_tokens as i64,
+                usage.cost_usd,
+                usage.created_at,
+                usage.repaired
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Per model: how many requests were made and how many replies needed a repair request,
+    /// most repaired first
+    pub fn get_model_reliability(&self) -> Result<Vec<ModelReliability>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT model, COUNT(*) - COUNT(repaired), COUNT(repaired), COALESCE(SUM(repaired), 0)
+             FROM api_usage GROUP BY model ORDER BY 3 DESC, 2 DESC, model",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ModelReliability {
+                model: row.get(0)?,
+                calls: row.get::<_, i64>(1)? as u64,
+                repairs: row.get::<_, i64>(2)? as u64,
+                repaired: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Total cost in dollars of the API calls made at or after `since` (Unix ms)
+    pub fn get_api_spend_since(&self, since: i64) -> Result<f64, DbError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row(
+            "SELECT COALESCE(SUM(cost_usd), 0.0) FROM api_usage WHERE created_at >= ?1",
+            params![since],
+            |row| row.get(0),
+        )?)
+    }
+
+    // --------------------------------------------------------
+    // HANDLED FILES
+    // --------------------------------------------------------
+
+    /// Record that the file that was at `source_path` has been moved, picking up when it
+    /// was detected. Returns how long it waited (detection to move) in ms, if known.
+    pub fn record_handled_file(&self, source_path: Option<&str>, moved_at: i64) -> Result<Option<i64>, DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let detected_at: Option<i64> = match source_path {
+            Some(path) => {
+                let detected_at = tx
+                    .query_row(
+                        "SELECT detected_at FROM file_detections WHERE path = ?1",
+                        params![path],
+                        |row| row.get(0),
+                    )
+                    .ok();
+                tx.execute("DELETE FROM file_detections WHERE path = ?1", params![path])?;
+                detected_at
+            }
+            None => None,
+        };
+        tx.execute(
+            "INSERT INTO handled_files (detected_at, moved_at) VALUES (?1, ?2)",
+            params![detected_at, moved_at],
+        )?;
+        tx.execute(
+            "DELETE FROM file_detections WHERE detected_at < ?1",
+            params![moved_at - MAX_DETECTION_AGE_MS],
+        )?;
+        tx.execute(
+            "DELETE FROM handled_files WHERE moved_at < ?1",
+            params![moved_at - MAX_HANDLED_FILE_AGE_MS],
+        )?;
+        tx.commit()?;
+        Ok(detected_at.map(|at| moved_at - at))
+    }
+
+    /// Files moved since `since` (Unix timestamp ms), oldest first
+    pub fn get_handled_files(&self, since: i64) -> Result<Vec<HandledFile>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT detected_at, moved_at FROM handled_files WHERE moved_at >= ?1 ORDER BY moved_at",
+        )?;
+        let files = stmt
+            .query_map(params![since], |row| {
+                Ok(HandledFile {
+                    detected_at: row.get(0)?,
+                    moved_at: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
     // --------------------------------------------------------
     // MIGRATION FROM LOCALSTORAGE
     // --------------------------------------------------------
 
     /// Import corrections from localStorage format
+    ///
+    /// Returns the number of corrections actually inserted; ones already present are skipped.
     pub fn import_corrections(&self, corrections: Vec<Correction>) -> Result<usize, DbError> {
         let conn = self.conn.lock().unwrap();
         let mut count = 0;
 
         for c in corrections {
-            conn.execute(
+            count += conn.execute(
                 "INSERT OR IGNORE INTO corrections
-                 (filename, ai_suggested, user_chose, correction_type, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                 (filename, ai_suggested, user_chose, correction_type, confidence, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
                 params![
                     c.filename,
                     c.ai_suggested,
                     c.user_chose,
                     c.correction_type,
+                    c.confidence.map(f64::from),
                     c.created_at
                 ],
             )?;
-            count += 1;
         }
 
+        enforce_corrections_limit(&conn)?;
+
         Ok(count)
     }
 
@@ -424,11 +2200,13 @@ impl Database {
         for e in entries {
             conn.execute(
                 "INSERT OR IGNORE INTO activity_log
-                 (filename, original_filename, from_folder, to_folder, undone, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                 (filename, original_filename, source_path, session_id, from_folder, to_folder, undone, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
                 params![
                     e.filename,
                     e.original_filename,
+                    e.source_path,
+                    e.session_id,
                     e.from_folder,
                     e.to_folder,
                     e.undone as i32,
@@ -440,6 +2218,239 @@ impl Database {
 
         Ok(count)
     }
+
+    // ------------------------------------------------------------
+    // PATH REMAPPING
+    // ------------------------------------------------------------
+
+    /// Point stored paths under `old_prefix` at `new_prefix` instead (a folder was moved or
+    /// renamed outside the app), across activity, rules, folder preferences and the file
+    /// index in one transaction
+    pub fn remap_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<RemapReport, DbError> {
+        // A folder can't be moved into itself (and rows left under the old prefix are dropped)
+        let inside = |path: &str| {
+            path == old_prefix
+                || path.starts_with(&format!("{}/", old_prefix))
+                || path.starts_with(&format!("{}\\", old_prefix))
+        };
+        if inside(new_prefix) {
+            return Err(DbError::UpdateFailed(format!("{} is inside {}", new_prefix, old_prefix)));
+        }
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let prefix = (old_prefix, new_prefix);
+        let report = RemapReport {
+            activity: remap_table(&tx, "activity_log", &["from_folder", "to_folder", "source_path"], prefix)?,
+            rules: remap_table(&tx, "rules", &["target_folder"], prefix)?,
+            folder_prefs: remap_table(&tx, "folder_prefs", &["folder"], prefix)?,
+            file_index: remap_table(&tx, "file_index", &["path", "folder", "dest_folder"], prefix)?,
+        };
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Delete activity entries and file index rows whose files were deleted outside the app,
+    /// in one transaction. Returns how many of each were removed.
+    pub fn purge_orphans(&self, activity_ids: &[i64], index_paths: &[String]) -> Result<(usize, usize), DbError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut activity = 0;
+        for id in activity_ids {
+            // The file is gone, so its session no longer needs the move to be undone as a whole
+            tx.execute(
+                "UPDATE sessions SET moves = moves - 1 WHERE id = (SELECT session_id FROM activity_log WHERE id = ?1)",
+                params![id],
+            )?;
+            activity += tx.execute("DELETE FROM activity_log WHERE id = ?1", params![id])?;
+        }
+        let mut index = 0;
+        for path in index_paths {
+            index += tx.execute("DELETE FROM file_index WHERE path = ?1", params![path])?;
+        }
+        tx.commit()?;
+        Ok((activity, index))
+    }
+
+    // ------------------------------------------------------------
+    // Maintenance
+    // ------------------------------------------------------------
+
+    /// Fold the WAL file back into the main database and truncate it
+    pub fn checkpoint(&self) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Write a consistent copy of the whole database to `path`, which must not exist yet
+    ///
+    /// Uses VACUUM INTO, so changes still in the WAL are included and the copy is compact.
+    pub fn copy_to(&self, path: &Path) -> Result<(), DbError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Run SQLite's integrity check on a database file that isn't open, read-only
+    pub fn check_file_integrity(path: &Path) -> Result<Vec<String>, DbError> {
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        integrity_check(&conn)
+    }
+
+    /// Run SQLite's integrity check without changing anything (["ok"] when healthy)
+    pub fn check_integrity(&self) -> Result<Vec<String>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        integrity_check(&conn)
+    }
+
+    /// Check integrity, then VACUUM and checkpoint to reclaim space
+    ///
+    /// VACUUM is skipped when the integrity check fails, so a damaged file isn't rewritten.
+    pub fn run_maintenance(&self) -> Result<MaintenanceReport, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let size_before = database_size(&conn)?;
+
+        let integrity_messages = integrity_check(&conn)?;
+        let integrity_ok = integrity_messages.len() == 1 && integrity_messages[0] == "ok";
+
+        if integrity_ok {
+            conn.execute_batch("VACUUM;")?;
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        }
+
+        Ok(MaintenanceReport {
+            integrity_ok,
+            integrity_messages,
+            size_before,
+            size_after: database_size(&conn)?,
+        })
+    }
+}
+
+fn integrity_check(conn: &Connection) -> Result<Vec<String>, DbError> {
+    Ok(conn
+        .prepare("PRAGMA integrity_check")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<_, _>>()?)
+}
+
+/// Archive the oldest corrections beyond MAX_CORRECTIONS, and delete those beyond
+/// MAX_CORRECTION_HISTORY
+fn enforce_corrections_limit(conn: &Connection) -> Result<(), DbError> {
+    conn.execute(
+        "UPDATE corrections SET archived = 1 WHERE archived = 0 AND id NOT IN (
+            SELECT id FROM corrections ORDER BY created_at DESC LIMIT ?1
+        )",
+        params![MAX_CORRECTIONS],
+    )?;
+    conn.execute(
+        "DELETE FROM corrections WHERE id NOT IN (
+            SELECT id FROM corrections ORDER BY created_at DESC LIMIT ?1
+        )",
+        params![MAX_CORRECTION_HISTORY],
+    )?;
+    Ok(())
+}
+
+/// Size of the main database file in bytes (page_count * page_size)
+fn database_size(conn: &Connection) -> Result<i64, DbError> {
+    let pages: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok(pages * page_size)
+}
+
+// ============================================================
+// ASYNC WORKER
+// ============================================================
+
+type DbJob = Box<dyn FnOnce(&Database) + Send>;
+
+/// Handle to the dedicated database thread
+///
+/// Jobs run one at a time, in submission order, on a thread that owns the database.
+/// Async commands await their result, so a slow query or limit-enforcement delete
+/// never blocks the thread that invoked the command. The worker also checkpoints
+/// the WAL every `CHECKPOINT_INTERVAL` so it doesn't grow unbounded.
+#[derive(Clone)]
+pub struct DbWorker {
+    sender: Sender<DbJob>,
+}
+
+impl DbWorker {
+    /// Spawn the worker thread for a database
+    pub fn spawn(db: Arc<Database>) -> Result<Self, DbError> {
+        let (sender, receiver) = channel::<DbJob>();
+
+        thread::Builder::new()
+            .name("db-worker".to_string())
+            .spawn(move || {
+                let mut last_checkpoint = Instant::now();
+                loop {
+                    match receiver.recv_timeout(CHECKPOINT_INTERVAL) {
+                        Ok(job) => job(&db),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+                        if let Err(e) = db.checkpoint() {
+                            eprintln!("[DB] WAL checkpoint failed: {}", e);
+                        }
+                        last_checkpoint = Instant::now();
+                    }
+                }
+                println!("[DB] Worker thread exiting");
+            })
+            .map_err(|e| DbError::InitFailed(format!("Failed to spawn database worker: {}", e)))?;
+
+        Ok(DbWorker { sender })
+    }
+
+    /// Run a query on the worker thread and await its result
+    pub async fn call<R, F>(&self, f: F) -> Result<R, DbError>
+    where
+        F: FnOnce(&Database) -> Result<R, DbError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        self.sender
+            .send(Box::new(move |db| {
+                let _ = tx.send(f(db));
+            }))
+            .map_err(|_| DbError::QueryFailed("Database worker has stopped".to_string()))?;
+
+        rx.await
+            .map_err(|_| DbError::QueryFailed("Database worker dropped the request".to_string()))?
+    }
+
+    /// Run a query on the worker thread and wait for its result, from a thread that may
+    /// block (file work in `spawn_blocking` that records as it goes)
+    pub fn call_blocking<R, F>(&self, f: F) -> Result<R, DbError>
+    where
+        F: FnOnce(&Database) -> Result<R, DbError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = channel();
+
+        self.sender
+            .send(Box::new(move |db| {
+                let _ = tx.send(f(db));
+            }))
+            .map_err(|_| DbError::QueryFailed("Database worker has stopped".to_string()))?;
+
+        rx.recv()
+            .map_err(|_| DbError::QueryFailed("Database worker dropped the request".to_string()))?
+    }
+
+    /// Queue a write without waiting for it (for synchronous callers)
+    pub fn submit<F>(&self, f: F)
+    where
+        F: FnOnce(&Database) + Send + 'static,
+    {
+        if self.sender.send(Box::new(f)).is_err() {
+            eprintln!("[DB] Worker has stopped, dropping queued write");
+        }
+    }
 }
 
 // ============================================================
@@ -477,6 +2488,7 @@ mod tests {
             ai_suggested: "Math".to_string(),
             user_chose: "Physics".to_string(),
             correction_type: "corrected".to_string(),
+            confidence: Some(0.8),
             created_at: 1234567890,
         };
 
@@ -488,6 +2500,19 @@ mod tests {
         assert_eq!(corrections[0].filename, "test.pdf");
         assert_eq!(corrections[0].ai_suggested, "Math");
         assert_eq!(corrections[0].user_chose, "Physics");
+        assert_eq!(corrections[0].confidence, Some(0.8));
+    }
+
+    #[test]
+    fn test_save_prompt_keeps_latest() {
+        let db = temp_db();
+        db.save_prompt("ps1.pdf", "first", 1).unwrap();
+        db.save_prompt("ps1.pdf", "second", 2).unwrap();
+        db.save_prompt("notes.pdf", "other", 3).unwrap();
+
+        let prompts = db.get_prompts().unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts["ps1.pdf"], "second");
     }
 
     #[test]
@@ -502,6 +2527,7 @@ mod tests {
                 ai_suggested: "Folder".to_string(),
                 user_chose: "Folder".to_string(),
                 correction_type: "accepted".to_string(),
+                confidence: None,
                 created_at: i as i64,
             };
             db.add_correction(correction).unwrap();
@@ -512,6 +2538,11 @@ mod tests {
 
         // Newest should be kept (highest created_at)
         assert_eq!(corrections[0].filename, "file59.pdf");
+
+        // The older ones are archived for the fine-tuning export, not deleted
+        let history = db.get_correction_history().unwrap();
+        assert_eq!(history.len(), 60);
+        assert_eq!(history[59].filename, "file0.pdf");
     }
 
     #[test]
@@ -524,6 +2555,7 @@ mod tests {
             ai_suggested: "Math".to_string(),
             user_chose: "Math".to_string(),
             correction_type: "accepted".to_string(),
+            confidence: None,
             created_at: 1234567890,
         };
         db.add_correction(correction).unwrap();
@@ -541,48 +2573,172 @@ mod tests {
             id: None,
             filename: "notes.pdf".to_string(),
             original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "Downloads".to_string(),
+            to_folder: "Math".to_string(),
+            undone: false,
+            created_at: 1234567890,
+        };
+
+        let id = db.add_activity(entry.clone()).unwrap();
+        assert!(id > 0);
+
+        let entries = db.get_activity_log().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename, "notes.pdf");
+        assert!(!entries[0].undone);
+    }
+
+    #[test]
+    fn test_record_handled_file() {
+        let db = temp_db();
+        let file = FileEntry {
+            name: "notes.pdf".to_string(),
+            path: "/Downloads/notes.pdf".to_string(),
+            size: 10,
+            modified: 1,
+        };
+        db.index_file("/Downloads", &file).unwrap();
+        let detected_at = db.get_file_index("/Downloads").unwrap()["/Downloads/notes.pdf"].updated_at;
+
+        let waited = db.record_handled_file(Some("/Downloads/notes.pdf"), detected_at + 5_000).unwrap();
+        assert_eq!(waited, Some(5_000));
+        // The detection is used up; a move with no known arrival still counts
+        assert_eq!(db.record_handled_file(Some("/Downloads/notes.pdf"), detected_at + 6_000).unwrap(), None);
+
+        let handled = db.get_handled_files(0).unwrap();
+        assert_eq!(handled.len(), 2);
+        assert_eq!(handled[0].detected_at, Some(detected_at));
+        assert_eq!(db.get_handled_files(detected_at + 5_500).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_activity_content_hash_roundtrip() {
+        let db = temp_db();
+
+        let id = db
+            .add_activity(ActivityEntry {
+                id: None,
+                filename: "notes.pdf".to_string(),
+                original_filename: None,
+                source_path: None,
+                session_id: None,
+                content_hash: Some("abc123".to_string()),
+                content_size: Some(2048),
+                confidence: None,
+                from_folder: "Downloads".to_string(),
+                to_folder: "Math".to_string(),
+                undone: false,
+                created_at: 1234567890,
+            })
+            .unwrap();
+
+        let entry = db.get_activity(id).unwrap().unwrap();
+        assert_eq!(entry.content_hash.as_deref(), Some("abc123"));
+        assert_eq!(entry.content_size, Some(2048));
+    }
+
+    #[test]
+    fn test_mark_activity_undone() {
+        let db = temp_db();
+
+        let entry = ActivityEntry {
+            id: None,
+            filename: "notes.pdf".to_string(),
+            original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
             from_folder: "Downloads".to_string(),
             to_folder: "Math".to_string(),
             undone: false,
             created_at: 1234567890,
         };
+        let id = db.add_activity(entry).unwrap();
+
+        let updated = db.mark_activity_undone(id).unwrap();
+        assert!(updated);
+
+        let entries = db.get_activity_log().unwrap();
+        assert!(entries[0].undone);
+    }
+
+    #[test]
+    fn test_mark_activity_undone_not_found() {
+        let db = temp_db();
+        let updated = db.mark_activity_undone(9999999999).unwrap();
+        assert!(!updated);
+    }
+
+    #[test]
+    fn test_mark_activity_undone_same_timestamp() {
+        let db = temp_db();
+
+        // Two moves landing in the same millisecond must stay independent
+        let mut ids = Vec::new();
+        for name in ["a.pdf", "b.pdf"] {
+            let entry = ActivityEntry {
+                id: None,
+                filename: name.to_string(),
+                original_filename: None,
+                source_path: Some(format!("C:\\Downloads\\{}", name)),
+                session_id: None,
+                content_hash: None,
+                content_size: None,
+                confidence: None,
+                from_folder: "Downloads".to_string(),
+                to_folder: "Math".to_string(),
+                undone: false,
+                created_at: 1234567890,
+            };
+            ids.push(db.add_activity(entry).unwrap());
+        }
 
-        let id = db.add_activity(entry.clone()).unwrap();
-        assert!(id > 0);
+        assert!(db.mark_activity_undone(ids[0]).unwrap());
 
-        let entries = db.get_activity_log().unwrap();
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].filename, "notes.pdf");
-        assert!(!entries[0].undone);
+        let first = db.get_activity(ids[0]).unwrap().unwrap();
+        let second = db.get_activity(ids[1]).unwrap().unwrap();
+        assert!(first.undone);
+        assert!(!second.undone);
+        assert_eq!(first.source_path.as_deref(), Some("C:\\Downloads\\a.pdf"));
     }
 
     #[test]
-    fn test_mark_activity_undone() {
+    fn test_find_activity_at() {
         let db = temp_db();
-
-        let entry = ActivityEntry {
+        let moved = |filename: &str, to_folder: &str, undone: bool, created_at: i64| ActivityEntry {
             id: None,
-            filename: "notes.pdf".to_string(),
+            filename: filename.to_string(),
             original_filename: None,
-            from_folder: "Downloads".to_string(),
-            to_folder: "Math".to_string(),
-            undone: false,
-            created_at: 1234567890,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "/Downloads".to_string(),
+            to_folder: to_folder.to_string(),
+            undone,
+            created_at,
         };
-        db.add_activity(entry).unwrap();
-
-        let updated = db.mark_activity_undone(1234567890).unwrap();
-        assert!(updated);
+        db.add_activity(moved("ps1.pdf", "/Uni/ML/", false, 1)).unwrap();
+        let newest = db.add_activity(moved("ps1.pdf", "/Uni/ML", false, 2)).unwrap();
+        db.add_activity(moved("ps1.pdf", "/Uni/ML", true, 3)).unwrap();
+        db.add_activity(moved("ps1.pdf", "/Uni/Econ", false, 4)).unwrap();
 
-        let entries = db.get_activity_log().unwrap();
-        assert!(entries[0].undone);
+        assert_eq!(db.find_activity_at("/Uni/ML/ps1.pdf").unwrap().and_then(|e| e.id), Some(newest));
+        assert!(db.find_activity_at("/Uni/ML/ps2.pdf").unwrap().is_none());
     }
 
     #[test]
-    fn test_mark_activity_undone_not_found() {
+    fn test_get_activity_not_found() {
         let db = temp_db();
-        let updated = db.mark_activity_undone(9999999999).unwrap();
-        assert!(!updated);
+        assert!(db.get_activity(42).unwrap().is_none());
     }
 
     #[test]
@@ -595,6 +2751,11 @@ mod tests {
                 id: None,
                 filename: format!("file{}.pdf", i),
                 original_filename: None,
+                source_path: None,
+                session_id: None,
+                content_hash: None,
+                content_size: None,
+                confidence: None,
                 from_folder: "Downloads".to_string(),
                 to_folder: "Folder".to_string(),
                 undone: false,
@@ -608,6 +2769,11 @@ mod tests {
 
         // Newest should be kept
         assert_eq!(entries[0].filename, "file109.pdf");
+
+        // The older ones are archived for exports, not deleted
+        let history = db.get_activity_history().unwrap();
+        assert_eq!(history.len(), 110);
+        assert_eq!(history[109].filename, "file0.pdf");
     }
 
     #[test]
@@ -621,6 +2787,7 @@ mod tests {
                 ai_suggested: "Math".to_string(),
                 user_chose: "Math".to_string(),
                 correction_type: "accepted".to_string(),
+                confidence: None,
                 created_at: 1000,
             },
             Correction {
@@ -629,6 +2796,7 @@ mod tests {
                 ai_suggested: "Physics".to_string(),
                 user_chose: "Chemistry".to_string(),
                 correction_type: "corrected".to_string(),
+                confidence: None,
                 created_at: 2000,
             },
         ];
@@ -640,6 +2808,65 @@ mod tests {
         assert_eq!(result.len(), 2);
     }
 
+    fn sample_correction(filename: &str, created_at: i64) -> Correction {
+        Correction {
+            id: None,
+            filename: filename.to_string(),
+            ai_suggested: "Math".to_string(),
+            user_chose: "Physics".to_string(),
+            correction_type: "corrected".to_string(),
+            confidence: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_reimport_corrections_skips_duplicates() {
+        let db = temp_db();
+        let corrections = vec![sample_correction("a.pdf", 1000), sample_correction("b.pdf", 2000)];
+
+        assert_eq!(db.import_corrections(corrections.clone()).unwrap(), 2);
+        assert_eq!(db.import_corrections(corrections).unwrap(), 0);
+        assert_eq!(db.get_corrections().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_add_duplicate_correction_returns_existing_id() {
+        let db = temp_db();
+        let id1 = db.add_correction(sample_correction("a.pdf", 1000)).unwrap();
+        let id2 = db.add_correction(sample_correction("a.pdf", 1000)).unwrap();
+        assert_eq!(id1, id2);
+
+        // Same file corrected again later is a separate correction
+        let id3 = db.add_correction(sample_correction("a.pdf", 5000)).unwrap();
+        assert_ne!(id1, id3);
+        assert_eq!(db.get_corrections().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_migration_removes_existing_duplicate_corrections() {
+        let db = temp_db();
+        {
+            // Simulate a database from before the uniqueness key existed
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch("DROP INDEX idx_corrections_unique;").unwrap();
+            for _ in 0..3 {
+                conn.execute(
+                    "INSERT INTO corrections (filename, ai_suggested, user_chose, correction_type, created_at)
+                     VALUES ('a.pdf', 'Math', 'Physics', 'corrected', 1000)",
+                    [],
+                )
+                .unwrap();
+            }
+        }
+
+        db.run_migrations().unwrap();
+
+        let corrections = db.get_corrections().unwrap();
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(db.add_correction(sample_correction("a.pdf", 1000)).unwrap(), corrections[0].id.unwrap());
+    }
+
     #[test]
     fn test_import_activity_log() {
         let db = temp_db();
@@ -649,6 +2876,11 @@ mod tests {
                 id: None,
                 filename: "a.pdf".to_string(),
                 original_filename: None,
+                source_path: None,
+                session_id: None,
+                content_hash: None,
+                content_size: None,
+                confidence: None,
                 from_folder: "Downloads".to_string(),
                 to_folder: "Math".to_string(),
                 undone: false,
@@ -658,6 +2890,11 @@ mod tests {
                 id: None,
                 filename: "b.pdf".to_string(),
                 original_filename: None,
+                source_path: None,
+                session_id: None,
+                content_hash: None,
+                content_size: None,
+                confidence: None,
                 from_folder: "Downloads".to_string(),
                 to_folder: "Physics".to_string(),
                 undone: true,
@@ -673,12 +2910,251 @@ mod tests {
         assert!(result[0].undone || result[1].undone); // One should be undone
     }
 
+    #[test]
+    fn test_sessions_group_activity() {
+        let db = temp_db();
+
+        let session = db.create_session("Batch organize").unwrap();
+        for i in 0..3 {
+            db.add_activity(ActivityEntry {
+                id: None,
+                filename: format!("file{}.pdf", i),
+                original_filename: None,
+                source_path: None,
+                session_id: Some(session),
+                content_hash: None,
+                content_size: None,
+                confidence: None,
+                from_folder: "Downloads".to_string(),
+                to_folder: "Math".to_string(),
+                undone: false,
+                created_at: i,
+            })
+            .unwrap();
+        }
+        // An unrelated single move outside the session
+        db.add_activity(ActivityEntry {
+            id: None,
+            filename: "solo.pdf".to_string(),
+            original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "Downloads".to_string(),
+            to_folder: "Math".to_string(),
+            undone: false,
+            created_at: 10,
+        })
+        .unwrap();
+
+        assert!(db.end_session(session).unwrap());
+        assert!(!db.end_session(session).unwrap()); // already ended
+
+        let sessions = db.get_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].file_count, 3);
+        assert!(sessions[0].ended_at.is_some());
+
+        let entries = db.get_session_activity(session).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].filename, "file2.pdf");
+    }
+
+    #[test]
+    fn test_session_items_track_progress() {
+        let db = temp_db();
+        let session = db.create_session("Apply plan (3 files)").unwrap();
+        let items: Vec<(String, serde_json::Value)> = ["a.pdf", "b.pdf", "c.pdf"]
+            .iter()
+            .map(|name| (format!("C:\\Downloads\\{}", name), serde_json::json!({ "filename": name })))
+            .collect();
+        let ids = db.add_session_items(session, &items, "rename").unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(db.get_sessions().unwrap()[0].pending_items, 3);
+
+        assert!(db.set_session_item_status(ids[0], ITEM_DONE, None).unwrap());
+        assert!(db.set_session_item_status(ids[1], ITEM_FAILED, Some("File not found")).unwrap());
+        assert!(!db.set_session_item_status(9999, ITEM_DONE, None).unwrap());
+
+        let stored = db.get_session_items(session).unwrap();
+        assert_eq!(stored.iter().map(|i| i.status.as_str()).collect::<Vec<_>>(), vec![ITEM_DONE, ITEM_FAILED, ITEM_PENDING]);
+        assert_eq!(stored[1].error.as_deref(), Some("File not found"));
+        assert_eq!(stored[2].planned["filename"], "c.pdf");
+        assert_eq!(stored[2].policy, "rename");
+        assert_eq!(db.get_sessions().unwrap()[0].pending_items, 1);
+    }
+
+    #[test]
+    fn test_deferred_items_across_sessions() {
+        let db = temp_db();
+        let mut deferred = Vec::new();
+        for label in ["Organize Downloads", "Organize Desktop"] {
+            let session = db.create_session(label).unwrap();
+            let item = (format!("/{}/ps1.pdf", label), serde_json::json!({ "dest_folder": "E:\\Uni\\ML" }));
+            let ids = db.add_session_items(session, &[item.clone(), item], "rename").unwrap();
+            assert!(db.set_session_item_status(ids[0], ITEM_DEFERRED, None).unwrap());
+            deferred.push(ids[0]);
+        }
+
+        let items = db.get_deferred_items().unwrap();
+        assert_eq!(items.iter().map(|i| i.id).collect::<Vec<_>>(), deferred);
+        assert_eq!(items[1].planned["dest_folder"], "E:\\Uni\\ML");
+        // Parked moves don't make a session look interrupted
+        assert!(db.get_sessions().unwrap().iter().all(|s| s.pending_items == 1));
+    }
+
+    #[test]
+    fn test_mark_session_undone() {
+        let db = temp_db();
+
+        let session = db.create_session("Batch organize").unwrap();
+        db.add_activity(ActivityEntry {
+            id: None,
+            filename: "a.pdf".to_string(),
+            original_filename: None,
+            source_path: None,
+            session_id: Some(session),
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "Downloads".to_string(),
+            to_folder: "Math".to_string(),
+            undone: false,
+            created_at: 1,
+        })
+        .unwrap();
+
+        assert!(db.mark_session_undone(session).unwrap());
+        assert!(db.get_sessions().unwrap()[0].undone);
+        assert!(db.get_session_activity(session).unwrap()[0].undone);
+    }
+
+    fn operation(op_type: &str, before: &str, after: Option<&str>, created_at: i64) -> Operation {
+        Operation {
+            id: None,
+            op_type: op_type.to_string(),
+            before_path: before.to_string(),
+            after_path: after.map(|a| a.to_string()),
+            status: "done".to_string(),
+            created_at,
+            updated_at: created_at,
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_add_and_get_operation() {
+        let db = temp_db();
+
+        let id = db
+            .add_operation(operation("move", "C:\\Downloads\\a.pdf", Some("C:\\ML\\a.pdf"), 1000))
+            .unwrap();
+
+        let op = db.get_operation(id).unwrap().unwrap();
+        assert_eq!(op.op_type, "move");
+        assert_eq!(op.after_path.as_deref(), Some("C:\\ML\\a.pdf"));
+        assert_eq!(op.status, "done");
+        assert!(db.get_operation(id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_last_operation_undo_and_redo_stacks() {
+        let db = temp_db();
+
+        let first = db.add_operation(operation("move", "a", Some("b"), 1000)).unwrap();
+        let second = db.add_operation(operation("rename", "c", Some("d"), 2000)).unwrap();
+
+        // Undo stack top is the newest "done" operation
+        assert_eq!(db.last_operation("done").unwrap().unwrap().id, Some(second));
+        assert!(db.last_operation("undone").unwrap().is_none());
+
+        db.set_operation_status(second, "undone").unwrap();
+        assert_eq!(db.last_operation("done").unwrap().unwrap().id, Some(first));
+        assert_eq!(db.last_operation("undone").unwrap().unwrap().id, Some(second));
+    }
+
+    #[test]
+    fn test_last_operation_skips_trash() {
+        let db = temp_db();
+
+        let moved = db.add_operation(operation("move", "a", Some("b"), 1000)).unwrap();
+        db.add_operation(operation("trash", "c", None, 2000)).unwrap();
+
+        // Trashing can't be undone here, so it doesn't hide the move before it
+        assert_eq!(db.last_operation("done").unwrap().unwrap().id, Some(moved));
+    }
+
+    #[test]
+    fn test_new_operation_discards_redo_stack() {
+        let db = temp_db();
+
+        let first = db.add_operation(operation("move", "a", Some("b"), 1000)).unwrap();
+        db.set_operation_status(first, "undone").unwrap();
+
+        db.add_operation(operation("trash", "c", None, 2000)).unwrap();
+
+        assert!(db.last_operation("undone").unwrap().is_none());
+        assert_eq!(db.get_operation(first).unwrap().unwrap().status, "discarded");
+    }
+
+    #[test]
+    fn test_get_operations_newest_first() {
+        let db = temp_db();
+
+        db.add_operation(operation("move", "a", Some("b"), 1000)).unwrap();
+        db.add_operation(operation("rename", "c", Some("d"), 2000)).unwrap();
+
+        let ops = db.get_operations(10).unwrap();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].op_type, "rename");
+        assert_eq!(db.get_operations(1).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_worker_call_returns_result() {
+        let worker = DbWorker::spawn(Arc::new(temp_db())).unwrap();
+
+        let id = worker
+            .call(|db| db.add_rule("*.pdf", "C:\\PDFs", None))
+            .await
+            .unwrap();
+        assert!(id > 0);
+
+        let rules = worker.call(|db| db.get_rules()).await.unwrap();
+        assert_eq!(rules.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_jobs_in_order() {
+        let worker = DbWorker::spawn(Arc::new(temp_db())).unwrap();
+
+        // A fire-and-forget write is visible to the next awaited query
+        worker.submit(|db| {
+            db.add_rule("Lecture*", "C:\\Lectures", None).unwrap();
+        });
+        let rules = worker.call(|db| db.get_rules()).await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].pattern, "Lecture*");
+    }
+
+    #[tokio::test]
+    async fn test_worker_propagates_errors() {
+        let worker = DbWorker::spawn(Arc::new(temp_db())).unwrap();
+
+        let result: Result<(), DbError> = worker
+            .call(|_| Err(DbError::QueryFailed("boom".to_string())))
+            .await;
+        assert!(matches!(result, Err(DbError::QueryFailed(_))));
+    }
+
     #[test]
     fn test_add_and_get_rules() {
         let db = temp_db();
 
-        let id1 = db.add_rule("*_ML_*", "C:\\Courses\\ML").unwrap();
-        let id2 = db.add_rule("Lecture*", "C:\\Courses\\Lectures").unwrap();
+        let id1 = db.add_rule("*_ML_*", "C:\\Courses\\ML", None).unwrap();
+        let id2 = db.add_rule("Lecture*", "C:\\Courses\\Lectures", None).unwrap();
         assert!(id1 > 0);
         assert!(id2 > 0);
 
@@ -692,16 +3168,481 @@ mod tests {
     fn test_delete_rule() {
         let db = temp_db();
 
-        let id = db.add_rule("*.pdf", "C:\\PDFs").unwrap();
+        let id = db.add_rule("*.pdf", "C:\\PDFs", None).unwrap();
         assert!(db.delete_rule(id).unwrap());
 
         let rules = db.get_rules().unwrap();
         assert!(rules.is_empty());
     }
 
+    #[test]
+    fn test_watch_profiles_and_profile_rules() {
+        let db = temp_db();
+
+        let mut profile = WatchProfile {
+            id: None,
+            name: "Screenshots".to_string(),
+            watch_path: "C:\\Screenshots".to_string(),
+            folders: vec!["C:\\Notes\\Maths".to_string(), "C:\\Notes\\Physics".to_string()],
+            mode: "ocr".to_string(),
+            min_confidence: 0.8,
+            created_at: 0,
+            excluded: vec!["node_modules".to_string()],
+        };
+        let id = db.save_watch_profile(&profile).unwrap();
+
+        let profiles = db.get_watch_profiles().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].folders.len(), 2);
+        assert_eq!(profiles[0].excluded, vec!["node_modules"]);
+        assert_eq!(profiles[0].mode, "ocr");
+
+        profile.id = Some(id);
+        profile.min_confidence = 0.95;
+        assert_eq!(db.save_watch_profile(&profile).unwrap(), id);
+        assert_eq!(db.get_watch_profiles().unwrap()[0].min_confidence, 0.95);
+
+        // Two profiles can't watch the same folder
+        assert!(db.save_watch_profile(&WatchProfile { id: None, ..profile.clone() }).is_err());
+
+        // Profile rules come before global ones, and only for their own profile
+        db.add_rule("*.png", "C:\\Images", None).unwrap();
+        db.add_rule("*.png", "C:\\Notes\\Maths", Some(id)).unwrap();
+        let rules = db.get_rules_for_profile(Some(id)).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].profile_id, Some(id));
+        assert_eq!(db.get_rules_for_profile(None).unwrap().len(), 1);
+
+        assert!(db.delete_watch_profile(id).unwrap());
+        assert!(db.get_watch_profiles().unwrap().is_empty());
+        assert_eq!(db.get_rules().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_save_setup_is_all_or_nothing() {
+        let db = temp_db();
+        let profile = WatchProfile {
+            id: None,
+            name: "Downloads".to_string(),
+            watch_path: "C:\\Downloads".to_string(),
+            folders: vec!["C:\\Uni\\ML".to_string()],
+            mode: "ai".to_string(),
+            min_confidence: 0.8,
+            created_at: 0,
+            excluded: Vec::new(),
+        };
+        let settings = vec![("privacy_mode".to_string(), "true".to_string())];
+        let rules = vec![ProfileRule {
+            pattern: "*lecture*".to_string(),
+            target_folder: "C:\\Uni\\ML".to_string(),
+            watch_path: Some("C:\\Downloads".to_string()),
+        }];
+
+        // The second profile watches the same folder, so nothing before it is kept either
+        let clash = vec![profile.clone(), profile.clone()];
+        assert!(db.save_setup(&settings, &clash, &rules, &[]).is_err());
+        assert_eq!(db.get_setting("privacy_mode").unwrap(), None);
+        assert!(db.get_watch_profiles().unwrap().is_empty());
+        assert!(db.get_rules().unwrap().is_empty());
+
+        db.save_setup(&settings, &[profile], &rules, &[]).unwrap();
+        assert_eq!(db.get_setting("privacy_mode").unwrap().as_deref(), Some("true"));
+        let profile_id = db.get_watch_profiles().unwrap()[0].id;
+        assert_eq!(db.get_rules().unwrap()[0].profile_id, profile_id);
+    }
+
     #[test]
     fn test_delete_nonexistent_rule() {
         let db = temp_db();
         assert!(!db.delete_rule(999).unwrap());
     }
+
+    #[test]
+    fn test_busy_timeout_is_set() {
+        let db = temp_db();
+        let conn = db.conn.lock().unwrap();
+        let timeout: i64 = conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0)).unwrap();
+        assert_eq!(timeout, BUSY_TIMEOUT.as_millis() as i64);
+    }
+
+    #[test]
+    fn test_api_spend_since() {
+        let db = temp_db();
+        let usage = |cost_usd, created_at| ApiUsage {
+            model: "gpt-4o-mini".to_string(),
+            prompt_tokens: 1_000,
+            completion_tokens: 100,
+            cost_usd,
+            created_at,
+            repaired: None,
+        };
+        assert_eq!(db.get_api_spend_since(0).unwrap(), 0.0);
+        db.add_api_usage(&usage(0.25, 1_000)).unwrap();
+        db.add_api_usage(&usage(0.5, 2_000)).unwrap();
+        db.add_api_usage(&usage(1.0, 3_000)).unwrap();
+        assert!((db.get_api_spend_since(2_000).unwrap() - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_model_reliability_counts_repairs() {
+        let db = temp_db();
+        let usage = |model: &str, repaired| ApiUsage {
+            model: model.to_string(),
+            prompt_tokens: 100,
+            completion_tokens: 10,
+            cost_usd: 0.0,
+            created_at: 1_000,
+            repaired,
+        };
+        for _ in 0..3 {
+            db.add_api_usage(&usage("gpt-4o-mini", None)).unwrap();
+        }
+        db.add_api_usage(&usage("llama3.2", None)).unwrap();
+        db.add_api_usage(&usage("llama3.2", Some(true))).unwrap();
+        db.add_api_usage(&usage("llama3.2", Some(false))).unwrap();
+
+        let stats = db.get_model_reliability().unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0], ModelReliability { model: "llama3.2".to_string(), calls: 1, repairs: 2, repaired: 1 });
+        assert_eq!(stats[1], ModelReliability { model: "gpt-4o-mini".to_string(), calls: 3, repairs: 0, repaired: 0 });
+    }
+
+    #[test]
+    fn test_synced_database_keeps_one_file() {
+        let path = temp_dir().join(format!("test_db_synced_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = Database::open(path.clone(), StorageMode::Synced).unwrap();
+        db.set_setting("privacy_mode", "true").unwrap();
+        {
+            let conn = db.conn.lock().unwrap();
+            let journal: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+            assert_eq!(journal, "delete");
+        }
+        assert!(!temp_dir().join(format!("test_db_synced_{}.db-wal", std::process::id())).exists());
+        assert_eq!(Database::check_file_integrity(&path).unwrap(), vec!["ok"]);
+
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_maintenance_reclaims_space() {
+        let db = temp_db();
+        for i in 0..MAX_ACTIVITY_LOG + 50 {
+            db.add_activity(ActivityEntry {
+                id: None,
+                filename: format!("{}_{}.pdf", "x".repeat(200), i),
+                original_filename: None,
+                source_path: None,
+                session_id: None,
+                content_hash: None,
+                content_size: None,
+                confidence: None,
+                from_folder: "Downloads".to_string(),
+                to_folder: "Docs".to_string(),
+                undone: false,
+                created_at: i as i64,
+            })
+            .unwrap();
+        }
+        db.clear_activity_log().unwrap();
+
+        let report = db.run_maintenance().unwrap();
+        assert!(report.integrity_ok);
+        assert_eq!(report.integrity_messages, vec!["ok".to_string()]);
+        assert!(report.size_after < report.size_before);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let db = temp_db();
+        db.add_rule("*.pdf", "Docs", None).unwrap();
+        db.checkpoint().unwrap();
+        assert_eq!(db.get_rules().unwrap().len(), 1);
+    }
+
+    fn pref(folder: &str, never_suggest: bool) -> FolderPref {
+        FolderPref {
+            folder: folder.to_string(),
+            never_suggest,
+            require_confirm: false,
+            naming_template: None,
+            description: None,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_folder_prefs_upsert() {
+        let db = temp_db();
+        db.set_folder_pref(pref("C:\\Year2\\Archive", true)).unwrap();
+
+        let mut updated = pref("C:\\Year2\\Archive", false);
+        updated.require_confirm = true;
+        updated.naming_template = Some("{date}_{name}".to_string());
+        db.set_folder_pref(updated).unwrap();
+
+        let prefs = db.get_folder_prefs().unwrap();
+        assert_eq!(prefs.len(), 1);
+        assert!(!prefs[0].never_suggest);
+        assert!(prefs[0].require_confirm);
+        assert_eq!(prefs[0].naming_template.as_deref(), Some("{date}_{name}"));
+        assert!(prefs[0].updated_at > 0);
+    }
+
+    #[test]
+    fn test_set_folder_description_keeps_prefs() {
+        let db = temp_db();
+        db.set_folder_pref(pref("C:\\Year2\\ML", true)).unwrap();
+        db.set_folder_description("C:\\Year2\\ML", Some("COMP4702 Machine Learning")).unwrap();
+        db.set_folder_description("C:\\Year2\\Macro", Some("ECON202 Intermediate Macroeconomics")).unwrap();
+
+        let prefs = db.get_folder_prefs().unwrap();
+        assert_eq!(prefs.len(), 2);
+        assert_eq!(prefs[0].folder, "C:\\Year2\\ML");
+        assert!(prefs[0].never_suggest);
+        assert_eq!(prefs[0].description.as_deref(), Some("COMP4702 Machine Learning"));
+        assert!(!prefs[1].never_suggest);
+    }
+
+    #[test]
+    fn test_delete_folder_pref() {
+        let db = temp_db();
+        db.set_folder_pref(pref("C:\\Year2\\ML", true)).unwrap();
+        db.set_folder_pref(pref("C:\\Year2\\Physics", true)).unwrap();
+
+        assert!(db.delete_folder_pref("C:\\Year2\\ML").unwrap());
+        assert!(!db.delete_folder_pref("C:\\Year2\\ML").unwrap());
+
+        let prefs = db.get_folder_prefs().unwrap();
+        assert_eq!(prefs.len(), 1);
+        assert_eq!(prefs[0].folder, "C:\\Year2\\Physics");
+    }
+
+    #[test]
+    fn test_remap_paths() {
+        let db = temp_db();
+        let moved = |from_folder: &str, to_folder: &str| ActivityEntry {
+            id: None,
+            filename: "ps3.pdf".to_string(),
+            original_filename: None,
+            source_path: Some(format!("{}/ps3.pdf", from_folder)),
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: from_folder.to_string(),
+            to_folder: to_folder.to_string(),
+            undone: false,
+            created_at: 1,
+        };
+        db.add_activity(moved("/Downloads", "/Uni/ML/Week 3")).unwrap();
+        db.add_activity(moved("/Downloads", "/Uni/ML2")).unwrap(); // not inside /Uni/ML
+        db.add_rule("*comp4702*", "/Uni/ML", None).unwrap();
+        let pref = |folder: &str, template: &str| FolderPref {
+            folder: folder.to_string(),
+            never_suggest: false,
+            require_confirm: false,
+            naming_template: Some(template.to_string()),
+            description: None,
+            updated_at: 1,
+        };
+        db.set_folder_pref(pref("/Uni/ML", "{date}_{name}")).unwrap();
+        db.set_folder_pref(pref("/Uni/2024/ML/Week 3", "new")).unwrap();
+        db.set_folder_pref(pref("/Uni/ML/Week 3", "old")).unwrap(); // clashes once remapped
+        let file = FileEntry { name: "a.pdf".to_string(), path: "/Uni/ML/a.pdf".to_string(), size: 1, modified: 1 };
+        db.index_file("/Uni/ML", &file).unwrap();
+
+        let report = db.remap_paths("/Uni/ML", "/Uni/2024/ML").unwrap();
+        assert_eq!(report, RemapReport { activity: 1, rules: 1, folder_prefs: 1, file_index: 1 });
+
+        let to_folders: Vec<String> = db.get_activity_log().unwrap().into_iter().map(|e| e.to_folder).collect();
+        assert!(to_folders.contains(&"/Uni/2024/ML/Week 3".to_string()) && to_folders.contains(&"/Uni/ML2".to_string()));
+        assert_eq!(db.get_rules().unwrap()[0].target_folder, "/Uni/2024/ML");
+        let prefs = db.get_folder_prefs().unwrap();
+        assert_eq!(prefs.len(), 2);
+        let week3 = prefs.iter().find(|p| p.folder == "/Uni/2024/ML/Week 3").unwrap();
+        assert_eq!(week3.naming_template.as_deref(), Some("new"), "the pref already at the new path is kept");
+        assert!(db.get_file_index("/Uni/ML").unwrap().is_empty());
+        assert!(db.get_file_index("/Uni/2024/ML").unwrap().contains_key("/Uni/2024/ML/a.pdf"));
+
+        assert!(db.remap_paths("/Uni", "/Uni/2024").is_err());
+    }
+
+    #[test]
+    fn test_purge_orphans() {
+        let db = temp_db();
+        let entry = |filename: &str| ActivityEntry {
+            id: None,
+            filename: filename.to_string(),
+            original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "/Downloads".to_string(),
+            to_folder: "/Uni/ML".to_string(),
+            undone: false,
+            created_at: 1,
+        };
+        let gone = db.add_activity(entry("gone.pdf")).unwrap();
+        db.add_activity(entry("kept.pdf")).unwrap();
+        for name in ["gone.pdf", "kept.pdf"] {
+            let file = FileEntry { name: name.to_string(), path: format!("/Downloads/{}", name), size: 1, modified: 1 };
+            db.index_file("/Downloads", &file).unwrap();
+        }
+
+        let purged = db.purge_orphans(&[gone, 999], &["/Downloads/gone.pdf".to_string()]).unwrap();
+        assert_eq!(purged, (1, 1));
+        let left: Vec<String> = db.get_activity_log().unwrap().into_iter().map(|e| e.filename).collect();
+        assert_eq!(left, vec!["kept.pdf"]);
+        let paths: Vec<String> = db.get_indexed_paths().unwrap().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, vec!["/Downloads/kept.pdf"]);
+    }
+
+    #[test]
+    fn test_folder_aliases_replace() {
+        let db = temp_db();
+        let alias = |alias: &str, folder: &str| FolderAlias { alias: alias.to_string(), folder: folder.to_string() };
+        db.set_folder_aliases(&[alias("Operations Research", "C:\\Uni\\OR"), alias("Old", "C:\\Uni\\Old")]).unwrap();
+        db.set_folder_aliases(&[
+            alias("Operations Research", "C:\\Uni\\OR"),
+            alias("Machine Learning", "C:\\Uni\\ML"),
+            alias("machine learning", "C:\\Uni\\ML2"), // same alias: the last wins
+        ])
+        .unwrap();
+
+        assert_eq!(
+            db.get_folder_aliases().unwrap(),
+            vec![alias("machine learning", "C:\\Uni\\ML2"), alias("Operations Research", "C:\\Uni\\OR")]
+        );
+    }
+
+    #[test]
+    fn test_deleted_files_expiry() {
+        let db = temp_db();
+        let deleted = |trash_path: &str, expires_at: i64| DeletedFile {
+            id: None,
+            original_path: "C:\\Downloads\\old.pdf".to_string(),
+            trash_path: trash_path.to_string(),
+            size: 10,
+            deleted_at: expires_at - 100,
+            expires_at,
+        };
+        let old = db.add_deleted_file(deleted("T\\1\\old.pdf", 1_000)).unwrap();
+        db.add_deleted_file(deleted("T\\2\\old.pdf", 5_000)).unwrap();
+
+        assert_eq!(db.get_deleted_files(None).unwrap().len(), 2);
+        let expired = db.get_deleted_files(Some(2_000)).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, Some(old));
+
+        assert!(db.remove_deleted_file(old).unwrap());
+        assert!(db.get_deleted_files(Some(2_000)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_provenance_follows_the_file() {
+        let db = temp_db();
+        let first = db
+            .record_provenance("C:\\Downloads\\hw3.pdf", "C:\\Year2\\ML\\hw3.pdf", 1_000, Some("ML homework"))
+            .unwrap();
+        assert_eq!(first.original_path, "C:\\Downloads\\hw3.pdf");
+
+        // Moved again by hand: still from Downloads, still the AI's reasoning
+        db.record_provenance("C:\\Year2\\ML\\hw3.pdf", "C:\\Year2\\ML\\Homework\\hw3.pdf", 2_000, None)
+            .unwrap();
+        assert_eq!(db.get_provenance("C:\\Year2\\ML\\hw3.pdf").unwrap(), None);
+        let moved = db.get_provenance("C:\\Year2\\ML\\Homework\\hw3.pdf").unwrap().unwrap();
+        assert_eq!(moved.original_path, "C:\\Downloads\\hw3.pdf");
+        assert_eq!(moved.organized_at, 2_000);
+        assert_eq!(moved.reasoning.as_deref(), Some("ML homework"));
+    }
+
+    #[test]
+    fn test_pending_queue() {
+        let db = temp_db();
+        let pending = |path: &str, detected_at: i64| PendingFile {
+            path: path.to_string(),
+            name: path.rsplit('\\').next().unwrap().to_string(),
+            size: 10,
+            detected_at,
+            classification: None,
+        };
+        db.add_pending_file(&pending("C:\\Downloads\\b.pdf", 2_000)).unwrap();
+        db.add_pending_file(&pending("C:\\Downloads\\a.pdf", 1_000)).unwrap();
+
+        let classification = serde_json::json!({ "suggested_folder": "C:\\Year2\\ML", "confidence": 0.9 });
+        assert!(db.set_pending_classification("C:\\Downloads\\a.pdf", &classification).unwrap());
+        assert!(!db.set_pending_classification("C:\\Downloads\\gone.pdf", &classification).unwrap());
+
+        let files = db.get_pending_files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "C:\\Downloads\\a.pdf");
+        assert_eq!(files[0].classification, Some(classification));
+        assert_eq!(files[1].classification, None);
+
+        // Renamed files stay queued under their new name; detected again, they start over
+        assert!(db.rename_pending_file("C:\\Downloads\\a.pdf", "C:\\Downloads\\ML_a.pdf", "ML_a.pdf").unwrap());
+        db.add_pending_file(&pending("C:\\Downloads\\ML_a.pdf", 3_000)).unwrap();
+        let files = db.get_pending_files().unwrap();
+        assert_eq!(files[1].name, "ML_a.pdf");
+        assert_eq!(files[1].classification, None);
+
+        assert!(db.remove_pending_file("C:\\Downloads\\b.pdf").unwrap());
+        assert!(!db.remove_pending_file("C:\\Downloads\\b.pdf").unwrap());
+        assert_eq!(db.get_pending_files().unwrap().len(), 1);
+    }
+
+    fn quarantined(path: &str, created_at: i64) -> QuarantineItem {
+        QuarantineItem {
+            id: None,
+            path: path.to_string(),
+            filename: "scan.pdf".to_string(),
+            original_folder: "C:\\Downloads".to_string(),
+            reason: "low_confidence".to_string(),
+            confidence: 0.4,
+            suggested_folder: Some("C:\\Year2\\ML".to_string()),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_quarantine_items() {
+        let db = temp_db();
+        db.add_quarantine_item(quarantined("C:\\Review\\scan.pdf", 1)).unwrap();
+        db.add_quarantine_item(quarantined("C:\\Review\\scan_1.pdf", 2)).unwrap();
+        // Re-quarantining the same path replaces the old entry
+        db.add_quarantine_item(quarantined("C:\\Review\\scan.pdf", 3)).unwrap();
+
+        let items = db.get_quarantine_items().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].path, "C:\\Review\\scan.pdf");
+        assert_eq!(items[0].suggested_folder.as_deref(), Some("C:\\Year2\\ML"));
+
+        assert!(db.remove_quarantine_item("C:\\Review\\scan.pdf").unwrap());
+        assert!(!db.remove_quarantine_item("C:\\Review\\scan.pdf").unwrap());
+        assert_eq!(db.get_quarantine_items().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_worker_keeps_order_and_returns_errors() {
+        let worker = DbWorker::spawn(Arc::new(temp_db())).unwrap();
+
+        // Queued writes run before anything submitted after them, whichever way it waits
+        worker.submit(|db| db.set_setting("order", "first").unwrap());
+        worker.submit(|db| db.set_setting("order", "second").unwrap());
+        let value = futures::executor::block_on(worker.call(|db| db.get_setting("order"))).unwrap();
+        assert_eq!(value.as_deref(), Some("second"));
+        worker.submit(|db| db.set_setting("order", "third").unwrap());
+        assert_eq!(worker.call_blocking(|db| db.get_setting("order")).unwrap().as_deref(), Some("third"));
+
+        // A query's error comes back to the caller, and the worker carries on
+        let failed = futures::executor::block_on(worker.call(|_| Err::<(), _>(DbError::QueryFailed("boom".to_string()))));
+        assert!(matches!(failed, Err(DbError::QueryFailed(e)) if e == "boom"));
+        let failed = worker.call_blocking(|db| db.get_operation(1).and(Err::<(), _>(DbError::InitFailed("later".to_string()))));
+        assert!(matches!(failed, Err(DbError::InitFailed(e)) if e == "later"));
+        assert_eq!(worker.call_blocking(|db| db.get_setting("order")).unwrap().as_deref(), Some("third"));
+    }
 }
@@ -0,0 +1,307 @@
+//! Duplicate files and hard-link consolidation
+//!
+//! Finds files with identical contents under a folder (same size first, then the same
+//! blake3 hash from `checksum`) and can replace the extra copies with hard links to one
+//! kept file, so the same lecture PDF downloaded five times only takes up space once.
+//!
+//! Safety checks before a copy is replaced: it must be a regular file (not a symlink) on
+//! the same volume as the kept file, and its contents are hashed again right before the
+//! swap. The link is made under a temporary name next to the copy and renamed over it,
+//! so a failure at any point leaves the copy as it was.
+
+use crate::checksum;
+use crate::journal::FileConflict;
+use crate::CommandError;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Files under a folder that all have the same contents
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: u64,          // of each file
+    pub paths: Vec<String>, // sorted
+    pub reclaimable: u64,   // bytes freed by linking them all to one file
+}
+
+/// Result of replacing copies with hard links
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkReport {
+    pub linked: Vec<String>,
+    pub reclaimed: u64, // bytes
+    pub failed: Vec<FileConflict>,
+}
+
+/// Find groups of identical files under `folder` (including subfolders)
+///
+/// Hidden files, symlinks and empty files are skipped. Copies that are already hard links
+/// to each other don't count towards `reclaimable`. Largest savings first.
+pub fn find(folder: &Path) -> io::Result<Vec<DuplicateGroup>> {
+    if !folder.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Not a folder: {}", folder.display()),
+        ));
+    }
+
+    // Only files sharing a size can be duplicates, so most files are never hashed
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == folder => return Err(e),
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            // Symlinked folders are not followed to avoid cycles
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else if meta.is_file() && meta.len() > 0 {
+                by_size.entry(meta.len()).or_default().push(entry.path());
+            }
+        }
+    }
+
+    let candidates: Vec<(u64, PathBuf)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .flat_map(|(size, paths)| paths.into_iter().map(move |path| (size, path)))
+        .collect();
+    let hashed: Vec<(u64, String, PathBuf)> = candidates
+        .into_par_iter()
+        .filter_map(|(size, path)| Some((size, checksum::hash_file(&path).ok()?, path)))
+        .collect();
+
+    let mut by_hash: HashMap<(u64, String), Vec<PathBuf>> = HashMap::new();
+    for (size, hash, path) in hashed {
+        by_hash.entry((size, hash)).or_default().push(path);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|((size, hash), mut paths)| {
+            paths.sort();
+            let distinct = distinct_files(&paths);
+            DuplicateGroup {
+                hash,
+                size,
+                reclaimable: size * (distinct as u64 - 1),
+                paths: paths.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| b.reclaimable.cmp(&a.reclaimable).then_with(|| a.paths.cmp(&b.paths)));
+    Ok(groups)
+}
+
+/// Replace each of `copies` with a hard link to `keep`
+///
+/// Every copy is checked on its own; one that fails a safety check is reported in `failed`
+/// and the rest still go ahead.
+pub fn link(keep: &Path, copies: &[PathBuf]) -> Result<LinkReport, CommandError> {
+    if !fs::symlink_metadata(keep).map(|m| m.is_file()).unwrap_or(false) {
+        return Err(CommandError::FileNotFound(keep.display().to_string()));
+    }
+    let hash = checksum::hash_file(keep)?;
+
+    let mut report = LinkReport::default();
+    for copy in copies {
+        match replace_with_link(keep, copy, &hash) {
+            Ok(size) => {
+                report.linked.push(copy.to_string_lossy().to_string());
+                report.reclaimed += size;
+            }
+            Err(e) => report.failed.push(FileConflict {
+                path: copy.display().to_string(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// Link `copy` to `keep` again after an undo (journal redo)
+pub fn relink(keep: &Path, copy: &Path) -> Result<(), CommandError> {
+    replace_with_link(keep, copy, &checksum::hash_file(keep)?).map(|_| ())
+}
+
+/// Give a hard-linked `path` its own copy of the contents again (journal undo)
+///
+/// The copy is written under a temporary name and renamed over the link, so the other
+/// names of the file are never touched.
+pub fn separate(path: &Path) -> Result<(), CommandError> {
+    if !path.is_file() {
+        return Err(CommandError::FileNotFound(path.display().to_string()));
+    }
+    let temp = temp_path(path)?;
+    let result = fs::copy(path, &temp)
+        .map_err(CommandError::from)
+        .and_then(|_| {
+            if let Err(e) = crate::fileop::copy_attributes(path, &temp) {
+                eprintln!("[DEDUPE] Couldn't keep attributes of {}: {}", path.display(), e);
+            }
+            fs::rename(&temp, path).map_err(CommandError::from)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    println!("[DEDUPE] Separated {} from its hard links", path.display());
+    result
+}
+
+/// Check `copy` and swap it for a hard link to `keep`, returning the bytes freed
+fn replace_with_link(keep: &Path, copy: &Path, hash: &str) -> Result<u64, CommandError> {
+    let meta = fs::symlink_metadata(copy).map_err(|_| CommandError::FileNotFound(copy.display().to_string()))?;
+    if !meta.is_file() {
+        return Err(CommandError::InvalidPath(format!("Not a regular file: {}", copy.display())));
+    }
+    if copy == keep || same_file(keep, copy) {
+        return Err(CommandError::InvalidPath("Already the kept file".to_string()));
+    }
+    if !same_volume(keep, copy) {
+        return Err(CommandError::InvalidPath("On a different drive than the kept file".to_string()));
+    }
+    if checksum::hash_file(copy)? != hash {
+        return Err(CommandError::ChecksumMismatch(format!(
+            "{} no longer matches the kept file",
+            copy.display()
+        )));
+    }
+
+    let temp = temp_path(copy)?;
+    fs::hard_link(keep, &temp)?;
+    if let Err(e) = fs::rename(&temp, copy) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    println!("[DEDUPE] Linked {} -> {}", copy.display(), keep.display());
+    Ok(meta.len())
+}
+
+/// An unused name next to `path` for building its replacement
+fn temp_path(path: &Path) -> Result<PathBuf, CommandError> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(CommandError::InvalidPath(path.display().to_string()));
+    };
+    let temp = dir.join(format!(".{}.fileorg-link", name.to_string_lossy()));
+    if temp.exists() {
+        return Err(CommandError::DuplicateExists(temp.display().to_string()));
+    }
+    Ok(temp)
+}
+
+/// How many different files (not names) are in `paths`
+#[cfg(unix)]
+fn distinct_files(paths: &[PathBuf]) -> usize {
+    use std::os::unix::fs::MetadataExt;
+    let mut ids: Vec<(u64, u64)> = paths
+        .iter()
+        .filter_map(|p| fs::metadata(p).ok())
+        .map(|m| (m.dev(), m.ino()))
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids.len().max(1)
+}
+
+// File ids aren't exposed on Windows without unstable APIs; count every name
+#[cfg(not(unix))]
+fn distinct_files(paths: &[PathBuf]) -> usize {
+    paths.len()
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+/// Whether a hard link between the two files is possible
+#[cfg(unix)]
+fn same_volume(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+/// Whether a hard link between the two files is possible (same drive letter or share)
+#[cfg(not(unix))]
+fn same_volume(a: &Path, b: &Path) -> bool {
+    let prefix = |p: &Path| match p.components().next() {
+        Some(std::path::Component::Prefix(prefix)) => Some(prefix.as_os_str().to_ascii_lowercase()),
+        _ => None,
+    };
+    prefix(a).is_some() && prefix(a) == prefix(b)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_link_duplicates() {
+        let tmp = std::env::temp_dir().join("fileorg_test_dedupe");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("ML")).unwrap();
+        fs::write(tmp.join("lecture3.pdf"), "same slides").unwrap();
+        fs::write(tmp.join("lecture3 (1).pdf"), "same slides").unwrap();
+        fs::write(tmp.join("ML").join("Lecture 3.pdf"), "same slides").unwrap();
+        fs::write(tmp.join("other.pdf"), "diff slides").unwrap(); // same size, different contents
+        fs::write(tmp.join(".hidden.pdf"), "same slides").unwrap();
+
+        let groups = find(&tmp).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].paths.len(), 3);
+        assert_eq!(groups[0].reclaimable, 22);
+
+        let keep = tmp.join("ML").join("Lecture 3.pdf");
+        let copies = vec![tmp.join("lecture3.pdf"), tmp.join("lecture3 (1).pdf"), tmp.join("other.pdf"), keep.clone()];
+        let report = link(&keep, &copies).unwrap();
+        assert_eq!(report.linked.len(), 2);
+        assert_eq!(report.reclaimed, 22);
+        // The different file and the kept file itself are refused
+        assert_eq!(report.failed.len(), 2);
+        assert_eq!(fs::read_to_string(tmp.join("other.pdf")).unwrap(), "diff slides");
+        assert_eq!(fs::read_to_string(tmp.join("lecture3.pdf")).unwrap(), "same slides");
+        assert!(!tmp.join(".lecture3.pdf.fileorg-link").exists());
+
+        #[cfg(unix)]
+        {
+            assert!(same_file(&keep, &tmp.join("lecture3.pdf")));
+            assert_eq!(find(&tmp).unwrap()[0].reclaimable, 0, "already linked");
+
+            // Undo gives the copy its own contents again, leaving the kept file alone
+            separate(&tmp.join("lecture3.pdf")).unwrap();
+            assert!(!same_file(&keep, &tmp.join("lecture3.pdf")));
+            fs::write(tmp.join("lecture3.pdf"), "edited").unwrap();
+            assert_eq!(fs::read_to_string(&keep).unwrap(), "same slides");
+        }
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
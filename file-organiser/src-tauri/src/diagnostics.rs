@@ -0,0 +1,166 @@
+//! Setup diagnostics
+//!
+//! Checks the things that make classification or moves fail quietly later on: OCR
+//! availability, whether the API key is accepted, database integrity, and whether the
+//! watched and destination folders can be written to. Each check reports ok, a warning
+//! (something optional is missing) or an error, with a sentence the Settings screen shows.
+
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// The outcome of one check
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl DiagnosticCheck {
+    pub fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        DiagnosticCheck { name: name.to_string(), status, detail: detail.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub ok: bool, // no check failed (warnings allowed)
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl DiagnosticsReport {
+    pub fn new(checks: Vec<DiagnosticCheck>) -> Self {
+        DiagnosticsReport {
+            ok: checks.iter().all(|c| c.status != CheckStatus::Error),
+            checks,
+        }
+    }
+}
+
+/// Whether Tesseract can be run for OCR (optional, so a missing install is a warning)
+pub fn check_tesseract() -> DiagnosticCheck {
+    match rusty_tesseract::get_tesseract_version() {
+        Ok(version) => {
+            let version = version.lines().next().unwrap_or_default().trim().to_string();
+            DiagnosticCheck::new("Tesseract OCR", CheckStatus::Ok, version)
+        }
+        Err(e) => DiagnosticCheck::new(
+            "Tesseract OCR",
+            CheckStatus::Warning,
+            format!("Not found, so text in images can't be read ({})", e),
+        ),
+    }
+}
+
+/// Interpret the provider's answer to a key check
+pub fn api_key_check(result: Result<reqwest::StatusCode, String>) -> DiagnosticCheck {
+    const NAME: &str = "API key";
+    match result {
+        Ok(status) if status.is_success() => DiagnosticCheck::new(NAME, CheckStatus::Ok, "Accepted by the provider"),
+        Ok(status) if status.as_u16() == 401 || status.as_u16() == 403 => {
+            DiagnosticCheck::new(NAME, CheckStatus::Error, "Rejected by the provider; check it in Settings")
+        }
+        Ok(status) if status.as_u16() == 429 => {
+            DiagnosticCheck::new(NAME, CheckStatus::Warning, "Rate limited or out of credit")
+        }
+        Ok(status) => DiagnosticCheck::new(NAME, CheckStatus::Warning, format!("Provider answered {}", status)),
+        Err(e) => DiagnosticCheck::new(NAME, CheckStatus::Warning, format!("Couldn't reach the provider: {}", e)),
+    }
+}
+
+/// Interpret SQLite's integrity check
+pub fn database_check(messages: Result<Vec<String>, String>) -> DiagnosticCheck {
+    const NAME: &str = "Database";
+    match messages {
+        Ok(messages) if messages.len() == 1 && messages[0] == "ok" => {
+            DiagnosticCheck::new(NAME, CheckStatus::Ok, "Integrity check passed")
+        }
+        Ok(messages) => DiagnosticCheck::new(NAME, CheckStatus::Error, format!("Integrity check failed: {}", messages.join("; "))),
+        Err(e) => DiagnosticCheck::new(NAME, CheckStatus::Error, e),
+    }
+}
+
+/// How a database in a synced folder is doing: fine, or with conflicted copies the sync
+/// client made when two computers changed it at once
+pub fn database_sync_check(provider: Option<&str>, conflicted_copies: &[std::path::PathBuf]) -> DiagnosticCheck {
+    const NAME: &str = "Database sync";
+    let place = provider.map(|p| format!("in {}", p)).unwrap_or_else(|| "next to the database".to_string());
+    if conflicted_copies.is_empty() {
+        return DiagnosticCheck::new(NAME, CheckStatus::Ok, format!("Saved {} in sync-safe mode", place));
+    }
+    let names: Vec<String> = conflicted_copies
+        .iter()
+        .map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string())
+        .collect();
+    DiagnosticCheck::new(
+        NAME,
+        CheckStatus::Warning,
+        format!(
+            "Conflicted copies {}: {}. Changes in them aren't used; delete them once you've checked nothing is missing",
+            place,
+            names.join(", ")
+        ),
+    )
+}
+
+/// Whether files can be created in `folder`, by writing and removing a scratch file
+pub fn check_writable(name: &str, folder: &Path) -> DiagnosticCheck {
+    if !folder.is_dir() {
+        return DiagnosticCheck::new(name, CheckStatus::Error, format!("{} doesn't exist", folder.display()));
+    }
+    let probe = folder.join(format!(".file-organiser-write-test-{}", std::process::id()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            DiagnosticCheck::new(name, CheckStatus::Ok, format!("{} is writable", folder.display()))
+        }
+        Err(e) => DiagnosticCheck::new(name, CheckStatus::Error, format!("Can't write to {}: {}", folder.display(), e)),
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_writable() {
+        let tmp = std::env::temp_dir().join("fileorg_test_diagnostics");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+
+        let check = check_writable("Watch folder", &tmp);
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert_eq!(std::fs::read_dir(&tmp).unwrap().count(), 0, "scratch file is removed");
+        assert_eq!(check_writable("Watch folder", &tmp.join("missing")).status, CheckStatus::Error);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_report_statuses() {
+        assert_eq!(api_key_check(Ok(reqwest::StatusCode::OK)).status, CheckStatus::Ok);
+        assert_eq!(api_key_check(Ok(reqwest::StatusCode::UNAUTHORIZED)).status, CheckStatus::Error);
+        assert_eq!(api_key_check(Ok(reqwest::StatusCode::TOO_MANY_REQUESTS)).status, CheckStatus::Warning);
+        assert_eq!(api_key_check(Err("timed out".to_string())).status, CheckStatus::Warning);
+
+        assert_eq!(database_check(Ok(vec!["ok".to_string()])).status, CheckStatus::Ok);
+        let damaged = database_check(Ok(vec!["row 3 missing from index".to_string()]));
+        assert_eq!(damaged.status, CheckStatus::Error);
+
+        // Warnings don't fail the report; errors do
+        let warning = api_key_check(Err("offline".to_string()));
+        assert!(DiagnosticsReport::new(vec![warning.clone()]).ok);
+        assert!(!DiagnosticsReport::new(vec![warning, damaged]).ok);
+    }
+}
@@ -0,0 +1,454 @@
+//! On-device text embeddings
+//!
+//! Similarity features (similar past outcomes in prompts, near-duplicate files, clustering
+//! unsorted files) compare short texts as vectors. With the all-MiniLM-L6-v2 model
+//! (`config.json`, `tokenizer.json` and `model.safetensors`, about 90 MB) texts are embedded
+//! on this computer: it works offline, costs nothing per call and keeps filenames local.
+//!
+//! The model is used from the app's `embedding-model` resource folder when it was bundled,
+//! and otherwise downloaded into the app data folder the first time it's needed (not in
+//! local-only mode). A download is only kept once each file has the size the model
+//! repository lists, the weights match the SHA-256 pinned here, and the model loads. Until
+//! then similarity features wait, or use the AI provider if the user allowed that
+//! (`set_provider_fallback`); `status` reports which, so the settings screen can say so.
+
+use candle_core::{Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+use tokio::io::AsyncWriteExt;
+
+// Resource folder (and app data folder) holding the model files
+pub const MODEL_DIR_NAME: &str = "embedding-model";
+
+// Where the model is downloaded from when it isn't bundled
+const MODEL_URL: &str = "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main";
+
+/// A model file, with the SHA-256 a download must have (pinned, never taken from the server)
+struct ModelFile {
+    name: &'static str,
+    sha256: Option<&'static str>,
+}
+
+const MODEL_FILES: &[ModelFile] = &[
+    ModelFile { name: "config.json", sha256: None },
+    ModelFile { name: "tokenizer.json", sha256: None },
+    ModelFile {
+        name: "model.safetensors",
+        sha256: Some("53aa51172d142c89d9012cce15ae4d6cc0ca6895895114379cacb4fab128d9db"),
+    },
+];
+
+// Longer texts are cut; a filename and a short snippet fit comfortably
+const MAX_TOKENS: usize = 128;
+
+// Texts embedded per forward pass
+const BATCH_SIZE: usize = 32;
+
+// Embeddings remembered between calls (correction filenames are embedded over and over)
+const MAX_CACHED: usize = 5000;
+
+struct LocalModel {
+    model: BertModel,
+    tokenizer: Tokenizer,
+}
+
+/// Where the local model stands
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelState {
+    NotChecked,  // looked for on first use
+    Loading,
+    Ready,
+    Downloading,
+    Missing,     // not bundled, and nowhere to download it to
+    Failed,      // the download or load failed (see `error`)
+}
+
+/// The local model's state for the settings screen, and how often the AI provider
+/// embedded texts instead
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelStatus {
+    pub state: ModelState,
+    pub downloaded: u64, // bytes, while downloading
+    pub total: u64,
+    pub error: Option<String>,
+    pub provider_fallback: bool, // whether the AI provider may embed texts until the model is ready
+    pub provider_fallbacks: u64,
+}
+
+static MODEL_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+static DOWNLOAD_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+static MODEL: OnceLock<LocalModel> = OnceLock::new();
+static STATUS: Mutex<ModelStatus> = Mutex::new(ModelStatus {
+    state: ModelState::NotChecked,
+    downloaded: 0,
+    total: 0,
+    error: None,
+    provider_fallback: false,
+    provider_fallbacks: 0,
+});
+static DOWNLOADED: AtomicU64 = AtomicU64::new(0);
+static CACHE: OnceLock<Mutex<HashMap<String, Vec<f32>>>> = OnceLock::new();
+
+/// Look for a bundled model in `dir` (call before the first embedding)
+pub fn set_model_dir(dir: PathBuf) {
+    *MODEL_DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir);
+}
+
+/// Download the model into `dir` when it isn't bundled (call before the first embedding)
+pub fn set_download_dir(dir: PathBuf) {
+    *DOWNLOAD_DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir);
+}
+
+/// Where the model is looked for: EMBEDDING_MODEL_DIR, the bundled folder, the download
+/// folder, or an `embedding-model` folder next to the executable (the first that has it)
+fn model_dir() -> Option<PathBuf> {
+    let env_dir = std::env::var_os("EMBEDDING_MODEL_DIR").filter(|d| !d.is_empty()).map(PathBuf::from);
+    let bundled = MODEL_DIR.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.join(MODEL_DIR_NAME)));
+    [env_dir, bundled, download_dir(), exe_dir]
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.join("model.safetensors").is_file())
+}
+
+fn download_dir() -> Option<PathBuf> {
+    DOWNLOAD_DIR.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn set_state(state: ModelState, error: Option<String>) {
+    let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+    status.state = state;
+    status.error = error;
+}
+
+/// The local model's state, and how often the AI provider was used instead
+pub fn status() -> ModelStatus {
+    let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    status.downloaded = DOWNLOADED.load(Ordering::Relaxed);
+    status
+}
+
+/// Let the AI provider embed texts while the local model isn't ready (off by default, since
+/// it sends filenames off this computer)
+pub fn set_provider_fallback(enabled: bool) {
+    STATUS.lock().unwrap_or_else(|e| e.into_inner()).provider_fallback = enabled;
+}
+
+pub fn provider_fallback() -> bool {
+    STATUS.lock().unwrap_or_else(|e| e.into_inner()).provider_fallback
+}
+
+fn load(dir: &Path) -> Result<LocalModel, String> {
+    let config = std::fs::read_to_string(dir.join("config.json")).map_err(|e| format!("config.json: {}", e))?;
+    let config: Config = serde_json::from_str(&config).map_err(|e| format!("config.json: {}", e))?;
+
+    let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json")).map_err(|e| format!("tokenizer.json: {}", e))?;
+    tokenizer.with_padding(Some(PaddingParams::default()));
+    tokenizer
+        .with_truncation(Some(TruncationParams { max_length: MAX_TOKENS, ..Default::default() }))
+        .map_err(|e| format!("tokenizer.json: {}", e))?;
+
+    // Safety: the weights file is only read, and isn't changed while the app runs
+    let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[dir.join("model.safetensors")], DTYPE, &Device::Cpu) }
+        .map_err(|e| format!("model.safetensors: {}", e))?;
+    let model = BertModel::load(vb, &config).map_err(|e| format!("model.safetensors: {}", e))?;
+    Ok(LocalModel { model, tokenizer })
+}
+
+/// The local model, loaded on first use (None while it's missing, downloading or broken)
+///
+/// A model that isn't there is downloaded in the background.
+fn model() -> Option<&'static LocalModel> {
+    if let Some(local) = MODEL.get() {
+        return Some(local);
+    }
+    {
+        let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+        if status.state != ModelState::NotChecked {
+            return None;
+        }
+        status.state = ModelState::Loading;
+    }
+
+    let Some(dir) = model_dir() else {
+        if download_dir().is_none() {
+            println!("[EMBEDDINGS] No local model, using the AI provider");
+            set_state(ModelState::Missing, None);
+            return None;
+        }
+        println!("[EMBEDDINGS] No local model yet, downloading it (the AI provider is used meanwhile)");
+        set_state(ModelState::Downloading, None);
+        tauri::async_runtime::spawn(async {
+            let _ = download().await;
+        });
+        return None;
+    };
+    match load(&dir) {
+        Ok(local) => {
+            println!("[EMBEDDINGS] Loaded local model from {}", dir.display());
+            let _ = MODEL.set(local);
+            set_state(ModelState::Ready, None);
+            MODEL.get()
+        }
+        Err(e) => {
+            eprintln!("[EMBEDDINGS] Failed to load the local model: {}", e);
+            set_state(ModelState::Failed, Some(e));
+            None
+        }
+    }
+}
+
+/// What a downloaded file must be: the size the model repository lists, and its pinned SHA-256
+#[derive(Debug, Default)]
+struct ExpectedFile {
+    size: Option<u64>,
+    sha256: Option<String>,
+}
+
+async fn expected_file(file: &ModelFile) -> Result<ExpectedFile, String> {
+    let url = format!("{}/{}", MODEL_URL, file.name);
+    let timeout = std::time::Duration::from_secs(crate::classifier::timeouts().text_secs);
+    let client = crate::classifier::http_client(&url, timeout).map_err(|e| e.to_string())?;
+    let response = client.head(&url).send().await.map_err(|e| format!("{}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{}: HTTP {}", url, response.status()));
+    }
+    Ok(ExpectedFile {
+        size: response.content_length(),
+        sha256: file.sha256.map(str::to_string),
+    })
+}
+
+/// Whether a downloaded file is the one listed
+fn check_file(name: &str, size: u64, sha256: &str, expected: &ExpectedFile) -> Result<(), String> {
+    if expected.size.is_some_and(|listed| listed != size) {
+        return Err(format!("{} is {} bytes, expected {}", name, size, expected.size.unwrap_or_default()));
+    }
+    if expected.sha256.as_deref().is_some_and(|listed| !listed.eq_ignore_ascii_case(sha256)) {
+        return Err(format!("{} doesn't match its known checksum", name));
+    }
+    Ok(())
+}
+
+async fn download_file(url: &str, dest: &Path, expected: &ExpectedFile) -> Result<(), String> {
+    let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let client = crate::classifier::download_client(url).map_err(|e| e.to_string())?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("{}: {}", name, e))?;
+    let mut file = tokio::fs::File::create(dest).await.map_err(|e| format!("{}: {}", name, e))?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    // The whole file may take a while on a slow connection; a stalled one is given up on
+    let read_timeout = std::time::Duration::from_secs(crate::classifier::timeouts().text_secs);
+    loop {
+        let chunk = tokio::time::timeout(read_timeout, response.chunk())
+            .await
+            .map_err(|_| format!("{}: no data for {} seconds", name, read_timeout.as_secs()))?
+            .map_err(|e| format!("{}: {}", name, e))?;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(|e| format!("{}: {}", name, e))?;
+        size += chunk.len() as u64;
+        DOWNLOADED.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+    }
+    file.flush().await.map_err(|e| format!("{}: {}", name, e))?;
+    check_file(&name, size, &format!("{:x}", hasher.finalize()), expected)
+}
+
+/// Download the model into the download folder, checking it before it's used
+///
+/// Files go to a `.part` folder first, so an interrupted or bad download is never loaded.
+pub async fn download() -> Result<(), String> {
+    let result = download_checked().await;
+    match &result {
+        Ok(()) => {
+            println!("[EMBEDDINGS] Model downloaded");
+            // Loaded by the next embedding
+            set_state(ModelState::NotChecked, None);
+        }
+        Err(e) => {
+            eprintln!("[EMBEDDINGS] Model download failed: {}", e);
+            set_state(ModelState::Failed, Some(e.clone()));
+        }
+    }
+    result
+}
+
+async fn download_checked() -> Result<(), String> {
+    let dir = download_dir().ok_or("No folder to download the embedding model to")?;
+    let partial = dir.with_extension("part");
+    let _ = tokio::fs::remove_dir_all(&partial).await;
+    tokio::fs::create_dir_all(&partial).await.map_err(|e| format!("{}: {}", partial.display(), e))?;
+
+    let mut expected = Vec::new();
+    for file in MODEL_FILES {
+        expected.push(expected_file(file).await?);
+    }
+    DOWNLOADED.store(0, Ordering::Relaxed);
+    STATUS.lock().unwrap_or_else(|e| e.into_inner()).total = expected.iter().filter_map(|e| e.size).sum();
+
+    for (file, expected) in MODEL_FILES.iter().zip(&expected) {
+        download_file(&format!("{}/{}", MODEL_URL, file.name), &partial.join(file.name), expected).await?;
+    }
+
+    let check_dir = partial.clone();
+    tokio::task::spawn_blocking(move || load(&check_dir).map(drop))
+        .await
+        .map_err(|e| format!("Model check failed: {}", e))??;
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    tokio::fs::rename(&partial, &dir).await.map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+/// Try the download again after it failed
+pub async fn retry_download() -> Result<(), String> {
+    {
+        let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+        match status.state {
+            ModelState::Ready | ModelState::Downloading | ModelState::Loading => return Ok(()),
+            _ if download_dir().is_none() => return Err("No folder to download the embedding model to".to_string()),
+            _ => status.state = ModelState::Downloading,
+        }
+        status.error = None;
+    }
+    download().await
+}
+
+/// Whether texts are embedded on this computer (loads or starts downloading the model)
+pub fn available() -> bool {
+    model().is_some()
+}
+
+/// Mean of the token vectors (padding left out), scaled to unit length
+fn embed_batch(local: &LocalModel, texts: &[String]) -> candle_core::Result<Vec<Vec<f32>>> {
+    let encodings = local
+        .tokenizer
+        .encode_batch(texts.to_vec(), true)
+        .map_err(|e| candle_core::Error::Msg(e.to_string()))?;
+    let device = &local.model.device;
+    let ids = encodings.iter().map(|e| Tensor::new(e.get_ids(), device)).collect::<candle_core::Result<Vec<_>>>()?;
+    let masks = encodings
+        .iter()
+        .map(|e| Tensor::new(e.get_attention_mask(), device))
+        .collect::<candle_core::Result<Vec<_>>>()?;
+    let ids = Tensor::stack(&ids, 0)?;
+    let mask = Tensor::stack(&masks, 0)?;
+
+    let output = local.model.forward(&ids, &ids.zeros_like()?, Some(&mask))?;
+    let mask = mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+    let summed = output.broadcast_mul(&mask)?.sum(1)?;
+    let pooled = summed.broadcast_div(&mask.sum(1)?)?;
+    let normalized = pooled.broadcast_div(&pooled.sqr()?.sum_keepdim(1)?.sqrt()?)?;
+    normalized.to_vec2()
+}
+
+/// Embed `texts` with the bundled model, in input order (None if there's no local model)
+pub fn embed_local(texts: &[String]) -> Option<Result<Vec<Vec<f32>>, String>> {
+    let local = model()?;
+    let cache = CACHE.get_or_init(Default::default);
+
+    let missing: Vec<String> = {
+        let cached = cache.lock().unwrap_or_else(|e| e.into_inner());
+        let mut missing: Vec<String> = texts.iter().filter(|t| !cached.contains_key(*t)).cloned().collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    };
+    let mut computed = Vec::with_capacity(missing.len());
+    for batch in missing.chunks(BATCH_SIZE) {
+        match embed_batch(local, batch) {
+            Ok(vectors) => computed.extend(batch.iter().cloned().zip(vectors)),
+            Err(e) => return Some(Err(format!("Local embedding failed: {}", e))),
+        }
+    }
+
+    let mut cached = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if cached.len() + computed.len() > MAX_CACHED {
+        cached.clear();
+    }
+    let mut fresh: HashMap<String, Vec<f32>> = computed.into_iter().collect();
+    let vectors = texts
+        .iter()
+        .map(|text| cached.get(text).cloned().or_else(|| fresh.get(text).cloned()).unwrap_or_default())
+        .collect();
+    cached.extend(fresh.drain());
+    Some(Ok(vectors))
+}
+
+/// Embed `texts` on this computer if the model is ready, otherwise with the AI provider if
+/// the user allowed that
+pub async fn compute_embedding(api_key: &str, texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+    // Loading the model and running it are CPU-bound
+    let (local, texts) = tokio::task::spawn_blocking(move || (embed_local(&texts), texts))
+        .await
+        .map_err(|e| format!("Embedding task failed: {}", e))?;
+    match local {
+        Some(result) => result,
+        None if !provider_fallback() => Err(
+            "The local similarity model isn't ready yet (allow the AI provider in Settings to use it meanwhile)"
+                .to_string(),
+        ),
+        None => {
+            STATUS.lock().unwrap_or_else(|e| e.into_inner()).provider_fallbacks += 1;
+            println!("[EMBEDDINGS] Local model not ready, embedding {} text(s) with the AI provider", texts.len());
+            crate::classifier::embed_texts(api_key.to_string(), texts).await
+        }
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_model_falls_back() {
+        let dir = std::env::temp_dir().join("embeddings_test_no_model");
+        let _ = std::fs::create_dir_all(&dir);
+        assert!(load(&dir).is_err(), "a folder without the model files doesn't load");
+    }
+
+    #[test]
+    fn test_check_file() {
+        let hash = format!("{:x}", Sha256::digest(b"weights"));
+        let listed = ExpectedFile { size: Some(7), sha256: Some(hash.to_uppercase()) };
+        assert!(check_file("model.safetensors", 7, &hash, &listed).is_ok());
+        assert!(check_file("model.safetensors", 6, &hash, &listed).is_err(), "a truncated download is refused");
+        let other = format!("{:x}", Sha256::digest(b"something else"));
+        assert!(check_file("model.safetensors", 7, &other, &listed).is_err());
+        assert!(check_file("config.json", 612, &other, &ExpectedFile::default()).is_ok(), "nothing listed to compare");
+    }
+
+    #[test]
+    fn test_pinned_hashes_are_sha256() {
+        for file in MODEL_FILES {
+            if let Some(hash) = file.sha256 {
+                assert!(hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()), "{}", file.name);
+            }
+        }
+        assert!(MODEL_FILES.iter().any(|f| f.name == "model.safetensors" && f.sha256.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_provider_fallback_is_opt_in() {
+        // Tests set no model folders, so there's no local model
+        let result = compute_embedding("sk-test", vec!["lecture 3.pdf".to_string()]).await;
+        assert!(result.unwrap_err().contains("isn't ready"));
+        assert_eq!(status().provider_fallbacks, 0, "nothing was sent to the AI provider");
+    }
+}
@@ -0,0 +1,202 @@
+//! Organization history as files for other tools
+//!
+//! `export_activity_csv` writes the history of moves as a spreadsheet, archived ones included
+//! rather than just the activity log's newest, narrowed to a date range (e.g. one semester)
+//! or a destination folder, so the user can look back over where things went.
+//!
+//! `export_corrections_jsonl` writes the corrections as an OpenAI fine-tuning dataset, so a
+//! cheap model can be trained on how this user files things. Each example is the prompt the
+//! file was classified with, when it was kept, and an answer naming the folder the user chose.
+
+use crate::db::{ActivityEntry, Correction};
+use crate::report::Table;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Which activity entries an export includes (everything when left empty)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ActivityFilters {
+    pub since: Option<i64>,     // Unix timestamp ms, inclusive
+    pub until: Option<i64>,     // Unix timestamp ms, exclusive
+    pub folder: Option<String>, // only moves into this folder or its subfolders
+    pub exclude_undone: bool,
+}
+
+impl ActivityFilters {
+    pub fn matches(&self, entry: &ActivityEntry) -> bool {
+        self.since.is_none_or(|since| entry.created_at >= since)
+            && self.until.is_none_or(|until| entry.created_at < until)
+            && self.folder.as_deref().is_none_or(|folder| is_within(&entry.to_folder, folder))
+            && !(self.exclude_undone && entry.undone)
+    }
+}
+
+/// Whether `path` is `folder` or inside it (whole components only)
+fn is_within(path: &str, folder: &str) -> bool {
+    let folder = folder.trim_end_matches(['/', '\\']);
+    path.strip_prefix(folder).is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+}
+
+/// One row per move, oldest first, with times in `zone`
+pub fn activity_table<Tz: chrono::TimeZone>(entries: &[ActivityEntry], filters: &ActivityFilters, zone: &Tz) -> Table
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut entries: Vec<&ActivityEntry> = entries.iter().filter(|e| filters.matches(e)).collect();
+    entries.sort_by_key(|e| (e.created_at, e.id));
+    let rows = entries
+        .into_iter()
+        .map(|e| {
+            let source = e.source_path.clone().unwrap_or_else(|| {
+                let name = e.original_filename.as_deref().unwrap_or(&e.filename);
+                Path::new(&e.from_folder).join(name).to_string_lossy().to_string()
+            });
+            vec![
+                chrono::DateTime::from_timestamp_millis(e.created_at)
+                    .map(|at| at.with_timezone(zone).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+                e.original_filename.clone().unwrap_or_else(|| e.filename.clone()),
+                e.filename.clone(),
+                source,
+                e.to_folder.clone(),
+                e.confidence.map(|c| format!("{:.2}", c)).unwrap_or_default(),
+                if e.undone { "yes" } else { "no" }.to_string(),
+            ]
+        })
+        .collect();
+    Table {
+        headers: vec!["Date", "Original filename", "Filename", "Source", "Destination", "Confidence", "Undone"],
+        rows,
+    }
+}
+
+/// One JSONL line per accepted or corrected filing, oldest first, and how many there are
+///
+/// `prompts` are the stored prompts by filename; files without one get the filename-only
+/// prompt `prompt_for` builds from the folders the corrections name. Dismissals say nothing
+/// about where a file goes, so they're left out.
+pub fn corrections_jsonl(
+    corrections: &[Correction],
+    prompts: &HashMap<String, String>,
+    prompt_for: impl Fn(&str, &[String]) -> String,
+) -> (String, usize) {
+    let mut corrections: Vec<&Correction> = corrections
+        .iter()
+        .filter(|c| c.correction_type != "dismissed" && !c.user_chose.trim().is_empty())
+        .collect();
+    corrections.sort_by_key(|c| (c.created_at, c.id));
+    let folders: Vec<String> = corrections
+        .iter()
+        .flat_map(|c| [&c.user_chose, &c.ai_suggested])
+        .filter(|folder| !folder.trim().is_empty())
+        .cloned()
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect();
+
+    let mut out = String::new();
+    for c in &corrections {
+        let prompt = prompts.get(&c.filename).cloned().unwrap_or_else(|| prompt_for(&c.filename, &folders));
+        let answer = serde_json::json!({
+            "is_relevant": true,
+            "folder": c.user_chose,
+            "confidence": 1.0,
+            "reasoning": format!("Files like this belong in {}", c.user_chose),
+        });
+        let example = serde_json::json!({
+            "messages": [
+                { "role": "user", "content": prompt },
+                { "role": "assistant", "content": answer.to_string() },
+            ]
+        });
+        out.push_str(&example.to_string());
+        out.push('\n');
+    }
+    (out, corrections.len())
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(id: i64, to_folder: &str, created_at: i64) -> ActivityEntry {
+        ActivityEntry {
+            id: Some(id),
+            filename: format!("ps{}.pdf", id),
+            original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "/Downloads".to_string(),
+            to_folder: to_folder.to_string(),
+            undone: false,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_activity_table() {
+        let day = 24 * 60 * 60 * 1000;
+        let renamed = ActivityEntry {
+            original_filename: Some("PS 2 (final).pdf".to_string()),
+            source_path: Some("/Desktop/PS 2 (final).pdf".to_string()),
+            confidence: Some(0.914),
+            undone: true,
+            ..moved(2, "/Uni/ML/Week 2", 2 * day)
+        };
+        let entries = vec![renamed, moved(1, "/Uni/ML", day), moved(3, "/Uni/ML2", 3 * day), moved(4, "/Uni/ML", 10 * day)];
+
+        let filters = ActivityFilters { until: Some(5 * day), folder: Some("/Uni/ML/".to_string()), ..Default::default() };
+        let table = activity_table(&entries, &filters, &chrono::Utc);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["1970-01-02 00:00", "ps1.pdf", "ps1.pdf", "/Downloads/ps1.pdf", "/Uni/ML", "", "no"],
+                vec!["1970-01-03 00:00", "PS 2 (final).pdf", "ps2.pdf", "/Desktop/PS 2 (final).pdf", "/Uni/ML/Week 2", "0.91", "yes"],
+            ]
+        );
+
+        let filters = ActivityFilters { since: Some(2 * day), exclude_undone: true, ..Default::default() };
+        let names: Vec<String> = activity_table(&entries, &filters, &chrono::Utc).rows.into_iter().map(|row| row[1].clone()).collect();
+        assert_eq!(names, vec!["ps3.pdf", "ps4.pdf"]);
+    }
+
+    fn correction(id: i64, filename: &str, ai_suggested: &str, user_chose: &str, correction_type: &str) -> Correction {
+        Correction {
+            id: Some(id),
+            filename: filename.to_string(),
+            ai_suggested: ai_suggested.to_string(),
+            user_chose: user_chose.to_string(),
+            correction_type: correction_type.to_string(),
+            confidence: None,
+            created_at: id,
+        }
+    }
+
+    #[test]
+    fn test_corrections_jsonl() {
+        let corrections = vec![
+            correction(3, "IMG_0042.png", "Econ", "dismissed", "dismissed"),
+            correction(2, "ps1.pdf", "Econ", "Stats", "corrected"),
+            correction(1, "week1.pdf", "ML", "ML", "accepted"),
+        ];
+        let prompts = HashMap::from([("ps1.pdf".to_string(), "Classify ps1.pdf".to_string())]);
+        let (jsonl, count) = corrections_jsonl(&corrections, &prompts, |filename, folders| format!("{} in {}", filename, folders.join("|")));
+        assert_eq!(count, 2);
+
+        let examples: Vec<serde_json::Value> = jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0]["messages"][0]["content"], "week1.pdf in Econ|ML|Stats");
+        assert_eq!(examples[1]["messages"][0]["content"], "Classify ps1.pdf");
+        let answer: serde_json::Value = serde_json::from_str(examples[1]["messages"][1]["content"].as_str().unwrap()).unwrap();
+        assert_eq!(answer["folder"], "Stats");
+        assert_eq!(examples[1]["messages"][1]["role"], "assistant");
+    }
+}
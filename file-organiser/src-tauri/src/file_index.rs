@@ -0,0 +1,175 @@
+//! Persistent index of the files in scanned and watched folders
+//!
+//! Scans and the watcher keep a `file_index` row per file (size, modified time and, once it
+//! has been classified, its content hash and the AI's answer). The UI reads its counts
+//! instead of listing the folder, and organize runs reuse an earlier classification while
+//! the file's size and modified time are unchanged, so a big Downloads folder isn't sent to
+//! the AI again on every run. Files that leave the folder drop out of the index.
+
+use crate::scan::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+pub const STATUS_NEW: &str = "new"; // not classified yet
+pub const STATUS_CLASSIFIED: &str = "classified"; // the AI suggested a folder
+pub const STATUS_SKIPPED: &str = "skipped"; // the AI said it doesn't belong anywhere
+
+/// One indexed file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub folder: String, // the scanned or watched folder it's in
+    pub size: u64,
+    pub modified: u64,        // Unix timestamp in seconds
+    pub hash: Option<String>, // blake3 of the content, set when classified
+    pub status: String,       // STATUS_NEW, STATUS_CLASSIFIED or STATUS_SKIPPED
+    pub dest_folder: Option<String>,
+    pub suggested_filename: Option<String>,
+    pub confidence: Option<f32>,
+    pub reasoning: Option<String>, // the AI's reasoning, or why it was skipped
+    pub updated_at: i64,           // Unix timestamp ms
+}
+
+impl IndexedFile {
+    pub fn new(folder: &str, file: &FileEntry, updated_at: i64) -> Self {
+        IndexedFile {
+            path: file.path.clone(),
+            folder: folder.to_string(),
+            size: file.size,
+            modified: file.modified,
+            hash: None,
+            status: STATUS_NEW.to_string(),
+            dest_folder: None,
+            suggested_filename: None,
+            confidence: None,
+            reasoning: None,
+            updated_at,
+        }
+    }
+
+    /// Whether the file on disk still looks like the one that was indexed
+    pub fn is_current(&self, file: &FileEntry) -> bool {
+        self.size == file.size && self.modified == file.modified
+    }
+}
+
+/// File counts for one folder, for the "N unsorted files" badge
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexSummary {
+    pub folder: String,
+    pub unsorted: usize,     // files still in the folder
+    pub unclassified: usize, // of those, files not classified yet
+    pub total_size: u64,
+}
+
+/// The listing entry for a single file (None if it's gone or isn't a file)
+pub fn entry_for(path: &Path) -> Option<FileEntry> {
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some(FileEntry {
+        name: path.file_name()?.to_str()?.to_string(),
+        path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        modified,
+    })
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn file(path: &str, size: u64, modified: u64) -> FileEntry {
+        FileEntry {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            size,
+            modified,
+        }
+    }
+
+    #[test]
+    fn test_sync_file_index() {
+        let tmp = std::env::temp_dir().join("fileorg_test_file_index");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let db = Database::new(tmp.join("test.db")).unwrap();
+
+        let a = file("/Downloads/a.pdf", 100, 1000);
+        let b = file("/Downloads/b.pdf", 200, 2000);
+        db.sync_file_index("/Downloads", &[a.clone(), b.clone()]).unwrap();
+        db.save_indexed_file(&IndexedFile {
+            status: STATUS_CLASSIFIED.to_string(),
+            dest_folder: Some("/Uni/ML".to_string()),
+            confidence: Some(0.7),
+            hash: Some("abc".to_string()),
+            ..IndexedFile::new("/Downloads", &a, 5000)
+        })
+        .unwrap();
+
+        // An unchanged file keeps its classification; a changed one goes back to new; a gone one is dropped
+        let b_changed = file("/Downloads/b.pdf", 250, 3000);
+        db.sync_file_index("/Downloads", &[a.clone(), b_changed]).unwrap();
+        let indexed = db.get_file_index("/Downloads").unwrap();
+        assert_eq!(indexed.len(), 2);
+        assert_eq!(indexed["/Downloads/a.pdf"].status, STATUS_CLASSIFIED);
+        assert_eq!(indexed["/Downloads/a.pdf"].dest_folder.as_deref(), Some("/Uni/ML"));
+        assert_eq!(indexed["/Downloads/b.pdf"].size, 250);
+
+        db.sync_file_index("/Downloads", &[a]).unwrap();
+        let summary = db.file_index_summary("/Downloads").unwrap();
+        assert_eq!((summary.unsorted, summary.unclassified, summary.total_size), (1, 0, 100));
+
+        // Sample names for the prompt's folder list match whole folder names only
+        assert_eq!(db.indexed_names_in("/Downloads/", 5).unwrap(), vec!["a.pdf"]);
+        assert!(db.indexed_names_in("/Down", 5).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_indexed_names_in() {
+        let tmp = std::env::temp_dir().join("fileorg_test_indexed_names_in");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let db = Database::new(tmp.join("test.db")).unwrap();
+
+        db.sync_file_index(
+            "/Uni/ML",
+            &[
+                file("/Uni/ML/old.pdf", 100, 1000),
+                file("/Uni/ML/Week 3/newest.pdf", 100, 3000),
+                file("/Uni/ML/middle.pdf", 100, 2000),
+            ],
+        )
+        .unwrap();
+        db.sync_file_index("/Uni/ML Notes", &[file("/Uni/ML Notes/other.pdf", 100, 4000)]).unwrap();
+
+        // Newest first, from any depth, and never from a folder that only shares the prefix
+        assert_eq!(db.indexed_names_in("/Uni/ML", 5).unwrap(), vec!["newest.pdf", "middle.pdf", "old.pdf"]);
+        assert_eq!(db.indexed_names_in("/Uni/ML", 2).unwrap(), vec!["newest.pdf", "middle.pdf"]);
+        assert!(db.indexed_names_in("/Uni/Empty", 5).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_is_current() {
+        let indexed = IndexedFile::new("/Downloads", &file("/Downloads/a.pdf", 100, 1000), 0);
+        assert!(indexed.is_current(&file("/Downloads/a.pdf", 100, 1000)));
+        assert!(!indexed.is_current(&file("/Downloads/a.pdf", 100, 1001)), "re-downloaded over the old copy");
+        assert!(!indexed.is_current(&file("/Downloads/a.pdf", 101, 1000)));
+    }
+}
@@ -0,0 +1,193 @@
+//! Filename sanitization
+//!
+//! Names come from users, the AI's rename suggestions and paper titles, and any of them
+//! can contain characters the filesystem rejects ("Lecture 3: Intro?.pdf"), runs of
+//! whitespace, or be too long. `sanitize` turns them into a single valid path component:
+//! illegal characters become "_", whitespace is collapsed, and on Windows trailing
+//! dots/spaces and reserved device names (CON, NUL, COM1, ...) are handled. Over-long
+//! names are shortened, keeping the extension.
+//!
+//! Names can also arrive in different Unicode forms: macOS writes "ü" decomposed (NFD,
+//! "u" + combining diaeresis) while typed rules and most other systems use the composed
+//! form (NFC). Names are compared in NFC so they match whichever form they're stored in.
+
+use std::path::{Path, PathBuf};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+// Longest file name most filesystems allow (NTFS counts UTF-16 units, ext4/APFS bytes;
+// counting bytes is the stricter of the two)
+pub const MAX_COMPONENT_BYTES: usize = 255;
+
+const WINDOWS_ILLEGAL: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Which filesystem rules to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    Unix, // Linux and macOS
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Unix
+        }
+    }
+}
+
+/// `name` made valid for this platform (None if nothing usable is left)
+pub fn sanitize(name: &str) -> Option<String> {
+    sanitize_for(name, Platform::current())
+}
+
+/// `name` made valid for `platform` (None if nothing usable is left)
+pub fn sanitize_for(name: &str, platform: Platform) -> Option<String> {
+    let replaced: String = name
+        .chars()
+        .filter(|c| !c.is_control() || c.is_whitespace())
+        .map(|c| match platform {
+            Platform::Windows if WINDOWS_ILLEGAL.contains(&c) => '_',
+            Platform::Unix if c == '/' => '_',
+            _ => c,
+        })
+        .collect();
+    let mut name = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if platform == Platform::Windows {
+        name = name.trim_end_matches(['.', ' ']).to_string();
+        let stem = name.split('.').next().unwrap_or("");
+        if WINDOWS_RESERVED.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+            name.insert(stem.len(), '_');
+        }
+    }
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+    Some(truncate(&name, MAX_COMPONENT_BYTES))
+}
+
+/// `name` in composed Unicode form (NFC)
+pub fn nfc(name: &str) -> String {
+    if is_nfc(name) {
+        name.to_string()
+    } else {
+        name.nfc().collect()
+    }
+}
+
+/// The file in `dir` named `name`, in whichever Unicode form it's stored
+///
+/// Filesystems that keep names byte-for-byte (ext4, NTFS) can hold both "Müller.pdf" (NFC)
+/// and "Müller.pdf" (NFD) side by side, so a plain `exists()` misses the duplicate.
+pub fn existing(dir: &Path, name: &std::ffi::OsStr) -> Option<PathBuf> {
+    let exact = dir.join(name);
+    if exact.exists() {
+        return Some(exact);
+    }
+    let name = name.to_str()?;
+    // ASCII names have only one form
+    if name.is_ascii() {
+        return None;
+    }
+    let wanted = nfc(name);
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .find(|entry| entry.file_name().to_str().is_some_and(|n| nfc(n) == wanted))
+        .map(|entry| entry.path())
+}
+
+/// Shorten `name` to at most `max_bytes`, cutting the stem and keeping the extension
+fn truncate(name: &str, max_bytes: usize) -> String {
+    if name.len() <= max_bytes {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rfind('.') {
+        // A very long "extension" is just part of the name
+        Some(dot) if dot > 0 && name.len() - dot <= 16 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let mut end = max_bytes.saturating_sub(ext.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", stem[..end].trim_end(), ext)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_replaces_illegal_characters() {
+        assert_eq!(sanitize_for("Lecture 3: Intro?.pdf", Platform::Windows).as_deref(), Some("Lecture 3_ Intro_.pdf"));
+        assert_eq!(sanitize_for("Lecture 3: Intro?.pdf", Platform::Unix).as_deref(), Some("Lecture 3: Intro?.pdf"));
+        assert_eq!(sanitize_for("PS1/solutions.pdf", Platform::Unix).as_deref(), Some("PS1_solutions.pdf"));
+        assert_eq!(sanitize_for("notes\u{0}\u{7}.txt", Platform::Unix).as_deref(), Some("notes.txt"));
+    }
+
+    #[test]
+    fn test_sanitize_collapses_whitespace() {
+        assert_eq!(sanitize_for("  Week   3\tslides .pdf ", Platform::Unix).as_deref(), Some("Week 3 slides .pdf"));
+        assert_eq!(sanitize_for("   ", Platform::Unix), None);
+        assert_eq!(sanitize_for("..", Platform::Unix), None);
+    }
+
+    #[test]
+    fn test_sanitize_windows_trailing_dots_and_reserved_names() {
+        assert_eq!(sanitize_for("draft. . .", Platform::Windows).as_deref(), Some("draft"));
+        assert_eq!(sanitize_for("con.txt", Platform::Windows).as_deref(), Some("con_.txt"));
+        assert_eq!(sanitize_for("NUL", Platform::Windows).as_deref(), Some("NUL_"));
+        assert_eq!(sanitize_for("console.txt", Platform::Windows).as_deref(), Some("console.txt"));
+        assert_eq!(sanitize_for("con.txt", Platform::Unix).as_deref(), Some("con.txt"));
+    }
+
+    #[test]
+    fn test_nfc_composes_umlauts_and_cjk() {
+        // "Müller" with a combining diaeresis, as macOS stores it
+        assert_eq!(nfc("Mu\u{308}ller.pdf"), "Müller.pdf");
+        // Japanese kana with a combining voiced mark, and Hangul spelled out as jamo
+        assert_eq!(nfc("\u{304b}\u{3099}\u{304f}.txt"), "\u{304c}\u{304f}.txt");
+        assert_eq!(nfc("\u{1112}\u{1161}\u{11ab}.docx"), "\u{d55c}.docx");
+        assert_eq!(nfc("Lecture 3.pdf"), "Lecture 3.pdf");
+    }
+
+    #[test]
+    fn test_existing_finds_other_normal_form() {
+        let tmp = std::env::temp_dir().join("fileorg_test_filename_existing");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("Mu\u{308}ller.pdf"), "nfd").unwrap();
+        std::fs::write(tmp.join("\u{1112}\u{1161}\u{11ab}.docx"), "jamo").unwrap();
+
+        let found = existing(&tmp, std::ffi::OsStr::new("Müller.pdf")).unwrap();
+        assert_eq!(std::fs::read_to_string(found).unwrap(), "nfd");
+        assert!(existing(&tmp, std::ffi::OsStr::new("\u{d55c}.docx")).is_some());
+        assert!(existing(&tmp, std::ffi::OsStr::new("Muller.pdf")).is_none());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_sanitize_enforces_max_length() {
+        let long = format!("{}.pdf", "a".repeat(300));
+        let short = sanitize_for(&long, Platform::Unix).unwrap();
+        assert_eq!(short.len(), MAX_COMPONENT_BYTES);
+        assert!(short.ends_with("aaa.pdf"));
+
+        // Never cuts through a multi-byte character
+        let long = format!("{}.pdf", "ü".repeat(200));
+        let short = sanitize_for(&long, Platform::Unix).unwrap();
+        assert!(short.len() <= MAX_COMPONENT_BYTES);
+        assert!(short.ends_with("ü.pdf"));
+    }
+}
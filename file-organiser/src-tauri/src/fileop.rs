@@ -0,0 +1,398 @@
+//! Chunked copies with progress events and cancellation
+//!
+//! Copying a multi-GB lecture recording to another drive can take minutes, and a plain
+//! `fs::copy` gives no feedback. Copies here go in chunks: large ones get an operation
+//! id, report `file-op-progress` events to the frontend, and stop (deleting the partial
+//! destination file) when `cancel_file_operation` is called with that id.
+//!
+//! A copy is a new file, so it would show today's date. `copy_attributes` carries the
+//! original's timestamps, read-only flag and extended attributes over to it.
+
+use crate::CommandError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File, FileTimes};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+pub const PROGRESS_EVENT: &str = "file-op-progress";
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+// Smaller copies finish quickly enough that progress events would just be noise
+const PROGRESS_MIN_BYTES: u64 = 16 * 1024 * 1024;
+
+// Don't flood the frontend: at most one progress event per interval (plus a final one)
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static OPERATIONS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+static OP_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// Progress of a running copy, sent as a `file-op-progress` event
+#[derive(Debug, Clone, Serialize)]
+pub struct FileOpProgress {
+    pub op_id: String,
+    pub filename: String,
+    pub bytes_copied: u64,
+    pub total_bytes: u64,
+    pub bytes_per_sec: u64,
+    pub done: bool,
+}
+
+/// Let copies emit progress events (called once at startup)
+pub fn init(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+/// Request cancellation of a running operation; false if no such operation is running
+pub fn cancel(op_id: &str) -> bool {
+    let ops = OPERATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    match ops.as_ref().and_then(|ops| ops.get(op_id)) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Cancel every running copy (when quitting); returns how many were running
+pub fn cancel_all() -> usize {
+    let ops = OPERATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let flags: Vec<&Arc<AtomicBool>> = ops.iter().flat_map(|ops| ops.values()).collect();
+    for flag in &flags {
+        flag.store(true, Ordering::SeqCst);
+    }
+    flags.len()
+}
+
+/// Keeps an operation's cancel flag registered for as long as the copy runs
+struct Registration {
+    op_id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Registration {
+    fn new() -> Self {
+        let op_id = format!("op-{}", OP_COUNTER.fetch_add(1, Ordering::SeqCst));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        OPERATIONS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(HashMap::new)
+            .insert(op_id.clone(), cancelled.clone());
+        Registration { op_id, cancelled }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(ops) = OPERATIONS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            ops.remove(&self.op_id);
+        }
+    }
+}
+
+fn emit(progress: &FileOpProgress) {
+    if let Some(app) = APP_HANDLE.get() {
+        let _ = app.emit(PROGRESS_EVENT, progress);
+    }
+}
+
+/// Copy a file in chunks, reporting progress for large files and honouring cancellation
+///
+/// On cancellation or error the partially written destination is removed.
+pub fn copy_with_progress(from: &Path, to: &Path) -> Result<u64, CommandError> {
+    let _op = crate::shutdown::begin_operation()?;
+    let total_bytes = std::fs::metadata(from)?.len();
+    let registration = (total_bytes >= PROGRESS_MIN_BYTES).then(Registration::new);
+    copy_registered(from, to, total_bytes, registration.as_ref())
+}
+
+fn copy_registered(
+    from: &Path,
+    to: &Path,
+    total_bytes: u64,
+    registration: Option<&Registration>,
+) -> Result<u64, CommandError> {
+    let filename = from
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    copy_stream(File::open(from)?, to, filename, total_bytes, registration)
+}
+
+/// Copy everything `source` yields to `to`, removing the partial file on cancellation or error
+fn copy_stream(
+    source: impl Read,
+    to: &Path,
+    filename: String,
+    total_bytes: u64,
+    registration: Option<&Registration>,
+) -> Result<u64, CommandError> {
+    let result = copy_chunks(source, to, filename, total_bytes, registration);
+    if result.is_err() {
+        let _ = std::fs::remove_file(to);
+    }
+    result
+}
+
+fn copy_chunks(
+    source: impl Read,
+    to: &Path,
+    filename: String,
+    total_bytes: u64,
+    registration: Option<&Registration>,
+) -> Result<u64, CommandError> {
+    let mut reader = BufReader::new(source);
+    let mut writer = BufWriter::new(File::create(to)?);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    let started = Instant::now();
+    let mut last_report = started;
+    let mut copied = 0u64;
+
+    let progress = |copied: u64, done: bool, op_id: &str| {
+        let elapsed = started.elapsed().as_secs_f64();
+        FileOpProgress {
+            op_id: op_id.to_string(),
+            filename: filename.clone(),
+            bytes_copied: copied,
+            total_bytes,
+            bytes_per_sec: if elapsed > 0.0 { (copied as f64 / elapsed) as u64 } else { 0 },
+            done,
+        }
+    };
+
+    loop {
+        if let Some(reg) = registration {
+            if reg.cancelled.load(Ordering::SeqCst) {
+                return Err(CommandError::Cancelled(format!("Copy of {} was cancelled", filename)));
+            }
+        }
+
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+
+        if let Some(reg) = registration {
+            if last_report.elapsed() >= PROGRESS_INTERVAL {
+                emit(&progress(copied, false, &reg.op_id));
+                last_report = Instant::now();
+            }
+        }
+    }
+
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    if let Some(reg) = registration {
+        emit(&progress(copied, true, &reg.op_id));
+    }
+    Ok(copied)
+}
+
+/// Give `to` the modified/accessed (and, where supported, created) times, read-only flag
+/// and extended attributes of `from`
+///
+/// Extended attributes are best effort: the destination filesystem may not support them.
+pub fn copy_attributes(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::metadata(from)?;
+
+    #[cfg(unix)]
+    if let Ok(names) = xattr::list(from) {
+        for name in names {
+            if let Ok(Some(value)) = xattr::get(from, &name) {
+                if let Err(e) = xattr::set(to, &name, &value) {
+                    eprintln!("[FILEOP] Could not copy attribute {:?} to {}: {}", name, to.display(), e);
+                }
+            }
+        }
+    }
+
+    let mut times = FileTimes::new();
+    if let Ok(modified) = meta.modified() {
+        times = times.set_modified(modified);
+    }
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    #[cfg(windows)]
+    if let Ok(created) = meta.created() {
+        use std::os::windows::fs::FileTimesExt;
+        times = times.set_created(created);
+    }
+    #[cfg(target_os = "macos")]
+    if let Ok(created) = meta.created() {
+        use std::os::macos::fs::FileTimesExt;
+        times = times.set_created(created);
+    }
+    File::options().write(true).open(to)?.set_times(times)?;
+
+    // Last, since a read-only file can't be opened to set its times
+    fs::set_permissions(to, meta.permissions())
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_copy_with_progress_copies_contents() {
+        let tmp = std::env::temp_dir().join("fileorg_test_fileop_copy");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        // Spans several chunks, with a partial last chunk
+        let data: Vec<u8> = (0..(CHUNK_SIZE * 2 + 123)).map(|i| (i % 251) as u8).collect();
+        fs::write(tmp.join("recording.mp4"), &data).unwrap();
+
+        let copied = copy_with_progress(&tmp.join("recording.mp4"), &tmp.join("copy.mp4")).unwrap();
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(fs::read(tmp.join("copy.mp4")).unwrap(), data);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_cancelled_copy_removes_partial_file() {
+        let tmp = std::env::temp_dir().join("fileorg_test_fileop_cancel");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(tmp.join("recording.mp4"), vec![7u8; CHUNK_SIZE * 3]).unwrap();
+
+        let registration = Registration::new();
+        assert!(cancel(&registration.op_id));
+        let dest = tmp.join("copy.mp4");
+        let result = copy_registered(&tmp.join("recording.mp4"), &dest, CHUNK_SIZE as u64 * 3, Some(&registration));
+        assert!(matches!(result, Err(CommandError::Cancelled(_))));
+        assert!(!dest.exists());
+
+        drop(registration);
+        assert!(!cancel("op-does-not-exist"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    /// A source that hands out one chunk, then waits for the test before carrying on
+    struct GatedSource {
+        served: usize,
+        started: std::sync::mpsc::Sender<()>,
+        gate: std::sync::mpsc::Receiver<()>,
+    }
+
+    impl Read for GatedSource {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.served == 1 {
+                let _ = self.started.send(());
+                let _ = self.gate.recv();
+            }
+            if self.served == 8 {
+                return Ok(0);
+            }
+            self.served += 1;
+            buf.fill(7);
+            Ok(buf.len())
+        }
+    }
+
+    #[test]
+    fn test_cancel_during_copy() {
+        let tmp = std::env::temp_dir().join("fileorg_test_fileop_cancel_running");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let dest = tmp.join("copy.mp4");
+
+        let (started_tx, started) = std::sync::mpsc::channel();
+        let (gate, gate_rx) = std::sync::mpsc::channel();
+        let source = GatedSource { served: 0, started: started_tx, gate: gate_rx };
+        let registration = Registration::new();
+        let op_id = registration.op_id.clone();
+        let copy_dest = dest.clone();
+        let copy = std::thread::spawn(move || {
+            copy_stream(source, &copy_dest, "recording.mp4".to_string(), CHUNK_SIZE as u64 * 8, Some(&registration))
+        });
+
+        // Cancelled from another thread while the copy is partway through
+        started.recv().unwrap();
+        assert!(dest.exists());
+        assert!(cancel(&op_id));
+        gate.send(()).unwrap();
+
+        assert!(matches!(copy.join().unwrap(), Err(CommandError::Cancelled(_))));
+        assert!(!dest.exists(), "the partial copy is removed");
+        assert!(!cancel(&op_id), "the operation is gone once the copy stopped");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_cancel_all_stops_running_copies() {
+        let tmp = std::env::temp_dir().join("fileorg_test_fileop_cancel_all");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let dest = tmp.join("copy.mp4");
+
+        let (started_tx, started) = std::sync::mpsc::channel();
+        let (gate, gate_rx) = std::sync::mpsc::channel();
+        let source = GatedSource { served: 0, started: started_tx, gate: gate_rx };
+        let registration = Registration::new();
+        let copy_dest = dest.clone();
+        let copy = std::thread::spawn(move || {
+            copy_stream(source, &copy_dest, "recording.mp4".to_string(), CHUNK_SIZE as u64 * 8, Some(&registration))
+        });
+
+        // As Quit does once running operations have had their time
+        started.recv().unwrap();
+        assert!(cancel_all() >= 1);
+        gate.send(()).unwrap();
+
+        assert!(matches!(copy.join().unwrap(), Err(CommandError::Cancelled(_))));
+        assert!(!dest.exists(), "the partial copy is removed");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_copy_attributes_keeps_times_and_read_only() {
+        let tmp = std::env::temp_dir().join("fileorg_test_fileop_attributes");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let from = tmp.join("lecture1.pdf");
+        let to = tmp.join("copy.pdf");
+        fs::write(&from, "slides").unwrap();
+
+        // Last term's file, read-only
+        let modified = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        File::options().write(true).open(&from).unwrap().set_times(FileTimes::new().set_modified(modified)).unwrap();
+        let mut permissions = fs::metadata(&from).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&from, permissions).unwrap();
+
+        copy_with_progress(&from, &to).unwrap();
+        assert_ne!(fs::metadata(&to).unwrap().modified().unwrap(), modified);
+        copy_attributes(&from, &to).unwrap();
+        let copied = fs::metadata(&to).unwrap();
+        assert_eq!(copied.modified().unwrap(), modified);
+        assert!(copied.permissions().readonly());
+
+        for path in [&from, &to] {
+            let mut permissions = fs::metadata(path).unwrap().permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            fs::set_permissions(path, permissions).unwrap();
+        }
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,182 @@
+//! Content-based file type detection
+//!
+//! Downloads are often saved with the wrong extension (`download.bin`, `file`,
+//! `getdoc.php`), so routing on the extension alone skips files we could read.
+//! The first bytes of the file are checked against known signatures, and the
+//! extension is only used when the content isn't recognized.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Zip containers that infer may report as plain "zip" when it can't see the inner layout
+const ZIP_BASED_EXTENSIONS: &[&str] = &["docx", "xlsx", "pptx", "odt", "ods", "odp", "epub", "jar"];
+
+// How much of an unrecognized file to check when deciding whether it's plain text
+const TEXT_SNIFF_BYTES: usize = 8192;
+
+/// Detected type of a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileType {
+    pub extension: String, // canonical lowercase extension, e.g. "pdf" (empty if unknown)
+    pub mime_type: String,
+    pub from_content: bool, // false if we fell back to the filename
+}
+
+/// Detect a file's type from its content, falling back to its extension
+pub fn detect(path: &Path) -> FileType {
+    let named_ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    // Reading an online-only file would download it, so it goes by its name
+    if crate::metadata::skip_content(path) {
+        return by_name(named_ext);
+    }
+
+    if let Ok(Some(kind)) = infer::get_from_path(path) {
+        let sniffed = kind.extension();
+        // Trust the name for Office/ODF documents, which are zips underneath
+        let keep_named = sniffed == "zip" && ZIP_BASED_EXTENSIONS.contains(&named_ext.as_str());
+        if !keep_named {
+            return FileType {
+                extension: normalize(sniffed).to_string(),
+                mime_type: kind.mime_type().to_string(),
+                from_content: true,
+            };
+        }
+    }
+
+    // Unknown signature: no-extension or misnamed files that are valid UTF-8 are read as text
+    let extension = if (named_ext.is_empty() || !is_known_extension(&named_ext)) && looks_like_text(path) {
+        "txt".to_string()
+    } else {
+        named_ext
+    };
+
+    by_name(extension)
+}
+
+/// The type `extension` stands for, without looking at the content
+fn by_name(extension: String) -> FileType {
+    FileType {
+        mime_type: crate::metadata::mime_for_extension(&extension).to_string(),
+        extension,
+        from_content: false,
+    }
+}
+
+/// Map infer's extension names onto the ones the rest of the app matches on
+fn normalize(ext: &str) -> &str {
+    match ext {
+        "jpe" | "jfif" => "jpg",
+        "tif" => "tiff",
+        other => other,
+    }
+}
+
+fn is_known_extension(ext: &str) -> bool {
+    crate::metadata::mime_for_extension(ext) != "application/octet-stream"
+}
+
+/// Heuristic: the start of the file decodes as UTF-8 and contains no NUL bytes
+fn looks_like_text(path: &Path) -> bool {
+    let mut buf = Vec::with_capacity(TEXT_SNIFF_BYTES);
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    if file.take(TEXT_SNIFF_BYTES as u64).read_to_end(&mut buf).is_err() || buf.is_empty() {
+        return false;
+    }
+    if buf.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(&buf) {
+        Ok(_) => true,
+        // The sniff window may end in the middle of a multi-byte character
+        Err(e) => e.error_len().is_none() && buf.len() == TEXT_SNIFF_BYTES,
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_by_content() {
+        let tmp = std::env::temp_dir().join("fileorg_test_filetype");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        // A PDF saved without its extension
+        let pdf = tmp.join("download.bin");
+        fs::write(&pdf, b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj\n").unwrap();
+        let detected = detect(&pdf);
+        assert_eq!(detected.extension, "pdf");
+        assert_eq!(detected.mime_type, "application/pdf");
+        assert!(detected.from_content);
+
+        // A PNG named as a JPEG
+        let png = tmp.join("photo.jpg");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        assert_eq!(detect(&png).extension, "png");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_detect_falls_back_to_extension_and_text() {
+        let tmp = std::env::temp_dir().join("fileorg_test_filetype_fallback");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        let md = tmp.join("notes.md");
+        fs::write(&md, "# Week 3\nEigenvalues").unwrap();
+        let detected = detect(&md);
+        assert_eq!(detected.extension, "md");
+        assert!(!detected.from_content);
+
+        let readme = tmp.join("README");
+        fs::write(&readme, "Lab instructions for week 3 — bring a calculator").unwrap();
+        assert_eq!(detect(&readme).extension, "txt");
+
+        let binary = tmp.join("blob");
+        fs::write(&binary, [0u8, 159, 146, 150]).unwrap();
+        assert_eq!(detect(&binary).extension, "");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_office_documents_keep_their_extension() {
+        let tmp = std::env::temp_dir().join("fileorg_test_filetype_office");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        // A bare zip header: infer only sees "zip", the name says it's a Word document
+        let docx = tmp.join("essay.docx");
+        fs::write(&docx, b"PK\x03\x04\x14\0\0\0\0\0").unwrap();
+        assert_eq!(detect(&docx).extension, "docx");
+
+        let zip = tmp.join("lecture_materials");
+        fs::write(&zip, b"PK\x03\x04\x14\0\0\0\0\0").unwrap();
+        assert_eq!(detect(&zip).extension, "zip");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_by_name() {
+        // What online-only files are typed as: their extension, never sniffed content
+        let pdf = by_name("pdf".to_string());
+        assert_eq!((pdf.extension.as_str(), pdf.mime_type.as_str(), pdf.from_content), ("pdf", "application/pdf", false));
+        let unknown = by_name(String::new());
+        assert_eq!((unknown.extension.as_str(), unknown.mime_type.as_str()), ("", "application/octet-stream"));
+    }
+}
@@ -0,0 +1,226 @@
+//! Checking the classifier's folder against the list it was given
+//!
+//! Models don't always copy the folder verbatim: they change its casing, add a trailing
+//! slash, shorten it to its last part or invent a subfolder ("...\ML\Week 3"). The answer is
+//! matched to the real entry it most likely means, so a move never targets a folder that
+//! doesn't exist; an answer that doesn't clearly mean any entry is left unsorted.
+//!
+//! Folders can also have aliases ("Operations Research" for `OR`): they're listed with the
+//! folder in the prompt, and an answer naming one goes to its folder.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub const UNSORTED: &str = "__UNSORTED__";
+
+// How alike (0-1, by edit distance) a misspelt folder name must be to a real one
+const MIN_SIMILARITY: f64 = 0.8;
+
+/// Another name for a destination folder, e.g. the full course name of `ML`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderAlias {
+    pub alias: String,
+    pub folder: String, // full path, or just its end ("ML") if that's unique
+}
+
+static ALIASES: Mutex<Vec<FolderAlias>> = Mutex::new(Vec::new());
+
+/// Use `aliases` from now on (loaded from the `folder_aliases` table)
+pub fn set_aliases(aliases: Vec<FolderAlias>) {
+    *ALIASES.lock().unwrap_or_else(|e| e.into_inner()) = aliases;
+}
+
+pub fn aliases() -> Vec<FolderAlias> {
+    ALIASES.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// A path compared loosely: forward slashes, no trailing slash, lower case
+fn normalize(path: &str) -> String {
+    path.trim().replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+fn last_component(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Levenshtein distance over characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The digits in a name, which tell courses apart ("COMP3506" isn't a typo of "COMP3505")
+fn digits(name: &str) -> String {
+    name.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// 1 for identical strings, 0 for nothing in common
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+/// The folder a normalized `path` names: the same path, or the end of exactly one of them
+fn find_listed<'a>(path: &str, folders: &[(&'a str, String)]) -> Option<&'a str> {
+    if let Some((folder, _)) = folders.iter().find(|(_, listed)| listed == path) {
+        return Some(folder);
+    }
+    let suffix = format!("/{}", path);
+    let mut ending = folders.iter().filter(|(_, listed)| listed.ends_with(&suffix));
+    match (ending.next(), ending.next()) {
+        (Some((folder, _)), None) => Some(folder),
+        _ => None,
+    }
+}
+
+fn normalize_all<'a>(folders: &[&'a str]) -> Vec<(&'a str, String)> {
+    folders.iter().map(|&folder| (folder, normalize(folder))).collect()
+}
+
+/// Each of `aliases` whose folder is one of `folders`, with that folder
+pub fn listed_aliases<'a, 'b>(aliases: &'b [FolderAlias], folders: &[&'a str]) -> Vec<(&'b str, &'a str)> {
+    let folders = normalize_all(folders);
+    aliases
+        .iter()
+        .filter(|alias| !alias.alias.trim().is_empty())
+        .filter_map(|alias| Some((alias.alias.trim(), find_listed(&normalize(&alias.folder), &folders)?)))
+        .collect()
+}
+
+/// The entry of `folders` (paths without descriptions) that `answer` means, if it clearly
+/// means one
+pub fn resolve<'a>(answer: &str, folders: &[&'a str], aliases: &[FolderAlias]) -> Option<&'a str> {
+    let answer = normalize(answer);
+    if answer.is_empty() {
+        return None;
+    }
+    let aliases: Vec<(String, &'a str)> = listed_aliases(aliases, folders)
+        .into_iter()
+        .map(|(alias, folder)| (normalize(alias), folder))
+        .collect();
+    let folders = normalize_all(folders);
+
+    // The same path written differently, or just the end of one ("Machine Learning")
+    if let Some(folder) = find_listed(&answer, &folders) {
+        return Some(folder);
+    }
+
+    // A subfolder the model made up: the deepest listed folder it's inside
+    if let Some((folder, _)) = folders
+        .iter()
+        .filter(|(_, path)| answer.starts_with(&format!("{}/", path)))
+        .max_by_key(|(_, path)| path.len())
+    {
+        return Some(folder);
+    }
+
+    // An alias, on its own or at the end of a made-up path
+    let name = last_component(&answer);
+    if let Some((_, folder)) = aliases.iter().find(|(alias, _)| *alias == answer || alias == name) {
+        return Some(folder);
+    }
+
+    // A misspelt folder name or alias, if it's close to just one folder
+    let names = folders
+        .iter()
+        .map(|(folder, path)| (last_component(path), *folder))
+        .chain(aliases.iter().map(|(alias, folder)| (alias.as_str(), *folder)));
+    let mut best: Option<(&'a str, f64)> = None;
+    let mut tied = false;
+    for (folder_name, folder) in names {
+        if digits(name) != digits(folder_name) {
+            continue;
+        }
+        let score = similarity(name, folder_name);
+        match best {
+            Some((_, best_score)) if score < best_score => {}
+            Some((best_folder, best_score)) if score == best_score => tied |= best_folder != folder,
+            _ => {
+                best = Some((folder, score));
+                tied = false;
+            }
+        }
+    }
+    best.filter(|&(_, score)| score >= MIN_SIMILARITY && !tied).map(|(folder, _)| folder)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOLDERS: &[&str] = &[
+        "C:\\Uni\\Year2\\Machine Learning",
+        "C:\\Uni\\Year2\\Linear Algebra",
+        "C:\\Uni\\Year1\\Linear Algebra",
+        "/home/sam/uni/COMP3506",
+    ];
+
+    #[test]
+    fn test_resolve_matches_loosely_written_folders() {
+        assert_eq!(resolve("C:\\Uni\\Year2\\Machine Learning", FOLDERS, &[]), Some(FOLDERS[0]));
+        assert_eq!(resolve("c:/uni/year2/machine learning/", FOLDERS, &[]), Some(FOLDERS[0]));
+        assert_eq!(resolve("/home/sam/uni/comp3506\\", FOLDERS, &[]), Some(FOLDERS[3]));
+        // Invented subfolder
+        assert_eq!(resolve("C:\\Uni\\Year2\\Machine Learning\\Week 3", FOLDERS, &[]), Some(FOLDERS[0]));
+        // Shortened to the folder name
+        assert_eq!(resolve("Machine Learning", FOLDERS, &[]), Some(FOLDERS[0]));
+        assert_eq!(resolve("Year1/Linear Algebra", FOLDERS, &[]), Some(FOLDERS[2]));
+        // Misspelt
+        assert_eq!(resolve("C:\\Uni\\Year2\\Machine Learnin", FOLDERS, &[]), Some(FOLDERS[0]));
+    }
+
+    #[test]
+    fn test_resolve_rejects_unclear_answers() {
+        assert_eq!(resolve("", FOLDERS, &[]), None);
+        assert_eq!(resolve("Quantum Physics", FOLDERS, &[]), None);
+        // Two folders are called that
+        assert_eq!(resolve("Linear Algebra", FOLDERS, &[]), None);
+        assert_eq!(resolve("Linear Algebr", FOLDERS, &[]), None);
+        // Course codes aren't misspellings of each other
+        assert_eq!(resolve("COMP3505", FOLDERS, &[]), None);
+        assert_eq!(resolve("Machine Learning", &[], &[]), None);
+    }
+
+    #[test]
+    fn test_resolve_aliases() {
+        let aliases = vec![
+            FolderAlias { alias: "Operations Research".to_string(), folder: "C:\\Uni\\OR".to_string() },
+            FolderAlias { alias: "Machine Learning".to_string(), folder: "ML".to_string() },
+            FolderAlias { alias: "Quantum Physics".to_string(), folder: "QP".to_string() }, // not listed
+        ];
+        let folders = ["C:\\Uni\\OR", "C:\\Uni\\ML", "C:\\Uni\\PS"];
+
+        assert_eq!(resolve("Operations Research", &folders, &aliases), Some(folders[0]));
+        assert_eq!(resolve("C:\\Uni\\machine learning", &folders, &aliases), Some(folders[1]));
+        assert_eq!(resolve("Operations Reserch", &folders, &aliases), Some(folders[0]));
+        assert_eq!(resolve("Quantum Physics", &folders, &aliases), None);
+        assert_eq!(
+            listed_aliases(&aliases, &folders),
+            vec![("Operations Research", folders[0]), ("Machine Learning", folders[1])]
+        );
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert!(similarity("machine learnin", "machine learning") >= MIN_SIMILARITY);
+        assert!(similarity("physics", "maths") < MIN_SIMILARITY);
+    }
+}
@@ -0,0 +1,302 @@
+//! Per-folder statistics for the dashboard
+//!
+//! Walks a destination folder (including subfolders) and summarizes what's in it:
+//! how many files, how much space, the newest and oldest file, and a breakdown by
+//! extension. Hidden files are skipped, matching `scan_files`.
+//!
+//! `disk_usage` answers a different question, which subfolders take up the space, so it
+//! counts everything on disk, hidden files included.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// A file referenced from the stats (newest/oldest)
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsFile {
+    pub name: String,
+    pub path: String,
+    pub modified: u64, // Unix timestamp in seconds
+}
+
+/// File count and size for one extension
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionStats {
+    pub extension: String, // lowercase, empty for files without one
+    pub count: usize,
+    pub total_size: u64,
+}
+
+/// Summary of one folder's contents
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderStats {
+    pub path: String,
+    pub file_count: usize,
+    pub total_size: u64,
+    pub newest: Option<StatsFile>,
+    pub oldest: Option<StatsFile>,
+    pub extensions: Vec<ExtensionStats>, // largest count first
+    pub error: Option<String>,
+}
+
+impl FolderStats {
+    fn empty(path: &Path) -> Self {
+        FolderStats {
+            path: path.to_string_lossy().to_string(),
+            file_count: 0,
+            total_size: 0,
+            newest: None,
+            oldest: None,
+            extensions: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Stats for a folder that couldn't be read, so one bad path doesn't fail the batch
+    pub fn failed(path: &Path, error: String) -> Self {
+        FolderStats {
+            error: Some(error),
+            ..FolderStats::empty(path)
+        }
+    }
+}
+
+/// Collect statistics for everything under `folder`
+pub fn collect(folder: &Path) -> io::Result<FolderStats> {
+    if !folder.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Not a folder: {}", folder.display()),
+        ));
+    }
+
+    let mut stats = FolderStats::empty(folder);
+    let mut by_extension: HashMap<String, ExtensionStats> = HashMap::new();
+    let mut pending = vec![folder.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        // Unreadable subfolders are skipped; only the top-level folder has to be readable
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == folder => return Err(e),
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            // Symlinked folders are not followed to avoid cycles
+            if file_type.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+
+            let size = meta.len();
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let extension = Path::new(&name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            stats.file_count += 1;
+            stats.total_size += size;
+
+            let ext = by_extension.entry(extension.clone()).or_insert(ExtensionStats {
+                extension,
+                count: 0,
+                total_size: 0,
+            });
+            ext.count += 1;
+            ext.total_size += size;
+
+            let file = || StatsFile {
+                name: name.clone(),
+                path: entry.path().to_string_lossy().to_string(),
+                modified,
+            };
+            if stats.newest.as_ref().is_none_or(|f| modified > f.modified) {
+                stats.newest = Some(file());
+            }
+            if stats.oldest.as_ref().is_none_or(|f| modified < f.modified) {
+                stats.oldest = Some(file());
+            }
+        }
+    }
+
+    stats.extensions = by_extension.into_values().collect();
+    stats.extensions.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.total_size.cmp(&a.total_size))
+            .then(a.extension.cmp(&b.extension))
+    });
+    Ok(stats)
+}
+
+// Deepest breakdown `disk_usage` returns, however deep the caller asks for
+pub const MAX_USAGE_DEPTH: usize = 6;
+
+/// Size of a folder and, down to the requested depth, of each subfolder
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsage {
+    pub name: String,
+    pub path: String,
+    pub size: u64, // everything underneath, however deep
+    pub file_count: usize,
+    pub children: Vec<DiskUsage>, // subfolders, largest first; empty below the depth cap
+}
+
+/// Size-by-subfolder breakdown of `folder`, `depth` levels of subfolders deep
+///
+/// Folders below the depth are still counted in their parent's size. Files directly in a
+/// folder aren't listed, only added to its size. Unreadable subfolders count as empty.
+pub fn disk_usage(folder: &Path, depth: usize) -> io::Result<DiskUsage> {
+    if !folder.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Not a folder: {}", folder.display()),
+        ));
+    }
+    // Only the top-level folder has to be readable
+    fs::read_dir(folder)?;
+    Ok(usage_of(folder, depth.min(MAX_USAGE_DEPTH)))
+}
+
+fn usage_of(dir: &Path, depth: usize) -> DiskUsage {
+    let mut usage = DiskUsage {
+        name: dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: dir.to_string_lossy().to_string(),
+        size: 0,
+        file_count: 0,
+        children: Vec::new(),
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return usage;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        // Symlinked folders are not followed to avoid cycles (and counting space twice)
+        if file_type.is_dir() {
+            let child = usage_of(&entry.path(), depth.saturating_sub(1));
+            usage.size += child.size;
+            usage.file_count += child.file_count;
+            if depth > 0 {
+                usage.children.push(child);
+            }
+        } else if file_type.is_file() {
+            usage.size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            usage.file_count += 1;
+        }
+    }
+
+    usage.children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    usage
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn set_modified(path: &Path, secs: u64) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    }
+
+    #[test]
+    fn test_collect_folder_stats() {
+        let tmp = std::env::temp_dir().join("fileorg_test_folder_stats");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Week 1")).unwrap();
+
+        fs::write(tmp.join("syllabus.pdf"), "12345").unwrap();
+        fs::write(tmp.join("Week 1").join("lecture.PDF"), "1234567890").unwrap();
+        fs::write(tmp.join("Week 1").join("notes.md"), "abc").unwrap();
+        fs::write(tmp.join(".DS_Store"), "hidden").unwrap();
+        set_modified(&tmp.join("syllabus.pdf"), 1_700_000_000);
+        set_modified(&tmp.join("Week 1").join("lecture.PDF"), 1_700_100_000);
+        set_modified(&tmp.join("Week 1").join("notes.md"), 1_700_200_000);
+
+        let stats = collect(&tmp).unwrap();
+        assert_eq!(stats.file_count, 3);
+        assert_eq!(stats.total_size, 18);
+        assert_eq!(stats.newest.unwrap().name, "notes.md");
+        assert_eq!(stats.oldest.unwrap().name, "syllabus.pdf");
+
+        assert_eq!(stats.extensions[0].extension, "pdf");
+        assert_eq!(stats.extensions[0].count, 2);
+        assert_eq!(stats.extensions[0].total_size, 15);
+        assert_eq!(stats.extensions[1].extension, "md");
+        assert!(stats.error.is_none());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_collect_missing_folder() {
+        let tmp = std::env::temp_dir().join("fileorg_test_folder_stats_missing");
+        let _ = fs::remove_dir_all(&tmp);
+        assert!(collect(&tmp).is_err());
+
+        // An empty folder has no newest/oldest file
+        fs::create_dir_all(&tmp).unwrap();
+        let stats = collect(&tmp).unwrap();
+        assert_eq!(stats.file_count, 0);
+        assert!(stats.newest.is_none());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_disk_usage_breakdown() {
+        let tmp = std::env::temp_dir().join("fileorg_test_disk_usage");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("ML").join("Lectures").join("Week 1")).unwrap();
+        fs::create_dir_all(tmp.join("Physics")).unwrap();
+        fs::write(tmp.join("syllabus.pdf"), "12345").unwrap();
+        fs::write(tmp.join("ML").join("notes.md"), "12").unwrap();
+        fs::write(tmp.join("ML").join("Lectures").join("Week 1").join("slides.pdf"), "1234567890").unwrap();
+        fs::write(tmp.join("Physics").join(".cache"), "123").unwrap();
+
+        let usage = disk_usage(&tmp, 1).unwrap();
+        assert_eq!((usage.size, usage.file_count), (20, 4));
+        assert_eq!(usage.children.len(), 2);
+        assert_eq!((usage.children[0].name.as_str(), usage.children[0].size), ("ML", 12));
+        assert_eq!((usage.children[1].name.as_str(), usage.children[1].size), ("Physics", 3));
+        // Below the depth cap, subfolders only count towards their parent
+        assert!(usage.children[0].children.is_empty());
+
+        let usage = disk_usage(&tmp, 2).unwrap();
+        assert_eq!(usage.children[0].children[0].name, "Lectures");
+        assert_eq!(usage.children[0].children[0].size, 10);
+        assert!(usage.children[0].children[0].children.is_empty());
+
+        assert!(disk_usage(&tmp.join("missing"), 2).is_err());
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,648 @@
+//! Rule suggestions from repeated corrections
+//!
+//! When the user keeps moving the same kind of file away from the folder the AI picked
+//! ("romer_growth.pdf" suggested for ML, moved to Econ), the corrections table holds the
+//! evidence for a rule. Corrections are grouped by AI-suggested → user-chose pair, and the
+//! filename word the group shares most becomes a `*word*` rule the user can create with
+//! one click.
+//!
+//! The same corrections, joined with the activity log, give an accuracy report per
+//! destination folder and per rule, showing which courses the AI keeps getting wrong.
+//! Files the app has moved give a rough figure for the time it saved the user.
+//!
+//! Past outcomes for files named like the one being classified, accepted suggestions as
+//! well as corrections, are added to the prompt, so patterns the AI already gets right are
+//! reinforced rather than only its mistakes being pointed out.
+//!
+//! The AI's own confidence tends to run high. Outcomes at similar confidence, and for the
+//! same suggested folder, turn it into an estimate of how often it's actually right.
+
+use crate::classifier::Classification;
+use crate::db::{ActivityEntry, Correction, HandledFile, Rule};
+use crate::planner;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// A pair has to repeat this often before a rule is suggested
+pub const MIN_REPEATS: usize = 3;
+
+// Words too short or too common in filenames to make a useful rule
+const STOP_WORDS: [&str; 8] = ["the", "and", "for", "copy", "final", "draft", "new", "file"];
+
+const MAX_EXAMPLES: usize = 3;
+
+// Past outcomes for similar filenames added to a prompt
+pub const MAX_PROMPT_EXAMPLES: usize = 8;
+
+/// A suggested rule backed by repeated corrections
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrectionInsight {
+    pub ai_suggested: String,
+    pub user_chose: String,
+    pub target_folder: Option<String>, // full path of `user_chose`, if it's still available
+    pub pattern: String,               // e.g. "*romer*"
+    pub count: usize,                  // corrections in this pair matching the pattern
+    pub conflicts: usize,              // other corrected/accepted files matching it that went elsewhere
+    pub examples: Vec<String>,         // a few of the filenames
+    pub message: String,
+}
+
+/// The words a filename could be matched on: lowercase, extension dropped, split on
+/// anything that isn't a letter or digit
+pub fn filename_words(filename: &str) -> Vec<String> {
+    let stem = match filename.rfind('.') {
+        Some(dot) if dot > 0 => &filename[..dot],
+        _ => filename,
+    };
+    let mut words: Vec<String> = stem
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3 && !w.chars().all(|c| c.is_ascii_digit()))
+        .filter(|w| !STOP_WORDS.contains(w))
+        .map(str::to_string)
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+/// How many filename words (see `filename_words`) two names have in common
+pub fn shared_words(a: &str, b: &str) -> usize {
+    let words = filename_words(a);
+    filename_words(b).iter().filter(|w| words.contains(w)).count()
+}
+
+/// The filename a correction-history line is about (the quoted part at its start)
+pub fn example_filename(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('"')?;
+    rest.split_once('"').map(|(name, _)| name)
+}
+
+/// Rules worth creating, most-repeated first
+///
+/// Only "corrected" entries count towards a suggestion; accepted and corrected entries
+/// that went to a different folder count as conflicts. Patterns that already exist as
+/// rules aren't suggested again.
+pub fn suggest_rules(corrections: &[Correction], rules: &[Rule], available_folders: &[String]) -> Vec<CorrectionInsight> {
+    let mut pairs: HashMap<(String, String), Vec<&Correction>> = HashMap::new();
+    for c in corrections.iter().filter(|c| c.correction_type == "corrected") {
+        if c.ai_suggested.eq_ignore_ascii_case(&c.user_chose) {
+            continue;
+        }
+        pairs
+            .entry((c.ai_suggested.clone(), c.user_chose.clone()))
+            .or_default()
+            .push(c);
+    }
+
+    let mut insights = Vec::new();
+    for ((ai_suggested, user_chose), group) in pairs {
+        if group.len() < MIN_REPEATS {
+            continue;
+        }
+
+        // The word shared by the most files in the group (ties go to the longer word)
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for c in &group {
+            for word in filename_words(&c.filename) {
+                *counts.entry(word).or_default() += 1;
+            }
+        }
+        let Some((word, count)) = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= MIN_REPEATS)
+            .max_by(|a, b| a.1.cmp(&b.1).then(a.0.len().cmp(&b.0.len())).then(b.0.cmp(&a.0)))
+        else {
+            continue;
+        };
+
+        let pattern = format!("*{}*", word);
+        if rules.iter().any(|r| r.pattern.eq_ignore_ascii_case(&pattern)) {
+            continue;
+        }
+
+        let matches = |c: &Correction| filename_words(&c.filename).contains(&word);
+        let conflicts = corrections
+            .iter()
+            .filter(|c| c.correction_type == "accepted" || c.correction_type == "corrected")
+            .filter(|c| matches(c))
+            .filter(|c| !c.user_chose.eq_ignore_ascii_case(&user_chose))
+            .count();
+        let examples = group
+            .iter()
+            .filter(|c| matches(c))
+            .take(MAX_EXAMPLES)
+            .map(|c| c.filename.clone())
+            .collect();
+        let target_folder = available_folders
+            .iter()
+            .find(|folder| {
+                let name = folder.rsplit(['/', '\\']).next().unwrap_or(folder);
+                name.eq_ignore_ascii_case(&user_chose)
+            })
+            .cloned();
+
+        insights.push(CorrectionInsight {
+            message: format!(
+                "{} times you moved `{}` from {} to {} — create rule?",
+                count, pattern, ai_suggested, user_chose
+            ),
+            ai_suggested,
+            user_chose,
+            target_folder,
+            pattern,
+            count,
+            conflicts,
+            examples,
+        });
+    }
+
+    insights.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.pattern.cmp(&b.pattern)));
+    insights
+}
+
+/// A past outcome as a line of the prompt's correction history (the same format the
+/// frontend uses for recent ones)
+fn example_line(c: &Correction) -> String {
+    if c.correction_type == "accepted" {
+        format!("\"{}\" → {} (correct)", c.filename, c.user_chose)
+    } else {
+        format!("\"{}\" → AI suggested {}, but user moved to {}", c.filename, c.ai_suggested, c.user_chose)
+    }
+}
+
+/// Up to `limit` accepted and corrected outcomes for files sharing words with `filename`,
+/// most words in common first, then newest
+pub fn similar_examples(corrections: &[Correction], filename: &str, limit: usize) -> Vec<String> {
+    let words = filename_words(filename);
+    if words.is_empty() {
+        return Vec::new();
+    }
+    ranked_examples(corrections, filename, limit, |c| {
+        let shared = filename_words(&c.filename).iter().filter(|w| words.contains(w)).count();
+        (shared > 0).then_some(shared as f32)
+    })
+}
+
+/// Up to `limit` accepted and corrected outcomes for other files, highest `score` first,
+/// then newest (outcomes scored None are left out)
+pub fn ranked_examples(
+    corrections: &[Correction],
+    filename: &str,
+    limit: usize,
+    score: impl Fn(&Correction) -> Option<f32>,
+) -> Vec<String> {
+    let mut scored: Vec<(f32, &Correction)> = corrections
+        .iter()
+        .filter(|c| c.correction_type == "accepted" || c.correction_type == "corrected")
+        .filter(|c| c.filename != filename)
+        .filter_map(|c| score(c).map(|s| (s, c)))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.created_at.cmp(&a.1.created_at)));
+
+    let mut lines: Vec<String> = Vec::new();
+    for (_, c) in scored {
+        let line = example_line(c);
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+        if lines.len() == limit {
+            break;
+        }
+    }
+    lines
+}
+
+/// `correction_history` plus similar past outcomes (see `similar_examples`) it doesn't
+/// already have
+pub fn add_similar_examples(mut correction_history: Vec<String>, corrections: &[Correction], filename: &str) -> Vec<String> {
+    for line in similar_examples(corrections, filename, MAX_PROMPT_EXAMPLES) {
+        if !correction_history.contains(&line) {
+            correction_history.push(line);
+        }
+    }
+    correction_history
+}
+
+/// How often suggestions for one destination folder were right
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FolderAccuracy {
+    pub folder: String,       // folder name, as stored in corrections
+    pub path: Option<String>, // full path, from the activity log
+    pub accepted: usize,      // suggested here and the user agreed
+    pub corrected: usize,     // suggested here but the user picked another folder
+    pub missed: usize,        // the user picked this folder over another suggestion
+    pub moves: usize,         // files moved here and not undone
+    pub undone: usize,        // moves here that were undone
+    pub accuracy: Option<f32>, // accepted / (accepted + corrected), None without suggestions
+}
+
+/// How often files matching a rule ended up in the rule's folder
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleAccuracy {
+    pub rule_id: Option<i64>,
+    pub pattern: String,
+    pub target_folder: String,
+    pub accepted: usize,
+    pub corrected: usize,
+    pub accuracy: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccuracyReport {
+    pub folders: Vec<FolderAccuracy>, // least accurate first
+    pub rules: Vec<RuleAccuracy>,
+}
+
+fn folder_name(path: &str) -> &str {
+    path.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+fn ratio(accepted: usize, corrected: usize) -> Option<f32> {
+    (accepted + corrected > 0).then(|| accepted as f32 / (accepted + corrected) as f32)
+}
+
+/// Accepted vs corrected suggestions per destination folder and per rule
+///
+/// Corrections name folders while activity entries hold full paths, so the two are
+/// joined on the folder name (ignoring case). A rule's files are the corrected or
+/// accepted ones it's the first match for.
+pub fn accuracy_report(corrections: &[Correction], activity: &[ActivityEntry], rules: &[Rule]) -> AccuracyReport {
+    let mut folders: HashMap<String, FolderAccuracy> = HashMap::new();
+    fn entry<'a>(folders: &'a mut HashMap<String, FolderAccuracy>, name: &str) -> &'a mut FolderAccuracy {
+        folders.entry(name.to_lowercase()).or_insert_with(|| FolderAccuracy {
+            folder: name.to_string(),
+            ..Default::default()
+        })
+    }
+
+    let mut rule_counts: HashMap<usize, (usize, usize)> = HashMap::new();
+    for c in corrections {
+        if c.correction_type != "accepted" && c.correction_type != "corrected" {
+            continue;
+        }
+        let agreed = c.correction_type == "accepted" || c.ai_suggested.eq_ignore_ascii_case(&c.user_chose);
+        if !c.ai_suggested.is_empty() {
+            let suggested = entry(&mut folders, &c.ai_suggested);
+            if agreed {
+                suggested.accepted += 1;
+            } else {
+                suggested.corrected += 1;
+            }
+        }
+        if !agreed {
+            entry(&mut folders, &c.user_chose).missed += 1;
+        }
+
+        if let Some(rule) = planner::match_rule(&c.filename, rules) {
+            let index = rules.iter().position(|r| std::ptr::eq(r, rule)).unwrap_or_default();
+            let counts = rule_counts.entry(index).or_default();
+            if folder_name(&rule.target_folder).eq_ignore_ascii_case(&c.user_chose) {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+    }
+
+    for a in activity {
+        let folder = entry(&mut folders, folder_name(&a.to_folder));
+        folder.path.get_or_insert_with(|| a.to_folder.clone());
+        if a.undone {
+            folder.undone += 1;
+        } else {
+            folder.moves += 1;
+        }
+    }
+
+    let mut folders: Vec<FolderAccuracy> = folders
+        .into_values()
+        .map(|f| FolderAccuracy { accuracy: ratio(f.accepted, f.corrected), ..f })
+        .collect();
+    folders.sort_by(|a, b| match (a.accuracy, b.accuracy) {
+        (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| b.corrected.cmp(&a.corrected)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.folder.to_lowercase().cmp(&b.folder.to_lowercase()),
+    });
+
+    let rules = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let (accepted, corrected) = rule_counts.get(&i).copied().unwrap_or_default();
+            RuleAccuracy {
+                rule_id: rule.id,
+                pattern: rule.pattern.clone(),
+                target_folder: rule.target_folder.clone(),
+                accepted,
+                corrected,
+                accuracy: ratio(accepted, corrected),
+            }
+        })
+        .collect();
+
+    AccuracyReport { folders, rules }
+}
+
+// Raw confidences are compared in bands this many wide (0-0.1, 0.1-0.2, ...)
+const CALIBRATION_BANDS: usize = 10;
+
+// How many outcomes the AI's own confidence counts for when weighed against history
+const CALIBRATION_PRIOR_WEIGHT: f32 = 5.0;
+
+/// Accepted vs corrected suggestions by the AI's confidence and by suggested folder
+#[derive(Debug, Clone, Default)]
+pub struct Calibration {
+    bands: [(usize, usize); CALIBRATION_BANDS], // (accepted, corrected) per confidence band
+    folders: HashMap<String, (usize, usize)>,   // by lowercase folder name
+}
+
+fn band(confidence: f32) -> usize {
+    ((confidence.clamp(0.0, 1.0) * CALIBRATION_BANDS as f32) as usize).min(CALIBRATION_BANDS - 1)
+}
+
+/// `estimate` moved toward the observed accuracy, the more so the more outcomes there are
+fn blend(estimate: f32, (accepted, corrected): (usize, usize)) -> f32 {
+    (estimate * CALIBRATION_PRIOR_WEIGHT + accepted as f32) / (CALIBRATION_PRIOR_WEIGHT + (accepted + corrected) as f32)
+}
+
+impl Calibration {
+    pub fn from_corrections(corrections: &[Correction]) -> Self {
+        let mut calibration = Calibration::default();
+        for c in corrections {
+            if (c.correction_type != "accepted" && c.correction_type != "corrected") || c.ai_suggested.is_empty() {
+                continue;
+            }
+            let agreed = c.correction_type == "accepted" || c.ai_suggested.eq_ignore_ascii_case(&c.user_chose);
+            let count = |counts: &mut (usize, usize)| {
+                if agreed {
+                    counts.0 += 1;
+                } else {
+                    counts.1 += 1;
+                }
+            };
+            if let Some(confidence) = c.confidence {
+                count(&mut calibration.bands[band(confidence)]);
+            }
+            count(calibration.folders.entry(folder_name(&c.ai_suggested).to_lowercase()).or_default());
+        }
+        calibration
+    }
+
+    /// How likely a suggestion of `folder` at `confidence` is to be right (None without
+    /// outcomes at that confidence or for that folder)
+    ///
+    /// The raw confidence is first blended with the accuracy of past suggestions in its
+    /// band, then with the accuracy of past suggestions of the same folder.
+    pub fn estimate(&self, folder: &str, confidence: f32) -> Option<f32> {
+        let in_band = self.bands[band(confidence)];
+        let for_folder = self.folders.get(&folder_name(folder).to_lowercase()).copied().unwrap_or_default();
+        if in_band == (0, 0) && for_folder == (0, 0) {
+            return None;
+        }
+        Some(blend(blend(confidence.clamp(0.0, 1.0), in_band), for_folder))
+    }
+
+    /// `classification` with its calibrated confidence filled in, if there's history for it
+    pub fn apply(&self, classification: Classification) -> Classification {
+        if !classification.is_relevant || classification.suggested_folder.is_empty() || classification.suggested_folder == "__UNSORTED__" {
+            return classification;
+        }
+        Classification {
+            calibrated_confidence: self.estimate(&classification.suggested_folder, classification.confidence),
+            ..classification
+        }
+    }
+}
+
+// Rough time to sort one file by hand: spot it, find its folder, drag it there, rename it
+pub const MANUAL_SECONDS_PER_FILE: u64 = 45;
+
+/// Time-saved figures for the dashboard
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TimeSaved {
+    pub since: i64,                        // Unix timestamp ms the figures start from
+    pub files: usize,                      // files moved by the app
+    pub timed_files: usize,                // of those, files whose arrival was seen
+    pub median_handling_ms: Option<i64>,   // arrival to move
+    pub average_handling_ms: Option<i64>,
+    pub manual_seconds_saved: u64,         // estimated effort avoided
+}
+
+/// Handling times and the manual effort avoided for files moved since `since`
+pub fn time_saved(handled: &[HandledFile], since: i64) -> TimeSaved {
+    let handled: Vec<&HandledFile> = handled.iter().filter(|f| f.moved_at >= since).collect();
+    let mut waits: Vec<i64> = handled
+        .iter()
+        .filter_map(|f| f.detected_at.map(|at| (f.moved_at - at).max(0)))
+        .collect();
+    waits.sort_unstable();
+
+    TimeSaved {
+        since,
+        files: handled.len(),
+        timed_files: waits.len(),
+        median_handling_ms: waits.get(waits.len() / 2).copied(),
+        average_handling_ms: (!waits.is_empty()).then(|| waits.iter().sum::<i64>() / waits.len() as i64),
+        manual_seconds_saved: handled.len() as u64 * MANUAL_SECONDS_PER_FILE,
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correction(filename: &str, ai_suggested: &str, user_chose: &str, correction_type: &str) -> Correction {
+        Correction {
+            id: None,
+            filename: filename.to_string(),
+            ai_suggested: ai_suggested.to_string(),
+            user_chose: user_chose.to_string(),
+            correction_type: correction_type.to_string(),
+            confidence: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_suggest_rules_from_repeated_pair() {
+        let corrections = vec![
+            correction("Romer_Growth_2019.pdf", "ML", "Econ", "corrected"),
+            correction("romer-ch2.pdf", "ML", "Econ", "corrected"),
+            correction("Romer ch3 notes.pdf", "ML", "Econ", "corrected"),
+            correction("romer_ch4.pdf", "ML", "Econ", "corrected"),
+            correction("romer_slides.pdf", "ML", "ML", "accepted"),
+            correction("lecture1.pdf", "Econ", "ML", "corrected"),
+        ];
+        let folders = vec!["/Uni/ML".to_string(), "/Uni/Econ".to_string()];
+
+        let insights = suggest_rules(&corrections, &[], &folders);
+        assert_eq!(insights.len(), 1, "a single correction isn't enough");
+        let insight = &insights[0];
+        assert_eq!(insight.pattern, "*romer*");
+        assert_eq!(insight.count, 4);
+        assert_eq!(insight.conflicts, 1);
+        assert_eq!(insight.target_folder.as_deref(), Some("/Uni/Econ"));
+        assert_eq!(insight.examples.len(), 3);
+        assert_eq!(insight.message, "4 times you moved `*romer*` from ML to Econ — create rule?");
+
+        // Not suggested again once the rule exists
+        let rule = Rule {
+            id: Some(1),
+            pattern: "*Romer*".to_string(),
+            target_folder: "/Uni/Econ".to_string(),
+            created_at: 0,
+            profile_id: None,
+        };
+        assert!(suggest_rules(&corrections, &[rule], &folders).is_empty());
+    }
+
+    #[test]
+    fn test_accuracy_report() {
+        let corrections = vec![
+            correction("a.pdf", "ML", "ML", "accepted"),
+            correction("b.pdf", "ML", "Econ", "corrected"),
+            correction("c.pdf", "ML", "Econ", "corrected"),
+            correction("PS1.pdf", "Econ", "Econ", "accepted"),
+            correction("PS2.pdf", "Econ", "ML", "corrected"),
+            correction("d.pdf", "ML", "dismissed", "dismissed"),
+        ];
+        let activity = vec![ActivityEntry {
+            id: None,
+            filename: "b.pdf".to_string(),
+            original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "/Downloads".to_string(),
+            to_folder: "/Uni/econ".to_string(),
+            undone: false,
+            created_at: 0,
+        }];
+        let rules = vec![Rule {
+            id: Some(7),
+            pattern: "PS*".to_string(),
+            target_folder: "/Uni/Econ".to_string(),
+            created_at: 0,
+            profile_id: None,
+        }];
+
+        let report = accuracy_report(&corrections, &activity, &rules);
+        let names: Vec<&str> = report.folders.iter().map(|f| f.folder.as_str()).collect();
+        assert_eq!(names, vec!["ML", "Econ"], "least accurate first");
+        let ml = &report.folders[0];
+        assert_eq!((ml.accepted, ml.corrected, ml.missed), (1, 2, 1));
+        let econ = &report.folders[1];
+        assert_eq!((econ.accepted, econ.corrected, econ.missed, econ.moves), (1, 1, 2, 1));
+        assert_eq!(econ.path.as_deref(), Some("/Uni/econ"), "joined on the name ignoring case");
+        assert_eq!(econ.accuracy, Some(0.5));
+
+        assert_eq!((report.rules[0].accepted, report.rules[0].corrected), (1, 1));
+    }
+
+    #[test]
+    fn test_calibration() {
+        let mut corrections: Vec<Correction> = (0..10)
+            .map(|i| Correction {
+                confidence: Some(0.92),
+                ..correction("x.pdf", "ML", if i < 3 { "ML" } else { "Econ" }, if i < 3 { "accepted" } else { "corrected" })
+            })
+            .collect();
+        corrections.push(correction("y.pdf", "Econ", "Econ", "accepted"));
+        let calibration = Calibration::from_corrections(&corrections);
+
+        // Right 3 times in 10 at ~90%: a 95% suggestion for ML is far less likely than it claims
+        let ml = calibration.estimate("/Uni/ML", 0.95).unwrap();
+        assert!(ml > 0.3 && ml < 0.5, "{}", ml);
+        // Same band, but Econ suggestions have been right
+        assert!(calibration.estimate("/Uni/Econ", 0.95).unwrap() > ml);
+        // No outcomes in the band or for the folder
+        assert_eq!(calibration.estimate("/Uni/Art", 0.3), None);
+
+        let classification = Classification {
+            is_relevant: true,
+            suggested_folder: "/Uni/ML".to_string(),
+            confidence: 0.95,
+            reasoning: String::new(),
+            suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
+        };
+        assert_eq!(calibration.apply(classification.clone()).calibrated_confidence, Some(ml));
+        let unsorted = Classification { suggested_folder: "__UNSORTED__".to_string(), ..classification };
+        assert_eq!(calibration.apply(unsorted).calibrated_confidence, None);
+    }
+
+    #[test]
+    fn test_time_saved() {
+        let handled = vec![
+            HandledFile { detected_at: None, moved_at: 500 },
+            HandledFile { detected_at: Some(1_000), moved_at: 4_000 },
+            HandledFile { detected_at: Some(2_000), moved_at: 3_000 },
+            HandledFile { detected_at: None, moved_at: 5_000 },
+            HandledFile { detected_at: Some(5_000), moved_at: 13_000 },
+        ];
+
+        let saved = time_saved(&handled, 1_000);
+        assert_eq!((saved.files, saved.timed_files), (4, 3), "the move before `since` isn't counted");
+        assert_eq!(saved.median_handling_ms, Some(3_000));
+        assert_eq!(saved.average_handling_ms, Some(4_000));
+        assert_eq!(saved.manual_seconds_saved, 4 * MANUAL_SECONDS_PER_FILE);
+
+        assert_eq!(time_saved(&[], 0).median_handling_ms, None);
+    }
+
+    #[test]
+    fn test_similar_examples_include_accepted() {
+        let mut corrections = vec![
+            correction("romer_growth.pdf", "Econ", "Econ", "accepted"),
+            correction("romer_growth_ch2.pdf", "ML", "Econ", "corrected"),
+            correction("solow_growth.pdf", "Econ", "Econ", "accepted"),
+            correction("romer_growth_ch3.pdf", "", "", "dismissed"),
+            correction("cnn_lecture.pdf", "ML", "ML", "accepted"),
+        ];
+        corrections[1].created_at = 5;
+        corrections[2].created_at = 10;
+
+        let examples = similar_examples(&corrections, "Romer_Growth_ch4.pdf", 3);
+        assert_eq!(
+            examples,
+            vec![
+                "\"romer_growth_ch2.pdf\" → AI suggested ML, but user moved to Econ".to_string(),
+                "\"romer_growth.pdf\" → Econ (correct)".to_string(),
+                "\"solow_growth.pdf\" → Econ (correct)".to_string(),
+            ],
+            "most words in common first; dismissed and unrelated files left out"
+        );
+
+        let history = vec!["\"romer_growth.pdf\" → Econ (correct)".to_string()];
+        let merged = add_similar_examples(history, &corrections, "Romer_Growth_ch4.pdf");
+        assert_eq!(merged.len(), 3, "lines already in the history aren't repeated");
+        assert!(similar_examples(&corrections, "IMG_2041.png", 3).is_empty());
+    }
+
+    #[test]
+    fn test_filename_words() {
+        assert_eq!(filename_words("PS3_Final-copy (2).pdf"), vec!["ps3"]);
+        assert_eq!(filename_words("2024 Lecture.Notes.docx"), vec!["lecture", "notes"]);
+        assert!(filename_words(".bashrc").contains(&"bashrc".to_string()));
+    }
+
+    #[test]
+    fn test_example_filename_and_shared_words() {
+        assert_eq!(example_filename("\"ps3_romer.pdf\" → Macro (corrected)"), Some("ps3_romer.pdf"));
+        assert_eq!(example_filename("Macro files look like this"), None);
+        assert_eq!(shared_words("Romer Growth PS3.pdf", "romer_growth_notes.pdf"), 2);
+        assert_eq!(shared_words("lecture.pdf", "tutorial.pdf"), 0);
+    }
+}
@@ -0,0 +1,359 @@
+//! Operations journal: reverting and re-applying recorded file operations
+//!
+//! Each file command records an `Operation` (before/after paths) in the database.
+//! This module performs the filesystem side of stepping those operations
+//! backwards (undo) and forwards again (redo). Files are moved with
+//! `checksum::move_verified`, so undo and redo work across drives too.
+
+use crate::db::Operation;
+use crate::{checksum, CommandError};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file that blocked a batch undo, with the reason it couldn't be restored
+#[derive(Debug, Clone, Serialize)]
+pub struct FileConflict {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Revert an operation by moving the file from its "after" path back to its "before" path
+///
+/// Copies are reverted by deleting the copy, unless it was edited since. Trashed files can't be restored from here
+/// since the recycle bin is owned by the OS. For "replace", only the moved file is
+/// restored — the overwritten file is gone. A copy replaced by a hard link gets its own
+/// contents back.
+pub fn undo(op: &Operation) -> Result<(), CommandError> {
+    let after = op
+        .after_path
+        .as_deref()
+        .ok_or_else(|| CommandError::NotUndoable(format!("{} operations cannot be undone", op.op_type)))?;
+
+    if op.op_type == "copy" {
+        return remove_copy(Path::new(after), op.content_hash.as_deref());
+    }
+    if op.op_type == "hardlink" {
+        return crate::dedupe::separate(Path::new(&op.before_path));
+    }
+
+    relocate(Path::new(after), Path::new(&op.before_path))
+}
+
+/// Re-apply an operation that was previously undone
+pub fn redo(op: &Operation) -> Result<(), CommandError> {
+    let after = op
+        .after_path
+        .as_deref()
+        .ok_or_else(|| CommandError::NotUndoable(format!("{} operations cannot be redone", op.op_type)))?;
+
+    if op.op_type == "copy" {
+        // A changed original would give a copy that no longer matches the recorded hash
+        if let Some(hash) = &op.content_hash {
+            checksum::verify(Path::new(&op.before_path), hash, None)?;
+        }
+        return duplicate(Path::new(&op.before_path), Path::new(after));
+    }
+    if op.op_type == "hardlink" {
+        return crate::dedupe::relink(Path::new(after), Path::new(&op.before_path));
+    }
+
+    relocate(Path::new(&op.before_path), Path::new(after))
+}
+
+/// Move a batch of files all-or-nothing, given (from, to) pairs
+///
+/// Every move is checked up front; if any would fail, nothing is touched and all
+/// conflicts are returned. If a move still fails midway, completed moves are rolled back.
+pub fn relocate_all(moves: &[(PathBuf, PathBuf)]) -> Result<(), Vec<FileConflict>> {
+    let mut conflicts = Vec::new();
+    let mut targets = HashSet::new();
+
+    for (from, to) in moves {
+        let reason = if !from.is_file() {
+            Some("File no longer exists at its organized location".to_string())
+        } else if to.exists() {
+            Some(format!("A file already exists at {}", to.display()))
+        } else if !targets.insert(to.clone()) {
+            Some(format!("Another file in this batch is also restored to {}", to.display()))
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            conflicts.push(FileConflict {
+                path: from.display().to_string(),
+                reason,
+            });
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    for (done, (from, to)) in moves.iter().enumerate() {
+        if let Err(e) = relocate(from, to) {
+            // Roll back everything moved so far, newest first
+            for (rb_from, rb_to) in moves[..done].iter().rev() {
+                let _ = checksum::move_verified(rb_to, rb_from);
+            }
+            return Err(vec![FileConflict {
+                path: from.display().to_string(),
+                reason: e.to_string(),
+            }]);
+        }
+    }
+
+    Ok(())
+}
+
+/// Move a file between two full paths, refusing to overwrite anything at the target
+fn relocate(from: &Path, to: &Path) -> Result<(), CommandError> {
+    if !from.is_file() {
+        return Err(CommandError::FileNotFound(from.display().to_string()));
+    }
+    if to.exists() {
+        return Err(CommandError::DuplicateExists(to.display().to_string()));
+    }
+
+    if let Some(parent) = to.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    checksum::move_verified(from, to)?;
+    println!("[JOURNAL] {} -> {}", from.display(), to.display());
+    Ok(())
+}
+
+/// Copy a file between two full paths, refusing to overwrite anything at the target
+fn duplicate(from: &Path, to: &Path) -> Result<(), CommandError> {
+    if !from.is_file() {
+        return Err(CommandError::FileNotFound(from.display().to_string()));
+    }
+    if to.exists() {
+        return Err(CommandError::DuplicateExists(to.display().to_string()));
+    }
+
+    if let Some(parent) = to.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    fs::copy(from, to)?;
+    println!("[JOURNAL] {} => {} (copy)", from.display(), to.display());
+    Ok(())
+}
+
+/// Delete a file created by a copy operation, refusing if it no longer matches the copy's hash
+fn remove_copy(path: &Path, hash: Option<&str>) -> Result<(), CommandError> {
+    if !path.is_file() {
+        return Err(CommandError::FileNotFound(path.display().to_string()));
+    }
+    if let Some(hash) = hash {
+        checksum::verify(path, hash, None)?;
+    }
+
+    fs::remove_file(path)?;
+    println!("[JOURNAL] Removed copy {}", path.display());
+    Ok(())
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(op_type: &str, before: &Path, after: Option<&Path>) -> Operation {
+        Operation {
+            id: Some(1),
+            op_type: op_type.to_string(),
+            before_path: before.to_string_lossy().to_string(),
+            after_path: after.map(|a| a.to_string_lossy().to_string()),
+            status: "done".to_string(),
+            created_at: 0,
+            updated_at: 0,
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_undo_then_redo_move() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_move");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("ML")).unwrap();
+
+        let before = tmp.join("lecture.pdf");
+        let after = tmp.join("ML").join("lecture.pdf");
+        fs::write(&after, "moved").unwrap();
+        let move_op = op("move", &before, Some(&after));
+
+        undo(&move_op).unwrap();
+        assert!(before.exists());
+        assert!(!after.exists());
+
+        redo(&move_op).unwrap();
+        assert!(!before.exists());
+        assert!(after.exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_undo_recreates_missing_original_folder() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_mkdir");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        let before = tmp.join("gone").join("notes.txt");
+        let after = tmp.join("notes.txt");
+        fs::write(&after, "data").unwrap();
+
+        undo(&op("move", &before, Some(&after))).unwrap();
+        assert!(before.exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_undo_refuses_to_overwrite() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_conflict");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        let before = tmp.join("a.txt");
+        let after = tmp.join("b.txt");
+        fs::write(&before, "newer work").unwrap();
+        fs::write(&after, "moved").unwrap();
+
+        let result = undo(&op("rename", &before, Some(&after)));
+        assert!(matches!(result.unwrap_err(), CommandError::DuplicateExists(_)));
+        assert_eq!(fs::read_to_string(&before).unwrap(), "newer work");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_undo_missing_file() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_missing");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        let result = undo(&op("move", &tmp.join("a.txt"), Some(&tmp.join("b.txt"))));
+        assert!(matches!(result.unwrap_err(), CommandError::FileNotFound(_)));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_relocate_all_moves_every_file() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_batch");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("ML")).unwrap();
+        fs::write(tmp.join("ML").join("a.pdf"), "a").unwrap();
+        fs::write(tmp.join("ML").join("b.pdf"), "b").unwrap();
+
+        let moves = vec![
+            (tmp.join("ML").join("a.pdf"), tmp.join("a.pdf")),
+            (tmp.join("ML").join("b.pdf"), tmp.join("b.pdf")),
+        ];
+        relocate_all(&moves).unwrap();
+        assert!(tmp.join("a.pdf").exists());
+        assert!(tmp.join("b.pdf").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_relocate_all_reports_conflicts_without_moving() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_batch_conflict");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("ML")).unwrap();
+        fs::write(tmp.join("ML").join("a.pdf"), "a").unwrap();
+        fs::write(tmp.join("ML").join("b.pdf"), "b").unwrap();
+        fs::write(tmp.join("b.pdf"), "newer download").unwrap();
+
+        let moves = vec![
+            (tmp.join("ML").join("a.pdf"), tmp.join("a.pdf")),
+            (tmp.join("ML").join("b.pdf"), tmp.join("b.pdf")),
+            (tmp.join("ML").join("gone.pdf"), tmp.join("gone.pdf")),
+        ];
+        let conflicts = relocate_all(&moves).unwrap_err();
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts[0].path.contains("b.pdf"));
+        assert!(conflicts[1].path.contains("gone.pdf"));
+
+        // Nothing moved, since the batch is all-or-nothing
+        assert!(tmp.join("ML").join("a.pdf").exists());
+        assert!(!tmp.join("a.pdf").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_undo_then_redo_copy() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_copy");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Archive")).unwrap();
+
+        let original = tmp.join("notes.pdf");
+        let copy = tmp.join("Archive").join("notes.pdf");
+        fs::write(&original, "data").unwrap();
+        fs::write(&copy, "data").unwrap();
+        let copy_op = op("copy", &original, Some(&copy));
+
+        undo(&copy_op).unwrap();
+        assert!(original.exists());
+        assert!(!copy.exists());
+
+        redo(&copy_op).unwrap();
+        assert!(original.exists());
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "data");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_undo_copy_refuses_edited_copy() {
+        let tmp = std::env::temp_dir().join("fileorg_test_journal_copy_edited");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Archive")).unwrap();
+
+        let original = tmp.join("notes.pdf");
+        let copy = tmp.join("Archive").join("notes.pdf");
+        fs::write(&original, "data").unwrap();
+        fs::write(&copy, "data").unwrap();
+        let copy_op = Operation {
+            content_hash: Some(checksum::hash_file(&copy).unwrap()),
+            ..op("copy", &original, Some(&copy))
+        };
+
+        // Edits made to the copy since would be lost, so it's kept
+        fs::write(&copy, "data plus annotations").unwrap();
+        assert!(matches!(undo(&copy_op).unwrap_err(), CommandError::ChangedSinceMove(_)));
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "data plus annotations");
+
+        fs::write(&copy, "data").unwrap();
+        undo(&copy_op).unwrap();
+        assert!(!copy.exists());
+
+        // Redoing from an original edited since wouldn't recreate the recorded copy
+        fs::write(&original, "rewritten").unwrap();
+        assert!(matches!(redo(&copy_op).unwrap_err(), CommandError::ChangedSinceMove(_)));
+        assert!(!copy.exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_trash_not_undoable() {
+        let result = undo(&op("trash", Path::new("a.txt"), None));
+        assert!(matches!(result.unwrap_err(), CommandError::NotUndoable(_)));
+    }
+}
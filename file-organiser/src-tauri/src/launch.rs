@@ -0,0 +1,171 @@
+//! Opening files in other apps
+//!
+//! `open_file` and `open_with` hand a file to another program, so a buggy or compromised
+//! frontend could use them to start one. Only documents, images and media are opened (a
+//! Downloads folder is full of installers, shortcuts and scripts, and there are too many kinds
+//! of those to list), and `open_with` only takes one of the viewers and editors in KNOWN_APPS
+//! that's installed, never a program the frontend names.
+
+use std::path::Path;
+
+// What the default app shows rather than runs. Web pages and SVGs run scripts, so they're left out
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "csv", "tsv", "xls", "xlsx", "ods", "ppt", "pptx", "odp",
+    "epub", "tex", "bib", "pages", "key", "numbers", "json", "log", "png", "jpg", "jpeg", "gif", "bmp", "tif",
+    "tiff", "webp", "heic", "mp3", "wav", "m4a", "flac", "ogg", "aac", "mp4", "mov", "m4v", "mkv", "avi", "webm",
+];
+
+// Apps a file may be opened with, as `open -a` (macOS), the shell (Windows) or PATH (Linux) know them
+#[cfg(target_os = "macos")]
+const KNOWN_APPS: &[&str] = &[
+    "Preview", "TextEdit", "QuickTime Player", "Safari", "Google Chrome", "Firefox", "Microsoft Word",
+    "Microsoft Excel", "Microsoft PowerPoint", "Pages", "Numbers", "Keynote", "Visual Studio Code",
+    "Adobe Acrobat Reader", "VLC",
+];
+#[cfg(windows)]
+const KNOWN_APPS: &[&str] = &[
+    "notepad", "mspaint", "winword", "excel", "powerpnt", "msedge", "chrome", "firefox", "code", "AcroRd32", "vlc",
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const KNOWN_APPS: &[&str] = &[
+    "evince", "okular", "atril", "xreader", "eog", "gimp", "gedit", "gnome-text-editor", "kate", "code",
+    "libreoffice", "vlc", "mpv", "firefox", "chromium", "google-chrome",
+];
+
+/// Whether `path` is safe to hand to its default app: a document, image or media file by its
+/// extension, or a file without one (a README) that isn't marked executable
+pub fn is_openable(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => DOCUMENT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()),
+        None => !has_executable_bit(path),
+    }
+}
+
+#[cfg(unix)]
+fn has_executable_bit(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn has_executable_bit(_path: &Path) -> bool {
+    false
+}
+
+/// The apps in KNOWN_APPS that are installed here
+pub fn installed_apps() -> Vec<String> {
+    KNOWN_APPS.iter().filter(|app| is_installed(app)).map(|app| app.to_string()).collect()
+}
+
+/// The installed app named `name` (case aside), as it's passed to the OS
+pub fn find_app(name: &str) -> Option<&'static str> {
+    KNOWN_APPS.iter().copied().find(|app| app.eq_ignore_ascii_case(name.trim())).filter(|app| is_installed(app))
+}
+
+#[cfg(target_os = "macos")]
+fn is_installed(app: &str) -> bool {
+    ["/Applications", "/System/Applications", "/System/Applications/Utilities"]
+        .iter()
+        .any(|dir| Path::new(dir).join(format!("{}.app", app)).is_dir())
+}
+
+// The shell finds apps in the Windows folder or through the registry's App Paths, so look there
+#[cfg(windows)]
+fn is_installed(app: &str) -> bool {
+    let exe = format!("{}.exe", app);
+    let in_windows = std::env::var_os("SystemRoot").is_some_and(|root| {
+        let root = Path::new(&root);
+        root.join(&exe).is_file() || root.join("System32").join(&exe).is_file()
+    });
+    in_windows || ["HKCU", "HKLM"].iter().any(|hive| in_app_paths(hive, &exe))
+}
+
+#[cfg(windows)]
+fn in_app_paths(hive: &str, exe: &str) -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let key = format!("{}\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\{}", hive, exe);
+    std::process::Command::new("reg")
+        .args(["query", &key, "/ve"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn is_installed(app: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(app).is_file()))
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_openable() {
+        assert!(is_openable(Path::new("/Downloads/lecture1.pdf")));
+        assert!(is_openable(Path::new("/Downloads/notes.DOCX")));
+        assert!(is_openable(Path::new("C:\\Users\\me\\Downloads\\photo.jpg")));
+        for name in [
+            "setup.exe", "install.SH", "run.bat", "Report.lnk", "site.url", "explorer.scf", "page.hta", "tool.jar",
+            "app.appref-ms", "launcher.desktop", "start.command", "page.html", "logo.svg", "archive.zip",
+        ] {
+            assert!(!is_openable(&Path::new("/Downloads").join(name)), "{} was openable", name);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_openable_without_extension() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join("fileorg_test_launch");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("installer");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::write(dir.join("README"), "text").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(!is_openable(&script));
+        assert!(is_openable(&dir.join("README")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_is_installed_looks_in_applications() {
+        assert!(is_installed("TextEdit"));
+        assert!(!is_installed("fileorg-no-such-app"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_is_installed_looks_in_windows_and_app_paths() {
+        assert!(is_installed("notepad"));
+        assert!(!is_installed("fileorg-no-such-app"));
+        assert!(!in_app_paths("HKLM", "fileorg-no-such-app.exe"));
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    #[test]
+    fn test_is_installed_looks_on_path() {
+        assert!(is_installed("sh"));
+        assert!(!is_installed("fileorg-no-such-app"));
+    }
+
+    #[test]
+    fn test_find_app_only_known_apps() {
+        assert_eq!(find_app("/bin/sh"), None);
+        assert_eq!(find_app("cmd.exe"), None);
+        assert_eq!(find_app(""), None);
+        for app in installed_apps() {
+            assert_eq!(find_app(&app.to_uppercase()), Some(app.as_str()));
+        }
+    }
+}
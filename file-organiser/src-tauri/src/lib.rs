@@ -2,9 +2,62 @@
 mod watcher;  // Import our file watcher module
 mod classifier;  // Import AI classifier module
 mod db;  // SQLite database module
-
-use db::{ActivityEntry, Correction, Database, DbError, Rule};
-use serde::Serialize;
+mod journal;  // Undo/redo of recorded file operations
+mod checksum;  // Content hashing to verify moves
+mod planner;  // Dry-run organization plans
+mod archive;  // Zip inspection and extraction
+mod video;  // Video metadata and poster frames
+mod metadata;  // Timestamps, MIME type and EXIF for files
+mod filetype;  // Content-based file type detection
+mod safe_delete;  // App-managed trash with retention
+mod fileop;  // Chunked copies with progress and cancellation
+mod folder_stats;  // File counts, sizes and extension breakdowns per folder
+mod search;  // Parallel file search across watched and destination folders
+mod scheduler;  // Scheduled automatic organization
+mod buckets;  // Type-bucket classification without AI
+mod topics;  // Clustering unsorted files into suggested new folders
+mod profiles;  // Per-watched-folder folders, rules, mode and threshold
+mod timetable;  // Course folders and rules from a timetable .ics export
+mod lms;  // Course names from Canvas or Moodle as folder descriptions
+mod papers;  // DOI/arXiv detection and Zotero/BibTeX hand-off
+mod webhooks;  // JSON POSTs for auto-moves and finished sessions
+mod markdown;  // Daily/weekly activity notes for Obsidian
+mod shortcut;  // System-wide key combination to organize or show the app
+mod cli;  // `file-organiser organize <folder>` without the window
+mod quick_sort;  // Files waiting in the always-on-top quick-sort popup
+mod diagnostics;  // OCR, API key, database and folder-access checks
+mod roots;  // Folders file operations are allowed to touch
+mod audit;  // Hash-chained log of every file operation
+mod scan;  // Parallel directory listings with limits and streamed batches
+mod file_index;  // Persistent index of files in watched and scanned folders
+mod quota;  // Size limits for watched folders, checked in the background
+mod mock_classifier;  // Offline keyword classifier for development and demos
+mod insights;  // Rule suggestions from repeated corrections
+mod embeddings;  // On-device text embeddings for similarity features
+mod chat_export;  // Saved ChatGPT conversations and their AI notes subfolder
+mod links;  // Targets of .lnk and .url shortcuts
+mod provenance;  // Where organized files came from, optionally written onto the files
+mod filename;  // Making names valid on every platform
+mod dedupe;  // Identical files and replacing copies with hard links
+mod shutdown;  // Finishing file operations and flushing the database before quitting
+mod settings;  // Types, limits and defaults of the user-facing settings
+mod setup_profile;  // Exporting and importing settings, rules and profiles as one file
+mod data_dir;  // Where the database and trash live: app data, portable or a chosen folder
+mod budget;  // Monthly API spend limit, with cheaper or free classifiers near and past it
+mod folder_match;  // Matching the classifier's folder (or a folder alias) to a real entry of the list it was given
+mod onboarding;  // First-run folders, rules and a dry run from an existing course tree
+mod orphans;  // Activity and index entries whose files were deleted outside the app
+mod report;  // CSV and Markdown tables for reports saved to a file
+mod volumes;  // Destination folders on external drives and network shares that may be unplugged
+mod export;  // Activity history as a spreadsheet, corrections as a fine-tuning dataset
+mod launch;  // Which files may be opened in other apps, and with which apps
+
+use db::{ActivityEntry, Correction, Database, DbError, DbWorker, DeletedFile, FolderPref, MaintenanceReport, Operation, PendingFile, QuarantineItem, Rule, Session, WatchProfile};
+use file_index::IndexedFile;
+use futures::StreamExt;
+use scan::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use tauri::Emitter;
@@ -16,6 +69,9 @@ use thiserror::Error;
 // Global database instance
 static DATABASE: OnceLock<Arc<Database>> = OnceLock::new();
 
+// Dedicated database thread used by async commands
+static DB_WORKER: OnceLock<DbWorker> = OnceLock::new();
+
 // Secure API key storage (only traverses IPC once via set_api_key)
 static API_KEY: OnceLock<Mutex<String>> = OnceLock::new();
 
@@ -41,11 +97,47 @@ pub enum CommandError {
     #[error("Path traversal not allowed")]
     PathTraversal,
 
+    #[error("Outside the allowed folders: {0}")]
+    OutsideAllowedRoots(String),
+
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
     #[error("IO error: {0}")]
     IoError(String),
+
+    #[error("Nothing to undo")]
+    NothingToUndo,
+
+    #[error("Nothing to redo")]
+    NothingToRedo,
+
+    #[error("Operation cannot be undone: {0}")]
+    NotUndoable(String),
+
+    #[error("Database error: {0}")]
+    Database(String),
+
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("Modified or replaced since it was moved: {0}")]
+    ChangedSinceMove(String),
+
+    #[error("Destination drive not connected: {0}")]
+    DestinationUnavailable(String),
+}
+
+impl From<DbError> for CommandError {
+    fn from(err: DbError) -> Self {
+        CommandError::Database(err.to_string())
+    }
 }
 
 impl From<std::io::Error> for CommandError {
@@ -67,25 +159,69 @@ impl From<std::io::Error> for CommandError {
 // The #[serde(tag = "type", content = "message")] attribute ensures errors
 // serialize to JSON like: {"type": "FileNotFound", "message": "path/to/file"}
 
-#[derive(Debug, Serialize)]
-struct FileEntry {
-    name: String,
-    path: String,
-    size: u64,
-    modified: u64, // Unix timestamp in seconds
-}
-
 #[derive(Debug, Serialize)]
 struct FilePreview {
-    preview_type: String, // "image", "text", "none"
-    content: String,      // base64 data URL for images, text for docs
+    preview_type: String, // "image", "text", "video", "none"
+    content: String,      // base64 data URL for images and video posters, text for docs
     error: Option<String>,
+    video: Option<video::VideoInfo>, // duration/resolution for videos
 }
 
 static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
 
+/// Rewrite an absolute Windows path in extended-length form (`\\?\C:\...` or
+/// `\\?\UNC\server\share\...`) so file APIs accept it beyond MAX_PATH.
+/// Returns None for relative paths, which can't use the prefix.
+fn extended_length_path(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") {
+        return Some(path.to_string());
+    }
+    // The prefix turns off all normalization, so separators and "." segments are fixed up here
+    let normalized = path.replace('/', "\\");
+    let join = |parts: &str| {
+        parts
+            .split('\\')
+            .filter(|s| !s.is_empty() && *s != ".")
+            .collect::<Vec<_>>()
+            .join("\\")
+    };
+
+    if let Some(unc) = normalized.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{}", join(unc)));
+    }
+    let bytes = normalized.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return Some(format!(r"\\?\{}\{}", &normalized[..2], join(&normalized[3..])));
+    }
+    None
+}
+
+/// On Windows, give absolute paths the extended-length prefix; a no-op elsewhere
+///
+/// Applied to every path, not just long ones: a short destination folder can still end up
+/// over MAX_PATH (260) once a filename or `_N` suffix is joined onto it. `canonicalize`
+/// already returns prefixed paths on Windows, so this only changes not-yet-existing paths.
+fn long_path_safe(path: std::path::PathBuf) -> std::path::PathBuf {
+    if cfg!(windows) {
+        if let Some(extended) = extended_length_path(&path.to_string_lossy()) {
+            return std::path::PathBuf::from(extended);
+        }
+    }
+    path
+}
+
+/// Validate a path for an operation that moves, renames, deletes, reads or writes a file, or
+/// lists a folder: as `validate_path`, and it must also be inside the allowed roots (see `roots`).
+fn validate_allowed_path(path: &str) -> Result<std::path::PathBuf, CommandError> {
+    let validated = validate_path(path)?;
+    if !roots::is_allowed(&validated) {
+        return Err(CommandError::OutsideAllowedRoots(path.to_string()));
+    }
+    Ok(validated)
+}
+
 /// Validate that a path doesn't contain traversal sequences and resolves to a real location.
-/// Returns the canonicalized path on success.
+/// Returns the canonicalized path on success (long-path safe on Windows).
 fn validate_path(path: &str) -> Result<std::path::PathBuf, CommandError> {
     if path.contains("..") {
         return Err(CommandError::PathTraversal);
@@ -101,7 +237,7 @@ fn validate_path(path: &str) -> Result<std::path::PathBuf, CommandError> {
                 if canonical.to_string_lossy().contains("..") {
                     return Err(CommandError::PathTraversal);
                 }
-                return Ok(p.to_path_buf());
+                return Ok(long_path_safe(p.to_path_buf()));
             }
             if parent == check {
                 break; // reached root
@@ -118,7 +254,7 @@ fn validate_path(path: &str) -> Result<std::path::PathBuf, CommandError> {
     if canonical.to_string_lossy().contains("..") {
         return Err(CommandError::PathTraversal);
     }
-    Ok(canonical)
+    Ok(long_path_safe(canonical))
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -127,8 +263,19 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// Cancel a running copy/move reported through `file-op-progress` events
+///
+/// The partially copied destination file is removed and the original is left in place.
+/// Called from frontend with: invoke('cancel_file_operation', { opId: 'op-3' })
+#[tauri::command]
+fn cancel_file_operation(op_id: String) -> bool {
+    println!("[COMMAND] cancel_file_operation: {}", op_id);
+    fileop::cancel(&op_id)
+}
+
 /// Start watching a directory for new files
 ///
+/// Every watch profile's folder is watched as well.
 /// Called from frontend with: invoke('start_watching', { path: '/path/to/folder' })
 #[tauri::command]
 fn start_watching(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
@@ -150,8 +297,29 @@ fn start_watching(app_handle: tauri::AppHandle, path: String) -> Result<(), Stri
         return Err(format!("Path is not a directory: {}", path));
     }
 
+    // Profile folders are best-effort: a missing one is skipped rather than failing the start
+    let mut watch_paths = vec![path.clone()];
+    if let Ok(db) = get_db() {
+        for profile in db.get_watch_profiles().unwrap_or_default() {
+            if std::path::Path::new(&profile.watch_path).is_dir() && !watch_paths.contains(&profile.watch_path) {
+                watch_paths.push(profile.watch_path);
+            }
+        }
+    }
+
+    // Bring the file index up to date with what arrived while the app wasn't watching
+    let index_paths = watch_paths.clone();
+    std::thread::spawn(move || {
+        for path in index_paths {
+            match scan::list_files(std::path::Path::new(&path), usize::MAX) {
+                Ok(files) => update_file_index(std::path::Path::new(&path), &files),
+                Err(e) => eprintln!("[INDEX] Failed to index {}: {}", path, e),
+            }
+        }
+    });
+
     // Start the watcher in background thread
-    match watcher::start_watcher(app_handle, path) {
+    match watcher::start_watcher(app_handle, watch_paths) {
         Ok(()) => Ok(()),
         Err(e) => {
             WATCHER_STARTED.store(false, Ordering::SeqCst);
@@ -187,14 +355,15 @@ fn stop_watching() -> Result<(), String> {
 
 /// Move a file to a destination folder
 ///
-/// Called from frontend with: invoke('move_file', { sourcePath: '...', destFolder: '...' })
-#[tauri::command]
-fn move_file(source_path: String, dest_folder: String) -> Result<String, CommandError> {
+/// `reasoning` is the AI's reason for the folder, kept as the file's provenance.
+/// Called from frontend with: invoke('move_file', { sourcePath: '...', destFolder: '...', reasoning: '...' })
+#[tauri::command(async)]
+fn move_file(source_path: String, dest_folder: String, reasoning: Option<String>) -> Result<String, CommandError> {
     use std::fs;
 
     println!("[COMMAND] move_file: {} -> {}", source_path, dest_folder);
 
-    let source = validate_path(&source_path)?;
+    let source = validate_allowed_path(&source_path)?;
 
     if !source.exists() {
         return Err(CommandError::FileNotFound(source_path));
@@ -204,7 +373,7 @@ fn move_file(source_path: String, dest_folder: String) -> Result<String, Command
         return Err(CommandError::InvalidPath(format!("Source is not a file: {}", source_path)));
     }
 
-    let dest_dir_validated = validate_path(&dest_folder)?;
+    let dest_dir_validated = validate_allowed_path(&dest_folder)?;
     if !dest_dir_validated.exists() {
         fs::create_dir_all(&dest_dir_validated)?;
     }
@@ -214,11 +383,13 @@ fn move_file(source_path: String, dest_folder: String) -> Result<String, Command
 
     let dest_path = dest_dir_validated.join(filename);
 
-    if dest_path.exists() {
-        return Err(CommandError::DuplicateExists(dest_path.display().to_string()));
+    if let Some(existing) = filename::existing(&dest_dir_validated, filename) {
+        return Err(CommandError::DuplicateExists(existing.display().to_string()));
     }
 
-    fs::rename(&source, &dest_path)?;
+    checksum::move_verified(&source, &dest_path)?;
+    record_operation("move", &source, Some(&dest_path));
+    record_provenance(&source, &dest_path, reasoning);
 
     println!("[COMMAND] File moved successfully to: {}", dest_path.display());
     Ok(format!("Moved to {}", dest_path.display()))
@@ -227,14 +398,14 @@ fn move_file(source_path: String, dest_folder: String) -> Result<String, Command
 /// Move a file to a destination folder, auto-renaming if a duplicate exists
 ///
 /// Appends _1, _2, etc. to the filename (before extension) until a unique name is found.
-/// Called from frontend with: invoke('move_file_with_rename', { sourcePath: '...', destFolder: '...' })
-#[tauri::command]
-fn move_file_with_rename(source_path: String, dest_folder: String) -> Result<String, CommandError> {
+/// Called from frontend with: invoke('move_file_with_rename', { sourcePath: '...', destFolder: '...', reasoning: '...' })
+#[tauri::command(async)]
+fn move_file_with_rename(source_path: String, dest_folder: String, reasoning: Option<String>) -> Result<String, CommandError> {
     use std::fs;
 
     println!("[COMMAND] move_file_with_rename: {} -> {}", source_path, dest_folder);
 
-    let source = validate_path(&source_path)?;
+    let source = validate_allowed_path(&source_path)?;
     if !source.exists() {
         return Err(CommandError::FileNotFound(source_path));
     }
@@ -242,7 +413,7 @@ fn move_file_with_rename(source_path: String, dest_folder: String) -> Result<Str
         return Err(CommandError::InvalidPath(format!("Source is not a file: {}", source_path)));
     }
 
-    let dest_dir = validate_path(&dest_folder)?;
+    let dest_dir = validate_allowed_path(&dest_folder)?;
     if !dest_dir.exists() {
         fs::create_dir_all(&dest_dir)?;
     }
@@ -250,47 +421,57 @@ fn move_file_with_rename(source_path: String, dest_folder: String) -> Result<Str
     let filename = source.file_name()
         .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
 
-    let mut dest_path = dest_dir.join(filename);
-
-    // If file exists, find a unique name with _1, _2, etc.
-    if dest_path.exists() {
-        let stem = source.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("file");
-        let ext = source.extension()
-            .and_then(|e| e.to_str())
-            .map(|e| format!(".{}", e))
-            .unwrap_or_default();
-
-        let mut counter = 1u32;
-        loop {
-            let new_name = format!("{}_{}{}", stem, counter, ext);
-            dest_path = dest_dir.join(&new_name);
-            if !dest_path.exists() {
-                break;
-            }
-            counter += 1;
-            if counter > 9999 {
-                return Err(CommandError::IoError("Too many duplicate files at destination".to_string()));
-            }
-        }
-    }
+    let dest_path = unique_dest_path(&dest_dir, filename)?;
 
-    fs::rename(&source, &dest_path)?;
+    checksum::move_verified(&source, &dest_path)?;
+    record_operation("move", &source, Some(&dest_path));
+    record_provenance(&source, &dest_path, reasoning);
 
     println!("[COMMAND] File moved (with rename) to: {}", dest_path.display());
     Ok(format!("Moved to {}", dest_path.display()))
 }
 
+/// Pick a path in dest_dir for filename that doesn't exist yet
+///
+/// Appends _1, _2, etc. to the filename (before extension) until a unique name is found.
+/// Names count as taken in either Unicode form, so "Müller.pdf" doesn't land next to its
+/// decomposed twin.
+fn unique_dest_path(dest_dir: &std::path::Path, filename: &std::ffi::OsStr) -> Result<std::path::PathBuf, CommandError> {
+    if filename::existing(dest_dir, filename).is_none() {
+        return Ok(dest_dir.join(filename));
+    }
+
+    let original = std::path::Path::new(filename);
+    let stem = original.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file");
+    let ext = original.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+
+    let mut counter = 1u32;
+    loop {
+        let new_name = format!("{}_{}{}", stem, counter, ext);
+        if filename::existing(dest_dir, new_name.as_ref()).is_none() {
+            return Ok(dest_dir.join(new_name));
+        }
+        counter += 1;
+        if counter > 9999 {
+            return Err(CommandError::IoError("Too many duplicate files at destination".to_string()));
+        }
+    }
+}
+
 /// Replace an existing file at the destination with the source file.
-/// Called from frontend with: invoke('replace_file', { sourcePath: '...', destFolder: '...' })
-#[tauri::command]
-fn replace_file(source_path: String, dest_folder: String) -> Result<String, CommandError> {
+/// Called from frontend with: invoke('replace_file', { sourcePath: '...', destFolder: '...', reasoning: '...' })
+#[tauri::command(async)]
+fn replace_file(source_path: String, dest_folder: String, reasoning: Option<String>) -> Result<String, CommandError> {
     use std::fs;
 
     println!("[COMMAND] replace_file: {} -> {}", source_path, dest_folder);
 
-    let source = validate_path(&source_path)?;
+    let source = validate_allowed_path(&source_path)?;
     if !source.exists() {
         return Err(CommandError::FileNotFound(source_path));
     }
@@ -298,738 +479,6606 @@ fn replace_file(source_path: String, dest_folder: String) -> Result<String, Comm
         return Err(CommandError::InvalidPath(format!("Source is not a file: {}", source_path)));
     }
 
-    let dest_dir = validate_path(&dest_folder)?;
+    let dest_dir = validate_allowed_path(&dest_folder)?;
     let filename = source.file_name()
         .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
     let dest_path = dest_dir.join(filename);
 
-    // Remove existing file if it exists
-    if dest_path.exists() {
-        fs::remove_file(&dest_path)?;
+    // Remove existing file if it exists, in whichever Unicode form it's named
+    if let Some(existing) = filename::existing(&dest_dir, filename) {
+        fs::remove_file(existing)?;
     }
 
-    fs::rename(&source, &dest_path)?;
+    checksum::move_verified(&source, &dest_path)?;
+    record_operation("replace", &source, Some(&dest_path));
+    record_provenance(&source, &dest_path, reasoning);
 
     println!("[COMMAND] File replaced at: {}", dest_path.display());
     Ok(format!("Replaced {}", dest_path.display()))
 }
 
-/// Get the stored API key from the in-memory cache
-fn get_stored_api_key() -> Result<String, String> {
-    let key = API_KEY
-        .get()
-        .ok_or_else(|| "API key storage not initialized".to_string())?
-        .lock()
-        .unwrap_or_else(|e| e.into_inner())
-        .clone();
-    if key.is_empty() {
-        Err("No API key configured. Please set your OpenAI API key in Settings.".to_string())
-    } else {
-        Ok(key)
-    }
+// ============================================================
+// TYPED SETTINGS
+// ============================================================
+
+/// Apply the saved settings that live in memory (classifier options, provenance, cloud files)
+fn load_settings(db: &Database) {
+    load_privacy_mode(db);
+    load_sibling_context(db);
+    load_suggest_subfolders(db);
+    load_read_cloud_placeholders(db);
+    load_provenance_in_files(db);
+    load_local_only(db);
+    load_embedding_fallback(db);
+    load_ai_endpoint(db);
+    load_model_routes(db);
+    load_fallback_chain(db);
+    load_prompt_token_budget(db);
+    load_ai_timeouts(db);
+    load_max_concurrent_requests(db);
+    load_chat_export_subfolder(db);
+    load_api_budget(db);
+    load_folder_aliases(db);
 }
 
-/// Store the API key securely on the Rust side
-///
-/// The key is held in memory and persisted to the SQLite database.
-/// This avoids passing it over IPC on every classify call.
+/// Get every option in the settings schema as a typed value, with defaults for ones never set
+/// Called from frontend with: invoke('get_all_settings')
 #[tauri::command]
-fn set_api_key(key: String) -> Result<(), String> {
-    // Store in memory
-    if let Some(mutex) = API_KEY.get() {
-        let mut stored = mutex.lock().unwrap_or_else(|e| e.into_inner());
-        *stored = key.clone();
-    }
-    // Persist to database
-    if let Ok(db) = get_db() {
-        db.set_setting("api_key", &key)
-            .map_err(|e| format!("Failed to save API key: {}", e))?;
-    }
-    Ok(())
+async fn get_all_settings() -> Result<serde_json::Map<String, serde_json::Value>, DbError> {
+    db_worker()?.call(settings::get_all).await
 }
 
-/// Check if an API key is stored (returns the key for settings display)
+/// Save several settings at once, e.g. everything on the settings page
+///
+/// Each value is checked against the schema first (its type, the allowed names and the same
+/// checks the single-setting commands make); if any is invalid, nothing is saved. Numbers
+/// out of range are clamped. The new values take effect right away; returns all settings.
+/// Called from frontend with: invoke('set_settings', { values: { privacy_mode: true, max_concurrent_requests: 4 } })
 #[tauri::command]
-fn get_api_key() -> Result<String, String> {
-    let key = API_KEY
-        .get()
-        .ok_or_else(|| "Not initialized".to_string())?
-        .lock()
-        .unwrap_or_else(|e| e.into_inner())
-        .clone();
-    Ok(key)
+async fn set_settings(
+    values: serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Map<String, serde_json::Value>, CommandError> {
+    println!("[COMMAND] set_settings: {}", values.keys().cloned().collect::<Vec<_>>().join(", "));
+    let prepared = settings::prepare(values).map_err(CommandError::InvalidInput)?;
+    let all = db_worker()?
+        .call(move |db| {
+            db.set_settings(&prepared)?;
+            load_settings(db);
+            settings::get_all(db)
+        })
+        .await?;
+    Ok(all)
 }
 
-/// Classify a file using AI
+// ============================================================
+// SETUP PROFILES (EXPORT / IMPORT)
+// ============================================================
+
+/// Save settings, rules, watch profiles and folder preferences to a JSON file at `path`
 ///
-/// Called from frontend with: invoke('classify_file', { filename: '...', availableFolders: [...], correctionHistory: [...] })
+/// The API key and a custom AI endpoint's extra headers are left out, so the file can be
+/// shared. Returns what was written.
+/// Called from frontend with: invoke('export_profile', { path: '...' })
 #[tauri::command]
-async fn classify_file(
-    filename: String,
-    available_folders: Vec<String>,
-    correction_history: Vec<String>,
-) -> Result<classifier::Classification, String> {
-    println!("[COMMAND] classify_file: {} (with {} corrections)", filename, correction_history.len());
-    let api_key = get_stored_api_key()?;
+async fn export_profile(path: String) -> Result<setup_profile::SetupProfile, CommandError> {
+    println!("[COMMAND] export_profile: {}", path);
+    let target = validate_allowed_path(&path)?;
+    if !target.parent().is_some_and(|dir| dir.is_dir()) {
+        return Err(CommandError::FileNotFound(path));
+    }
 
-    classifier::classify_file(api_key, filename, available_folders, correction_history).await
+    let profile = db_worker()?.call(setup_profile::export).await?;
+    let json = serde_json::to_string_pretty(&profile).map_err(|e| CommandError::IoError(e.to_string()))?;
+    std::fs::write(&target, json)?;
+    println!(
+        "[COMMAND] export_profile: {} rule(s), {} watch profile(s)",
+        profile.rules.len(),
+        profile.watch_profiles.len()
+    );
+    Ok(profile)
 }
 
-/// Classify an image file using OCR text extraction + GPT-3.5 (cheap path)
+/// Merge a profile saved by `export_profile` (e.g. on another computer) into this setup
 ///
-/// Extracts text from image using Tesseract OCR, then classifies with GPT-3.5.
-/// Returns error if OCR extracts too little text (caller should fall back to vision).
+/// Nothing is saved if any part of the file is invalid. Imported settings take effect
+/// right away, except a custom AI endpoint: it's returned in `endpoint_to_confirm` for the
+/// user to confirm with `set_ai_endpoint`, since it would be sent the API key.
+/// Called from frontend with: invoke('import_profile', { path: '...' })
 #[tauri::command]
-async fn classify_image_with_ocr(
-    file_path: String,
-    filename: String,
-    available_folders: Vec<String>,
-    correction_history: Vec<String>,
-) -> Result<classifier::Classification, String> {
-    println!("[COMMAND] classify_image_with_ocr: {} (OCR mode)", filename);
-    let api_key = get_stored_api_key()?;
-    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
-    let file_path = validated.to_string_lossy().to_string();
-
-    // Extract text using Tesseract OCR
-    let text_content = classifier::extract_image_text(&file_path)?;
-
-    // If OCR extracted too little text, signal caller to use vision fallback
-    if text_content.len() < 20 {
-        return Err("OCR_INSUFFICIENT_TEXT".to_string());
+async fn import_profile(path: String) -> Result<setup_profile::ImportReport, CommandError> {
+    println!("[COMMAND] import_profile: {}", path);
+    let source = validate_path(&path)?;
+    if !source.is_file() {
+        return Err(CommandError::FileNotFound(path));
     }
+    let profile: setup_profile::SetupProfile = serde_json::from_str(&std::fs::read_to_string(&source)?)
+        .map_err(|e| CommandError::InvalidInput(format!("Not a profile file: {}", e)))?;
+
+    let report = db_worker()?
+        .call(|db| {
+            let report = setup_profile::import(db, profile);
+            if report.is_ok() {
+                load_settings(db);
+            }
+            Ok(report)
+        })
+        .await??;
+    println!(
+        "[COMMAND] import_profile: {} setting(s), {} rule(s) added, {} watch profile(s) added, {} updated",
+        report.settings, report.rules_added, report.watch_profiles_added, report.watch_profiles_updated
+    );
+    Ok(report)
+}
 
-    println!("[COMMAND] OCR extracted {} chars from {}", text_content.len(), filename);
+// ============================================================
+// CONFLICT POLICY
+// ============================================================
 
-    classifier::classify_with_text_content(api_key, filename, text_content, available_folders, correction_history).await
+/// What to do when a file with the same name already exists at the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ConflictPolicy {
+    Skip,
+    Rename,
+    Replace,
+    Error,
 }
 
-/// Classify an image file using GPT-4o vision (reads actual image content)
-///
-/// Called from frontend with: invoke('classify_image_file', { apiKey: '...', filePath: '...', filename: '...', availableFolders: [...], correctionHistory: [...] })
-#[tauri::command]
-async fn classify_image_file(
-    file_path: String,
-    filename: String,
-    available_folders: Vec<String>,
-    correction_history: Vec<String>,
-) -> Result<classifier::Classification, String> {
-    println!("[COMMAND] classify_image_file: {} (vision mode)", filename);
-    let api_key = get_stored_api_key()?;
-    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
-    let file_path = validated.to_string_lossy().to_string();
+impl ConflictPolicy {
+    fn as_str(self) -> &'static str {
+        match self {
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Rename => "rename",
+            ConflictPolicy::Replace => "replace",
+            ConflictPolicy::Error => "error",
+        }
+    }
 
-    classifier::classify_image_file(api_key, file_path, filename, available_folders, correction_history).await
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "skip" => Some(ConflictPolicy::Skip),
+            "rename" => Some(ConflictPolicy::Rename),
+            "replace" => Some(ConflictPolicy::Replace),
+            "error" => Some(ConflictPolicy::Error),
+            _ => None,
+        }
+    }
 }
 
-/// Classify a file using extracted text content (second pass for PDFs, etc.)
-///
-/// Called from frontend with: invoke('classify_with_content', { filePath: '...', filename: '...', availableFolders: [...], correctionHistory: [...] })
-#[tauri::command]
-async fn classify_with_content(
-    file_path: String,
-    filename: String,
-    available_folders: Vec<String>,
-    correction_history: Vec<String>,
-) -> Result<classifier::Classification, String> {
-    println!("[COMMAND] classify_with_content: {} (content extraction mode)", filename);
-    let api_key = get_stored_api_key()?;
-    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
-    let file_path = validated.to_string_lossy().to_string();
+const CONFLICT_POLICY_SETTING: &str = "conflict_policy";
 
-    // Determine file type and extract text
-    let ext = file_path.rsplit('.').next().unwrap_or("").to_lowercase();
+/// The user's default conflict policy (auto-rename if unset, matching batch moves)
+fn stored_conflict_policy() -> ConflictPolicy {
+    get_db()
+        .ok()
+        .and_then(|db| db.get_setting(CONFLICT_POLICY_SETTING).ok().flatten())
+        .and_then(|value| ConflictPolicy::parse(&value))
+        .unwrap_or(ConflictPolicy::Rename)
+}
 
-    let text_content = match ext.as_str() {
-        "pdf" => {
-            classifier::extract_pdf_text(&file_path)?
-        }
-        "txt" | "md" | "csv" => {
-            // Read first 500 chars of plain text files
-            let content = std::fs::read_to_string(&file_path)
-                .map_err(|e| format!("Failed to read text file: {}", e))?;
-            content.chars().take(500).collect()
-        }
-        _ => {
-            return Err(format!("Unsupported file type for content extraction: .{}", ext));
-        }
-    };
+/// Result of a move performed under a conflict policy
+#[derive(Debug, Serialize)]
+struct PolicyMoveResult {
+    outcome: String, // "moved", "renamed", "replaced", "skipped"
+    dest_path: Option<String>,
+    #[serde(skip)]
+    content_hash: Option<String>, // when the move had to copy, and so hashed the file
+    #[serde(skip)]
+    activity_id: Option<i64>, // activity log row, when the move was logged
+}
 
-    if text_content.trim().is_empty() {
-        return Err("No text content could be extracted from the file".to_string());
+/// Move a validated source file into dest_dir as `name`, resolving conflicts by policy
+fn move_with_policy(
+    source: &std::path::Path,
+    dest_dir: &std::path::Path,
+    name: &std::ffi::OsStr,
+    policy: ConflictPolicy,
+) -> Result<PolicyMoveResult, CommandError> {
+    let mut dest_path = dest_dir.join(name);
+    let mut outcome = "moved";
+    let mut op_type = "move";
+
+    if let Some(existing) = filename::existing(dest_dir, name) {
+        match policy {
+            ConflictPolicy::Skip => {
+                return Ok(PolicyMoveResult {
+                    outcome: "skipped".to_string(),
+                    dest_path: None,
+                    content_hash: None,
+                    activity_id: None,
+                })
+            }
+            ConflictPolicy::Error => {
+                return Err(CommandError::DuplicateExists(existing.display().to_string()))
+            }
+            ConflictPolicy::Rename => {
+                dest_path = unique_dest_path(dest_dir, name)?;
+                outcome = "renamed";
+            }
+            ConflictPolicy::Replace => {
+                std::fs::remove_file(&existing)?;
+                outcome = "replaced";
+                op_type = "replace";
+            }
+        }
     }
 
-    classifier::classify_with_text_content(api_key, filename, text_content, available_folders, correction_history).await
+    let hash = checksum::move_verified(source, &dest_path)?;
+    record_operation(op_type, source, Some(&dest_path));
+
+    Ok(PolicyMoveResult {
+        outcome: outcome.to_string(),
+        dest_path: Some(dest_path.to_string_lossy().to_string()),
+        content_hash: hash,
+        activity_id: None,
+    })
 }
 
-/// Scan a directory and return list of subdirectories
+/// Move a file, resolving a name conflict with the given policy (or the stored default)
 ///
-/// Called from frontend with: invoke('scan_folders', { path: '...', recursive: true })
+/// Called from frontend with: invoke('move_file_with_policy', { sourcePath: '...', destFolder: '...', policy: 'rename' })
+#[tauri::command(async)]
+fn move_file_with_policy(
+    source_path: String,
+    dest_folder: String,
+    policy: Option<ConflictPolicy>,
+) -> Result<PolicyMoveResult, CommandError> {
+    let policy = policy.unwrap_or_else(stored_conflict_policy);
+    println!("[COMMAND] move_file_with_policy: {} -> {} ({})", source_path, dest_folder, policy.as_str());
+
+    let source = validate_allowed_path(&source_path)?;
+    if !source.exists() {
+        return Err(CommandError::FileNotFound(source_path));
+    }
+    if !source.is_file() {
+        return Err(CommandError::InvalidPath(format!("Source is not a file: {}", source_path)));
+    }
+
+    let dest_dir = validate_allowed_path(&dest_folder)?;
+    if !dest_dir.exists() {
+        std::fs::create_dir_all(&dest_dir)?;
+    }
+
+    let filename = source.file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
+
+    let result = move_with_policy(&source, &dest_dir, filename, policy)?;
+    println!("[COMMAND] move_file_with_policy: {}", result.outcome);
+    Ok(result)
+}
+
+/// Get the default conflict policy used by batch moves
 #[tauri::command]
-fn scan_folders(path: String, recursive: Option<bool>) -> Result<Vec<String>, String> {
-    use std::fs;
-    use std::path::Path;
+fn get_conflict_policy() -> ConflictPolicy {
+    stored_conflict_policy()
+}
 
-    println!("[COMMAND] scan_folders: {} (recursive: {:?})", path, recursive);
+/// Set the default conflict policy used by batch moves
+/// Called from frontend with: invoke('set_conflict_policy', { policy: 'skip' })
+#[tauri::command]
+async fn set_conflict_policy(policy: ConflictPolicy) -> Result<(), DbError> {
+    println!("[COMMAND] set_conflict_policy: {}", policy.as_str());
+    db_worker()?
+        .call(move |db| db.set_setting(CONFLICT_POLICY_SETTING, policy.as_str()))
+        .await
+}
 
-    let dir = Path::new(&path);
+// ============================================================
+// CLASSIFICATION MODE (AI OR TYPE BUCKETS)
+// ============================================================
 
-    if !dir.exists() {
-        return Err(format!("Path does not exist: {}", path));
-    }
+const CLASSIFICATION_MODE_SETTING: &str = "classification_mode";
+const BUCKET_FOLDER_SETTING: &str = "bucket_folder";
 
-    if !dir.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
-    }
+/// Whether unmatched files go to the AI or into type buckets, and where the buckets live
+#[derive(Debug, Serialize)]
+struct ClassificationModeSettings {
+    mode: buckets::ClassificationMode,
+    bucket_folder: Option<String>, // None: buckets are created next to each file
+}
 
-    let mut folders = Vec::new();
+fn stored_classification_mode(db: &Database) -> Result<ClassificationModeSettings, DbError> {
+    Ok(ClassificationModeSettings {
+        mode: db
+            .get_setting(CLASSIFICATION_MODE_SETTING)?
+            .and_then(|value| buckets::ClassificationMode::parse(&value))
+            .unwrap_or_default(),
+        bucket_folder: db.get_setting(BUCKET_FOLDER_SETTING)?.filter(|f| !f.is_empty()),
+    })
+}
 
-    if recursive.unwrap_or(false) {
-        collect_folders_recursive(dir, dir, &mut folders)?;
-    } else {
-        match fs::read_dir(dir) {
-            Ok(entries) => {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_dir() {
-                            if let Some(folder_name) = path.file_name() {
-                                if let Some(name_str) = folder_name.to_str() {
-                                    folders.push(name_str.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => return Err(format!("Failed to read directory: {}", e)),
-        }
+/// Folder the type buckets go in for a file: the configured one, or the file's own folder
+fn bucket_base_folder(file: &std::path::Path, bucket_folder: Option<&str>) -> std::path::PathBuf {
+    match bucket_folder {
+        Some(folder) => std::path::PathBuf::from(folder),
+        None => file.parent().map(|p| p.to_path_buf()).unwrap_or_default(),
     }
+}
 
-    folders.sort();
-    println!("[COMMAND] Found {} folders", folders.len());
-    Ok(folders)
+/// Get the classification mode and bucket folder
+/// Called from frontend with: invoke('get_classification_mode')
+#[tauri::command]
+async fn get_classification_mode() -> Result<ClassificationModeSettings, DbError> {
+    db_worker()?.call(stored_classification_mode).await
 }
 
-fn collect_folders_recursive(
-    base: &std::path::Path,
-    current: &std::path::Path,
-    folders: &mut Vec<String>,
-) -> Result<(), String> {
-    let entries = std::fs::read_dir(current).map_err(|e| e.to_string())?;
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if path.is_dir() {
-                if let Some(name) = path.strip_prefix(base).ok().and_then(|r| r.to_str()) {
-                    folders.push(name.to_string());
-                }
-                collect_folders_recursive(base, &path, folders)?;
-            }
-        }
-    }
-    Ok(())
+/// Set the classification mode ("ai" or "buckets") and bucket folder (None or "" for next to each file)
+/// Called from frontend with: invoke('set_classification_mode', { mode: 'buckets', bucketFolder: '...' })
+#[tauri::command]
+async fn set_classification_mode(mode: buckets::ClassificationMode, bucket_folder: Option<String>) -> Result<(), DbError> {
+    println!("[COMMAND] set_classification_mode: {} ({:?})", mode.as_str(), bucket_folder);
+    db_worker()?
+        .call(move |db| {
+            db.set_setting(CLASSIFICATION_MODE_SETTING, mode.as_str())?;
+            db.set_setting(BUCKET_FOLDER_SETTING, bucket_folder.as_deref().unwrap_or(""))
+        })
+        .await
 }
 
-/// Get a preview of a file's content
+/// Classify a file into a type bucket (Documents, Images, Archives, Installers, Media) without AI
 ///
-/// For images: returns base64-encoded thumbnail data URL
+/// The bucket folders go in `base_folder`, else the stored bucket folder, else the file's folder.
+/// Called from frontend with: invoke('classify_by_type', { filePath: '...', baseFolder: null })
+#[tauri::command]
+async fn classify_by_type(file_path: String, base_folder: Option<String>) -> Result<classifier::Classification, CommandError> {
+    println!("[COMMAND] classify_by_type: {}", file_path);
+    let validated = validate_path(&file_path)?;
+    if !validated.is_file() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+    let base_folder = match base_folder.filter(|f| !f.is_empty()) {
+        Some(folder) => Some(folder),
+        None => db_worker()?.call(stored_classification_mode).await?.bucket_folder,
+    };
+    let base = bucket_base_folder(&validated, base_folder.as_deref());
+    Ok(buckets::classify(&validated, &base))
+}
+
+// ============================================================
+// PRIVACY MODE
+// ============================================================
+
+const PRIVACY_MODE_SETTING: &str = "privacy_mode";
+
+/// Apply the saved privacy mode to the classifier
+fn load_privacy_mode(db: &Database) {
+    match db.get_setting(PRIVACY_MODE_SETTING) {
+        Ok(value) => classifier::set_privacy_mode(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load privacy mode: {}", e),
+    }
+}
+
+/// Whether filenames are kept out of AI requests
+/// Called from frontend with: invoke('get_privacy_mode')
+#[tauri::command]
+fn get_privacy_mode() -> bool {
+    classifier::privacy_mode()
+}
+
+/// Turn privacy mode on or off
+///
+/// While it's on, prompts carry only the file's content and a stand-in name that keeps the
+/// extension, never the real filename (which may contain names or ID numbers).
+/// Called from frontend with: invoke('set_privacy_mode', { enabled: true })
+#[tauri::command]
+async fn set_privacy_mode(enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_privacy_mode: {}", enabled);
+    db_worker()?
+        .call(move |db| db.set_setting(PRIVACY_MODE_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    classifier::set_privacy_mode(enabled);
+    Ok(())
+}
+
+// ============================================================
+// SIBLING CONTEXT
+// ============================================================
+
+const SIBLING_CONTEXT_SETTING: &str = "sibling_context";
+
+/// Apply the saved sibling-context setting to the classifier
+fn load_sibling_context(db: &Database) {
+    match db.get_setting(SIBLING_CONTEXT_SETTING) {
+        Ok(value) => classifier::set_sibling_context(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load sibling context: {}", e),
+    }
+}
+
+/// Whether prompts list a few files already in each candidate folder
+/// Called from frontend with: invoke('get_sibling_context')
+#[tauri::command]
+fn get_sibling_context() -> bool {
+    classifier::sibling_context()
+}
+
+/// Turn sibling context on or off
+///
+/// While it's on, each folder in the prompt is listed with about five filenames already in
+/// it (from the file index, or a quick listing), the ones named most like the file being
+/// classified. Skipped in privacy mode.
+/// Called from frontend with: invoke('set_sibling_context', { enabled: true })
+#[tauri::command]
+async fn set_sibling_context(enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_sibling_context: {}", enabled);
+    db_worker()?
+        .call(move |db| db.set_setting(SIBLING_CONTEXT_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    classifier::set_sibling_context(enabled);
+    Ok(())
+}
+
+// ============================================================
+// SUBFOLDER SUGGESTIONS
+// ============================================================
+
+const SUGGEST_SUBFOLDERS_SETTING: &str = "suggest_subfolders";
+
+/// Apply the saved subfolder-suggestion setting to the classifier
+fn load_suggest_subfolders(db: &Database) {
+    match db.get_setting(SUGGEST_SUBFOLDERS_SETTING) {
+        Ok(value) => classifier::set_suggest_subfolders(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load subfolder suggestions: {}", e),
+    }
+}
+
+/// Whether the classifier also picks a subfolder inside the course folder
+/// Called from frontend with: invoke('get_suggest_subfolders')
+#[tauri::command]
+fn get_suggest_subfolders() -> bool {
+    classifier::suggest_subfolders()
+}
+
+/// Turn subfolder suggestions on or off
+///
+/// While they're on, each folder in the prompt is listed with the subfolders it already has
+/// (Lectures, Problem Sets...), and the AI picks one with a confidence of its own. Files go
+/// into the subfolder when that confidence is at least `SUBFOLDER_MIN_CONFIDENCE`.
+/// Called from frontend with: invoke('set_suggest_subfolders', { enabled: true })
+#[tauri::command]
+async fn set_suggest_subfolders(enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_suggest_subfolders: {}", enabled);
+    db_worker()?
+        .call(move |db| db.set_setting(SUGGEST_SUBFOLDERS_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    classifier::set_suggest_subfolders(enabled);
+    Ok(())
+}
+
+/// Find the subfolders of each folder for the classifier to choose from
+async fn find_subfolders(folders: Vec<String>) {
+    let _ = tokio::task::spawn_blocking(move || {
+        let watch_profiles = stored_watch_profiles();
+        let skip = |path: &std::path::Path| profiles::excluded(&watch_profiles, path);
+        for folder in folders {
+            match scan::list_folders(std::path::Path::new(&folder), true, classifier::SUBFOLDER_LIMIT, None, &skip) {
+                Ok(subfolders) => classifier::set_subfolders(&folder, subfolders),
+                Err(e) => eprintln!("[CLASSIFIER] Failed to list subfolders of {}: {}", folder, e),
+            }
+        }
+    })
+    .await;
+}
+
+// ============================================================
+// PROVENANCE
+// ============================================================
+
+const PROVENANCE_IN_FILES_SETTING: &str = "provenance_in_files";
+
+/// Apply the saved setting for writing provenance onto moved files
+fn load_provenance_in_files(db: &Database) {
+    match db.get_setting(PROVENANCE_IN_FILES_SETTING) {
+        Ok(value) => provenance::set_write_to_files(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load provenance setting: {}", e),
+    }
+}
+
+/// Whether provenance is also written onto moved files
+/// Called from frontend with: invoke('get_provenance_in_files')
+#[tauri::command]
+fn get_provenance_in_files() -> bool {
+    provenance::write_to_files()
+}
+
+/// Turn writing provenance onto moved files on or off
+///
+/// Provenance is always kept in the database; with this on it's also stored on the file
+/// (an extended attribute, or an alternate data stream on Windows) so it travels with it.
+/// Called from frontend with: invoke('set_provenance_in_files', { enabled: true })
+#[tauri::command]
+async fn set_provenance_in_files(enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_provenance_in_files: {}", enabled);
+    db_worker()?
+        .call(move |db| db.set_setting(PROVENANCE_IN_FILES_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    provenance::set_write_to_files(enabled);
+    Ok(())
+}
+
+/// Where a file was before it was organized, when, and why it went where it is
+///
+/// Looked up in the database, falling back to provenance written onto the file.
+/// Called from frontend with: invoke('get_file_provenance', { path: '...' })
+#[tauri::command]
+async fn get_file_provenance(path: String) -> Result<Option<db::Provenance>, CommandError> {
+    let validated = validate_path(&path)?;
+    let key = validated.to_string_lossy().to_string();
+    let recorded = db_worker()?.call(move |db| db.get_provenance(&key)).await?;
+    Ok(recorded.or_else(|| provenance::read_from_file(&validated)))
+}
+
+// ============================================================
+// CLOUD PLACEHOLDERS
+// ============================================================
+
+const READ_CLOUD_PLACEHOLDERS_SETTING: &str = "read_cloud_placeholders";
+
+/// Apply the saved setting for reading online-only files
+fn load_read_cloud_placeholders(db: &Database) {
+    match db.get_setting(READ_CLOUD_PLACEHOLDERS_SETTING) {
+        Ok(value) => metadata::set_read_cloud_placeholders(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load cloud placeholder setting: {}", e),
+    }
+}
+
+/// Whether content of online-only cloud files is read (downloading them)
+/// Called from frontend with: invoke('get_read_cloud_placeholders')
+#[tauri::command]
+fn get_read_cloud_placeholders() -> bool {
+    metadata::read_cloud_placeholders()
+}
+
+/// Allow or stop reading online-only cloud files
+///
+/// Off by default: OneDrive/iCloud placeholders are classified by filename, and preview,
+/// OCR and content extraction skip them so the sync client doesn't download them.
+/// Called from frontend with: invoke('set_read_cloud_placeholders', { enabled: true })
+#[tauri::command]
+async fn set_read_cloud_placeholders(enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_read_cloud_placeholders: {}", enabled);
+    db_worker()?
+        .call(move |db| db.set_setting(READ_CLOUD_PLACEHOLDERS_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    metadata::set_read_cloud_placeholders(enabled);
+    Ok(())
+}
+
+// ============================================================
+// LOCAL-ONLY MODE
+// ============================================================
+
+const LOCAL_ONLY_SETTING: &str = "local_only";
+const AI_PROVIDER_SETTING: &str = "ai_provider";
+
+/// Whether cloud AI is switched off, and which provider classifies files
+#[derive(Debug, Serialize)]
+struct LocalOnlySettings {
+    enabled: bool,
+    provider: classifier::Provider,
+    ai_available: bool, // false: files are sorted by rules, the correction cache and type buckets
+}
+
+/// Apply the saved provider and local-only switch to the classifier
+fn load_local_only(db: &Database) {
+    let provider = db.get_setting(AI_PROVIDER_SETTING).ok().flatten().and_then(|p| p.parse().ok());
+    classifier::set_provider(provider.unwrap_or_default());
+    match db.get_setting(LOCAL_ONLY_SETTING) {
+        Ok(value) => classifier::set_local_only(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load local-only mode: {}", e),
+    }
+}
+
+fn local_only_settings() -> LocalOnlySettings {
+    LocalOnlySettings {
+        enabled: classifier::local_only(),
+        provider: classifier::provider(),
+        ai_available: classifier::ai_available(),
+    }
+}
+
+/// Get the local-only switch and AI provider
+/// Called from frontend with: invoke('get_local_only')
+#[tauri::command]
+fn get_local_only() -> LocalOnlySettings {
+    local_only_settings()
+}
+
+/// Turn local-only mode on or off and choose the AI provider ("openai", "ollama" or "mock")
+///
+/// While it's on, the classifier refuses every request that would leave this computer or
+/// local network. With Ollama as the provider, classification (including of OCR text) runs
+/// on it; otherwise files are sorted by rules, past corrections and type buckets.
+/// Called from frontend with: invoke('set_local_only', { enabled: true, provider: 'ollama' })
+#[tauri::command]
+async fn set_local_only(enabled: bool, provider: classifier::Provider) -> Result<LocalOnlySettings, DbError> {
+    println!("[COMMAND] set_local_only: {} ({:?})", enabled, provider);
+    db_worker()?
+        .call(move |db| {
+            db.set_setting(AI_PROVIDER_SETTING, provider.as_str())?;
+            db.set_setting(LOCAL_ONLY_SETTING, if enabled { "true" } else { "false" })
+        })
+        .await?;
+    classifier::set_provider(provider);
+    classifier::set_local_only(enabled);
+    Ok(local_only_settings())
+}
+
+// ============================================================
+// CUSTOM AI ENDPOINT
+// ============================================================
+
+const AI_ENDPOINT_SETTING: &str = "ai_endpoint";
+
+/// Apply the saved OpenAI-compatible endpoint (if any) to the classifier
+fn load_ai_endpoint(db: &Database) {
+    match db.get_setting(AI_ENDPOINT_SETTING) {
+        Ok(json) => classifier::set_endpoint_override(
+            json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default(),
+        ),
+        Err(e) => eprintln!("[APP] Failed to load the AI endpoint: {}", e),
+    }
+}
+
+/// Get the custom base URL, extra headers and model names for OpenAI requests
+/// Called from frontend with: invoke('get_ai_endpoint')
+#[tauri::command]
+fn get_ai_endpoint() -> classifier::EndpointOverride {
+    classifier::endpoint_override()
+}
+
+/// Send OpenAI requests to an OpenAI-compatible server instead of api.openai.com
+///
+/// Blank fields fall back to the defaults, so an empty base URL restores api.openai.com.
+/// Called from frontend with: invoke('set_ai_endpoint', { endpoint: { base_url: 'http://localhost:4000/v1', headers: { 'X-Team': 'research' }, text_model: null, vision_model: null } })
+#[tauri::command]
+async fn set_ai_endpoint(endpoint: classifier::EndpointOverride) -> Result<classifier::EndpointOverride, CommandError> {
+    let endpoint = endpoint.normalized();
+    endpoint.validate().map_err(CommandError::InvalidInput)?;
+    println!(
+        "[COMMAND] set_ai_endpoint: {} ({} extra header(s))",
+        endpoint.base_url.as_deref().unwrap_or("default"),
+        endpoint.headers.len()
+    );
+
+    let json = serde_json::to_string(&endpoint).map_err(|e| CommandError::IoError(e.to_string()))?;
+    db_worker()?.call(move |db| db.set_setting(AI_ENDPOINT_SETTING, &json)).await?;
+    classifier::set_endpoint_override(endpoint.clone());
+    Ok(endpoint)
+}
+
+// ============================================================
+// MODEL ROUTING
+// ============================================================
+
+const MODEL_ROUTES_SETTING: &str = "model_routes";
+
+/// Apply the saved per-request-kind models (if any) to the classifier
+fn load_model_routes(db: &Database) {
+    match db.get_setting(MODEL_ROUTES_SETTING) {
+        Ok(json) => classifier::set_model_routes(json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default()),
+        Err(e) => eprintln!("[APP] Failed to load the model routes: {}", e),
+    }
+}
+
+/// Get which model filename-only, content and image requests go to
+/// Called from frontend with: invoke('get_model_routes')
+#[tauri::command]
+fn get_model_routes() -> classifier::ModelRoutes {
+    classifier::model_routes()
+}
+
+/// Choose the model for each kind of OpenAI request; blank kinds use the endpoint's models
+/// Called from frontend with: invoke('set_model_routes', { routes: { filename: 'gpt-4o-mini', content: 'gpt-4o-mini', vision: 'gemini-2.0-flash' } })
+#[tauri::command]
+async fn set_model_routes(routes: classifier::ModelRoutes) -> Result<classifier::ModelRoutes, CommandError> {
+    let routes = routes.normalized();
+    println!(
+        "[COMMAND] set_model_routes: filename {}, content {}, vision {}",
+        routes.filename.as_deref().unwrap_or("default"),
+        routes.content.as_deref().unwrap_or("default"),
+        routes.vision.as_deref().unwrap_or("default")
+    );
+
+    let json = serde_json::to_string(&routes).map_err(|e| CommandError::IoError(e.to_string()))?;
+    db_worker()?.call(move |db| db.set_setting(MODEL_ROUTES_SETTING, &json)).await?;
+    classifier::set_model_routes(routes.clone());
+    Ok(routes)
+}
+
+// ============================================================
+// FOLDER ALIASES
+// ============================================================
+
+/// Load the folder aliases the classifier lists in prompts and resolves answers with
+fn load_folder_aliases(db: &Database) {
+    match db.get_folder_aliases() {
+        Ok(aliases) => folder_match::set_aliases(aliases),
+        Err(e) => eprintln!("[APP] Failed to load folder aliases: {}", e),
+    }
+}
+
+/// Get the other names given to destination folders
+/// Called from frontend with: invoke('get_folder_aliases')
+#[tauri::command]
+fn get_folder_aliases() -> Vec<folder_match::FolderAlias> {
+    folder_match::aliases()
+}
+
+/// Replace the folder aliases, e.g. "Operations Research" for the folder `OR`
+///
+/// The folder can be a full path or just its name. Aliases are shown to the AI next to
+/// their folder, and an answer naming one is sorted into that folder.
+/// Called from frontend with: invoke('set_folder_aliases', { aliases: [{ alias: 'Operations Research', folder: 'OR' }] })
+#[tauri::command]
+async fn set_folder_aliases(aliases: Vec<folder_match::FolderAlias>) -> Result<Vec<folder_match::FolderAlias>, CommandError> {
+    let aliases: Vec<folder_match::FolderAlias> = aliases
+        .into_iter()
+        .map(|a| folder_match::FolderAlias { alias: a.alias.trim().to_string(), folder: a.folder.trim().to_string() })
+        .filter(|a| !a.alias.is_empty())
+        .collect();
+    if let Some(alias) = aliases.iter().find(|a| a.folder.is_empty()) {
+        return Err(CommandError::InvalidPath(format!("No folder given for \"{}\"", alias.alias)));
+    }
+    println!("[COMMAND] set_folder_aliases: {} alias(es)", aliases.len());
+
+    let saved = db_worker()?
+        .call(move |db| {
+            db.set_folder_aliases(&aliases)?;
+            load_folder_aliases(db);
+            db.get_folder_aliases()
+        })
+        .await?;
+    Ok(saved)
+}
+
+// ============================================================
+// AI FALLBACK CHAIN
+// ============================================================
+
+const FALLBACK_CHAIN_SETTING: &str = "ai_fallback_chain";
+
+/// Apply the saved fallback chain (if any) to the classifier
+fn load_fallback_chain(db: &Database) {
+    match db.get_setting(FALLBACK_CHAIN_SETTING) {
+        Ok(json) => classifier::set_fallback_chain(
+            json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default(),
+        ),
+        Err(e) => eprintln!("[APP] Failed to load the AI fallback chain: {}", e),
+    }
+}
+
+/// Get the providers and models classification falls back through (empty: only the chosen provider)
+/// Called from frontend with: invoke('get_fallback_chain')
+#[tauri::command]
+fn get_fallback_chain() -> Vec<classifier::FallbackTier> {
+    classifier::fallback_chain()
+}
+
+/// Try these providers and models in order, moving to the next on auth, quota or timeout errors
+///
+/// Each classification records which step answered in its `tier` field.
+/// Called from frontend with: invoke('set_fallback_chain', { chain: [{ provider: 'openai', model: 'gpt-4o-mini' }, { provider: 'ollama', model: null }, { provider: 'mock', model: null }] })
+#[tauri::command]
+async fn set_fallback_chain(chain: Vec<classifier::FallbackTier>) -> Result<Vec<classifier::FallbackTier>, CommandError> {
+    classifier::validate_fallback_chain(&chain).map_err(CommandError::InvalidInput)?;
+    println!(
+        "[COMMAND] set_fallback_chain: {}",
+        chain.iter().map(classifier::FallbackTier::label).collect::<Vec<_>>().join(" -> ")
+    );
+
+    let json = serde_json::to_string(&chain).map_err(|e| CommandError::IoError(e.to_string()))?;
+    db_worker()?.call(move |db| db.set_setting(FALLBACK_CHAIN_SETTING, &json)).await?;
+    classifier::set_fallback_chain(chain.clone());
+    Ok(chain)
+}
+
+// ============================================================
+// PROMPT TOKEN BUDGET
+// ============================================================
+
+const PROMPT_TOKEN_BUDGET_SETTING: &str = "prompt_token_budget";
+
+/// Apply the saved prompt token budget (if any) to the classifier
+fn load_prompt_token_budget(db: &Database) {
+    match db.get_setting(PROMPT_TOKEN_BUDGET_SETTING) {
+        Ok(value) => {
+            if let Some(tokens) = value.and_then(|v| v.parse().ok()) {
+                classifier::set_prompt_token_budget(tokens);
+            }
+        }
+        Err(e) => eprintln!("[APP] Failed to load the prompt token budget: {}", e),
+    }
+}
+
+/// Get how many tokens a classification prompt may use
+/// Called from frontend with: invoke('get_prompt_token_budget')
+#[tauri::command]
+fn get_prompt_token_budget() -> usize {
+    classifier::prompt_token_budget()
+}
+
+/// Trim prompts to `tokens`: the oldest corrections go first, then the end of the extracted text
+///
+/// Classifications from a trimmed prompt say what was left out in their `truncation` field.
+/// Budgets below the minimum are raised to it; returns the budget in effect.
+/// Called from frontend with: invoke('set_prompt_token_budget', { tokens: 8000 })
+#[tauri::command]
+async fn set_prompt_token_budget(tokens: usize) -> Result<usize, DbError> {
+    let tokens = tokens.max(classifier::MIN_PROMPT_TOKEN_BUDGET);
+    println!("[COMMAND] set_prompt_token_budget: {}", tokens);
+    db_worker()?
+        .call(move |db| db.set_setting(PROMPT_TOKEN_BUDGET_SETTING, &tokens.to_string()))
+        .await?;
+    classifier::set_prompt_token_budget(tokens);
+    Ok(tokens)
+}
+
+// ============================================================
+// AI TIMEOUTS
+// ============================================================
+
+const AI_TIMEOUTS_SETTING: &str = "ai_timeouts";
+
+/// Apply the saved request timeouts (if any) to the classifier
+fn load_ai_timeouts(db: &Database) {
+    match db.get_setting(AI_TIMEOUTS_SETTING) {
+        Ok(json) => classifier::set_timeouts(json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default()),
+        Err(e) => eprintln!("[APP] Failed to load the AI timeouts: {}", e),
+    }
+}
+
+/// Get how long text, vision and OCR requests may take
+/// Called from frontend with: invoke('get_ai_timeouts')
+#[tauri::command]
+fn get_ai_timeouts() -> classifier::Timeouts {
+    classifier::timeouts()
+}
+
+/// Set how long text, vision and OCR requests may take before they're abandoned (in seconds)
+///
+/// Slow connections mostly need a longer vision timeout, since images are uploaded.
+/// Called from frontend with: invoke('set_ai_timeouts', { timeouts: { text_secs: 30, vision_secs: 90, ocr_secs: 30 } })
+#[tauri::command]
+async fn set_ai_timeouts(timeouts: classifier::Timeouts) -> Result<classifier::Timeouts, CommandError> {
+    timeouts.validate().map_err(CommandError::InvalidInput)?;
+    println!(
+        "[COMMAND] set_ai_timeouts: text {}s, vision {}s, OCR {}s",
+        timeouts.text_secs, timeouts.vision_secs, timeouts.ocr_secs
+    );
+
+    let json = serde_json::to_string(&timeouts).map_err(|e| CommandError::IoError(e.to_string()))?;
+    db_worker()?.call(move |db| db.set_setting(AI_TIMEOUTS_SETTING, &json)).await?;
+    classifier::set_timeouts(timeouts);
+    Ok(timeouts)
+}
+
+// ============================================================
+// AI NOTES SUBFOLDER
+// ============================================================
+
+const CHAT_EXPORT_SUBFOLDER_SETTING: &str = "chat_export_subfolder";
+
+/// Apply the saved AI notes subfolder (if any) to ChatGPT conversation placement
+fn load_chat_export_subfolder(db: &Database) {
+    match db.get_setting(CHAT_EXPORT_SUBFOLDER_SETTING) {
+        Ok(value) => {
+            if let Some(subfolder) = value {
+                chat_export::set_subfolder(subfolder);
+            }
+        }
+        Err(e) => eprintln!("[APP] Failed to load the AI notes subfolder: {}", e),
+    }
+}
+
+/// Get the subfolder of a course that saved ChatGPT conversations go into
+/// Called from frontend with: invoke('get_chat_export_subfolder')
+#[tauri::command]
+fn get_chat_export_subfolder() -> String {
+    chat_export::subfolder()
+}
+
+/// Put saved ChatGPT conversations into `subfolder` of the course they're about
+///
+/// An empty name puts them straight into the course folder. The subfolder is created on
+/// the first move.
+/// Called from frontend with: invoke('set_chat_export_subfolder', { subfolder: 'AI notes' })
+#[tauri::command]
+async fn set_chat_export_subfolder(subfolder: String) -> Result<String, CommandError> {
+    let subfolder = chat_export::validate_subfolder(&subfolder).map_err(CommandError::InvalidInput)?;
+    println!("[COMMAND] set_chat_export_subfolder: {:?}", subfolder);
+    let saved = subfolder.clone();
+    db_worker()?
+        .call(move |db| db.set_setting(CHAT_EXPORT_SUBFOLDER_SETTING, &saved))
+        .await?;
+    chat_export::set_subfolder(subfolder.clone());
+    Ok(subfolder)
+}
+
+/// The AI notes subfolder of `folder` if `source` is a saved ChatGPT conversation
+/// (None for other files, or with the subfolder turned off)
+fn chat_notes_folder(source: &std::path::Path, folder: &str) -> Option<String> {
+    let subfolder = chat_export::subfolder();
+    if subfolder.is_empty() || folder.is_empty() || folder == "__UNSORTED__" {
+        return None;
+    }
+    // Already pointed at the notes folder itself
+    if std::path::Path::new(folder).file_name().is_some_and(|name| name.to_string_lossy() == subfolder) {
+        return None;
+    }
+    chat_export::read(source, &filetype::detect(source).extension)?;
+    Some(std::path::Path::new(folder).join(&subfolder).to_string_lossy().to_string())
+}
+
+/// The subfolder of the course folder the AI chose for a file, if it's sure enough of it
+fn confident_subfolder(classification: &classifier::Classification) -> Option<String> {
+    let subfolder = classification.suggested_subfolder.as_deref()?;
+    if classification.subfolder_confidence? < classifier::SUBFOLDER_MIN_CONFIDENCE {
+        return None;
+    }
+    Some(std::path::Path::new(&classification.suggested_folder).join(subfolder).to_string_lossy().to_string())
+}
+
+/// `classification` pointed at the course's AI notes subfolder if `source` is a saved
+/// ChatGPT conversation, or else at the subfolder the AI chose if it's sure enough of it
+fn course_destination(source: &std::path::Path, classification: classifier::Classification) -> classifier::Classification {
+    if !classification.is_relevant {
+        return classification;
+    }
+    if let Some(folder) = chat_notes_folder(source, &classification.suggested_folder) {
+        return classifier::Classification {
+            reasoning: format!("{} (ChatGPT conversation, filed under {})", classification.reasoning, chat_export::subfolder()),
+            suggested_folder: folder,
+            ..classification
+        };
+    }
+    match confident_subfolder(&classification) {
+        Some(folder) => classifier::Classification {
+            reasoning: format!(
+                "{} (into {})",
+                classification.reasoning,
+                classification.suggested_subfolder.as_deref().unwrap_or_default()
+            ),
+            suggested_folder: folder,
+            ..classification
+        },
+        None => classification,
+    }
+}
+
+// ============================================================
+// AI CONCURRENCY
+// ============================================================
+
+const MAX_CONCURRENT_REQUESTS_SETTING: &str = "max_concurrent_requests";
+
+/// Apply the saved concurrency limit (if any) to the classifier
+fn load_max_concurrent_requests(db: &Database) {
+    match db.get_setting(MAX_CONCURRENT_REQUESTS_SETTING) {
+        Ok(value) => {
+            if let Some(limit) = value.and_then(|v| v.parse().ok()) {
+                classifier::set_max_concurrent_requests(limit);
+            }
+        }
+        Err(e) => eprintln!("[APP] Failed to load the AI concurrency limit: {}", e),
+    }
+}
+
+/// Get how many AI requests may be in flight at once
+/// Called from frontend with: invoke('get_max_concurrent_requests')
+#[tauri::command]
+fn get_max_concurrent_requests() -> usize {
+    classifier::max_concurrent_requests()
+}
+
+/// Let up to `limit` AI requests run at once, so organize runs classify several files in parallel
+///
+/// Requests still start at least half a second apart. Limits outside 1 to 8 are clamped;
+/// returns the limit in effect.
+/// Called from frontend with: invoke('set_max_concurrent_requests', { limit: 3 })
+#[tauri::command]
+async fn set_max_concurrent_requests(limit: usize) -> Result<usize, DbError> {
+    let limit = limit.clamp(1, classifier::MAX_CONCURRENT_REQUESTS);
+    println!("[COMMAND] set_max_concurrent_requests: {}", limit);
+    db_worker()?
+        .call(move |db| db.set_setting(MAX_CONCURRENT_REQUESTS_SETTING, &limit.to_string()))
+        .await?;
+    classifier::set_max_concurrent_requests(limit);
+    Ok(limit)
+}
+
+// ============================================================
+// API BUDGET
+// ============================================================
+
+const API_BUDGET_SETTING: &str = "api_budget";
+const BUDGET_EXCEEDED_EVENT: &str = "budget-exceeded";
+
+/// Apply the saved spend budget (if any) and this month's spending so far
+fn load_api_budget(db: &Database) {
+    match db.get_setting(API_BUDGET_SETTING) {
+        Ok(json) => budget::set_settings(json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default()),
+        Err(e) => eprintln!("[APP] Failed to load the API budget: {}", e),
+    }
+    match db.get_api_spend_since(budget::month_start_ms()) {
+        Ok(spent) => budget::set_spent_this_month(spent),
+        Err(e) => eprintln!("[APP] Failed to load this month's API spending: {}", e),
+    }
+}
+
+/// Get the monthly spend limit and how much has been spent this month
+/// Called from frontend with: invoke('get_api_budget')
+#[tauri::command]
+fn get_api_budget() -> budget::BudgetStatus {
+    budget::status()
+}
+
+/// Set the monthly spend limit (0 turns it off), the cheaper model used once spending
+/// passes `downgrade_at` of it, and that point
+///
+/// At the limit only free classifiers (Ollama, a local endpoint, offline keyword rules) are
+/// used until the month ends.
+/// Called from frontend with: invoke('set_api_budget', { settings: { monthly_limit_usd: 5, downgrade_model: 'gpt-4o-mini', downgrade_at: 0.8 } })
+#[tauri::command]
+async fn set_api_budget(settings: budget::BudgetSettings) -> Result<budget::BudgetStatus, CommandError> {
+    let settings = budget::BudgetSettings { downgrade_model: settings.downgrade_model.trim().to_string(), ..settings };
+    settings.validate().map_err(CommandError::InvalidInput)?;
+    println!(
+        "[COMMAND] set_api_budget: ${:.2}/month, {} after {:.0}%",
+        settings.monthly_limit_usd,
+        settings.downgrade_model,
+        settings.downgrade_at * 100.0
+    );
+
+    let json = serde_json::to_string(&settings).map_err(|e| CommandError::IoError(e.to_string()))?;
+    db_worker()?
+        .call(move |db| {
+            db.set_setting(API_BUDGET_SETTING, &json)?;
+            load_api_budget(db);
+            Ok(())
+        })
+        .await?;
+    Ok(budget::status())
+}
+
+/// Per model: requests made and replies that weren't valid JSON and needed a repair request
+/// Called from frontend with: invoke('get_model_reliability')
+#[tauri::command]
+async fn get_model_reliability() -> Result<Vec<budget::ModelReliability>, CommandError> {
+    Ok(db_worker()?.call(|db| db.get_model_reliability()).await?)
+}
+
+/// Tell the frontend and the user that the month's AI budget is used up
+fn warn_budget_exceeded(app: &tauri::AppHandle, status: budget::BudgetStatus) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let message = format!(
+        "${:.2} of ${:.2} spent in {}. Files are sorted without paid AI until next month.",
+        status.spent_usd, status.settings.monthly_limit_usd, status.month
+    );
+    let _ = app.emit(BUDGET_EXCEEDED_EVENT, &status);
+    if let Err(e) = app.notification().builder().title("AI budget reached").body(&message).show() {
+        eprintln!("[BUDGET] Failed to show notification: {}", e);
+    }
+}
+
+// ============================================================
+// ALLOWED ROOTS
+// ============================================================
+
+/// Get the folders file operations are restricted to (see `roots::load_or_configured`)
+/// Called from frontend with: invoke('get_allowed_roots')
+#[tauri::command]
+async fn get_allowed_roots() -> Result<Vec<String>, DbError> {
+    db_worker()?.call(roots::load_or_configured).await
+}
+
+/// Restrict moves, renames, trashing and previews to these folders and everything in them
+///
+/// Pass the watched folders and destination trees. Whole drives, folders holding the whole
+/// home folder and an empty list are rejected.
+/// Called from frontend with: invoke('set_allowed_roots', { roots: ['...', '...'] })
+#[tauri::command]
+async fn set_allowed_roots(roots: Vec<String>) -> Result<Vec<String>, CommandError> {
+    let roots = roots::validate(roots).map_err(CommandError::InvalidPath)?;
+    println!("[COMMAND] set_allowed_roots: {} folder(s)", roots.len());
+
+    let saved = roots.clone();
+    db_worker()?.call(move |db| roots::save(db, &saved)).await?;
+    roots::set(&roots);
+    Ok(roots)
+}
+
+// ============================================================
+// WATCH PROFILES
+// ============================================================
+
+/// List the watch profiles
+/// Called from frontend with: invoke('list_watch_profiles')
+#[tauri::command]
+async fn list_watch_profiles() -> Result<Vec<WatchProfile>, DbError> {
+    db_worker()?.call(|db| db.get_watch_profiles()).await
+}
+
+/// Create or update a watch profile (updated when it has an id)
+///
+/// The watched folder must exist. Takes effect for watching the next time the watcher starts;
+/// excluded subfolders apply to the running watcher and scans straight away.
+/// Called from frontend with: invoke('save_watch_profile', { profile: { id: null, name: 'Screenshots', watch_path: '...', folders: [...], mode: 'ocr', min_confidence: 0.8, created_at: 0, excluded: ['node_modules', '.git'] } })
+#[tauri::command]
+async fn save_watch_profile(profile: WatchProfile) -> Result<WatchProfile, CommandError> {
+    println!("[COMMAND] save_watch_profile: {} ({})", profile.name, profile.watch_path);
+
+    let mut profile = profiles::validate(profile).map_err(CommandError::InvalidInput)?;
+    let watch_dir = validate_path(&profile.watch_path)?;
+    if !watch_dir.is_dir() {
+        return Err(CommandError::FileNotFound(profile.watch_path));
+    }
+    profile.watch_path = watch_dir.to_string_lossy().to_string();
+
+    let stored = profile.clone();
+    let id = db_worker()?.call(move |db| db.save_watch_profile(&stored)).await?;
+    profile.id = Some(id);
+    Ok(profile)
+}
+
+/// Watch profiles, or none if the database isn't open
+fn stored_watch_profiles() -> Vec<WatchProfile> {
+    get_db().and_then(|db| db.get_watch_profiles()).unwrap_or_default()
+}
+
+/// Whether the watcher should ignore `path` because its profile excludes the subfolder it's in
+fn is_watch_excluded(path: &std::path::Path) -> bool {
+    profiles::excluded(&stored_watch_profiles(), path)
+}
+
+/// Delete a watch profile along with its rules
+/// Called from frontend with: invoke('delete_watch_profile', { id: 1 })
+#[tauri::command]
+async fn delete_watch_profile(id: i64) -> Result<bool, DbError> {
+    println!("[COMMAND] delete_watch_profile: {}", id);
+    db_worker()?.call(move |db| db.delete_watch_profile(id)).await
+}
+
+// ============================================================
+// QUARANTINE ("NEEDS REVIEW" FOLDER)
+// ============================================================
+
+const QUARANTINE_FOLDER_SETTING: &str = "quarantine_folder";
+const QUARANTINE_ENABLED_SETTING: &str = "quarantine_enabled";
+
+/// Where unsorted and low-confidence files are parked, and whether the pipeline sends them there
+#[derive(Debug, Serialize)]
+struct QuarantineSettings {
+    folder: Option<String>,
+    enabled: bool,
+}
+
+fn stored_quarantine_settings(db: &Database) -> Result<QuarantineSettings, DbError> {
+    Ok(QuarantineSettings {
+        folder: db
+            .get_setting(QUARANTINE_FOLDER_SETTING)?
+            .filter(|f| !f.is_empty()),
+        enabled: db.get_setting(QUARANTINE_ENABLED_SETTING)?.as_deref() == Some("true"),
+    })
+}
+
+/// Get the quarantine folder and whether automatic quarantining is on
+/// Called from frontend with: invoke('get_quarantine_settings')
+#[tauri::command]
+async fn get_quarantine_settings() -> Result<QuarantineSettings, DbError> {
+    db_worker()?.call(stored_quarantine_settings).await
+}
+
+/// Set the quarantine folder (None or "" to clear it) and the automatic quarantine option
+/// Called from frontend with: invoke('set_quarantine_settings', { folder: '...', enabled: true })
+#[tauri::command]
+async fn set_quarantine_settings(folder: Option<String>, enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_quarantine_settings: {:?} (enabled: {})", folder, enabled);
+    db_worker()?
+        .call(move |db| {
+            db.set_setting(QUARANTINE_FOLDER_SETTING, folder.as_deref().unwrap_or(""))?;
+            db.set_setting(QUARANTINE_ENABLED_SETTING, if enabled { "true" } else { "false" })
+        })
+        .await
+}
+
+/// Move a file into the quarantine folder and remember why it's there
+///
+/// `reason` is "unsorted" (no matching folder) or "low_confidence". Name clashes inside the
+/// quarantine folder are resolved by renaming, so nothing there is ever overwritten.
+/// Called from frontend with: invoke('quarantine_file', { sourcePath: '...', reason: 'unsorted', confidence: 0.4, suggestedFolder: null })
+#[tauri::command]
+async fn quarantine_file(
+    source_path: String,
+    reason: String,
+    confidence: f64,
+    suggested_folder: Option<String>,
+) -> Result<QuarantineItem, CommandError> {
+    println!("[COMMAND] quarantine_file: {} ({})", source_path, reason);
+
+    if reason != "unsorted" && reason != "low_confidence" {
+        return Err(CommandError::InvalidPath(format!("Unknown quarantine reason: {}", reason)));
+    }
+
+    let worker = db_worker()?;
+    let folder = worker
+        .call(stored_quarantine_settings)
+        .await?
+        .folder
+        .ok_or_else(|| CommandError::InvalidPath("No quarantine folder is configured".to_string()))?;
+
+    let source = validate_allowed_path(&source_path)?;
+    if !source.is_file() {
+        return Err(CommandError::FileNotFound(source_path));
+    }
+    let dest_dir = validate_allowed_path(&folder)?;
+    std::fs::create_dir_all(&dest_dir)?;
+
+    let filename = source.file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
+    let dest_path = unique_dest_path(&dest_dir, filename)?;
+    checksum::move_verified(&source, &dest_path)?;
+    record_operation("move", &source, Some(&dest_path));
+
+    let mut item = QuarantineItem {
+        id: None,
+        path: dest_path.to_string_lossy().to_string(),
+        filename: filename.to_string_lossy().to_string(),
+        original_folder: source.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+        reason,
+        confidence,
+        suggested_folder: suggested_folder.filter(|f| !f.is_empty() && f != "__UNSORTED__"),
+        created_at: current_timestamp_ms(),
+    };
+    let stored = item.clone();
+    item.id = Some(worker.call(move |db| db.add_quarantine_item(stored)).await?);
+
+    println!("[COMMAND] Quarantined {} -> {}", item.filename, item.path);
+    Ok(item)
+}
+
+/// List files waiting in quarantine, newest first
+///
+/// Entries whose file is no longer there (moved out by hand, deleted) are dropped.
+/// Called from frontend with: invoke('list_quarantine')
+#[tauri::command]
+async fn list_quarantine() -> Result<Vec<QuarantineItem>, DbError> {
+    db_worker()?
+        .call(|db| {
+            let (present, gone): (Vec<_>, Vec<_>) = db
+                .get_quarantine_items()?
+                .into_iter()
+                .partition(|item| std::path::Path::new(&item.path).is_file());
+            for item in gone {
+                db.remove_quarantine_item(&item.path)?;
+            }
+            Ok(present)
+        })
+        .await
+}
+
+/// Validate a copy request and resolve the source file and destination folder
+///
+/// Same checks as the move commands; the destination folder is created if missing.
+fn prepare_copy(source_path: &str, dest_folder: &str) -> Result<(std::path::PathBuf, std::path::PathBuf), CommandError> {
+    let source = validate_allowed_path(source_path)?;
+    if !source.exists() {
+        return Err(CommandError::FileNotFound(source_path.to_string()));
+    }
+    if !source.is_file() {
+        return Err(CommandError::InvalidPath(format!("Source is not a file: {}", source_path)));
+    }
+
+    let dest_dir = validate_allowed_path(dest_folder)?;
+    if !dest_dir.exists() {
+        std::fs::create_dir_all(&dest_dir)?;
+    }
+
+    Ok((source, dest_dir))
+}
+
+/// Copy a file into a destination folder, leaving the original in place
+///
+/// Called from frontend with: invoke('copy_file', { sourcePath: '...', destFolder: '...' })
+#[tauri::command(async)]
+fn copy_file(source_path: String, dest_folder: String) -> Result<String, CommandError> {
+    println!("[COMMAND] copy_file: {} -> {}", source_path, dest_folder);
+
+    let (source, dest_dir) = prepare_copy(&source_path, &dest_folder)?;
+    let filename = source.file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
+    let dest_path = dest_dir.join(filename);
+
+    if let Some(existing) = filename::existing(&dest_dir, filename) {
+        return Err(CommandError::DuplicateExists(existing.display().to_string()));
+    }
+
+    fileop::copy_with_progress(&source, &dest_path)?;
+    record_copy(&source, &dest_path);
+
+    println!("[COMMAND] File copied successfully to: {}", dest_path.display());
+    Ok(format!("Copied to {}", dest_path.display()))
+}
+
+/// Copy a file into a destination folder, auto-renaming if a duplicate exists
+///
+/// Called from frontend with: invoke('copy_file_with_rename', { sourcePath: '...', destFolder: '...' })
+#[tauri::command(async)]
+fn copy_file_with_rename(source_path: String, dest_folder: String) -> Result<String, CommandError> {
+    println!("[COMMAND] copy_file_with_rename: {} -> {}", source_path, dest_folder);
+
+    let (source, dest_dir) = prepare_copy(&source_path, &dest_folder)?;
+    let filename = source.file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
+    let dest_path = unique_dest_path(&dest_dir, filename)?;
+
+    fileop::copy_with_progress(&source, &dest_path)?;
+    record_copy(&source, &dest_path);
+
+    println!("[COMMAND] File copied (with rename) to: {}", dest_path.display());
+    Ok(format!("Copied to {}", dest_path.display()))
+}
+
+/// Copy a file into a destination folder, overwriting any existing file with the same name
+///
+/// Called from frontend with: invoke('copy_replace_file', { sourcePath: '...', destFolder: '...' })
+#[tauri::command(async)]
+fn copy_replace_file(source_path: String, dest_folder: String) -> Result<String, CommandError> {
+    println!("[COMMAND] copy_replace_file: {} -> {}", source_path, dest_folder);
+
+    let (source, dest_dir) = prepare_copy(&source_path, &dest_folder)?;
+    let filename = source.file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid source file path".to_string()))?;
+    let dest_path = dest_dir.join(filename);
+
+    // Not journaled: undoing would delete the only remaining file at the destination
+    fileop::copy_with_progress(&source, &dest_path)?;
+
+    println!("[COMMAND] File copied over: {}", dest_path.display());
+    Ok(format!("Replaced {}", dest_path.display()))
+}
+
+/// Get the stored API key from the in-memory cache
+fn get_stored_api_key() -> Result<String, String> {
+    let key = API_KEY
+        .get()
+        .ok_or_else(|| "API key storage not initialized".to_string())?
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if key.is_empty() {
+        Err("No API key configured. Please set your OpenAI API key in Settings.".to_string())
+    } else {
+        Ok(key)
+    }
+}
+
+/// The key to send with a classification request: the stored one, or none for providers
+/// that don't need one (Ollama, the mock provider, a keyless custom endpoint)
+fn request_api_key() -> Result<String, String> {
+    get_stored_api_key().or_else(|e| if classifier::needs_api_key() { Err(e) } else { Ok(String::new()) })
+}
+
+/// Initialize API key storage with `key`, or the key saved in the database
+fn load_api_key(key: Option<String>) {
+    let key = key.or_else(|| DATABASE.get().and_then(|db| db.get_setting("api_key").ok().flatten()));
+    if key.is_some() {
+        println!("[APP] API key loaded");
+    }
+    let _ = API_KEY.set(Mutex::new(key.unwrap_or_default()));
+}
+
+/// Store the API key securely on the Rust side
+///
+/// The key is held in memory and persisted to the SQLite database.
+/// This avoids passing it over IPC on every classify call.
+#[tauri::command]
+async fn set_api_key(key: String) -> Result<(), String> {
+    // Persist to database first, so a key that wasn't saved is never reported as saved
+    let saved = key.clone();
+    db_worker()
+        .map_err(|e| e.to_string())?
+        .call(move |db| db.set_setting("api_key", &saved))
+        .await
+        .map_err(|e| format!("Failed to save API key: {}", e))?;
+    // Store in memory
+    if let Some(mutex) = API_KEY.get() {
+        let mut stored = mutex.lock().unwrap_or_else(|e| e.into_inner());
+        *stored = key;
+    }
+    Ok(())
+}
+
+/// Check if an API key is stored (returns the key for settings display)
+#[tauri::command]
+fn get_api_key() -> Result<String, String> {
+    let key = API_KEY
+        .get()
+        .ok_or_else(|| "Not initialized".to_string())?
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    Ok(key)
+}
+
+/// Drop folders the user has marked "never suggest" before they reach the prompt
+///
+/// Falls back to the full list if preferences can't be read, so classification still works.
+async fn suggestable_folders(available_folders: Vec<String>) -> Vec<String> {
+    let Ok(worker) = db_worker() else {
+        return available_folders;
+    };
+    let prefs = worker.call(|db| db.get_folder_prefs()).await.unwrap_or_default();
+
+    available_folders
+        .into_iter()
+        .filter(|folder| !prefs.iter().any(|p| p.never_suggest && p.folder == *folder))
+        .collect()
+}
+
+/// The suggestable folders as listed in the prompt, each followed by its description
+/// (e.g. the real course name from LMS sync) and aliases if it has any
+///
+/// With sibling context on (and privacy mode off), a few files already in each folder are
+/// listed too, so "PS4.pdf" lands next to "PS1.pdf" to "PS3.pdf": the ones named most like
+/// `filename` if it's given, otherwise the newest.
+async fn prompt_folders(available_folders: Vec<String>, filename: Option<&str>) -> Vec<String> {
+    let Ok(worker) = db_worker() else {
+        return available_folders;
+    };
+    let prefs = worker.call(|db| db.get_folder_prefs()).await.unwrap_or_default();
+    let available_folders: Vec<String> = available_folders
+        .into_iter()
+        .filter(|folder| !prefs.iter().any(|p| p.never_suggest && p.folder == *folder))
+        .collect();
+    let siblings = if classifier::sibling_context() && !classifier::privacy_mode() {
+        sibling_samples(available_folders.clone(), filename).await
+    } else {
+        HashMap::new()
+    };
+    if classifier::suggest_subfolders() {
+        find_subfolders(available_folders.clone()).await;
+    }
+    let all_aliases = folder_match::aliases();
+    let paths: Vec<&str> = available_folders.iter().map(String::as_str).collect();
+    let aliases = folder_match::listed_aliases(&all_aliases, &paths);
+
+    available_folders
+        .iter()
+        .map(|folder| {
+            let description = prefs.iter().find(|p| p.folder == *folder).and_then(|p| p.description.as_deref());
+            let names: Vec<&str> = aliases.iter().filter(|(_, f)| *f == folder.as_str()).map(|(alias, _)| *alias).collect();
+            let description = classifier::describe_with_aliases(description, &names);
+            let description =
+                classifier::describe_with_siblings(description.as_deref(), siblings.get(folder).map_or(&[], Vec::as_slice));
+            classifier::describe_folder(folder, description.as_deref())
+        })
+        .collect()
+}
+
+/// A few filenames already in each folder, packed into `SIBLING_TOKEN_BUDGET` tokens: the
+/// ones named most like `filename`, or the newest without one
+///
+/// Candidates come from the file index, or a quick listing for folders that aren't indexed.
+async fn sibling_samples(folders: Vec<String>, filename: Option<&str>) -> HashMap<String, Vec<String>> {
+    let Ok(worker) = db_worker() else {
+        return HashMap::new();
+    };
+    let indexed = worker
+        .call(move |db| {
+            let mut samples = Vec::new();
+            for folder in folders {
+                let names = db.indexed_names_in(&folder, classifier::SIBLING_CANDIDATES)?;
+                samples.push((folder, names));
+            }
+            Ok::<_, DbError>(samples)
+        })
+        .await
+        .unwrap_or_default();
+
+    let candidates: Vec<(String, Vec<String>)> = tokio::task::spawn_blocking(move || {
+        indexed
+            .into_iter()
+            .map(|(folder, names)| {
+                let names = if names.is_empty() { listed_names(&folder) } else { names };
+                (folder, names)
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default();
+
+    // Scored all at once, so the embedding model runs one batch
+    let all_names: Vec<String> = candidates.iter().flat_map(|(_, names)| names.iter().cloned()).collect();
+    let mut scores = match filename {
+        Some(filename) => name_relevance(filename, all_names).await,
+        None => Vec::new(),
+    }
+    .into_iter();
+    candidates
+        .into_iter()
+        .map(|(folder, names)| {
+            let count = names.len();
+            let scored = names
+                .into_iter()
+                .enumerate()
+                .map(|(newest, name)| (scores.next().unwrap_or((count - newest) as f32), name))
+                .collect();
+            (folder, classifier::pack_by_relevance(scored, classifier::SIBLING_TOKEN_BUDGET))
+        })
+        .collect()
+}
+
+/// How alike each of `names` is to `filename`: by meaning with the local embedding model,
+/// otherwise by words in common
+async fn name_relevance(filename: &str, names: Vec<String>) -> Vec<f32> {
+    let mut texts = vec![filename.to_string()];
+    texts.extend(names.iter().cloned());
+    let embedded = tokio::task::spawn_blocking(move || embeddings::embed_local(&texts)).await.ok().flatten();
+    match embedded {
+        Some(Ok(vectors)) => vectors[1..].iter().map(|vector| topics::cosine_similarity(&vectors[0], vector)).collect(),
+        _ => names.iter().map(|name| insights::shared_words(filename, name) as f32).collect(),
+    }
+}
+
+// Entries looked at when listing an unindexed folder for sibling names
+const SIBLING_LISTING_CAP: usize = 1000;
+
+/// Names of the newest files directly in `folder` (hidden files left out)
+fn listed_names(folder: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(std::time::SystemTime, String)> = entries
+        .flatten()
+        .take(SIBLING_LISTING_CAP)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let meta = entry.metadata().ok().filter(|m| m.is_file() && !name.starts_with('.'))?;
+            Some((meta.modified().unwrap_or(std::time::UNIX_EPOCH), name))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().take(classifier::SIBLING_CANDIDATES).map(|(_, name)| name).collect()
+}
+
+// How similar (by local embedding) a past filename must be to count as a similar outcome
+const SIMILAR_EXAMPLE_THRESHOLD: f32 = 0.6;
+
+/// The past outcomes to show the AI for `filename`: `correction_history` (the frontend's
+/// recent ones) and similar ones from the database, ranked by how alike their filenames
+/// are to `filename` and packed into the examples' share of the prompt budget
+async fn with_similar_examples(correction_history: Vec<String>, filename: &str) -> Vec<String> {
+    pack_examples(add_similar_examples(correction_history, filename).await, filename).await
+}
+
+/// `examples` (correction-history lines, oldest first) ranked by how alike their filenames
+/// are to `filename` and packed into the examples' share of the prompt budget
+async fn pack_examples(mut examples: Vec<String>, filename: &str) -> Vec<String> {
+    // Newest first, so equally relevant outcomes go to the newer ones
+    examples.reverse();
+    let names = examples.iter().map(|line| insights::example_filename(line).unwrap_or(line).to_string()).collect();
+    let scores = name_relevance(filename, names).await;
+    classifier::pack_by_relevance(scores.into_iter().zip(examples).collect(), classifier::examples_token_budget())
+}
+
+/// `correction_history` plus past outcomes for files named like `filename`, accepted
+/// suggestions as well as corrections
+///
+/// With the local embedding model, past filenames are ranked by meaning; otherwise by
+/// words in common (see `insights::similar_examples`). Falls back to the history as
+/// given if corrections can't be read.
+async fn add_similar_examples(correction_history: Vec<String>, filename: &str) -> Vec<String> {
+    let Ok(worker) = db_worker() else {
+        return correction_history;
+    };
+    let corrections = worker.call(|db| db.get_corrections()).await.unwrap_or_default();
+
+    let mut texts = vec![filename.to_string()];
+    texts.extend(corrections.iter().map(|c| c.filename.clone()));
+    let embedded = tokio::task::spawn_blocking(move || embeddings::embed_local(&texts)).await.ok().flatten();
+    let Some(Ok(vectors)) = embedded else {
+        return insights::add_similar_examples(correction_history, &corrections, filename);
+    };
+
+    let scores: HashMap<&str, f32> = corrections
+        .iter()
+        .zip(&vectors[1..])
+        .map(|(c, vector)| (c.filename.as_str(), topics::cosine_similarity(&vectors[0], vector)))
+        .collect();
+    let similar = insights::ranked_examples(&corrections, filename, insights::MAX_PROMPT_EXAMPLES, |c| {
+        scores.get(c.filename.as_str()).copied().filter(|s| *s >= SIMILAR_EXAMPLE_THRESHOLD)
+    });
+    let mut correction_history = correction_history;
+    for line in similar {
+        if !correction_history.contains(&line) {
+            correction_history.push(line);
+        }
+    }
+    correction_history
+}
+
+/// How often past suggestions were right, by confidence and folder (empty if corrections
+/// can't be read)
+async fn load_calibration() -> insights::Calibration {
+    let Ok(worker) = db_worker() else {
+        return insights::Calibration::default();
+    };
+    let corrections = worker.call(|db| db.get_corrections()).await.unwrap_or_default();
+    insights::Calibration::from_corrections(&corrections)
+}
+
+/// Classify a file using AI
+///
+/// Called from frontend with: invoke('classify_file', { filename: '...', availableFolders: [...], correctionHistory: [...] })
+#[tauri::command]
+async fn classify_file(
+    filename: String,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<classifier::Classification, String> {
+    println!("[COMMAND] classify_file: {} (with {} corrections)", filename, correction_history.len());
+    let api_key = request_api_key()?;
+    let available_folders = prompt_folders(available_folders, Some(&filename)).await;
+    let correction_history = with_similar_examples(correction_history, &filename).await;
+
+    let classification = classifier::classify_file(api_key, filename, available_folders, correction_history).await?;
+    Ok(load_calibration().await.apply(classification))
+}
+
+/// Classify an image file using OCR text extraction + GPT-3.5 (cheap path)
+///
+/// Extracts text from image using Tesseract OCR, then classifies with GPT-3.5.
+/// Returns error if OCR extracts too little text (caller should fall back to vision).
+#[tauri::command]
+async fn classify_image_with_ocr(
+    file_path: String,
+    filename: String,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<classifier::Classification, String> {
+    println!("[COMMAND] classify_image_with_ocr: {} (OCR mode)", filename);
+    let api_key = request_api_key()?;
+    let available_folders = prompt_folders(available_folders, Some(&filename)).await;
+    let correction_history = with_similar_examples(correction_history, &filename).await;
+    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
+    let text_content = ocr_classification_text(&validated, &filename)?;
+
+    let classification = classifier::classify_with_text_content(api_key, filename, text_content, available_folders, correction_history).await?;
+    Ok(load_calibration().await.apply(classification))
+}
+
+// Returned when OCR reads too little text to classify on; the image should go to vision
+const OCR_INSUFFICIENT_TEXT: &str = "OCR_INSUFFICIENT_TEXT";
+
+// Returned instead of reading an online-only file's content (which would download it)
+const CLOUD_PLACEHOLDER_SKIPPED: &str = "Online-only file - content not downloaded";
+
+/// Extract an image's text with Tesseract OCR for classification, prefixed with its capture
+/// date and device when the photo has them
+fn ocr_classification_text(validated: &std::path::Path, filename: &str) -> Result<String, String> {
+    if metadata::skip_content(validated) {
+        return Err(CLOUD_PLACEHOLDER_SKIPPED.to_string());
+    }
+    let mut text_content = classifier::extract_image_text(&validated.to_string_lossy())?;
+
+    // If OCR extracted too little text, signal caller to use vision fallback
+    if text_content.len() < 20 {
+        return Err(OCR_INSUFFICIENT_TEXT.to_string());
+    }
+
+    println!("[COMMAND] OCR extracted {} chars from {}", text_content.len(), filename);
+
+    // Capture date and device help place camera-scanned notes in the right term/week
+    if let Some(summary) = metadata::read_exif(validated).and_then(|e| e.summary()) {
+        text_content = format!("[{}]\n{}", summary, text_content);
+    }
+    Ok(text_content)
+}
+
+/// Classify an image file using GPT-4o vision (reads actual image content)
+///
+/// Called from frontend with: invoke('classify_image_file', { apiKey: '...', filePath: '...', filename: '...', availableFolders: [...], correctionHistory: [...] })
+#[tauri::command]
+async fn classify_image_file(
+    file_path: String,
+    filename: String,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<classifier::Classification, String> {
+    println!("[COMMAND] classify_image_file: {} (vision mode)", filename);
+    let api_key = request_api_key()?;
+    let available_folders = prompt_folders(available_folders, Some(&filename)).await;
+    let correction_history = with_similar_examples(correction_history, &filename).await;
+    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
+    if metadata::skip_content(&validated) {
+        return Err(CLOUD_PLACEHOLDER_SKIPPED.to_string());
+    }
+    let file_path = validated.to_string_lossy().to_string();
+
+    let classification = classifier::classify_image_file(api_key, file_path, filename, available_folders, correction_history).await?;
+    Ok(load_calibration().await.apply(classification))
+}
+
+/// Classify a file using extracted text content (second pass for PDFs, etc.)
+///
+/// Called from frontend with: invoke('classify_with_content', { filePath: '...', filename: '...', availableFolders: [...], correctionHistory: [...] })
+#[tauri::command]
+async fn classify_with_content(
+    file_path: String,
+    filename: String,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<classifier::Classification, String> {
+    println!("[COMMAND] classify_with_content: {} (content extraction mode)", filename);
+    let api_key = request_api_key()?;
+    let available_folders = prompt_folders(available_folders, Some(&filename)).await;
+    let correction_history = with_similar_examples(correction_history, &filename).await;
+    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
+    let text_content = extract_classification_text(&validated)?;
+
+    let classification = classifier::classify_with_text_content(api_key, filename, text_content, available_folders, correction_history).await?;
+    Ok(course_destination(&validated, load_calibration().await.apply(classification)))
+}
+
+// File types extract_classification_text can read
+const CONTENT_EXTRACTABLE_EXTENSIONS: &[&str] = &["pdf", "txt", "md", "csv", "html", "htm"];
+
+/// Extract the text used for content-based classification (PDF text, the start of a text
+/// file, or a saved ChatGPT conversation's title and questions)
+fn extract_classification_text(validated: &std::path::Path) -> Result<String, String> {
+    let file_path = validated.to_string_lossy().to_string();
+    if metadata::skip_content(validated) {
+        return Err(CLOUD_PLACEHOLDER_SKIPPED.to_string());
+    }
+
+    // Determine file type (by content, so misnamed downloads still work) and extract text
+    let ext = filetype::detect(validated).extension;
+    if let Some(chat) = chat_export::read(validated, &ext) {
+        return Ok(chat.classification_text());
+    }
+
+    let text_content = match ext.as_str() {
+        "pdf" => {
+            classifier::extract_pdf_text(&file_path)?
+        }
+        "txt" | "md" | "csv" => {
+            // Read first 500 chars of plain text files
+            let content = std::fs::read_to_string(&file_path)
+                .map_err(|e| format!("Failed to read text file: {}", e))?;
+            content.chars().take(500).collect()
+        }
+        _ => {
+            return Err(format!("Unsupported file type for content extraction: .{}", ext));
+        }
+    };
+
+    if text_content.trim().is_empty() {
+        return Err("No text content could be extracted from the file".to_string());
+    }
+    Ok(text_content)
+}
+
+/// List the entries in a zip archive
+///
+/// Called from frontend with: invoke('list_archive', { filePath: '...' })
+#[tauri::command]
+fn list_archive(file_path: String) -> Result<Vec<archive::ArchiveEntry>, CommandError> {
+    println!("[COMMAND] list_archive: {}", file_path);
+    let validated = validate_path(&file_path)?;
+    archive::list_entries(&validated)
+}
+
+/// Classify a zip archive by the filenames it contains
+///
+/// Called from frontend with: invoke('classify_archive', { filePath: '...', filename: '...', availableFolders: [...], correctionHistory: [...] })
+#[tauri::command]
+async fn classify_archive(
+    file_path: String,
+    filename: String,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<classifier::Classification, String> {
+    println!("[COMMAND] classify_archive: {} (archive contents mode)", filename);
+    let api_key = request_api_key()?;
+    let available_folders = prompt_folders(available_folders, Some(&filename)).await;
+    let correction_history = with_similar_examples(correction_history, &filename).await;
+    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
+    let text_content = archive_classification_text(&validated)?;
+
+    let classification = classifier::classify_with_text_content(api_key, filename, text_content, available_folders, correction_history).await?;
+    Ok(load_calibration().await.apply(classification))
+}
+
+/// Describe a zip archive's entries for classification
+fn archive_classification_text(validated: &std::path::Path) -> Result<String, String> {
+    let entries = archive::list_entries(validated).map_err(|e| e.to_string())?;
+    if entries.iter().all(|e| e.is_dir) {
+        return Err("Archive is empty".to_string());
+    }
+    Ok(archive::describe_for_classification(&entries, 50))
+}
+
+// ============================================================
+// AUTO CLASSIFICATION
+// ============================================================
+
+const CLASSIFY_PROGRESS_EVENT: &str = "classify-progress";
+
+// Image types the vision pass can send
+const VISION_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+/// Which pass of `classify_auto` produced the answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ClassifyPass {
+    Type, // file type buckets, no AI
+    Filename,
+    Ocr,
+    Vision,
+    Content,
+    Archive,
+    Link, // what a shortcut points at
+}
+
+/// Progress of one `classify_auto` call, sent as a `classify-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct ClassifyProgress {
+    file_path: String,
+    pass: ClassifyPass,
+    message: String,
+}
+
+/// A classification and the pass it came from (the provider that answered is in `tier`)
+#[derive(Debug, Clone, Serialize)]
+struct AutoClassification {
+    #[serde(flatten)]
+    classification: classifier::Classification,
+    pass: ClassifyPass,
+    link: Option<links::LinkTarget>, // set for shortcuts, so a broken one can be offered for deletion
+    cloud_placeholder: bool,          // online-only file, classified by name without downloading it
+}
+
+/// Classify a file, escalating from the filename to its content when that's worth it
+///
+/// The filename goes first. Unclear images are read with OCR, and sent for vision when OCR
+/// finds too little text (never in OCR mode); PDFs and text files are classified from their
+/// content and zips from the names inside. If a later pass fails the filename result stands.
+/// Bucket mode, or local-only mode with no local AI, sorts by file type instead. Each pass
+/// is announced with a `classify-progress` event. Online-only cloud files stop at the
+/// filename pass unless reading them is turned on, since reading would download them.
+/// Called from frontend with: invoke('classify_auto', { filePath: '...', availableFolders: [...], correctionHistory: [...], mode: 'ai' })
+#[tauri::command]
+async fn classify_auto(
+    app: tauri::AppHandle,
+    file_path: String,
+    available_folders: Vec<String>,
+    correction_history: Option<Vec<String>>,
+    mode: Option<buckets::ClassificationMode>, // a watch profile's mode; None: the stored mode
+) -> Result<AutoClassification, String> {
+    // Quitting drops the classification; the file stays queued and is classified next time
+    tokio::select! {
+        result = classify_auto_passes(app, file_path, available_folders, correction_history, mode) => result,
+        _ = shutdown::requested() => Err(CommandError::Cancelled("The app is shutting down".to_string()).to_string()),
+    }
+}
+
+async fn classify_auto_passes(
+    app: tauri::AppHandle,
+    file_path: String,
+    available_folders: Vec<String>,
+    correction_history: Option<Vec<String>>,
+    mode: Option<buckets::ClassificationMode>,
+) -> Result<AutoClassification, String> {
+    use ClassifyPass::*;
+
+    println!("[COMMAND] classify_auto: {}", file_path);
+    let validated = validate_path(&file_path).map_err(|e| e.to_string())?;
+    if !validated.is_file() {
+        return Err(CommandError::FileNotFound(file_path).to_string());
+    }
+    let filename = validated.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let settings = db_worker()
+        .map_err(|e| e.to_string())?
+        .call(stored_classification_mode)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mode = mode.unwrap_or(settings.mode);
+    let placeholder = metadata::skip_content(&validated);
+
+    let progress = |pass: ClassifyPass, message: &str| {
+        let _ = app.emit(
+            CLASSIFY_PROGRESS_EVENT,
+            ClassifyProgress { file_path: file_path.clone(), pass, message: message.to_string() },
+        );
+    };
+    if mode == buckets::ClassificationMode::Buckets || !classifier::ai_available() {
+        progress(Type, "Sorting by file type...");
+        let base = bucket_base_folder(&validated, settings.bucket_folder.as_deref());
+        let classification = buckets::classify(&validated, &base);
+        return Ok(AutoClassification { classification, pass: Type, link: None, cloud_placeholder: placeholder });
+    }
+
+    let calibration = load_calibration().await;
+    let done = |classification, pass| {
+        let classification = course_destination(&validated, calibration.apply(classification));
+        Ok(AutoClassification { classification, pass, link: None, cloud_placeholder: placeholder })
+    };
+
+    let api_key = request_api_key()?;
+    let available_folders = prompt_folders(available_folders, Some(&filename)).await;
+    let correction_history = with_similar_examples(correction_history.unwrap_or_default(), &filename).await;
+    let classify_text = |text: String| {
+        classifier::classify_with_text_content(
+            api_key.clone(),
+            filename.clone(),
+            text,
+            available_folders.clone(),
+            correction_history.clone(),
+        )
+    };
+
+    // A shortcut is classified by its target: the file's name and content, or the page's title
+    if let Some(mut link) = links::resolve(&validated) {
+        progress(Link, "Shortcut - classifying what it points at...");
+        if link.is_url && !classifier::local_only() {
+            link.title = links::page_title(&link.target).await;
+        }
+        let target_text = match validate_path(&link.target) {
+            Ok(target) if link.exists && target.is_file() => extract_classification_text(&target).ok(),
+            _ => None,
+        };
+        let text = match target_text {
+            Some(content) => format!("{}\n\n{}", link.classification_text(), content),
+            None => link.classification_text(),
+        };
+        let by_link = classifier::classify_with_text_content(
+            api_key.clone(),
+            link.target_name().unwrap_or_else(|| filename.clone()),
+            text,
+            available_folders.clone(),
+            correction_history.clone(),
+        )
+        .await;
+        match by_link {
+            Ok(classification) => {
+                let classification = calibration.apply(classification);
+                return Ok(AutoClassification { classification, pass: Link, link: Some(link), cloud_placeholder: false });
+            }
+            Err(e) => eprintln!("[CLASSIFY] Shortcut target pass failed ({}), using the filename for {}", e, filename),
+        }
+    }
+
+    progress(Filename, "Analyzing filename...");
+    let first_pass =
+        classifier::classify_file(api_key.clone(), filename.clone(), available_folders.clone(), correction_history.clone())
+            .await?;
+    println!(
+        "[CLASSIFY] {} filename pass: confidence={}, relevant={}, folder=\"{}\"",
+        filename, first_pass.confidence, first_pass.is_relevant, first_pass.suggested_folder
+    );
+    if placeholder {
+        println!("[CLASSIFY] {} is online-only, keeping the filename result", filename);
+        return done(first_pass, Filename);
+    }
+
+    // Routed on the detected type, so misnamed downloads still get a second look
+    let extension = filetype::detect(&validated).extension;
+    let is_image = VISION_IMAGE_EXTENSIONS.contains(&extension.as_str());
+    let is_text = CONTENT_EXTRACTABLE_EXTENSIONS.contains(&extension.as_str());
+    let is_archive = extension == "zip";
+
+    // A screenshot's name says little, so unclear images get a second look; PDFs, text files
+    // and archives always do, since names like "PS1_sol.pdf" are too generic to trust
+    let unclear = first_pass.confidence < DEFAULT_ORGANIZE_MIN_CONFIDENCE;
+    let second_look = (is_image && unclear) || is_text || is_archive;
+    if !second_look {
+        return done(first_pass, Filename);
+    }
+
+    if is_image {
+        let by_ocr = async {
+            let text = ocr_classification_text(&validated, &filename)?;
+            classify_text(text).await
+        };
+        let by_vision = || {
+            classifier::classify_image_file(
+                api_key.clone(),
+                validated.to_string_lossy().to_string(),
+                filename.clone(),
+                available_folders.clone(),
+                correction_history.clone(),
+            )
+        };
+        return match classify_image_passes(mode, &filename, &progress, by_ocr, by_vision).await {
+            Some((classification, pass)) => done(classification, pass),
+            None => done(first_pass, Filename),
+        };
+    }
+
+    let (pass, text) = if is_text {
+        progress(Content, "Filename unclear - reading file content for better classification...");
+        (Content, extract_classification_text(&validated))
+    } else {
+        progress(Archive, "Looking inside archive for better classification...");
+        (Archive, archive_classification_text(&validated))
+    };
+    let second_pass = match text {
+        Ok(text) => classify_text(text).await,
+        Err(e) => Err(e),
+    };
+    match second_pass {
+        Ok(classification) => done(classification, pass),
+        Err(e) => {
+            eprintln!("[CLASSIFY] {:?} pass failed ({}), using the filename result for {}", pass, e, filename);
+            done(first_pass, Filename)
+        }
+    }
+}
+
+/// The second look at an unclear image: OCR first (free, local), then vision if OCR read too
+/// little or failed
+///
+/// `vision` is only called outside OCR mode, which never uploads images. None means neither
+/// pass worked and the filename result stands.
+async fn classify_image_passes<Vision, VisionResult>(
+    mode: buckets::ClassificationMode,
+    filename: &str,
+    progress: impl Fn(ClassifyPass, &str),
+    ocr: impl std::future::Future<Output = Result<classifier::Classification, String>>,
+    vision: Vision,
+) -> Option<(classifier::Classification, ClassifyPass)>
+where
+    Vision: FnOnce() -> VisionResult,
+    VisionResult: std::future::Future<Output = Result<classifier::Classification, String>>,
+{
+    progress(ClassifyPass::Ocr, "Filename unclear - extracting text from image (OCR)...");
+    let ocr_error = match ocr.await {
+        Ok(classification) => return Some((classification, ClassifyPass::Ocr)),
+        Err(e) => e,
+    };
+    // OCR-only mode never uploads images for vision
+    if mode == buckets::ClassificationMode::Ocr {
+        println!("[CLASSIFY] OCR unavailable ({}), keeping the filename result for {}", ocr_error, filename);
+        return None;
+    }
+    if ocr_error == OCR_INSUFFICIENT_TEXT {
+        println!("[CLASSIFY] OCR extracted too little text, falling back to vision for {}", filename);
+    } else {
+        eprintln!("[CLASSIFY] OCR failed ({}), falling back to vision for {}", ocr_error, filename);
+    }
+
+    progress(ClassifyPass::Vision, "OCR insufficient - analyzing with AI vision...");
+    match vision().await {
+        Ok(classification) => Some((classification, ClassifyPass::Vision)),
+        Err(e) => {
+            eprintln!("[CLASSIFY] Vision failed ({}), using the filename result for {}", e, filename);
+            None
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ExtractResult {
+    dest_folder: String,
+    files: Vec<String>,
+}
+
+/// Extract a zip archive into a destination folder
+///
+/// By default files go into a subfolder named after the archive (e.g. "week3.zip" -> "week3/").
+/// Nothing is extracted if any entry would overwrite a file or escape the destination.
+/// Called from frontend with: invoke('extract_archive', { filePath: '...', destFolder: '...', intoSubfolder: true })
+#[tauri::command(async)]
+fn extract_archive(
+    file_path: String,
+    dest_folder: String,
+    into_subfolder: Option<bool>,
+) -> Result<ExtractResult, CommandError> {
+    println!("[COMMAND] extract_archive: {} -> {}", file_path, dest_folder);
+
+    let source = validate_allowed_path(&file_path)?;
+    if !source.is_file() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+
+    let mut dest_dir = validate_allowed_path(&dest_folder)?;
+    if into_subfolder.unwrap_or(true) {
+        let stem = source.file_stem()
+            .ok_or_else(|| CommandError::InvalidPath("Invalid archive file name".to_string()))?;
+        dest_dir = dest_dir.join(stem);
+    }
+
+    let files = archive::extract(&source, &dest_dir)?;
+    println!("[COMMAND] Extracted {} file(s) to {}", files.len(), dest_dir.display());
+
+    Ok(ExtractResult {
+        dest_folder: dest_dir.to_string_lossy().to_string(),
+        files: files.iter().map(|f| f.to_string_lossy().to_string()).collect(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct ArchiveFolderResult {
+    zip_path: String,
+    file_count: usize,
+    originals_trashed: bool,
+}
+
+/// Compress a folder into a zip (e.g. an old semester's course folder)
+///
+/// With `trashOriginals`, the folder is sent to the recycle bin once the zip has been verified.
+/// The archive is recorded in the activity log.
+/// Called from frontend with: invoke('archive_folder', { path: '...', destZipfile: '...', trashOriginals: true })
+#[tauri::command]
+async fn archive_folder(
+    path: String,
+    dest_zipfile: String,
+    trash_originals: Option<bool>,
+) -> Result<ArchiveFolderResult, CommandError> {
+    println!("[COMMAND] archive_folder: {} -> {}", path, dest_zipfile);
+
+    let (result, entry) =
+        run_blocking(move || archive_to_zip(&path, &dest_zipfile, trash_originals.unwrap_or(false))).await??;
+
+    // Activity logging is best-effort so a database problem never hides a finished archive
+    if let Ok(worker) = db_worker() {
+        if let Err(e) = worker.call(move |db| db.add_activity(entry)).await {
+            eprintln!("[COMMAND] archive_folder: failed to log activity: {}", e);
+        }
+    }
+    Ok(result)
+}
+
+/// Zip `path` into `dest_zipfile` (trashing the folder afterwards if asked), returning the
+/// result and the activity entry to log for it
+fn archive_to_zip(
+    path: &str,
+    dest_zipfile: &str,
+    trash_originals: bool,
+) -> Result<(ArchiveFolderResult, ActivityEntry), CommandError> {
+    use std::path::Path;
+
+    let folder = validate_allowed_path(path)?;
+    if !folder.is_dir() {
+        return Err(CommandError::InvalidPath(format!("Not a folder: {}", path)));
+    }
+    let dest_zip = validate_allowed_path(dest_zipfile)?;
+    if let Some(parent) = dest_zip.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let file_count = archive::create(&folder, &dest_zip)?;
+
+    let mut originals_trashed = false;
+    if trash_originals {
+        trash::delete(&folder)
+            .map_err(|e| CommandError::IoError(format!("Archive created, but failed to move folder to recycle bin: {}", e)))?;
+        record_operation("trash", &folder, None);
+        originals_trashed = true;
+    }
+
+    let parent_of = |p: &Path| p.parent().map(|d| d.to_string_lossy().to_string()).unwrap_or_default();
+    let entry = ActivityEntry {
+        id: None,
+        filename: dest_zip.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        original_filename: folder.file_name().map(|n| n.to_string_lossy().to_string()),
+        source_path: Some(folder.to_string_lossy().to_string()),
+        session_id: None,
+        content_hash: checksum::hash_file(&dest_zip).ok(),
+        content_size: std::fs::metadata(&dest_zip).ok().map(|m| m.len()),
+        confidence: None,
+        from_folder: parent_of(&folder),
+        to_folder: parent_of(&dest_zip),
+        undone: false,
+        created_at: current_timestamp_ms(),
+    };
+
+    println!("[COMMAND] Archived {} file(s) to {}", file_count, dest_zip.display());
+    let result = ArchiveFolderResult {
+        zip_path: dest_zip.to_string_lossy().to_string(),
+        file_count,
+        originals_trashed,
+    };
+    Ok((result, entry))
+}
+
+/// Detect a file's type from its content (magic bytes), falling back to its extension
+///
+/// Called from frontend with: invoke('detect_file_type', { filePath: '...' })
+#[tauri::command]
+fn detect_file_type(file_path: String) -> Result<filetype::FileType, CommandError> {
+    let validated = validate_path(&file_path)?;
+    if !validated.is_file() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+    Ok(filetype::detect(&validated))
+}
+
+/// Get timestamps, size, MIME type and EXIF (for photos) of a file for the details panel
+///
+/// Called from frontend with: invoke('get_file_metadata', { filePath: '...' })
+#[tauri::command]
+fn get_file_metadata(file_path: String) -> Result<metadata::FileMetadata, CommandError> {
+    println!("[COMMAND] get_file_metadata: {}", file_path);
+    let validated = validate_path(&file_path)?;
+    if !validated.is_file() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+    Ok(metadata::read(&validated)?)
+}
+
+/// Search for files by name (substring or glob) with optional extension/size/date filters
+///
+/// `filters.roots` lists the folders to search, usually the watched folder and the destination base.
+///
+/// Called from frontend with: invoke('search_files', { query: 'ps3', filters: { roots: ['...'], extensions: ['pdf'] } })
+#[tauri::command]
+async fn search_files(query: String, filters: search::SearchFilters) -> Result<Vec<search::SearchResult>, CommandError> {
+    println!("[COMMAND] search_files: '{}' in {} folder(s)", query, filters.roots.len());
+
+    for root in &filters.roots {
+        validate_path(root)?;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let watch_profiles = stored_watch_profiles();
+        search::search(&query, filters, &|path| profiles::excluded(&watch_profiles, path))
+    })
+        .await
+        .map_err(|e| CommandError::IoError(format!("Search task failed: {}", e)))?
+        .map_err(CommandError::InvalidPath)
+}
+
+/// Get file count, total size, newest/oldest file and per-extension breakdown for each folder
+///
+/// Folders that can't be read get an entry with `error` set instead of failing the whole call.
+///
+/// Called from frontend with: invoke('get_folder_stats', { paths: ['...', '...'] })
+#[tauri::command]
+async fn get_folder_stats(paths: Vec<String>) -> Result<Vec<folder_stats::FolderStats>, CommandError> {
+    println!("[COMMAND] get_folder_stats: {} folder(s)", paths.len());
+
+    tokio::task::spawn_blocking(move || {
+        paths
+            .iter()
+            .map(|path| {
+                let stats = validate_allowed_path(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|folder| folder_stats::collect(&folder).map_err(|e| e.to_string()));
+                stats.unwrap_or_else(|e| folder_stats::FolderStats::failed(std::path::Path::new(path), e))
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| CommandError::IoError(format!("Folder stats task failed: {}", e)))
+}
+
+/// Size of a folder broken down by subfolder, `maxDepth` levels deep (default 3)
+///
+/// Called from frontend with: invoke('analyze_disk_usage', { path: '...', maxDepth: 2 })
+#[tauri::command]
+async fn analyze_disk_usage(path: String, max_depth: Option<usize>) -> Result<folder_stats::DiskUsage, CommandError> {
+    println!("[COMMAND] analyze_disk_usage: {} (depth {:?})", path, max_depth);
+    let folder = validate_allowed_path(&path)?;
+
+    tokio::task::spawn_blocking(move || folder_stats::disk_usage(&folder, max_depth.unwrap_or(3)))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Disk usage task failed: {}", e)))?
+        .map_err(CommandError::from)
+}
+
+/// Find groups of identical files under a folder (including subfolders), largest savings first
+///
+/// Called from frontend with: invoke('find_duplicates', { folder: '...' })
+#[tauri::command]
+async fn find_duplicates(folder: String) -> Result<Vec<dedupe::DuplicateGroup>, CommandError> {
+    println!("[COMMAND] find_duplicates: {}", folder);
+    let folder = validate_allowed_path(&folder)?;
+
+    tokio::task::spawn_blocking(move || dedupe::find(&folder))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Duplicate search task failed: {}", e)))?
+        .map_err(CommandError::from)
+}
+
+/// Replace copies of a file with hard links to the one being kept, freeing their space
+///
+/// Each copy must be on the same drive and still match the kept file's contents. Every link
+/// is recorded in the operations journal, so it can be undone (the copy gets its own
+/// contents back).
+/// Called from frontend with: invoke('link_duplicates', { keepPath: '...', copyPaths: ['...'] })
+#[tauri::command]
+async fn link_duplicates(keep_path: String, copy_paths: Vec<String>) -> Result<dedupe::LinkReport, CommandError> {
+    println!("[COMMAND] link_duplicates: {} cop(ies) of {}", copy_paths.len(), keep_path);
+    let keep = validate_allowed_path(&keep_path)?;
+    let mut report = dedupe::LinkReport::default();
+    let mut copies = Vec::new();
+    for path in copy_paths {
+        match validate_allowed_path(&path) {
+            Ok(copy) => copies.push(copy),
+            Err(e) => report.failed.push(journal::FileConflict { path, reason: e.to_string() }),
+        }
+    }
+
+    let linked = tokio::task::spawn_blocking(move || dedupe::link(&keep, &copies).map(|report| (keep, report)))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Hard-link task failed: {}", e)))?;
+    let (keep, linked) = linked?;
+    for copy in &linked.linked {
+        record_operation("hardlink", std::path::Path::new(copy), Some(&keep));
+    }
+    println!("[COMMAND] Linked {} cop(ies), {} bytes reclaimed", linked.linked.len(), linked.reclaimed);
+
+    report.linked = linked.linked;
+    report.reclaimed = linked.reclaimed;
+    report.failed.extend(linked.failed);
+    Ok(report)
+}
+
+/// Scan a directory and return list of subdirectories (at most `limit`, default 50,000)
+///
+/// With `stream`, each directory's subfolders are also emitted as `scan-folders-batch`
+/// events while the walk runs.
+///
+/// Called from frontend with: invoke('scan_folders', { path: '...', recursive: true })
+#[tauri::command(async)]
+fn scan_folders(
+    app: tauri::AppHandle,
+    path: String,
+    recursive: Option<bool>,
+    limit: Option<usize>,
+    stream: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let emit = |folders: Vec<String>| {
+        let _ = app.emit(SCAN_FOLDERS_BATCH_EVENT, scan::ScanBatch { root: path.clone(), entries: folders });
+    };
+    let on_batch: Option<&(dyn Fn(Vec<String>) + Sync)> = if stream.unwrap_or(false) { Some(&emit) } else { None };
+    list_folders(&path, recursive, limit, on_batch)
+}
+
+/// List the subfolders directly inside a folder, with counts of what's in each
+///
+/// Lets the destination-folder picker expand a deep tree one level at a time instead of
+/// walking all of it up front with a recursive `scan_folders`.
+///
+/// Called from frontend with: invoke('list_dir_children', { path: '...' })
+#[tauri::command(async)]
+fn list_dir_children(path: String) -> Result<Vec<scan::DirChild>, CommandError> {
+    let dir = validate_allowed_path(&path)?;
+    if !dir.is_dir() {
+        return Err(CommandError::InvalidPath(format!("Path is not a directory: {}", path)));
+    }
+    scan::list_children(std::path::Path::new(&path)).map_err(CommandError::IoError)
+}
+
+// Emitted by scan_folders with `stream` for each directory read
+const SCAN_FOLDERS_BATCH_EVENT: &str = "scan-folders-batch";
+
+fn list_folders(
+    path: &str,
+    recursive: Option<bool>,
+    limit: Option<usize>,
+    on_batch: Option<&(dyn Fn(Vec<String>) + Sync)>,
+) -> Result<Vec<String>, String> {
+    use std::path::Path;
+
+    println!("[COMMAND] scan_folders: {} (recursive: {:?})", path, recursive);
+
+    let dir = Path::new(path);
+
+    if !dir.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    if !dir.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    let watch_profiles = stored_watch_profiles();
+    let skip = |path: &Path| profiles::excluded(&watch_profiles, path);
+    let folders = scan::list_folders(dir, recursive.unwrap_or(false), limit.unwrap_or(scan::DEFAULT_LIMIT), on_batch, &skip)?;
+    println!("[COMMAND] Found {} folders", folders.len());
+    Ok(folders)
+}
+
+/// Get a preview of a file's content
+///
+/// For images: returns base64-encoded thumbnail data URL
 /// For PDFs: returns extracted text (first ~200 chars)
 /// For text files: returns first ~200 chars
 #[tauri::command]
-fn get_file_preview(file_path: String) -> Result<FilePreview, String> {
-    use base64::Engine;
+fn get_file_preview(file_path: String) -> Result<FilePreview, String> {
+    use base64::Engine;
+
+    // Validate path to prevent arbitrary file reads
+    let validated = validate_allowed_path(&file_path).map_err(|e| format!("{}", e))?;
+    let file_path = validated.to_string_lossy().to_string();
+
+    // Previewing an online-only file would download it
+    if metadata::skip_content(&validated) {
+        return Ok(FilePreview {
+            preview_type: "none".to_string(),
+            content: String::new(),
+            error: Some("Online-only file - not downloaded for preview".to_string()),
+            video: None,
+        });
+    }
+
+    // Route on the detected type rather than the extension (PDFs saved as .bin, etc.)
+    let ext = filetype::detect(&validated).extension;
+
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" => {
+            // Read image and base64 encode for thumbnail
+            let bytes = std::fs::read(&file_path)
+                .map_err(|e| format!("Failed to read image: {}", e))?;
+
+            // Skip files larger than 5MB for preview
+            if bytes.len() > 5 * 1024 * 1024 {
+                return Ok(FilePreview {
+                    preview_type: "image".to_string(),
+                    content: String::new(),
+                    error: Some("File too large for preview".to_string()),
+                    video: None,
+                });
+            }
+
+            let mime = match ext.as_str() {
+                "png" => "image/png",
+                "jpg" | "jpeg" => "image/jpeg",
+                "gif" => "image/gif",
+                "webp" => "image/webp",
+                "bmp" => "image/bmp",
+                _ => "image/png",
+            };
+
+            let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
+            Ok(FilePreview {
+                preview_type: "image".to_string(),
+                content: format!("data:{};base64,{}", mime, b64),
+                error: None,
+                video: None,
+            })
+        }
+        "pdf" => {
+            match classifier::extract_pdf_text(&file_path) {
+                Ok(text) => {
+                    let preview: String = text.chars().take(200).collect();
+                    Ok(FilePreview {
+                        preview_type: "text".to_string(),
+                        content: preview,
+                        error: None,
+                        video: None,
+                    })
+                }
+                Err(e) => Ok(FilePreview {
+                    preview_type: "text".to_string(),
+                    content: String::new(),
+                    error: Some(e),
+                    video: None,
+                }),
+            }
+        }
+        "txt" | "md" | "csv" => {
+            match std::fs::read_to_string(&file_path) {
+                Ok(text) => {
+                    let preview: String = text.chars().take(200).collect();
+                    Ok(FilePreview {
+                        preview_type: "text".to_string(),
+                        content: preview,
+                        error: None,
+                        video: None,
+                    })
+                }
+                Err(e) => Ok(FilePreview {
+                    preview_type: "text".to_string(),
+                    content: String::new(),
+                    error: Some(format!("Failed to read: {}", e)),
+                    video: None,
+                }),
+            }
+        }
+        ext if video::is_video_extension(ext) => {
+            let path = std::path::Path::new(&file_path);
+            let info = match video::probe(path) {
+                Ok(info) => info,
+                Err(e) => {
+                    return Ok(FilePreview {
+                        preview_type: "video".to_string(),
+                        content: String::new(),
+                        error: Some(e),
+                        video: None,
+                    })
+                }
+            };
+
+            // Metadata alone is still useful if the poster frame can't be grabbed
+            let content = match video::poster_frame(path, info.duration_secs) {
+                Ok(jpeg) => format!(
+                    "data:image/jpeg;base64,{}",
+                    base64::engine::general_purpose::STANDARD.encode(&jpeg)
+                ),
+                Err(e) => {
+                    eprintln!("[COMMAND] get_file_preview: {}", e);
+                    String::new()
+                }
+            };
+
+            Ok(FilePreview {
+                preview_type: "video".to_string(),
+                content,
+                error: None,
+                video: Some(info),
+            })
+        }
+        _ => {
+            Ok(FilePreview {
+                preview_type: "none".to_string(),
+                content: String::new(),
+                error: None,
+                video: None,
+            })
+        }
+    }
+}
+
+/// Scan a directory and return list of files (not directories), newest first
+///
+/// At most `limit` files are returned (default 50,000); older ones are dropped.
+///
+/// Called from frontend with: invoke('scan_files', { path: '...' })
+#[tauri::command(async)]
+fn scan_files(path: String, limit: Option<usize>) -> Result<Vec<FileEntry>, String> {
+    use std::path::Path;
+
+    println!("[COMMAND] scan_files: {}", path);
+
+    let dir = Path::new(&path);
+
+    if !dir.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    if !dir.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    let limit = limit.unwrap_or(scan::DEFAULT_LIMIT);
+    let files = scan::list_files(dir, limit)?;
+    println!("[COMMAND] Found {} files", files.len());
+    // A cut-off listing would drop the older files from the index
+    if files.len() < limit {
+        update_file_index(dir, &files);
+    }
+    Ok(files)
+}
+
+// Emitted by scan_files_stream for each batch of files, then once when it's done
+const SCAN_FILES_BATCH_EVENT: &str = "scan-files-batch";
+const SCAN_FILES_COMPLETE_EVENT: &str = "scan-files-complete";
+
+/// Scan a directory like `scan_files`, but send the files as `scan-files-batch` events
+/// (unsorted, 500 at a time) followed by a `scan-files-complete` event, so a huge folder
+/// can be shown as it's read. Returns the same completion summary.
+///
+/// Called from frontend with: invoke('scan_files_stream', { path: '...' })
+#[tauri::command(async)]
+fn scan_files_stream(app: tauri::AppHandle, path: String, limit: Option<usize>) -> Result<scan::ScanComplete, String> {
+    use std::path::Path;
+
+    println!("[COMMAND] scan_files_stream: {}", path);
+
+    let dir = Path::new(&path);
+
+    if !dir.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    if !dir.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    // Kept for the file index, which needs the whole listing
+    let listed = std::cell::RefCell::new(Vec::new());
+    let on_batch = |files: Vec<FileEntry>| {
+        listed.borrow_mut().extend(files.iter().cloned());
+        let _ = app.emit(SCAN_FILES_BATCH_EVENT, scan::ScanBatch { root: path.clone(), entries: files });
+    };
+    let (total, truncated) = scan::stream_files(
+        dir,
+        limit.unwrap_or(scan::DEFAULT_LIMIT),
+        scan::STREAM_BATCH_SIZE,
+        &on_batch,
+    )?;
+    if !truncated {
+        update_file_index(dir, &listed.into_inner());
+    }
+
+    let complete = scan::ScanComplete { root: path.clone(), total, truncated };
+    let _ = app.emit(SCAN_FILES_COMPLETE_EVENT, &complete);
+    println!("[COMMAND] scan_files_stream: sent {} files", total);
+    Ok(complete)
+}
+
+// ============================================================
+// FILE INDEX
+// ============================================================
+
+/// The index's key for a file: its folder and path with the folder resolved like
+/// `validate_path` does, so every way of reaching a folder shares one set of rows
+fn index_location(folder: &std::path::Path, name: &std::ffi::OsStr) -> Option<(String, String)> {
+    let folder = validate_path(&folder.to_string_lossy()).ok()?;
+    Some((folder.to_string_lossy().to_string(), folder.join(name).to_string_lossy().to_string()))
+}
+
+/// Sync the index with a full listing of `dir` (best-effort, on the database thread)
+fn update_file_index(dir: &std::path::Path, files: &[FileEntry]) {
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let Some((folder, _)) = index_location(dir, std::ffi::OsStr::new("")) else {
+        return;
+    };
+    let files: Vec<FileEntry> = files
+        .iter()
+        .map(|f| FileEntry { path: std::path::Path::new(&folder).join(&f.name).to_string_lossy().to_string(), ..f.clone() })
+        .collect();
+    worker.submit(move |db| match db.sync_file_index(&folder, &files) {
+        Ok(changed) if changed > 0 => println!("[INDEX] {}: {} new or changed file(s)", folder, changed),
+        Ok(_) => {}
+        Err(e) => eprintln!("[INDEX] Failed to update index for {}: {}", folder, e),
+    });
+}
+
+/// Index a file the watcher just saw arrive
+fn index_detected_file(path: &std::path::Path) {
+    let (Ok(worker), Some(parent), Some(name)) = (db_worker(), path.parent(), path.file_name()) else {
+        return;
+    };
+    let (Some((folder, indexed_path)), Some(entry)) = (index_location(parent, name), file_index::entry_for(path)) else {
+        return;
+    };
+    let entry = FileEntry { path: indexed_path, ..entry };
+    worker.submit(move |db| {
+        if let Err(e) = db.index_file(&folder, &entry) {
+            eprintln!("[INDEX] Failed to index {}: {}", entry.path, e);
+        }
+    });
+}
+
+/// The index path of a file that has just been moved away from `source_path`, under
+/// which its arrival was recorded
+fn detection_key(source_path: &str) -> Option<String> {
+    let source = std::path::Path::new(source_path);
+    index_location(source.parent()?, source.file_name()?).map(|(_, path)| path)
+}
+
+/// Count a move towards the time-saved stats (best-effort)
+fn record_handled(db: &Database, source_path: Option<&str>, moved_at: i64) {
+    let key = source_path.and_then(detection_key);
+    if let Err(e) = db.record_handled_file(key.as_deref(), moved_at) {
+        eprintln!("[STATS] Failed to record handled file: {}", e);
+    }
+}
+
+/// Drop a file that left a watched folder from the index
+fn unindex_file(path: &std::path::Path) {
+    let (Ok(worker), Some(parent), Some(name)) = (db_worker(), path.parent(), path.file_name()) else {
+        return;
+    };
+    let Some((_, indexed_path)) = index_location(parent, name) else {
+        return;
+    };
+    worker.submit(move |db| {
+        let _ = db.remove_indexed_file(&indexed_path);
+    });
+}
+
+/// Keep a classification in the index, with the file's hash, so it's reused while the
+/// file is unchanged
+async fn remember_classification(indexed: IndexedFile) {
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let path = std::path::PathBuf::from(&indexed.path);
+    let hash = tokio::task::spawn_blocking(move || checksum::hash_file(&path).ok())
+        .await
+        .unwrap_or(None);
+    let indexed = IndexedFile { hash, ..indexed };
+    worker.submit(move |db| {
+        if let Err(e) = db.save_indexed_file(&indexed) {
+            eprintln!("[INDEX] Failed to save classification for {}: {}", indexed.path, e);
+        }
+    });
+}
+
+/// Count the indexed files in each folder, without listing them
+///
+/// Called from frontend with: invoke('get_file_index_summary', { folders: ['...'] })
+#[tauri::command]
+async fn get_file_index_summary(folders: Vec<String>) -> Result<Vec<file_index::IndexSummary>, CommandError> {
+    let folders: Vec<String> = folders
+        .iter()
+        .filter_map(|folder| validate_path(folder).ok())
+        .map(|folder| folder.to_string_lossy().to_string())
+        .collect();
+    Ok(db_worker()?
+        .call(move |db| folders.iter().map(|folder| db.file_index_summary(folder)).collect::<Result<Vec<_>, _>>())
+        .await?)
+}
+
+/// Undo a file move - move it back from destination to original source folder
+///
+/// The move is found by `activityId`, or by where the file is now when that isn't given, so a
+/// file edited or replaced since it was moved is never moved back.
+/// Called from frontend with: invoke('undo_move', { filePath: '...', originalFolder: '...', activityId: 12 })
+#[tauri::command]
+async fn undo_move(file_path: String, original_folder: String, activity_id: Option<i64>) -> Result<String, CommandError> {
+    println!("[COMMAND] undo_move: {} -> {}", file_path, original_folder);
+
+    let moved_to = file_path.clone();
+    let recorded = db_worker()?
+        .call(move |db| match activity_id {
+            Some(id) => db.get_activity(id),
+            None => db.find_activity_at(&moved_to),
+        })
+        .await?;
+    run_blocking(move || restore_moved_file(&file_path, &original_folder, recorded.as_ref())).await?
+}
+
+/// Move a file back to `original_folder`, first checking it's still the one `recorded` moved
+fn restore_moved_file(file_path: &str, original_folder: &str, recorded: Option<&ActivityEntry>) -> Result<String, CommandError> {
+    use std::fs;
+    use std::path::Path;
+
+    let source = validate_allowed_path(file_path)?;
+    let _ = validate_allowed_path(original_folder)?;
+
+    if !source.exists() {
+        return Err(CommandError::FileNotFound(file_path.to_string()));
+    }
+
+    if !source.is_file() {
+        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+    }
+
+    let dest_dir = Path::new(original_folder);
+    if !dest_dir.exists() {
+        fs::create_dir_all(&dest_dir)?;
+    }
+
+    let filename = source.file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid file path".to_string()))?;
+
+    let dest_path = dest_dir.join(filename);
+
+    if dest_path.exists() {
+        return Err(CommandError::DuplicateExists(dest_path.display().to_string()));
+    }
+
+    // Make sure this is still the file that was organized before moving it back,
+    // so newer work saved over it (or a different file under its name) isn't moved away.
+    // That hash also verifies a cross-device copy back, so the file is read only once.
+    match recorded {
+        Some(ActivityEntry { content_hash: Some(expected), content_size, .. }) => {
+            checksum::verify(&source, expected, *content_size)?;
+            checksum::move_with_hash(&source, &dest_path, expected)?;
+        }
+        _ => {
+            checksum::move_verified(&source, &dest_path)?;
+        }
+    }
+    record_operation("move", &source, Some(&dest_path));
+
+    println!("[COMMAND] Undo successful, file restored to: {}", dest_path.display());
+    Ok(format!("Restored to {}", dest_path.display()))
+}
+
+const DELETE_MODE_SETTING: &str = "delete_mode";
+const TRASH_RETENTION_SETTING: &str = "trash_retention_days";
+
+/// How deletes are performed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DeleteMode {
+    RecycleBin, // system recycle bin
+    AppTrash,   // app-managed .file-organiser-trash with retention
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteSettings {
+    mode: DeleteMode,
+    retention_days: u32,
+}
+
+fn stored_delete_settings(db: Option<&Database>) -> DeleteSettings {
+    let setting = |key: &str| db.and_then(|db| db.get_setting(key).ok().flatten());
+    DeleteSettings {
+        mode: match setting(DELETE_MODE_SETTING).as_deref() {
+            Some("app_trash") => DeleteMode::AppTrash,
+            _ => DeleteMode::RecycleBin,
+        },
+        retention_days: setting(TRASH_RETENTION_SETTING)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(safe_delete::DEFAULT_RETENTION_DAYS),
+    }
+}
+
+/// Get how deletes are performed and how long the app trash keeps files
+#[tauri::command]
+async fn get_delete_settings() -> Result<DeleteSettings, DbError> {
+    db_worker()?.call(|db| Ok(stored_delete_settings(Some(db)))).await
+}
+
+/// Choose between the system recycle bin and the app trash, and the app trash retention
+/// Called from frontend with: invoke('set_delete_settings', { mode: 'app_trash', retentionDays: 30 })
+#[tauri::command]
+async fn set_delete_settings(mode: DeleteMode, retention_days: u32) -> Result<(), DbError> {
+    println!("[COMMAND] set_delete_settings: {:?}, {} day(s)", mode, retention_days);
+    let retention_days = retention_days.clamp(1, 365);
+    db_worker()?
+        .call(move |db| {
+            let mode = match mode {
+                DeleteMode::RecycleBin => "recycle_bin",
+                DeleteMode::AppTrash => "app_trash",
+            };
+            db.set_setting(DELETE_MODE_SETTING, mode)?;
+            db.set_setting(TRASH_RETENTION_SETTING, &retention_days.to_string())
+        })
+        .await
+}
+
+/// List files in the app trash, most recently deleted first
+/// Called from frontend with: invoke('list_deleted')
+#[tauri::command]
+async fn list_deleted() -> Result<Vec<DeletedFile>, DbError> {
+    db_worker()?.call(safe_delete::prune_missing).await
+}
+
+/// Files the assistant deleted that can still be restored: the app trash, plus what it
+/// sent to the system recycle bin where the OS lets us read it (Windows, Linux)
+/// Called from frontend with: invoke('list_trash')
+#[tauri::command]
+async fn list_trash() -> Result<Vec<safe_delete::TrashEntry>, CommandError> {
+    // The listing's own errors (e.g. an unreadable recycle bin) come back inside the result
+    db_worker()?.call(|db| Ok(safe_delete::list(db))).await?
+}
+
+/// Restore deleted files (ids from `list_trash`) to where they were
+///
+/// A file whose original path has been taken again stays in the trash and is reported.
+/// Called from frontend with: invoke('restore_trash_items', { ids: ['app:3', 'bin:...'] })
+#[tauri::command]
+async fn restore_trash_items(ids: Vec<String>) -> Result<safe_delete::RestoreReport, CommandError> {
+    println!("[COMMAND] restore_trash_items: {} item(s)", ids.len());
+    db_worker()?.call(move |db| Ok(safe_delete::restore(db, &ids))).await?
+}
+
+/// Permanently remove app-trash files whose retention period has ended
+/// Called from frontend with: invoke('purge_deleted')
+#[tauri::command]
+async fn purge_deleted() -> Result<usize, DbError> {
+    println!("[COMMAND] purge_deleted");
+    let now = current_timestamp_ms();
+    db_worker()?.call(move |db| safe_delete::purge_expired(db, now)).await
+}
+
+/// Delete a file recoverably: to the system recycle bin, or to the app trash
+/// if that delete mode is selected
+///
+/// Called from frontend with: invoke('trash_file', { filePath: '...' })
+#[tauri::command]
+async fn trash_file(file_path: String) -> Result<String, CommandError> {
+    println!("[COMMAND] trash_file: {}", file_path);
+    with_optional_db(move |db| trash_path(&file_path, db)).await?
+}
+
+/// Delete a file the way the delete settings in the database say (the recycle bin without one)
+fn trash_path(file_path: &str, db: Option<&DbWorker>) -> Result<String, CommandError> {
+    let path = validate_allowed_path(file_path)?;
+    if !path.exists() {
+        return Err(CommandError::FileNotFound(file_path.to_string()));
+    }
+    if !path.is_file() {
+        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+    }
+
+    let settings = match db {
+        Some(worker) => worker.call_blocking(|db| Ok(stored_delete_settings(Some(db))))?,
+        None => stored_delete_settings(None),
+    };
+    if let (DeleteMode::AppTrash, Some(worker)) = (settings.mode, db) {
+        let deleted = safe_delete::move_to_trash(
+            &path,
+            safe_delete::trash_dir()?,
+            current_timestamp_ms(),
+            settings.retention_days,
+        )?;
+        // Journaled as a move so undo brings the file back from the app trash
+        record_operation("move", &path, Some(std::path::Path::new(&deleted.trash_path)));
+        worker.call_blocking(move |db| db.add_deleted_file(deleted))?;
+
+        println!("[COMMAND] File moved to app trash: {}", file_path);
+        return Ok(format!("Moved to app trash (kept for {} days)", settings.retention_days));
+    }
+
+    trash::delete(&path)
+        .map_err(|e| CommandError::IoError(format!("Failed to move to recycle bin: {}", e)))?;
+    record_operation("trash", &path, None);
+
+    println!("[COMMAND] File sent to recycle bin: {}", file_path);
+    Ok(format!("Sent to recycle bin"))
+}
+
+/// Make a filename valid for this platform (illegal characters replaced, whitespace
+/// collapsed, trailing dots/spaces and reserved names handled on Windows, length capped)
+///
+/// Called from frontend with: invoke('sanitize_filename', { name: '...' })
+#[tauri::command]
+fn sanitize_filename(name: String) -> Result<String, CommandError> {
+    filename::sanitize(&name).ok_or_else(|| CommandError::InvalidPath("Filename cannot be empty".to_string()))
+}
+
+/// A user- or AI-supplied new name, refusing anything that could leave the folder and
+/// sanitizing the rest
+fn checked_new_name(new_name: &str) -> Result<String, CommandError> {
+    if new_name.contains("..") {
+        return Err(CommandError::PathTraversal);
+    }
+    if new_name.contains('/') || new_name.contains('\\') {
+        return Err(CommandError::InvalidPath("New name cannot contain path separators".to_string()));
+    }
+    filename::sanitize(new_name).ok_or_else(|| CommandError::InvalidPath("New name cannot be empty".to_string()))
+}
+
+/// Rename a file in place (same directory, new name)
+///
+/// Called from frontend with: invoke('rename_file', { filePath: '...', newName: '...' })
+#[tauri::command]
+fn rename_file(file_path: String, new_name: String) -> Result<String, CommandError> {
+    use std::fs;
+    use std::path::Path;
+
+    println!("[COMMAND] rename_file: {} -> {}", file_path, new_name);
+
+    let _ = validate_allowed_path(&file_path)?;
+    let new_name = checked_new_name(&new_name)?;
+
+    let source = Path::new(&file_path);
+    if !source.exists() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+    if !source.is_file() {
+        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+    }
+
+    let parent = source.parent()
+        .ok_or_else(|| CommandError::InvalidPath("Cannot determine parent directory".to_string()))?;
+    let new_path = parent.join(&new_name);
+
+    if new_path.exists() {
+        return Err(CommandError::DuplicateExists(new_name));
+    }
+
+    fs::rename(&source, &new_path)?;
+    record_operation("rename", source, Some(&new_path));
+
+    let new_path_str = new_path.to_string_lossy().to_string();
+    println!("[COMMAND] File renamed to: {}", new_path_str);
+    Ok(new_path_str)
+}
+
+/// Rename a file and move it to a destination folder (atomic: rollback rename if move fails)
+///
+/// Called from frontend with: invoke('rename_and_move_file', { filePath: '...', newName: '...', destFolder: '...', reasoning: '...' })
+#[tauri::command(async)]
+fn rename_and_move_file(
+    file_path: String,
+    new_name: String,
+    dest_folder: String,
+    reasoning: Option<String>,
+) -> Result<String, CommandError> {
+    use std::fs;
+    use std::path::Path;
+
+    println!("[COMMAND] rename_and_move_file: {} -> {} into {}", file_path, new_name, dest_folder);
+
+    // Validate file path and destination
+    let _ = validate_allowed_path(&file_path)?;
+    let _ = validate_allowed_path(&dest_folder)?;
+
+    // Validate new name (same checks as rename_file)
+    let new_name = checked_new_name(&new_name)?;
+
+    // Validate source exists
+    let source = Path::new(&file_path);
+    if !source.exists() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+    if !source.is_file() {
+        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+    }
+
+    // Step 1: Rename in place
+    let parent = source.parent()
+        .ok_or_else(|| CommandError::InvalidPath("Cannot determine parent directory".to_string()))?;
+    let renamed_path = parent.join(&new_name);
+
+    if renamed_path.exists() {
+        return Err(CommandError::DuplicateExists(new_name.clone()));
+    }
+
+    fs::rename(&source, &renamed_path)?;
+    println!("[COMMAND] Step 1 - Renamed to: {}", renamed_path.display());
+
+    // Step 2: Move renamed file to dest_folder
+    let dest_dir = Path::new(&dest_folder);
+    if !dest_dir.exists() {
+        // Rollback rename before returning error
+        let _ = fs::rename(&renamed_path, &source);
+        return Err(CommandError::FileNotFound(dest_folder));
+    }
+
+    let final_path = dest_dir.join(&new_name);
+    if final_path.exists() {
+        // Rollback rename before returning error
+        let _ = fs::rename(&renamed_path, &source);
+        return Err(CommandError::DuplicateExists(new_name));
+    }
+
+    if let Err(e) = checksum::move_verified(&renamed_path, &final_path) {
+        // Rollback rename
+        println!("[COMMAND] Move failed, rolling back rename: {}", e);
+        let _ = fs::rename(&renamed_path, &source);
+        return Err(e);
+    }
+
+    record_operation("move", source, Some(&final_path));
+    record_provenance(source, &final_path, reasoning);
+
+    let final_path_str = final_path.to_string_lossy().to_string();
+    println!("[COMMAND] Step 2 - Moved to: {}", final_path_str);
+    Ok(final_path_str)
+}
+
+/// Create a folder if it doesn't exist
+///
+/// Called from frontend with: invoke('create_folder', { path: '...' })
+#[tauri::command]
+fn create_folder(path: String) -> Result<String, CommandError> {
+    use std::fs;
+
+    println!("[COMMAND] create_folder: {}", path);
+
+    let _ = validate_allowed_path(&path)?;
+
+    fs::create_dir_all(&path)?;
+    Ok(format!("Folder created: {}", path))
+}
+
+/// Show a file in Explorer/Finder/the Linux file manager with the file selected
+///
+/// If the file has since been moved or deleted, its folder is opened instead.
+///
+/// Called from frontend with: invoke('open_file_location', { path: '...' })
+#[tauri::command]
+fn open_file_location(path: String) -> Result<(), CommandError> {
+    println!("[COMMAND] open_file_location: {}", path);
+
+    let validated = validate_path(&path)?;
+    let result = if validated.exists() {
+        // Opens the parent folder with the item selected on every platform
+        tauri_plugin_opener::reveal_item_in_dir(&validated)
+    } else {
+        match validated.parent() {
+            Some(parent) if parent.is_dir() => tauri_plugin_opener::open_path(parent, None::<&str>),
+            _ => return Err(CommandError::FileNotFound(path)),
+        }
+    };
+
+    result.map_err(|e| CommandError::IoError(format!("Failed to open file manager: {}", e)))
+}
+
+/// Open a file in its default app, e.g. to check a pending file before approving its folder
+///
+/// Called from frontend with: invoke('open_file', { path: '...' })
+#[tauri::command]
+fn open_file(path: String) -> Result<(), CommandError> {
+    println!("[COMMAND] open_file: {}", path);
+    open_in(&path, None)
+}
+
+/// Open a file in a specific app, one of those `get_open_with_apps` lists ("Preview", "code")
+///
+/// Called from frontend with: invoke('open_with', { path: '...', app: '...' })
+#[tauri::command]
+fn open_with(path: String, app: String) -> Result<(), CommandError> {
+    println!("[COMMAND] open_with: {} in {}", path, app);
+    let app = launch::find_app(&app)
+        .ok_or_else(|| CommandError::InvalidInput(format!("Not an app files can be opened with: {}", app)))?;
+    open_in(&path, Some(app))
+}
+
+/// The installed apps `open_with` can open files in
+///
+/// Called from frontend with: invoke('get_open_with_apps')
+#[tauri::command]
+fn get_open_with_apps() -> Vec<String> {
+    launch::installed_apps()
+}
+
+fn open_in(path: &str, app: Option<&str>) -> Result<(), CommandError> {
+    let validated = validate_allowed_path(path)?;
+    if !validated.is_file() {
+        return Err(CommandError::FileNotFound(path.to_string()));
+    }
+    if !launch::is_openable(&validated) {
+        return Err(CommandError::PermissionDenied(format!(
+            "{} isn't a document, image or media file, so it isn't opened from here",
+            validated.display()
+        )));
+    }
+    tauri_plugin_opener::open_path(&validated, app)
+        .map_err(|e| CommandError::IoError(format!("Failed to open {}: {}", validated.display(), e)))
+}
+
+// ============================================================
+// NEW FOLDER SUGGESTIONS
+// ============================================================
+
+/// Propose new folders for unsorted files that share a topic
+///
+/// Looks at the given files plus everything quarantined as "unsorted", embeds each
+/// one's name and a content snippet, and clusters them. Each cluster of at least
+/// `topics::DEFAULT_MIN_CLUSTER_SIZE` files gets an AI-proposed folder name that
+/// doesn't clash with `available_folders`. Create the folder with `create_folder`.
+/// Called from frontend with: invoke('suggest_new_folders', { files: [...], availableFolders: [...] })
+#[tauri::command]
+async fn suggest_new_folders(
+    files: Option<Vec<String>>,
+    available_folders: Vec<String>,
+) -> Result<Vec<topics::TopicSuggestion>, String> {
+    println!("[COMMAND] suggest_new_folders ({} files given)", files.as_ref().map_or(0, Vec::len));
+    if classifier::privacy_mode() {
+        // Clustering and naming both send filenames to the AI
+        return Err("Folder suggestions are unavailable in privacy mode".to_string());
+    }
+    let api_key = request_api_key()?;
+
+    let mut paths = Vec::new();
+    for file in files.unwrap_or_default() {
+        let validated = validate_path(&file).map_err(|e| e.to_string())?;
+        if validated.is_file() {
+            paths.push(validated);
+        }
+    }
+    if let Ok(worker) = db_worker() {
+        let quarantined = worker.call(|db| db.get_quarantine_items()).await.unwrap_or_default();
+        paths.extend(
+            quarantined
+                .into_iter()
+                .filter(|item| item.reason == "unsorted")
+                .map(|item| std::path::PathBuf::from(item.path))
+                .filter(|path| path.is_file()),
+        );
+    }
+    paths.sort();
+    paths.dedup();
+    paths.truncate(topics::MAX_FILES);
+
+    if paths.len() < topics::DEFAULT_MIN_CLUSTER_SIZE {
+        return Ok(Vec::new());
+    }
+
+    // PDF text extraction is slow, so snippets are read off the async runtime
+    let (paths, texts) = tokio::task::spawn_blocking(move || {
+        let texts = paths
+            .iter()
+            .map(|path| topics::embedding_text(path, extract_classification_text(path).ok().as_deref()))
+            .collect::<Vec<_>>();
+        (paths, texts)
+    })
+    .await
+    .map_err(|e| format!("Failed to read files: {}", e))?;
+
+    let embeddings = embeddings::compute_embedding(&api_key, texts).await?;
+    let clusters = topics::cluster(
+        &embeddings,
+        topics::DEFAULT_SIMILARITY_THRESHOLD,
+        topics::DEFAULT_MIN_CLUSTER_SIZE,
+    );
+
+    // Names are compared (and shown to the AI) without the destination base path
+    let mut taken: Vec<String> = available_folders
+        .iter()
+        .map(|folder| {
+            std::path::Path::new(folder)
+                .file_name()
+                .map_or_else(|| folder.clone(), |name| name.to_string_lossy().to_string())
+        })
+        .collect();
+    let mut suggestions = Vec::new();
+    for cluster in clusters {
+        let files: Vec<String> = cluster.members.iter().map(|&i| paths[i].to_string_lossy().to_string()).collect();
+        let filenames = cluster
+            .members
+            .iter()
+            .filter_map(|&i| paths[i].file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+
+        let raw_name = match classifier::suggest_topic_name(api_key.clone(), filenames, taken.clone()).await {
+            Ok(name) => name,
+            Err(e) => {
+                eprintln!("[COMMAND] Failed to name a cluster of {} files: {}", files.len(), e);
+                continue;
+            }
+        };
+        let Some(name) = topics::clean_folder_name(&raw_name, &taken) else {
+            continue;
+        };
+
+        taken.push(name.clone());
+        suggestions.push(topics::TopicSuggestion { name, files, cohesion: cluster.cohesion });
+    }
+
+    println!("[COMMAND] Suggested {} new folder(s) from {} unsorted files", suggestions.len(), paths.len());
+    Ok(suggestions)
+}
+
+/// Whether similarity features run on the local embedding model or the AI provider, and
+/// how far its download has got
+/// Called from frontend with: invoke('get_embedding_status')
+#[tauri::command]
+fn get_embedding_status() -> embeddings::ModelStatus {
+    embeddings::status()
+}
+
+/// Download the local embedding model again after a failed download
+/// Called from frontend with: invoke('download_embedding_model')
+#[tauri::command]
+async fn download_embedding_model() -> Result<(), String> {
+    println!("[COMMAND] download_embedding_model");
+    embeddings::retry_download().await
+}
+
+const EMBEDDING_FALLBACK_SETTING: &str = "embedding_provider_fallback";
+
+/// Apply the saved choice of whether the AI provider embeds texts until the local model is ready
+fn load_embedding_fallback(db: &Database) {
+    match db.get_setting(EMBEDDING_FALLBACK_SETTING) {
+        Ok(value) => embeddings::set_provider_fallback(value.as_deref() == Some("true")),
+        Err(e) => eprintln!("[APP] Failed to load the embedding fallback: {}", e),
+    }
+}
+
+/// Let the AI provider embed texts while the local model isn't ready
+///
+/// Off by default: similarity features then wait for the local model rather than send
+/// filenames and snippets to the provider.
+/// Called from frontend with: invoke('set_embedding_fallback', { enabled: true })
+#[tauri::command]
+async fn set_embedding_fallback(enabled: bool) -> Result<(), DbError> {
+    println!("[COMMAND] set_embedding_fallback: {}", enabled);
+    db_worker()?
+        .call(move |db| db.set_setting(EMBEDDING_FALLBACK_SETTING, if enabled { "true" } else { "false" }))
+        .await?;
+    embeddings::set_provider_fallback(enabled);
+    Ok(())
+}
+
+// ============================================================
+// NEAR-DUPLICATE FILES
+// ============================================================
+
+/// Pairs of files whose names and contents are nearly the same (e.g. "Report final.docx"
+/// and "Report final (2).docx" after small edits), most similar first
+///
+/// Exact copies are caught by hash when moving; this finds the near misses. Files are
+/// embedded on this computer when the local model is bundled, otherwise by the AI.
+/// Called from frontend with: invoke('find_similar_files', { files: [...], threshold: 0.95 })
+#[tauri::command]
+async fn find_similar_files(files: Vec<String>, threshold: Option<f32>) -> Result<Vec<topics::SimilarPair>, String> {
+    println!("[COMMAND] find_similar_files ({} files)", files.len());
+    let threshold = threshold.unwrap_or(topics::DEFAULT_DUPLICATE_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(CommandError::InvalidInput("Similarity threshold must be between 0 and 1".to_string()).to_string());
+    }
+
+    let mut paths = Vec::new();
+    for file in files {
+        let validated = validate_path(&file).map_err(|e| e.to_string())?;
+        if validated.is_file() {
+            paths.push(validated);
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    paths.truncate(topics::MAX_FILES);
+    if paths.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    let local = tokio::task::spawn_blocking(embeddings::available).await.unwrap_or(false);
+    if !local && classifier::privacy_mode() {
+        return Err("Finding similar files needs the local embedding model in privacy mode".to_string());
+    }
+    let api_key = if local || !embeddings::provider_fallback() { String::new() } else { request_api_key()? };
+
+    let (paths, texts) = tokio::task::spawn_blocking(move || {
+        let texts = paths
+            .iter()
+            .map(|path| topics::embedding_text(path, extract_classification_text(path).ok().as_deref()))
+            .collect::<Vec<_>>();
+        (paths, texts)
+    })
+    .await
+    .map_err(|e| format!("Failed to read files: {}", e))?;
+
+    let embeddings = embeddings::compute_embedding(&api_key, texts).await?;
+    let pairs: Vec<topics::SimilarPair> = topics::similar_pairs(&embeddings, threshold)
+        .into_iter()
+        .map(|(i, j, similarity)| topics::SimilarPair {
+            a: paths[i].to_string_lossy().to_string(),
+            b: paths[j].to_string_lossy().to_string(),
+            similarity,
+        })
+        .collect();
+
+    println!("[COMMAND] Found {} similar pair(s) among {} files", pairs.len(), paths.len());
+    Ok(pairs)
+}
+
+// ============================================================
+// TIMETABLE IMPORT
+// ============================================================
+
+// Timetable exports are small; anything bigger isn't one
+const MAX_TIMETABLE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// What importing a timetable created
+#[derive(Debug, Serialize)]
+struct TimetableImport {
+    folders: Vec<String>, // folder names under the base folder, one per course
+    created: usize,       // how many of them didn't exist yet
+    rules: Vec<Rule>,     // rules added (existing identical rules are not duplicated)
+}
+
+/// Read the courses out of a timetable .ics export, without creating anything
+///
+/// Called from frontend with: invoke('parse_timetable', { filePath: '...' })
+#[tauri::command]
+fn parse_timetable(file_path: String) -> Result<Vec<timetable::TimetableCourse>, CommandError> {
+    println!("[COMMAND] parse_timetable: {}", file_path);
+
+    let validated = validate_path(&file_path)?;
+    if !validated.is_file() {
+        return Err(CommandError::FileNotFound(file_path));
+    }
+    if std::fs::metadata(&validated)?.len() > MAX_TIMETABLE_BYTES {
+        return Err(CommandError::InvalidPath("File is too large to be a timetable".to_string()));
+    }
+    let content = std::fs::read_to_string(&validated)?;
+    let courses = timetable::parse_ics(&content);
+
+    println!("[COMMAND] parse_timetable: found {} course(s)", courses.len());
+    Ok(courses)
+}
+
+/// Create a folder and a filename rule for each course from `parse_timetable`
+///
+/// Folders go under `base_folder`; existing folders are reused.
+/// Called from frontend with: invoke('import_timetable', { courses: [...], baseFolder: '...' })
+#[tauri::command]
+async fn import_timetable(
+    courses: Vec<timetable::TimetableCourse>,
+    base_folder: String,
+) -> Result<TimetableImport, CommandError> {
+    println!("[COMMAND] import_timetable: {} course(s) into {}", courses.len(), base_folder);
+
+    let base = validate_allowed_path(&base_folder)?;
+    let mut result = TimetableImport { folders: Vec::new(), created: 0, rules: Vec::new() };
+    let mut wanted_rules = Vec::new();
+
+    for course in courses {
+        let name = course.folder_name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(CommandError::InvalidPath(format!("Invalid folder name: {}", course.folder_name)));
+        }
+        let folder = base.join(name);
+        if !folder.is_dir() {
+            std::fs::create_dir_all(&folder)?;
+            result.created += 1;
+        }
+        result.folders.push(name.to_string());
+        wanted_rules.push((course.rule_pattern, folder.to_string_lossy().to_string()));
+    }
+
+    result.rules = db_worker()?
+        .call(move |db| {
+            let existing = db.get_rules()?;
+            let mut added = Vec::new();
+            for (pattern, target_folder) in wanted_rules {
+                let exists = existing
+                    .iter()
+                    .any(|r| r.profile_id.is_none() && r.pattern == pattern && r.target_folder == target_folder);
+                if exists || pattern.trim().is_empty() {
+                    continue;
+                }
+                let id = db.add_rule(&pattern, &target_folder, None)?;
+                added.push(Rule {
+                    id: Some(id),
+                    pattern,
+                    target_folder,
+                    created_at: current_timestamp_ms(),
+                    profile_id: None,
+                });
+            }
+            Ok(added)
+        })
+        .await?;
+
+    println!(
+        "[COMMAND] import_timetable: {} folder(s) created, {} rule(s) added",
+        result.created,
+        result.rules.len()
+    );
+    Ok(result)
+}
+
+// ============================================================
+// ONBOARDING
+// ============================================================
+
+/// Work out a first setup from the user's existing course tree, without saving or moving anything
+///
+/// Returns the course folders under `root_path`, a rule for each course whose files share a
+/// word no other course's do, and where the newest files in `downloads_path` (the system
+/// Downloads folder if not given) would go with those folders and rules (see `onboarding`).
+/// Called from frontend with: invoke('bootstrap_setup', { rootPath: '...', downloadsPath: null })
+#[tauri::command]
+async fn bootstrap_setup(
+    root_path: String,
+    downloads_path: Option<String>,
+) -> Result<onboarding::BootstrapSetup, CommandError> {
+    println!("[COMMAND] bootstrap_setup: {}", root_path);
+
+    let root = validate_allowed_path(&root_path)?;
+    if !root.is_dir() {
+        return Err(CommandError::InvalidPath(format!("Path is not a directory: {}", root_path)));
+    }
+    let downloads = match downloads_path {
+        Some(path) => Some(validate_allowed_path(&path)?).filter(|dir| dir.is_dir()),
+        None => dirs::download_dir().filter(|dir| dir.is_dir()),
+    };
+
+    let existing_rules = db_worker()?.call(|db| db.get_rules()).await?;
+    let (courses, rules) = tokio::task::spawn_blocking(move || {
+        let courses = onboarding::find_courses(&root);
+        let rules = onboarding::suggest_rules(&courses, &existing_rules);
+        (courses, rules)
+    })
+    .await
+    .map_err(|e| CommandError::IoError(format!("Course scan failed: {}", e)))?;
+    let folders: Vec<String> = courses.iter().map(|course| course.path.clone()).collect();
+
+    let mut sample = Vec::new();
+    let mut sample_skipped = Vec::new();
+    if let Some(downloads) = &downloads {
+        let files = scan::list_files(downloads, onboarding::SAMPLE_SIZE).map_err(CommandError::InvalidPath)?;
+        let mut context = PlanContext::load(downloads, folders.clone(), Vec::new()).await?;
+        // As if the suggested rules had already been saved
+        context.rules.extend(rules.iter().map(onboarding::as_rule));
+
+        let mut plans = plan_files(files, &context);
+        while let Some((file, planned)) = plans.next().await {
+            match planned {
+                Ok(planned) => sample.push(planned),
+                Err(unplaced) => sample_skipped.push(planner::PlanSkip {
+                    source_path: file.path.clone(),
+                    filename: file.name.clone(),
+                    reason: unplaced.into_reason(),
+                }),
+            }
+        }
+    }
+
+    println!(
+        "[COMMAND] bootstrap_setup: {} course folder(s), {} rule(s), {} of {} sample file(s) placed",
+        folders.len(),
+        rules.len(),
+        sample.len(),
+        sample.len() + sample_skipped.len()
+    );
+    Ok(onboarding::BootstrapSetup {
+        root: root_path,
+        folders: courses
+            .into_iter()
+            .map(|course| onboarding::CourseFolder { files: course.filenames.len(), path: course.path })
+            .collect(),
+        rules,
+        downloads: downloads.map(|dir| dir.to_string_lossy().to_string()),
+        sample,
+        sample_skipped,
+    })
+}
+
+// ============================================================
+// COURSE SYNC (CANVAS / MOODLE)
+// ============================================================
+
+/// An enrolled course and the destination folder it was synced to
+#[derive(Debug, Serialize)]
+struct SyncedCourse {
+    course: lms::LmsCourse,
+    folder: Option<String>, // None: no matching folder and none was created
+    created: bool,
+}
+
+/// Get the LMS connection (provider, address and token)
+///
+/// Called from frontend with: invoke('get_lms_settings')
+#[tauri::command]
+async fn get_lms_settings() -> Result<lms::LmsSettings, DbError> {
+    db_worker()?.call(lms::load).await
+}
+
+/// Save the LMS connection
+///
+/// Called from frontend with: invoke('set_lms_settings', { settings: { provider: 'canvas', base_url: 'https://...', token: '...' } })
+#[tauri::command]
+async fn set_lms_settings(settings: lms::LmsSettings) -> Result<lms::LmsSettings, String> {
+    println!("[COMMAND] set_lms_settings: {:?} {}", settings.provider, settings.base_url);
+    let settings = lms::validate(settings)?;
+    let saved = settings.clone();
+    db_worker()
+        .map_err(|e| e.to_string())?
+        .call(move |db| lms::save(db, &saved))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Fetch the enrolled courses and give each matching destination folder the course's
+/// real name as its description
+///
+/// Courses with no matching folder get one under `base_folder`, if it's given.
+/// Called from frontend with: invoke('sync_lms_courses', { availableFolders: [...], baseFolder: '...' })
+#[tauri::command]
+async fn sync_lms_courses(
+    available_folders: Vec<String>,
+    base_folder: Option<String>,
+) -> Result<Vec<SyncedCourse>, String> {
+    println!("[COMMAND] sync_lms_courses: {} folder(s), base {:?}", available_folders.len(), base_folder);
+
+    let worker = db_worker().map_err(|e| e.to_string())?;
+    let settings = worker.call(lms::load).await.map_err(|e| e.to_string())?;
+    let courses = lms::fetch_courses(&settings).await?;
+
+    let base = match base_folder.filter(|b| !b.trim().is_empty()) {
+        Some(base) => Some(validate_allowed_path(&base).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    let mut synced = Vec::new();
+    for course in courses {
+        let mut created = false;
+        let folder = match (lms::match_folder(&course, &available_folders), &base) {
+            (Some(folder), _) => Some(folder.clone()),
+            (None, Some(base)) => {
+                let name = timetable::sanitize_folder_name(&course.description());
+                if name.is_empty() {
+                    None
+                } else {
+                    let folder = base.join(name);
+                    if !folder.is_dir() {
+                        std::fs::create_dir_all(&folder)
+                            .map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+                        created = true;
+                    }
+                    Some(folder.to_string_lossy().to_string())
+                }
+            }
+            (None, None) => None,
+        };
+        synced.push(SyncedCourse { course, folder, created });
+    }
+
+    let descriptions: Vec<(String, String)> = synced
+        .iter()
+        .filter_map(|s| s.folder.clone().map(|folder| (folder, s.course.description())))
+        .collect();
+    worker
+        .call(move |db| {
+            for (folder, description) in &descriptions {
+                db.set_folder_description(folder, Some(description))?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "[COMMAND] sync_lms_courses: {} course(s), {} matched, {} folder(s) created",
+        synced.len(),
+        synced.iter().filter(|s| s.folder.is_some() && !s.created).count(),
+        synced.iter().filter(|s| s.created).count()
+    );
+    Ok(synced)
+}
+
+// ============================================================
+// ACADEMIC PAPERS
+// ============================================================
+
+/// The DOI or arXiv ID of a PDF that looks like an academic paper
+async fn paper_identifier(validated: std::path::PathBuf) -> Result<Option<papers::PaperId>, String> {
+    if filetype::detect(&validated).extension != "pdf" {
+        return Ok(None);
+    }
+    tokio::task::spawn_blocking(move || papers::read_identifier(&validated))
+        .await
+        .map_err(|e| format!("Paper detection failed: {}", e))?
+}
+
+/// Check whether a PDF is an academic paper (has a DOI or arXiv ID on its first page)
+///
+/// Called from frontend with: invoke('detect_paper', { filePath: '...' })
+#[tauri::command]
+async fn detect_paper(file_path: String) -> Result<Option<papers::PaperId>, String> {
+    println!("[COMMAND] detect_paper: {}", file_path);
+    let validated = validate_path(&file_path).map_err(|e| e.to_string())?;
+    paper_identifier(validated).await
+}
+
+/// Add a paper to Zotero through its local connector (Zotero must be running)
+///
+/// Called from frontend with: invoke('send_paper_to_zotero', { filePath: '...' })
+#[tauri::command]
+async fn send_paper_to_zotero(file_path: String) -> Result<papers::PaperId, String> {
+    println!("[COMMAND] send_paper_to_zotero: {}", file_path);
+    let validated = validate_path(&file_path).map_err(|e| e.to_string())?;
+    let id = paper_identifier(validated.clone())
+        .await?
+        .ok_or("No DOI or arXiv ID found in this PDF")?;
+    if !papers::zotero_available().await {
+        return Err("Zotero isn't running (or its connector is disabled)".to_string());
+    }
+
+    let session_id = format!("fileorg-{}", current_timestamp_ms());
+    papers::send_to_zotero(&validated, &id, &session_id).await?;
+    println!("[COMMAND] send_paper_to_zotero: sent {:?}", id);
+    Ok(id)
+}
+
+/// Filename for a paper from its looked-up metadata ("Vaswani2017_Attention_Is_All_You_Need.pdf")
+async fn paper_filename(validated: std::path::PathBuf) -> Result<Option<String>, String> {
+    let Some(id) = paper_identifier(validated).await? else {
+        return Ok(None);
+    };
+    // The metadata comes from Crossref or arXiv
+    if classifier::local_only() {
+        return Ok(None);
+    }
+    let metadata = papers::lookup(&id).await?;
+    Ok(Some(papers::paper_filename(&metadata, "pdf")))
+}
+
+/// Suggest a filename for a file from its metadata
+///
+/// Academic papers are named after their first author, year and title, looked up from
+/// their DOI or arXiv ID. Returns None for anything else.
+/// Called from frontend with: invoke('suggest_filename', { filePath: '...' })
+#[tauri::command]
+async fn suggest_filename(file_path: String) -> Result<Option<String>, String> {
+    println!("[COMMAND] suggest_filename: {}", file_path);
+    let validated = validate_path(&file_path).map_err(|e| e.to_string())?;
+    let name = paper_filename(validated).await?;
+    println!("[COMMAND] suggest_filename: {:?}", name);
+    Ok(name)
+}
+
+/// Write a BibTeX entry for a paper, named after it, into `dest_folder`
+///
+/// The entry goes where the PDF is being filed (its own folder if `dest_folder` is None),
+/// so the two stay together. Returns the path of the .bib file.
+/// Called from frontend with: invoke('save_paper_bibtex', { filePath: '...', destFolder: '...' })
+#[tauri::command]
+async fn save_paper_bibtex(file_path: String, dest_folder: Option<String>) -> Result<String, String> {
+    println!("[COMMAND] save_paper_bibtex: {} -> {:?}", file_path, dest_folder);
+    let validated = validate_path(&file_path).map_err(|e| e.to_string())?;
+    let folder = match dest_folder.filter(|f| !f.trim().is_empty()) {
+        Some(folder) => validate_allowed_path(&folder).map_err(|e| e.to_string())?,
+        None => {
+            let parent = validated.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+            validate_allowed_path(&parent).map_err(|e| e.to_string())?
+        }
+    };
+    let filename = validated.file_name().ok_or("Invalid file path")?;
+    let id = paper_identifier(validated.clone())
+        .await?
+        .ok_or("No DOI or arXiv ID found in this PDF")?;
+
+    let bib_path = folder.join(filename).with_extension("bib");
+    if bib_path.exists() {
+        return Err(CommandError::DuplicateExists(bib_path.to_string_lossy().to_string()).to_string());
+    }
+    std::fs::write(&bib_path, papers::bibtex_entry(&id, &folder.join(filename)))
+        .map_err(|e| format!("Failed to write {}: {}", bib_path.display(), e))?;
+    Ok(bib_path.to_string_lossy().to_string())
+}
+
+// ============================================================
+// WEBHOOKS
+// ============================================================
+
+/// Post an event to the configured webhook in the background
+///
+/// Best-effort: does nothing if no webhook is enabled, and failures are only logged.
+fn notify_webhook(event: webhooks::WebhookEvent) {
+    let Ok(db) = get_db() else {
+        return;
+    };
+    let settings = match webhooks::load(&db) {
+        Ok(settings) if settings.enabled && !settings.url.is_empty() => settings,
+        _ => return,
+    };
+    let timestamp = current_timestamp_ms();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = webhooks::send(&settings.url, &event, timestamp).await {
+            eprintln!("[WEBHOOK] {}", e);
+        }
+    });
+}
+
+/// Send the "session completed" webhook for a session that just ended
+/// End a session whose moves have run and announce it (best-effort, queued behind its moves)
+fn finish_session(worker: &DbWorker, session_id: i64, skipped: usize, failed: usize) {
+    worker.submit(move |db| {
+        let _ = db.end_session(session_id);
+        notify_session_completed(db, session_id, skipped, failed);
+    });
+}
+
+fn notify_session_completed(db: &Database, session_id: i64, skipped: usize, failed: usize) {
+    let Some(session) = db.get_sessions().ok().and_then(|s| s.into_iter().find(|s| s.id == session_id)) else {
+        return;
+    };
+    notify_webhook(webhooks::WebhookEvent::SessionCompleted {
+        session_id,
+        label: session.label,
+        moved: session.file_count,
+        skipped,
+        failed,
+    });
+}
+
+/// Get the webhook URL and whether it's enabled
+///
+/// Called from frontend with: invoke('get_webhook_settings')
+#[tauri::command]
+async fn get_webhook_settings() -> Result<webhooks::WebhookSettings, DbError> {
+    db_worker()?.call(webhooks::load).await
+}
+
+/// Save the webhook URL and whether it's enabled
+///
+/// Called from frontend with: invoke('set_webhook_settings', { settings: { enabled: true, url: 'https://...' } })
+#[tauri::command]
+async fn set_webhook_settings(settings: webhooks::WebhookSettings) -> Result<webhooks::WebhookSettings, String> {
+    println!("[COMMAND] set_webhook_settings: enabled={}", settings.enabled);
+    let settings = webhooks::validate(settings)?;
+    let saved = settings.clone();
+    db_worker()
+        .map_err(|e| e.to_string())?
+        .call(move |db| webhooks::save(db, &saved))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Send a test event to a webhook URL and report whether it was accepted
+///
+/// Called from frontend with: invoke('test_webhook', { url: 'https://...' })
+#[tauri::command]
+async fn test_webhook(url: String) -> Result<(), String> {
+    println!("[COMMAND] test_webhook");
+    let settings = webhooks::validate(webhooks::WebhookSettings { enabled: true, url })?;
+    webhooks::send(&settings.url, &webhooks::WebhookEvent::Test, current_timestamp_ms()).await
+}
+
+/// Report a file the frontend moved automatically (auto-move above the confidence threshold)
+///
+/// Called from frontend with: invoke('notify_file_auto_moved', { sourcePath: '...', destPath: '...', confidence: 0.95 })
+#[tauri::command]
+fn notify_file_auto_moved(source_path: String, dest_path: String, confidence: Option<f32>) {
+    let filename = std::path::Path::new(&dest_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    notify_webhook(webhooks::WebhookEvent::FileMoved {
+        filename,
+        source_path,
+        dest_path,
+        confidence,
+        origin: "ai".to_string(),
+    });
+}
+
+// ============================================================
+// MARKDOWN EXPORT
+// ============================================================
+
+/// Write today's or this week's moves into a note in `path` (e.g. an Obsidian vault folder)
+///
+/// The note is named like a daily or weekly note ("2025-03-14.md", "2025-W11.md"). If it
+/// already exists, only the previously exported list in it is replaced. Returns the note's path.
+/// Called from frontend with: invoke('export_activity_markdown', { path: '...', period: 'day' })
+#[tauri::command]
+async fn export_activity_markdown(path: String, period: markdown::Period) -> Result<String, CommandError> {
+    println!("[COMMAND] export_activity_markdown: {:?} into {}", period, path);
+
+    let folder = validate_allowed_path(&path)?;
+    if !folder.is_dir() {
+        return Err(CommandError::FileNotFound(path));
+    }
+    let range = markdown::note_range(period, chrono::Local::now());
+    let entries = db_worker()?.call(|db| db.get_activity_log()).await?;
+    let section = markdown::render_section(&entries, &range);
+
+    let note_path = folder.join(format!("{}.md", range.file_stem));
+    let existing = match std::fs::read_to_string(&note_path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    std::fs::write(&note_path, markdown::merge_section(existing.as_deref(), &section))?;
+
+    println!("[COMMAND] export_activity_markdown: wrote {}", note_path.display());
+    Ok(note_path.to_string_lossy().to_string())
+}
+
+// ============================================================
+// HISTORY EXPORT
+// ============================================================
+
+/// Save the activity log as a CSV file at `path`, oldest move first: original filename,
+/// source, destination, confidence and whether it was undone
+///
+/// Archived moves are included, so it covers far more than the activity log shows: the last
+/// 20,000 moves. `filters` narrow it to a date range (e.g. a semester), a destination folder
+/// or moves that weren't undone. Returns the rows written.
+/// Called from frontend with: invoke('export_activity_csv', { path: '...', filters: { since: 1709251200000, until: 1719792000000, folder: '...', exclude_undone: true } })
+#[tauri::command]
+async fn export_activity_csv(path: String, filters: Option<export::ActivityFilters>) -> Result<usize, CommandError> {
+    println!("[COMMAND] export_activity_csv: {}", path);
+    let target = validate_allowed_path(&path)?;
+    if !target.parent().is_some_and(|dir| dir.is_dir()) {
+        return Err(CommandError::FileNotFound(path));
+    }
+
+    let entries = db_worker()?.call(|db| db.get_activity_history()).await?;
+    let table = export::activity_table(&entries, &filters.unwrap_or_default(), &chrono::Local);
+    std::fs::write(&target, table.render(report::Format::Csv, ""))?;
+
+    println!("[COMMAND] export_activity_csv: {} row(s)", table.rows.len());
+    Ok(table.rows.len())
+}
+
+/// Save the corrections as an OpenAI fine-tuning dataset (JSONL) at `path`, one example per
+/// accepted or corrected filing
+///
+/// Each example pairs the prompt the file was classified with (a filename-only one when it
+/// wasn't kept) with an answer naming the folder the user chose. Archived corrections are
+/// included, not just the newest 50 that prompts learn from, so the dataset covers the last
+/// 20,000. Returns the examples written.
+/// Called from frontend with: invoke('export_corrections_jsonl', { path: '...' })
+#[tauri::command]
+async fn export_corrections_jsonl(path: String) -> Result<usize, CommandError> {
+    println!("[COMMAND] export_corrections_jsonl: {}", path);
+    let target = validate_allowed_path(&path)?;
+    if !target.parent().is_some_and(|dir| dir.is_dir()) {
+        return Err(CommandError::FileNotFound(path));
+    }
+
+    let (corrections, prompts) = db_worker()?.call(|db| Ok((db.get_correction_history()?, db.get_prompts()?))).await?;
+    let (jsonl, count) = export::corrections_jsonl(&corrections, &prompts, classifier::filename_prompt);
+    std::fs::write(&target, jsonl)?;
+
+    println!("[COMMAND] export_corrections_jsonl: {} example(s)", count);
+    Ok(count)
+}
+
+// ============================================================
+// DIAGNOSTICS
+// ============================================================
+
+/// Check OCR, the API key, the database and access to the watched and destination folders
+///
+/// Folders from saved watch profiles are checked too. The API key check makes one cheap
+/// request to the provider's model list, so nothing is billed.
+///
+/// Called from frontend with: invoke('run_diagnostics', { watchPath: '...', destinationFolders: ['...'] })
+#[tauri::command]
+async fn run_diagnostics(
+    watch_path: Option<String>,
+    destination_folders: Vec<String>,
+) -> Result<diagnostics::DiagnosticsReport, CommandError> {
+    println!("[COMMAND] run_diagnostics: {} destination folder(s)", destination_folders.len());
+
+    let mut checks = vec![tokio::task::spawn_blocking(diagnostics::check_tesseract)
+        .await
+        .map_err(|e| CommandError::IoError(format!("Diagnostics task failed: {}", e)))?];
+
+    checks.push(if !classifier::ai_available() {
+        diagnostics::DiagnosticCheck::new("API key", diagnostics::CheckStatus::Ok, "Not used: cloud AI is turned off")
+    } else if !classifier::needs_api_key() {
+        diagnostics::DiagnosticCheck::new("API key", diagnostics::CheckStatus::Ok, "Not needed for the local provider")
+    } else {
+        match get_stored_api_key() {
+            Ok(key) => diagnostics::api_key_check(classifier::verify_api_key(&key).await),
+            Err(e) => diagnostics::DiagnosticCheck::new("API key", diagnostics::CheckStatus::Error, e),
+        }
+    });
+
+    let db = db_worker()?;
+    let integrity = db.call(|db| db.check_integrity()).await.map_err(|e| e.to_string());
+    checks.push(diagnostics::database_check(integrity));
+    if let Ok(location) = data_dir::current() {
+        let conflicts = data_dir::conflicted_copies(location);
+        if location.synced.is_some() || !conflicts.is_empty() {
+            checks.push(diagnostics::database_sync_check(location.synced.as_deref(), &conflicts));
+        }
+    }
+    let profiles = db.call(|db| db.get_watch_profiles()).await.unwrap_or_default();
+
+    let mut watch_folders: Vec<String> = watch_path.into_iter().collect();
+    let mut dest_folders = destination_folders;
+    for profile in profiles {
+        watch_folders.push(profile.watch_path);
+        dest_folders.extend(profile.folders);
+    }
+    watch_folders.sort();
+    watch_folders.dedup();
+    dest_folders.sort();
+    dest_folders.dedup();
+
+    let folder_checks = tokio::task::spawn_blocking(move || {
+        let watch = watch_folders.iter().map(|f| ("Watch folder", f));
+        let dest = dest_folders.iter().map(|f| ("Destination folder", f));
+        watch
+            .chain(dest)
+            .map(|(name, folder)| match validate_path(folder) {
+                Ok(path) => diagnostics::check_writable(name, &path),
+                Err(e) => diagnostics::DiagnosticCheck::new(name, diagnostics::CheckStatus::Error, e.to_string()),
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| CommandError::IoError(format!("Diagnostics task failed: {}", e)))?;
+    checks.extend(folder_checks);
+
+    let report = diagnostics::DiagnosticsReport::new(checks);
+    println!("[COMMAND] run_diagnostics: {} check(s), ok = {}", report.checks.len(), report.ok);
+    Ok(report)
+}
+
+// ============================================================
+// DATABASE COMMANDS
+// ============================================================
+
+/// Get the database instance
+fn get_db() -> Result<Arc<Database>, DbError> {
+    DATABASE
+        .get()
+        .cloned()
+        .ok_or_else(|| DbError::InitFailed("Database not initialized".to_string()))
+}
+
+/// Get the database worker handle (for async commands and fire-and-forget writes)
+fn db_worker() -> Result<DbWorker, DbError> {
+    DB_WORKER
+        .get()
+        .cloned()
+        .ok_or_else(|| DbError::InitFailed("Database not initialized".to_string()))
+}
+
+/// Run blocking file work (moves, copies, hashing) off the async runtime
+async fn run_blocking<R, F>(f: F) -> Result<R, CommandError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| CommandError::IoError(format!("Task failed: {}", e)))
+}
+
+/// Run file work off the async runtime, handing it the database worker (if there is one)
+/// for its reads and writes, for moves whose activity logging is best-effort
+///
+/// Only those short queries go to the worker, so a large move never holds up other commands.
+async fn with_optional_db<R, F>(f: F) -> Result<R, CommandError>
+where
+    F: FnOnce(Option<&DbWorker>) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let worker = db_worker().ok();
+    run_blocking(move || f(worker.as_ref())).await
+}
+
+/// Initialize the database (called during app setup)
+///
+/// The database lives in the app data folder unless portable mode or a relocation says
+/// otherwise (see data_dir.rs).
+fn init_database(app_handle: &tauri::AppHandle) -> Result<(), DbError> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| DbError::InitFailed(format!("Failed to get app data dir: {}", e)))?;
+
+    let location = data_dir::resolve(app_data_dir);
+    open_database(location.clone())?;
+    data_dir::finish_relocation(&location);
+    Ok(())
+}
+
+/// Open the database file and start its worker (shared by the app and command-line mode)
+///
+/// A database in a synced folder is opened in sync-safe mode, and not at all while another
+/// computer has it open.
+fn open_database(location: data_dir::DataLocation) -> Result<(), DbError> {
+    println!("[DB] Initializing database at: {}", location.database.display());
+    if let Some(provider) = &location.synced {
+        println!("[DB] Database is in {}, using sync-safe mode", provider);
+    }
+    data_dir::acquire_lock(&location).map_err(DbError::InitFailed)?;
+    data_dir::init(location.clone());
+
+    let db = Arc::new(Database::open(location.database.clone(), data_dir::storage_mode(&location))?);
+    let worker = DbWorker::spawn(db.clone())?;
+    DATABASE
+        .set(db)
+        .map_err(|_| DbError::InitFailed("Database already initialized".to_string()))?;
+
+    // Each priced API call is saved for the monthly budget
+    let recorder = worker.clone();
+    budget::set_recorder(move |usage| {
+        recorder.submit(move |db| {
+            if let Err(e) = db.add_api_usage(&usage) {
+                eprintln!("[BUDGET] Failed to record API usage: {}", e);
+            }
+        })
+    });
+    // and each classification's prompt for the fine-tuning export
+    let recorder = worker.clone();
+    classifier::set_prompt_recorder(move |filename, prompt| {
+        let (filename, prompt) = (filename.to_string(), prompt.to_string());
+        recorder.submit(move |db| {
+            if let Err(e) = db.save_prompt(&filename, &prompt, current_timestamp_ms()) {
+                eprintln!("[DB] Failed to save classification prompt: {}", e);
+            }
+        })
+    });
+    let _ = DB_WORKER.set(worker);
+
+    Ok(())
+}
+
+/// Helper to get current timestamp in milliseconds
+fn current_timestamp_ms() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// Add a correction to the database, with the AI's confidence in its suggestion if known
+#[tauri::command]
+async fn db_add_correction(
+    filename: String,
+    ai_suggested: String,
+    user_chose: String,
+    correction_type: String,
+    confidence: Option<f32>,
+) -> Result<i64, DbError> {
+    let correction = Correction {
+        id: None,
+        filename,
+        ai_suggested,
+        user_chose,
+        correction_type,
+        confidence,
+        created_at: current_timestamp_ms(),
+    };
+    db_worker()?.call(move |db| db.add_correction(correction)).await
+}
+
+/// Get all corrections
+#[tauri::command]
+async fn db_get_corrections() -> Result<Vec<Correction>, DbError> {
+    db_worker()?.call(move |db| db.get_corrections()).await
+}
+
+/// Clear all corrections
+#[tauri::command]
+async fn db_clear_corrections() -> Result<(), DbError> {
+    db_worker()?.call(move |db| db.clear_corrections()).await
+}
+
+/// Add an activity entry, returning its row id (used to undo/redo this exact move)
+#[tauri::command]
+async fn db_add_activity(
+    filename: String,
+    from_folder: String,
+    to_folder: String,
+    original_filename: Option<String>,
+    source_path: Option<String>,
+    session_id: Option<i64>,
+    confidence: Option<f32>,
+) -> Result<i64, DbError> {
+    // Hash the file at its new location so undo can check it's still the same file
+    let moved_path = std::path::Path::new(&to_folder).join(&filename);
+    let (content_hash, content_size) = tokio::task::spawn_blocking(move || {
+        (checksum::hash_file(&moved_path).ok(), std::fs::metadata(&moved_path).ok().map(|m| m.len()))
+    })
+    .await
+    .unwrap_or((None, None));
+
+    let entry = ActivityEntry {
+        id: None,
+        filename,
+        from_folder,
+        to_folder,
+        undone: false,
+        created_at: current_timestamp_ms(),
+        original_filename,
+        source_path,
+        session_id,
+        content_hash,
+        content_size,
+        confidence,
+    };
+    db_worker()?
+        .call(move |db| {
+            record_handled(db, entry.source_path.as_deref(), entry.created_at);
+            db.add_activity(entry)
+        })
+        .await
+}
+
+/// Get activity log
+#[tauri::command]
+async fn db_get_activity_log() -> Result<Vec<ActivityEntry>, DbError> {
+    db_worker()?.call(move |db| db.get_activity_log()).await
+}
+
+/// Mark activity as undone by its row id
+#[tauri::command]
+async fn db_mark_activity_undone(id: i64) -> Result<bool, DbError> {
+    db_worker()?.call(move |db| db.mark_activity_undone(id)).await
+}
+
+/// Clear activity log
+#[tauri::command]
+async fn db_clear_activity_log() -> Result<(), DbError> {
+    db_worker()?.call(move |db| db.clear_activity_log()).await
+}
+
+/// Start an organize session that groups the following moves
+#[tauri::command]
+async fn db_start_session(label: String) -> Result<i64, DbError> {
+    db_worker()?.call(move |db| db.create_session(&label)).await
+}
+
+/// Mark an organize session as finished
+#[tauri::command]
+async fn db_end_session(id: i64) -> Result<bool, DbError> {
+    db_worker()?
+        .call(move |db| {
+            let ended = db.end_session(id)?;
+            if ended {
+                notify_session_completed(db, id, 0, 0);
+            }
+            Ok(ended)
+        })
+        .await
+}
+
+/// Get all organize sessions with file counts
+#[tauri::command]
+async fn db_get_sessions() -> Result<Vec<Session>, DbError> {
+    db_worker()?.call(move |db| db.get_sessions()).await
+}
+
+/// Outcome of undoing a whole session
+#[derive(Debug, Serialize)]
+struct SessionUndoResult {
+    session_id: i64,
+    restored: usize,
+    conflicts: Vec<journal::FileConflict>,
+}
+
+/// Undo every move in an organize session, all-or-nothing
+///
+/// If any file can't be restored (missing, or its original spot is taken), nothing
+/// is moved and the per-file conflicts are returned so the user can resolve them. A session
+/// with moves missing from the history isn't undone at all.
+/// Called from frontend with: invoke('undo_session', { id: 7 })
+#[tauri::command]
+async fn undo_session(id: i64) -> Result<SessionUndoResult, CommandError> {
+    println!("[COMMAND] undo_session: {}", id);
+    let worker = db_worker()?;
+    run_blocking(move || undo_session_moves(&worker, id)).await?
+}
+
+/// Put every file a session moved back where it came from, or none of them
+fn undo_session_moves(worker: &DbWorker, id: i64) -> Result<SessionUndoResult, CommandError> {
+    use std::path::PathBuf;
+
+    let (entries, moves_made) =
+        worker.call_blocking(move |db| Ok((db.get_session_activity(id)?, db.get_session_move_count(id)?)))?;
+    if (entries.len() as i64) < moves_made {
+        // Restoring what's left would leave the rest of the session where it was moved to
+        return Err(CommandError::NotUndoable(format!(
+            "{} of this session's {} moves are no longer in the history",
+            moves_made - entries.len() as i64,
+            moves_made
+        )));
+    }
+    let entries: Vec<ActivityEntry> = entries.into_iter().filter(|e| !e.undone).collect();
+
+    // Files edited or swapped since they were organized block the undo like any other conflict
+    let changed: Vec<journal::FileConflict> = entries
+        .iter()
+        .filter_map(|e| {
+            let current = PathBuf::from(&e.to_folder).join(&e.filename);
+            let expected = e.content_hash.as_deref()?;
+            if !current.is_file() {
+                return None;
+            }
+            checksum::verify(&current, expected, e.content_size).err().map(|err| journal::FileConflict {
+                path: current.display().to_string(),
+                reason: err.to_string(),
+            })
+        })
+        .collect();
+    if !changed.is_empty() {
+        println!("[COMMAND] undo_session {} blocked by {} changed file(s)", id, changed.len());
+        return Ok(SessionUndoResult {
+            session_id: id,
+            restored: 0,
+            conflicts: changed,
+        });
+    }
+
+    let moves: Vec<(PathBuf, PathBuf)> = entries
+        .into_iter()
+        .map(|e| {
+            let current = PathBuf::from(&e.to_folder).join(&e.filename);
+            let original = match e.source_path {
+                Some(path) => PathBuf::from(path),
+                None => PathBuf::from(&e.from_folder)
+                    .join(e.original_filename.as_deref().unwrap_or(&e.filename)),
+            };
+            (current, original)
+        })
+        .collect();
+
+    if let Err(conflicts) = journal::relocate_all(&moves) {
+        println!("[COMMAND] undo_session {} blocked by {} conflict(s)", id, conflicts.len());
+        return Ok(SessionUndoResult {
+            session_id: id,
+            restored: 0,
+            conflicts,
+        });
+    }
+
+    let restored = moves.len();
+    worker.call_blocking(move |db| {
+        for (current, original) in &moves {
+            audit_logged(db, "undo", &current.to_string_lossy(), Some(&original.to_string_lossy()));
+        }
+        db.mark_session_undone(id)
+    })?;
+    println!("[COMMAND] undo_session {} restored {} file(s)", id, restored);
+    Ok(SessionUndoResult {
+        session_id: id,
+        restored,
+        conflicts: Vec::new(),
+    })
+}
+
+/// Add a classification rule, optionally limited to one watch profile
+#[tauri::command]
+async fn db_add_rule(pattern: String, target_folder: String, profile_id: Option<i64>) -> Result<i64, DbError> {
+    db_worker()?.call(move |db| db.add_rule(&pattern, &target_folder, profile_id)).await
+}
+
+/// Get all classification rules
+#[tauri::command]
+async fn db_get_rules() -> Result<Vec<Rule>, DbError> {
+    db_worker()?.call(move |db| db.get_rules()).await
+}
+
+/// Delete a classification rule
+#[tauri::command]
+async fn db_delete_rule(id: i64) -> Result<bool, DbError> {
+    db_worker()?.call(move |db| db.delete_rule(id)).await
+}
+
+/// Suggest rules from AI suggestions the user keeps correcting the same way
+///
+/// Each insight names a pattern and the full path of the folder to send it to, ready to
+/// pass to db_add_rule.
+///
+/// Called from frontend with: invoke('get_correction_insights', { availableFolders: [...] })
+#[tauri::command]
+async fn get_correction_insights(available_folders: Vec<String>) -> Result<Vec<insights::CorrectionInsight>, DbError> {
+    let (corrections, rules) = db_worker()?.call(|db| Ok((db.get_corrections()?, db.get_rules()?))).await?;
+    let insights = insights::suggest_rules(&corrections, &rules, &available_folders);
+    println!("[COMMAND] get_correction_insights: {} suggestion(s)", insights.len());
+    Ok(insights)
+}
+
+/// How long files waited to be organized and roughly how much sorting by hand that saved,
+/// for files moved since `since` (Unix timestamp ms, default: the start of this month)
+///
+/// Called from frontend with: invoke('get_time_saved', { since: null })
+#[tauri::command]
+async fn get_time_saved(since: Option<i64>) -> Result<insights::TimeSaved, DbError> {
+    let since = since.unwrap_or_else(|| {
+        use chrono::{Datelike, TimeZone};
+        let now = chrono::Local::now();
+        chrono::Local
+            .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+            .earliest()
+            .map(|start| start.timestamp_millis())
+            .unwrap_or_default()
+    });
+    db_worker()?
+        .call(move |db| Ok(insights::time_saved(&db.get_handled_files(since)?, since)))
+        .await
+}
+
+/// How often AI suggestions were accepted vs corrected, per destination folder and per rule
+///
+/// Called from frontend with: invoke('get_accuracy_report')
+#[tauri::command]
+async fn get_accuracy_report() -> Result<insights::AccuracyReport, DbError> {
+    db_worker()?
+        .call(|db| {
+            let corrections = db.get_corrections()?;
+            let activity = db.get_activity_log()?;
+            let rules = db.get_rules()?;
+            Ok(insights::accuracy_report(&corrections, &activity, &rules))
+        })
+        .await
+}
+
+/// Import data from localStorage (migration)
+#[tauri::command]
+async fn db_import_from_localstorage(
+    corrections: Vec<Correction>,
+    activity_log: Vec<ActivityEntry>,
+) -> Result<(usize, usize), DbError> {
+    db_worker()?
+        .call(move |db| {
+            let corrections_count = db.import_corrections(corrections)?;
+            let activity_count = db.import_activity_log(activity_log)?;
+            Ok((corrections_count, activity_count))
+        })
+        .await
+}
+
+/// Set per-folder preferences (replaces any existing preferences for the folder)
+/// Called from frontend with: invoke('db_set_folder_pref', { folder, neverSuggest, requireConfirm, namingTemplate, description })
+#[tauri::command]
+async fn db_set_folder_pref(
+    folder: String,
+    never_suggest: bool,
+    require_confirm: bool,
+    naming_template: Option<String>,
+    description: Option<String>,
+) -> Result<(), DbError> {
+    let pref = FolderPref {
+        folder,
+        never_suggest,
+        require_confirm,
+        naming_template: naming_template.filter(|t| !t.trim().is_empty()),
+        description: description.filter(|d| !d.trim().is_empty()),
+        updated_at: current_timestamp_ms(),
+    };
+    db_worker()?.call(move |db| db.set_folder_pref(pref)).await
+}
+
+/// Get preferences for all folders that have any set
+#[tauri::command]
+async fn db_get_folder_prefs() -> Result<Vec<FolderPref>, DbError> {
+    db_worker()?.call(|db| db.get_folder_prefs()).await
+}
+
+/// Reset a folder's preferences to defaults
+#[tauri::command]
+async fn db_delete_folder_pref(folder: String) -> Result<bool, DbError> {
+    db_worker()?.call(move |db| db.delete_folder_pref(&folder)).await
+}
+
+/// Point everything recorded under a folder at its new location after the user moved or
+/// renamed it outside the app: activity log entries, rule targets, folder preferences and
+/// the file index all change in one transaction, so undo and suggestions keep working.
+/// Called from frontend with: invoke('remap_paths', { oldPrefix: '...', newPrefix: '...' })
+#[tauri::command]
+async fn remap_paths(old_prefix: String, new_prefix: String) -> Result<db::RemapReport, CommandError> {
+    let trim = |p: &str| p.trim().trim_end_matches(['/', '\\']).to_string();
+    let (old, new) = (trim(&old_prefix), trim(&new_prefix));
+    if old.is_empty() || new.is_empty() {
+        return Err(CommandError::InvalidPath("Both the old and the new folder are needed".to_string()));
+    }
+    if old == new {
+        return Err(CommandError::InvalidPath("The old and new folders are the same".to_string()));
+    }
+    if old.contains("..") {
+        return Err(CommandError::PathTraversal);
+    }
+    if !validate_path(&new)?.is_dir() {
+        return Err(CommandError::FileNotFound(new));
+    }
+    println!("[COMMAND] remap_paths: {} -> {}", old, new);
+    let report = db_worker()?.call(move |db| db.remap_paths(&old, &new)).await?;
+    println!(
+        "[COMMAND] remap_paths: {} activity, {} rules, {} folder prefs, {} indexed files",
+        report.activity, report.rules, report.folder_prefs, report.file_index
+    );
+    Ok(report)
+}
+
+/// Activity log entries and indexed files whose files were deleted outside the app
+async fn find_orphaned_entries() -> Result<Vec<orphans::OrphanedEntry>, CommandError> {
+    let (activity, indexed) = db_worker()?
+        .call(|db| Ok((db.get_activity_log()?, db.get_indexed_paths()?)))
+        .await?;
+    tokio::task::spawn_blocking(move || orphans::find(&activity, &indexed, orphans::is_deleted))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Orphan check failed: {}", e)))
+}
+
+/// List the history and index entries whose files no longer exist, for review
+/// Called from frontend with: invoke('get_orphaned_entries')
+#[tauri::command]
+async fn get_orphaned_entries() -> Result<Vec<orphans::OrphanedEntry>, CommandError> {
+    println!("[COMMAND] get_orphaned_entries called");
+    let found = find_orphaned_entries().await?;
+    println!("[COMMAND] get_orphaned_entries: {} found", found.len());
+    Ok(found)
+}
+
+/// Remove the history and index entries whose files no longer exist
+///
+/// The files are checked again first, so an entry whose file came back since it was
+/// listed (e.g. restored from the recycle bin) is kept.
+/// Called from frontend with: invoke('purge_orphaned_entries')
+#[tauri::command]
+async fn purge_orphaned_entries() -> Result<orphans::PurgeReport, CommandError> {
+    println!("[COMMAND] purge_orphaned_entries called");
+    let found = find_orphaned_entries().await?;
+    let activity_ids: Vec<i64> = found.iter().filter(|o| o.kind == "activity").filter_map(|o| o.id).collect();
+    let index_paths: Vec<String> = found.into_iter().filter(|o| o.kind == "index").map(|o| o.path).collect();
+    let (activity, index) = db_worker()?
+        .call(move |db| db.purge_orphans(&activity_ids, &index_paths))
+        .await?;
+    println!("[COMMAND] purge_orphaned_entries: {} activity, {} indexed files", activity, index);
+    Ok(orphans::PurgeReport { activity, index })
+}
+
+/// Check database integrity, then VACUUM and checkpoint the WAL to reclaim space
+/// Called from frontend with: invoke('db_maintenance')
+#[tauri::command]
+async fn db_maintenance() -> Result<MaintenanceReport, DbError> {
+    println!("[COMMAND] db_maintenance called");
+    let report = db_worker()?.call(|db| db.run_maintenance()).await?;
+    println!(
+        "[COMMAND] db_maintenance: integrity_ok={}, {} -> {} bytes",
+        report.integrity_ok, report.size_before, report.size_after
+    );
+    Ok(report)
+}
+
+// How long the frontend gets to show the result of relocate_database before the restart
+const RELOCATE_RESTART_DELAY: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// Where the database and app trash are, and whether portable mode is on
+/// Called from frontend with: invoke('get_data_location')
+#[tauri::command]
+fn get_data_location() -> Result<data_dir::DataLocation, CommandError> {
+    data_dir::current().cloned()
+}
+
+/// Move the database into another folder (e.g. next to a portable copy of the app)
+///
+/// Running file operations are finished and the watcher stopped first, the same way Quit
+/// does it, so nothing is written to the old database after it's copied. The app then
+/// restarts: on the new database if the copy was verified, on the old one otherwise.
+/// Called from frontend with: invoke('relocate_database', { newPath: '...' })
+#[tauri::command]
+async fn relocate_database(app_handle: tauri::AppHandle, new_path: String) -> Result<data_dir::DataLocation, CommandError> {
+    println!("[COMMAND] relocate_database: {}", new_path);
+    let from = data_dir::current()?.clone();
+    let new_dir = std::path::PathBuf::from(new_path.trim());
+    data_dir::check_target(&from, &new_dir)?;
+
+    finish_running_work().await;
+    let result = db_worker()?
+        .call(move |db| Ok(data_dir::relocate(db, &from, &new_dir)))
+        .await
+        .map_err(CommandError::from)
+        .and_then(|moved| moved);
+    match &result {
+        Ok(location) => println!("[COMMAND] relocate_database: moved to {}, restarting", location.database.display()),
+        Err(e) => eprintln!("[COMMAND] relocate_database failed, restarting on the old database: {}", e),
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(RELOCATE_RESTART_DELAY).await;
+        shutdown_and_restart(&app_handle).await;
+    });
+    result
+}
+
+/// Switch to a database already in a folder, e.g. one another computer keeps in Dropbox
+///
+/// The current database stays where it is. The app restarts onto the chosen one.
+/// Called from frontend with: invoke('use_existing_database', { path: '...' })
+#[tauri::command]
+async fn use_existing_database(app_handle: tauri::AppHandle, path: String) -> Result<data_dir::DataLocation, CommandError> {
+    println!("[COMMAND] use_existing_database: {}", path);
+    let from = data_dir::current()?.clone();
+    let dir = std::path::PathBuf::from(path.trim());
+    let location = tokio::task::spawn_blocking(move || data_dir::use_existing(&from, &dir))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Database check failed: {}", e)))??;
+    println!("[COMMAND] use_existing_database: switching to {}, restarting", location.database.display());
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(RELOCATE_RESTART_DELAY).await;
+        shutdown_and_restart(&app_handle).await;
+    });
+    Ok(location)
+}
+
+// ============================================================
+// ORGANIZATION PLANS
+// ============================================================
+
+/// Scan a folder and propose where each file should go, without moving anything
+///
+/// Rules are applied first, then folders the user already chose for the same filename;
+/// remaining files are classified (see `plan_file`). The plan is kept in memory and can
+/// be executed with `apply_plan`.
+/// Called from frontend with: invoke('plan_organization', { folder: '...', availableFolders: [...], correctionHistory: [...] })
+#[tauri::command]
+async fn plan_organization(
+    folder: String,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<planner::OrganizationPlan, CommandError> {
+    println!("[COMMAND] plan_organization: {}", folder);
+
+    let validated = validate_allowed_path(&folder)?;
+    let files = scan_files(validated.to_string_lossy().to_string(), None).map_err(CommandError::InvalidPath)?;
+    let context = PlanContext::load(&validated, available_folders, correction_history).await?;
+
+    let mut moves = Vec::new();
+    let mut skipped = Vec::new();
+
+    let mut plans = plan_files(files, &context);
+    while let Some((file, planned)) = plans.next().await {
+        match planned {
+            Ok(planned) => moves.push(planned),
+            Err(unplaced) => skipped.push(planner::PlanSkip {
+                source_path: file.path.clone(),
+                filename: file.name.clone(),
+                reason: unplaced.into_reason(),
+            }),
+        }
+    }
+
+    let created_at = current_timestamp_ms();
+    let plan = planner::OrganizationPlan {
+        id: planner::next_plan_id(created_at),
+        folder,
+        created_at,
+        moves,
+        skipped,
+        trash: Vec::new(),
+    };
+    println!(
+        "[COMMAND] plan_organization: {} move(s), {} skipped (plan {})",
+        plan.moves.len(),
+        plan.skipped.len(),
+        plan.id
+    );
+
+    planner::store(plan.clone());
+    Ok(plan)
+}
+
+/// Plan several files at once (up to the AI concurrency limit), yielding them in order
+fn plan_files<'a>(
+    files: Vec<FileEntry>,
+    context: &'a PlanContext,
+) -> impl futures::Stream<Item = (FileEntry, Result<planner::PlannedMove, planner::Unplaced>)> + Unpin + 'a {
+    futures::stream::iter(files)
+        .map(move |file| async move {
+            let planned = plan_file(&file, context).await;
+            (file, planned)
+        })
+        .buffered(classifier::max_concurrent_requests())
+        .boxed()
+}
+
+/// Everything needed to decide where files go, loaded once per plan/organize run
+///
+/// If `folder` belongs to a watch profile, the profile's folders, rules and mode are used
+/// instead of the global ones.
+struct PlanContext {
+    rules: Vec<Rule>,
+    corrections: Vec<Correction>,
+    mode: ClassificationModeSettings,
+    available_folders: Vec<String>,
+    prompt_folders: Vec<String>, // available_folders with their descriptions, for the AI
+    correction_history: Vec<String>,
+    api_key: Option<String>,
+    min_confidence: Option<f32>, // the profile's threshold, if there is one
+    folder: String,                      // the folder being organized, as indexed
+    index: HashMap<String, IndexedFile>, // earlier classifications of its files, by path
+}
+
+impl PlanContext {
+    async fn load(
+        folder: &std::path::Path,
+        available_folders: Vec<String>,
+        correction_history: Vec<String>,
+    ) -> Result<Self, CommandError> {
+        let folder = folder.to_path_buf();
+        let indexed_folder = folder.to_string_lossy().to_string();
+        let index_key = indexed_folder.clone();
+        let (profile, rules, corrections, mut mode, index) = db_worker()?
+            .call(move |db| {
+                let profiles = db.get_watch_profiles()?;
+                let profile = profiles::profile_for_path(&profiles, &folder).cloned();
+                let rules = db.get_rules_for_profile(profile.as_ref().and_then(|p| p.id))?;
+                let index = db.get_file_index(&index_key)?;
+                Ok((profile, rules, db.get_corrections()?, stored_classification_mode(db)?, index))
+            })
+            .await?;
+
+        let mut available_folders = available_folders;
+        if let Some(profile) = &profile {
+            mode.mode = profiles::mode(profile);
+            if !profile.folders.is_empty() {
+                available_folders = profile.folders.clone();
+            }
+        }
+        Ok(PlanContext {
+            rules,
+            corrections,
+            mode,
+            prompt_folders: prompt_folders(available_folders.clone(), None).await,
+            available_folders: suggestable_folders(available_folders).await,
+            correction_history,
+            // Local providers take requests without a key
+            api_key: request_api_key().ok(),
+            min_confidence: profile.map(|p| p.min_confidence as f32),
+            folder: indexed_folder,
+            index,
+        })
+    }
+}
+
+/// Decide where one file should go: rules, then the correction cache, then the AI
+/// (or type buckets, in bucket mode or when local-only mode leaves no AI to ask)
+///
+/// Like the frontend pipeline, PDFs and text files (and images, in OCR mode) are classified
+/// from their content (falling back to the filename result if extraction fails). Returns the reason when
+/// the file should stay where it is.
+async fn plan_file(file: &FileEntry, context: &PlanContext) -> Result<planner::PlannedMove, planner::Unplaced> {
+    use planner::Unplaced;
+
+    let planned = |dest_folder: String, confidence: f32, reasoning: String, origin: &str| planner::PlannedMove {
+        source_path: file.path.clone(),
+        filename: file.name.clone(),
+        dest_folder,
+        confidence,
+        reasoning,
+        suggested_filename: None,
+        origin: origin.to_string(),
+    };
+
+    if let Some(rule) = planner::match_rule(&file.name, &context.rules) {
+        return Ok(planned(rule.target_folder.clone(), 1.0, format!("Matched rule: {}", rule.pattern), "rule"));
+    }
+    if let Some(folder) = planner::cached_folder(&file.name, &context.corrections, &context.available_folders) {
+        return Ok(planned(folder.clone(), 1.0, "Previously classified by you".to_string(), "cache"));
+    }
+
+    // Local-only mode without a local provider: sort by file type instead of asking the AI
+    if context.mode.mode == buckets::ClassificationMode::Buckets || !classifier::ai_available() {
+        let source = std::path::Path::new(&file.path);
+        let base = bucket_base_folder(source, context.mode.bucket_folder.as_deref());
+        let c = buckets::classify(source, &base);
+        if c.suggested_folder == "__UNSORTED__" {
+            return Err(Unplaced::NoMatch(c.reasoning));
+        }
+        return Ok(planned(c.suggested_folder, c.confidence, c.reasoning, "type"));
+    }
+
+    // Classified on an earlier run and unchanged since: reuse the answer instead of asking again
+    if let Some(indexed) = context.index.get(&file.path).filter(|i| i.is_current(file)) {
+        let reasoning = indexed.reasoning.clone().unwrap_or_default();
+        match (indexed.status.as_str(), &indexed.dest_folder) {
+            (file_index::STATUS_SKIPPED, _) => return Err(Unplaced::NoMatch(reasoning)),
+            (file_index::STATUS_CLASSIFIED, Some(dest)) if context.available_folders.contains(dest) => {
+                let dest = chat_notes_folder(std::path::Path::new(&file.path), dest).unwrap_or_else(|| dest.clone());
+                return Ok(planner::PlannedMove {
+                    suggested_filename: indexed.suggested_filename.clone(),
+                    ..planned(dest, indexed.confidence.unwrap_or(0.0), reasoning, "ai")
+                });
+            }
+            _ => {}
+        }
+    }
+    let indexed = IndexedFile::new(&context.folder, file, current_timestamp_ms());
+
+    let Some(key) = context.api_key.clone() else {
+        return Err(Unplaced::Failed("No rule matched and no API key is configured".to_string()));
+    };
+
+    let correction_history = pack_examples(
+        insights::add_similar_examples(context.correction_history.clone(), &context.corrections, &file.name),
+        &file.name,
+    )
+    .await;
+    let first_pass = classifier::classify_file(
+        key.clone(),
+        file.name.clone(),
+        context.prompt_folders.clone(),
+        correction_history.clone(),
+    )
+    .await
+    .map_err(|e| Unplaced::Failed(format!("Classification failed: {}", e)))?;
+
+    let source = std::path::Path::new(&file.path);
+    let detected = filetype::detect(source);
+    let content_text = if metadata::skip_content(source) {
+        // Online-only files stay in the cloud; the filename result stands
+        None
+    } else if let Some(link) = links::resolve(source) {
+        // Shortcuts are placed by their target (page titles aren't fetched in bulk runs)
+        Some(Ok(link.classification_text()))
+    } else if CONTENT_EXTRACTABLE_EXTENSIONS.contains(&detected.extension.as_str()) {
+        Some(extract_classification_text(source))
+    } else if context.mode.mode == buckets::ClassificationMode::Ocr && detected.mime_type.starts_with("image/") {
+        // OCR mode reads screenshots locally instead of leaving them to the filename
+        Some(classifier::extract_image_text(&file.path))
+    } else {
+        None
+    };
+    let classification = if let Some(content_text) = content_text {
+        let by_content = match content_text {
+            Ok(text) => {
+                classifier::classify_with_text_content(
+                    key,
+                    file.name.clone(),
+                    text,
+                    context.prompt_folders.clone(),
+                    correction_history,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        by_content.unwrap_or(first_pass)
+    } else {
+        first_pass
+    };
+
+    let skip_reason = if !classification.is_relevant {
+        Some(classification.reasoning.clone())
+    } else if classification.suggested_folder == "__UNSORTED__" {
+        Some("No matching folder".to_string())
+    } else {
+        None
+    };
+    if let Some(reason) = skip_reason {
+        remember_classification(IndexedFile {
+            status: file_index::STATUS_SKIPPED.to_string(),
+            reasoning: Some(reason.clone()),
+            ..indexed
+        })
+        .await;
+        return Err(Unplaced::NoMatch(reason));
+    }
+
+    // Papers are named from their metadata rather than the AI's guess
+    let paper_name = match detected.extension.as_str() {
+        "pdf" => paper_filename(source.to_path_buf()).await.unwrap_or_else(|e| {
+            eprintln!("[ORGANIZE] Paper lookup failed for {}: {}", file.name, e);
+            None
+        }),
+        _ => None,
+    };
+    let subfolder = confident_subfolder(&classification);
+    let suggested_filename = paper_name
+        .or(classification.suggested_filename)
+        .and_then(|n| filename::sanitize(&n))
+        .filter(|n| *n != file.name);
+    remember_classification(IndexedFile {
+        status: file_index::STATUS_CLASSIFIED.to_string(),
+        dest_folder: Some(classification.suggested_folder.clone()),
+        suggested_filename: suggested_filename.clone(),
+        confidence: Some(classification.confidence),
+        reasoning: Some(classification.reasoning.clone()),
+        ..indexed
+    })
+    .await;
+    // The index keeps the course folder, so the answer is still reused once it's in the notes
+    // subfolder or the one the AI chose
+    let dest_folder = chat_notes_folder(source, &classification.suggested_folder)
+        .or(subfolder)
+        .unwrap_or(classification.suggested_folder);
+    Ok(planner::PlannedMove {
+        suggested_filename,
+        ..planned(dest_folder, classification.confidence, classification.reasoning, "ai")
+    })
+}
+
+/// Move a planned file with the conflict policy and record it in the activity log
+///
+/// Activity logging is best-effort so a database problem never blocks the move.
+fn execute_planned_move(
+    planned: &planner::PlannedMove,
+    policy: ConflictPolicy,
+    db: Option<&DbWorker>,
+    session_id: Option<i64>,
+) -> Result<PolicyMoveResult, CommandError> {
+    use std::path::Path;
+
+    let source = validate_allowed_path(&planned.source_path)?;
+    if !source.is_file() {
+        return Err(CommandError::FileNotFound(planned.source_path.clone()));
+    }
+    let dest_dir = validate_allowed_path(&planned.dest_folder)?;
+    if !dest_dir.exists() {
+        std::fs::create_dir_all(&dest_dir)?;
+    }
+
+    // Ignore AI-suggested names that would escape the destination folder
+    let name = planned
+        .suggested_filename
+        .as_deref()
+        .and_then(|n| checked_new_name(n).ok())
+        .unwrap_or_else(|| planned.filename.clone());
+    let mut result = move_with_policy(&source, &dest_dir, std::ffi::OsStr::new(&name), policy)?;
+    if let Some(dest_path) = &result.dest_path {
+        record_provenance(&source, Path::new(dest_path), Some(planned.reasoning.clone()));
+    }
+
+    if let (Some(worker), Some(dest_path)) = (db, &result.dest_path) {
+        let final_name = Path::new(dest_path).file_name().map(|n| n.to_string_lossy().to_string());
+        let entry = ActivityEntry {
+            id: None,
+            filename: final_name.unwrap_or_else(|| planned.filename.clone()),
+            original_filename: Some(planned.filename.clone()),
+            source_path: Some(planned.source_path.clone()),
+            session_id,
+            // A rename didn't hash the file; undo needs its hash to check it's unchanged
+            content_hash: result.content_hash.clone().or_else(|| checksum::hash_file(Path::new(dest_path)).ok()),
+            content_size: std::fs::metadata(dest_path).ok().map(|m| m.len()),
+            confidence: Some(planned.confidence),
+            from_folder: Path::new(&planned.source_path)
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            to_folder: planned.dest_folder.clone(),
+            undone: false,
+            created_at: current_timestamp_ms(),
+        };
+        let logged = worker.call_blocking(move |db| {
+            record_handled(db, entry.source_path.as_deref(), entry.created_at);
+            db.add_activity(entry)
+        });
+        match logged {
+            Ok(id) => result.activity_id = Some(id),
+            Err(e) => eprintln!("[COMMAND] failed to log activity for {}: {}", planned.filename, e),
+        }
+    }
+    Ok(result)
+}
+
+// ============================================================
+// RESUMABLE SESSIONS
+// ============================================================
+// A session's planned moves are stored as pending before they run and marked done,
+// skipped or failed as they finish, so after a crash partway through a bulk organize
+// it's clear which files were handled and `resume_session` can carry out the rest.
+
+/// Record planned moves of a session as pending, returning their item ids in order
+///
+/// Best-effort like activity logging: without a database the moves just aren't resumable.
+fn record_session_items(
+    db: Option<&DbWorker>,
+    session_id: Option<i64>,
+    moves: &[planner::PlannedMove],
+    policy: ConflictPolicy,
+) -> Vec<Option<i64>> {
+    let (Some(worker), Some(session_id)) = (db, session_id) else {
+        return vec![None; moves.len()];
+    };
+    let items: Vec<(String, serde_json::Value)> = moves
+        .iter()
+        .map(|m| (m.source_path.clone(), serde_json::to_value(m).unwrap_or_default()))
+        .collect();
+    match worker.call_blocking(move |db| db.add_session_items(session_id, &items, policy.as_str())) {
+        Ok(ids) => ids.into_iter().map(Some).collect(),
+        Err(e) => {
+            eprintln!("[SESSION] Couldn't record the plan of session {}: {}", session_id, e);
+            vec![None; moves.len()]
+        }
+    }
+}
+
+/// `execute_planned_move`, then record how it went on the session item
+///
+/// Moves refused because the app is quitting stay pending, to be picked up by `resume_session`.
+fn execute_session_item(
+    planned: &planner::PlannedMove,
+    policy: ConflictPolicy,
+    db: Option<&DbWorker>,
+    session_id: Option<i64>,
+    item_id: Option<i64>,
+) -> Result<PolicyMoveResult, CommandError> {
+    let result = execute_planned_move(planned, policy, db, session_id);
+    if let (Some(worker), Some(item_id)) = (db, item_id) {
+        let (status, error) = match &result {
+            Ok(PolicyMoveResult { dest_path: Some(_), .. }) => (db::ITEM_DONE, None),
+            Ok(PolicyMoveResult { dest_path: None, .. }) => (db::ITEM_SKIPPED, None),
+            Err(CommandError::Cancelled(_)) => return result,
+            Err(e) => (db::ITEM_FAILED, Some(e.to_string())),
+        };
+        if let Err(e) = worker.call_blocking(move |db| db.set_session_item_status(item_id, status, error.as_deref())) {
+            eprintln!("[SESSION] Couldn't record progress of {}: {}", planned.filename, e);
+        }
+    }
+    result
+}
+
+#[derive(Debug, Serialize)]
+struct ResumeSessionResult {
+    session_id: i64,
+    moved: usize,
+    already_done: usize, // moved before the interruption, but not yet marked done
+    skipped: usize,
+    failed: Vec<journal::FileConflict>,
+}
+
+/// Carry out the moves of an interrupted session that are still pending
+///
+/// Each runs with the conflict policy the session started with. A file the session had
+/// already moved when it was interrupted (the move went through, recording it didn't) is
+/// marked done rather than looked for again. Files gone from their old spot are reported
+/// in `failed`. Undone sessions can't be resumed.
+/// Called from frontend with: invoke('resume_session', { id: 12 })
+#[tauri::command]
+async fn resume_session(id: i64) -> Result<ResumeSessionResult, CommandError> {
+    println!("[COMMAND] resume_session: {}", id);
+    let worker = db_worker()?;
+    let result = run_blocking(move || {
+        let result = resume_pending_items(&worker, id)?;
+        finish_session(&worker, id, result.skipped, result.failed.len());
+        Ok::<_, CommandError>(result)
+    })
+    .await??;
+    println!(
+        "[COMMAND] resume_session {}: moved {}, already done {}, skipped {}, failed {}",
+        id,
+        result.moved,
+        result.already_done,
+        result.skipped,
+        result.failed.len()
+    );
+    Ok(result)
+}
+
+fn resume_pending_items(worker: &DbWorker, session_id: i64) -> Result<ResumeSessionResult, CommandError> {
+    let (session, activity, items) = worker.call_blocking(move |db| {
+        let session = db.get_sessions()?.into_iter().find(|s| s.id == session_id);
+        Ok((session, db.get_session_activity(session_id)?, db.get_session_items(session_id)?))
+    })?;
+    let session = session.ok_or_else(|| CommandError::InvalidInput(format!("Session not found: {}", session_id)))?;
+    if session.undone {
+        return Err(CommandError::NotUndoable("This session was undone, so it can't be resumed".to_string()));
+    }
+
+    let moved_sources: std::collections::HashSet<String> = activity
+        .into_iter()
+        .filter(|e| !e.undone)
+        .filter_map(|e| e.source_path)
+        .collect();
+    let set_status = |item_id: i64, status: &'static str, error: Option<String>| {
+        let _ = worker.call_blocking(move |db| db.set_session_item_status(item_id, status, error.as_deref()));
+    };
+
+    let mut result = ResumeSessionResult {
+        session_id,
+        moved: 0,
+        already_done: 0,
+        skipped: 0,
+        failed: Vec::new(),
+    };
+    for item in items {
+        if item.status != db::ITEM_PENDING {
+            continue;
+        }
+        if moved_sources.contains(&item.source_path) {
+            set_status(item.id, db::ITEM_DONE, None);
+            result.already_done += 1;
+            continue;
+        }
+        let planned: planner::PlannedMove = match serde_json::from_value(item.planned) {
+            Ok(planned) => planned,
+            Err(e) => {
+                let reason = format!("Couldn't read the planned move: {}", e);
+                set_status(item.id, db::ITEM_FAILED, Some(reason.clone()));
+                result.failed.push(journal::FileConflict { path: item.source_path, reason });
+                continue;
+            }
+        };
+        let policy = ConflictPolicy::parse(&item.policy).unwrap_or(ConflictPolicy::Rename);
+        match execute_session_item(&planned, policy, Some(worker), Some(session_id), Some(item.id)) {
+            Ok(PolicyMoveResult { dest_path: None, .. }) => result.skipped += 1,
+            Ok(_) => result.moved += 1,
+            Err(e) => result.failed.push(journal::FileConflict {
+                path: item.source_path,
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Serialize)]
+struct ApplyPlanResult {
+    plan_id: String,
+    session_id: Option<i64>,
+    moved: usize,
+    skipped: usize,
+    trashed: usize,
+    failed: Vec<journal::FileConflict>,
+}
+
+/// Execute a previously generated plan
+///
+/// Each file is moved (and renamed, if the plan suggests it), resolving name conflicts with
+/// the stored conflict policy. All moves are grouped into one session so they can be undone together.
+/// Files the plan proposes trashing (cleanup plans) are deleted recoverably, like `trash_file`.
+/// Files listed in `keep` are left alone, so the user can untick parts of the preview.
+/// Files that can no longer be moved are reported in `failed`; the rest still go ahead.
+/// Called from frontend with: invoke('apply_plan', { planId: '...' })
+#[tauri::command]
+async fn apply_plan(plan_id: String, keep: Option<Vec<String>>) -> Result<ApplyPlanResult, CommandError> {
+    println!("[COMMAND] apply_plan: {}", plan_id);
+
+    // Checked before the plan is taken, so it can be applied once the drive is back
+    if let Some(plan) = planner::get(&plan_id) {
+        check_destinations(plan.moves.iter().map(|m| &m.dest_folder))?;
+    }
+    let mut plan = planner::take(&plan_id)
+        .ok_or_else(|| CommandError::InvalidInput(format!("Plan not found or already applied: {}", plan_id)))?;
+    plan.keep(&keep.unwrap_or_default());
+
+    let result = with_optional_db(move |db| apply_plan_moves(plan, db)).await?;
+    println!(
+        "[COMMAND] apply_plan {}: moved {}, skipped {}, trashed {}, failed {}",
+        result.plan_id,
+        result.moved,
+        result.skipped,
+        result.trashed,
+        result.failed.len()
+    );
+    Ok(result)
+}
+
+/// Carry out a plan's moves as one session, then its proposed deletes
+fn apply_plan_moves(plan: planner::OrganizationPlan, db: Option<&DbWorker>) -> ApplyPlanResult {
+    let label = format!("Apply plan ({} files)", plan.moves.len());
+    let session_id = db.and_then(|worker| worker.call_blocking(move |db| db.create_session(&label)).ok());
+
+    let policy = stored_conflict_policy();
+    let mut moved = 0;
+    let mut skipped = 0;
+    let mut failed = Vec::new();
+
+    let item_ids = record_session_items(db, session_id, &plan.moves, policy);
+    for (planned, item_id) in plan.moves.iter().zip(item_ids) {
+        match execute_session_item(planned, policy, db, session_id, item_id) {
+            Ok(PolicyMoveResult { dest_path: None, .. }) => skipped += 1,
+            Ok(_) => moved += 1,
+            Err(e) => failed.push(journal::FileConflict {
+                path: planned.source_path.clone(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    let mut trashed = 0;
+    for item in &plan.trash {
+        match trash_path(&item.source_path, db) {
+            Ok(_) => trashed += 1,
+            Err(e) => failed.push(journal::FileConflict {
+                path: item.source_path.clone(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    if let (Some(worker), Some(id)) = (db, session_id) {
+        finish_session(worker, id, skipped, failed.len());
+    }
+
+    ApplyPlanResult {
+        plan_id: plan.id,
+        session_id,
+        moved,
+        skipped,
+        trashed,
+        failed,
+    }
+}
+
+/// Save a dry-run plan as a table of its proposed moves, with confidences and reasons, so a
+/// big reorganization can be reviewed before it's applied
+///
+/// The format follows the file's extension: .csv for a spreadsheet, .md or .markdown for
+/// notes. The plan itself is left as it was and can still be applied. Returns the rows written.
+/// Called from frontend with: invoke('export_plan_report', { planId: 'plan-...', path: '...' })
+#[tauri::command]
+fn export_plan_report(plan_id: String, path: String) -> Result<usize, CommandError> {
+    println!("[COMMAND] export_plan_report: {} to {}", plan_id, path);
+    let target = validate_allowed_path(&path)?;
+    let format = report::Format::from_path(&target)
+        .ok_or_else(|| CommandError::InvalidPath(format!("Reports are saved as .csv or .md: {}", path)))?;
+    if !target.parent().is_some_and(|dir| dir.is_dir()) {
+        return Err(CommandError::FileNotFound(path));
+    }
+    let plan = planner::get(&plan_id)
+        .ok_or_else(|| CommandError::InvalidInput(format!("Plan not found or already applied: {}", plan_id)))?;
+
+    let table = planner::report_table(&plan);
+    let created = chrono::DateTime::from_timestamp_millis(plan.created_at)
+        .map(|at| at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let title = format!("Plan for {} ({})", plan.folder, created);
+    std::fs::write(&target, table.render(format, &title))?;
+
+    println!("[COMMAND] export_plan_report: {} row(s)", table.rows.len());
+    Ok(table.rows.len())
+}
+
+#[derive(Debug, Serialize)]
+struct MergeFoldersResult {
+    session_id: Option<i64>,
+    moved: usize,
+    renamed: usize, // moved under a new name because the target already had one
+    replaced: usize,
+    skipped: usize,
+    failed: Vec<journal::FileConflict>,
+    source_removed: bool, // everything moved, so the emptied source folder was deleted
+}
+
+/// Merge one folder into another, e.g. `ML` into `Machine Learning`
+///
+/// Every file under `source` is moved to the same relative spot under `target` (subfolders
+/// are created as needed), resolving name clashes with `policy` or the stored default.
+/// The moves form one session, so `undo_session` puts everything back. Subfolders of
+/// `source` left empty are removed, and `source` itself if nothing was left behind.
+/// Called from frontend with: invoke('merge_folders', { source: '...', target: '...', policy: 'rename' })
+#[tauri::command]
+async fn merge_folders(source: String, target: String, policy: Option<ConflictPolicy>) -> Result<MergeFoldersResult, CommandError> {
+    let policy = policy.unwrap_or_else(stored_conflict_policy);
+    println!("[COMMAND] merge_folders: {} -> {} ({})", source, target, policy.as_str());
+
+    let tree = tokio::task::spawn_blocking(move || list_merge_tree(&source, &target))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Merge task failed: {}", e)))??;
+    let result = with_optional_db(move |db| merge_listed_tree(tree, policy, db)).await?;
+
+    println!(
+        "[COMMAND] merge_folders: moved {}, renamed {}, replaced {}, skipped {}, failed {}",
+        result.moved,
+        result.renamed,
+        result.replaced,
+        result.skipped,
+        result.failed.len()
+    );
+    Ok(result)
+}
+
+/// The files and folders under a merge's source, and where each file goes
+struct MergeTree {
+    source_dir: std::path::PathBuf,
+    target_dir: std::path::PathBuf,
+    dirs: Vec<std::path::PathBuf>,
+    moves: Vec<planner::PlannedMove>,
+}
+
+/// Walk `source` and plan each file's move to the same spot under `target`
+fn list_merge_tree(source: &str, target: &str) -> Result<MergeTree, CommandError> {
+    let source_dir = validate_allowed_path(source)?;
+    let target_dir = validate_allowed_path(target)?;
+    if !source_dir.is_dir() {
+        return Err(CommandError::FileNotFound(source.to_string()));
+    }
+    if !target_dir.is_dir() {
+        return Err(CommandError::FileNotFound(target.to_string()));
+    }
+    if target_dir.starts_with(&source_dir) || source_dir.starts_with(&target_dir) {
+        return Err(CommandError::InvalidPath("Can't merge a folder with a folder inside it".to_string()));
+    }
+
+    // List everything first so files moved in don't get walked again
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    let mut pending = vec![source_dir.clone()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            // Symlinked folders are moved as links, not followed
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+        dirs.push(dir);
+    }
+
+    let moves = files
+        .iter()
+        .map(|file| {
+            let relative_dir = file
+                .parent()
+                .and_then(|parent| parent.strip_prefix(&source_dir).ok())
+                .unwrap_or(std::path::Path::new(""));
+            planner::PlannedMove {
+                source_path: file.to_string_lossy().to_string(),
+                filename: file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                dest_folder: target_dir.join(relative_dir).to_string_lossy().to_string(),
+                confidence: 1.0,
+                reasoning: format!("Merged from {}", source_dir.display()),
+                suggested_filename: None,
+                origin: "merge".to_string(),
+            }
+        })
+        .collect();
+    Ok(MergeTree { source_dir, target_dir, dirs, moves })
+}
+
+/// Carry out a merge's moves as one session, then remove the folders they emptied
+fn merge_listed_tree(tree: MergeTree, policy: ConflictPolicy, db: Option<&DbWorker>) -> MergeFoldersResult {
+    let MergeTree { source_dir, target_dir, dirs, moves } = tree;
+    let source_name = source_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let target_name = target_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let label = format!("Merge {} into {} ({} files)", source_name, target_name, moves.len());
+    let session_id = db.and_then(|worker| worker.call_blocking(move |db| db.create_session(&label)).ok());
+
+    let mut result = MergeFoldersResult {
+        session_id,
+        moved: 0,
+        renamed: 0,
+        replaced: 0,
+        skipped: 0,
+        failed: Vec::new(),
+        source_removed: false,
+    };
+    let item_ids = record_session_items(db, session_id, &moves, policy);
+    for (planned, item_id) in moves.into_iter().zip(item_ids) {
+        match execute_session_item(&planned, policy, db, session_id, item_id) {
+            Ok(moved) => match moved.outcome.as_str() {
+                "renamed" => result.renamed += 1,
+                "replaced" => result.replaced += 1,
+                "skipped" => result.skipped += 1,
+                _ => result.moved += 1,
+            },
+            Err(e) => result.failed.push(journal::FileConflict {
+                path: planned.source_path,
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    // Deepest first, so a parent is only removed once its subfolders are gone
+    for dir in dirs.iter().rev() {
+        let _ = std::fs::remove_dir(dir);
+    }
+    result.source_removed = !source_dir.exists();
+
+    if let (Some(worker), Some(id)) = (db, session_id) {
+        finish_session(worker, id, result.skipped, result.failed.len());
+    }
+    result
+}
+
+/// Propose a cleanup of files that have sat in a folder for more than `older_than_days`
+///
+/// The watcher only sees files arriving while the app runs, so old downloads pile up. Each
+/// old file is classified like `plan_organization`; those that fit a folder are proposed as
+/// moves, and those that don't belong anywhere are proposed for the trash. Files that
+/// couldn't be classified are left alone. Apply the result with `apply_plan`.
+/// Called from frontend with: invoke('plan_aging_cleanup', { folder: '...', olderThanDays: 30, availableFolders: [...], correctionHistory: [...] })
+#[tauri::command]
+async fn plan_aging_cleanup(
+    folder: String,
+    older_than_days: u32,
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+) -> Result<planner::OrganizationPlan, CommandError> {
+    println!("[COMMAND] plan_aging_cleanup: {} (older than {} days)", folder, older_than_days);
+
+    let validated = validate_allowed_path(&folder)?;
+    let cutoff = cleanup_cutoff(current_timestamp_ms(), older_than_days);
+    let files: Vec<FileEntry> = scan_files(validated.to_string_lossy().to_string(), None)
+        .map_err(CommandError::InvalidPath)?
+        .into_iter()
+        .filter(|file| file.modified < cutoff)
+        .collect();
+    let context = PlanContext::load(&validated, available_folders, correction_history).await?;
+
+    let created_at = current_timestamp_ms();
+    let mut plan = planner::OrganizationPlan {
+        id: planner::next_plan_id(created_at),
+        folder,
+        created_at,
+        moves: Vec::new(),
+        skipped: Vec::new(),
+        trash: Vec::new(),
+    };
+    let mut plans = plan_files(files, &context);
+    while let Some((file, planned)) = plans.next().await {
+        plan.add_cleanup(&file.path, &file.name, planned);
+    }
+    println!(
+        "[COMMAND] plan_aging_cleanup: {} to move, {} to trash, {} skipped (plan {})",
+        plan.moves.len(),
+        plan.trash.len(),
+        plan.skipped.len(),
+        plan.id
+    );
+
+    planner::store(plan.clone());
+    Ok(plan)
+}
 
-    // Validate path to prevent arbitrary file reads
-    let validated = validate_path(&file_path).map_err(|e| format!("{}", e))?;
-    let file_path = validated.to_string_lossy().to_string();
+/// Files last modified before this (Unix seconds) are old enough for a cleanup plan
+fn cleanup_cutoff(now_ms: i64, older_than_days: u32) -> u64 {
+    ((now_ms / 1000) as u64).saturating_sub(older_than_days as u64 * 24 * 60 * 60)
+}
 
-    let ext = file_path.rsplit('.').next().unwrap_or("").to_lowercase();
+// ============================================================
+// ORGANIZE PIPELINE
+// ============================================================
 
-    match ext.as_str() {
-        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" => {
-            // Read image and base64 encode for thumbnail
-            let bytes = std::fs::read(&file_path)
-                .map_err(|e| format!("Failed to read image: {}", e))?;
+const ORGANIZE_PROGRESS_EVENT: &str = "organize-progress";
+const DESTINATIONS_AVAILABLE_EVENT: &str = "destinations-available";
 
-            // Skip files larger than 5MB for preview
-            if bytes.len() > 5 * 1024 * 1024 {
-                return Ok(FilePreview {
-                    preview_type: "image".to_string(),
-                    content: String::new(),
-                    error: Some("File too large for preview".to_string()),
-                });
-            }
+// Same cut-off the frontend uses before it trusts a suggestion
+const DEFAULT_ORGANIZE_MIN_CONFIDENCE: f32 = 0.7;
+const ORGANIZE_MIN_CONFIDENCE_SETTING: &str = "organize_min_confidence";
 
-            let mime = match ext.as_str() {
-                "png" => "image/png",
-                "jpg" | "jpeg" => "image/jpeg",
-                "gif" => "image/gif",
-                "webp" => "image/webp",
-                "bmp" => "image/bmp",
-                _ => "image/png",
-            };
+/// The cut-off below which organize runs leave AI suggestions in place
+fn stored_organize_min_confidence() -> f32 {
+    get_db()
+        .ok()
+        .and_then(|db| settings::get(&db, ORGANIZE_MIN_CONFIDENCE_SETTING).ok())
+        .and_then(|value| value.as_f64())
+        .map_or(DEFAULT_ORGANIZE_MIN_CONFIDENCE, |value| value as f32)
+}
 
-            let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
-            Ok(FilePreview {
-                preview_type: "image".to_string(),
-                content: format!("data:{};base64,{}", mime, b64),
-                error: None,
-            })
+/// Stop before moving anything when some of `folders` are on a drive that isn't connected,
+/// rather than failing every file bound for it, and watch for the drive to come back
+fn check_destinations<'a>(folders: impl IntoIterator<Item = &'a String>) -> Result<(), CommandError> {
+    let missing = volumes::unavailable(folders);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    println!("[ORGANIZE] Destination drive(s) not connected: {}", missing.join(", "));
+    volumes::wait_for(&missing);
+    Err(CommandError::DestinationUnavailable(missing.join(", ")))
+}
+
+/// Park a planned move as a deferred session item until `volume`, the drive its
+/// destination is on, is plugged back in
+fn defer_move(
+    db: Option<&DbWorker>,
+    session_id: Option<i64>,
+    planned: &planner::PlannedMove,
+    policy: ConflictPolicy,
+    volume: &str,
+) -> Result<(), String> {
+    let item_id = record_session_items(db, session_id, std::slice::from_ref(planned), policy)[0];
+    let (Some(worker), Some(item_id)) = (db, item_id) else {
+        return Err(format!("{} isn't plugged in", volume));
+    };
+    worker
+        .call_blocking(move |db| db.set_session_item_status(item_id, db::ITEM_DEFERRED, None))
+        .map_err(|e| format!("{} isn't plugged in, and the move couldn't be saved for later: {}", volume, e))?;
+    volumes::wait_for(&[volume.to_string()]);
+    Ok(())
+}
+
+/// Deferred moves carried out once their drive was back, sent as a `destinations-available` event
+#[derive(Debug, Serialize)]
+struct DeferredMovesResult {
+    volumes: Vec<String>,
+    moved: usize,
+    skipped: usize,
+    failed: Vec<journal::FileConflict>,
+}
+
+/// Carry out the deferred moves bound for `reconnected`, drives that were just plugged back
+/// in, then tell the frontend (and the user, in case the window is hidden in the tray)
+fn run_deferred_moves(app: &tauri::AppHandle, reconnected: Vec<String>) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let items = match worker.call_blocking(|db| db.get_deferred_items()) {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("[ORGANIZE] Failed to load deferred moves: {}", e);
+            return;
         }
-        "pdf" => {
-            match classifier::extract_pdf_text(&file_path) {
-                Ok(text) => {
-                    let preview: String = text.chars().take(200).collect();
-                    Ok(FilePreview {
-                        preview_type: "text".to_string(),
-                        content: preview,
-                        error: None,
-                    })
-                }
-                Err(e) => Ok(FilePreview {
-                    preview_type: "text".to_string(),
-                    content: String::new(),
-                    error: Some(e),
-                }),
+    };
+    let mut result = DeferredMovesResult {
+        volumes: reconnected,
+        moved: 0,
+        skipped: 0,
+        failed: Vec::new(),
+    };
+    for item in items {
+        let planned: planner::PlannedMove = match serde_json::from_value(item.planned) {
+            Ok(planned) => planned,
+            Err(e) => {
+                let reason = format!("Couldn't read the planned move: {}", e);
+                let (item_id, failed) = (item.id, reason.clone());
+                worker.submit(move |db| {
+                    let _ = db.set_session_item_status(item_id, db::ITEM_FAILED, Some(&failed));
+                });
+                result.failed.push(journal::FileConflict { path: item.source_path, reason });
+                continue;
             }
+        };
+        let back = volumes::volume_root(std::path::Path::new(&planned.dest_folder))
+            .is_some_and(|root| result.volumes.contains(&root.to_string_lossy().to_string()));
+        if !back {
+            continue;
         }
-        "txt" | "md" | "csv" => {
-            match std::fs::read_to_string(&file_path) {
-                Ok(text) => {
-                    let preview: String = text.chars().take(200).collect();
-                    Ok(FilePreview {
-                        preview_type: "text".to_string(),
-                        content: preview,
-                        error: None,
-                    })
-                }
-                Err(e) => Ok(FilePreview {
-                    preview_type: "text".to_string(),
-                    content: String::new(),
-                    error: Some(format!("Failed to read: {}", e)),
-                }),
+        let policy = ConflictPolicy::parse(&item.policy).unwrap_or(ConflictPolicy::Rename);
+        match execute_session_item(&planned, policy, Some(&worker), Some(item.session_id), Some(item.id)) {
+            Ok(PolicyMoveResult { dest_path: Some(_), .. }) => {
+                result.moved += 1;
+                let moved_from = item.source_path.clone();
+                worker.submit(move |db| {
+                    let _ = db.remove_indexed_file(&moved_from);
+                });
             }
+            Ok(PolicyMoveResult { dest_path: None, .. }) => result.skipped += 1,
+            Err(e) => result.failed.push(journal::FileConflict {
+                path: item.source_path,
+                reason: e.to_string(),
+            }),
         }
-        _ => {
-            Ok(FilePreview {
-                preview_type: "none".to_string(),
-                content: String::new(),
-                error: None,
-            })
+    }
+
+    println!(
+        "[ORGANIZE] {} plugged in: moved {} deferred file(s), skipped {}, failed {}",
+        result.volumes.join(", "),
+        result.moved,
+        result.skipped,
+        result.failed.len()
+    );
+    let _ = app.emit(DESTINATIONS_AVAILABLE_EVENT, &result);
+    if result.moved > 0 || !result.failed.is_empty() {
+        let body = scheduler::summary_message(result.moved, result.skipped, result.failed.len());
+        if let Err(e) = app.notification().builder().title("Drive plugged back in").body(&body).show() {
+            eprintln!("[ORGANIZE] Failed to show notification: {}", e);
         }
     }
 }
 
-/// Scan a directory and return list of files (not directories)
-///
-/// Called from frontend with: invoke('scan_files', { path: '...' })
-#[tauri::command]
-fn scan_files(path: String) -> Result<Vec<FileEntry>, String> {
-    use std::fs;
-    use std::path::Path;
-    use std::time::UNIX_EPOCH;
-
-    println!("[COMMAND] scan_files: {}", path);
+/// Options for `organize_folder`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct OrganizeOptions {
+    available_folders: Vec<String>,
+    correction_history: Vec<String>,
+    min_confidence: Option<f32>, // AI suggestions below this are left in place
+}
 
-    let dir = Path::new(&path);
+/// Per-file progress, sent as an `organize-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct OrganizeProgress {
+    folder: String,
+    index: usize, // 1-based
+    total: usize,
+    filename: String,
+    status: String, // "moved", "skipped", "failed"
+    dest_path: Option<String>,
+    message: Option<String>,
+}
 
-    if !dir.exists() {
-        return Err(format!("Path does not exist: {}", path));
-    }
+#[derive(Debug, Serialize)]
+struct OrganizeResult {
+    folder: String,
+    session_id: Option<i64>,
+    moved: usize,
+    deferred: usize, // parked until their destination drive is plugged back in
+    skipped: Vec<planner::PlanSkip>,
+    failed: Vec<journal::FileConflict>,
+}
 
-    if !dir.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
-    }
+/// Scan, classify and move every file in a folder, emitting progress when `app` is given
+async fn run_organize(
+    folder: String,
+    options: OrganizeOptions,
+    app: Option<&tauri::AppHandle>,
+) -> Result<OrganizeResult, CommandError> {
+    let validated = validate_allowed_path(&folder)?;
+    let files = scan_files(validated.to_string_lossy().to_string(), None).map_err(CommandError::InvalidPath)?;
+    let context = PlanContext::load(&validated, options.available_folders, options.correction_history).await?;
+    // Files bound for a drive that isn't plugged in are parked, and moved once it's back
+    let offline = volumes::unavailable(context.available_folders.iter().chain(context.rules.iter().map(|r| &r.target_folder)));
+    let min_confidence = context
+        .min_confidence
+        .or(options.min_confidence)
+        .unwrap_or_else(stored_organize_min_confidence);
+    let policy = stored_conflict_policy();
+
+    // Activity logging is best-effort so a database problem never blocks the moves
+    let db = db_worker().ok();
+    let label = format!("Organize {} ({} files)", folder, files.len());
+    let session_id = match &db {
+        Some(worker) => worker.call(move |db| db.create_session(&label)).await.ok(),
+        None => None,
+    };
 
-    let mut files = Vec::new();
+    let total = files.len();
+    let mut notified = 0;
+    let mut result = OrganizeResult {
+        folder: folder.clone(),
+        session_id,
+        moved: 0,
+        deferred: 0,
+        skipped: Vec::new(),
+        failed: Vec::new(),
+    };
 
-    match fs::read_dir(dir) {
-        Ok(entries) => {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let entry_path = entry.path();
-                    if entry_path.is_file() {
-                        if let Some(file_name) = entry_path.file_name() {
-                            if let Some(name_str) = file_name.to_str() {
-                                // Skip hidden files
-                                if name_str.starts_with('.') {
-                                    continue;
-                                }
-                                let metadata = entry.metadata().ok();
-                                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-                                let modified = metadata
-                                    .and_then(|m| m.modified().ok())
-                                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                                    .map(|d| d.as_secs())
-                                    .unwrap_or(0);
-                                files.push(FileEntry {
-                                    name: name_str.to_string(),
-                                    path: entry_path.to_string_lossy().to_string(),
-                                    size,
-                                    modified,
-                                });
-                            }
+    // Later files are classified while earlier ones are moved
+    let mut plans = plan_files(files, &context);
+    let mut i = 0;
+    while let Some((file, planned)) = plans.next().await {
+        i += 1;
+        let planned = planned.map_err(planner::Unplaced::into_reason).and_then(|planned| {
+            if planned.confidence < min_confidence {
+                Err(format!(
+                    "Low confidence ({:.0}%) for {}",
+                    planned.confidence * 100.0,
+                    planned.dest_folder
+                ))
+            } else {
+                Ok(planned)
+            }
+        });
+        let parked_on = planned
+            .as_ref()
+            .ok()
+            .and_then(|planned| volumes::volume_root(std::path::Path::new(&planned.dest_folder)))
+            .map(|root| root.to_string_lossy().to_string())
+            .filter(|root| offline.contains(root));
+
+        let (status, dest_path, message) = match planned {
+            Err(reason) => {
+                result.skipped.push(planner::PlanSkip {
+                    source_path: file.path.clone(),
+                    filename: file.name.clone(),
+                    reason: reason.clone(),
+                });
+                ("skipped", None, Some(reason))
+            }
+            Ok(planned) if parked_on.is_some() => {
+                let volume = parked_on.unwrap_or_default();
+                let (defer_db, parked) = (db.clone(), volume.clone());
+                let deferred = run_blocking(move || defer_move(defer_db.as_ref(), session_id, &planned, policy, &parked))
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()));
+                match deferred {
+                    Ok(()) => {
+                        result.deferred += 1;
+                        ("deferred", None, Some(format!("Waiting for {} to be plugged in", volume)))
+                    }
+                    Err(reason) => {
+                        result.skipped.push(planner::PlanSkip {
+                            source_path: file.path.clone(),
+                            filename: file.name.clone(),
+                            reason: reason.clone(),
+                        });
+                        ("skipped", None, Some(reason))
+                    }
+                }
+            }
+            Ok(planned) => {
+                let (origin, confidence) = (planned.origin.clone(), planned.confidence);
+                let move_db = db.clone();
+                let moved = run_blocking(move || {
+                    // Files are recorded as they're planned; ones not classified yet when the
+                    // run stops are still in the folder and get picked up by the next organize
+                    let item_id = record_session_items(move_db.as_ref(), session_id, std::slice::from_ref(&planned), policy)[0];
+                    execute_session_item(&planned, policy, move_db.as_ref(), session_id, item_id)
+                })
+                .await
+                .and_then(|r| r);
+
+                match moved {
+                    Ok(PolicyMoveResult { dest_path: Some(dest_path), activity_id, .. }) => {
+                        result.moved += 1;
+                        if let Some(worker) = &db {
+                            let moved_from = file.path.clone();
+                            worker.submit(move |db| {
+                                let _ = db.remove_indexed_file(&moved_from);
+                            });
+                        }
+                        if let Some(app) = app.filter(|app| notified < MAX_MOVE_NOTIFICATIONS && main_window_hidden(app)) {
+                            notify_auto_move(app, &file.path, &dest_path, activity_id);
+                            notified += 1;
                         }
+                        notify_webhook(webhooks::WebhookEvent::FileMoved {
+                            filename: file.name.clone(),
+                            source_path: file.path.clone(),
+                            dest_path: dest_path.clone(),
+                            confidence: Some(confidence),
+                            origin,
+                        });
+                        ("moved", Some(dest_path), None)
+                    }
+                    Ok(PolicyMoveResult { dest_path: None, .. }) => {
+                        let reason = "A file with this name is already in the destination".to_string();
+                        result.skipped.push(planner::PlanSkip {
+                            source_path: file.path.clone(),
+                            filename: file.name.clone(),
+                            reason: reason.clone(),
+                        });
+                        ("skipped", None, Some(reason))
+                    }
+                    Err(e) => {
+                        result.failed.push(journal::FileConflict {
+                            path: file.path.clone(),
+                            reason: e.to_string(),
+                        });
+                        ("failed", None, Some(e.to_string()))
                     }
                 }
             }
+        };
+
+        if let Some(app) = app {
+            let progress = OrganizeProgress {
+                folder: folder.clone(),
+                index: i,
+                total,
+                filename: file.name,
+                status: status.to_string(),
+                dest_path,
+                message,
+            };
+            let _ = app.emit(ORGANIZE_PROGRESS_EVENT, &progress);
         }
-        Err(e) => return Err(format!("Failed to read directory: {}", e)),
     }
 
-    // Sort by modification time, most recent first
-    files.sort_by(|a, b| b.modified.cmp(&a.modified));
-    println!("[COMMAND] Found {} files", files.len());
-    Ok(files)
+    if let (Some(worker), Some(id)) = (&db, session_id) {
+        finish_session(worker, id, result.skipped.len(), result.failed.len());
+    }
+    Ok(result)
 }
 
-/// Undo a file move - move it back from destination to original source folder
+/// Organize a whole folder in one call: rules, correction cache, classification,
+/// conflict policy and activity logging all run here instead of over many IPC calls
 ///
-/// Called from frontend with: invoke('undo_move', { filePath: '...', originalFolder: '...' })
+/// Emits an `organize-progress` event after each file. All moves share one session,
+/// so the run can be undone with `undo_session`.
+/// Called from frontend with: invoke('organize_folder', { path: '...', options: { available_folders: [...], correction_history: [...], min_confidence: 0.7 } })
 #[tauri::command]
-fn undo_move(file_path: String, original_folder: String) -> Result<String, CommandError> {
-    use std::fs;
-    use std::path::Path;
+async fn organize_folder(
+    app_handle: tauri::AppHandle,
+    path: String,
+    options: OrganizeOptions,
+) -> Result<OrganizeResult, CommandError> {
+    println!("[COMMAND] organize_folder: {}", path);
+
+    let result = run_organize(path, options, Some(&app_handle)).await?;
+    println!(
+        "[COMMAND] organize_folder {}: moved {}, skipped {}, failed {}",
+        result.folder,
+        result.moved,
+        result.skipped.len(),
+        result.failed.len()
+    );
+    Ok(result)
+}
 
-    println!("[COMMAND] undo_move: {} -> {}", file_path, original_folder);
+// ============================================================
+// SCHEDULED ORGANIZATION
+// ============================================================
 
-    let source = validate_path(&file_path)?;
-    let _ = validate_path(&original_folder)?;
+const SCHEDULED_ORGANIZE_EVENT: &str = "scheduled-organize-finished";
 
-    if !source.exists() {
-        return Err(CommandError::FileNotFound(file_path));
-    }
+/// Schedule plus when it will next run, for the settings screen
+#[derive(Debug, Serialize)]
+struct ScheduleStatus {
+    settings: scheduler::ScheduleSettings,
+    next_run_at: Option<i64>, // Unix timestamp ms
+}
 
-    if !source.is_file() {
-        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+/// Get the automatic organization schedule
+///
+/// Called from frontend with: invoke('get_schedule_settings')
+#[tauri::command]
+async fn get_schedule_settings() -> Result<ScheduleStatus, DbError> {
+    let settings = db_worker()?.call(scheduler::load).await?;
+    Ok(ScheduleStatus {
+        next_run_at: scheduler::next_run(&settings, current_timestamp_ms()),
+        settings,
+    })
+}
+
+/// Save the automatic organization schedule
+///
+/// Enabling the schedule or changing its timing restarts it from now.
+/// Called from frontend with: invoke('set_schedule_settings', { settings: { enabled: true, schedule: { kind: 'daily', hour: 18, minute: 0 }, folders: [...], available_folders: [...], min_confidence: 0.9 } })
+#[tauri::command]
+async fn set_schedule_settings(settings: scheduler::ScheduleSettings) -> Result<ScheduleStatus, DbError> {
+    println!("[COMMAND] set_schedule_settings: enabled={} {:?}", settings.enabled, settings.schedule);
+
+    let now = current_timestamp_ms();
+    let settings = db_worker()?
+        .call(move |db| {
+            let previous = scheduler::load(db)?;
+            let mut settings = settings;
+            settings.last_run_at = if previous.enabled && previous.schedule == settings.schedule {
+                previous.last_run_at
+            } else {
+                Some(now)
+            };
+            scheduler::save(db, &settings)?;
+            Ok(settings)
+        })
+        .await?;
+    Ok(ScheduleStatus {
+        next_run_at: scheduler::next_run(&settings, now),
+        settings,
+    })
+}
+
+/// Run the organize pipeline on every scheduled folder and report what happened
+///
+/// Runs on the scheduler thread; the window may be hidden in the tray, so the summary
+/// goes out as a system notification as well as an event for the frontend.
+fn run_scheduled_organize(app: &tauri::AppHandle, settings: scheduler::ScheduleSettings) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let (moved, skipped, failed) =
+        organize_in_background(app, &settings.folders, &settings.available_folders, settings.min_confidence);
+    let summary = scheduler::summary_message(moved, skipped, failed);
+    println!("[SCHEDULER] {}", summary);
+    let _ = app.emit(SCHEDULED_ORGANIZE_EVENT, &summary);
+    if moved > 0 || failed > 0 {
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title("Scheduled organize finished")
+            .body(&summary)
+            .show()
+        {
+            eprintln!("[SCHEDULER] Failed to show notification: {}", e);
+        }
     }
+}
 
-    let dest_dir = Path::new(&original_folder);
-    if !dest_dir.exists() {
-        fs::create_dir_all(&dest_dir)?;
+/// Organize each folder in turn on the current (non-async) thread
+///
+/// Returns the number of files moved, left for review and failed across all folders.
+fn organize_in_background(
+    app: &tauri::AppHandle,
+    folders: &[String],
+    available_folders: &[String],
+    min_confidence: f32,
+) -> (usize, usize, usize) {
+    let (mut moved, mut skipped, mut failed) = (0, 0, 0);
+    for folder in folders {
+        let options = OrganizeOptions {
+            available_folders: available_folders.to_vec(),
+            correction_history: Vec::new(),
+            min_confidence: Some(min_confidence),
+        };
+        match tauri::async_runtime::block_on(run_organize(folder.clone(), options, Some(app))) {
+            Ok(result) => {
+                moved += result.moved;
+                skipped += result.skipped.len();
+                failed += result.failed.len();
+            }
+            Err(e) => {
+                eprintln!("[ORGANIZE] Failed to organize {}: {}", folder, e);
+                failed += 1;
+            }
+        }
     }
+    (moved, skipped, failed)
+}
 
-    let filename = source.file_name()
-        .ok_or_else(|| CommandError::InvalidPath("Invalid file path".to_string()))?;
+// ============================================================
+// COMMAND-LINE MODE
+// ============================================================
 
-    let dest_path = dest_dir.join(filename);
+/// Run a command-line invocation (see cli.rs) and return its exit code
+///
+/// Returns None when `args` (without the program name) aren't a CLI command, in which
+/// case the app should start normally.
+pub fn run_cli(args: Vec<String>) -> Option<i32> {
+    let command = match cli::parse_args(&args)? {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            return Some(2);
+        }
+    };
+    let cli::CliCommand::Organize(args) = command else {
+        println!("{}", cli::USAGE);
+        return Some(0);
+    };
 
-    if dest_path.exists() {
-        return Err(CommandError::DuplicateExists(dest_path.display().to_string()));
-    }
+    #[cfg(debug_assertions)]
+    dotenv::dotenv().ok();
 
-    fs::rename(&source, &dest_path)?;
+    let Some(location) = cli::data_location() else {
+        eprintln!("Couldn't find the app data folder");
+        return Some(1);
+    };
+    if let Err(e) = open_database(location) {
+        eprintln!("Failed to open the database: {}", e);
+        return Some(1);
+    }
+    load_api_key(std::env::var("OPENAI_API_KEY").ok().filter(|k| !k.is_empty()));
+    if let Some(db) = DATABASE.get() {
+        load_settings(db);
+    }
+    budget::set_exceeded_listener(|status| {
+        eprintln!(
+            "Monthly AI budget reached (${:.2} of ${:.2}); classifying the rest without paid AI",
+            status.spent_usd, status.settings.monthly_limit_usd
+        )
+    });
+    if let Some(provider) = args.provider {
+        classifier::set_provider(provider);
+    }
 
-    println!("[COMMAND] Undo successful, file restored to: {}", dest_path.display());
-    Ok(format!("Restored to {}", dest_path.display()))
+    let result = tauri::async_runtime::block_on(run_cli_organize(args));
+    if let Ok(location) = data_dir::current() {
+        data_dir::release_lock(location);
+    }
+    match result {
+        Ok(code) => Some(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
 }
 
-/// Send a file to the system recycle bin (recoverable delete)
-///
-/// Called from frontend with: invoke('trash_file', { filePath: '...' })
-#[tauri::command]
-fn trash_file(file_path: String) -> Result<String, CommandError> {
-    println!("[COMMAND] trash_file: {}", file_path);
+async fn run_cli_organize(args: cli::OrganizeArgs) -> Result<i32, String> {
+    // Without --into, use the destinations the app last saved for background runs
+    let mut destinations = args.destinations;
+    if destinations.is_empty() {
+        destinations = db_worker()
+            .map_err(|e| e.to_string())?
+            .call(scheduler::load)
+            .await
+            .map(|settings| settings.available_folders)
+            .unwrap_or_default();
+    }
+    if destinations.is_empty() {
+        eprintln!("[CLI] No destination folders given; only rules and watch profile folders will be used (pass --into <folder>)");
+    }
 
-    let path = validate_path(&file_path)?;
-    if !path.exists() {
-        return Err(CommandError::FileNotFound(file_path));
+    if args.dry_run {
+        let plan = plan_organization(args.folder, destinations, Vec::new())
+            .await
+            .map_err(|e| e.to_string())?;
+        println!("{}", cli::plan_report(&plan));
+        return Ok(0);
     }
-    if !path.is_file() {
-        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+
+    let options = OrganizeOptions {
+        available_folders: destinations,
+        correction_history: Vec::new(),
+        min_confidence: args.min_confidence,
+    };
+    let result = run_organize(args.folder, options, None).await.map_err(|e| e.to_string())?;
+    for skip in &result.skipped {
+        println!("skipped {}: {}", skip.filename, skip.reason);
+    }
+    for failure in &result.failed {
+        println!("failed {}: {}", failure.path, failure.reason);
     }
+    if result.deferred > 0 {
+        println!("deferred {} file(s) until their destination drive is plugged in (moved next time the app runs)", result.deferred);
+    }
+    println!("{}", scheduler::summary_message(result.moved, result.skipped.len(), result.failed.len()));
+    Ok(if result.failed.is_empty() { 0 } else { 1 })
+}
 
-    trash::delete(&path)
-        .map_err(|e| CommandError::IoError(format!("Failed to move to recycle bin: {}", e)))?;
+// ============================================================
+// FOLDER SIZE ALERTS
+// ============================================================
 
-    println!("[COMMAND] File sent to recycle bin: {}", file_path);
-    Ok(format!("Sent to recycle bin"))
+const QUOTA_WARNING_EVENT: &str = "quota-warning";
+
+/// Get the folder size limits
+///
+/// Called from frontend with: invoke('get_quota_settings')
+#[tauri::command]
+async fn get_quota_settings() -> Result<quota::QuotaSettings, DbError> {
+    db_worker()?.call(quota::load).await
 }
 
-/// Rename a file in place (same directory, new name)
+/// Save the folder size limits
 ///
-/// Called from frontend with: invoke('rename_file', { filePath: '...', newName: '...' })
+/// Called from frontend with: invoke('set_quota_settings', { settings: { enabled: true, quotas: [{ folder: '...', max_bytes: 10737418240 }], interval_minutes: 60 } })
 #[tauri::command]
-fn rename_file(file_path: String, new_name: String) -> Result<String, CommandError> {
-    use std::fs;
-    use std::path::Path;
+async fn set_quota_settings(settings: quota::QuotaSettings) -> Result<quota::QuotaSettings, CommandError> {
+    quota::validate(&settings).map_err(CommandError::InvalidInput)?;
+    println!("[COMMAND] set_quota_settings: enabled={} {} folder(s)", settings.enabled, settings.quotas.len());
+
+    let saved = settings.clone();
+    db_worker()?.call(move |db| quota::save(db, &saved)).await?;
+    Ok(settings)
+}
+
+/// Measure every folder with a limit now
+///
+/// Called from frontend with: invoke('check_folder_quotas')
+#[tauri::command]
+async fn check_folder_quotas() -> Result<Vec<quota::QuotaUsage>, CommandError> {
+    let settings = db_worker()?.call(quota::load).await?;
+    tokio::task::spawn_blocking(move || quota::check(&settings))
+        .await
+        .map_err(|e| CommandError::IoError(format!("Quota check failed: {}", e)))
+}
+
+/// Tell the user a folder has grown past its limit: an event for the open window and a
+/// system notification in case it's hidden in the tray
+fn warn_quota_exceeded(app: &tauri::AppHandle, usage: quota::QuotaUsage) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let name = std::path::Path::new(&usage.folder)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| usage.folder.clone());
+    let message = format!(
+        "{} is {} (limit {}). Run an organize session to tidy it up.",
+        name,
+        quota::format_size(usage.size),
+        quota::format_size(usage.max_bytes)
+    );
+    let _ = app.emit(QUOTA_WARNING_EVENT, &usage);
+    if let Err(e) = app.notification().builder().title("Folder getting full").body(&message).show() {
+        eprintln!("[QUOTA] Failed to show notification: {}", e);
+    }
+}
+
+// ============================================================
+// GLOBAL SHORTCUT
+// ============================================================
 
-    println!("[COMMAND] rename_file: {} -> {}", file_path, new_name);
+const SHORTCUT_ORGANIZE_EVENT: &str = "shortcut-organize-finished";
 
-    let _ = validate_path(&file_path)?;
-    if new_name.contains("..") {
-        return Err(CommandError::PathTraversal);
-    }
-    if new_name.contains('/') || new_name.contains('\\') {
-        return Err(CommandError::InvalidPath("New name cannot contain path separators".to_string()));
-    }
-    let invalid_chars = ['<', '>', ':', '"', '|', '?', '*'];
-    if new_name.chars().any(|c| invalid_chars.contains(&c)) {
-        return Err(CommandError::InvalidPath("New name contains invalid characters".to_string()));
-    }
-    if new_name.trim().is_empty() {
-        return Err(CommandError::InvalidPath("New name cannot be empty".to_string()));
-    }
+// Set while a shortcut-triggered organize runs, so holding or mashing the keys starts only one
+static SHORTCUT_ORGANIZE_RUNNING: AtomicBool = AtomicBool::new(false);
 
-    let source = Path::new(&file_path);
-    if !source.exists() {
-        return Err(CommandError::FileNotFound(file_path));
+/// Show, restore and focus the main window
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.unminimize();
     }
-    if !source.is_file() {
-        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+}
+
+/// Replace whatever shortcut is registered with the configured one (if enabled)
+fn register_global_shortcut(app: &tauri::AppHandle, settings: &shortcut::ShortcutSettings) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+    if !settings.enabled {
+        return Ok(());
     }
+    let key = shortcut::parse(&settings.shortcut)?;
+    shortcuts
+        .register(key)
+        .map_err(|e| format!("Couldn't register {} (another app may be using it): {}", settings.shortcut, e))?;
+    println!("[SHORTCUT] Registered {} ({:?})", settings.shortcut, settings.action);
+    Ok(())
+}
 
-    let parent = source.parent()
-        .ok_or_else(|| CommandError::InvalidPath("Cannot determine parent directory".to_string()))?;
-    let new_path = parent.join(&new_name);
+/// The configured shortcut was pressed
+///
+/// Runs on the event loop, so organizing happens on its own thread.
+fn on_global_shortcut(app: &tauri::AppHandle) {
+    use tauri_plugin_notification::NotificationExt;
 
-    if new_path.exists() {
-        return Err(CommandError::DuplicateExists(new_name));
+    let settings = match get_db().and_then(|db| shortcut::load(&db)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("[SHORTCUT] Failed to load shortcut settings: {}", e);
+            return;
+        }
+    };
+    if settings.action == shortcut::ShortcutAction::ShowWindow || settings.folder.is_empty() {
+        show_main_window(app);
+        return;
+    }
+    if SHORTCUT_ORGANIZE_RUNNING.swap(true, Ordering::SeqCst) {
+        println!("[SHORTCUT] Organize already running");
+        return;
     }
 
-    fs::rename(&source, &new_path)?;
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("shortcut-organize".to_string())
+        .spawn(move || {
+            println!("[SHORTCUT] Organizing {}", settings.folder);
+            let (moved, skipped, failed) = organize_in_background(
+                &app,
+                std::slice::from_ref(&settings.folder),
+                &settings.available_folders,
+                settings.min_confidence,
+            );
+            SHORTCUT_ORGANIZE_RUNNING.store(false, Ordering::SeqCst);
+
+            let summary = scheduler::summary_message(moved, skipped, failed);
+            println!("[SHORTCUT] {}", summary);
+            let _ = app.emit(SHORTCUT_ORGANIZE_EVENT, &summary);
+            if let Err(e) = app.notification().builder().title("Organize finished").body(&summary).show() {
+                eprintln!("[SHORTCUT] Failed to show notification: {}", e);
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("[SHORTCUT] Failed to start organize thread: {}", e);
+        SHORTCUT_ORGANIZE_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
 
-    let new_path_str = new_path.to_string_lossy().to_string();
-    println!("[COMMAND] File renamed to: {}", new_path_str);
-    Ok(new_path_str)
+/// Get the global shortcut configuration
+///
+/// Called from frontend with: invoke('get_global_shortcut')
+#[tauri::command]
+async fn get_global_shortcut() -> Result<shortcut::ShortcutSettings, DbError> {
+    db_worker()?.call(shortcut::load).await
 }
 
-/// Rename a file and move it to a destination folder (atomic: rollback rename if move fails)
+/// Save the global shortcut configuration and register it with the OS
 ///
-/// Called from frontend with: invoke('rename_and_move_file', { filePath: '...', newName: '...', destFolder: '...' })
+/// The shortcut is registered before saving, so one that's invalid or already taken
+/// by another app is reported and the previous configuration is kept.
+/// Called from frontend with: invoke('set_global_shortcut', { settings: { enabled: true, shortcut: 'CmdOrCtrl+Alt+O', action: 'organize', folder: '...', available_folders: [...], min_confidence: 0.9 } })
 #[tauri::command]
-fn rename_and_move_file(file_path: String, new_name: String, dest_folder: String) -> Result<String, CommandError> {
-    use std::fs;
-    use std::path::Path;
+async fn set_global_shortcut(
+    app: tauri::AppHandle,
+    settings: shortcut::ShortcutSettings,
+) -> Result<shortcut::ShortcutSettings, String> {
+    println!("[COMMAND] set_global_shortcut: enabled={} {} {:?}", settings.enabled, settings.shortcut, settings.action);
+
+    let settings = shortcut::validate(settings)?;
+    let worker = db_worker().map_err(|e| e.to_string())?;
+    if let Err(e) = register_global_shortcut(&app, &settings) {
+        // Put back whatever was working before
+        if let Ok(previous) = worker.call(shortcut::load).await {
+            let _ = register_global_shortcut(&app, &previous);
+        }
+        return Err(e);
+    }
+    let saved = settings.clone();
+    worker.call(move |db| shortcut::save(db, &saved)).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
 
-    println!("[COMMAND] rename_and_move_file: {} -> {} into {}", file_path, new_name, dest_folder);
+// ============================================================
+// QUICK-SORT POPUP
+// ============================================================
 
-    // Validate file path
-    let _ = validate_path(&file_path)?;
+// Sent to the popup when the file it should show changes
+const QUICK_SORT_UPDATED_EVENT: &str = "quick-sort-updated";
 
-    // Validate new name (same checks as rename_file)
-    if new_name.contains("..") {
-        return Err(CommandError::PathTraversal);
-    }
-    if new_name.contains('/') || new_name.contains('\\') {
-        return Err(CommandError::InvalidPath("New name cannot contain path separators".to_string()));
-    }
-    let invalid_chars = ['<', '>', ':', '"', '|', '?', '*'];
-    if new_name.chars().any(|c| invalid_chars.contains(&c)) {
-        return Err(CommandError::InvalidPath("New name contains invalid characters".to_string()));
-    }
-    if new_name.trim().is_empty() {
-        return Err(CommandError::InvalidPath("New name cannot be empty".to_string()));
+// Sent to the main window to carry out a choice made in the popup
+const QUICK_SORT_DECISION_EVENT: &str = "quick-sort-decision";
+
+/// The file the popup shows and how many are waiting in total
+#[derive(Debug, Serialize)]
+struct QuickSortStatus {
+    item: Option<quick_sort::QuickSortItem>,
+    queued: usize,
+}
+
+fn quick_sort_status() -> QuickSortStatus {
+    QuickSortStatus {
+        item: quick_sort::current(),
+        queued: quick_sort::len(),
     }
+}
 
-    // Validate source exists
-    let source = Path::new(&file_path);
-    if !source.exists() {
-        return Err(CommandError::FileNotFound(file_path));
+/// Show the popup, creating it the first time, or hide it once nothing is waiting
+fn refresh_quick_sort_window(app: &tauri::AppHandle) -> Result<(), String> {
+    let window = app.get_webview_window(quick_sort::WINDOW_LABEL);
+    if quick_sort::len() == 0 {
+        if let Some(window) = window {
+            let _ = window.hide();
+        }
+        return Ok(());
     }
-    if !source.is_file() {
-        return Err(CommandError::InvalidPath(format!("Path is not a file: {}", file_path)));
+    if let Some(window) = window {
+        let _ = window.emit(QUICK_SORT_UPDATED_EVENT, quick_sort::len());
+        return window.show().map_err(|e| e.to_string());
     }
 
-    // Step 1: Rename in place
-    let parent = source.parent()
-        .ok_or_else(|| CommandError::InvalidPath("Cannot determine parent directory".to_string()))?;
-    let renamed_path = parent.join(&new_name);
+    tauri::WebviewWindowBuilder::new(
+        app,
+        quick_sort::WINDOW_LABEL,
+        tauri::WebviewUrl::App("quick-sort.html".into()),
+    )
+    .title("Quick Sort")
+    .inner_size(380.0, 240.0)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .focused(false) // don't take the keyboard from whatever the user is typing in
+    .build()
+    .map_err(|e| format!("Failed to open quick-sort window: {}", e))?;
+    Ok(())
+}
 
-    if renamed_path.exists() {
-        return Err(CommandError::DuplicateExists(new_name.clone()));
+/// Offer a classified file in the quick-sort popup, if the main window is hidden
+///
+/// Returns whether the file was queued; with the window open it's triaged there instead.
+/// Called from frontend with: invoke('offer_quick_sort', { item: { path, name, suggested_folder, confidence, reasoning, folders: [...] } })
+#[tauri::command]
+fn offer_quick_sort(app_handle: tauri::AppHandle, item: quick_sort::QuickSortItem) -> Result<bool, String> {
+    if !main_window_hidden(&app_handle) {
+        return Ok(false);
     }
+    println!("[COMMAND] offer_quick_sort: {}", item.name);
+    quick_sort::push(item);
+    refresh_quick_sort_window(&app_handle)?;
+    Ok(true)
+}
 
-    fs::rename(&source, &renamed_path)?;
-    println!("[COMMAND] Step 1 - Renamed to: {}", renamed_path.display());
+/// The file the popup should show
+///
+/// Called from frontend with: invoke('get_quick_sort_item')
+#[tauri::command]
+fn get_quick_sort_item() -> QuickSortStatus {
+    quick_sort_status()
+}
 
-    // Step 2: Move renamed file to dest_folder
-    let dest_dir = Path::new(&dest_folder);
-    if !dest_dir.exists() {
-        // Rollback rename before returning error
-        let _ = fs::rename(&renamed_path, &source);
-        return Err(CommandError::FileNotFound(dest_folder));
+/// Record the popup's choice for a file and pass it to the main window to carry out
+///
+/// Called from frontend with: invoke('decide_quick_sort', { path: '...', action: 'change', folder: '...' })
+#[tauri::command]
+fn decide_quick_sort(
+    app_handle: tauri::AppHandle,
+    path: String,
+    action: quick_sort::QuickSortAction,
+    folder: Option<String>,
+) -> Result<QuickSortStatus, String> {
+    println!("[COMMAND] decide_quick_sort: {} {:?}", path, action);
+    if action == quick_sort::QuickSortAction::Change && folder.is_none() {
+        return Err("Choose a folder first".to_string());
     }
 
-    let final_path = dest_dir.join(&new_name);
-    if final_path.exists() {
-        // Rollback rename before returning error
-        let _ = fs::rename(&renamed_path, &source);
-        return Err(CommandError::DuplicateExists(new_name));
+    quick_sort::remove(&path);
+    if action != quick_sort::QuickSortAction::Skip {
+        let decision = quick_sort::QuickSortDecision { path, action, folder };
+        if let Some(main_window) = app_handle.get_webview_window("main") {
+            let _ = main_window.emit(QUICK_SORT_DECISION_EVENT, &decision);
+        }
     }
+    refresh_quick_sort_window(&app_handle)?;
+    Ok(quick_sort_status())
+}
 
-    if let Err(e) = fs::rename(&renamed_path, &final_path) {
-        // Rollback rename
-        println!("[COMMAND] Move failed, rolling back rename: {}", e);
-        let _ = fs::rename(&renamed_path, &source);
-        return Err(CommandError::IoError(e.to_string()));
+/// Drop a file from the popup because it was handled (or removed) in the main window
+///
+/// Called from frontend with: invoke('dismiss_quick_sort', { path: '...' })
+#[tauri::command]
+fn dismiss_quick_sort(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    if quick_sort::remove(&path) {
+        refresh_quick_sort_window(&app_handle)?;
     }
+    Ok(())
+}
 
-    let final_path_str = final_path.to_string_lossy().to_string();
-    println!("[COMMAND] Step 2 - Moved to: {}", final_path_str);
-    Ok(final_path_str)
+// ============================================================
+// AUTO-MOVE NOTIFICATIONS
+// ============================================================
+
+// Files announced one by one per organize run; the rest are covered by the run's summary
+const MAX_MOVE_NOTIFICATIONS: usize = 3;
+
+// Emitted after "Undo Last Auto-Move" in the tray so the frontend refreshes its activity log
+const AUTO_MOVE_UNDONE_EVENT: &str = "auto-move-undone";
+
+/// The latest move announced in a notification, undone by the tray's "Undo Last Auto-Move"
+#[derive(Debug, Clone)]
+struct AnnouncedMove {
+    dest_path: String,
+    original_folder: String,
+    activity_id: Option<i64>,
 }
 
-/// Create a folder if it doesn't exist
+static LAST_ANNOUNCED_MOVE: Mutex<Option<AnnouncedMove>> = Mutex::new(None);
+
+/// Whether the main window is hidden in the tray (or gone), so nobody sees the file list
+fn main_window_hidden(app: &tauri::AppHandle) -> bool {
+    app.get_webview_window("main")
+        .map(|window| !window.is_visible().unwrap_or(false))
+        .unwrap_or(true)
+}
+
+/// Show a system notification for a file the pipeline moved, and remember it for undo
 ///
-/// Called from frontend with: invoke('create_folder', { path: '...' })
-#[tauri::command]
-fn create_folder(path: String) -> Result<String, CommandError> {
-    use std::fs;
+/// Desktop notifications can't carry actions, so the undo lives in the tray menu.
+fn notify_auto_move(app: &tauri::AppHandle, source_path: &str, dest_path: &str, activity_id: Option<i64>) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let announced = AnnouncedMove::new(source_path, dest_path, activity_id);
+    let title = announced.title();
+    *LAST_ANNOUNCED_MOVE.lock().unwrap_or_else(|e| e.into_inner()) = Some(announced);
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body("Undo it from the tray menu: Undo Last Auto-Move")
+        .show()
+    {
+        eprintln!("[NOTIFY] Failed to show notification: {}", e);
+    }
+}
 
-    println!("[COMMAND] create_folder: {}", path);
+impl AnnouncedMove {
+    fn new(source_path: &str, dest_path: &str, activity_id: Option<i64>) -> Self {
+        AnnouncedMove {
+            dest_path: dest_path.to_string(),
+            original_folder: std::path::Path::new(source_path)
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            activity_id,
+        }
+    }
+
+    /// The notification's title, with the last two folders of the destination, e.g.
+    /// "Moved PS1.pdf → Uni/ML"
+    fn title(&self) -> String {
+        let dest = std::path::Path::new(&self.dest_path);
+        let filename = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let folder: Vec<String> = dest
+            .parent()
+            .map(|p| p.iter().map(|c| c.to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        format!("Moved {} → {}", filename, folder[folder.len().saturating_sub(2)..].join("/"))
+    }
+}
 
-    let _ = validate_path(&path)?;
+/// Move the last announced file back where it came from (tray menu)
+async fn undo_last_announced_move(app: &tauri::AppHandle) {
+    use tauri_plugin_notification::NotificationExt;
 
-    fs::create_dir_all(&path)?;
-    Ok(format!("Folder created: {}", path))
+    let Some(announced) = LAST_ANNOUNCED_MOVE.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        println!("[TRAY] No auto-move to undo");
+        return;
+    };
+    let filename = std::path::Path::new(&announced.dest_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let message = match undo_move(announced.dest_path, announced.original_folder, announced.activity_id).await {
+        Ok(_) => {
+            let _ = app.emit(AUTO_MOVE_UNDONE_EVENT, &filename);
+            format!("Moved {} back", filename)
+        }
+        Err(e) => format!("Couldn't undo {}: {}", filename, e),
+    };
+    println!("[TRAY] {}", message);
+    if let Err(e) = app.notification().builder().title("Undo auto-move").body(&message).show() {
+        eprintln!("[NOTIFY] Failed to show notification: {}", e);
+    }
 }
 
 // ============================================================
-// DATABASE COMMANDS
+// PENDING QUEUE
 // ============================================================
 
-/// Get the database instance
-fn get_db() -> Result<Arc<Database>, DbError> {
-    DATABASE
-        .get()
-        .cloned()
-        .ok_or_else(|| DbError::InitFailed("Database not initialized".to_string()))
+/// Queue a file the watcher just saw arrive, so it's still waiting for review after a restart
+fn queue_detected_file(file: &watcher::FileInfo) {
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let pending = PendingFile {
+        path: file.path.clone(),
+        name: file.name.clone(),
+        size: file.size,
+        detected_at: current_timestamp_ms(),
+        classification: None,
+    };
+    worker.submit(move |db| {
+        if let Err(e) = db.add_pending_file(&pending) {
+            eprintln!("[QUEUE] Failed to queue {}: {}", pending.path, e);
+        }
+    });
 }
 
-/// Initialize the database (called during app setup)
-fn init_database(app_handle: &tauri::AppHandle) -> Result<(), DbError> {
-    let app_data_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| DbError::InitFailed(format!("Failed to get app data dir: {}", e)))?;
-
-    let db_path = app_data_dir.join("file_organiser.db");
-    println!("[DB] Initializing database at: {}", db_path.display());
-
-    let db = Database::new(db_path)?;
-    DATABASE
-        .set(Arc::new(db))
-        .map_err(|_| DbError::InitFailed("Database already initialized".to_string()))?;
-
-    Ok(())
+/// Take a file that left a watched folder off the queue
+fn unqueue_file(path: &std::path::Path) {
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let path = path.to_string_lossy().to_string();
+    worker.submit(move |db| {
+        let _ = db.remove_pending_file(&path);
+    });
 }
 
-/// Helper to get current timestamp in milliseconds
-fn current_timestamp_ms() -> i64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64
+/// Keep the queue in step with a file operation: a renamed file stays queued under its new
+/// name, a moved or deleted one has been dealt with, and a copy leaves the original waiting
+fn follow_pending_file(db: &Database, op: &Operation) {
+    let result = match (op.op_type.as_str(), op.after_path.as_deref()) {
+        ("copy" | "hardlink", _) => return,
+        ("rename", Some(after)) => {
+            let name = std::path::Path::new(after).file_name().map(|n| n.to_string_lossy().to_string());
+            db.rename_pending_file(&op.before_path, after, &name.unwrap_or_default())
+        }
+        _ => db.remove_pending_file(&op.before_path),
+    };
+    if let Err(e) = result {
+        eprintln!("[QUEUE] Failed to update queue for {}: {}", op.before_path, e);
+    }
 }
 
-/// Add a correction to the database
+/// Files detected but not reviewed yet, oldest first, with their classification if they
+/// have one. Files that have disappeared since are dropped from the queue.
+///
+/// Called from frontend with: invoke('list_pending_files')
 #[tauri::command]
-fn db_add_correction(
-    filename: String,
-    ai_suggested: String,
-    user_chose: String,
-    correction_type: String,
-) -> Result<i64, DbError> {
-    let db = get_db()?;
-    db.add_correction(Correction {
-        id: None,
-        filename,
-        ai_suggested,
-        user_chose,
-        correction_type,
-        created_at: current_timestamp_ms(),
-    })
+async fn list_pending_files() -> Result<Vec<PendingFile>, DbError> {
+    db_worker()?
+        .call(|db| {
+            let (present, gone): (Vec<PendingFile>, Vec<PendingFile>) = db
+                .get_pending_files()?
+                .into_iter()
+                .partition(|file| std::path::Path::new(&file.path).is_file());
+            for file in &gone {
+                println!("[QUEUE] {} no longer exists, dropping it", file.path);
+                db.remove_pending_file(&file.path)?;
+            }
+            Ok(present)
+        })
+        .await
 }
 
-/// Get all corrections
+/// Take a file off the pending queue (dismissed or handled in the UI)
+///
+/// Called from frontend with: invoke('remove_pending_file', { path: '...' })
 #[tauri::command]
-fn db_get_corrections() -> Result<Vec<Correction>, DbError> {
-    let db = get_db()?;
-    db.get_corrections()
+async fn remove_pending_file(path: String) -> Result<bool, DbError> {
+    db_worker()?.call(move |db| db.remove_pending_file(&path)).await
 }
 
-/// Clear all corrections
+/// Keep the classification of a queued file so it isn't classified again after a restart
+///
+/// Called from frontend with: invoke('set_pending_classification', { path: '...', classification: {...} })
 #[tauri::command]
-fn db_clear_corrections() -> Result<(), DbError> {
-    let db = get_db()?;
-    db.clear_corrections()
+async fn set_pending_classification(path: String, classification: serde_json::Value) -> Result<bool, DbError> {
+    db_worker()?.call(move |db| db.set_pending_classification(&path, &classification)).await
 }
 
-/// Add an activity entry
-#[tauri::command]
-fn db_add_activity(
-    filename: String,
-    from_folder: String,
-    to_folder: String,
-    original_filename: Option<String>,
-) -> Result<i64, DbError> {
-    let db = get_db()?;
-    db.add_activity(ActivityEntry {
+// ============================================================
+// UNDO / REDO JOURNAL
+// ============================================================
+
+/// Record a completed file operation in the journal.
+///
+/// Best-effort: file commands still succeed if the database is unavailable.
+fn record_operation(op_type: &str, before: &std::path::Path, after: Option<&std::path::Path>) {
+    journal_operation(op_type, before, after, None);
+}
+
+/// Record a copy in the journal with the copy's hash, so undoing it won't delete edits
+/// made to the copy since
+fn record_copy(source: &std::path::Path, copy: &std::path::Path) {
+    let hash = checksum::hash_file(copy)
+        .map_err(|e| eprintln!("[JOURNAL] Could not hash copy {}: {}", copy.display(), e))
+        .ok();
+    journal_operation("copy", source, Some(copy), hash);
+}
+
+fn journal_operation(op_type: &str, before: &std::path::Path, after: Option<&std::path::Path>, content_hash: Option<String>) {
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let now = current_timestamp_ms();
+    let op = Operation {
         id: None,
-        filename,
-        from_folder,
-        to_folder,
-        undone: false,
-        created_at: current_timestamp_ms(),
-        original_filename,
-    })
+        op_type: op_type.to_string(),
+        before_path: before.to_string_lossy().to_string(),
+        after_path: after.map(|p| p.to_string_lossy().to_string()),
+        status: "done".to_string(),
+        created_at: now,
+        updated_at: now,
+        content_hash,
+    };
+    let op_type = op_type.to_string();
+    worker.submit(move |db| {
+        audit_logged(db, &op_type, &op.before_path, op.after_path.as_deref());
+        follow_pending_file(db, &op);
+        if let Err(e) = db.add_operation(op) {
+            eprintln!("[JOURNAL] Failed to record {} operation: {}", op_type, e);
+        }
+    });
 }
 
-/// Get activity log
-#[tauri::command]
-fn db_get_activity_log() -> Result<Vec<ActivityEntry>, DbError> {
-    let db = get_db()?;
-    db.get_activity_log()
+/// Record where a moved file came from and why (best-effort, like the journal), and write
+/// it onto the file too if that's turned on
+fn record_provenance(before: &std::path::Path, after: &std::path::Path, reasoning: Option<String>) {
+    let Ok(worker) = db_worker() else {
+        return;
+    };
+    let before = before.to_string_lossy().to_string();
+    let after = after.to_string_lossy().to_string();
+    let reasoning = reasoning.filter(|r| !r.trim().is_empty());
+    worker.submit(move |db| {
+        match db.record_provenance(&before, &after, current_timestamp_ms(), reasoning.as_deref()) {
+            Ok(recorded) if provenance::write_to_files() => {
+                if let Err(e) = provenance::write_to_file(&recorded) {
+                    eprintln!("[PROVENANCE] Failed to write provenance onto {}: {}", after, e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[PROVENANCE] Failed to record provenance of {}: {}", after, e),
+        }
+    });
+}
+
+/// Append an entry to the audit log (best-effort, like the journal)
+fn audit_logged(db: &Database, action: &str, before: &str, after: Option<&str>) {
+    if let Err(e) = db.append_audit(action, before, after, current_timestamp_ms()) {
+        eprintln!("[AUDIT] Failed to record {} of {}: {}", action, before, e);
+    }
 }
 
-/// Mark activity as undone
+/// Undo a journal operation on disk and mark it undone
+async fn undo_recorded(worker: DbWorker, mut op: Operation) -> Result<Operation, CommandError> {
+    let id = op.id.ok_or_else(|| CommandError::InvalidInput("Operation has no id".to_string()))?;
+    let undone = op.clone();
+    run_blocking(move || journal::undo(&undone)).await??;
+
+    let (op_type, before, after) = (op.op_type.clone(), op.before_path.clone(), op.after_path.clone());
+    worker
+        .call(move |db| {
+            if let Some(after) = &after {
+                match op_type.as_str() {
+                    "copy" => audit_logged(db, "undo", after, None),
+                    "hardlink" => audit_logged(db, "undo", &before, None),
+                    _ => audit_logged(db, "undo", after, Some(before.as_str())),
+                }
+            }
+            db.set_operation_status(id, "undone")
+        })
+        .await?;
+    op.status = "undone".to_string();
+    Ok(op)
+}
+
+/// Undo the most recent file operation that is still in effect
+///
+/// Called from frontend with: invoke('undo_last')
 #[tauri::command]
-fn db_mark_activity_undone(timestamp: i64) -> Result<bool, DbError> {
-    let db = get_db()?;
-    db.mark_activity_undone(timestamp)
+async fn undo_last() -> Result<Operation, CommandError> {
+    let worker = db_worker()?;
+    let op = worker.call(|db| db.last_operation("done")).await?.ok_or(CommandError::NothingToUndo)?;
+    println!("[COMMAND] undo_last: operation {:?} ({})", op.id, op.op_type);
+    undo_recorded(worker, op).await
 }
 
-/// Clear activity log
+/// Redo the most recently undone file operation
+///
+/// Called from frontend with: invoke('redo_last')
 #[tauri::command]
-fn db_clear_activity_log() -> Result<(), DbError> {
-    let db = get_db()?;
-    db.clear_activity_log()
+async fn redo_last() -> Result<Operation, CommandError> {
+    let worker = db_worker()?;
+    let mut op = worker.call(|db| db.last_operation("undone")).await?.ok_or(CommandError::NothingToRedo)?;
+    let id = op.id.ok_or_else(|| CommandError::InvalidInput("Operation has no id".to_string()))?;
+    println!("[COMMAND] redo_last: operation {} ({})", id, op.op_type);
+
+    let redone = op.clone();
+    run_blocking(move || journal::redo(&redone)).await??;
+    let (before, after) = (op.before_path.clone(), op.after_path.clone());
+    worker
+        .call(move |db| {
+            audit_logged(db, "redo", &before, after.as_deref());
+            db.set_operation_status(id, "done")
+        })
+        .await?;
+    op.status = "done".to_string();
+    Ok(op)
 }
 
-/// Add a classification rule
+/// Undo a specific operation from the journal, even if newer ones exist
+///
+/// Called from frontend with: invoke('undo_operation', { id: 42 })
 #[tauri::command]
-fn db_add_rule(pattern: String, target_folder: String) -> Result<i64, DbError> {
-    let db = get_db()?;
-    db.add_rule(&pattern, &target_folder)
+async fn undo_operation(id: i64) -> Result<Operation, CommandError> {
+    println!("[COMMAND] undo_operation: {}", id);
+    let worker = db_worker()?;
+    let op = worker
+        .call(move |db| db.get_operation(id))
+        .await?
+        .ok_or_else(|| CommandError::NotUndoable(format!("Operation {} not found", id)))?;
+    if op.status != "done" {
+        return Err(CommandError::NotUndoable(format!("Operation {} is already {}", id, op.status)));
+    }
+    undo_recorded(worker, op).await
 }
 
-/// Get all classification rules
+/// Check that the audit log hasn't been edited, reordered or had entries removed
+///
+/// Also returns the newest entry's hash; noting it down somewhere else lets a later
+/// check also catch entries removed from the end.
+/// Called from frontend with: invoke('verify_audit_log')
 #[tauri::command]
-fn db_get_rules() -> Result<Vec<Rule>, DbError> {
-    let db = get_db()?;
-    db.get_rules()
+async fn verify_audit_log() -> Result<audit::AuditVerification, DbError> {
+    let entries = db_worker()?.call(|db| db.get_audit_log()).await?;
+    let verification = audit::verify(&entries);
+    println!("[COMMAND] verify_audit_log: {} entries, ok = {}", verification.entries, verification.ok);
+    Ok(verification)
 }
 
-/// Delete a classification rule
+/// Get the audit log (oldest first)
+/// Called from frontend with: invoke('get_audit_log')
 #[tauri::command]
-fn db_delete_rule(id: i64) -> Result<bool, DbError> {
-    let db = get_db()?;
-    db.delete_rule(id)
+async fn get_audit_log() -> Result<Vec<audit::AuditEntry>, DbError> {
+    db_worker()?.call(|db| db.get_audit_log()).await
 }
 
-/// Import data from localStorage (migration)
+/// Get the most recent journal operations (newest first)
 #[tauri::command]
-fn db_import_from_localstorage(
-    corrections: Vec<Correction>,
-    activity_log: Vec<ActivityEntry>,
-) -> Result<(usize, usize), DbError> {
-    let db = get_db()?;
-    let corrections_count = db.import_corrections(corrections)?;
-    let activity_count = db.import_activity_log(activity_log)?;
-    Ok((corrections_count, activity_count))
+async fn get_operations(limit: Option<usize>) -> Result<Vec<Operation>, DbError> {
+    let limit = limit.unwrap_or(50);
+    db_worker()?.call(move |db| db.get_operations(limit)).await
 }
 
 // ============================================================
@@ -1053,7 +7102,7 @@ mod tests {
         // Also create a file (should NOT appear in results)
         fs::write(tmp.join("readme.txt"), "hello").unwrap();
 
-        let result = super::scan_folders(tmp.to_string_lossy().to_string(), None).unwrap();
+        let result = super::list_folders(&tmp.to_string_lossy(), None, None, None).unwrap();
         assert_eq!(result, vec!["Alpha", "Middle", "Zebra"]);
 
         let _ = fs::remove_dir_all(&tmp);
@@ -1065,7 +7114,7 @@ mod tests {
         let _ = fs::remove_dir_all(&tmp);
         fs::create_dir_all(&tmp).unwrap();
 
-        let result = super::scan_folders(tmp.to_string_lossy().to_string(), None).unwrap();
+        let result = super::list_folders(&tmp.to_string_lossy(), None, None, None).unwrap();
         assert!(result.is_empty());
 
         let _ = fs::remove_dir_all(&tmp);
@@ -1073,7 +7122,7 @@ mod tests {
 
     #[test]
     fn test_scan_folders_nonexistent_path() {
-        let result = super::scan_folders("C:\\nonexistent_path_12345".to_string(), None);
+        let result = super::list_folders("C:\\nonexistent_path_12345", None, None, None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Path does not exist"));
     }
@@ -1083,7 +7132,7 @@ mod tests {
         let tmp = std::env::temp_dir().join("fileorg_test_scan_file.txt");
         fs::write(&tmp, "not a dir").unwrap();
 
-        let result = super::scan_folders(tmp.to_string_lossy().to_string(), None);
+        let result = super::list_folders(&tmp.to_string_lossy(), None, None, None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Path is not a directory"));
 
@@ -1099,7 +7148,7 @@ mod tests {
         fs::create_dir_all(tmp.join("Year1").join("Physics")).unwrap();
         fs::create_dir(tmp.join("Year2")).unwrap();
 
-        let result = super::scan_folders(tmp.to_string_lossy().to_string(), Some(true)).unwrap();
+        let result = super::list_folders(&tmp.to_string_lossy(), Some(true), None, None).unwrap();
         assert!(result.contains(&"Year1".to_string()));
         assert!(result.contains(&"Year2".to_string()));
         let sep = std::path::MAIN_SEPARATOR_STR;
@@ -1128,7 +7177,7 @@ mod tests {
         // Also create a subdirectory (should NOT appear in results)
         fs::create_dir(tmp.join("subfolder")).unwrap();
 
-        let result = super::scan_files(tmp.to_string_lossy().to_string()).unwrap();
+        let result = super::scan_files(tmp.to_string_lossy().to_string(), None).unwrap();
         assert_eq!(result.len(), 3);
         // Most recent first
         assert_eq!(result[0].name, "newest.txt");
@@ -1148,7 +7197,7 @@ mod tests {
         fs::write(tmp.join(".hidden"), "h").unwrap();
         fs::write(tmp.join("visible.txt"), "v").unwrap();
 
-        let result = super::scan_files(tmp.to_string_lossy().to_string()).unwrap();
+        let result = super::scan_files(tmp.to_string_lossy().to_string(), None).unwrap();
         let names: Vec<&str> = result.iter().map(|f| f.name.as_str()).collect();
         assert_eq!(names, vec!["visible.txt"]);
 
@@ -1161,7 +7210,7 @@ mod tests {
         let _ = fs::remove_dir_all(&tmp);
         fs::create_dir_all(&tmp).unwrap();
 
-        let result = super::scan_files(tmp.to_string_lossy().to_string()).unwrap();
+        let result = super::scan_files(tmp.to_string_lossy().to_string(), None).unwrap();
         assert!(result.is_empty());
 
         let _ = fs::remove_dir_all(&tmp);
@@ -1169,7 +7218,7 @@ mod tests {
 
     #[test]
     fn test_scan_files_nonexistent_path() {
-        let result = super::scan_files("C:\\nonexistent_path_12345".to_string());
+        let result = super::scan_files("C:\\nonexistent_path_12345".to_string(), None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Path does not exist"));
     }
@@ -1181,7 +7230,7 @@ mod tests {
         fs::create_dir_all(&tmp).unwrap();
         fs::write(tmp.join("test.txt"), "hello world").unwrap();
 
-        let result = super::scan_files(tmp.to_string_lossy().to_string()).unwrap();
+        let result = super::scan_files(tmp.to_string_lossy().to_string(), None).unwrap();
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].name, "test.txt");
         assert!(result[0].size > 0);
@@ -1205,15 +7254,69 @@ mod tests {
     }
 
     #[test]
-    fn test_create_folder_already_exists() {
-        let tmp = std::env::temp_dir().join("fileorg_test_create_exists");
+    fn test_create_folder_already_exists() {
+        let tmp = std::env::temp_dir().join("fileorg_test_create_exists");
+        fs::create_dir_all(&tmp).unwrap();
+
+        // Should succeed even if folder already exists
+        let result = super::create_folder(tmp.to_string_lossy().to_string());
+        assert!(result.is_ok());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_commands_refuse_paths_outside_allowed_roots() {
+        let temp = std::env::temp_dir();
+        let inside = temp.join("fileorg_test_allowed_roots");
+        let _ = fs::remove_dir_all(&inside);
+        let outside = temp.parent().unwrap_or(&temp).join("fileorg_test_outside_roots");
+
+        // Tests run with only the temp folder allowed (see `roots::initial_roots`)
+        let created = super::create_folder(outside.to_string_lossy().to_string());
+        let listed = super::list_dir_children(temp.parent().unwrap_or(&temp).to_string_lossy().to_string());
+        let exported = super::export_plan_report("plan-test-roots".to_string(), outside.join("plan.csv").to_string_lossy().to_string());
+        let created_inside = super::create_folder(inside.to_string_lossy().to_string());
+
+        assert!(matches!(created, Err(super::CommandError::OutsideAllowedRoots(_))));
+        assert!(!outside.exists());
+        assert!(matches!(listed, Err(super::CommandError::OutsideAllowedRoots(_))));
+        assert!(matches!(exported, Err(super::CommandError::OutsideAllowedRoots(_))));
+        assert!(created_inside.is_ok());
+
+        let _ = fs::remove_dir_all(&inside);
+    }
+
+    #[test]
+    fn test_open_file_refuses_programs_and_unknown_apps() {
+        let tmp = std::env::temp_dir().join("fileorg_test_open_refused");
+        let _ = fs::remove_dir_all(&tmp);
         fs::create_dir_all(&tmp).unwrap();
+        fs::write(tmp.join("setup.exe"), "MZ").unwrap();
+        fs::write(tmp.join("install.sh"), "#!/bin/sh").unwrap();
+        fs::write(tmp.join("notes.pdf"), "%PDF").unwrap();
 
-        // Should succeed even if folder already exists
-        let result = super::create_folder(tmp.to_string_lossy().to_string());
-        assert!(result.is_ok());
+        for program in ["setup.exe", "install.sh"] {
+            let result = super::open_file(tmp.join(program).to_string_lossy().to_string());
+            assert!(matches!(result, Err(super::CommandError::PermissionDenied(_))), "{} was opened", program);
+        }
+        let pdf = tmp.join("notes.pdf").to_string_lossy().to_string();
+        for app in ["/bin/sh", "cmd.exe", "", "rm"] {
+            let result = super::open_with(pdf.clone(), app.to_string());
+            assert!(matches!(result, Err(super::CommandError::InvalidInput(_))), "{:?} was accepted", app);
+        }
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 
+    #[test]
+    fn test_open_file_location_missing_folder() {
+        let tmp = std::env::temp_dir().join("fileorg_test_open_location_missing");
         let _ = fs::remove_dir_all(&tmp);
+
+        // Neither the file nor its folder exists, so there's nothing to show
+        let result = super::open_file_location(tmp.join("notes.pdf").to_string_lossy().to_string());
+        assert!(matches!(result, Err(super::CommandError::FileNotFound(_))));
     }
 
     // --- move_file tests ---
@@ -1233,6 +7336,7 @@ mod tests {
         let result = super::move_file(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         ).unwrap();
 
         assert!(result.contains("Moved to"));
@@ -1252,6 +7356,7 @@ mod tests {
         let result = super::move_file(
             fake_file.to_string_lossy().to_string(),
             tmp.to_string_lossy().to_string(),
+            None,
         );
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), super::CommandError::FileNotFound(_)));
@@ -1274,6 +7379,7 @@ mod tests {
         let result = super::move_file(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         );
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), super::CommandError::DuplicateExists(_)));
@@ -1296,6 +7402,7 @@ mod tests {
         let result = super::move_file(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         ).unwrap();
 
         assert!(result.contains("Moved to"));
@@ -1305,6 +7412,53 @@ mod tests {
         let _ = fs::remove_dir_all(std::env::temp_dir().join("fileorg_test_move_mkdir_dest"));
     }
 
+    #[test]
+    fn test_move_file_long_destination_path() {
+        let root = std::env::temp_dir().join("fileorg_test_move_long_path");
+        let _ = fs::remove_dir_all(&root);
+        let src_dir = root.join("src");
+        fs::create_dir_all(&src_dir).unwrap();
+        let src_file = src_dir.join("lecture_recording_week_11.txt");
+        fs::write(&src_file, "data").unwrap();
+
+        // Deeply nested course folders, well past Windows' 260-character MAX_PATH
+        let mut dest_dir = root.clone();
+        for i in 0..8 {
+            dest_dir = dest_dir.join(format!("Year 2 Semester 1 Course Materials {}", i));
+        }
+        assert!(dest_dir.join("lecture_recording_week_11.txt").to_string_lossy().len() > 260);
+
+        let result = super::move_file(
+            src_file.to_string_lossy().to_string(),
+            dest_dir.to_string_lossy().to_string(),
+            None,
+        );
+        assert!(result.is_ok(), "long path move failed: {:?}", result);
+        assert!(dest_dir.join("lecture_recording_week_11.txt").exists());
+        assert!(!src_file.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_extended_length_path() {
+        assert_eq!(
+            super::extended_length_path(r"C:\Users\me\Year2/ML\.\notes.pdf").as_deref(),
+            Some(r"\\?\C:\Users\me\Year2\ML\notes.pdf")
+        );
+        assert_eq!(
+            super::extended_length_path(r"\\server\share\Course\notes.pdf").as_deref(),
+            Some(r"\\?\UNC\server\share\Course\notes.pdf")
+        );
+        // Already prefixed paths are left alone; relative paths can't be prefixed
+        assert_eq!(
+            super::extended_length_path(r"\\?\C:\Course").as_deref(),
+            Some(r"\\?\C:\Course")
+        );
+        assert_eq!(super::extended_length_path(r"Course\notes.pdf"), None);
+        assert_eq!(super::extended_length_path("/home/me/Course"), None);
+    }
+
     #[test]
     fn test_move_file_source_is_directory() {
         let tmp = std::env::temp_dir().join("fileorg_test_move_dir");
@@ -1314,6 +7468,7 @@ mod tests {
         let result = super::move_file(
             tmp.to_string_lossy().to_string(),
             "C:\\some_dest".to_string(),
+            None,
         );
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), super::CommandError::InvalidPath(_)));
@@ -1338,6 +7493,7 @@ mod tests {
         let result = super::move_file_with_rename(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         ).unwrap();
 
         assert!(result.contains("Moved to"));
@@ -1365,6 +7521,7 @@ mod tests {
         let result = super::move_file_with_rename(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         ).unwrap();
 
         assert!(result.contains("Moved to"));
@@ -1394,6 +7551,7 @@ mod tests {
         let result = super::move_file_with_rename(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         ).unwrap();
 
         assert!(dest_dir.join("doc_3.txt").exists());
@@ -1419,6 +7577,7 @@ mod tests {
         let result = super::move_file_with_rename(
             src_file.to_string_lossy().to_string(),
             dest_dir.to_string_lossy().to_string(),
+            None,
         ).unwrap();
 
         assert!(dest_dir.join("README_1").exists());
@@ -1428,8 +7587,387 @@ mod tests {
         let _ = fs::remove_dir_all(&dest_dir);
     }
 
+    // --- plan_files tests ---
+
+    #[tokio::test]
+    async fn test_plan_files_yields_in_order() {
+        use futures::StreamExt;
+        let rule = |pattern: &str, target: &str| super::Rule {
+            id: None,
+            pattern: pattern.to_string(),
+            target_folder: target.to_string(),
+            created_at: 0,
+            profile_id: None,
+        };
+        let context = super::PlanContext {
+            rules: vec![rule("*.pdf", "PDFs"), rule("*.docx", "Notes")],
+            corrections: Vec::new(),
+            mode: super::ClassificationModeSettings { mode: Default::default(), bucket_folder: None },
+            available_folders: Vec::new(),
+            prompt_folders: Vec::new(),
+            correction_history: Vec::new(),
+            api_key: None,
+            min_confidence: None,
+            folder: "/Downloads".to_string(),
+            index: std::collections::HashMap::new(),
+        };
+        // More files than the concurrency limit, so several batches are in flight
+        let names: Vec<String> = (0..super::classifier::MAX_CONCURRENT_REQUESTS * 2)
+            .map(|i| if i % 3 == 0 { format!("notes{}.docx", i) } else { format!("lecture{}.pdf", i) })
+            .collect();
+        let files = names
+            .iter()
+            .map(|name| super::FileEntry { name: name.clone(), path: format!("/Downloads/{}", name), size: 1, modified: 0 })
+            .collect();
+
+        let mut plans = super::plan_files(files, &context);
+        let mut planned = Vec::new();
+        while let Some((file, result)) = plans.next().await {
+            let planned_move = result.unwrap_or_else(|_| panic!("{} had no rule", file.name));
+            assert_eq!(planned_move.source_path, file.path);
+            planned.push((file.name, planned_move.dest_folder));
+        }
+        let expected: Vec<(String, String)> = names
+            .iter()
+            .map(|name| (name.clone(), if name.ends_with(".docx") { "Notes" } else { "PDFs" }.to_string()))
+            .collect();
+        assert_eq!(planned, expected);
+    }
+
     // --- undo_move tests ---
 
+    #[test]
+    fn test_apply_plan_moves_and_renames() {
+        let tmp = std::env::temp_dir().join("fileorg_test_apply_plan");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("ML")).unwrap();
+        fs::write(tmp.join("a.pdf"), "a").unwrap();
+        fs::write(tmp.join("b.pdf"), "b").unwrap();
+        fs::write(tmp.join("ML").join("a.pdf"), "already here").unwrap();
+
+        let planned = |name: &str, rename: Option<&str>| super::planner::PlannedMove {
+            source_path: tmp.join(name).to_string_lossy().to_string(),
+            filename: name.to_string(),
+            dest_folder: tmp.join("ML").to_string_lossy().to_string(),
+            confidence: 1.0,
+            reasoning: String::new(),
+            suggested_filename: rename.map(|r| r.to_string()),
+            origin: "rule".to_string(),
+        };
+        let plan = super::planner::OrganizationPlan {
+            id: "plan-test-apply".to_string(),
+            folder: tmp.to_string_lossy().to_string(),
+            created_at: 0,
+            moves: vec![
+                planned("a.pdf", None),
+                planned("b.pdf", Some("ML_b.pdf")),
+                planned("gone.pdf", None),
+            ],
+            skipped: Vec::new(),
+            trash: Vec::new(),
+        };
+        super::planner::store(plan);
+
+        let plan = super::planner::take("plan-test-apply").unwrap();
+        let result = super::apply_plan_moves(plan, None);
+        assert_eq!(result.moved, 2);
+        assert_eq!(result.failed.len(), 1);
+        assert!(result.failed[0].path.contains("gone.pdf"));
+        assert_eq!(fs::read_to_string(tmp.join("ML").join("a_1.pdf")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(tmp.join("ML").join("ML_b.pdf")).unwrap(), "b");
+
+        // A plan can't be applied twice
+        assert!(super::planner::take("plan-test-apply").is_none());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_merge_folders() {
+        let tmp = std::env::temp_dir().join("fileorg_test_merge_folders");
+        let _ = fs::remove_dir_all(&tmp);
+        let source = tmp.join("ML");
+        let target = tmp.join("Machine Learning");
+        fs::create_dir_all(source.join("Lectures")).unwrap();
+        fs::create_dir_all(target.join("Lectures")).unwrap();
+        fs::write(source.join("hw1.pdf"), "hw1").unwrap();
+        fs::write(source.join("Lectures").join("week1.pdf"), "new week1").unwrap();
+        fs::write(source.join("Lectures").join("week2.pdf"), "week2").unwrap();
+        fs::write(target.join("Lectures").join("week1.pdf"), "old week1").unwrap();
+
+        let path = |p: &std::path::Path| p.to_string_lossy().to_string();
+        assert!(super::list_merge_tree(&path(&source), &path(&source.join("Lectures"))).is_err());
+
+        let tree = super::list_merge_tree(&path(&source), &path(&target)).unwrap();
+        let result = super::merge_listed_tree(tree, super::ConflictPolicy::Rename, None);
+        assert_eq!((result.moved, result.renamed, result.skipped), (2, 1, 0));
+        assert!(result.failed.is_empty());
+        assert!(result.source_removed);
+        assert_eq!(fs::read_to_string(target.join("hw1.pdf")).unwrap(), "hw1");
+        assert_eq!(fs::read_to_string(target.join("Lectures").join("week1.pdf")).unwrap(), "old week1");
+        assert_eq!(fs::read_to_string(target.join("Lectures").join("week1_1.pdf")).unwrap(), "new week1");
+        assert!(!source.exists());
+
+        // With "skip", clashing files stay behind and so does the source folder
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("hw1.pdf"), "hw1 again").unwrap();
+        let tree = super::list_merge_tree(&path(&source), &path(&target)).unwrap();
+        let result = super::merge_listed_tree(tree, super::ConflictPolicy::Skip, None);
+        assert_eq!((result.moved, result.skipped), (0, 1));
+        assert!(!result.source_removed);
+        assert_eq!(fs::read_to_string(source.join("hw1.pdf")).unwrap(), "hw1 again");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_resume_session_moves_pending_items() {
+        let tmp = std::env::temp_dir().join("fileorg_test_resume_session");
+        let _ = fs::remove_dir_all(&tmp);
+        let (downloads, dest) = (tmp.join("Downloads"), tmp.join("ML"));
+        fs::create_dir_all(&downloads).unwrap();
+        for name in ["a.pdf", "b.pdf", "c.pdf"] {
+            fs::write(downloads.join(name), name).unwrap();
+        }
+        let db = std::sync::Arc::new(super::Database::new(tmp.join("test.db")).unwrap());
+        let worker = super::DbWorker::spawn(db.clone()).unwrap();
+        let planned = |name: &str| crate::planner::PlannedMove {
+            source_path: downloads.join(name).to_string_lossy().to_string(),
+            filename: name.to_string(),
+            dest_folder: dest.to_string_lossy().to_string(),
+            confidence: 0.9,
+            reasoning: "ML lecture".to_string(),
+            suggested_filename: None,
+            origin: "ai".to_string(),
+        };
+        let moves = vec![planned("a.pdf"), planned("b.pdf"), planned("c.pdf")];
+
+        // The run stops after moving a.pdf, and after moving b.pdf but before marking it done
+        let session = db.create_session("Apply plan (3 files)").unwrap();
+        let ids = super::record_session_items(Some(&worker), Some(session), &moves, super::ConflictPolicy::Rename);
+        super::execute_session_item(&moves[0], super::ConflictPolicy::Rename, Some(&worker), Some(session), ids[0]).unwrap();
+        super::execute_planned_move(&moves[1], super::ConflictPolicy::Rename, Some(&worker), Some(session)).unwrap();
+        assert_eq!(db.get_sessions().unwrap()[0].pending_items, 2);
+
+        let result = super::resume_pending_items(&worker, session).unwrap();
+        assert_eq!((result.moved, result.already_done, result.skipped), (1, 1, 0));
+        assert!(result.failed.is_empty());
+        for name in ["a.pdf", "b.pdf", "c.pdf"] {
+            assert_eq!(fs::read_to_string(dest.join(name)).unwrap(), name);
+        }
+        assert_eq!(db.get_sessions().unwrap()[0].pending_items, 0);
+        assert_eq!(db.get_session_activity(session).unwrap().len(), 3);
+
+        // Nothing left to do the second time
+        let result = super::resume_pending_items(&worker, session).unwrap();
+        assert_eq!((result.moved, result.already_done), (0, 0));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_undo_session_restores_more_than_the_activity_log_shows() {
+        let tmp = std::env::temp_dir().join("fileorg_test_undo_big_session");
+        let _ = fs::remove_dir_all(&tmp);
+        let (downloads, dest) = (tmp.join("Downloads"), tmp.join("ML"));
+        fs::create_dir_all(&downloads).unwrap();
+        let db = std::sync::Arc::new(super::Database::new(tmp.join("test.db")).unwrap());
+        let worker = super::DbWorker::spawn(db.clone()).unwrap();
+
+        let move_all = |names: &[String], session: i64| {
+            for name in names {
+                let planned = crate::planner::PlannedMove {
+                    source_path: downloads.join(name).to_string_lossy().to_string(),
+                    filename: name.clone(),
+                    dest_folder: dest.to_string_lossy().to_string(),
+                    confidence: 0.9,
+                    reasoning: String::new(),
+                    suggested_filename: None,
+                    origin: "rule".to_string(),
+                };
+                super::execute_planned_move(&planned, super::ConflictPolicy::Rename, Some(&worker), Some(session)).unwrap();
+            }
+        };
+        let names: Vec<String> = (0..130).map(|i| format!("lecture{}.pdf", i)).collect();
+        for name in &names {
+            fs::write(downloads.join(name), name).unwrap();
+        }
+
+        // Only the newest 100 moves are in the activity log, but the session keeps all of them
+        let session = db.create_session("Apply plan (130 files)").unwrap();
+        move_all(&names, session);
+        assert_eq!(db.get_activity_log().unwrap().len(), 100);
+        let result = super::undo_session_moves(&worker, session).unwrap();
+        assert_eq!(result.restored, 130);
+        assert!(names.iter().all(|name| downloads.join(name).is_file()));
+
+        // A session whose moves were deleted from the history isn't undone by halves
+        let other = db.create_session("Apply plan (130 files)").unwrap();
+        move_all(&names, other);
+        db.clear_activity_log().unwrap();
+        assert!(matches!(super::undo_session_moves(&worker, other), Err(super::CommandError::NotUndoable(_))));
+        assert!(names.iter().all(|name| dest.join(name).is_file()));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_move_file_with_policy_conflicts() {
+        let src_dir = std::env::temp_dir().join("fileorg_test_policy_src");
+        let dest_dir = std::env::temp_dir().join("fileorg_test_policy_dest");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::create_dir_all(&dest_dir).unwrap();
+        fs::write(dest_dir.join("notes.pdf"), "existing").unwrap();
+
+        let src_file = src_dir.join("notes.pdf");
+        let run = |policy| {
+            super::move_file_with_policy(
+                src_file.to_string_lossy().to_string(),
+                dest_dir.to_string_lossy().to_string(),
+                Some(policy),
+            )
+        };
+
+        fs::write(&src_file, "new").unwrap();
+        let skipped = run(super::ConflictPolicy::Skip).unwrap();
+        assert_eq!(skipped.outcome, "skipped");
+        assert!(skipped.dest_path.is_none());
+        assert!(src_file.exists());
+
+        let error = run(super::ConflictPolicy::Error);
+        assert!(matches!(error.unwrap_err(), super::CommandError::DuplicateExists(_)));
+        assert!(src_file.exists());
+
+        let renamed = run(super::ConflictPolicy::Rename).unwrap();
+        assert_eq!(renamed.outcome, "renamed");
+        assert!(renamed.dest_path.unwrap().ends_with("notes_1.pdf"));
+        assert_eq!(fs::read_to_string(dest_dir.join("notes.pdf")).unwrap(), "existing");
+
+        fs::write(&src_file, "newest").unwrap();
+        let replaced = run(super::ConflictPolicy::Replace).unwrap();
+        assert_eq!(replaced.outcome, "replaced");
+        assert_eq!(fs::read_to_string(dest_dir.join("notes.pdf")).unwrap(), "newest");
+        assert!(!src_file.exists());
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+    }
+
+    #[test]
+    fn test_conflict_policy_parse_roundtrip() {
+        for policy in [
+            super::ConflictPolicy::Skip,
+            super::ConflictPolicy::Rename,
+            super::ConflictPolicy::Replace,
+            super::ConflictPolicy::Error,
+        ] {
+            assert_eq!(super::ConflictPolicy::parse(policy.as_str()), Some(policy));
+            assert_eq!(serde_json::to_string(&policy).unwrap(), format!("\"{}\"", policy.as_str()));
+        }
+        assert_eq!(super::ConflictPolicy::parse("overwrite"), None);
+    }
+
+    #[test]
+    fn test_copy_file_keeps_original() {
+        let src_dir = std::env::temp_dir().join("fileorg_test_copy_src");
+        let dest_dir = std::env::temp_dir().join("fileorg_test_copy_dest");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+
+        let src_file = src_dir.join("notes.pdf");
+        fs::write(&src_file, "content").unwrap();
+
+        let result = super::copy_file(
+            src_file.to_string_lossy().to_string(),
+            dest_dir.to_string_lossy().to_string(),
+        ).unwrap();
+
+        assert!(result.contains("Copied to"));
+        assert!(src_file.exists());
+        assert_eq!(fs::read_to_string(dest_dir.join("notes.pdf")).unwrap(), "content");
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+    }
+
+    #[test]
+    fn test_copy_file_dest_already_exists() {
+        let src_dir = std::env::temp_dir().join("fileorg_test_copy_dup_src");
+        let dest_dir = std::env::temp_dir().join("fileorg_test_copy_dup_dest");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::create_dir_all(&dest_dir).unwrap();
+
+        let src_file = src_dir.join("dup.txt");
+        fs::write(&src_file, "source").unwrap();
+        fs::write(dest_dir.join("dup.txt"), "already here").unwrap();
+
+        let result = super::copy_file(
+            src_file.to_string_lossy().to_string(),
+            dest_dir.to_string_lossy().to_string(),
+        );
+        assert!(matches!(result.unwrap_err(), super::CommandError::DuplicateExists(_)));
+        assert_eq!(fs::read_to_string(dest_dir.join("dup.txt")).unwrap(), "already here");
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+    }
+
+    #[test]
+    fn test_copy_file_with_rename_conflict() {
+        let src_dir = std::env::temp_dir().join("fileorg_test_copy_rename_src");
+        let dest_dir = std::env::temp_dir().join("fileorg_test_copy_rename_dest");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::create_dir_all(&dest_dir).unwrap();
+
+        fs::write(dest_dir.join("lecture.pdf"), "existing").unwrap();
+        let src_file = src_dir.join("lecture.pdf");
+        fs::write(&src_file, "new version").unwrap();
+
+        super::copy_file_with_rename(
+            src_file.to_string_lossy().to_string(),
+            dest_dir.to_string_lossy().to_string(),
+        ).unwrap();
+
+        assert!(src_file.exists());
+        assert_eq!(fs::read_to_string(dest_dir.join("lecture.pdf")).unwrap(), "existing");
+        assert_eq!(fs::read_to_string(dest_dir.join("lecture_1.pdf")).unwrap(), "new version");
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+    }
+
+    #[test]
+    fn test_copy_replace_file_overwrites() {
+        let src_dir = std::env::temp_dir().join("fileorg_test_copy_replace_src");
+        let dest_dir = std::env::temp_dir().join("fileorg_test_copy_replace_dest");
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::create_dir_all(&dest_dir).unwrap();
+
+        fs::write(dest_dir.join("lecture.pdf"), "old").unwrap();
+        let src_file = src_dir.join("lecture.pdf");
+        fs::write(&src_file, "new").unwrap();
+
+        super::copy_replace_file(
+            src_file.to_string_lossy().to_string(),
+            dest_dir.to_string_lossy().to_string(),
+        ).unwrap();
+
+        assert!(src_file.exists());
+        assert_eq!(fs::read_to_string(dest_dir.join("lecture.pdf")).unwrap(), "new");
+
+        let _ = fs::remove_dir_all(&src_dir);
+        let _ = fs::remove_dir_all(&dest_dir);
+    }
+
     #[test]
     fn test_undo_move_success() {
         let moved_dir = std::env::temp_dir().join("fileorg_test_undo_moved");
@@ -1441,9 +7979,10 @@ mod tests {
         let moved_file = moved_dir.join("undoable.txt");
         fs::write(&moved_file, "undo me").unwrap();
 
-        let result = super::undo_move(
-            moved_file.to_string_lossy().to_string(),
-            original_dir.to_string_lossy().to_string(),
+        let result = super::restore_moved_file(
+            &moved_file.to_string_lossy(),
+            &original_dir.to_string_lossy(),
+            None,
         ).unwrap();
 
         assert!(result.contains("Restored to"));
@@ -1460,9 +7999,10 @@ mod tests {
         let _ = fs::remove_dir_all(&tmp);
         fs::create_dir_all(&tmp).unwrap();
         let fake_file = tmp.join("gone.txt");
-        let result = super::undo_move(
-            fake_file.to_string_lossy().to_string(),
-            tmp.to_string_lossy().to_string(),
+        let result = super::restore_moved_file(
+            &fake_file.to_string_lossy(),
+            &tmp.to_string_lossy(),
+            None,
         );
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), super::CommandError::FileNotFound(_)));
@@ -1482,9 +8022,10 @@ mod tests {
         fs::write(&moved_file, "moved version").unwrap();
         fs::write(original_dir.join("conflict.txt"), "original still here").unwrap();
 
-        let result = super::undo_move(
-            moved_file.to_string_lossy().to_string(),
-            original_dir.to_string_lossy().to_string(),
+        let result = super::restore_moved_file(
+            &moved_file.to_string_lossy(),
+            &original_dir.to_string_lossy(),
+            None,
         );
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), super::CommandError::DuplicateExists(_)));
@@ -1492,6 +8033,181 @@ mod tests {
         let _ = fs::remove_dir_all(&moved_dir);
         let _ = fs::remove_dir_all(&original_dir);
     }
+
+    #[test]
+    fn test_undo_move_file_changed_since_move() {
+        let tmp = std::env::temp_dir().join("fileorg_test_undo_changed");
+        let _ = fs::remove_dir_all(&tmp);
+        let (downloads, dest) = (tmp.join("Downloads"), tmp.join("ML"));
+        fs::create_dir_all(&downloads).unwrap();
+        fs::write(downloads.join("notes.txt"), "lecture notes").unwrap();
+        let db = std::sync::Arc::new(super::Database::new(tmp.join("test.db")).unwrap());
+        let worker = super::DbWorker::spawn(db.clone()).unwrap();
+        let planned = crate::planner::PlannedMove {
+            source_path: downloads.join("notes.txt").to_string_lossy().to_string(),
+            filename: "notes.txt".to_string(),
+            dest_folder: dest.to_string_lossy().to_string(),
+            confidence: 0.9,
+            reasoning: String::new(),
+            suggested_filename: None,
+            origin: "rule".to_string(),
+        };
+        super::execute_planned_move(&planned, super::ConflictPolicy::Rename, Some(&worker), None).unwrap();
+        fs::write(dest.join("notes.txt"), "lecture notes, edited after the move").unwrap();
+
+        // Found by where the file is now when the frontend has no activity id
+        let moved = dest.join("notes.txt").to_string_lossy().to_string();
+        let recorded = db.find_activity_at(&moved).unwrap();
+        assert!(recorded.is_some());
+        let result = super::restore_moved_file(&moved, &downloads.to_string_lossy(), recorded.as_ref());
+        assert!(matches!(result, Err(super::CommandError::ChangedSinceMove(_))));
+        assert!(dest.join("notes.txt").exists());
+        assert!(!downloads.join("notes.txt").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    // --- plan_aging_cleanup tests ---
+
+    #[test]
+    fn test_cleanup_cutoff() {
+        let day = 24 * 60 * 60;
+        let now_ms = 100 * day as i64 * 1000;
+        assert_eq!(super::cleanup_cutoff(now_ms, 30), 70 * day);
+        assert_eq!(super::cleanup_cutoff(now_ms, 0), 100 * day, "0 days: everything modified before now");
+        assert_eq!(super::cleanup_cutoff(now_ms, 365), 0, "never before the epoch");
+    }
+
+    // --- auto-move notification tests ---
+
+    #[test]
+    fn test_announced_move_title_and_undo() {
+        let tmp = std::env::temp_dir().join("fileorg_test_announced_move");
+        let _ = fs::remove_dir_all(&tmp);
+        let (downloads, dest) = (tmp.join("Downloads"), tmp.join("Uni").join("ML"));
+        fs::create_dir_all(&downloads).unwrap();
+        fs::write(downloads.join("PS1.pdf"), "problem set").unwrap();
+        let db = std::sync::Arc::new(super::Database::new(tmp.join("test.db")).unwrap());
+        let worker = super::DbWorker::spawn(db.clone()).unwrap();
+        let planned = crate::planner::PlannedMove {
+            source_path: downloads.join("PS1.pdf").to_string_lossy().to_string(),
+            filename: "PS1.pdf".to_string(),
+            dest_folder: dest.to_string_lossy().to_string(),
+            confidence: 0.9,
+            reasoning: String::new(),
+            suggested_filename: None,
+            origin: "rule".to_string(),
+        };
+
+        // The logged move's id travels with the announcement, so the tray undo checks the file
+        let moved = super::execute_planned_move(&planned, super::ConflictPolicy::Rename, Some(&worker), None).unwrap();
+        let dest_path = moved.dest_path.clone().unwrap();
+        assert!(moved.activity_id.is_some());
+        let announced = super::AnnouncedMove::new(&planned.source_path, &dest_path, moved.activity_id);
+        assert_eq!(announced.title(), "Moved PS1.pdf → Uni/ML");
+        assert_eq!(announced.original_folder, downloads.to_string_lossy());
+
+        let recorded = db.get_activity(announced.activity_id.unwrap()).unwrap();
+        assert_eq!(recorded.as_ref().map(|e| e.filename.as_str()), Some("PS1.pdf"));
+        super::restore_moved_file(&announced.dest_path, &announced.original_folder, recorded.as_ref()).unwrap();
+        assert_eq!(fs::read_to_string(downloads.join("PS1.pdf")).unwrap(), "problem set");
+
+        // Without a database the move still happens, just without an id
+        let moved = super::execute_planned_move(&planned, super::ConflictPolicy::Rename, None, None).unwrap();
+        assert!(moved.dest_path.is_some());
+        assert!(moved.activity_id.is_none());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    // --- classify_auto image passes tests ---
+
+    fn classified(folder: &str) -> Result<super::classifier::Classification, String> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "is_relevant": true,
+            "suggested_folder": folder,
+            "confidence": 0.9,
+            "reasoning": "",
+        }))
+        .unwrap())
+    }
+
+    #[test]
+    fn test_classify_image_insufficient_ocr_text_escalates_to_vision() {
+        use super::ClassifyPass;
+        let passes = std::cell::RefCell::new(Vec::new());
+        let result = futures::executor::block_on(super::classify_image_passes(
+            super::buckets::ClassificationMode::Ai,
+            "IMG_2041.png",
+            |pass, _: &str| passes.borrow_mut().push(pass),
+            async { Err(super::OCR_INSUFFICIENT_TEXT.to_string()) },
+            || async { classified("Linear Algebra") },
+        ));
+
+        let (classification, pass) = result.unwrap();
+        assert_eq!(pass, ClassifyPass::Vision);
+        assert_eq!(classification.suggested_folder, "Linear Algebra");
+        assert_eq!(*passes.borrow(), vec![ClassifyPass::Ocr, ClassifyPass::Vision]);
+    }
+
+    #[test]
+    fn test_classify_image_ocr_mode_never_uploads() {
+        use super::ClassifyPass;
+        let vision_called = std::cell::Cell::new(false);
+        let result = futures::executor::block_on(super::classify_image_passes(
+            super::buckets::ClassificationMode::Ocr,
+            "IMG_2041.png",
+            |pass, _: &str| assert_ne!(pass, ClassifyPass::Vision),
+            async { Err(super::OCR_INSUFFICIENT_TEXT.to_string()) },
+            || {
+                vision_called.set(true);
+                async { classified("Linear Algebra") }
+            },
+        ));
+        assert!(result.is_none(), "the filename result stands");
+        assert!(!vision_called.get(), "the image is never sent for vision in OCR mode");
+
+        // Text OCR can read is used in OCR mode as in AI mode
+        let result = futures::executor::block_on(super::classify_image_passes(
+            super::buckets::ClassificationMode::Ocr,
+            "IMG_2042.png",
+            |_, _: &str| {},
+            async { classified("Statistics") },
+            || {
+                vision_called.set(true);
+                async { classified("Linear Algebra") }
+            },
+        ));
+        assert_eq!(result.map(|(c, pass)| (c.suggested_folder, pass)), Some(("Statistics".to_string(), ClassifyPass::Ocr)));
+        assert!(!vision_called.get());
+    }
+
+    // --- sibling context tests ---
+
+    #[test]
+    fn test_listed_names() {
+        let tmp = std::env::temp_dir().join("fileorg_test_listed_names");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Week 3")).unwrap();
+        let base = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for (name, age) in [("old.pdf", 3), ("newest.pdf", 1), ("middle.pdf", 2), (".DS_Store", 0)] {
+            let path = tmp.join(name);
+            fs::write(&path, "x").unwrap();
+            fs::File::options().write(true).open(&path).unwrap().set_modified(base - std::time::Duration::from_secs(age * 60)).unwrap();
+        }
+
+        // Newest files directly in the folder; hidden files and subfolders are left out
+        assert_eq!(super::listed_names(tmp.to_str().unwrap()), vec!["newest.pdf", "middle.pdf", "old.pdf"]);
+        assert!(super::listed_names(tmp.join("missing").to_str().unwrap()).is_empty());
+
+        // At most SIBLING_CANDIDATES names
+        for i in 0..super::classifier::SIBLING_CANDIDATES {
+            fs::write(tmp.join(format!("extra{}.pdf", i)), "x").unwrap();
+        }
+        assert_eq!(super::listed_names(tmp.to_str().unwrap()).len(), super::classifier::SIBLING_CANDIDATES);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -1508,6 +8224,15 @@ pub fn run() {
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             None,
         ))
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, _shortcut, event| {
+                    if event.state() == tauri_plugin_global_shortcut::ShortcutState::Pressed {
+                        on_global_shortcut(app);
+                    }
+                })
+                .build(),
+        )
         .setup(|app| {
             // Initialize database
             if let Err(e) = init_database(app.handle()) {
@@ -1515,25 +8240,85 @@ pub fn run() {
                 // Don't fail app startup - frontend can fall back to localStorage
             }
 
-            // Initialize API key storage and load from database
-            let _ = API_KEY.set(Mutex::new(String::new()));
+            // Large copies/moves report progress to the frontend
+            fileop::init(app.handle().clone());
+
+            // Embedding model, loaded (or downloaded, if it isn't bundled) the first time something is embedded
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                embeddings::set_model_dir(resource_dir.join(embeddings::MODEL_DIR_NAME));
+            }
+            if let Ok(location) = data_dir::current() {
+                embeddings::set_download_dir(location.data_dir.join(embeddings::MODEL_DIR_NAME));
+            }
+
+            // App trash for safe deletes, purged of expired files in the background
+            if let Ok(location) = data_dir::current() {
+                safe_delete::init(location.data_dir.join(safe_delete::TRASH_DIR_NAME));
+            }
+            let handle = app.handle().clone();
+            budget::set_exceeded_listener(move |status| warn_budget_exceeded(&handle, status));
+
             if let Some(db) = DATABASE.get() {
-                if let Ok(Some(key)) = db.get_setting("api_key") {
-                    if let Some(mutex) = API_KEY.get() {
-                        let mut stored = mutex.lock().unwrap_or_else(|e| e.into_inner());
-                        *stored = key;
+                safe_delete::spawn_purge_thread(db);
+
+                // Scheduled organization keeps running while the window is hidden in the tray
+                let handle = app.handle().clone();
+                scheduler::spawn_thread(db, move |settings| run_scheduled_organize(&handle, settings));
+
+                // Folder size limits are checked in the background too
+                let handle = app.handle().clone();
+                quota::spawn_thread(db, move |usage| warn_quota_exceeded(&handle, usage));
+
+                // Drives with deferred moves (from this run or an earlier one) are watched
+                // until they're plugged back in, then the moves go ahead
+                let handle = app.handle().clone();
+                volumes::spawn_thread(move |reconnected| run_deferred_moves(&handle, reconnected));
+                match db.get_deferred_items() {
+                    Ok(items) => {
+                        // Drives already plugged in are picked up on the thread's first check
+                        let drives: Vec<String> = items
+                            .iter()
+                            .filter_map(|item| item.planned["dest_folder"].as_str())
+                            .filter_map(|folder| volumes::volume_root(std::path::Path::new(folder)))
+                            .map(|root| root.to_string_lossy().to_string())
+                            .collect();
+                        volumes::wait_for(&drives);
+                    }
+                    Err(e) => eprintln!("[ORGANIZE] Failed to load deferred moves: {}", e),
+                }
+
+                // Organize-now / show-window key combination, if the user set one up
+                match shortcut::load(db) {
+                    Ok(settings) => {
+                        if let Err(e) = register_global_shortcut(app.handle(), &settings) {
+                            eprintln!("[SHORTCUT] {}", e);
+                        }
                     }
-                    println!("[APP] API key loaded from database");
+                    Err(e) => eprintln!("[SHORTCUT] Failed to load shortcut settings: {}", e),
+                }
+
+                // Filenames stay out of AI requests if the user turned on privacy mode,
+                // and nothing leaves the machine in local-only mode
+                load_settings(db);
+
+                // Moves, renames, trashing and previews stay inside the configured folders
+                match roots::load_or_configured(db) {
+                    Ok(allowed) => roots::set(&allowed),
+                    Err(e) => eprintln!("[APP] Failed to load allowed folders: {}", e),
                 }
             }
 
+            // Initialize API key storage and load from database
+            load_api_key(None);
+
             // Build tray menu
             let show_item = MenuItemBuilder::with_id("show", "Show Window").build(app)?;
+            let undo_item = MenuItemBuilder::with_id("undo_last_move", "Undo Last Auto-Move").build(app)?;
             let stop_item = MenuItemBuilder::with_id("stop", "Stop Watching").build(app)?;
             let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
 
             let menu = MenuBuilder::new(app)
-                .items(&[&show_item, &stop_item, &quit_item])
+                .items(&[&show_item, &undo_item, &stop_item, &quit_item])
                 .build()?;
 
             // Build system tray icon
@@ -1547,12 +8332,10 @@ pub fn run() {
                 .show_menu_on_left_click(false)
                 .on_menu_event(move |app: &tauri::AppHandle, event: tauri::menu::MenuEvent| {
                     match event.id().as_ref() {
-                        "show" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                                let _ = window.unminimize();
-                            }
+                        "show" => show_main_window(app),
+                        "undo_last_move" => {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move { undo_last_announced_move(&app).await });
                         }
                         "stop" => {
                             if WATCHER_STARTED.load(Ordering::SeqCst) {
@@ -1562,19 +8345,15 @@ pub fn run() {
                             }
                         }
                         "quit" => {
-                            app.exit(0);
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move { shutdown_and_exit(&app).await });
                         }
                         _ => {}
                     }
                 })
                 .on_tray_icon_event(|tray: &tauri::tray::TrayIcon, event: tauri::tray::TrayIconEvent| {
                     if let tauri::tray::TrayIconEvent::Click { button: tauri::tray::MouseButton::Left, .. } = event {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.unminimize();
-                        }
+                        show_main_window(tray.app_handle());
                     }
                 })
                 .build(app)?;
@@ -1582,6 +8361,21 @@ pub fn run() {
             Ok(())
         })
         .on_window_event(|window, event| {
+            // Dropped files come from the OS, so file operations on them are allowed wherever they are
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                roots::allow_dropped(paths);
+            }
+
+            // Closing the quick-sort popup leaves the rest of its files in the main window's list
+            if window.label() == quick_sort::WINDOW_LABEL {
+                if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                    api.prevent_close();
+                    let _ = window.hide();
+                    quick_sort::clear();
+                }
+                return;
+            }
+
             // Minimize to tray instead of closing
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                 // Prevent the window from closing
@@ -1609,17 +8403,148 @@ pub fn run() {
             move_file,
             move_file_with_rename,
             replace_file,
+            copy_file,
+            copy_file_with_rename,
+            copy_replace_file,
+            move_file_with_policy,
+            get_conflict_policy,
+            set_conflict_policy,
+            plan_organization,
+            apply_plan,
+            export_plan_report,
             undo_move,
             classify_file,
             classify_image_with_ocr,
             classify_image_file,
             classify_with_content,
+            list_archive,
+            classify_archive,
+            classify_auto,
+            extract_archive,
+            archive_folder,
+            get_quarantine_settings,
+            set_quarantine_settings,
+            quarantine_file,
+            list_quarantine,
+            get_delete_settings,
+            set_delete_settings,
+            list_deleted,
+            sanitize_filename,
+            list_trash,
+            restore_trash_items,
+            purge_deleted,
+            cancel_file_operation,
             set_api_key,
             get_api_key,
             scan_folders,
             scan_files,
+            scan_files_stream,
+            list_dir_children,
+            plan_aging_cleanup,
+            get_quota_settings,
+            set_quota_settings,
+            check_folder_quotas,
+            get_correction_insights,
+            get_accuracy_report,
+            get_time_saved,
             get_file_preview,
+            get_file_metadata,
+            get_folder_stats,
+            analyze_disk_usage,
+            find_duplicates,
+            link_duplicates,
+            merge_folders,
+            open_file,
+            open_with,
+            get_open_with_apps,
+            list_pending_files,
+            remove_pending_file,
+            set_pending_classification,
+            resume_session,
+            get_all_settings,
+            set_settings,
+            export_profile,
+            import_profile,
+            get_model_routes,
+            set_model_routes,
+            get_folder_aliases,
+            set_folder_aliases,
+            get_api_budget,
+            set_api_budget,
+            get_model_reliability,
+            get_data_location,
+            relocate_database,
+            use_existing_database,
+            get_file_index_summary,
+            search_files,
+            organize_folder,
+            get_schedule_settings,
+            get_classification_mode,
+            set_classification_mode,
+            classify_by_type,
+            suggest_new_folders,
+            find_similar_files,
+            get_embedding_status,
+            download_embedding_model,
+            set_embedding_fallback,
+            list_watch_profiles,
+            parse_timetable,
+            import_timetable,
+            bootstrap_setup,
+            get_lms_settings,
+            set_lms_settings,
+            sync_lms_courses,
+            detect_paper,
+            send_paper_to_zotero,
+            save_paper_bibtex,
+            suggest_filename,
+            get_webhook_settings,
+            set_webhook_settings,
+            test_webhook,
+            notify_file_auto_moved,
+            export_activity_markdown,
+            export_activity_csv,
+            export_corrections_jsonl,
+            run_diagnostics,
+            get_privacy_mode,
+            set_privacy_mode,
+            get_sibling_context,
+            set_sibling_context,
+            get_suggest_subfolders,
+            set_suggest_subfolders,
+            get_read_cloud_placeholders,
+            set_read_cloud_placeholders,
+            get_provenance_in_files,
+            set_provenance_in_files,
+            get_file_provenance,
+            get_ai_endpoint,
+            set_ai_endpoint,
+            get_fallback_chain,
+            set_fallback_chain,
+            get_prompt_token_budget,
+            set_prompt_token_budget,
+            get_ai_timeouts,
+            set_ai_timeouts,
+            get_max_concurrent_requests,
+            set_max_concurrent_requests,
+            get_chat_export_subfolder,
+            set_chat_export_subfolder,
+            get_local_only,
+            set_local_only,
+            get_allowed_roots,
+            set_allowed_roots,
+            get_global_shortcut,
+            set_global_shortcut,
+            offer_quick_sort,
+            get_quick_sort_item,
+            decide_quick_sort,
+            dismiss_quick_sort,
+            save_watch_profile,
+            delete_watch_profile,
+            set_schedule_settings,
+            detect_file_type,
             create_folder,
+            open_file_location,
             trash_file,
             rename_file,
             rename_and_move_file,
@@ -1631,11 +8556,88 @@ pub fn run() {
             db_get_activity_log,
             db_mark_activity_undone,
             db_clear_activity_log,
+            db_start_session,
+            db_end_session,
+            db_get_sessions,
+            undo_session,
             db_add_rule,
             db_get_rules,
             db_delete_rule,
-            db_import_from_localstorage
+            db_set_folder_pref,
+            db_get_folder_prefs,
+            db_delete_folder_pref,
+            remap_paths,
+            db_import_from_localstorage,
+            db_maintenance,
+            get_orphaned_entries,
+            purge_orphaned_entries,
+            // Undo/redo journal
+            undo_last,
+            redo_last,
+            undo_operation,
+            get_operations,
+            verify_audit_log,
+            get_audit_log
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Quitting from the OS (e.g. Cmd+Q) also waits for running work
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if !SHUTDOWN_COMPLETE.load(Ordering::SeqCst) {
+                    api.prevent_exit();
+                    if !shutdown::is_shutting_down() {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move { shutdown_and_exit(&app).await });
+                    }
+                }
+            }
+        });
+}
+
+// Longest Quit waits for moves and copies under way before cancelling what's left
+const SHUTDOWN_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+static SHUTDOWN_COMPLETE: AtomicBool = AtomicBool::new(false);
+
+/// Quit once running work is done
+async fn shutdown_and_exit(app: &tauri::AppHandle) {
+    finish_running_work().await;
+    SHUTDOWN_COMPLETE.store(true, Ordering::SeqCst);
+    app.exit(0);
+}
+
+/// Restart once running work is done (after the database has moved)
+async fn shutdown_and_restart(app: &tauri::AppHandle) {
+    finish_running_work().await;
+    SHUTDOWN_COMPLETE.store(true, Ordering::SeqCst);
+    app.restart();
+}
+
+/// Stop the watcher and new operations, let moves already under way finish (cancelling
+/// long copies after a while), then flush queued database writes
+async fn finish_running_work() {
+    shutdown::begin();
+    if WATCHER_STARTED.swap(false, Ordering::SeqCst) {
+        watcher::signal_stop();
+    }
+
+    let left = shutdown::drain(SHUTDOWN_DRAIN_TIMEOUT).await;
+    if left > 0 {
+        println!("[SHUTDOWN] {} operation(s) still running, cancelling", left);
+        fileop::cancel_all();
+        shutdown::drain(std::time::Duration::from_secs(5)).await;
+    }
+
+    // Jobs run in order, so once this one is done every write queued before it is too
+    if let Ok(worker) = db_worker() {
+        match worker.call(|db| db.checkpoint()).await {
+            Ok(()) => println!("[SHUTDOWN] Database flushed"),
+            Err(e) => eprintln!("[SHUTDOWN] Failed to flush database: {}", e),
+        }
+    }
+    // Another computer sharing a synced database may open it now
+    if let Ok(location) = data_dir::current() {
+        data_dir::release_lock(location);
+    }
 }
@@ -0,0 +1,337 @@
+//! Windows shortcuts (.lnk) and internet shortcuts (.url)
+//!
+//! A shortcut's own name ("lecture3.pdf - Shortcut.lnk", "Course page.url") says little,
+//! and its bytes are a binary Shell Link or a two-line INI file. The target path or URL is
+//! read out of it here, so the shortcut can be classified by what it points at: the target
+//! file's content, or the linked page's title. A shortcut whose target is gone is only
+//! worth deleting.
+
+use crate::classifier;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+// Shortcuts are tiny; anything bigger isn't one
+const MAX_LINK_BYTES: u64 = 1024 * 1024;
+
+// Fetching a linked page for its title
+const TITLE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_PAGE_BYTES: usize = 64 * 1024;
+const USER_AGENT: &str = "file-organiser (shortcut titles)";
+
+// Shell Link header size and the LinkFlags bits used below (MS-SHLLINK 2.1.1)
+const HEADER_SIZE: usize = 0x4C;
+const HAS_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const IS_UNICODE: u32 = 0x80;
+
+// LinkInfoFlags (MS-SHLLINK 2.3)
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+const COMMON_NETWORK_RELATIVE_LINK: u32 = 0x02;
+
+/// What a shortcut points at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkTarget {
+    pub target: String,        // full path of the target file or folder, or the URL
+    pub is_url: bool,          // an internet shortcut
+    pub exists: bool,          // the target file is still there (always true for URLs)
+    pub title: Option<String>, // the linked page's title, once fetched
+}
+
+impl LinkTarget {
+    /// What the AI classifies the shortcut on, alongside the target's own content if any
+    pub fn classification_text(&self) -> String {
+        if self.is_url {
+            match &self.title {
+                Some(title) => format!("Internet shortcut to {}\nPage title: {}", self.target, title),
+                None => format!("Internet shortcut to {}", self.target),
+            }
+        } else {
+            format!("Shortcut to {}", self.target)
+        }
+    }
+
+    /// The target's filename, which is usually more telling than the shortcut's
+    pub fn target_name(&self) -> Option<String> {
+        if self.is_url {
+            return None;
+        }
+        self.target.rsplit(['\\', '/']).next().filter(|n| !n.is_empty()).map(str::to_string)
+    }
+}
+
+/// Whether `path` is named like a shortcut
+pub fn is_link(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| e == "lnk" || e == "url")
+}
+
+/// Read what the shortcut at `path` points at (None if it isn't a readable shortcut)
+pub fn resolve(path: &Path) -> Option<LinkTarget> {
+    if !is_link(path) || std::fs::metadata(path).ok()?.len() > MAX_LINK_BYTES || crate::metadata::skip_content(path) {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    let is_url = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("url"));
+
+    if is_url {
+        let url = parse_url_file(&String::from_utf8_lossy(&bytes))?;
+        return Some(LinkTarget { target: url, is_url: true, exists: true, title: None });
+    }
+    let target = parse_lnk(&bytes)?;
+    let exists = Path::new(&target).exists();
+    Some(LinkTarget { target, is_url: false, exists, title: None })
+}
+
+/// The URL in an internet shortcut ("[InternetShortcut]\r\nURL=https://...")
+fn parse_url_file(text: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line.eq_ignore_ascii_case("[InternetShortcut]");
+        } else if in_section {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("URL") && !value.trim().is_empty() {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as usize)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// A NUL-terminated ANSI string starting at `at`
+fn c_string(bytes: &[u8], at: usize) -> Option<String> {
+    let rest = bytes.get(at..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).to_string())
+}
+
+/// A NUL-terminated UTF-16 string starting at `at`
+fn c_wide_string(bytes: &[u8], at: usize) -> Option<String> {
+    let units: Vec<u16> = bytes
+        .get(at..)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// The target path in a Shell Link: from LinkInfo if it has one, else the working directory
+/// joined with the relative path
+fn parse_lnk(bytes: &[u8]) -> Option<String> {
+    if u32_at(bytes, 0)? as usize != HEADER_SIZE {
+        return None;
+    }
+    let flags = u32_at(bytes, 0x14)?;
+    let mut at = HEADER_SIZE;
+    if flags & HAS_TARGET_ID_LIST != 0 {
+        at += 2 + u16_at(bytes, at)?;
+    }
+
+    let mut from_link_info = None;
+    if flags & HAS_LINK_INFO != 0 {
+        let info = at;
+        let info_size = u32_at(bytes, info)? as usize;
+        let header_size = u32_at(bytes, info + 4)? as usize;
+        let info_flags = u32_at(bytes, info + 8)?;
+        let suffix = if header_size >= 0x24 {
+            match u32_at(bytes, info + 0x20)? as usize {
+                0 => c_string(bytes, info + u32_at(bytes, info + 0x18)? as usize)?,
+                offset => c_wide_string(bytes, info + offset)?,
+            }
+        } else {
+            c_string(bytes, info + u32_at(bytes, info + 0x18)? as usize)?
+        };
+
+        if info_flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+            let base = if header_size >= 0x24 && u32_at(bytes, info + 0x1C)? != 0 {
+                c_wide_string(bytes, info + u32_at(bytes, info + 0x1C)? as usize)?
+            } else {
+                c_string(bytes, info + u32_at(bytes, info + 0x10)? as usize)?
+            };
+            from_link_info = Some(join_suffix(&base, &suffix));
+        } else if info_flags & COMMON_NETWORK_RELATIVE_LINK != 0 {
+            let network = info + u32_at(bytes, info + 0x14)? as usize;
+            let share = c_string(bytes, network + u32_at(bytes, network + 8)? as usize)?;
+            from_link_info = Some(join_suffix(&share, &suffix));
+        }
+        at += info_size;
+    }
+    if let Some(target) = from_link_info.filter(|t| !t.is_empty()) {
+        return Some(target);
+    }
+
+    // StringData: counted strings in a fixed order, each present only if its flag is set
+    let unicode = flags & IS_UNICODE != 0;
+    let read_string = |at: &mut usize| -> Option<String> {
+        let count = u16_at(bytes, *at)?;
+        *at += 2;
+        let len = if unicode { count * 2 } else { count };
+        let raw = bytes.get(*at..*at + len)?;
+        *at += len;
+        Some(if unicode {
+            String::from_utf16_lossy(&raw.chunks_exact(2).map(|p| u16::from_le_bytes([p[0], p[1]])).collect::<Vec<_>>())
+        } else {
+            String::from_utf8_lossy(raw).to_string()
+        })
+    };
+    if flags & HAS_NAME != 0 {
+        read_string(&mut at)?;
+    }
+    let relative = if flags & HAS_RELATIVE_PATH != 0 { Some(read_string(&mut at)?) } else { None };
+    let working_dir = if flags & HAS_WORKING_DIR != 0 { Some(read_string(&mut at)?) } else { None };
+    let relative = relative?;
+    Some(match working_dir.filter(|d| !d.is_empty()) {
+        Some(dir) => join_suffix(&dir, relative.trim_start_matches(".\\")),
+        None => relative,
+    })
+}
+
+fn join_suffix(base: &str, suffix: &str) -> String {
+    if suffix.is_empty() || base.ends_with(['\\', '/']) {
+        format!("{}{}", base, suffix)
+    } else {
+        format!("{}\\{}", base, suffix)
+    }
+}
+
+/// The `<title>` of the page at `url`, fetched with a short timeout (None on any failure)
+///
+/// Only public http(s) pages are fetched. A shortcut can point at a router's admin page or a
+/// service on this computer, and the title goes to the classifier.
+pub async fn page_title(url: &str) -> Option<String> {
+    if !is_public_url(url).await {
+        return None;
+    }
+    let client = classifier::http_client(url, TITLE_TIMEOUT).ok()?;
+    let mut response = client.get(url).header(reqwest::header::USER_AGENT, USER_AGENT).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let mut page = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        page.extend_from_slice(&chunk);
+        if page.len() >= MAX_PAGE_BYTES {
+            break;
+        }
+    }
+    html_title(&String::from_utf8_lossy(&page))
+}
+
+// An http(s) URL whose host isn't this computer or the local network, and doesn't resolve to it
+async fn is_public_url(url: &str) -> bool {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    if !matches!(parsed.scheme(), "http" | "https") || classifier::is_local_url(url) {
+        return false;
+    }
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        return false;
+    };
+    // host_str keeps an IPv6 address's brackets, as a socket address wants them
+    let address = format!("{}:{}", host, port);
+    match tokio::time::timeout(TITLE_TIMEOUT, tokio::net::lookup_host(address)).await {
+        Ok(Ok(addrs)) => {
+            let addrs: Vec<_> = addrs.collect();
+            !addrs.is_empty() && !addrs.iter().any(|addr| classifier::is_local_ip(addr.ip()))
+        }
+        _ => false,
+    }
+}
+
+fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title>")?;
+    let title = html.get(start..end)?.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = title.replace("&amp;", "&").replace("&#39;", "'").replace("&quot;", "\"");
+    (!title.is_empty()).then_some(title)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal Shell Link with LinkInfo holding `base` as an ANSI local base path
+    fn lnk_with_local_path(base: &str) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        bytes[0x14..0x18].copy_from_slice(&HAS_LINK_INFO.to_le_bytes());
+
+        let header_size = 0x1Cu32;
+        let base_offset = header_size;
+        let suffix_offset = base_offset + base.len() as u32 + 1;
+        let size = suffix_offset + 1;
+        for value in [size, header_size, VOLUME_ID_AND_LOCAL_BASE_PATH, 0, base_offset, 0, suffix_offset] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(base.as_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_lnk() {
+        let bytes = lnk_with_local_path("C:\\Users\\me\\Uni\\ML\\lecture3.pdf");
+        assert_eq!(parse_lnk(&bytes).as_deref(), Some("C:\\Users\\me\\Uni\\ML\\lecture3.pdf"));
+        assert_eq!(parse_lnk(b"not a shortcut"), None);
+        assert_eq!(parse_lnk(&bytes[..HEADER_SIZE + 10]), None, "truncated");
+
+        let link = LinkTarget { target: "C:\\Uni\\ML\\lecture3.pdf".to_string(), is_url: false, exists: false, title: None };
+        assert_eq!(link.target_name().as_deref(), Some("lecture3.pdf"));
+    }
+
+    #[test]
+    fn test_parse_url_file() {
+        let text = "[{000214A0-0000-0000-C000-000000000046}]\r\nProp3=19,11\r\n[InternetShortcut]\r\nIDList=\r\nURL=https://canvas.example.edu/courses/4702\r\n";
+        assert_eq!(parse_url_file(text).as_deref(), Some("https://canvas.example.edu/courses/4702"));
+        assert_eq!(parse_url_file("URL=https://elsewhere.example"), None, "outside the section");
+
+        assert_eq!(
+            html_title("<html><head><TITLE>\n COMP4702 &amp; Machine Learning </title>").as_deref(),
+            Some("COMP4702 & Machine Learning")
+        );
+        assert_eq!(html_title("<html><title></title></html>"), None);
+    }
+
+    #[tokio::test]
+    async fn test_page_title_only_fetches_public_pages() {
+        for url in [
+            "http://localhost:8080/",
+            "http://127.0.0.1/admin",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://0.0.0.0:631/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[::ffff:10.0.0.1]/",
+            "http://printer.local/",
+            "file:///etc/passwd",
+            "ftp://8.8.8.8/",
+            "not a url",
+        ] {
+            assert!(!is_public_url(url).await, "{} counted as public", url);
+            assert_eq!(page_title(url).await, None);
+        }
+        assert!(is_public_url("https://8.8.8.8/").await);
+    }
+}
@@ -0,0 +1,320 @@
+//! Course list sync from Canvas or Moodle
+//!
+//! Students tend to name folders with abbreviations ("ML", "Macro") that mean nothing to
+//! the classifier. With an API token for their LMS, the enrolled courses are fetched and
+//! matched to the destination folders, and each matched folder gets the real course name
+//! as its description so the prompt can use it. Courses with no folder yet can have one
+//! created. The connection is stored as JSON in the settings table.
+
+use crate::db::{Database, DbError};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+pub const LMS_SETTING: &str = "lms_settings";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Canvas pages its course list; stop following "next" links after this many pages
+const MAX_PAGES: usize = 10;
+
+// Words left out of a course name's initials ("Theory of Computation" -> "TC")
+const MINOR_WORDS: &[&str] = &["a", "an", "and", "for", "in", "of", "on", "the", "to", "with"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LmsProvider {
+    #[default]
+    Canvas,
+    Moodle,
+}
+
+/// Persisted LMS connection
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LmsSettings {
+    pub provider: LmsProvider,
+    pub base_url: String, // e.g. "https://canvas.university.edu"
+    pub token: String,
+}
+
+/// An enrolled course
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LmsCourse {
+    pub id: i64,
+    pub code: String, // Canvas course_code / Moodle shortname
+    pub name: String, // Canvas name / Moodle fullname
+}
+
+impl LmsCourse {
+    /// Folder description for the course: its code and full name
+    pub fn description(&self) -> String {
+        if self.code.is_empty() || self.name.to_lowercase().contains(&self.code.to_lowercase()) {
+            self.name.clone()
+        } else {
+            format!("{} {}", self.code, self.name)
+        }
+    }
+}
+
+/// Load the stored connection (empty if none has been saved, or it can't be parsed)
+pub fn load(db: &Database) -> Result<LmsSettings, DbError> {
+    Ok(db
+        .get_setting(LMS_SETTING)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn save(db: &Database, settings: &LmsSettings) -> Result<(), DbError> {
+    let json = serde_json::to_string(settings).map_err(|e| DbError::QueryFailed(e.to_string()))?;
+    db.set_setting(LMS_SETTING, &json)
+}
+
+/// Check a connection before it's saved, returning it with the URL and token trimmed
+pub fn validate(mut settings: LmsSettings) -> Result<LmsSettings, String> {
+    settings.base_url = settings.base_url.trim().trim_end_matches('/').to_string();
+    settings.token = settings.token.trim().to_string();
+    if !settings.base_url.is_empty()
+        && !settings.base_url.starts_with("https://")
+        && !settings.base_url.starts_with("http://")
+    {
+        return Err("The LMS address must start with https://".to_string());
+    }
+    Ok(settings)
+}
+
+/// Fetch the courses the token's user is actively enrolled in
+pub async fn fetch_courses(settings: &LmsSettings) -> Result<Vec<LmsCourse>, String> {
+    if settings.base_url.is_empty() || settings.token.is_empty() {
+        return Err("Set the LMS address and API token first".to_string());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    match settings.provider {
+        LmsProvider::Canvas => fetch_canvas_courses(&client, settings).await,
+        LmsProvider::Moodle => fetch_moodle_courses(&client, settings).await,
+    }
+}
+
+async fn fetch_canvas_courses(client: &reqwest::Client, settings: &LmsSettings) -> Result<Vec<LmsCourse>, String> {
+    let mut url = format!("{}/api/v1/courses?enrollment_state=active&per_page=100", settings.base_url);
+    let mut courses = Vec::new();
+
+    for _ in 0..MAX_PAGES {
+        let response = client
+            .get(&url)
+            .bearer_auth(&settings.token)
+            .send()
+            .await
+            .map_err(|e| format!("Canvas request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Canvas returned {}", response.status()));
+        }
+
+        let next = response
+            .headers()
+            .get(reqwest::header::LINK)
+            .and_then(|link| link.to_str().ok())
+            .and_then(next_link);
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Canvas response wasn't valid JSON: {}", e))?;
+        courses.extend(parse_canvas_courses(&body));
+
+        match next {
+            Some(next) => url = next,
+            None => break,
+        }
+    }
+    Ok(courses)
+}
+
+async fn fetch_moodle_courses(client: &reqwest::Client, settings: &LmsSettings) -> Result<Vec<LmsCourse>, String> {
+    // Enrolments are looked up by user id, which the token alone doesn't give
+    let site_info = moodle_call(client, settings, "core_webservice_get_site_info", &[]).await?;
+    let user_id = site_info
+        .get("userid")
+        .and_then(|id| id.as_i64())
+        .ok_or("Moodle didn't return a user id for this token")?;
+
+    let courses = moodle_call(
+        client,
+        settings,
+        "core_enrol_get_users_courses",
+        &[("userid", user_id.to_string())],
+    )
+    .await?;
+    Ok(parse_moodle_courses(&courses))
+}
+
+async fn moodle_call(
+    client: &reqwest::Client,
+    settings: &LmsSettings,
+    function: &str,
+    params: &[(&str, String)],
+) -> Result<serde_json::Value, String> {
+    let mut query = vec![
+        ("wstoken", settings.token.clone()),
+        ("wsfunction", function.to_string()),
+        ("moodlewsrestformat", "json".to_string()),
+    ];
+    query.extend(params.iter().map(|(k, v)| (*k, v.clone())));
+
+    let response = client
+        .get(format!("{}/webservice/rest/server.php", settings.base_url))
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| format!("Moodle request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Moodle returned {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Moodle response wasn't valid JSON: {}", e))?;
+
+    // Moodle reports errors (bad token, disabled function) with a 200 and an exception object
+    if body.get("exception").is_some() {
+        let message = body.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+        return Err(format!("Moodle error: {}", message));
+    }
+    Ok(body)
+}
+
+/// The `rel="next"` URL from a Link header
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// Courses from a Canvas `/courses` response (restricted courses have no name and are skipped)
+fn parse_canvas_courses(body: &serde_json::Value) -> Vec<LmsCourse> {
+    parse_courses(body, "course_code", "name")
+}
+
+/// Courses from a Moodle `core_enrol_get_users_courses` response
+fn parse_moodle_courses(body: &serde_json::Value) -> Vec<LmsCourse> {
+    parse_courses(body, "shortname", "fullname")
+}
+
+fn parse_courses(body: &serde_json::Value, code_field: &str, name_field: &str) -> Vec<LmsCourse> {
+    let text = |course: &serde_json::Value, field: &str| {
+        course.get(field).and_then(|v| v.as_str()).map(|s| s.trim().to_string()).unwrap_or_default()
+    };
+    body.as_array()
+        .map(|courses| {
+            courses
+                .iter()
+                .filter_map(|course| {
+                    let name = text(course, name_field);
+                    if name.is_empty() {
+                        return None;
+                    }
+                    Some(LmsCourse {
+                        id: course.get("id").and_then(|id| id.as_i64()).unwrap_or_default(),
+                        code: text(course, code_field),
+                        name,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The destination folder a course belongs to, if the user already has one
+///
+/// A folder matches when its name contains the course code, equals the course name, or
+/// is the name's initials ("ML" for "Machine Learning").
+pub fn match_folder<'a>(course: &LmsCourse, folders: &'a [String]) -> Option<&'a String> {
+    let code = normalize(&course.code);
+    let name = normalize(&course.name);
+    let initials: String = course
+        .name
+        .split_whitespace()
+        .filter(|w| !MINOR_WORDS.contains(&w.to_lowercase().as_str()))
+        .filter_map(|w| w.chars().find(|c| c.is_alphanumeric()))
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    folders.iter().find(|folder| {
+        let base = Path::new(folder.as_str())
+            .file_name()
+            .map(|n| normalize(&n.to_string_lossy()))
+            .unwrap_or_default();
+        if base.is_empty() {
+            return false;
+        }
+        (code.len() >= 3 && base.contains(&code)) || base == name || (initials.len() >= 2 && base == initials)
+    })
+}
+
+/// Lowercase letters and digits only, so "COMP 1511" and "comp1511" compare equal
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(code: &str, name: &str) -> LmsCourse {
+        LmsCourse { id: 1, code: code.to_string(), name: name.to_string() }
+    }
+
+    #[test]
+    fn test_parse_courses() {
+        let canvas = serde_json::json!([
+            { "id": 101, "course_code": "ECON202", "name": "Intermediate Macroeconomics" },
+            { "id": 102, "access_restricted_by_date": true },
+        ]);
+        assert_eq!(parse_canvas_courses(&canvas), vec![LmsCourse {
+            id: 101,
+            code: "ECON202".to_string(),
+            name: "Intermediate Macroeconomics".to_string(),
+        }]);
+
+        let moodle = serde_json::json!([{ "id": 7, "shortname": "COMP4702", "fullname": " Machine Learning " }]);
+        assert_eq!(parse_moodle_courses(&moodle)[0].name, "Machine Learning");
+        assert!(parse_moodle_courses(&serde_json::json!({ "exception": "x" })).is_empty());
+
+        let link = "<https://lms.edu/api/v1/courses?page=1>; rel=\"current\", <https://lms.edu/api/v1/courses?page=2>; rel=\"next\"";
+        assert_eq!(next_link(link).as_deref(), Some("https://lms.edu/api/v1/courses?page=2"));
+        assert_eq!(next_link("<https://lms.edu/x>; rel=\"last\""), None);
+    }
+
+    #[test]
+    fn test_match_folder() {
+        let folders = vec![
+            "/Uni/ML".to_string(),
+            "/Uni/econ 202".to_string(),
+            "/Uni/Linear Algebra".to_string(),
+        ];
+        let found = |c: &LmsCourse| match_folder(c, &folders).map(String::as_str);
+
+        assert_eq!(found(&course("COMP4702", "Machine Learning")), Some("/Uni/ML"));
+        assert_eq!(found(&course("ECON202", "Intermediate Macroeconomics")), Some("/Uni/econ 202"));
+        assert_eq!(found(&course("MATH1151", "Linear Algebra")), Some("/Uni/Linear Algebra"));
+        assert_eq!(found(&course("PHYS101", "Physics of the Universe")), None);
+
+        assert_eq!(course("COMP4702", "Machine Learning").description(), "COMP4702 Machine Learning");
+        assert_eq!(course("ML", "ML: Machine Learning").description(), "ML: Machine Learning");
+        assert!(validate(LmsSettings { base_url: "canvas.edu".to_string(), ..Default::default() }).is_err());
+        assert_eq!(
+            validate(LmsSettings { base_url: " https://canvas.edu/ ".to_string(), ..Default::default() }).unwrap().base_url,
+            "https://canvas.edu"
+        );
+    }
+}
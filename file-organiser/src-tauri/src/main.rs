@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // `file-organiser organize <folder>` runs headless and exits (see cli.rs)
+    if let Some(code) = file_organiser_lib::run_cli(std::env::args().skip(1).collect()) {
+        std::process::exit(code);
+    }
     file_organiser_lib::run()
 }
@@ -0,0 +1,187 @@
+//! Markdown activity notes (e.g. for an Obsidian vault)
+//!
+//! Writes the day's or week's moves as a bullet list into a note named like a daily
+//! (`2025-03-14.md`) or weekly (`2025-W11.md`) note. The list sits between marker
+//! comments, so exporting again replaces it while anything else the user wrote in the
+//! note is left alone.
+
+use crate::db::ActivityEntry;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone};
+use serde::Deserialize;
+use std::path::Path;
+
+const SECTION_START: &str = "<!-- file-organiser:start -->";
+const SECTION_END: &str = "<!-- file-organiser:end -->";
+
+/// How much activity a note covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    #[serde(alias = "daily")]
+    Day,
+    #[serde(alias = "weekly")]
+    Week,
+}
+
+/// The time span and note name for a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteRange {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub file_stem: String, // "2025-03-14" or "2025-W11"
+    pub heading: String,
+}
+
+/// The period containing `now`: today, or this (Monday-first, ISO-numbered) week
+pub fn note_range(period: Period, now: DateTime<Local>) -> NoteRange {
+    let today = now.date_naive();
+    let (first_day, days, file_stem, heading) = match period {
+        Period::Day => (
+            today,
+            1,
+            today.format("%Y-%m-%d").to_string(),
+            format!("Files organized on {}", today.format("%A %-d %B %Y")),
+        ),
+        Period::Week => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            let week = today.iso_week();
+            (
+                monday,
+                7,
+                format!("{}-W{:02}", week.year(), week.week()),
+                format!("Files organized in week {} of {}", week.week(), week.year()),
+            )
+        }
+    };
+
+    let midnight = |date: chrono::NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|t| t.timestamp_millis())
+            .unwrap_or_default()
+    };
+    NoteRange {
+        start_ms: midnight(first_day),
+        end_ms: midnight(first_day + Duration::days(days)),
+        file_stem,
+        heading,
+    }
+}
+
+/// The note section for the moves in `range` (oldest first, undone moves left out)
+pub fn render_section(entries: &[ActivityEntry], range: &NoteRange) -> String {
+    let mut moves: Vec<&ActivityEntry> = entries
+        .iter()
+        .filter(|e| !e.undone && e.created_at >= range.start_ms && e.created_at < range.end_ms)
+        .collect();
+    moves.sort_by_key(|e| e.created_at);
+
+    let mut lines = vec![SECTION_START.to_string(), format!("## {}", range.heading), String::new()];
+    if moves.is_empty() {
+        lines.push("_No files moved._".to_string());
+    }
+    for entry in moves {
+        let mut line = format!("- moved {} → {}", entry.filename, short_folder(&entry.to_folder));
+        if let Some(original) = entry.original_filename.as_ref().filter(|o| **o != entry.filename) {
+            line.push_str(&format!(" (renamed from {})", original));
+        }
+        lines.push(line);
+    }
+    lines.push(SECTION_END.to_string());
+    lines.join("\n")
+}
+
+/// A destination as the last two folders of its path ("Econ/ProblemSets")
+fn short_folder(folder: &str) -> String {
+    let parts: Vec<String> = Path::new(folder)
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    parts[parts.len().saturating_sub(2)..].join("/")
+}
+
+/// The note with `section` in place of any earlier export, or appended to it
+pub fn merge_section(existing: Option<&str>, section: &str) -> String {
+    let Some(existing) = existing.filter(|e| !e.trim().is_empty()) else {
+        return format!("{}\n", section);
+    };
+    if let (Some(start), Some(end)) = (existing.find(SECTION_START), existing.find(SECTION_END)) {
+        if start < end {
+            let after = &existing[end + SECTION_END.len()..];
+            return format!("{}{}{}", &existing[..start], section, after);
+        }
+    }
+    format!("{}\n\n{}\n", existing.trim_end(), section)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(filename: &str, to_folder: &str, created_at: i64) -> ActivityEntry {
+        ActivityEntry {
+            id: None,
+            filename: filename.to_string(),
+            original_filename: None,
+            source_path: None,
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "/home/sam/Downloads".to_string(),
+            to_folder: to_folder.to_string(),
+            undone: false,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_note_range() {
+        // Thursday 13 March 2025, mid-afternoon
+        let now = Local.with_ymd_and_hms(2025, 3, 13, 15, 30, 0).unwrap();
+
+        let day = note_range(Period::Day, now);
+        assert_eq!(day.file_stem, "2025-03-13");
+        assert_eq!(day.start_ms, Local.with_ymd_and_hms(2025, 3, 13, 0, 0, 0).unwrap().timestamp_millis());
+        assert_eq!(day.end_ms, Local.with_ymd_and_hms(2025, 3, 14, 0, 0, 0).unwrap().timestamp_millis());
+
+        let week = note_range(Period::Week, now);
+        assert_eq!(week.file_stem, "2025-W11");
+        assert_eq!(week.start_ms, Local.with_ymd_and_hms(2025, 3, 10, 0, 0, 0).unwrap().timestamp_millis());
+        assert_eq!(week.end_ms, Local.with_ymd_and_hms(2025, 3, 17, 0, 0, 0).unwrap().timestamp_millis());
+    }
+
+    #[test]
+    fn test_render_and_merge_section() {
+        let range = NoteRange { start_ms: 100, end_ms: 200, file_stem: "x".to_string(), heading: "Today".to_string() };
+        let mut renamed = entry("Econ_PS2.pdf", "/Uni/Econ/ProblemSets", 150);
+        renamed.original_filename = Some("scan01.pdf".to_string());
+        let mut undone = entry("undone.pdf", "/Uni/Econ", 160);
+        undone.undone = true;
+        let entries = vec![renamed, entry("PS2.pdf", "/Uni/Econ/ProblemSets", 120), undone, entry("old.pdf", "/Uni", 50)];
+
+        let section = render_section(&entries, &range);
+        assert_eq!(
+            section,
+            "<!-- file-organiser:start -->\n## Today\n\n\
+             - moved PS2.pdf → Econ/ProblemSets\n\
+             - moved Econ_PS2.pdf → Econ/ProblemSets (renamed from scan01.pdf)\n\
+             <!-- file-organiser:end -->"
+        );
+        assert!(render_section(&[], &range).contains("_No files moved._"));
+
+        // New note, appended to the user's note, then replaced on the next export
+        assert_eq!(merge_section(None, &section), format!("{}\n", section));
+        let note = merge_section(Some("# Thursday\nLecture notes\n"), &section);
+        assert_eq!(note, format!("# Thursday\nLecture notes\n\n{}\n", section));
+        let updated = render_section(&[], &range);
+        assert_eq!(merge_section(Some(&note), &updated), format!("# Thursday\nLecture notes\n\n{}\n", updated));
+    }
+}
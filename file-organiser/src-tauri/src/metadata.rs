@@ -0,0 +1,343 @@
+//! File metadata for the details panel and photo-aware classification
+//!
+//! Besides filesystem timestamps and a MIME type, photos and phone/scanner captures
+//! carry EXIF data: when the picture was taken and on what device. Capture date is a
+//! much better signal than modified time for camera-scanned notes (copying a photo
+//! off a phone resets its modified time), so it's surfaced here and fed to the classifier.
+//!
+//! Files synced "online-only" by OneDrive/iCloud are placeholders: reading their content
+//! makes the sync client download them. They're detected from their attributes so that
+//! preview, OCR and content extraction can leave them alone.
+
+use exif::{In, Reader, Tag, Value};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// EXIF fields relevant to organizing photos and scans
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExifInfo {
+    pub captured_at: Option<String>, // "YYYY-MM-DDTHH:MM:SS", camera local time
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl ExifInfo {
+    /// One-line description for classification prompts, e.g.
+    /// "Photo taken 2025-10-03 14:02 with Apple iPhone 13"
+    pub fn summary(&self) -> Option<String> {
+        let device = match (&self.camera_make, &self.camera_model) {
+            // Models usually repeat the make ("Canon EOS 80D"), so don't print it twice
+            (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+                Some(model.clone())
+            }
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.clone().or_else(|| model.clone()),
+        };
+        let taken = self
+            .captured_at
+            .as_ref()
+            .map(|t| t.replacen('T', " ", 1).chars().take(16).collect::<String>());
+
+        match (taken, device) {
+            (Some(t), Some(d)) => Some(format!("Photo taken {} with {}", t, d)),
+            (Some(t), None) => Some(format!("Photo taken {}", t)),
+            (None, Some(d)) => Some(format!("Photo taken with {}", d)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Everything the details panel shows about a file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMetadata {
+    pub path: String,
+    pub size: u64,
+    pub created_at: Option<i64>,  // Unix timestamp ms (not available on every filesystem)
+    pub modified_at: Option<i64>, // Unix timestamp ms
+    pub mime_type: String,
+    pub exif: Option<ExifInfo>,
+    pub cloud_placeholder: bool, // online-only; content wasn't read
+}
+
+// Whether content of online-only files may be read anyway (downloading them)
+static READ_CLOUD_PLACEHOLDERS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_read_cloud_placeholders(enabled: bool) {
+    READ_CLOUD_PLACEHOLDERS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn read_cloud_placeholders() -> bool {
+    READ_CLOUD_PLACEHOLDERS.load(Ordering::Relaxed)
+}
+
+/// Whether Windows file attributes mark an online-only file (OneDrive "Files On-Demand")
+#[cfg(any(windows, test))]
+fn placeholder_attributes(attributes: u32) -> bool {
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+    attributes & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS) != 0
+}
+
+/// Whether macOS file flags mark a dataless (evicted iCloud/File Provider) file
+#[cfg(any(target_os = "macos", test))]
+fn dataless_flags(flags: u32) -> bool {
+    const SF_DATALESS: u32 = 0x40000000;
+    flags & SF_DATALESS != 0
+}
+
+/// Whether `path` is an online-only cloud file whose content must be downloaded to read
+#[cfg(windows)]
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    fs::symlink_metadata(path).is_ok_and(|meta| placeholder_attributes(meta.file_attributes()))
+}
+
+/// Whether `path` is an online-only cloud file whose content must be downloaded to read
+#[cfg(target_os = "macos")]
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    fs::symlink_metadata(path).is_ok_and(|meta| dataless_flags(meta.st_flags()))
+}
+
+/// Whether `path` is an online-only cloud file whose content must be downloaded to read
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_cloud_placeholder(_path: &Path) -> bool {
+    false
+}
+
+/// Whether to keep away from the content of `path` (a placeholder, and reading them is off)
+pub fn skip_content(path: &Path) -> bool {
+    !read_cloud_placeholders() && is_cloud_placeholder(path)
+}
+
+/// Read filesystem metadata, MIME type (detected from content) and (for images) EXIF,
+/// leaving the content of online-only files alone
+pub fn read(path: &Path) -> io::Result<FileMetadata> {
+    let meta = fs::metadata(path)?;
+    // Placeholders get their MIME type from the extension and no EXIF, so nothing downloads
+    let placeholder = skip_content(path);
+
+    Ok(FileMetadata {
+        path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        created_at: meta.created().ok().and_then(to_millis),
+        modified_at: meta.modified().ok().and_then(to_millis),
+        mime_type: crate::filetype::detect(path).mime_type,
+        exif: if placeholder { None } else { read_exif(path) },
+        cloud_placeholder: placeholder,
+    })
+}
+
+fn to_millis(time: SystemTime) -> Option<i64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as i64)
+}
+
+/// MIME type for a lowercase file extension
+pub fn mime_for_extension(ext: &str) -> &'static str {
+    match ext {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "heic" | "heif" => "image/heic",
+        "tif" | "tiff" => "image/tiff",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "zip" => "application/zip",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Read EXIF data, returning None for files without any (or that aren't images)
+pub fn read_exif(path: &Path) -> Option<ExifInfo> {
+    let file = File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let ascii = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY).and_then(|f| match &f.value {
+            Value::Ascii(parts) => parts
+                .first()
+                .map(|p| String::from_utf8_lossy(p).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+                .filter(|s| !s.is_empty()),
+            _ => None,
+        })
+    };
+    let number = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0))
+    };
+
+    let captured_at = [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| ascii(tag).and_then(|v| parse_exif_datetime(&v)));
+
+    let info = ExifInfo {
+        captured_at,
+        camera_make: ascii(Tag::Make),
+        camera_model: ascii(Tag::Model),
+        width: number(Tag::PixelXDimension),
+        height: number(Tag::PixelYDimension),
+    };
+
+    if info == ExifInfo::default() {
+        None
+    } else {
+        Some(info)
+    }
+}
+
+/// Convert EXIF's "YYYY:MM:DD HH:MM:SS" into ISO 8601 (without a time zone)
+fn parse_exif_datetime(value: &str) -> Option<String> {
+    let dt = exif::DateTime::from_ascii(value.as_bytes()).ok()?;
+    if dt.year == 0 || dt.month == 0 || dt.day == 0 {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+    ))
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal JPEG whose only content is an EXIF segment (make, model, capture date)
+    fn jpeg_with_exif(make: &str, model: &str, taken: &str) -> Vec<u8> {
+        fn ascii(s: &str) -> Vec<u8> {
+            let mut v = s.as_bytes().to_vec();
+            v.push(0);
+            v
+        }
+        fn entry(tiff: &mut Vec<u8>, tag: u16, typ: u16, count: u32, value: u32) {
+            tiff.extend_from_slice(&tag.to_le_bytes());
+            tiff.extend_from_slice(&typ.to_le_bytes());
+            tiff.extend_from_slice(&count.to_le_bytes());
+            tiff.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let (make, model, taken) = (ascii(make), ascii(model), ascii(taken));
+        let ifd0_end = 8 + 2 + 3 * 12 + 4;
+        let make_at = ifd0_end;
+        let model_at = make_at + make.len();
+        let exif_ifd_at = model_at + model.len();
+        let taken_at = exif_ifd_at + 2 + 12 + 4;
+
+        let mut tiff = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        entry(&mut tiff, 0x010F, 2, make.len() as u32, make_at as u32);
+        entry(&mut tiff, 0x0110, 2, model.len() as u32, model_at as u32);
+        entry(&mut tiff, 0x8769, 4, 1, exif_ifd_at as u32);
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(&make);
+        tiff.extend_from_slice(&model);
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        entry(&mut tiff, 0x9003, 2, taken.len() as u32, taken_at as u32);
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(&taken);
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_read_exif_from_jpeg() {
+        let tmp = std::env::temp_dir().join("fileorg_test_exif");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let photo = tmp.join("IMG_0042.jpg");
+        fs::write(&photo, jpeg_with_exif("Apple", "iPhone 13", "2025:10:03 14:02:09")).unwrap();
+
+        let exif = read_exif(&photo).unwrap();
+        assert_eq!(exif.captured_at.as_deref(), Some("2025-10-03T14:02:09"));
+        assert_eq!(exif.camera_make.as_deref(), Some("Apple"));
+        assert_eq!(exif.camera_model.as_deref(), Some("iPhone 13"));
+        assert_eq!(
+            exif.summary().unwrap(),
+            "Photo taken 2025-10-03 14:02 with Apple iPhone 13"
+        );
+
+        let meta = read(&photo).unwrap();
+        assert_eq!(meta.mime_type, "image/jpeg");
+        assert!(meta.modified_at.is_some());
+        assert_eq!(meta.exif, Some(exif));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_read_exif_missing() {
+        let tmp = std::env::temp_dir().join("fileorg_test_exif_missing");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let notes = tmp.join("notes.txt");
+        fs::write(&notes, "plain text").unwrap();
+
+        assert!(read_exif(&notes).is_none());
+        let meta = read(&notes).unwrap();
+        assert_eq!(meta.mime_type, "text/plain");
+        assert_eq!(meta.size, 10);
+        assert!(meta.exif.is_none());
+        assert!(!meta.cloud_placeholder);
+        assert!(!is_cloud_placeholder(&notes));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_summary_and_datetime_parsing() {
+        let canon = ExifInfo {
+            camera_make: Some("Canon".to_string()),
+            camera_model: Some("Canon EOS 80D".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(canon.summary().unwrap(), "Photo taken with Canon EOS 80D");
+        assert!(ExifInfo::default().summary().is_none());
+
+        assert_eq!(parse_exif_datetime("2024:01:31 08:05:00").as_deref(), Some("2024-01-31T08:05:00"));
+        assert!(parse_exif_datetime("0000:00:00 00:00:00").is_none());
+        assert!(parse_exif_datetime("    :  :     :  :  ").is_none());
+    }
+
+    #[test]
+    fn test_cloud_placeholder_flags() {
+        // OneDrive: offline or recall-on-access means the content isn't on disk
+        assert!(placeholder_attributes(0x1000));
+        assert!(placeholder_attributes(0x20 | 0x40000));
+        assert!(placeholder_attributes(0x20 | 0x400000));
+        assert!(!placeholder_attributes(0x20), "archive only: a downloaded file");
+        assert!(!placeholder_attributes(0x20 | 0x80000), "pinned (always keep on device)");
+
+        // iCloud/File Provider: dataless files
+        assert!(dataless_flags(0x40000000));
+        assert!(!dataless_flags(0x8000), "hidden only");
+        assert!(!dataless_flags(0));
+    }
+}
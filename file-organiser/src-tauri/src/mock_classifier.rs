@@ -0,0 +1,218 @@
+//! Offline stand-in for the AI provider
+//!
+//! With `provider = "mock"` the classifier answers from simple keyword rules instead of
+//! calling an API: a file goes to the folder whose name (or description) shares the most
+//! words with the filename and any extracted text. Answers are deterministic and need no
+//! network or API key, so the frontend can be developed, demoed and tested end to end
+//! without spending credits.
+
+use crate::classifier::{Classification, Explanation, FOLDER_DESCRIPTION_SEPARATOR};
+use std::path::Path;
+
+// Extensions the mock treats as not coursework, like the real prompt's memes and installers
+const IRRELEVANT_EXTENSIONS: [&str; 10] = ["exe", "msi", "dmg", "pkg", "deb", "mp3", "flac", "torrent", "iso", "apk"];
+
+// Length of the vectors returned by `embed`
+const EMBEDDING_DIMENSIONS: usize = 64;
+
+/// Lowercase words of at least two letters or digits
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 2)
+        .map(str::to_string)
+        .collect()
+}
+
+/// The words a folder is recognised by: its name, its description and, for names of
+/// several words, their initials ("Machine Learning" → "ml")
+fn folder_keywords(folder: &str) -> Vec<String> {
+    let (path, description) = folder.split_once(FOLDER_DESCRIPTION_SEPARATOR).unwrap_or((folder, ""));
+    let name = path.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next().unwrap_or(path);
+    let mut keywords = words(name);
+    if keywords.len() > 1 {
+        keywords.push(keywords.iter().filter_map(|w| w.chars().next()).collect());
+    }
+    keywords.extend(words(description));
+    keywords.sort();
+    keywords.dedup();
+    keywords
+}
+
+/// Whether a filename or content word counts as a mention of a folder keyword
+/// ("econ" matches "economics", short keywords only match exactly)
+fn mentions(word: &str, keyword: &str) -> bool {
+    word == keyword || (keyword.len() >= 4 && word.starts_with(keyword))
+}
+
+/// The words of `haystack` that mention one of `keywords`, first occurrence only
+fn matching_words(haystack: &[String], keywords: &[String]) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for word in haystack {
+        if keywords.iter().any(|keyword| mentions(word, keyword)) && !found.contains(word) {
+            found.push(word.clone());
+        }
+    }
+    found
+}
+
+/// Classify from the filename and (optionally) extracted text, as the real provider would
+pub fn classify(filename: &str, text: Option<&str>, available_folders: &[String]) -> Classification {
+    let extension = Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if IRRELEVANT_EXTENSIONS.contains(&extension.as_str()) {
+        return Classification {
+            is_relevant: false,
+            suggested_folder: String::new(),
+            confidence: 0.0,
+            reasoning: format!("Mock: .{} files aren't coursework", extension),
+            suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
+        };
+    }
+
+    let stem = Path::new(filename).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut haystack = words(&stem);
+    haystack.extend(words(text.unwrap_or_default()));
+
+    // Highest score wins; ties go to the folder listed first
+    let mut best: Option<(&String, Vec<String>)> = None;
+    for folder in available_folders {
+        let matched: Vec<String> = folder_keywords(folder)
+            .into_iter()
+            .filter(|keyword| haystack.iter().any(|word| mentions(word, keyword)))
+            .collect();
+        if !matched.is_empty() && best.as_ref().is_none_or(|(_, m)| matched.len() > m.len()) {
+            best = Some((folder, matched));
+        }
+    }
+
+    match best {
+        Some((folder, matched)) => Classification {
+            is_relevant: true,
+            suggested_folder: folder.split(FOLDER_DESCRIPTION_SEPARATOR).next().unwrap_or(folder).to_string(),
+            confidence: (0.5 + 0.2 * matched.len() as f32).min(0.95),
+            reasoning: format!("Mock: matched \"{}\"", matched.join("\", \"")),
+            suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: Some(Explanation {
+                // The words in the file, not the keywords they matched ("economics", not "econ")
+                keywords: matching_words(&haystack, &matched),
+                ..Explanation::default()
+            }),
+        },
+        None => Classification {
+            is_relevant: true,
+            suggested_folder: "__UNSORTED__".to_string(),
+            confidence: 0.3,
+            reasoning: "Mock: no folder name found in the file".to_string(),
+            suggested_filename: None,
+            tier: None,
+            truncation: None,
+            calibrated_confidence: None,
+            suggested_subfolder: None,
+            subfolder_confidence: None,
+            explanation: None,
+        },
+    }
+}
+
+/// A folder name for a group of files: the word most of them share, in Title Case
+pub fn topic_name(filenames: &[String], existing_folders: &[String]) -> String {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for filename in filenames {
+        let stem = Path::new(filename).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let mut seen = words(&stem);
+        seen.retain(|w| w.len() >= 3 && !w.chars().all(|c| c.is_ascii_digit()));
+        seen.dedup();
+        for word in seen {
+            match counts.iter_mut().find(|(w, _)| *w == word) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((word, 1)),
+            }
+        }
+    }
+    let taken = |name: &str| existing_folders.iter().any(|f| f.eq_ignore_ascii_case(name));
+    counts
+        .into_iter()
+        .filter(|(word, _)| !taken(word))
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(word, _)| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or(word)
+        })
+        .unwrap_or_else(|| "New Topic".to_string())
+}
+
+/// Bag-of-words vectors (each word hashed into one dimension), normalized, so texts that
+/// share words come out similar
+pub fn embed(texts: &[String]) -> Vec<Vec<f32>> {
+    texts
+        .iter()
+        .map(|text| {
+            let mut vector = vec![0.0f32; EMBEDDING_DIMENSIONS];
+            for word in words(text) {
+                let hash = blake3::hash(word.as_bytes());
+                vector[hash.as_bytes()[0] as usize % EMBEDDING_DIMENSIONS] += 1.0;
+            }
+            let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                vector.iter_mut().for_each(|v| *v /= norm);
+            }
+            vector
+        })
+        .collect()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_keywords() {
+        let folders = vec![
+            "/Uni/Machine Learning".to_string(),
+            "/Uni/Econ :: Macroeconomics".to_string(),
+            "/Uni/Algorithms".to_string(),
+        ];
+
+        let ml = classify("ML_lecture3.pdf", None, &folders);
+        assert_eq!(ml.suggested_folder, "/Uni/Machine Learning", "initials count");
+        assert!(ml.is_relevant && ml.confidence > 0.5);
+
+        let econ = classify("PS2.pdf", Some("Problem set on macroeconomics and growth"), &folders);
+        assert_eq!(econ.suggested_folder, "/Uni/Econ", "description words count, without the description");
+        assert_eq!(econ.explanation.unwrap().keywords, vec!["macroeconomics"]);
+
+        assert_eq!(classify("IMG_2041.png", None, &folders).suggested_folder, "__UNSORTED__");
+        assert!(!classify("setup.exe", None, &folders).is_relevant);
+
+        // Same input, same answer
+        assert_eq!(classify("ML_lecture3.pdf", None, &folders).confidence, ml.confidence);
+    }
+
+    #[test]
+    fn test_topic_name_and_embed() {
+        let files = vec!["resume_google.pdf".to_string(), "google_cover_letter.docx".to_string(), "resume_v2.pdf".to_string()];
+        assert_eq!(topic_name(&files, &[]), "Google");
+        assert_eq!(topic_name(&files, &["google".to_string()]), "Resume");
+
+        let vectors = embed(&["resume google".to_string(), "google resume".to_string(), String::new()]);
+        assert_eq!(vectors[0], vectors[1]);
+        assert!(vectors[2].iter().all(|v| *v == 0.0));
+    }
+}
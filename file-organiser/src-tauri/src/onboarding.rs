@@ -0,0 +1,240 @@
+//! First-run setup from an existing course tree
+//!
+//! Most students already have a folder of courses by the time they install the app. The
+//! onboarding wizard starts from it instead of an empty list: `bootstrap_setup` walks it
+//! for the course folders, suggests a rule for each course whose files share a word no
+//! other course uses (usually its code), and dry-runs a few recent downloads against the
+//! result. Nothing is saved or moved; the wizard shows it all and the user keeps what's right.
+//!
+//! A course folder is the first folder on each branch that holds files itself: in
+//! "Uni/Year2/ML/Lectures/week1.pdf", Year2 only holds folders, so ML is the course and
+//! Lectures one of its subfolders. A branch with no files ends at its deepest folder.
+
+use crate::db::Rule;
+use crate::insights;
+use crate::planner::{PlanSkip, PlannedMove};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Folders below the root looked at for courses ("Uni/Year2/Semester1/ML" is 4)
+const MAX_DEPTH: usize = 4;
+// Course folders found before the walk stops
+pub const MAX_COURSES: usize = 200;
+// Filenames read per course when looking for rule words, subfolders included
+const FILES_PER_COURSE: usize = 500;
+// Files a word must name before it's suggested as a course's rule
+const MIN_RULE_FILES: usize = 3;
+// Recent downloads classified as the dry run
+pub const SAMPLE_SIZE: usize = 5;
+
+/// A course folder found in the tree, with what was read from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Course {
+    pub path: String,
+    pub filenames: Vec<String>, // up to FILES_PER_COURSE, from it and its subfolders
+}
+
+/// A course folder as the wizard lists it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CourseFolder {
+    pub path: String,
+    pub files: usize, // capped at FILES_PER_COURSE
+}
+
+/// A rule the wizard offers: files naming `pattern` go to the course they always went to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SuggestedRule {
+    pub pattern: String, // e.g. "*comp3506*"
+    pub target_folder: String,
+    pub files: usize,          // files in the course matching it (none elsewhere do)
+    pub examples: Vec<String>, // a few of them
+}
+
+/// Everything the onboarding wizard shows, from one `bootstrap_setup` call
+#[derive(Debug, Clone, Serialize)]
+pub struct BootstrapSetup {
+    pub root: String,
+    pub folders: Vec<CourseFolder>,
+    pub rules: Vec<SuggestedRule>,
+    pub downloads: Option<String>, // the folder sampled, if there is one
+    pub sample: Vec<PlannedMove>,  // where its newest files would go with the folders and rules above
+    pub sample_skipped: Vec<PlanSkip>,
+}
+
+/// The course folders under `root` (see the module docs), sorted by path
+pub fn find_courses(root: &Path) -> Vec<Course> {
+    let mut courses = Vec::new();
+    visit(root, 0, &mut courses);
+    courses.sort_by(|a, b| a.path.cmp(&b.path));
+    courses
+}
+
+fn visit(dir: &Path, depth: usize, courses: &mut Vec<Course>) {
+    if courses.len() >= MAX_COURSES {
+        return;
+    }
+    let (files, mut subdirs) = visible_children(dir);
+    // Loose files in the root itself don't make it a course
+    if depth > 0 && (!files.is_empty() || subdirs.is_empty() || depth == MAX_DEPTH) {
+        let mut filenames = files;
+        collect_filenames(&subdirs, &mut filenames);
+        filenames.truncate(FILES_PER_COURSE);
+        courses.push(Course { path: dir.to_string_lossy().to_string(), filenames });
+        return;
+    }
+    subdirs.sort();
+    for subdir in subdirs {
+        visit(Path::new(&subdir), depth + 1, courses);
+    }
+}
+
+/// The names of the visible files and the paths of the visible folders directly in `dir`
+/// (symlinked folders are left out, so a link back up the tree isn't followed)
+fn visible_children(dir: &Path) -> (Vec<String>, Vec<String>) {
+    let (mut files, mut subdirs) = (Vec::new(), Vec::new());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (files, subdirs);
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => subdirs.push(entry.path().to_string_lossy().to_string()),
+            Ok(_) if entry.path().is_file() => files.push(name),
+            _ => {}
+        }
+    }
+    files.sort();
+    (files, subdirs)
+}
+
+/// Add the names of the files in `dirs` and their subfolders to `filenames`, up to FILES_PER_COURSE
+fn collect_filenames(dirs: &[String], filenames: &mut Vec<String>) {
+    for dir in dirs {
+        if filenames.len() >= FILES_PER_COURSE {
+            return;
+        }
+        let (files, subdirs) = visible_children(Path::new(dir));
+        filenames.extend(files);
+        collect_filenames(&subdirs, filenames);
+    }
+}
+
+/// A rule for each course whose filenames share a word that no other course's do
+///
+/// The word named in the most files wins, preferring ones with digits (course codes). Words
+/// an existing rule already matches on aren't suggested again.
+pub fn suggest_rules(courses: &[Course], existing: &[Rule]) -> Vec<SuggestedRule> {
+    // word -> course index -> its filenames containing the word
+    let mut uses: BTreeMap<String, BTreeMap<usize, Vec<&str>>> = BTreeMap::new();
+    for (index, course) in courses.iter().enumerate() {
+        for filename in &course.filenames {
+            for word in insights::filename_words(filename) {
+                uses.entry(word).or_default().entry(index).or_default().push(filename);
+            }
+        }
+    }
+
+    let mut best: BTreeMap<usize, (&str, &Vec<&str>)> = BTreeMap::new();
+    for (word, courses_using) in &uses {
+        let mut only = courses_using.iter();
+        let (Some((&index, files)), None) = (only.next(), only.next()) else {
+            continue;
+        };
+        let pattern = format!("*{}*", word);
+        if files.len() < MIN_RULE_FILES || existing.iter().any(|r| r.pattern.eq_ignore_ascii_case(&pattern)) {
+            continue;
+        }
+        let rank = |word: &str, files: &[&str]| (word.chars().any(|c| c.is_ascii_digit()), files.len());
+        // Words are visited alphabetically, so ties go to the first
+        if best.get(&index).is_none_or(|(current, current_files)| rank(word, files) > rank(current, current_files)) {
+            best.insert(index, (word, files));
+        }
+    }
+
+    best.into_iter()
+        .map(|(index, (word, files))| SuggestedRule {
+            pattern: format!("*{}*", word),
+            target_folder: courses[index].path.clone(),
+            files: files.len(),
+            examples: files.iter().take(3).map(|f| f.to_string()).collect(),
+        })
+        .collect()
+}
+
+/// A suggested rule in the form the planner applies, so the dry run uses it
+pub fn as_rule(rule: &SuggestedRule) -> Rule {
+    Rule {
+        id: None,
+        pattern: rule.pattern.clone(),
+        target_folder: rule.target_folder.clone(),
+        created_at: 0,
+        profile_id: None,
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"x").unwrap();
+    }
+
+    #[test]
+    fn test_find_courses() {
+        let root = std::env::temp_dir().join("fileorg_test_onboarding_courses");
+        let _ = fs::remove_dir_all(&root);
+        touch(&root.join("Year2/ML/Lectures/week1.pdf"));
+        touch(&root.join("Year2/ML/syllabus.pdf"));
+        touch(&root.join("Year2/Econ/Problem Sets/ps1.pdf"));
+        fs::create_dir_all(root.join("Year3/Thesis")).unwrap();
+        touch(&root.join("Year2/.hidden/notes.pdf"));
+        touch(&root.join("todo.txt"));
+
+        let courses = find_courses(&root);
+        let paths: Vec<String> = courses.iter().map(|c| c.path.clone()).collect();
+        let expected = |rel: &str| root.join(rel).to_string_lossy().to_string();
+        // ML holds a file itself; Econ only folders, so its one subfolder is where files first appear
+        assert_eq!(paths, vec![expected("Year2/Econ/Problem Sets"), expected("Year2/ML"), expected("Year3/Thesis")]);
+        let ml = courses.iter().find(|c| c.path == expected("Year2/ML")).unwrap();
+        assert_eq!(ml.filenames, vec!["syllabus.pdf", "week1.pdf"]);
+        assert!(courses[2].filenames.is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_suggest_rules() {
+        let course = |path: &str, filenames: &[&str]| Course {
+            path: path.to_string(),
+            filenames: filenames.iter().map(|f| f.to_string()).collect(),
+        };
+        let courses = vec![
+            course("/Uni/ML", &["COMP4702_lecture1.pdf", "COMP4702_lecture2.pdf", "comp4702 notes.pdf", "gradient_lecture.pdf"]),
+            course("/Uni/Econ", &["romer_ps1.pdf", "romer_ps2.pdf", "romer_ps3.pdf", "lecture_econ.pdf"]),
+            course("/Uni/Stats", &["week1.pdf", "week2.pdf"]),
+        ];
+
+        let rules = suggest_rules(&courses, &[]);
+        assert_eq!(rules.len(), 2, "Stats has no word in enough files");
+        assert_eq!(rules[0].pattern, "*comp4702*");
+        assert_eq!(rules[0].target_folder, "/Uni/ML");
+        assert_eq!(rules[0].files, 3);
+        // "lecture" is in both courses' files, so it's no one's rule
+        assert_eq!(rules[1].pattern, "*romer*");
+        assert_eq!(rules[1].examples, vec!["romer_ps1.pdf", "romer_ps2.pdf", "romer_ps3.pdf"]);
+
+        let existing = vec![as_rule(&rules[1])];
+        let rules = suggest_rules(&courses, &existing);
+        assert_eq!(rules.iter().map(|r| r.pattern.as_str()).collect::<Vec<_>>(), vec!["*comp4702*"]);
+    }
+}
@@ -0,0 +1,130 @@
+//! History and index entries whose files were deleted outside the app
+//!
+//! The activity log and the file index name files the app has seen, but nothing tells them
+//! when the user deletes one in Explorer or Finder. Those entries then offer undos that can't
+//! work and counts that are too high. `find` walks both and flags the ones whose file is gone,
+//! so the user can review and purge them.
+//!
+//! A file only counts as deleted when the folder it was in is still there. A whole folder
+//! missing more likely means an unplugged drive or a folder moved elsewhere (which
+//! `remap_paths` fixes), and purging its history then would lose it for good.
+
+use crate::db::ActivityEntry;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// One entry whose file no longer exists
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrphanedEntry {
+    pub kind: String,     // "activity" or "index"
+    pub id: Option<i64>,  // activity log id
+    pub path: String,     // where the file should be
+    pub recorded_at: i64, // Unix timestamp ms
+}
+
+/// How many entries `purge_orphaned_entries` removed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PurgeReport {
+    pub activity: usize,
+    pub index: usize,
+}
+
+/// Where the file of an activity entry should be now: its destination, or where it came
+/// from when the move was undone
+pub fn current_path(entry: &ActivityEntry) -> PathBuf {
+    if !entry.undone {
+        return Path::new(&entry.to_folder).join(&entry.filename);
+    }
+    match &entry.source_path {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&entry.from_folder).join(entry.original_filename.as_deref().unwrap_or(&entry.filename)),
+    }
+}
+
+/// Whether `path` was deleted: it's missing but the folder it was in isn't
+pub fn is_deleted(path: &Path) -> bool {
+    !path.exists() && path.parent().is_some_and(|parent| parent.is_dir())
+}
+
+/// The activity entries and indexed files (path, updated_at) whose file `deleted` says is gone
+pub fn find(activity: &[ActivityEntry], indexed: &[(String, i64)], deleted: impl Fn(&Path) -> bool) -> Vec<OrphanedEntry> {
+    let from_activity = activity.iter().filter_map(|entry| {
+        let path = current_path(entry);
+        deleted(&path).then(|| OrphanedEntry {
+            kind: "activity".to_string(),
+            id: entry.id,
+            path: path.to_string_lossy().to_string(),
+            recorded_at: entry.created_at,
+        })
+    });
+    let from_index = indexed.iter().filter(|(path, _)| deleted(Path::new(path))).map(|(path, updated_at)| OrphanedEntry {
+        kind: "index".to_string(),
+        id: None,
+        path: path.clone(),
+        recorded_at: *updated_at,
+    });
+    from_activity.chain(from_index).collect()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn moved(id: i64, filename: &str, to_folder: &Path, undone: bool) -> ActivityEntry {
+        ActivityEntry {
+            id: Some(id),
+            filename: filename.to_string(),
+            original_filename: None,
+            source_path: Some("/Downloads/ps1.pdf".to_string()),
+            session_id: None,
+            content_hash: None,
+            content_size: None,
+            confidence: None,
+            from_folder: "/Downloads".to_string(),
+            to_folder: to_folder.to_string_lossy().to_string(),
+            undone,
+            created_at: id,
+        }
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let dir = std::env::temp_dir().join("fileorg_test_orphans");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("kept.pdf"), b"x").unwrap();
+        let gone_drive = Path::new("/fileorg_test_unplugged_drive");
+
+        let activity = vec![
+            moved(1, "kept.pdf", &dir, false),
+            moved(2, "deleted.pdf", &dir, false),
+            moved(3, "offline.pdf", gone_drive, false),
+        ];
+        let indexed = vec![
+            (dir.join("kept.pdf").to_string_lossy().to_string(), 10),
+            (dir.join("deleted.docx").to_string_lossy().to_string(), 20),
+        ];
+
+        let orphans = find(&activity, &indexed, is_deleted);
+        let found: Vec<(&str, Option<i64>, i64)> = orphans.iter().map(|o| (o.kind.as_str(), o.id, o.recorded_at)).collect();
+        // The unplugged drive's folder is missing too, so its entry is left alone
+        assert_eq!(found, vec![("activity", Some(2), 2), ("index", None, 20)]);
+        assert_eq!(orphans[0].path, dir.join("deleted.pdf").to_string_lossy());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_current_path_after_undo() {
+        let entry = moved(1, "ps1.pdf", Path::new("/Uni/Econ"), true);
+        assert_eq!(current_path(&entry), PathBuf::from("/Downloads/ps1.pdf"));
+        let entry = ActivityEntry { source_path: None, original_filename: Some("PS 1.pdf".to_string()), ..entry };
+        assert_eq!(current_path(&entry), Path::new("/Downloads").join("PS 1.pdf"));
+        let entry = ActivityEntry { undone: false, ..entry };
+        assert_eq!(current_path(&entry), Path::new("/Uni/Econ").join("ps1.pdf"));
+    }
+}
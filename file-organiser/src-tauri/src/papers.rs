@@ -0,0 +1,447 @@
+//! Academic paper detection and reference-manager hand-off
+//!
+//! A PDF whose first page carries a DOI or an arXiv ID is a paper, and belongs in the
+//! student's reference manager as much as in a course folder. Papers can be pushed to a
+//! running Zotero through its local connector (Zotero then fetches the metadata itself),
+//! or get a BibTeX entry saved next to them for other reference managers. The identifier
+//! also resolves to title, authors and year (Crossref for DOIs, the arXiv API for arXiv
+//! IDs), which gives papers a proper filename like `Vaswani2017_Attention_Is_All_You_Need.pdf`.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+// Identifiers are looked for in roughly the first page; later pages cite other papers
+pub const SEARCH_CHARS: usize = 4000;
+
+const ZOTERO_CONNECTOR: &str = "http://127.0.0.1:23119/connector";
+
+const ZOTERO_TIMEOUT: Duration = Duration::from_secs(30);
+const ZOTERO_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Crossref asks clients to identify themselves
+const USER_AGENT: &str = "intelligent-file-assistant (https://github.com/rq1234/intelligent-file-assistant)";
+
+// Same cap as the AI's suggested filenames
+const MAX_FILENAME_CHARS: usize = 80;
+
+/// A paper's identifier
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum PaperId {
+    Doi(String),   // e.g. "10.1145/3290605.3300233"
+    Arxiv(String), // e.g. "2301.01234v2"
+}
+
+impl PaperId {
+    /// Where the paper lives online
+    pub fn url(&self) -> String {
+        match self {
+            PaperId::Doi(doi) => format!("https://doi.org/{}", doi),
+            PaperId::Arxiv(id) => format!("https://arxiv.org/abs/{}", id),
+        }
+    }
+}
+
+/// Title, authors and year of a paper
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PaperMetadata {
+    pub title: String,
+    pub authors: Vec<String>, // family names, in author order
+    pub year: Option<i32>,
+}
+
+/// The identifier on a PDF's first page, if it has one
+pub fn read_identifier(pdf: &Path) -> Result<Option<PaperId>, String> {
+    let bytes = std::fs::read(pdf).map_err(|e| format!("Failed to read PDF: {}", e))?;
+    let text = pdf_extract::extract_text_from_mem(&bytes).map_err(|e| format!("Failed to extract PDF text: {}", e))?;
+    Ok(find_identifier(&text))
+}
+
+/// Find a paper identifier in extracted text
+///
+/// An explicit arXiv ID wins over a DOI, since arXiv's own DOIs ("10.48550/arXiv...") and
+/// the DOIs of a preprint's eventual journal version are less useful than the ID itself.
+pub fn find_identifier(text: &str) -> Option<PaperId> {
+    let head: String = text.chars().take(SEARCH_CHARS).collect();
+    find_arxiv(&head).map(PaperId::Arxiv).or_else(|| {
+        find_doi(&head).map(|doi| match doi.to_lowercase().strip_prefix("10.48550/arxiv.") {
+            Some(id) => PaperId::Arxiv(id.to_string()),
+            None => PaperId::Doi(doi),
+        })
+    })
+}
+
+/// The first DOI: "10.", a registrant of 4+ digits, "/", then a suffix up to whitespace
+fn find_doi(text: &str) -> Option<String> {
+    for (start, _) in text.match_indices("10.") {
+        // Part of a longer number ("2010.5") isn't a DOI
+        if text[..start].chars().next_back().is_some_and(|c| c.is_ascii_alphanumeric()) {
+            continue;
+        }
+        let rest = &text[start + 3..];
+        let registrant = rest.chars().take_while(|c| c.is_ascii_digit() || *c == '.').count();
+        if !(4..=12).contains(&registrant) || !rest[registrant..].starts_with('/') {
+            continue;
+        }
+        let suffix: String = rest[registrant + 1..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !matches!(c, '"' | '<' | '>' | '{' | '}'))
+            .collect();
+        let suffix = suffix.trim_end_matches(['.', ',', ';', ':', ')', ']']);
+        if suffix.is_empty() {
+            continue;
+        }
+        return Some(format!("10.{}/{}", &rest[..registrant], suffix));
+    }
+    None
+}
+
+/// The first new-style arXiv ID marked as one ("arXiv:2301.01234v2", "arxiv.org/abs/2301.01234")
+fn find_arxiv(text: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    for marker in ["arxiv:", "arxiv.org/abs/", "arxiv.org/pdf/"] {
+        for (start, _) in lower.match_indices(marker) {
+            let rest = text[start + marker.len()..].trim_start();
+            let year_month = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if year_month != 4 || !rest[4..].starts_with('.') {
+                continue;
+            }
+            let number = rest[5..].chars().take_while(|c| c.is_ascii_digit()).count();
+            if !(4..=5).contains(&number) {
+                continue;
+            }
+            let mut end = 5 + number;
+            // Optional version: "v2"
+            if rest[end..].starts_with('v') {
+                let version = rest[end + 1..].chars().take_while(|c| c.is_ascii_digit()).count();
+                if version > 0 {
+                    end += 1 + version;
+                }
+            }
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+/// Look up a paper's title, authors and year
+pub async fn lookup(id: &PaperId) -> Result<PaperMetadata, String> {
+    let client = reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    match id {
+        PaperId::Doi(doi) => {
+            let response = client
+                .get(format!("https://api.crossref.org/works/{}", doi))
+                .send()
+                .await
+                .map_err(|e| format!("Crossref request failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("Crossref returned {} for {}", response.status(), doi));
+            }
+            let body: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("Crossref response wasn't valid JSON: {}", e))?;
+            parse_crossref(&body).ok_or_else(|| format!("Crossref has no title for {}", doi))
+        }
+        PaperId::Arxiv(arxiv) => {
+            let response = client
+                .get("https://export.arxiv.org/api/query")
+                .query(&[("id_list", arxiv.as_str())])
+                .send()
+                .await
+                .map_err(|e| format!("arXiv request failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("arXiv returned {} for {}", response.status(), arxiv));
+            }
+            let body = response.text().await.map_err(|e| format!("arXiv response unreadable: {}", e))?;
+            parse_arxiv_atom(&body).ok_or_else(|| format!("arXiv has no entry for {}", arxiv))
+        }
+    }
+}
+
+/// Metadata from a Crossref `/works/{doi}` response
+fn parse_crossref(body: &serde_json::Value) -> Option<PaperMetadata> {
+    let work = body.get("message")?;
+    let title = work.get("title")?.get(0)?.as_str().map(collapse_whitespace)?;
+    if title.is_empty() {
+        return None;
+    }
+    let authors = work
+        .get("author")
+        .and_then(|a| a.as_array())
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|a| a.get("family").or_else(|| a.get("name")).and_then(|n| n.as_str()))
+                .map(|n| n.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    // Print date if there is one, else whichever Crossref has
+    let year = ["published-print", "published-online", "issued"].iter().find_map(|field| {
+        work.get(*field)?.get("date-parts")?.get(0)?.get(0)?.as_i64().map(|y| y as i32)
+    });
+    Some(PaperMetadata { title, authors, year })
+}
+
+/// Metadata from the first entry of an arXiv API (Atom) response
+fn parse_arxiv_atom(xml: &str) -> Option<PaperMetadata> {
+    let entry = &xml[xml.find("<entry>")?..];
+    let title = xml_element(entry, "title").map(|t| collapse_whitespace(&xml_unescape(t)))?;
+    if title.is_empty() || title == "Error" {
+        return None;
+    }
+    let authors = entry
+        .split("<author>")
+        .skip(1)
+        .filter_map(|author| xml_element(author, "name"))
+        .filter_map(|name| xml_unescape(name).split_whitespace().last().map(str::to_string))
+        .collect();
+    let year = xml_element(entry, "published").and_then(|p| p.get(..4)?.parse().ok());
+    Some(PaperMetadata { title, authors, year })
+}
+
+/// The text of the first `<tag>...</tag>` in `xml`
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}", tag);
+    let start = xml.find(&open)?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    let end = content_start + xml[content_start..].find(&format!("</{}>", tag))?;
+    Some(xml[content_start..end].trim())
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Filename for a paper: first author's family name and year, then the title
+/// ("Vaswani2017_Attention_Is_All_You_Need.pdf"), at most 80 characters
+pub fn paper_filename(metadata: &PaperMetadata, extension: &str) -> String {
+    let word = |text: &str| -> String { text.chars().filter(|c| c.is_alphanumeric()).collect() };
+
+    let mut prefix = metadata.authors.first().map(|a| word(a)).unwrap_or_default();
+    if let Some(year) = metadata.year {
+        prefix.push_str(&year.to_string());
+    }
+    let mut parts: Vec<String> = Vec::new();
+    if !prefix.is_empty() {
+        parts.push(prefix);
+    }
+    // Long titles are cut at a word boundary
+    let suffix = format!(".{}", extension);
+    for title_word in metadata.title.split_whitespace().map(word).filter(|w| !w.is_empty()) {
+        let candidate_len = parts.iter().map(|p| p.chars().count() + 1).sum::<usize>() + title_word.chars().count();
+        if candidate_len + suffix.len() > MAX_FILENAME_CHARS && parts.len() > 1 {
+            break;
+        }
+        parts.push(title_word);
+    }
+    format!("{}{}", parts.join("_"), suffix)
+}
+
+/// A minimal BibTeX entry for a paper: the identifier, its URL and the local file
+///
+/// The title is taken from the filename; reference managers fill in the rest from the DOI.
+pub fn bibtex_entry(id: &PaperId, pdf: &Path) -> String {
+    let stem = pdf.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let key: String = stem.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let key = if key.is_empty() { "paper".to_string() } else { key };
+    let title = stem.replace(['_', '-'], " ").replace(['{', '}'], "");
+
+    let mut fields = vec![format!("  title = {{{}}}", title)];
+    match id {
+        PaperId::Doi(doi) => fields.push(format!("  doi = {{{}}}", doi)),
+        PaperId::Arxiv(arxiv) => {
+            fields.push(format!("  eprint = {{{}}}", arxiv));
+            fields.push("  archiveprefix = {arXiv}".to_string());
+        }
+    }
+    fields.push(format!("  url = {{{}}}", id.url()));
+    fields.push(format!("  file = {{{}}}", pdf.to_string_lossy().replace(['{', '}'], "")));
+
+    let entry_type = match id {
+        PaperId::Doi(_) => "article",
+        PaperId::Arxiv(_) => "misc",
+    };
+    format!("@{}{{{},\n{}\n}}\n", entry_type, key, fields.join(",\n"))
+}
+
+/// Whether Zotero is running with its connector enabled
+pub async fn zotero_available() -> bool {
+    let Ok(client) = reqwest::Client::builder().timeout(ZOTERO_PING_TIMEOUT).build() else {
+        return false;
+    };
+    client
+        .get(format!("{}/ping", ZOTERO_CONNECTOR))
+        .send()
+        .await
+        .is_ok_and(|r| r.status().is_success())
+}
+
+/// Add a paper to the library of the running Zotero
+///
+/// The PDF is saved as a standalone attachment, which Zotero then recognizes and files
+/// under a parent item with full metadata. Versions of Zotero without that endpoint
+/// get the BibTeX entry instead (metadata only, without the PDF).
+pub async fn send_to_zotero(pdf: &Path, id: &PaperId, session_id: &str) -> Result<(), String> {
+    let bytes = tokio::fs::read(pdf).await.map_err(|e| format!("Failed to read PDF: {}", e))?;
+    let title = pdf.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let metadata = serde_json::json!({ "sessionID": session_id, "title": title, "url": id.url() });
+
+    let client = reqwest::Client::builder()
+        .timeout(ZOTERO_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .post(format!("{}/saveStandaloneAttachment", ZOTERO_CONNECTOR))
+        .header("Content-Type", "application/pdf")
+        .header("X-Metadata", ascii_json(&metadata))
+        .body(bytes)
+        .send()
+        .await
+        .map_err(|e| format!("Couldn't reach Zotero (is it running?): {}", e))?;
+    if response.status().is_success() {
+        return Ok(());
+    }
+    if response.status() != reqwest::StatusCode::NOT_FOUND {
+        return Err(format!("Zotero returned {}", response.status()));
+    }
+
+    let response = client
+        .post(format!("{}/import", ZOTERO_CONNECTOR))
+        .query(&[("session", session_id)])
+        .header("Content-Type", "application/x-bibtex")
+        .body(bibtex_entry(id, pdf))
+        .send()
+        .await
+        .map_err(|e| format!("Couldn't reach Zotero (is it running?): {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Zotero returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// JSON with non-ASCII characters escaped, so it can go in an HTTP header
+fn ascii_json(value: &serde_json::Value) -> String {
+    let mut out = String::new();
+    for c in value.to_string().chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                out.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    out
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_identifier() {
+        let published = "Proceedings of CHI 2019. https://doi.org/10.1145/3290605.3300233. Permission to make copies";
+        assert_eq!(find_identifier(published), Some(PaperId::Doi("10.1145/3290605.3300233".to_string())));
+        assert_eq!(
+            find_identifier("DOI: 10.1016/j.jfineco.2020.06.001)"),
+            Some(PaperId::Doi("10.1016/j.jfineco.2020.06.001".to_string()))
+        );
+
+        let preprint = "Attention Is All You Need\narXiv:1706.03762v7 [cs.CL] 2 Aug 2023\n see 10.1145/1234.5678";
+        assert_eq!(find_identifier(preprint), Some(PaperId::Arxiv("1706.03762v7".to_string())));
+        assert_eq!(
+            find_identifier("https://doi.org/10.48550/arXiv.2301.01234"),
+            Some(PaperId::Arxiv("2301.01234".to_string()))
+        );
+
+        // Version numbers and identifiers past the first page don't count
+        assert_eq!(find_identifier("Version 2010.5/3 released, see arXiv: 12.34"), None);
+        let late = format!("{}10.1145/3290605.3300233", "x".repeat(SEARCH_CHARS));
+        assert_eq!(find_identifier(&late), None);
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let crossref = serde_json::json!({ "message": {
+            "title": ["Deep  Learning\nfor Finance"],
+            "author": [{ "given": "Ana", "family": "García" }, { "name": "OpenAI Team" }],
+            "issued": { "date-parts": [[2021, 3]] },
+            "published-print": { "date-parts": [[2022]] },
+        }});
+        assert_eq!(parse_crossref(&crossref), Some(PaperMetadata {
+            title: "Deep Learning for Finance".to_string(),
+            authors: vec!["García".to_string(), "OpenAI Team".to_string()],
+            year: Some(2022),
+        }));
+        assert_eq!(parse_crossref(&serde_json::json!({ "message": { "title": [] } })), None);
+
+        let atom = r#"<feed><title>ArXiv Query: id_list=1706.03762</title>
+<entry><id>http://arxiv.org/abs/1706.03762v7</id><published>2017-06-12T17:57:34Z</published>
+<title>Attention Is All
+  You Need</title>
+<author><name>Ashish Vaswani</name></author><author><name>Noam Shazeer</name></author></entry></feed>"#;
+        let metadata = parse_arxiv_atom(atom).unwrap();
+        assert_eq!(metadata.title, "Attention Is All You Need");
+        assert_eq!(metadata.authors, vec!["Vaswani".to_string(), "Shazeer".to_string()]);
+        assert_eq!(metadata.year, Some(2017));
+        assert_eq!(parse_arxiv_atom("<feed><title>ArXiv Query</title></feed>"), None);
+    }
+
+    #[test]
+    fn test_paper_filename() {
+        let metadata = PaperMetadata {
+            title: "Attention Is All You Need".to_string(),
+            authors: vec!["Vaswani".to_string()],
+            year: Some(2017),
+        };
+        assert_eq!(paper_filename(&metadata, "pdf"), "Vaswani2017_Attention_Is_All_You_Need.pdf");
+
+        let long = PaperMetadata {
+            title: "On the: Dangers of Stochastic Parrots — Can Language Models Be Too Big? A Very Long Subtitle Indeed".to_string(),
+            authors: vec!["Bender".to_string()],
+            year: None,
+        };
+        let name = paper_filename(&long, "pdf");
+        assert!(name.starts_with("Bender_On_the_Dangers_of_Stochastic_Parrots_Can"));
+        assert!(name.chars().count() <= MAX_FILENAME_CHARS);
+        assert!(name.ends_with(".pdf"));
+    }
+
+    #[test]
+    fn test_bibtex_entry() {
+        let entry = bibtex_entry(&PaperId::Arxiv("1706.03762".to_string()), Path::new("/papers/attention_is-all.pdf"));
+        assert!(entry.starts_with("@misc{attentionisall,\n  title = {attention is all},\n"));
+        assert!(entry.contains("  eprint = {1706.03762},\n  archiveprefix = {arXiv},\n"));
+        assert!(entry.contains("  url = {https://arxiv.org/abs/1706.03762},\n"));
+        assert!(entry.ends_with("  file = {/papers/attention_is-all.pdf}\n}\n"));
+
+        let entry = bibtex_entry(&PaperId::Doi("10.1145/3290605.3300233".to_string()), Path::new("/p/x.pdf"));
+        assert!(entry.starts_with("@article{x,"));
+        assert!(entry.contains("  doi = {10.1145/3290605.3300233},"));
+
+        let metadata = serde_json::json!({ "title": "Lösung 😀.pdf" });
+        assert_eq!(ascii_json(&metadata), r#"{"title":"L\u00f6sung \ud83d\ude00.pdf"}"#);
+    }
+}
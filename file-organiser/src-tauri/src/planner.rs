@@ -0,0 +1,391 @@
+//! Dry-run organization plans
+//!
+//! `plan_organization` proposes where every file in a folder should go without touching
+//! the filesystem. Plans are held in memory until `apply_plan` executes one, so the
+//! preview the user approved is exactly what gets moved.
+//!
+//! Cleanup plans (`plan_aging_cleanup`) work the same way, but also propose trashing old
+//! files that don't belong in any folder.
+
+use crate::db::{Correction, Rule};
+use crate::filename::nfc;
+use crate::report::Table;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Only the most recent plans are kept; older previews have to be generated again
+const MAX_PLANS: usize = 5;
+
+static PLANS: Mutex<Vec<OrganizationPlan>> = Mutex::new(Vec::new());
+static PLAN_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// A proposed move for one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedMove {
+    pub source_path: String,
+    pub filename: String,
+    pub dest_folder: String,
+    pub confidence: f32,
+    pub reasoning: String,
+    pub suggested_filename: Option<String>,
+    pub origin: String, // "rule", "cache", "type" or "ai"
+}
+
+/// A file the plan leaves where it is, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanSkip {
+    pub source_path: String,
+    pub filename: String,
+    pub reason: String,
+}
+
+/// Why planning left a file where it is
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unplaced {
+    NoMatch(String), // the file doesn't belong in any of the folders
+    Failed(String),  // it couldn't be classified (no API key, request failed, ...)
+}
+
+impl Unplaced {
+    pub fn into_reason(self) -> String {
+        match self {
+            Unplaced::NoMatch(reason) | Unplaced::Failed(reason) => reason,
+        }
+    }
+}
+
+/// Proposed organization of a folder
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizationPlan {
+    pub id: String,
+    pub folder: String,
+    pub created_at: i64, // Unix timestamp ms
+    pub moves: Vec<PlannedMove>,
+    pub skipped: Vec<PlanSkip>,
+    pub trash: Vec<PlanSkip>, // files to delete recoverably (cleanup plans only)
+}
+
+impl OrganizationPlan {
+    /// Add a file's planning outcome to a cleanup plan: moved if it fits a folder, trashed
+    /// if it belongs nowhere, left alone if it couldn't be classified
+    pub fn add_cleanup(&mut self, source_path: &str, filename: &str, outcome: Result<PlannedMove, Unplaced>) {
+        let unplaced = match outcome {
+            Ok(planned) => return self.moves.push(planned),
+            Err(unplaced) => unplaced,
+        };
+        let list = if matches!(unplaced, Unplaced::NoMatch(_)) { &mut self.trash } else { &mut self.skipped };
+        list.push(PlanSkip {
+            source_path: source_path.to_string(),
+            filename: filename.to_string(),
+            reason: unplaced.into_reason(),
+        });
+    }
+
+    /// Leave the files in `keep` (unticked in the preview) out of the moves and deletes
+    pub fn keep(&mut self, keep: &[String]) {
+        self.moves.retain(|m| !keep.contains(&m.source_path));
+        self.trash.retain(|t| !keep.contains(&t.source_path));
+    }
+}
+
+/// Generate a unique id for a new plan
+pub fn next_plan_id(created_at: i64) -> String {
+    format!("plan-{}-{}", created_at, PLAN_COUNTER.fetch_add(1, Ordering::SeqCst))
+}
+
+/// Keep a plan so it can be applied later, evicting the oldest beyond MAX_PLANS
+pub fn store(plan: OrganizationPlan) {
+    let mut plans = PLANS.lock().unwrap_or_else(|e| e.into_inner());
+    plans.push(plan);
+    if plans.len() > MAX_PLANS {
+        let excess = plans.len() - MAX_PLANS;
+        plans.drain(..excess);
+    }
+}
+
+/// Remove and return a stored plan (a plan can only be applied once)
+pub fn take(id: &str) -> Option<OrganizationPlan> {
+    let mut plans = PLANS.lock().unwrap_or_else(|e| e.into_inner());
+    let index = plans.iter().position(|p| p.id == id)?;
+    Some(plans.remove(index))
+}
+
+/// A copy of a stored plan, left in place so it can still be applied
+pub fn get(id: &str) -> Option<OrganizationPlan> {
+    let plans = PLANS.lock().unwrap_or_else(|e| e.into_inner());
+    plans.iter().find(|p| p.id == id).cloned()
+}
+
+/// One row per file in the plan: moves first, then files left where they are, then trash
+pub fn report_table(plan: &OrganizationPlan) -> Table {
+    let unmoved = |action: &str, skip: &PlanSkip| {
+        let mut row = vec![action.to_string(), skip.filename.clone(), skip.source_path.clone()];
+        row.extend(std::iter::repeat_n(String::new(), 4)); // no destination, name, confidence or origin
+        row.push(skip.reason.clone());
+        row
+    };
+    let rows = plan
+        .moves
+        .iter()
+        .map(|m| {
+            vec![
+                "move".to_string(),
+                m.filename.clone(),
+                m.source_path.clone(),
+                m.dest_folder.clone(),
+                m.suggested_filename.clone().unwrap_or_default(),
+                format!("{:.2}", m.confidence),
+                m.origin.clone(),
+                m.reasoning.clone(),
+            ]
+        })
+        .chain(plan.skipped.iter().map(|s| unmoved("skip", s)))
+        .chain(plan.trash.iter().map(|s| unmoved("trash", s)))
+        .collect();
+    Table {
+        headers: vec!["Action", "File", "Source", "Destination", "New name", "Confidence", "Origin", "Reason"],
+        rows,
+    }
+}
+
+/// Find the first rule whose glob pattern matches the filename
+///
+/// Mirrors the frontend's matchRule: `*` matches any run of characters, everything
+/// else is literal, and matching ignores case and Unicode normalization form.
+pub fn match_rule<'a>(filename: &str, rules: &'a [Rule]) -> Option<&'a Rule> {
+    let filename = nfc(filename).to_lowercase();
+    rules
+        .iter()
+        .find(|rule| glob_matches(&nfc(&rule.pattern).to_lowercase(), &filename))
+}
+
+/// Reuse the folder the user previously accepted or chose for a file with the same name
+///
+/// Mirrors the frontend's getCachedClassification: corrections are newest first, and the
+/// remembered folder only counts if it's still one of the available folders.
+pub fn cached_folder<'a>(
+    filename: &str,
+    corrections: &[Correction],
+    available_folders: &'a [String],
+) -> Option<&'a String> {
+    let filename = nfc(filename);
+    let cached = corrections.iter().find(|c| {
+        nfc(&c.filename) == filename && (c.correction_type == "accepted" || c.correction_type == "corrected")
+    })?;
+    available_folders.iter().find(|folder| {
+        let name = folder.rsplit(['/', '\\']).next().unwrap_or(folder);
+        name.eq_ignore_ascii_case(&cached.user_chose)
+    })
+}
+
+/// Match text against a pattern where `*` is the only wildcard
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+    if !text.starts_with(first) || text.len() < first.len() + last.len() || !text.ends_with(last) {
+        return false;
+    }
+
+    // Middle pieces must appear in order between the fixed prefix and suffix
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, target: &str) -> Rule {
+        Rule {
+            id: Some(1),
+            pattern: pattern.to_string(),
+            target_folder: target.to_string(),
+            created_at: 0,
+            profile_id: None,
+        }
+    }
+
+    fn plan(id: &str) -> OrganizationPlan {
+        OrganizationPlan {
+            id: id.to_string(),
+            folder: "Downloads".to_string(),
+            created_at: 0,
+            moves: Vec::new(),
+            skipped: Vec::new(),
+            trash: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_match_rule_patterns() {
+        let rules = vec![
+            rule("*_ML_*", "C:\\Year2\\ML"),
+            rule("Lecture*", "C:\\Year2\\Physics"),
+            rule("PS*.pdf", "C:\\Year2\\Maths"),
+        ];
+
+        assert_eq!(match_rule("homework_ML_notes.pdf", &rules).unwrap().target_folder, "C:\\Year2\\ML");
+        assert_eq!(match_rule("lecture3.pptx", &rules).unwrap().target_folder, "C:\\Year2\\Physics");
+        assert_eq!(match_rule("PS4.PDF", &rules).unwrap().target_folder, "C:\\Year2\\Maths");
+        assert!(match_rule("PS4.docx", &rules).is_none());
+        assert!(match_rule("notes.pdf", &rules).is_none());
+    }
+
+    #[test]
+    fn test_match_rule_ignores_normalization_form() {
+        let rules = vec![
+            rule("Müller*", "C:\\Year2\\German"),
+            rule("*\u{304c}\u{304f}\u{305b}\u{3044}*", "C:\\Year2\\Japanese"),
+        ];
+
+        // Decomposed names, as macOS writes them
+        assert_eq!(match_rule("Mu\u{308}ller_essay.pdf", &rules).unwrap().target_folder, "C:\\Year2\\German");
+        assert_eq!(match_rule("MU\u{308}LLER.pdf", &rules).unwrap().target_folder, "C:\\Year2\\German");
+        assert_eq!(
+            match_rule("\u{304b}\u{3099}\u{304f}\u{305b}\u{3044}_notes.pdf", &rules).unwrap().target_folder,
+            "C:\\Year2\\Japanese"
+        );
+        assert!(match_rule("Muller.pdf", &rules).is_none());
+
+        // And decomposed patterns against composed names
+        let rules = vec![rule("Mu\u{308}ller*", "C:\\Year2\\German")];
+        assert!(match_rule("Müller_essay.pdf", &rules).is_some());
+    }
+
+    #[test]
+    fn test_cached_folder() {
+        let correction = |filename: &str, chose: &str, kind: &str| Correction {
+            id: None,
+            filename: filename.to_string(),
+            ai_suggested: String::new(),
+            user_chose: chose.to_string(),
+            correction_type: kind.to_string(),
+            confidence: None,
+            created_at: 0,
+        };
+        let corrections = vec![
+            correction("PS3.pdf", "algorithms", "corrected"),
+            correction("PS3.pdf", "Physics", "accepted"),
+            correction("meme.png", "Physics", "dismissed"),
+            correction("old.pdf", "Deleted Module", "accepted"),
+            correction("U\u{308}bung.pdf", "Physics", "accepted"),
+        ];
+        let folders = vec!["C:\\Year2\\Algorithms".to_string(), "/home/me/Year2/Physics".to_string()];
+
+        assert_eq!(cached_folder("PS3.pdf", &corrections, &folders).unwrap(), "C:\\Year2\\Algorithms");
+        assert!(cached_folder("meme.png", &corrections, &folders).is_none());
+        assert!(cached_folder("old.pdf", &corrections, &folders).is_none());
+        assert!(cached_folder("new.pdf", &corrections, &folders).is_none());
+        assert_eq!(cached_folder("Übung.pdf", &corrections, &folders).unwrap(), "/home/me/Year2/Physics");
+    }
+
+    #[test]
+    fn test_glob_literal_and_overlap() {
+        assert!(glob_matches("notes.pdf", "notes.pdf"));
+        assert!(!glob_matches("notes.pdf", "notes1pdf"));
+        assert!(glob_matches("a*a", "aa"));
+        assert!(!glob_matches("ab*ba", "aba"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("*x*y*", "axbyc"));
+        assert!(!glob_matches("*y*x*", "axbyc"));
+    }
+
+    #[test]
+    fn test_take_plan_only_once() {
+        store(plan("plan-test-once"));
+        assert!(take("plan-test-once").is_some());
+        assert!(take("plan-test-once").is_none());
+    }
+
+    #[test]
+    fn test_get_plan_and_report_table() {
+        let mut stored = plan("plan-test-report");
+        stored.moves.push(PlannedMove {
+            source_path: "/Downloads/ps1.pdf".to_string(),
+            filename: "ps1.pdf".to_string(),
+            dest_folder: "/Uni/Econ".to_string(),
+            confidence: 0.875,
+            reasoning: "Problem set".to_string(),
+            suggested_filename: Some("ECON1010_ps1.pdf".to_string()),
+            origin: "ai".to_string(),
+        });
+        stored.skipped.push(PlanSkip {
+            source_path: "/Downloads/meme.png".to_string(),
+            filename: "meme.png".to_string(),
+            reason: "Not course material".to_string(),
+        });
+        store(stored);
+
+        let table = report_table(&get("plan-test-report").unwrap());
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0][5], "0.88");
+        assert_eq!(table.rows[0][4], "ECON1010_ps1.pdf");
+        assert_eq!(table.rows[1][0], "skip");
+        assert_eq!(table.rows[1][7], "Not course material");
+        assert!(take("plan-test-report").is_some(), "getting a plan leaves it to be applied");
+    }
+
+    #[test]
+    fn test_next_plan_id_unique() {
+        assert_ne!(next_plan_id(5), next_plan_id(5));
+    }
+
+    fn planned(name: &str) -> PlannedMove {
+        PlannedMove {
+            source_path: format!("/Downloads/{}", name),
+            filename: name.to_string(),
+            dest_folder: "/Uni/ML".to_string(),
+            confidence: 0.9,
+            reasoning: String::new(),
+            suggested_filename: None,
+            origin: "ai".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cleanup_plan_sorts_outcomes() {
+        let mut cleanup = plan("plan-test-cleanup");
+        cleanup.add_cleanup("/Downloads/lecture3.pdf", "lecture3.pdf", Ok(planned("lecture3.pdf")));
+        cleanup.add_cleanup("/Downloads/meme.png", "meme.png", Err(Unplaced::NoMatch("Not course material".to_string())));
+        cleanup.add_cleanup("/Downloads/scan.pdf", "scan.pdf", Err(Unplaced::Failed("Classification failed".to_string())));
+
+        assert_eq!(cleanup.moves.len(), 1);
+        // Only files that belong nowhere are proposed for the trash
+        assert_eq!(cleanup.trash.len(), 1);
+        assert_eq!((cleanup.trash[0].filename.as_str(), cleanup.trash[0].reason.as_str()), ("meme.png", "Not course material"));
+        // A file that couldn't be classified is never deleted
+        assert_eq!(cleanup.skipped.len(), 1);
+        assert_eq!(cleanup.skipped[0].source_path, "/Downloads/scan.pdf");
+
+        let table = report_table(&cleanup);
+        assert_eq!(table.rows.iter().map(|row| row[0].as_str()).collect::<Vec<_>>(), vec!["move", "skip", "trash"]);
+    }
+
+    #[test]
+    fn test_keep_leaves_files_out() {
+        let mut cleanup = plan("plan-test-keep");
+        cleanup.add_cleanup("/Downloads/a.pdf", "a.pdf", Ok(planned("a.pdf")));
+        cleanup.add_cleanup("/Downloads/b.pdf", "b.pdf", Ok(planned("b.pdf")));
+        cleanup.add_cleanup("/Downloads/old.zip", "old.zip", Err(Unplaced::NoMatch(String::new())));
+        cleanup.add_cleanup("/Downloads/older.zip", "older.zip", Err(Unplaced::NoMatch(String::new())));
+
+        cleanup.keep(&["/Downloads/b.pdf".to_string(), "/Downloads/old.zip".to_string()]);
+        assert_eq!(cleanup.moves.iter().map(|m| m.filename.as_str()).collect::<Vec<_>>(), vec!["a.pdf"]);
+        assert_eq!(cleanup.trash.iter().map(|t| t.filename.as_str()).collect::<Vec<_>>(), vec!["older.zip"]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Watch profiles
+//!
+//! A profile binds a watched folder to its own destination folders, rules, classification
+//! mode and auto-move threshold, so Downloads can go to course folders through the AI
+//! while Screenshots go into a Notes tree through OCR only. Profiles are stored in the
+//! `watch_profiles` table; this module decides which one applies to a path.
+//!
+//! A profile can also exclude subfolders of its watched tree (`node_modules`, `.git`, build
+//! caches): the watcher ignores files in them and recursive scans don't go into them.
+
+use crate::buckets::ClassificationMode;
+use crate::db::WatchProfile;
+use std::path::Path;
+
+/// The profile for a file or folder: the one watching it, or its nearest watched ancestor
+pub fn profile_for_path<'a>(profiles: &'a [WatchProfile], path: &Path) -> Option<&'a WatchProfile> {
+    profiles
+        .iter()
+        .filter(|p| !p.watch_path.is_empty() && path.starts_with(&p.watch_path))
+        .max_by_key(|p| Path::new(&p.watch_path).components().count())
+}
+
+/// Whether `path` is in one of the profile's excluded subfolders (or is one)
+///
+/// An entry that's just a name ("node_modules") matches a folder of that name at any depth;
+/// one with slashes ("build/cache") matches that path below the watched folder. Case is ignored.
+pub fn is_excluded(profile: &WatchProfile, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(&profile.watch_path) else {
+        return false;
+    };
+    let parts: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_lowercase()).collect();
+    profile.excluded.iter().any(|entry| {
+        let entry: Vec<String> = entry.split('/').map(str::to_lowercase).collect();
+        match entry.as_slice() {
+            [name] => parts.contains(name),
+            _ => parts.starts_with(&entry),
+        }
+    })
+}
+
+/// Whether `path` is excluded by the profile it belongs to
+pub fn excluded(profiles: &[WatchProfile], path: &Path) -> bool {
+    profile_for_path(profiles, path).is_some_and(|profile| is_excluded(profile, path))
+}
+
+/// An excluded subfolder as stored: a name or a path below `watch_path`, with forward slashes
+fn normalize_excluded(entry: &str, watch_path: &str) -> Result<String, String> {
+    let entry = entry.trim();
+    // A folder picked with the browse dialog comes as a full path
+    let relative = match Path::new(entry).strip_prefix(watch_path) {
+        Ok(relative) => relative.to_string_lossy().to_string(),
+        Err(_) if Path::new(entry).is_absolute() => {
+            return Err(format!("{} isn't inside the watched folder", entry));
+        }
+        Err(_) => entry.to_string(),
+    };
+    let relative = relative.replace('\\', "/").trim_matches('/').to_string();
+    if relative.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return Err(format!("Excluded folder must be a folder name or a path inside the watched folder: {}", entry));
+    }
+    Ok(relative)
+}
+
+/// The profile's classification mode (AI if the stored value isn't recognized)
+pub fn mode(profile: &WatchProfile) -> ClassificationMode {
+    ClassificationMode::parse(&profile.mode).unwrap_or_default()
+}
+
+/// Check a profile before it's saved, returning it with names trimmed and folders deduplicated
+pub fn validate(mut profile: WatchProfile) -> Result<WatchProfile, String> {
+    profile.name = profile.name.trim().to_string();
+    profile.watch_path = profile.watch_path.trim().to_string();
+    if profile.name.is_empty() {
+        return Err("Profile name can't be empty".to_string());
+    }
+    if profile.watch_path.is_empty() {
+        return Err("Choose a folder for the profile to watch".to_string());
+    }
+    if ClassificationMode::parse(&profile.mode).is_none() {
+        return Err(format!("Unknown classification mode: {}", profile.mode));
+    }
+    if !(0.0..=1.0).contains(&profile.min_confidence) {
+        return Err("Confidence threshold must be between 0 and 1".to_string());
+    }
+
+    let mut folders: Vec<String> = Vec::new();
+    for folder in profile.folders.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+        if !folders.iter().any(|f| f == folder) {
+            folders.push(folder.to_string());
+        }
+    }
+    profile.folders = folders;
+
+    let mut excluded: Vec<String> = Vec::new();
+    for entry in profile.excluded.iter().filter(|e| !e.trim().is_empty()) {
+        let entry = normalize_excluded(entry, &profile.watch_path)?;
+        if !excluded.iter().any(|e| e.eq_ignore_ascii_case(&entry)) {
+            excluded.push(entry);
+        }
+    }
+    profile.excluded = excluded;
+    Ok(profile)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(id: i64, watch_path: &str, mode: &str) -> WatchProfile {
+        WatchProfile {
+            id: Some(id),
+            name: format!("Profile {}", id),
+            watch_path: watch_path.to_string(),
+            folders: Vec::new(),
+            mode: mode.to_string(),
+            min_confidence: 0.9,
+            created_at: 0,
+            excluded: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_profile_for_path_picks_nearest_watched_folder() {
+        let profiles = vec![
+            profile(1, "/home/sam/Downloads", "ai"),
+            profile(2, "/home/sam/Downloads/Screenshots", "ocr"),
+        ];
+
+        let found = |path: &str| profile_for_path(&profiles, Path::new(path)).and_then(|p| p.id);
+        assert_eq!(found("/home/sam/Downloads/ps3.pdf"), Some(1));
+        assert_eq!(found("/home/sam/Downloads/Screenshots/shot.png"), Some(2));
+        assert_eq!(found("/home/sam/Downloads/Screenshots"), Some(2));
+        // Whole path components only: "Downloads2" isn't inside "Downloads"
+        assert_eq!(found("/home/sam/Downloads2/file.txt"), None);
+
+        assert_eq!(mode(&profiles[1]), ClassificationMode::Ocr);
+        assert_eq!(mode(&profile(3, "/x", "unknown")), ClassificationMode::Ai);
+    }
+
+    #[test]
+    fn test_validate_profile() {
+        let mut p = profile(1, "  /home/sam/Screenshots ", "ocr");
+        p.folders = vec![
+            "/home/sam/Notes/Maths".to_string(),
+            " ".to_string(),
+            "/home/sam/Notes/Maths".to_string(),
+        ];
+        let p = validate(p).unwrap();
+        assert_eq!(p.watch_path, "/home/sam/Screenshots");
+        assert_eq!(p.folders, vec!["/home/sam/Notes/Maths".to_string()]);
+
+        assert!(validate(profile(1, "", "ai")).is_err());
+        assert!(validate(profile(1, "/x", "vision")).is_err());
+        assert!(validate(WatchProfile { min_confidence: 1.5, ..profile(1, "/x", "ai") }).is_err());
+        assert!(validate(WatchProfile { name: " ".to_string(), ..profile(1, "/x", "ai") }).is_err());
+    }
+
+    #[test]
+    fn test_excluded_subfolders() {
+        let mut p = profile(1, "/home/sam/Projects", "ai");
+        p.excluded = vec![
+            " node_modules ".to_string(),
+            "build\\cache/".to_string(),
+            "/home/sam/Projects/site/.next".to_string(),
+            "Node_Modules".to_string(),
+            "".to_string(),
+        ];
+        let p = validate(p).unwrap();
+        assert_eq!(p.excluded, vec!["node_modules", "build/cache", "site/.next"]);
+
+        let skipped = |path: &str| is_excluded(&p, Path::new(path));
+        assert!(skipped("/home/sam/Projects/app/node_modules/react/index.js"));
+        assert!(skipped("/home/sam/Projects/node_modules"));
+        assert!(skipped("/home/sam/Projects/Build/Cache/a.o"));
+        assert!(skipped("/home/sam/Projects/site/.next/page.js"));
+        assert!(!skipped("/home/sam/Projects/app/build/cache/a.o"), "paths are relative to the watched folder");
+        assert!(!skipped("/home/sam/Projects/app/src/node_modules_notes.md"));
+        assert!(!skipped("/home/sam/Other/node_modules/x.js"));
+        assert!(excluded(std::slice::from_ref(&p), Path::new("/home/sam/Projects/a/node_modules")));
+
+        for bad in ["../secrets", "/home/sam/Other/node_modules", "a/./b"] {
+            let mut p = profile(1, "/home/sam/Projects", "ai");
+            p.excluded = vec![bad.to_string()];
+            assert!(validate(p).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Provenance of organized files
+//!
+//! Every organizing move is recorded in the `provenance` table: where the file was before,
+//! when it was organized and why it went where it did. Optionally the same record is also
+//! written onto the file itself (an extended attribute, or an NTFS alternate data stream
+//! on Windows), so it travels with the file when it's copied elsewhere or the app's
+//! database is gone.
+
+use crate::db::Provenance;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(unix)]
+const ATTRIBUTE_NAME: &str = "user.file-organiser.provenance";
+#[cfg(windows)]
+const STREAM_NAME: &str = "file-organiser.provenance";
+
+static WRITE_TO_FILES: AtomicBool = AtomicBool::new(false);
+
+/// Also write provenance onto moved files themselves
+pub fn set_write_to_files(enabled: bool) {
+    WRITE_TO_FILES.store(enabled, Ordering::Relaxed);
+}
+
+pub fn write_to_files() -> bool {
+    WRITE_TO_FILES.load(Ordering::Relaxed)
+}
+
+/// Write `provenance` onto the file at `provenance.path`
+pub fn write_to_file(provenance: &Provenance) -> Result<(), String> {
+    let json = serde_json::to_vec(provenance).map_err(|e| e.to_string())?;
+    let path = Path::new(&provenance.path);
+    #[cfg(unix)]
+    let written = xattr::set(path, ATTRIBUTE_NAME, &json);
+    #[cfg(windows)]
+    let written = std::fs::write(stream_path(path), json);
+    written.map_err(|e| e.to_string())
+}
+
+/// Read provenance written onto the file at `path`, if it has any
+pub fn read_from_file(path: &Path) -> Option<Provenance> {
+    #[cfg(unix)]
+    let json = xattr::get(path, ATTRIBUTE_NAME).ok()??;
+    #[cfg(windows)]
+    let json = std::fs::read(stream_path(path)).ok()?;
+
+    let provenance: Provenance = serde_json::from_slice(&json).ok()?;
+    // The file may have been moved or copied since; it's here now
+    Some(Provenance { path: path.to_string_lossy().to_string(), ..provenance })
+}
+
+/// `C:\dir\file.pdf:file-organiser.provenance`
+#[cfg(windows)]
+fn stream_path(path: &Path) -> std::path::PathBuf {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":");
+    stream.push(STREAM_NAME);
+    stream.into()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_provenance_round_trips_through_the_file() {
+        let tmp = std::env::temp_dir().join("fileorg_test_provenance");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let path = tmp.join("hw3.pdf");
+        fs::write(&path, "homework").unwrap();
+        assert_eq!(read_from_file(&path), None);
+
+        let provenance = Provenance {
+            path: path.to_string_lossy().to_string(),
+            original_path: "/home/student/Downloads/hw3.pdf".to_string(),
+            organized_at: 1_700_000_000_000,
+            reasoning: Some("ML homework".to_string()),
+        };
+        // Not every filesystem takes extended attributes (some tmpfs setups don't)
+        if write_to_file(&provenance).is_ok() {
+            assert_eq!(read_from_file(&path), Some(provenance.clone()));
+            let moved = tmp.join("moved.pdf");
+            fs::rename(&path, &moved).unwrap();
+            assert_eq!(read_from_file(&moved).unwrap().path, moved.to_string_lossy());
+        }
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,110 @@
+//! Quick-sort popup queue
+//!
+//! While the main window is hidden in the tray, files that need a decision are queued
+//! here and shown one at a time in a small always-on-top window. The popup only picks
+//! Accept / a different folder / Skip; the main window carries out the move, so
+//! corrections, the activity log and undo work exactly as if the card had been clicked.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+pub const WINDOW_LABEL: &str = "quick-sort";
+
+/// A classified file waiting for a decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuickSortItem {
+    pub path: String,
+    pub name: String,
+    pub suggested_folder: Option<String>, // None when no folder fits
+    pub confidence: f32,
+    pub reasoning: String,
+    pub folders: Vec<String>, // choices for "Change"
+}
+
+/// What the user picked in the popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickSortAction {
+    Accept, // move to the suggested folder
+    Change, // move to the folder they chose
+    Skip,   // leave it in the main window's list for later
+}
+
+/// A decision sent to the main window to carry out
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickSortDecision {
+    pub path: String,
+    pub action: QuickSortAction,
+    pub folder: Option<String>,
+}
+
+static QUEUE: Mutex<VecDeque<QuickSortItem>> = Mutex::new(VecDeque::new());
+
+/// Queue a file; a file already waiting is updated in place
+pub fn push(item: QuickSortItem) {
+    let mut queue = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    match queue.iter_mut().find(|queued| queued.path == item.path) {
+        Some(queued) => *queued = item,
+        None => queue.push_back(item),
+    }
+}
+
+/// The file the popup is showing
+pub fn current() -> Option<QuickSortItem> {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).front().cloned()
+}
+
+/// Take a file out of the queue (decided in the popup, or handled in the main window)
+///
+/// Returns whether it was queued.
+pub fn remove(path: &str) -> bool {
+    let mut queue = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    let before = queue.len();
+    queue.retain(|item| item.path != path);
+    queue.len() != before
+}
+
+/// Forget every waiting file (the popup was closed)
+pub fn clear() {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+pub fn len() -> usize {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, confidence: f32) -> QuickSortItem {
+        QuickSortItem {
+            path: path.to_string(),
+            name: path.trim_start_matches('/').to_string(),
+            suggested_folder: Some("/Uni/ML".to_string()),
+            confidence,
+            reasoning: String::new(),
+            folders: vec!["/Uni/ML".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_queue() {
+        push(item("/qs_a.pdf", 0.6));
+        push(item("/qs_b.pdf", 0.7));
+        // Reclassifying a queued file doesn't queue it twice
+        push(item("/qs_a.pdf", 0.8));
+        assert_eq!(current().map(|i| (i.path, i.confidence)), Some(("/qs_a.pdf".to_string(), 0.8)));
+
+        assert!(remove("/qs_a.pdf"));
+        assert!(!remove("/qs_a.pdf"));
+        assert_eq!(current().map(|i| i.path).as_deref(), Some("/qs_b.pdf"));
+        assert!(remove("/qs_b.pdf"));
+        assert_eq!(current(), None);
+        assert_eq!(len(), 0);
+    }
+}
@@ -0,0 +1,228 @@
+//! Folder size alerts
+//!
+//! A background thread adds up the size of each folder with a limit (usually the watched
+//! Downloads folder) every N minutes, and reports the ones that have grown past it so the
+//! app can nudge the user to run an organize session. A folder is reported once when it
+//! crosses its limit, and again only after it has dropped back under it.
+
+use crate::db::{Database, DbError};
+use crate::folder_stats;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+pub const QUOTA_SETTING: &str = "folder_quotas";
+
+// How often the background thread checks whether a size check is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const MINUTE_MS: i64 = 60 * 1000;
+
+/// A size limit for one folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderQuota {
+    pub folder: String,
+    pub max_bytes: u64,
+}
+
+/// Persisted quota configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaSettings {
+    pub enabled: bool,
+    pub quotas: Vec<FolderQuota>,
+    pub interval_minutes: u32, // time between size checks
+}
+
+impl Default for QuotaSettings {
+    fn default() -> Self {
+        QuotaSettings {
+            enabled: false,
+            quotas: Vec::new(),
+            interval_minutes: 60,
+        }
+    }
+}
+
+/// A folder's size against its limit
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaUsage {
+    pub folder: String,
+    pub size: u64,
+    pub max_bytes: u64,
+    pub exceeded: bool,
+    pub error: Option<String>, // the folder couldn't be read
+}
+
+/// Load the stored quotas (defaults if none have been saved, or they can't be parsed)
+pub fn load(db: &Database) -> Result<QuotaSettings, DbError> {
+    Ok(db
+        .get_setting(QUOTA_SETTING)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn save(db: &Database, settings: &QuotaSettings) -> Result<(), DbError> {
+    let json = serde_json::to_string(settings).map_err(|e| DbError::QueryFailed(e.to_string()))?;
+    db.set_setting(QUOTA_SETTING, &json)
+}
+
+/// Check quotas before they're saved
+pub fn validate(settings: &QuotaSettings) -> Result<(), String> {
+    if settings.interval_minutes == 0 {
+        return Err("Check interval must be at least a minute".to_string());
+    }
+    for quota in &settings.quotas {
+        if quota.folder.trim().is_empty() {
+            return Err("Choose a folder for every limit".to_string());
+        }
+        if quota.max_bytes == 0 {
+            return Err(format!("The limit for {} must be more than 0", quota.folder));
+        }
+    }
+    Ok(())
+}
+
+/// Measure every folder with a limit
+pub fn check(settings: &QuotaSettings) -> Vec<QuotaUsage> {
+    settings
+        .quotas
+        .iter()
+        .map(|quota| {
+            let size = folder_stats::collect(Path::new(&quota.folder)).map(|stats| stats.total_size);
+            QuotaUsage {
+                folder: quota.folder.clone(),
+                size: *size.as_ref().unwrap_or(&0),
+                max_bytes: quota.max_bytes,
+                exceeded: size.as_ref().is_ok_and(|size| *size > quota.max_bytes),
+                error: size.err().map(|e| e.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// The folders that have just gone over their limit, updating `warned` (folders already
+/// reported and still over)
+pub fn newly_exceeded(usage: Vec<QuotaUsage>, warned: &mut HashSet<String>) -> Vec<QuotaUsage> {
+    let mut crossed = Vec::new();
+    for usage in usage {
+        if usage.error.is_some() {
+            continue;
+        }
+        if !usage.exceeded {
+            warned.remove(&usage.folder);
+        } else if warned.insert(usage.folder.clone()) {
+            crossed.push(usage);
+        }
+    }
+    crossed
+}
+
+/// Human-readable size for notifications, e.g. "12.3 GB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Check folder sizes every `interval_minutes` on a background thread and call `warn` for
+/// each folder that has just gone over its limit
+pub fn spawn_thread(db: &'static Database, warn: impl Fn(QuotaUsage) + Send + 'static) {
+    let spawned = std::thread::Builder::new()
+        .name("quota-monitor".to_string())
+        .spawn(move || {
+            let mut warned = HashSet::new();
+            let mut last_check_at: Option<i64> = None;
+            loop {
+                std::thread::sleep(CHECK_INTERVAL);
+
+                let settings = match load(db) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        eprintln!("[QUOTA] Failed to load quotas: {}", e);
+                        continue;
+                    }
+                };
+                let now = crate::current_timestamp_ms();
+                if !settings.enabled
+                    || last_check_at.is_some_and(|last| now - last < settings.interval_minutes as i64 * MINUTE_MS)
+                {
+                    continue;
+                }
+                last_check_at = Some(now);
+
+                for usage in newly_exceeded(check(&settings), &mut warned) {
+                    println!("[QUOTA] {} is over its limit ({} bytes)", usage.folder, usage.size);
+                    warn(usage);
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("[QUOTA] Failed to start quota thread: {}", e);
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_and_warn_once() {
+        let tmp = std::env::temp_dir().join("fileorg_test_quota");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(tmp.join("Downloads")).unwrap();
+        std::fs::write(tmp.join("Downloads").join("big.zip"), vec![0u8; 2048]).unwrap();
+        let downloads = tmp.join("Downloads").to_string_lossy().to_string();
+
+        let settings = QuotaSettings {
+            enabled: true,
+            quotas: vec![
+                FolderQuota { folder: downloads.clone(), max_bytes: 1024 },
+                FolderQuota { folder: tmp.join("missing").to_string_lossy().to_string(), max_bytes: 1 },
+            ],
+            ..Default::default()
+        };
+        let usage = check(&settings);
+        assert_eq!(usage[0].size, 2048);
+        assert!(usage[0].exceeded);
+        assert!(usage[1].error.is_some() && !usage[1].exceeded);
+
+        // Reported once while over, and again after dropping under and crossing back
+        let mut warned = HashSet::new();
+        assert_eq!(newly_exceeded(check(&settings), &mut warned).len(), 1);
+        assert!(newly_exceeded(check(&settings), &mut warned).is_empty());
+        std::fs::remove_file(tmp.join("Downloads").join("big.zip")).unwrap();
+        assert!(newly_exceeded(check(&settings), &mut warned).is_empty());
+        std::fs::write(tmp.join("Downloads").join("big.zip"), vec![0u8; 2048]).unwrap();
+        assert_eq!(newly_exceeded(check(&settings), &mut warned).len(), 1);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_validate_and_format() {
+        let mut settings = QuotaSettings {
+            quotas: vec![FolderQuota { folder: "/Downloads".to_string(), max_bytes: 10 * 1024 * 1024 * 1024 }],
+            ..Default::default()
+        };
+        assert!(validate(&settings).is_ok());
+        settings.quotas[0].max_bytes = 0;
+        assert!(validate(&settings).is_err());
+
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(10 * 1024 * 1024 * 1024), "10.0 GB");
+    }
+}
@@ -0,0 +1,128 @@
+//! CSV and Markdown tables for reports saved to a file
+//!
+//! Reports are built as a plain `Table` and rendered in the format the user picked by the
+//! file's extension: CSV opens in a spreadsheet, Markdown reads well in a notes app or on
+//! GitHub. Cells are escaped for each so filenames with commas, quotes or pipes survive.
+
+use std::path::Path;
+
+/// The format a report is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    /// The format named by `path`'s extension (.csv, .md or .markdown)
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Format::Csv),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Rows of cells under a header row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// The table as a whole file; Markdown gets `title` as its heading, CSV has no room for one
+    pub fn render(&self, format: Format, title: &str) -> String {
+        match format {
+            Format::Csv => self.to_csv(),
+            Format::Markdown => format!("# {}\n\n{}", title, self.to_markdown()),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut out = csv_line(self.headers.iter().copied());
+        for row in &self.rows {
+            out.push_str(&csv_line(row.iter().map(String::as_str)));
+        }
+        out
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = markdown_line(self.headers.iter().copied());
+        out.push_str(&markdown_line(self.headers.iter().map(|_| "---")));
+        for row in &self.rows {
+            out.push_str(&markdown_line(row.iter().map(String::as_str)));
+        }
+        out
+    }
+}
+
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells: Vec<String> = cells.map(csv_cell).collect();
+    format!("{}\r\n", cells.join(","))
+}
+
+/// Quote a cell when it needs it (RFC 4180). Text a spreadsheet would run as a formula
+/// ("=HYPERLINK(...)" as a filename) is prefixed with an apostrophe so it stays text.
+fn csv_cell(cell: &str) -> String {
+    let cell = if cell.starts_with(['=', '+', '-', '@']) && cell.parse::<f64>().is_err() {
+        format!("'{}", cell)
+    } else {
+        cell.to_string()
+    };
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
+
+fn markdown_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells: Vec<String> = cells.map(|cell| cell.replace('|', "\\|").replace(['\r', '\n'], " ")).collect();
+    format!("| {} |\n", cells.join(" | "))
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        Table {
+            headers: vec!["File", "Reason"],
+            rows: vec![
+                vec!["ps1, final.pdf".to_string(), "says \"PS1\"".to_string()],
+                vec!["=cmd.pdf".to_string(), "a | b\nc".to_string()],
+                vec!["-0.5".to_string(), String::new()],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        assert_eq!(
+            table().render(Format::Csv, "ignored"),
+            "File,Reason\r\n\"ps1, final.pdf\",\"says \"\"PS1\"\"\"\r\n'=cmd.pdf,\"a | b\nc\"\r\n-0.5,\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        assert_eq!(
+            table().render(Format::Markdown, "Plan"),
+            "# Plan\n\n| File | Reason |\n| --- | --- |\n| ps1, final.pdf | says \"PS1\" |\n| =cmd.pdf | a \\| b c |\n| -0.5 |  |\n"
+        );
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("/tmp/plan.CSV")), Some(Format::Csv));
+        assert_eq!(Format::from_path(Path::new("plan.markdown")), Some(Format::Markdown));
+        assert_eq!(Format::from_path(Path::new("plan.txt")), None);
+        assert_eq!(Format::from_path(Path::new("plan")), None);
+    }
+}
@@ -0,0 +1,193 @@
+//! Allowed root folders for file operations
+//!
+//! Moves, renames, trashing, previews, new folders, exports and folder scans only touch paths
+//! inside these roots (the watched folders and destination trees), so a buggy or compromised
+//! frontend can't point them at arbitrary files. The list is set by the frontend from its configuration and stored in
+//! the settings table; until one has been saved, the folders the app's own settings name are
+//! used (see `load_or_configured`). With no roots at all, nothing is allowed. Files dragged onto
+//! the window are allowed too, for the rest of the session, since the OS reported the drop.
+
+use crate::db::{Database, DbError};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+pub const ALLOWED_ROOTS_SETTING: &str = "allowed_roots";
+
+static ROOTS: LazyLock<RwLock<Vec<PathBuf>>> = LazyLock::new(|| RwLock::new(initial_roots()));
+static DROPPED: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+/// Load the stored roots (empty if none have been saved, or they can't be parsed)
+pub fn load(db: &Database) -> Result<Vec<String>, DbError> {
+    Ok(db
+        .get_setting(ALLOWED_ROOTS_SETTING)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn save(db: &Database, roots: &[String]) -> Result<(), DbError> {
+    let json = serde_json::to_string(roots).map_err(|e| DbError::QueryFailed(e.to_string()))?;
+    db.set_setting(ALLOWED_ROOTS_SETTING, &json)
+}
+
+/// The stored roots, or if none have been saved, the folders named in the app's settings:
+/// each watch profile's watched folder and destinations, and the Needs Review and file-type
+/// folders. Any that wouldn't pass `validate` are left out.
+pub fn load_or_configured(db: &Database) -> Result<Vec<String>, DbError> {
+    let saved = load(db)?;
+    if !saved.is_empty() {
+        return Ok(saved);
+    }
+    let mut folders = Vec::new();
+    for profile in db.get_watch_profiles()? {
+        folders.push(profile.watch_path);
+        folders.extend(profile.folders);
+    }
+    for key in [crate::QUARANTINE_FOLDER_SETTING, crate::BUCKET_FOLDER_SETTING] {
+        folders.extend(db.get_setting(key)?);
+    }
+    Ok(folders.into_iter().filter(|folder| check(folder.trim()).is_ok()).collect())
+}
+
+/// Check roots before they're saved: trimmed, deduplicated, no blanks, no whole drives and
+/// nothing holding the whole home folder. At least one is needed, since no roots allow nothing.
+pub fn validate(roots: Vec<String>) -> Result<Vec<String>, String> {
+    let mut valid: Vec<String> = Vec::new();
+    for root in roots {
+        let root = root.trim().to_string();
+        if root.is_empty() || valid.contains(&root) {
+            continue;
+        }
+        check(&root)?;
+        valid.push(root);
+    }
+    if valid.is_empty() {
+        return Err("Choose at least one folder".to_string());
+    }
+    Ok(valid)
+}
+
+fn check(root: &str) -> Result<(), String> {
+    if root.is_empty() || root.contains("..") {
+        return Err(format!("\"{}\" isn't a valid folder", root));
+    }
+    let resolved = resolve(Path::new(root));
+    if resolved.parent().is_none() {
+        return Err(format!("{} is a whole drive; choose a folder on it", root));
+    }
+    if dirs::home_dir().is_some_and(|home| resolve(&home).starts_with(&resolved)) {
+        return Err(format!("{} holds your whole home folder; choose the folders inside it you organise", root));
+    }
+    Ok(())
+}
+
+/// Restrict file operations to `roots` from now on (no roots: only dropped files)
+pub fn set(roots: &[String]) {
+    let resolved = roots.iter().map(|root| resolve(Path::new(root))).collect();
+    *ROOTS.write().unwrap_or_else(|e| e.into_inner()) = resolved;
+}
+
+/// Allow files (or folders) the user dragged onto the window
+pub fn allow_dropped(paths: &[PathBuf]) {
+    let mut dropped = DROPPED.write().unwrap_or_else(|e| e.into_inner());
+    dropped.extend(paths.iter().map(|path| resolve(path)));
+}
+
+/// Whether file operations may touch `path`
+pub fn is_allowed(path: &Path) -> bool {
+    let roots = ROOTS.read().unwrap_or_else(|e| e.into_inner());
+    is_allowed_in(path, &roots, &DROPPED.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn is_allowed_in(path: &Path, roots: &[PathBuf], dropped: &[PathBuf]) -> bool {
+    is_within(path, roots) || is_within(path, dropped)
+}
+
+/// Nothing is allowed until startup loads the roots
+#[cfg(not(test))]
+fn initial_roots() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// Tests only work in the temp folder
+#[cfg(test)]
+fn initial_roots() -> Vec<PathBuf> {
+    vec![resolve(&std::env::temp_dir())]
+}
+
+fn is_within(path: &Path, roots: &[PathBuf]) -> bool {
+    let path = resolve(path);
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// The real location of a path that may not exist yet: its nearest existing ancestor with
+/// symlinks resolved, plus the rest of the path
+fn resolve(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_within() {
+        let tmp = std::env::temp_dir().join("fileorg_test_roots");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(tmp.join("Uni/ML")).unwrap();
+        std::fs::create_dir_all(tmp.join("Uni2")).unwrap();
+        std::fs::create_dir_all(tmp.join("Downloads")).unwrap();
+        let roots = vec![resolve(&tmp.join("Uni")), resolve(&tmp.join("Downloads"))];
+
+        assert!(is_within(&tmp.join("Uni/ML"), &roots));
+        assert!(is_within(&tmp.join("Downloads/new.pdf"), &roots), "files that don't exist yet");
+        assert!(is_within(&tmp.join("Uni/New Course/notes.pdf"), &roots));
+        assert!(!is_within(&tmp.join("Uni2"), &roots), "a sibling sharing the prefix isn't inside");
+        assert!(!is_within(&tmp, &roots));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_no_roots_allow_only_dropped_files() {
+        let tmp = std::env::temp_dir().join("fileorg_test_roots_empty");
+        let dropped = vec![resolve(&tmp.join("dropped.pdf"))];
+
+        assert!(!is_allowed_in(&tmp.join("notes.pdf"), &[], &[]));
+        assert!(!is_allowed_in(&tmp.join("notes.pdf"), &[], &dropped));
+        assert!(is_allowed_in(&tmp.join("dropped.pdf"), &[], &dropped));
+        assert!(is_allowed_in(&tmp.join("notes.pdf"), &[resolve(&tmp)], &[]));
+    }
+
+    #[test]
+    fn test_validate_roots() {
+        let tmp = std::env::temp_dir().join("fileorg_test_roots_validate");
+        let folder = tmp.to_string_lossy().to_string();
+        let roots = validate(vec![format!(" {} ", folder), folder.clone(), String::new()]).unwrap();
+        assert_eq!(roots, vec![folder.clone()]);
+
+        assert!(validate(vec!["/".to_string()]).is_err());
+        assert!(validate(vec![format!("{}/../..", folder)]).is_err());
+        assert!(validate(vec![]).is_err(), "an empty list would allow nothing");
+        assert!(validate(vec!["  ".to_string()]).is_err());
+        if let Some(home) = dirs::home_dir().filter(|home| home.parent().is_some()) {
+            assert!(validate(vec![home.to_string_lossy().to_string()]).is_err());
+            assert!(validate(vec![home.join("Uni").to_string_lossy().to_string()]).is_ok());
+        }
+    }
+}
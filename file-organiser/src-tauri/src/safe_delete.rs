@@ -0,0 +1,424 @@
+//! App-managed trash for "safe delete"
+//!
+//! The system recycle bin gets emptied without much thought, so deletes can instead
+//! go to a `.file-organiser-trash` folder in the app data directory. Each deleted file
+//! keeps its original name in its own subfolder, is recorded in the `deleted_files`
+//! table, and is purged once its retention period has passed.
+//!
+//! Files the assistant deleted can be listed and restored from either place: the app
+//! trash, and the system recycle bin where the OS lets us read it (Windows and Linux;
+//! macOS doesn't). Recycle bin items are only listed if the journal shows the assistant
+//! trashed them.
+
+use crate::db::{Database, DbError, DeletedFile};
+use crate::journal::FileConflict;
+use crate::{checksum, CommandError};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const TRASH_DIR_NAME: &str = ".file-organiser-trash";
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// Expired files are purged at startup and then on this interval
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+static TRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+static ENTRY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Set the app trash location (called once at startup)
+pub fn init(dir: PathBuf) {
+    let _ = TRASH_DIR.set(dir);
+}
+
+/// The app trash folder, if the app data directory was available at startup
+pub fn trash_dir() -> Result<&'static Path, CommandError> {
+    TRASH_DIR
+        .get()
+        .map(PathBuf::as_path)
+        .ok_or_else(|| CommandError::IoError("App trash folder is not available".to_string()))
+}
+
+/// When a file deleted at `deleted_at` should be purged
+pub fn expires_at(deleted_at: i64, retention_days: u32) -> i64 {
+    deleted_at + retention_days as i64 * DAY_MS
+}
+
+/// Move a file into the trash folder, returning the record to store
+pub fn move_to_trash(
+    path: &Path,
+    trash_dir: &Path,
+    deleted_at: i64,
+    retention_days: u32,
+) -> Result<DeletedFile, CommandError> {
+    let filename = path
+        .file_name()
+        .ok_or_else(|| CommandError::InvalidPath("Invalid file path".to_string()))?;
+    let size = fs::metadata(path)?.len() as i64;
+
+    // One subfolder per delete so same-named files never clash
+    let entry_dir = trash_dir.join(format!(
+        "{}-{}",
+        deleted_at,
+        ENTRY_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&entry_dir)?;
+    let trash_path = entry_dir.join(filename);
+
+    if let Err(e) = checksum::move_verified(path, &trash_path) {
+        let _ = fs::remove_dir(&entry_dir);
+        return Err(e);
+    }
+
+    Ok(DeletedFile {
+        id: None,
+        original_path: path.to_string_lossy().to_string(),
+        trash_path: trash_path.to_string_lossy().to_string(),
+        size,
+        deleted_at,
+        expires_at: expires_at(deleted_at, retention_days),
+    })
+}
+
+/// Permanently remove a trashed file and its per-delete subfolder
+fn remove_from_trash(file: &DeletedFile) {
+    let path = Path::new(&file.trash_path);
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("[TRASH] Failed to purge {}: {}", file.trash_path, e);
+            return;
+        }
+    }
+    if let Some(entry_dir) = path.parent() {
+        let _ = fs::remove_dir(entry_dir);
+    }
+}
+
+/// Purge every file whose retention ended before `now`, returning how many were removed
+pub fn purge_expired(db: &Database, now: i64) -> Result<usize, DbError> {
+    let expired = db.get_deleted_files(Some(now))?;
+    for file in &expired {
+        remove_from_trash(file);
+        if let Some(id) = file.id {
+            db.remove_deleted_file(id)?;
+        }
+    }
+    Ok(expired.len())
+}
+
+/// Drop records for files that are no longer in the trash (restored by undo, or removed by hand)
+pub fn prune_missing(db: &Database) -> Result<Vec<DeletedFile>, DbError> {
+    let mut present = Vec::new();
+    for file in db.get_deleted_files(None)? {
+        if Path::new(&file.trash_path).is_file() {
+            present.push(file);
+        } else if let Some(id) = file.id {
+            db.remove_deleted_file(id)?;
+        }
+    }
+    Ok(present)
+}
+
+/// Where a deleted file is
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashLocation {
+    AppTrash,
+    RecycleBin,
+}
+
+/// A file the assistant deleted that can still be restored
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashEntry {
+    pub id: String, // "app:<row id>" or "bin:<recycle bin id>"
+    pub name: String,
+    pub original_path: String,
+    pub deleted_at: i64, // Unix timestamp ms
+    pub size: Option<u64>,
+    pub location: TrashLocation,
+}
+
+/// Outcome of restoring several trash entries
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreReport {
+    pub restored: Vec<String>, // original paths the files are back at
+    pub failed: Vec<FileConflict>,
+}
+
+const APP_ID_PREFIX: &str = "app:";
+const BIN_ID_PREFIX: &str = "bin:";
+
+/// Files the assistant deleted, in the app trash and the recycle bin, newest first
+pub fn list(db: &Database) -> Result<Vec<TrashEntry>, CommandError> {
+    let mut entries: Vec<TrashEntry> = prune_missing(db)?
+        .into_iter()
+        .filter_map(|file| {
+            Some(TrashEntry {
+                id: format!("{}{}", APP_ID_PREFIX, file.id?),
+                name: Path::new(&file.original_path).file_name()?.to_string_lossy().to_string(),
+                original_path: file.original_path,
+                deleted_at: file.deleted_at,
+                size: Some(file.size as u64),
+                location: TrashLocation::AppTrash,
+            })
+        })
+        .collect();
+    entries.extend(recycle_bin::list(db)?);
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+    Ok(entries)
+}
+
+/// Put deleted files back where they were; a file whose original path is taken again
+/// is left in the trash and reported as failed
+pub fn restore(db: &Database, ids: &[String]) -> Result<RestoreReport, CommandError> {
+    let mut report = RestoreReport::default();
+    let app_files = db.get_deleted_files(None)?;
+    let mut bin_ids = Vec::new();
+
+    for id in ids {
+        if let Some(bin_id) = id.strip_prefix(BIN_ID_PREFIX) {
+            bin_ids.push(bin_id.to_string());
+            continue;
+        }
+        let file = id
+            .strip_prefix(APP_ID_PREFIX)
+            .and_then(|row| row.parse::<i64>().ok())
+            .and_then(|row| app_files.iter().find(|file| file.id == Some(row)));
+        let Some(file) = file else {
+            report.failed.push(FileConflict { path: id.clone(), reason: "No longer in the trash".to_string() });
+            continue;
+        };
+        match restore_from_app_trash(file) {
+            Ok(()) => {
+                if let Some(row) = file.id {
+                    db.remove_deleted_file(row)?;
+                }
+                report.restored.push(file.original_path.clone());
+            }
+            Err(e) => report.failed.push(FileConflict { path: file.original_path.clone(), reason: e.to_string() }),
+        }
+    }
+
+    if !bin_ids.is_empty() {
+        recycle_bin::restore(&bin_ids, &mut report)?;
+    }
+    Ok(report)
+}
+
+/// Move a file from the app trash back to its original path
+fn restore_from_app_trash(file: &DeletedFile) -> Result<(), CommandError> {
+    let original = Path::new(&file.original_path);
+    if original.exists() {
+        return Err(CommandError::DuplicateExists(file.original_path.clone()));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    checksum::move_verified(Path::new(&file.trash_path), original)?;
+    if let Some(entry_dir) = Path::new(&file.trash_path).parent() {
+        let _ = fs::remove_dir(entry_dir);
+    }
+    Ok(())
+}
+
+/// The system recycle bin, on platforms where its contents can be read
+#[cfg(any(windows, all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))))]
+mod recycle_bin {
+    use super::*;
+    use trash::os_limited;
+
+    fn to_command_error(e: trash::Error) -> CommandError {
+        CommandError::IoError(format!("Failed to read the recycle bin: {}", e))
+    }
+
+    /// Recycle bin items the journal shows the assistant deleted
+    pub fn list(db: &Database) -> Result<Vec<TrashEntry>, CommandError> {
+        let trashed = db.trashed_paths()?;
+        if trashed.is_empty() {
+            return Ok(Vec::new());
+        }
+        let items = os_limited::list().map_err(to_command_error)?;
+        Ok(items
+            .into_iter()
+            .filter_map(|item| {
+                let original_path = item.original_path().to_string_lossy().to_string();
+                if !trashed.contains(&original_path) {
+                    return None;
+                }
+                let size = os_limited::metadata(&item).ok().and_then(|meta| meta.size.size());
+                Some(TrashEntry {
+                    id: format!("{}{}", BIN_ID_PREFIX, item.id.to_string_lossy()),
+                    name: item.name,
+                    original_path,
+                    deleted_at: item.time_deleted * 1000,
+                    size,
+                    location: TrashLocation::RecycleBin,
+                })
+            })
+            .collect())
+    }
+
+    /// Restore the recycle bin items with these ids, one at a time so each failure is
+    /// reported against its own file
+    pub fn restore(ids: &[String], report: &mut RestoreReport) -> Result<(), CommandError> {
+        let items = os_limited::list().map_err(to_command_error)?;
+        for id in ids {
+            let Some(item) = items.iter().find(|item| item.id.to_string_lossy() == id.as_str()) else {
+                report.failed.push(FileConflict { path: id.clone(), reason: "No longer in the recycle bin".to_string() });
+                continue;
+            };
+            let original_path = item.original_path().to_string_lossy().to_string();
+            match os_limited::restore_all([item.clone()]) {
+                Ok(()) => report.restored.push(original_path),
+                Err(trash::Error::RestoreCollision { .. }) => report.failed.push(FileConflict {
+                    path: original_path,
+                    reason: "A file already exists at its original location".to_string(),
+                }),
+                Err(e) => report.failed.push(FileConflict { path: original_path, reason: e.to_string() }),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// macOS (and mobile) don't let apps read the recycle bin; only the app trash is listed
+#[cfg(not(any(windows, all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))))]
+mod recycle_bin {
+    use super::*;
+
+    pub fn list(_db: &Database) -> Result<Vec<TrashEntry>, CommandError> {
+        Ok(Vec::new())
+    }
+
+    pub fn restore(ids: &[String], report: &mut RestoreReport) -> Result<(), CommandError> {
+        for id in ids {
+            report.failed.push(FileConflict {
+                path: id.clone(),
+                reason: "The recycle bin can't be read on this platform".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Purge expired files now and then every PURGE_INTERVAL on a background thread
+pub fn spawn_purge_thread(db: &'static Database) {
+    let spawned = std::thread::Builder::new()
+        .name("trash-purge".to_string())
+        .spawn(move || loop {
+            match purge_expired(db, crate::current_timestamp_ms()) {
+                Ok(0) => {}
+                Ok(n) => println!("[TRASH] Purged {} expired file(s)", n),
+                Err(e) => eprintln!("[TRASH] Purge failed: {}", e),
+            }
+            std::thread::sleep(PURGE_INTERVAL);
+        });
+    if let Err(e) = spawned {
+        eprintln!("[TRASH] Failed to start purge thread: {}", e);
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(dir: &Path) -> Database {
+        Database::new(dir.join("test.db")).unwrap()
+    }
+
+    #[test]
+    fn test_move_to_trash_and_purge() {
+        let tmp = std::env::temp_dir().join("fileorg_test_safe_delete");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let trash = tmp.join(TRASH_DIR_NAME);
+        let db = temp_db(&tmp);
+
+        // Two files with the same name don't collide in the trash
+        let first = tmp.join("a").join("notes.pdf");
+        let second = tmp.join("b").join("notes.pdf");
+        for (path, content) in [(&first, "first"), (&second, "second")] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let old = move_to_trash(&first, &trash, 1_000, 1).unwrap();
+        let recent = move_to_trash(&second, &trash, 2 * DAY_MS, 30).unwrap();
+        assert!(!first.exists());
+        assert_ne!(old.trash_path, recent.trash_path);
+        assert_eq!(fs::read_to_string(&recent.trash_path).unwrap(), "second");
+        assert_eq!(old.expires_at, 1_000 + DAY_MS);
+        db.add_deleted_file(old.clone()).unwrap();
+        db.add_deleted_file(recent.clone()).unwrap();
+
+        // Only the file past its retention period is purged
+        assert_eq!(purge_expired(&db, 3 * DAY_MS).unwrap(), 1);
+        assert!(!Path::new(&old.trash_path).exists());
+        assert!(!Path::new(&old.trash_path).parent().unwrap().exists());
+        assert!(Path::new(&recent.trash_path).exists());
+        assert_eq!(db.get_deleted_files(None).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_prune_missing() {
+        let tmp = std::env::temp_dir().join("fileorg_test_safe_delete_prune");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let db = temp_db(&tmp);
+
+        let file = tmp.join("lab.txt");
+        fs::write(&file, "lab").unwrap();
+        let deleted = move_to_trash(&file, &tmp.join(TRASH_DIR_NAME), 1_000, 30).unwrap();
+        db.add_deleted_file(deleted.clone()).unwrap();
+
+        // Simulate an undo that moved the file back out of the trash
+        fs::rename(&deleted.trash_path, &file).unwrap();
+        assert!(prune_missing(&db).unwrap().is_empty());
+        assert!(db.get_deleted_files(None).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_list_and_restore_app_trash() {
+        let tmp = std::env::temp_dir().join("fileorg_test_safe_delete_restore");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        let db = temp_db(&tmp);
+        let trash = tmp.join(TRASH_DIR_NAME);
+
+        let lecture = tmp.join("Downloads").join("lecture5.pdf");
+        let receipt = tmp.join("Downloads").join("receipt.pdf");
+        for path in [&lecture, &receipt] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "content").unwrap();
+            db.add_deleted_file(move_to_trash(path, &trash, 1_000, 30).unwrap()).unwrap();
+        }
+
+        let entries = list(&db).unwrap();
+        let app_entries: Vec<_> = entries.iter().filter(|e| e.location == TrashLocation::AppTrash).collect();
+        assert_eq!(app_entries.len(), 2);
+        let lecture_entry = app_entries.iter().find(|e| e.name == "lecture5.pdf").unwrap();
+        let receipt_entry = app_entries.iter().find(|e| e.name == "receipt.pdf").unwrap();
+
+        // Something new took the receipt's name in the meantime: it stays in the trash
+        fs::write(&receipt, "new receipt").unwrap();
+        let report = restore(&db, &[lecture_entry.id.clone(), receipt_entry.id.clone(), "app:999".to_string()]).unwrap();
+        assert_eq!(report.restored, vec![lecture.to_string_lossy().to_string()]);
+        assert_eq!(report.failed.len(), 2);
+        assert_eq!(fs::read_to_string(&lecture).unwrap(), "content");
+        assert_eq!(fs::read_to_string(&receipt).unwrap(), "new receipt");
+        assert_eq!(db.get_deleted_files(None).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,342 @@
+//! Directory listings for the file list and folder pickers
+//!
+//! Entries are stat'ed in parallel with rayon and subfolders walked in parallel, so an
+//! archive folder of tens of thousands of files lists in well under a second. Listings
+//! stop at a limit (newest files are kept), and folder walks can report each directory's
+//! subfolders as they're found so the frontend can fill a picker before the walk ends.
+//! File listings can be streamed the same way, in batches followed by a completion event.
+//! Folder pickers can instead expand one level at a time with `list_children`.
+
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+
+// Entries beyond this are dropped unless the caller asks for more
+pub const DEFAULT_LIMIT: usize = 50_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: u64, // Unix timestamp in seconds
+}
+
+/// Part of a listing, sent as an event while the walk is still running
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanBatch<T> {
+    pub root: String, // the folder the listing was asked for
+    pub entries: Vec<T>,
+}
+
+// Files per streamed batch: small enough to render at once, large enough to keep events few
+pub const STREAM_BATCH_SIZE: usize = 500;
+
+/// Sent once a streamed listing has finished
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanComplete {
+    pub root: String,
+    pub total: usize,    // files sent in batches
+    pub truncated: bool, // the limit was reached before the folder was fully listed
+}
+
+/// The files directly inside `dir` (hidden files skipped), newest first
+pub fn list_files(dir: &Path, limit: usize) -> Result<Vec<FileEntry>, String> {
+    let mut files: Vec<FileEntry> = visible_entries(dir)?.par_iter().filter_map(file_entry).collect();
+    files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.name.cmp(&b.name)));
+    files.truncate(limit);
+    Ok(files)
+}
+
+/// Like `list_files`, but hands the files to `on_batch` in directory order as they're
+/// stat'ed, up to `limit` of them. Returns how many were sent and whether more were left.
+pub fn stream_files(
+    dir: &Path,
+    limit: usize,
+    batch_size: usize,
+    on_batch: &dyn Fn(Vec<FileEntry>),
+) -> Result<(usize, bool), String> {
+    let entries = visible_entries(dir)?;
+    let batch_size = batch_size.max(1);
+    let mut sent = 0;
+    for (i, chunk) in entries.chunks(batch_size).enumerate() {
+        let mut batch: Vec<FileEntry> = chunk.par_iter().filter_map(file_entry).collect();
+        let found = batch.len();
+        batch.truncate(limit - sent);
+        let cut_short = batch.len() < found || (i + 1) * batch_size < entries.len();
+        sent += batch.len();
+        if !batch.is_empty() {
+            on_batch(batch);
+        }
+        if sent == limit {
+            return Ok((sent, cut_short));
+        }
+    }
+    Ok((sent, false))
+}
+
+fn visible_entries(dir: &Path) -> Result<Vec<fs::DirEntry>, String> {
+    Ok(fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory: {}", e))?
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .collect())
+}
+
+fn file_entry(entry: &fs::DirEntry) -> Option<FileEntry> {
+    let path = entry.path();
+    // Follows symlinks, so a link to a file is listed like the file
+    let meta = fs::metadata(&path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some(FileEntry {
+        name: entry.file_name().to_str()?.to_string(),
+        path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        modified,
+    })
+}
+
+// Child counts stop here, so a huge subfolder doesn't slow down listing its parent
+pub const CHILD_COUNT_CAP: usize = 1000;
+
+/// A subfolder as shown in a folder picker, with hints about what's inside it
+#[derive(Debug, Clone, Serialize)]
+pub struct DirChild {
+    pub name: String,
+    pub path: String,
+    pub subfolders: usize, // capped at CHILD_COUNT_CAP
+    pub files: usize,      // capped at CHILD_COUNT_CAP
+}
+
+/// The visible subfolders directly inside `dir`, sorted by name, each with counts of its
+/// own subfolders and files (so the picker knows whether to show an expand arrow)
+pub fn list_children(dir: &Path) -> Result<Vec<DirChild>, String> {
+    let subdirs: Vec<fs::DirEntry> = visible_entries(dir)?
+        .into_iter()
+        .filter(|entry| entry.path().is_dir())
+        .collect();
+
+    let mut children: Vec<DirChild> = subdirs
+        .par_iter()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let (mut subfolders, mut files) = (0, 0);
+            if let Ok(entries) = fs::read_dir(&path) {
+                for child in entries.flatten().take(CHILD_COUNT_CAP * 2) {
+                    if child.file_name().to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                    if child.path().is_dir() {
+                        subfolders += 1;
+                    } else {
+                        files += 1;
+                    }
+                }
+            }
+            Some(DirChild {
+                name: entry.file_name().to_str()?.to_string(),
+                path: path.to_string_lossy().to_string(),
+                subfolders: subfolders.min(CHILD_COUNT_CAP),
+                files: files.min(CHILD_COUNT_CAP),
+            })
+        })
+        .collect();
+    children.sort_by_key(|child| child.name.to_lowercase());
+    Ok(children)
+}
+
+/// The subfolders of `dir`, relative to it, sorted
+///
+/// With `recursive`, nested folders are included too (as "Uni/ML"). `on_batch` gets each
+/// directory's subfolders as soon as they're read, in no particular order. Folders `skip`
+/// returns true for (a watch profile's excluded ones) are neither listed nor walked.
+pub fn list_folders(
+    dir: &Path,
+    recursive: bool,
+    limit: usize,
+    on_batch: Option<&(dyn Fn(Vec<String>) + Sync)>,
+    skip: &(dyn Fn(&Path) -> bool + Sync),
+) -> Result<Vec<String>, String> {
+    let found = AtomicUsize::new(0);
+    let walk = FolderWalk { base: dir, recursive, limit, found: &found, on_batch, skip };
+    let mut folders = walk.walk(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+    folders.sort();
+    folders.truncate(limit);
+    Ok(folders)
+}
+
+struct FolderWalk<'a> {
+    base: &'a Path,
+    recursive: bool,
+    limit: usize,
+    found: &'a AtomicUsize,
+    on_batch: Option<&'a (dyn Fn(Vec<String>) + Sync)>,
+    skip: &'a (dyn Fn(&Path) -> bool + Sync),
+}
+
+impl FolderWalk<'_> {
+    /// Subfolders of `dir`, recursing in parallel; only `dir` itself failing to read is an error
+    fn walk(&self, dir: &Path) -> std::io::Result<Vec<String>> {
+        let mut folders = Vec::new();
+        let mut subdirs = Vec::new();
+        for entry in fs::read_dir(dir)?.flatten() {
+            let path = entry.path();
+            if !path.is_dir() || (self.skip)(&path) {
+                continue;
+            }
+            if let Some(name) = path.strip_prefix(self.base).ok().and_then(|r| r.to_str()) {
+                folders.push(name.to_string());
+            }
+            // Symlinked folders are listed but not followed, to avoid cycles
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                subdirs.push(path);
+            }
+        }
+
+        let total = self.found.fetch_add(folders.len(), Ordering::Relaxed) + folders.len();
+        if let Some(on_batch) = self.on_batch {
+            if !folders.is_empty() {
+                on_batch(folders.clone());
+            }
+        }
+        if self.recursive && total < self.limit {
+            let nested: Vec<String> = subdirs
+                .par_iter()
+                .flat_map(|sub| self.walk(sub).unwrap_or_default())
+                .collect();
+            folders.extend(nested);
+        }
+        Ok(folders)
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_list_files_limit() {
+        let tmp = std::env::temp_dir().join("fileorg_test_scan_list_files");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("sub")).unwrap();
+        for i in 0..20 {
+            fs::write(tmp.join(format!("file{:02}.txt", i)), "x").unwrap();
+        }
+
+        assert_eq!(list_files(&tmp, DEFAULT_LIMIT).unwrap().len(), 20, "folders aren't listed");
+        assert_eq!(list_files(&tmp, 5).unwrap().len(), 5);
+
+        let batches = std::cell::RefCell::new(Vec::new());
+        let on_batch = |files: Vec<FileEntry>| batches.borrow_mut().push(files.len());
+        assert_eq!(stream_files(&tmp, DEFAULT_LIMIT, 8, &on_batch).unwrap(), (20, false));
+        assert_eq!(batches.borrow().iter().sum::<usize>(), 20, "the subfolder isn't sent");
+        assert_eq!(batches.borrow().len(), 3);
+        assert_eq!(stream_files(&tmp, 10, 8, &|_| {}).unwrap(), (10, true));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_stream_files_batches_and_limit() {
+        let tmp = std::env::temp_dir().join("fileorg_test_scan_stream_files");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        for i in 0..12 {
+            fs::write(tmp.join(format!("file{:02}.txt", i)), "x").unwrap();
+        }
+        fs::write(tmp.join(".DS_Store"), "x").unwrap();
+
+        // Every visible file is sent exactly once, in batches no bigger than asked for
+        let batches = Mutex::new(Vec::new());
+        let on_batch = |files: Vec<FileEntry>| batches.lock().unwrap().push(files);
+        assert_eq!(stream_files(&tmp, DEFAULT_LIMIT, 5, &on_batch).unwrap(), (12, false));
+        let batches = batches.into_inner().unwrap();
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![5, 5, 2]);
+        let mut names: Vec<String> = batches.concat().into_iter().map(|f| f.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 12);
+        assert!(!names.contains(&".DS_Store".to_string()), "hidden files aren't sent");
+
+        // A limit that's exactly the folder's size isn't reported as cut short
+        assert_eq!(stream_files(&tmp, 12, 5, &|_| {}).unwrap(), (12, false));
+        assert_eq!(stream_files(&tmp, 11, 5, &|_| {}).unwrap(), (11, true));
+        // A batch size of 0 is read as 1
+        assert_eq!(stream_files(&tmp, DEFAULT_LIMIT, 0, &|files| assert_eq!(files.len(), 1)).unwrap(), (12, false));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_stream_files_empty_and_missing() {
+        let tmp = std::env::temp_dir().join("fileorg_test_scan_stream_empty");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        let sent = Mutex::new(0);
+        assert_eq!(stream_files(&tmp, DEFAULT_LIMIT, 5, &|_| *sent.lock().unwrap() += 1).unwrap(), (0, false));
+        assert_eq!(*sent.lock().unwrap(), 0, "no empty batches");
+        assert!(stream_files(&tmp.join("missing"), DEFAULT_LIMIT, 5, &|_| {}).is_err());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_list_children() {
+        let tmp = std::env::temp_dir().join("fileorg_test_scan_children");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Uni").join("ML").join("Lectures")).unwrap();
+        fs::create_dir_all(tmp.join("Uni").join("Algorithms")).unwrap();
+        fs::create_dir_all(tmp.join(".cache")).unwrap();
+        fs::create_dir_all(tmp.join("archive")).unwrap();
+        fs::write(tmp.join("Uni").join("syllabus.pdf"), "x").unwrap();
+        fs::write(tmp.join("notes.txt"), "x").unwrap();
+
+        let children = list_children(&tmp).unwrap();
+        let names: Vec<&str> = children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["archive", "Uni"], "hidden folders and files aren't listed");
+        assert_eq!((children[1].subfolders, children[1].files), (2, 1));
+        assert_eq!(children[0].subfolders, 0, "nothing to expand");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_list_folders_batches() {
+        let tmp = std::env::temp_dir().join("fileorg_test_scan_list_folders");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Uni").join("ML")).unwrap();
+        fs::create_dir_all(tmp.join("Uni").join("Algorithms")).unwrap();
+        fs::create_dir_all(tmp.join("Work")).unwrap();
+
+        let batches = Mutex::new(Vec::new());
+        let on_batch = |folders: Vec<String>| batches.lock().unwrap().push(folders);
+        let folders = list_folders(&tmp, true, DEFAULT_LIMIT, Some(&on_batch), &|_| false).unwrap();
+        assert_eq!(folders.len(), 4);
+        assert_eq!(batches.lock().unwrap().concat().len(), 4, "every folder is streamed once");
+
+        // The walk stops going deeper once the limit is reached
+        assert_eq!(list_folders(&tmp, true, 2, None, &|_| false).unwrap(), vec!["Uni", "Work"]);
+
+        // Skipped folders aren't listed or walked into
+        let skip = |path: &Path| path.ends_with("Uni");
+        assert_eq!(list_folders(&tmp, true, DEFAULT_LIMIT, None, &skip).unwrap(), vec!["Work"]);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,207 @@
+//! Scheduled automatic organization
+//!
+//! Runs the organize pipeline on the configured folders every N minutes or once a day
+//! at a set local time. The schedule is stored as JSON in the settings table and checked
+//! by a background thread, so it keeps running while the window is hidden in the tray.
+
+use crate::db::{Database, DbError};
+use chrono::{Duration as ChronoDuration, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const SCHEDULE_SETTING: &str = "organize_schedule";
+
+// How often the background thread checks whether a run is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const MINUTE_MS: i64 = 60 * 1000;
+
+/// When scheduled runs happen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Schedule {
+    Interval { minutes: u32 },
+    Daily { hour: u32, minute: u32 }, // local time
+}
+
+/// Persisted scheduler configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleSettings {
+    pub enabled: bool,
+    pub schedule: Schedule,
+    pub folders: Vec<String>,           // folders to organize on each run
+    pub available_folders: Vec<String>, // destination folders, as configured in the app
+    pub min_confidence: f32,
+    pub last_run_at: Option<i64>, // Unix timestamp ms; schedule counts from here
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> Self {
+        ScheduleSettings {
+            enabled: false,
+            schedule: Schedule::Interval { minutes: 60 },
+            folders: Vec::new(),
+            available_folders: Vec::new(),
+            min_confidence: 0.9,
+            last_run_at: None,
+        }
+    }
+}
+
+/// Load the stored schedule (defaults if none has been saved, or it can't be parsed)
+pub fn load(db: &Database) -> Result<ScheduleSettings, DbError> {
+    Ok(db
+        .get_setting(SCHEDULE_SETTING)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn save(db: &Database, settings: &ScheduleSettings) -> Result<(), DbError> {
+    let json = serde_json::to_string(settings).map_err(|e| DbError::QueryFailed(e.to_string()))?;
+    db.set_setting(SCHEDULE_SETTING, &json)
+}
+
+/// When the next run is due after a run at `last_run_at` (Unix timestamp ms)
+pub fn next_run_after(schedule: &Schedule, last_run_at: i64) -> Option<i64> {
+    match *schedule {
+        Schedule::Interval { minutes } => Some(last_run_at + minutes.max(1) as i64 * MINUTE_MS),
+        Schedule::Daily { hour, minute } => {
+            let last = Local.timestamp_millis_opt(last_run_at).single()?;
+            // A few days ahead covers DST gaps where the time doesn't exist on a given day
+            (0..3).find_map(|days| {
+                let date = last.date_naive() + ChronoDuration::days(days);
+                let candidate = Local
+                    .from_local_datetime(&date.and_hms_opt(hour, minute, 0)?)
+                    .earliest()?;
+                (candidate > last).then(|| candidate.timestamp_millis())
+            })
+        }
+    }
+}
+
+/// When the next scheduled run will happen, if the schedule is active
+pub fn next_run(settings: &ScheduleSettings, now: i64) -> Option<i64> {
+    if !settings.enabled || settings.folders.is_empty() {
+        return None;
+    }
+    next_run_after(&settings.schedule, settings.last_run_at.unwrap_or(now))
+}
+
+/// Notification text for a finished run
+pub fn summary_message(moved: usize, skipped: usize, failed: usize) -> String {
+    let mut message = match moved {
+        0 => "No files needed moving".to_string(),
+        1 => "Moved 1 file".to_string(),
+        n => format!("Moved {} files", n),
+    };
+    if skipped > 0 {
+        message.push_str(&format!(", {} left for review", skipped));
+    }
+    if failed > 0 {
+        message.push_str(&format!(", {} failed", failed));
+    }
+    message
+}
+
+/// Check the schedule every CHECK_INTERVAL on a background thread and call `run` when due
+///
+/// The run time is recorded before `run` starts, so a run that crashes isn't retried in a loop.
+pub fn spawn_thread(db: &'static Database, run: impl Fn(ScheduleSettings) + Send + 'static) {
+    let spawned = std::thread::Builder::new()
+        .name("organize-scheduler".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(CHECK_INTERVAL);
+
+            let now = crate::current_timestamp_ms();
+            let mut settings = match load(db) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("[SCHEDULER] Failed to load schedule: {}", e);
+                    continue;
+                }
+            };
+            if next_run(&settings, now).is_none_or(|due| due > now) {
+                continue;
+            }
+
+            settings.last_run_at = Some(now);
+            if let Err(e) = save(db, &settings) {
+                eprintln!("[SCHEDULER] Failed to record run: {}", e);
+                continue;
+            }
+            println!("[SCHEDULER] Running scheduled organize on {} folder(s)", settings.folders.len());
+            run(settings);
+        });
+    if let Err(e) = spawned {
+        eprintln!("[SCHEDULER] Failed to start scheduler thread: {}", e);
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_ms(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> i64 {
+        Local.with_ymd_and_hms(y, mo, d, h, mi, 0).earliest().unwrap().timestamp_millis()
+    }
+
+    #[test]
+    fn test_next_run_interval() {
+        let schedule = Schedule::Interval { minutes: 30 };
+        assert_eq!(next_run_after(&schedule, 1_000), Some(1_000 + 30 * MINUTE_MS));
+
+        let mut settings = ScheduleSettings {
+            enabled: true,
+            schedule,
+            folders: vec!["Downloads".to_string()],
+            last_run_at: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(next_run(&settings, 5), Some(30 * MINUTE_MS));
+
+        settings.folders.clear();
+        assert_eq!(next_run(&settings, 5), None);
+    }
+
+    #[test]
+    fn test_next_run_daily() {
+        let schedule = Schedule::Daily { hour: 9, minute: 30 };
+        // Later the same day, or the next day once today's time has passed
+        assert_eq!(
+            next_run_after(&schedule, local_ms(2025, 11, 4, 8, 0)),
+            Some(local_ms(2025, 11, 4, 9, 30))
+        );
+        assert_eq!(
+            next_run_after(&schedule, local_ms(2025, 11, 4, 9, 30)),
+            Some(local_ms(2025, 11, 5, 9, 30))
+        );
+    }
+
+    #[test]
+    fn test_settings_round_trip_and_summary() {
+        let tmp = std::env::temp_dir().join("fileorg_test_scheduler");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let db = Database::new(tmp.join("test.db")).unwrap();
+
+        assert!(!load(&db).unwrap().enabled);
+        let settings = ScheduleSettings {
+            enabled: true,
+            schedule: Schedule::Daily { hour: 18, minute: 0 },
+            folders: vec!["Downloads".to_string()],
+            ..Default::default()
+        };
+        save(&db, &settings).unwrap();
+        assert_eq!(load(&db).unwrap().schedule, Schedule::Daily { hour: 18, minute: 0 });
+
+        assert_eq!(summary_message(0, 0, 0), "No files needed moving");
+        assert_eq!(summary_message(1, 0, 0), "Moved 1 file");
+        assert_eq!(summary_message(4, 2, 1), "Moved 4 files, 2 left for review, 1 failed");
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
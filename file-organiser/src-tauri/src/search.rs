@@ -0,0 +1,278 @@
+//! File search across the watched and destination folders
+//!
+//! Answers "where did the assistant put PS3" without leaving the app. The query
+//! matches file names case-insensitively, either as a substring or, if it contains
+//! `*`, `?` or `[`, as a glob. Subfolders are walked in parallel with rayon, since
+//! destination trees for a whole degree can hold tens of thousands of files.
+
+use glob::{MatchOptions, Pattern};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// Results beyond this are dropped (newest files are kept) unless the caller asks for more
+pub const DEFAULT_LIMIT: usize = 200;
+
+/// Optional constraints on top of the name query
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub roots: Vec<String>,              // folders to search (watched folder, destination base, ...)
+    pub extensions: Vec<String>,         // e.g. ["pdf", "docx"]; empty means any
+    pub min_size: Option<u64>,           // bytes
+    pub max_size: Option<u64>,           // bytes
+    pub modified_after: Option<u64>,     // Unix timestamp in seconds
+    pub modified_before: Option<u64>,    // Unix timestamp in seconds
+    pub limit: Option<usize>,
+}
+
+/// A file that matched the search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub name: String,
+    pub path: String,
+    pub folder: String,
+    pub size: u64,
+    pub modified: u64, // Unix timestamp in seconds
+}
+
+/// How a file name is matched against the query
+enum NameMatcher {
+    Any,
+    Substring(String),
+    Glob(Pattern),
+}
+
+impl NameMatcher {
+    fn new(query: &str) -> Result<Self, glob::PatternError> {
+        let query = query.trim();
+        if query.is_empty() {
+            Ok(NameMatcher::Any)
+        } else if query.contains(['*', '?', '[']) {
+            Ok(NameMatcher::Glob(Pattern::new(query)?))
+        } else {
+            Ok(NameMatcher::Substring(query.to_lowercase()))
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Any => true,
+            NameMatcher::Substring(needle) => name.to_lowercase().contains(needle),
+            NameMatcher::Glob(pattern) => pattern.matches_with(
+                name,
+                MatchOptions {
+                    case_sensitive: false,
+                    ..MatchOptions::new()
+                },
+            ),
+        }
+    }
+}
+
+/// Compiled form of a query plus filters
+struct Criteria {
+    name: NameMatcher,
+    extensions: Vec<String>,
+    filters: SearchFilters,
+}
+
+impl Criteria {
+    fn matches(&self, name: &str, size: u64, modified: u64) -> bool {
+        if !self.extensions.is_empty() {
+            let ext = Path::new(name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !self.extensions.contains(&ext) {
+                return false;
+            }
+        }
+        let f = &self.filters;
+        f.min_size.is_none_or(|min| size >= min)
+            && f.max_size.is_none_or(|max| size <= max)
+            && f.modified_after.is_none_or(|after| modified >= after)
+            && f.modified_before.is_none_or(|before| modified <= before)
+            && self.name.matches(name)
+    }
+}
+
+/// Search every root for files matching `query` and `filters`, newest first
+///
+/// Folders `skip` returns true for (a watch profile's excluded ones) aren't searched.
+pub fn search(query: &str, filters: SearchFilters, skip: &(dyn Fn(&Path) -> bool + Sync)) -> Result<Vec<SearchResult>, String> {
+    let name = NameMatcher::new(query).map_err(|e| format!("Invalid search pattern: {}", e))?;
+    let extensions = filters
+        .extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect();
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT);
+
+    // Overlapping roots (a watched folder inside the destination base) would report files twice
+    let mut roots: Vec<PathBuf> = filters.roots.iter().map(PathBuf::from).filter(|r| r.is_dir()).collect();
+    roots.sort();
+    roots.dedup_by(|inner, outer| inner.starts_with(outer));
+
+    let criteria = Criteria { name, extensions, filters };
+    let mut results: Vec<SearchResult> = roots
+        .par_iter()
+        .flat_map(|root| walk(root, &criteria, skip))
+        .collect();
+
+    results.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// Collect matches in `dir`, recursing into subfolders in parallel
+fn walk(dir: &Path, criteria: &Criteria, skip: &(dyn Fn(&Path) -> bool + Sync)) -> Vec<SearchResult> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut matches = Vec::new();
+    let mut subdirs = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // Skip hidden files and folders (including the app trash)
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        // Symlinked folders are not followed to avoid cycles
+        if file_type.is_dir() {
+            if !skip(&entry.path()) {
+                subdirs.push(entry.path());
+            }
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+
+        let size = meta.len();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if criteria.matches(&name, size, modified) {
+            matches.push(SearchResult {
+                name,
+                path: entry.path().to_string_lossy().to_string(),
+                folder: dir.to_string_lossy().to_string(),
+                size,
+                modified,
+            });
+        }
+    }
+
+    matches.extend(subdirs.par_iter().flat_map(|sub| walk(sub, criteria, skip)).collect::<Vec<_>>());
+    matches
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str) -> PathBuf {
+        let tmp = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("Downloads")).unwrap();
+        fs::create_dir_all(tmp.join("Uni").join("Algorithms").join("Problem Sets")).unwrap();
+        fs::write(tmp.join("Downloads").join("ps3_draft.docx"), "draft").unwrap();
+        fs::write(tmp.join("Uni").join("Algorithms").join("Problem Sets").join("PS3.pdf"), "solutions").unwrap();
+        fs::write(tmp.join("Uni").join("Algorithms").join("lecture1.pdf"), "slides").unwrap();
+        fs::write(tmp.join("Uni").join(".hidden_ps3.pdf"), "hidden").unwrap();
+        tmp
+    }
+
+    fn roots(tmp: &Path) -> Vec<String> {
+        vec![
+            tmp.join("Downloads").to_string_lossy().to_string(),
+            tmp.join("Uni").to_string_lossy().to_string(),
+        ]
+    }
+
+    fn names(results: &[SearchResult]) -> Vec<&str> {
+        let mut names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_search_substring_and_glob() {
+        let tmp = setup("fileorg_test_search");
+        let filters = SearchFilters { roots: roots(&tmp), ..Default::default() };
+
+        // Substring matching is case-insensitive and skips hidden files
+        let results = search("ps3", filters.clone(), &|_| false).unwrap();
+        assert_eq!(names(&results), vec!["PS3.pdf", "ps3_draft.docx"]);
+        let found = results.iter().find(|r| r.name == "PS3.pdf").unwrap();
+        assert!(found.folder.ends_with("Problem Sets"));
+
+        let results = search("*.pdf", filters.clone(), &|_| false).unwrap();
+        assert_eq!(names(&results), vec!["PS3.pdf", "lecture1.pdf"]);
+
+        assert!(search("[", filters, &|_| false).is_err());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let tmp = setup("fileorg_test_search_filters");
+
+        let by_ext = SearchFilters {
+            roots: roots(&tmp),
+            extensions: vec![".DOCX".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(names(&search("", by_ext, &|_| false).unwrap()), vec!["ps3_draft.docx"]);
+
+        let by_size = SearchFilters {
+            roots: roots(&tmp),
+            min_size: Some(6),
+            max_size: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(names(&search("", by_size, &|_| false).unwrap()), vec!["lecture1.pdf"]);
+
+        let future = SearchFilters {
+            roots: roots(&tmp),
+            modified_after: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert!(search("", future, &|_| false).unwrap().is_empty());
+
+        // Nested roots don't produce duplicates, and the limit is applied
+        let nested = SearchFilters {
+            roots: vec![
+                tmp.to_string_lossy().to_string(),
+                tmp.join("Uni").to_string_lossy().to_string(),
+            ],
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(search("", nested, &|_| false).unwrap().len(), 2);
+        let all = SearchFilters { roots: vec![tmp.to_string_lossy().to_string()], ..Default::default() };
+        assert_eq!(search("", all.clone(), &|_| false).unwrap().len(), 3);
+        let skip = |path: &Path| path.ends_with("Problem Sets");
+        assert_eq!(names(&search("", all, &skip).unwrap()), vec!["lecture1.pdf", "ps3_draft.docx"]);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,342 @@
+//! Typed settings
+//!
+//! Every setting is stored as text in the `settings` table, and each feature reads its own
+//! keys. This module is the schema for the user-facing options: each key's type (bool,
+//! whole number, number, one of a set of names, free text or JSON), its limits and its
+//! default. With it the frontend can read every option at once, with defaults filled in,
+//! and save several in one go. Values keep the text form the features already read
+//! ("true", "12000", JSON), so the per-feature commands and these stay interchangeable.
+//!
+//! Options with richer state and a command of their own (watch profiles, the schedule,
+//! the global shortcut, webhooks, LMS, quotas, allowed folders) aren't listed here.
+//! Neither is the API key.
+
+use crate::db::{Database, DbError};
+use crate::{budget, chat_export, classifier, safe_delete};
+use serde_json::{json, Map, Value};
+
+/// What a setting holds
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    Bool,
+    Int { min: i64, max: i64 }, // values out of range are clamped, as the single-setting commands do
+    Float { min: f64, max: f64 },
+    Choice(&'static [&'static str]),
+    Text,
+    Json,
+}
+
+pub struct Setting {
+    pub key: &'static str,
+    pub kind: Kind,
+    default: fn() -> Value,
+    check: fn(Value) -> Result<Value, String>, // checks beyond the type; may tidy the value
+}
+
+static SCHEMA: &[Setting] = &[
+    Setting {
+        key: crate::CONFLICT_POLICY_SETTING,
+        kind: Kind::Choice(&["skip", "rename", "replace", "error"]),
+        default: || json!("rename"),
+        check: Ok,
+    },
+    Setting {
+        key: crate::CLASSIFICATION_MODE_SETTING,
+        kind: Kind::Choice(&["ai", "ocr", "buckets"]),
+        default: || json!("ai"),
+        check: Ok,
+    },
+    Setting {
+        key: crate::BUCKET_FOLDER_SETTING,
+        kind: Kind::Text,
+        default: || json!(""),
+        check: Ok,
+    },
+    Setting {
+        key: crate::ORGANIZE_MIN_CONFIDENCE_SETTING,
+        kind: Kind::Float { min: 0.0, max: 1.0 },
+        default: || json!(0.7), // DEFAULT_ORGANIZE_MIN_CONFIDENCE
+        check: Ok,
+    },
+    Setting {
+        key: crate::PRIVACY_MODE_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::SIBLING_CONTEXT_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::SUGGEST_SUBFOLDERS_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::PROVENANCE_IN_FILES_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::READ_CLOUD_PLACEHOLDERS_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::LOCAL_ONLY_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::EMBEDDING_FALLBACK_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::AI_PROVIDER_SETTING,
+        kind: Kind::Choice(&["openai", "ollama", "mock"]),
+        default: || json!(classifier::Provider::default().as_str()),
+        check: Ok,
+    },
+    Setting {
+        key: crate::AI_ENDPOINT_SETTING,
+        kind: Kind::Json,
+        default: || json!(classifier::EndpointOverride::default()),
+        check: |value| {
+            let endpoint = serde_json::from_value::<classifier::EndpointOverride>(value)
+                .map_err(|e| e.to_string())?
+                .normalized();
+            endpoint.validate()?;
+            Ok(json!(endpoint))
+        },
+    },
+    Setting {
+        key: crate::MODEL_ROUTES_SETTING,
+        kind: Kind::Json,
+        default: || json!(classifier::ModelRoutes::default()),
+        check: |value| {
+            let routes = serde_json::from_value::<classifier::ModelRoutes>(value).map_err(|e| e.to_string())?;
+            Ok(json!(routes.normalized()))
+        },
+    },
+    Setting {
+        key: crate::FALLBACK_CHAIN_SETTING,
+        kind: Kind::Json,
+        default: || json!([]),
+        check: |value| {
+            let chain: Vec<classifier::FallbackTier> = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            classifier::validate_fallback_chain(&chain)?;
+            Ok(json!(chain))
+        },
+    },
+    Setting {
+        key: crate::PROMPT_TOKEN_BUDGET_SETTING,
+        kind: Kind::Int { min: classifier::MIN_PROMPT_TOKEN_BUDGET as i64, max: 1_000_000 },
+        default: || json!(classifier::DEFAULT_PROMPT_TOKEN_BUDGET),
+        check: Ok,
+    },
+    Setting {
+        key: crate::AI_TIMEOUTS_SETTING,
+        kind: Kind::Json,
+        default: || json!(classifier::Timeouts::default()),
+        check: |value| {
+            let timeouts: classifier::Timeouts = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            timeouts.validate()?;
+            Ok(json!(timeouts))
+        },
+    },
+    Setting {
+        key: crate::MAX_CONCURRENT_REQUESTS_SETTING,
+        kind: Kind::Int { min: 1, max: classifier::MAX_CONCURRENT_REQUESTS as i64 },
+        default: || json!(classifier::DEFAULT_MAX_CONCURRENT_REQUESTS),
+        check: Ok,
+    },
+    Setting {
+        key: crate::API_BUDGET_SETTING,
+        kind: Kind::Json,
+        default: || json!(budget::BudgetSettings::default()),
+        check: |value| {
+            let budget: budget::BudgetSettings = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            budget.validate()?;
+            Ok(json!(budget))
+        },
+    },
+    Setting {
+        key: crate::CHAT_EXPORT_SUBFOLDER_SETTING,
+        kind: Kind::Text,
+        default: || json!(chat_export::DEFAULT_SUBFOLDER),
+        check: |value| chat_export::validate_subfolder(value.as_str().unwrap_or_default()).map(Value::String),
+    },
+    Setting {
+        key: crate::QUARANTINE_FOLDER_SETTING,
+        kind: Kind::Text,
+        default: || json!(""),
+        check: Ok,
+    },
+    Setting {
+        key: crate::QUARANTINE_ENABLED_SETTING,
+        kind: Kind::Bool,
+        default: || json!(false),
+        check: Ok,
+    },
+    Setting {
+        key: crate::DELETE_MODE_SETTING,
+        kind: Kind::Choice(&["recycle_bin", "app_trash"]),
+        default: || json!("recycle_bin"),
+        check: Ok,
+    },
+    Setting {
+        key: crate::TRASH_RETENTION_SETTING,
+        kind: Kind::Int { min: 1, max: 365 },
+        default: || json!(safe_delete::DEFAULT_RETENTION_DAYS),
+        check: Ok,
+    },
+];
+
+fn find(key: &str) -> Option<&'static Setting> {
+    SCHEMA.iter().find(|s| s.key == key)
+}
+
+/// A stored value read as its type, or the default if it's unset or unreadable
+fn decode(setting: &Setting, stored: Option<String>) -> Value {
+    let Some(stored) = stored else {
+        return (setting.default)();
+    };
+    let value = match setting.kind {
+        Kind::Bool => Some(Value::Bool(stored == "true")),
+        Kind::Int { .. } => stored.parse::<i64>().ok().map(Value::from),
+        Kind::Float { .. } => stored.parse::<f64>().ok().filter(|f| f.is_finite()).map(Value::from),
+        Kind::Choice(names) => names.contains(&stored.as_str()).then_some(Value::String(stored)),
+        Kind::Text => Some(Value::String(stored)),
+        Kind::Json => serde_json::from_str(&stored).ok(),
+    };
+    value.unwrap_or_else(setting.default)
+}
+
+/// Check a value against its setting, returning the text it's stored as
+fn encode(setting: &Setting, value: Value) -> Result<String, String> {
+    let key = setting.key;
+    let value = match setting.kind {
+        Kind::Bool => value.as_bool().map(Value::Bool).ok_or_else(|| format!("{} must be true or false", key))?,
+        Kind::Int { min, max } => value
+            .as_i64()
+            .map(|n| Value::from(n.clamp(min, max)))
+            .ok_or_else(|| format!("{} must be a whole number", key))?,
+        Kind::Float { min, max } => value
+            .as_f64()
+            .filter(|f| f.is_finite())
+            .map(|f| Value::from(f.clamp(min, max)))
+            .ok_or_else(|| format!("{} must be a number", key))?,
+        Kind::Choice(names) => match value.as_str() {
+            Some(name) if names.contains(&name) => value,
+            _ => return Err(format!("{} must be one of: {}", key, names.join(", "))),
+        },
+        Kind::Text => match value {
+            Value::String(_) => value,
+            Value::Null => Value::String(String::new()),
+            _ => return Err(format!("{} must be text", key)),
+        },
+        Kind::Json => value,
+    };
+    let value = (setting.check)(value).map_err(|e| format!("{}: {}", key, e))?;
+    Ok(match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    })
+}
+
+/// Every setting in the schema with its current value (the default for ones never set)
+pub fn get_all(db: &Database) -> Result<Map<String, Value>, DbError> {
+    SCHEMA
+        .iter()
+        .map(|setting| Ok((setting.key.to_string(), decode(setting, db.get_setting(setting.key)?))))
+        .collect()
+}
+
+/// One setting's current value, or its default if it was never set (Null for unknown keys)
+pub fn get(db: &Database, key: &str) -> Result<Value, DbError> {
+    match find(key) {
+        Some(setting) => Ok(decode(setting, db.get_setting(key)?)),
+        None => Ok(Value::Null),
+    }
+}
+
+/// Check a batch of settings, returning (key, stored text) pairs ready to save
+///
+/// Fails on the first unknown key or invalid value, so a batch is saved whole or not at all.
+pub fn prepare(values: Map<String, Value>) -> Result<Vec<(String, String)>, String> {
+    values
+        .into_iter()
+        .map(|(key, value)| {
+            let setting = find(&key).ok_or_else(|| format!("Unknown setting: {}", key))?;
+            Ok((key, encode(setting, value)?))
+        })
+        .collect()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_falls_back_to_defaults() {
+        let setting = |key| find(key).unwrap();
+        assert_eq!(decode(setting("privacy_mode"), None), json!(false));
+        assert_eq!(decode(setting("privacy_mode"), Some("true".to_string())), json!(true));
+        assert_eq!(decode(setting("max_concurrent_requests"), Some("5".to_string())), json!(5));
+        assert_eq!(decode(setting("max_concurrent_requests"), Some("lots".to_string())), json!(3));
+        assert_eq!(decode(setting("conflict_policy"), Some("shred".to_string())), json!("rename"));
+        assert_eq!(decode(setting("ai_fallback_chain"), Some("not json".to_string())), json!([]));
+        assert_eq!(
+            decode(setting("organize_min_confidence"), None).as_f64().unwrap() as f32,
+            crate::DEFAULT_ORGANIZE_MIN_CONFIDENCE
+        );
+    }
+
+    #[test]
+    fn test_prepare_checks_and_converts_values() {
+        let values = |v: Value| v.as_object().unwrap().clone();
+        let mut prepared = prepare(values(json!({
+            "privacy_mode": true,
+            "max_concurrent_requests": 50,
+            "organize_min_confidence": 0.85,
+            "delete_mode": "app_trash",
+            "chat_export_subfolder": "  AI notes ",
+            "ai_timeouts": { "text_secs": 20, "vision_secs": 60, "ocr_secs": 20 },
+        })))
+        .unwrap();
+        prepared.sort();
+        assert_eq!(
+            prepared,
+            vec![
+                ("ai_timeouts".to_string(), r#"{"ocr_secs":20,"text_secs":20,"vision_secs":60}"#.to_string()),
+                ("chat_export_subfolder".to_string(), "AI notes".to_string()),
+                ("delete_mode".to_string(), "app_trash".to_string()),
+                ("max_concurrent_requests".to_string(), "8".to_string()), // clamped
+                ("organize_min_confidence".to_string(), "0.85".to_string()),
+                ("privacy_mode".to_string(), "true".to_string()),
+            ]
+        );
+
+        // Stored text reads back as the same typed value
+        let timeouts = find("ai_timeouts").unwrap();
+        assert_eq!(decode(timeouts, Some(prepared[0].1.clone()))["vision_secs"], json!(60));
+
+        assert!(prepare(values(json!({ "privacy_mode": "yes" }))).unwrap_err().contains("true or false"));
+        assert!(prepare(values(json!({ "conflict_policy": "shred" }))).unwrap_err().contains("one of"));
+        assert!(prepare(values(json!({ "api_key": "sk-..." }))).unwrap_err().contains("Unknown setting"));
+        assert!(prepare(values(json!({ "ai_timeouts": { "text_secs": 0 } }))).is_err());
+        assert!(prepare(values(json!({ "chat_export_subfolder": "a/b" }))).is_err());
+    }
+}
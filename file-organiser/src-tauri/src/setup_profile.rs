@@ -0,0 +1,258 @@
+//! Setup profiles: the user's whole configuration in one JSON file
+//!
+//! An exported profile holds the typed settings (see `settings`), classification rules,
+//! watch profiles and per-folder preferences (naming templates, descriptions), so a setup
+//! can be copied from a laptop to a desktop. The API key is never included, and neither
+//! are history (activity, corrections), settings holding credentials (LMS, webhooks) or the
+//! extra headers of a custom AI endpoint.
+//!
+//! Importing merges into the current setup: settings in the file overwrite the current
+//! ones, watch profiles are matched by the folder they watch, and rules already present
+//! aren't added twice. Everything is checked first and then saved in one transaction. A
+//! custom AI endpoint isn't applied: it would be sent the API key, so it's returned for the
+//! user to confirm.
+
+use crate::db::{Database, DbError, FolderPref, Rule, WatchProfile};
+use crate::classifier::EndpointOverride;
+use crate::{profiles, settings, CommandError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A rule in an exported profile, tied to its watch profile by the folder it watches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileRule {
+    pub pattern: String,
+    pub target_folder: String,
+    #[serde(default)]
+    pub watch_path: Option<String>, // None: applies to every watched folder
+}
+
+/// Contents of an exported profile file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupProfile {
+    pub version: u32,
+    pub exported_at: i64, // Unix timestamp ms
+    #[serde(default)]
+    pub settings: Map<String, Value>,
+    #[serde(default)]
+    pub rules: Vec<ProfileRule>,
+    #[serde(default)]
+    pub watch_profiles: Vec<WatchProfile>,
+    #[serde(default)]
+    pub folder_prefs: Vec<FolderPref>,
+}
+
+/// What an import changed
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub settings: usize,
+    pub rules_added: usize,
+    pub watch_profiles_added: usize,
+    pub watch_profiles_updated: usize,
+    pub folder_prefs: usize,
+    pub endpoint_to_confirm: Option<EndpointOverride>, // in the file but not applied; see `import`
+}
+
+/// Gather the current setup into a profile
+pub fn export(db: &Database) -> Result<SetupProfile, DbError> {
+    let watch_profiles = db.get_watch_profiles()?;
+    let watch_path_of = |id: Option<i64>| {
+        watch_profiles
+            .iter()
+            .find(|p| id.is_some() && p.id == id)
+            .map(|p| p.watch_path.clone())
+    };
+    let rules = db
+        .get_rules()?
+        .into_iter()
+        .map(|rule: Rule| ProfileRule {
+            watch_path: watch_path_of(rule.profile_id),
+            pattern: rule.pattern,
+            target_folder: rule.target_folder,
+        })
+        .collect();
+
+    let mut settings = settings::get_all(db)?;
+    // Extra headers for a custom endpoint usually carry credentials
+    if let Some(endpoint) = settings.get_mut(crate::AI_ENDPOINT_SETTING).and_then(Value::as_object_mut) {
+        endpoint.insert("headers".to_string(), json!({}));
+    }
+
+    Ok(SetupProfile {
+        version: FORMAT_VERSION,
+        exported_at: chrono::Utc::now().timestamp_millis(),
+        settings,
+        rules,
+        watch_profiles: watch_profiles.iter().cloned().map(|p| WatchProfile { id: None, ..p }).collect(),
+        folder_prefs: db.get_folder_prefs()?,
+    })
+}
+
+/// Merge a profile into the current setup
+pub fn import(db: &Database, mut profile: SetupProfile) -> Result<ImportReport, CommandError> {
+    if profile.version > FORMAT_VERSION {
+        return Err(CommandError::InvalidInput(format!(
+            "This profile was exported by a newer version of the app (format {})",
+            profile.version
+        )));
+    }
+
+    // Check everything first, so a bad entry doesn't leave half a profile imported
+    let endpoint = profile.settings.remove(crate::AI_ENDPOINT_SETTING);
+    let prepared = settings::prepare(profile.settings).map_err(CommandError::InvalidInput)?;
+    let endpoint = match endpoint {
+        Some(value) => {
+            let endpoint = serde_json::from_value::<EndpointOverride>(value)
+                .map_err(|e| CommandError::InvalidInput(format!("{}: {}", crate::AI_ENDPOINT_SETTING, e)))?
+                .normalized();
+            endpoint.validate().map_err(CommandError::InvalidInput)?;
+            Some(endpoint).filter(|endpoint| *endpoint != EndpointOverride::default())
+        }
+        None => None,
+    };
+    let watch_profiles = profile
+        .watch_profiles
+        .into_iter()
+        .map(profiles::validate)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(CommandError::InvalidInput)?;
+    if let Some(rule) = profile.rules.iter().find(|r| r.pattern.trim().is_empty() || r.target_folder.trim().is_empty()) {
+        return Err(CommandError::InvalidInput(format!("Rule \"{}\" is missing its pattern or folder", rule.pattern)));
+    }
+
+    let mut report = ImportReport {
+        settings: prepared.len(),
+        folder_prefs: profile.folder_prefs.len(),
+        endpoint_to_confirm: endpoint,
+        ..ImportReport::default()
+    };
+
+    let existing = db.get_watch_profiles()?;
+    let watch_profiles: Vec<WatchProfile> = watch_profiles
+        .into_iter()
+        .map(|watch_profile| {
+            let id = existing.iter().find(|p| p.watch_path == watch_profile.watch_path).and_then(|p| p.id);
+            match id {
+                Some(_) => report.watch_profiles_updated += 1,
+                None => report.watch_profiles_added += 1,
+            }
+            WatchProfile { id, ..watch_profile }
+        })
+        .collect();
+
+    let current_rules = db.get_rules()?;
+    let mut rules = Vec::new();
+    for rule in profile.rules {
+        let profile_id = match &rule.watch_path {
+            Some(path) => match existing.iter().find(|p| &p.watch_path == path) {
+                Some(p) => p.id,
+                None if watch_profiles.iter().any(|p| &p.watch_path == path) => {
+                    rules.push(rule); // its watch profile is new, so the rule is too
+                    continue;
+                }
+                None => continue, // its watch profile wasn't in the file either
+            },
+            None => None,
+        };
+        let present = current_rules
+            .iter()
+            .any(|r| r.pattern == rule.pattern && r.target_folder == rule.target_folder && r.profile_id == profile_id);
+        if !present {
+            rules.push(rule);
+        }
+    }
+    report.rules_added = rules.len();
+
+    db.save_setup(&prepared, &watch_profiles, &rules, &profile.folder_prefs)?;
+    Ok(report)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_db(name: &str) -> Database {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Database::new(dir.join("test.db")).unwrap()
+    }
+
+    #[test]
+    fn test_export_and_import_profile() {
+        let laptop = temp_db("fileorg_test_setup_profile_laptop");
+        laptop.set_setting("privacy_mode", "true").unwrap();
+        laptop.set_setting("api_key", "sk-secret").unwrap();
+        laptop
+            .set_setting(
+                crate::AI_ENDPOINT_SETTING,
+                r#"{"base_url":"https://gateway.example.com/v1","headers":{"X-Api-Token":"team-secret"}}"#,
+            )
+            .unwrap();
+        let downloads = laptop
+            .save_watch_profile(&WatchProfile {
+                id: None,
+                name: "Downloads".to_string(),
+                watch_path: "C:\\Users\\me\\Downloads".to_string(),
+                folders: vec!["C:\\Uni\\ML".to_string()],
+                mode: "ai".to_string(),
+                min_confidence: 0.8,
+                created_at: 0,
+                excluded: Vec::new(),
+            })
+            .unwrap();
+        laptop.add_rule("*lecture*", "C:\\Uni\\ML", Some(downloads)).unwrap();
+        laptop.add_rule("*.exe", "C:\\Installers", None).unwrap();
+        laptop
+            .set_folder_pref(FolderPref {
+                folder: "C:\\Uni\\ML".to_string(),
+                never_suggest: false,
+                require_confirm: true,
+                naming_template: Some("{date}_{name}".to_string()),
+                description: Some("Machine Learning".to_string()),
+                updated_at: 0,
+            })
+            .unwrap();
+
+        let exported = export(&laptop).unwrap();
+        let json = serde_json::to_string(&exported).unwrap();
+        assert!(!json.contains("sk-secret"));
+        assert!(!json.contains("team-secret"));
+        assert_eq!(exported.rules[0].watch_path.as_deref(), Some("C:\\Users\\me\\Downloads"));
+
+        let desktop = temp_db("fileorg_test_setup_profile_desktop");
+        desktop.add_rule("*.exe", "C:\\Installers", None).unwrap();
+        let report = import(&desktop, serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!((report.rules_added, report.watch_profiles_added, report.folder_prefs), (1, 1, 1));
+        assert_eq!(desktop.get_setting("privacy_mode").unwrap().as_deref(), Some("true"));
+        assert_eq!(desktop.get_setting("api_key").unwrap(), None);
+        // The endpoint would be sent the API key, so it waits for the user to confirm it
+        let endpoint = report.endpoint_to_confirm.unwrap();
+        assert_eq!(endpoint.base_url.as_deref(), Some("https://gateway.example.com/v1"));
+        assert!(endpoint.headers.is_empty());
+        assert_eq!(desktop.get_setting(crate::AI_ENDPOINT_SETTING).unwrap(), None);
+        let profile_id = desktop.get_watch_profiles().unwrap()[0].id;
+        assert!(desktop.get_rules().unwrap().iter().any(|r| r.pattern == "*lecture*" && r.profile_id == profile_id));
+
+        // Importing again changes nothing new
+        let report = import(&desktop, serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!((report.rules_added, report.watch_profiles_added, report.watch_profiles_updated), (0, 0, 1));
+        assert_eq!(desktop.get_rules().unwrap().len(), 2);
+
+        // A bad setting stops the whole import
+        let mut bad: SetupProfile = serde_json::from_str(&json).unwrap();
+        bad.settings.insert("max_concurrent_requests".to_string(), Value::from("many"));
+        bad.rules.push(ProfileRule { pattern: "*.zip".to_string(), target_folder: "C:\\Archives".to_string(), watch_path: None });
+        assert!(matches!(import(&desktop, bad), Err(CommandError::InvalidInput(_))));
+        assert_eq!(desktop.get_rules().unwrap().len(), 2);
+
+        let _ = fs::remove_dir_all(std::env::temp_dir().join("fileorg_test_setup_profile_laptop"));
+        let _ = fs::remove_dir_all(std::env::temp_dir().join("fileorg_test_setup_profile_desktop"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Global keyboard shortcut
+//!
+//! A system-wide key combination (Ctrl+Alt+O by default, Cmd+Alt+O on macOS) that
+//! organizes the watched folder right away or brings the app to the front, without
+//! going through the tray icon. The configuration is stored as JSON in the settings
+//! table and registered with the OS at startup and whenever it's saved.
+
+use crate::db::{Database, DbError};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_global_shortcut::Shortcut;
+
+pub const SHORTCUT_SETTING: &str = "global_shortcut";
+
+pub const DEFAULT_SHORTCUT: &str = "CmdOrCtrl+Alt+O";
+
+/// What pressing the shortcut does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    #[default]
+    Organize,   // run the organize pipeline on the watched folder
+    ShowWindow, // show and focus the main window
+}
+
+/// Persisted shortcut configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub enabled: bool,
+    pub shortcut: String, // accelerator, e.g. "CmdOrCtrl+Alt+O"
+    pub action: ShortcutAction,
+    pub folder: String,                 // folder to organize (the watched folder)
+    pub available_folders: Vec<String>, // destination folders, as configured in the app
+    pub min_confidence: f32,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        ShortcutSettings {
+            enabled: false,
+            shortcut: DEFAULT_SHORTCUT.to_string(),
+            action: ShortcutAction::default(),
+            folder: String::new(),
+            available_folders: Vec::new(),
+            min_confidence: 0.9,
+        }
+    }
+}
+
+/// Load the stored configuration (defaults if none has been saved, or it can't be parsed)
+pub fn load(db: &Database) -> Result<ShortcutSettings, DbError> {
+    Ok(db
+        .get_setting(SHORTCUT_SETTING)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn save(db: &Database, settings: &ShortcutSettings) -> Result<(), DbError> {
+    let json = serde_json::to_string(settings).map_err(|e| DbError::QueryFailed(e.to_string()))?;
+    db.set_setting(SHORTCUT_SETTING, &json)
+}
+
+/// Parse an accelerator string like "CmdOrCtrl+Alt+O"
+pub fn parse(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .parse::<Shortcut>()
+        .map_err(|e| format!("\"{}\" isn't a valid shortcut: {}", shortcut, e))
+}
+
+/// Check a configuration before it's saved, returning it with the shortcut trimmed
+pub fn validate(mut settings: ShortcutSettings) -> Result<ShortcutSettings, String> {
+    settings.shortcut = settings.shortcut.trim().to_string();
+    if settings.shortcut.is_empty() {
+        settings.shortcut = DEFAULT_SHORTCUT.to_string();
+    }
+    parse(&settings.shortcut)?;
+    if settings.enabled && settings.action == ShortcutAction::Organize && settings.folder.trim().is_empty() {
+        return Err("Choose a watch folder first".to_string());
+    }
+    Ok(settings)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_settings() {
+        let settings = |shortcut: &str, folder: &str| ShortcutSettings {
+            enabled: true,
+            shortcut: shortcut.to_string(),
+            folder: folder.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(validate(settings(" Ctrl+Shift+K ", "/Downloads")).unwrap().shortcut, "Ctrl+Shift+K");
+        assert_eq!(validate(settings("", "/Downloads")).unwrap().shortcut, DEFAULT_SHORTCUT);
+        assert!(validate(settings("Ctrl+Alt+", "/Downloads")).is_err());
+
+        // Organizing needs a folder; just showing the window doesn't
+        assert!(validate(settings(DEFAULT_SHORTCUT, "")).is_err());
+        let show = ShortcutSettings { action: ShortcutAction::ShowWindow, ..settings(DEFAULT_SHORTCUT, "") };
+        assert!(validate(show).is_ok());
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let tmp = std::env::temp_dir().join("fileorg_test_shortcut");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let db = Database::new(tmp.join("test.db")).unwrap();
+
+        let loaded = load(&db).unwrap();
+        assert!(!loaded.enabled);
+        assert_eq!(loaded.shortcut, DEFAULT_SHORTCUT);
+
+        let settings = ShortcutSettings {
+            enabled: true,
+            action: ShortcutAction::ShowWindow,
+            ..Default::default()
+        };
+        save(&db, &settings).unwrap();
+        assert_eq!(load(&db).unwrap().action, ShortcutAction::ShowWindow);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
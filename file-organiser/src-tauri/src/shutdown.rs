@@ -0,0 +1,195 @@
+//! Graceful shutdown
+//!
+//! Quitting used to drop whatever was running: a move halfway through its copy, queued
+//! database writes. Now Quit first stops new work from starting, lets file operations
+//! already under way finish (up to a time limit, after which long copies are cancelled,
+//! which removes the partial copy and keeps the original), cancels in-flight
+//! classifications (their files stay in the pending queue and are classified again on the
+//! next start), and flushes the database before the app exits.
+
+use crate::CommandError;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+static COORDINATOR: OnceLock<Coordinator> = OnceLock::new();
+
+thread_local! {
+    // File operations running on this thread; the steps of one that's already under way
+    // (e.g. the copy inside a cross-device move) aren't refused
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Tracks running file operations and whether Quit has been requested
+pub struct Coordinator {
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+    signal: watch::Sender<bool>,
+}
+
+/// A file operation counted as in flight until it's dropped (on the thread that started it)
+pub struct Operation<'a> {
+    coordinator: &'a Coordinator,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for Operation<'_> {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+        self.coordinator.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Coordinator {
+    fn new() -> Self {
+        Coordinator {
+            shutting_down: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            signal: watch::channel(false).0,
+        }
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    fn begin(&self) {
+        if !self.shutting_down.swap(true, Ordering::SeqCst) {
+            println!("[SHUTDOWN] Shutting down, {} file operation(s) still running", self.in_flight());
+            self.signal.send_replace(true);
+        }
+    }
+
+    async fn requested(&self) {
+        let mut receiver = self.signal.subscribe();
+        let _ = receiver.wait_for(|shutting_down| *shutting_down).await;
+    }
+
+    fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    fn begin_operation(&self) -> Result<Operation<'_>, CommandError> {
+        let nested = DEPTH.with(|depth| depth.get() > 0);
+        if self.is_shutting_down() && !nested {
+            return Err(CommandError::Cancelled("The app is shutting down".to_string()));
+        }
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        Ok(Operation { coordinator: self, _not_send: PhantomData })
+    }
+
+    async fn drain(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        while self.in_flight() > 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        self.in_flight()
+    }
+}
+
+fn coordinator() -> &'static Coordinator {
+    COORDINATOR.get_or_init(Coordinator::new)
+}
+
+/// Whether Quit has been requested
+pub fn is_shutting_down() -> bool {
+    coordinator().is_shutting_down()
+}
+
+/// Stop new work from starting and wake everything waiting on `requested`
+pub fn begin() {
+    coordinator().begin()
+}
+
+/// Resolves once shutdown has begun, for racing against work that can be dropped
+pub async fn requested() {
+    coordinator().requested().await
+}
+
+/// Register a file operation, or refuse to start one once shutdown has begun
+pub fn begin_operation() -> Result<Operation<'static>, CommandError> {
+    coordinator().begin_operation()
+}
+
+/// Wait for running file operations to finish, up to `timeout`; returns how many are left
+pub async fn drain(timeout: Duration) -> usize {
+    coordinator().drain(timeout).await
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A coordinator of its own, so other tests' moves aren't refused
+    #[tokio::test]
+    async fn test_shutdown_waits_for_operations_and_refuses_new_ones() {
+        let coordinator: &'static Coordinator = Box::leak(Box::new(Coordinator::new()));
+        let (started, wait_started) = std::sync::mpsc::channel();
+        let running = std::thread::spawn(move || {
+            let _op = coordinator.begin_operation().unwrap();
+            started.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(100));
+            // Steps of an operation already under way still go ahead
+            assert!(coordinator.begin_operation().is_ok());
+        });
+        wait_started.recv().unwrap();
+
+        coordinator.begin();
+        tokio::time::timeout(Duration::from_secs(1), coordinator.requested()).await.unwrap();
+        assert!(matches!(coordinator.begin_operation(), Err(CommandError::Cancelled(_))));
+
+        assert_eq!(coordinator.drain(Duration::from_secs(5)).await, 0);
+        running.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_after_timeout() {
+        let coordinator: &'static Coordinator = Box::leak(Box::new(Coordinator::new()));
+        let (started, wait_started) = std::sync::mpsc::channel();
+        let (release, wait_release) = std::sync::mpsc::channel::<()>();
+        let running = std::thread::spawn(move || {
+            let _op = coordinator.begin_operation().unwrap();
+            started.send(()).unwrap();
+            let _ = wait_release.recv();
+        });
+        wait_started.recv().unwrap();
+
+        coordinator.begin();
+        assert_eq!(coordinator.drain(Duration::from_millis(100)).await, 1, "still running at the deadline");
+        // A refused operation isn't counted
+        assert!(coordinator.begin_operation().is_err());
+        assert_eq!(coordinator.in_flight(), 1);
+
+        release.send(()).unwrap();
+        running.join().unwrap();
+        assert_eq!(coordinator.drain(Duration::from_secs(5)).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_work_raced_against_shutdown_is_dropped() {
+        let coordinator: &'static Coordinator = Box::leak(Box::new(Coordinator::new()));
+        assert!(!coordinator.is_shutting_down());
+
+        // As classify_auto does: the classification never finishes, so Quit wins the race
+        let classification = tokio::spawn(async move {
+            tokio::select! {
+                _ = std::future::pending::<()>() => "classified",
+                _ = coordinator.requested() => "cancelled",
+            }
+        });
+        coordinator.begin();
+        coordinator.begin();
+        assert_eq!(tokio::time::timeout(Duration::from_secs(1), classification).await.unwrap().unwrap(), "cancelled");
+
+        // Waiting once shutdown has already begun returns straight away
+        tokio::time::timeout(Duration::from_secs(1), coordinator.requested()).await.unwrap();
+        assert_eq!(coordinator.in_flight(), 0);
+    }
+}
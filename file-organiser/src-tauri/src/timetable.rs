@@ -0,0 +1,241 @@
+//! University timetable (.ics) import
+//!
+//! Timetable exports list every lecture and tutorial as a calendar event whose summary
+//! carries the course code ("ECON202 Intermediate Macroeconomics - Lecture"). Reading
+//! the codes and names out of those events gives the folder structure a student needs
+//! for the term, plus a seed rule per course so files with the code in their name are
+//! sorted without asking the AI.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Words that describe the kind of session rather than the course
+const SESSION_WORDS: &[&str] = &[
+    "lecture", "lectures", "lec", "tutorial", "tutorials", "tut", "lab", "labs", "laboratory",
+    "seminar", "workshop", "practical", "prac", "class", "exam", "online", "in-person", "stream",
+];
+
+/// A course found in the timetable, with the folder and rule it would get
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimetableCourse {
+    pub code: String,         // e.g. "ECON202"
+    pub name: Option<String>, // e.g. "Intermediate Macroeconomics", if the summaries include it
+    pub folder_name: String,  // e.g. "ECON202 Intermediate Macroeconomics"
+    pub rule_pattern: String, // e.g. "*ECON202*"
+    pub sessions: usize,      // number of events for the course
+}
+
+/// Find the courses in an .ics file's contents, sorted by code
+pub fn parse_ics(content: &str) -> Vec<TimetableCourse> {
+    let mut courses: BTreeMap<String, TimetableCourse> = BTreeMap::new();
+
+    for summary in event_summaries(content) {
+        let Some((code, name)) = parse_summary(&summary) else {
+            continue;
+        };
+        let course = courses.entry(code.clone()).or_insert_with(|| TimetableCourse {
+            folder_name: code.clone(),
+            rule_pattern: format!("*{}*", code),
+            code,
+            name: None,
+            sessions: 0,
+        });
+        course.sessions += 1;
+        // Some events only carry the code; keep the most descriptive name seen
+        if let Some(name) = name {
+            if course.name.as_ref().is_none_or(|n| name.len() > n.len()) {
+                course.folder_name = sanitize_folder_name(&format!("{} {}", course.code, name));
+                course.name = Some(name);
+            }
+        }
+    }
+
+    courses.into_values().collect()
+}
+
+/// The SUMMARY of every VEVENT, unfolded and unescaped
+fn event_summaries(content: &str) -> Vec<String> {
+    // Long lines are folded onto continuation lines that start with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut summaries = Vec::new();
+    let mut in_event = false;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Property parameters follow the name: "SUMMARY;LANGUAGE=en:..."
+        let name = key.split(';').next().unwrap_or(key).trim().to_ascii_uppercase();
+        match (name.as_str(), value.trim()) {
+            ("BEGIN", "VEVENT") => in_event = true,
+            ("END", "VEVENT") => in_event = false,
+            ("SUMMARY", value) if in_event => summaries.push(unescape(value)),
+            _ => {}
+        }
+    }
+    summaries
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push(' '),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Split an event summary into a course code and (if present) the course name
+///
+/// Codes are 2-5 letters followed by 3-4 digits, optionally separated by a space and
+/// followed by a letter ("ECON202", "COMP 1511", "MATH1131A").
+fn parse_summary(summary: &str) -> Option<(String, Option<String>)> {
+    let words: Vec<&str> = summary.split_whitespace().collect();
+
+    let mut found = None;
+    for i in 0..words.len() {
+        let word = trim_punctuation(words[i]);
+        if let Some(code) = as_course_code(word, None) {
+            found = Some((code, i, i));
+            break;
+        }
+        if let Some(next) = words.get(i + 1) {
+            if let Some(code) = as_course_code(word, Some(trim_punctuation(next))) {
+                found = Some((code, i, i + 1));
+                break;
+            }
+        }
+    }
+    let (code, start, end) = found?;
+
+    // Whatever isn't the code, a session word or a parenthesized note is the course name
+    let mut depth = 0i32;
+    let name_words: Vec<&str> = words
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i < start || *i > end)
+        .map(|(_, w)| *w)
+        .filter(|w| {
+            let opens = w.starts_with('(') || w.starts_with('[');
+            let closes = w.ends_with(')') || w.ends_with(']');
+            if opens {
+                depth += 1;
+            }
+            let inside = depth > 0;
+            if closes {
+                depth = (depth - 1).max(0);
+            }
+            !inside
+        })
+        .map(trim_punctuation)
+        .filter(|w| !w.is_empty() && !SESSION_WORDS.contains(&w.to_lowercase().as_str()))
+        .collect();
+
+    let name = name_words.join(" ");
+    let name = (name.chars().filter(|c| c.is_alphabetic()).count() >= 3).then_some(name);
+    Some((code, name))
+}
+
+/// `word` as a course code, or `word` + `next` when the code is written with a space
+fn as_course_code(word: &str, next: Option<&str>) -> Option<String> {
+    let joined = match next {
+        Some(next) => {
+            // "COMP 1511": letters, then a word that starts with digits
+            if !word.chars().all(|c| c.is_ascii_uppercase()) {
+                return None;
+            }
+            format!("{}{}", word, next)
+        }
+        None => word.to_string(),
+    };
+
+    let letters = joined.chars().take_while(|c| c.is_ascii_uppercase()).count();
+    let digits = joined[letters..].chars().take_while(|c| c.is_ascii_digit()).count();
+    let suffix = &joined[letters + digits..];
+    let valid = (2..=5).contains(&letters)
+        && (3..=4).contains(&digits)
+        && (suffix.is_empty() || (suffix.len() == 1 && suffix.chars().all(|c| c.is_ascii_uppercase())));
+    valid.then_some(joined)
+}
+
+fn trim_punctuation(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '&' && c != '+')
+}
+
+/// Drop characters that can't appear in a folder name
+pub fn sanitize_folder_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') && !c.is_control())
+        .collect();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ").trim_end_matches('.').to_string()
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+DTSTART:20250303T090000\r
+SUMMARY:ECON202 Lecture\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY;LANGUAGE=en:ECON202 Intermediate Macroeconomics - Tutorial (Week 2)\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Lecture: COMP 1511 Programming Fundamentals\\, Stream\r
+  A\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Library induction\r
+END:VEVENT\r
+BEGIN:VTODO\r
+SUMMARY:MATH1131 assignment\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_parse_ics_courses() {
+        let courses = parse_ics(SAMPLE);
+        assert_eq!(courses.len(), 2);
+
+        assert_eq!(courses[0].code, "COMP1511");
+        assert_eq!(courses[0].name.as_deref(), Some("Programming Fundamentals A"));
+        assert_eq!(courses[0].rule_pattern, "*COMP1511*");
+
+        assert_eq!(courses[1].code, "ECON202");
+        assert_eq!(courses[1].name.as_deref(), Some("Intermediate Macroeconomics"));
+        assert_eq!(courses[1].folder_name, "ECON202 Intermediate Macroeconomics");
+        assert_eq!(courses[1].sessions, 2);
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        assert_eq!(parse_summary("MATH1131A: Mathematics 1A"), Some(("MATH1131A".to_string(), Some("Mathematics 1A".to_string()))));
+        assert_eq!(parse_summary("PHYS 101 Lab"), Some(("PHYS101".to_string(), None)));
+        // Years and room numbers aren't course codes
+        assert_eq!(parse_summary("Welcome week 2025"), None);
+        assert_eq!(parse_summary("Room B12345 meeting"), None);
+        assert_eq!(sanitize_folder_name("ECON202 Money/Banking: Theory."), "ECON202 MoneyBanking Theory");
+    }
+}
@@ -0,0 +1,204 @@
+//! Topic clustering for unsorted files
+//!
+//! When a lot of files come back `__UNSORTED__`, they often share a topic the user
+//! hasn't made a folder for yet (a new course, a job hunt). Files are embedded from
+//! their name plus a content snippet and grouped greedily by cosine similarity to each
+//! group's centroid; groups big enough to be worth a folder are then named by the AI.
+
+use serde::Serialize;
+use std::path::Path;
+
+// How similar a file must be to a group's centroid to join it
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.75;
+
+// Groups smaller than this aren't worth a new folder
+pub const DEFAULT_MIN_CLUSTER_SIZE: usize = 3;
+
+// Characters of file content included alongside the name
+pub const SNIPPET_CHARS: usize = 300;
+
+// Files beyond this are left out of a single analysis (one embeddings request)
+pub const MAX_FILES: usize = 500;
+
+// How similar two files must be to be reported as near-duplicates
+pub const DEFAULT_DUPLICATE_THRESHOLD: f32 = 0.95;
+
+/// A proposed new folder and the unsorted files that would go in it
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicSuggestion {
+    pub name: String,
+    pub files: Vec<String>, // full paths
+    pub cohesion: f32,      // average similarity of the files to the group centroid
+}
+
+/// Two files whose names and contents embed almost the same
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarPair {
+    pub a: String, // full paths
+    pub b: String,
+    pub similarity: f32,
+}
+
+/// A group of similar files, as indexes into the embedded list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub members: Vec<usize>,
+    pub cohesion: f32,
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Group embeddings by similarity, largest group first
+///
+/// Each embedding joins the group whose centroid it's most similar to, if that's at
+/// least `threshold`, and otherwise starts a new group. Groups with fewer than
+/// `min_size` members are dropped.
+pub fn cluster(embeddings: &[Vec<f32>], threshold: f32, min_size: usize) -> Vec<Cluster> {
+    let mut groups: Vec<(Vec<f32>, Vec<usize>)> = Vec::new(); // (centroid sum, members)
+
+    for (i, embedding) in embeddings.iter().enumerate() {
+        let best = groups
+            .iter()
+            .enumerate()
+            .map(|(g, (sum, _))| (g, cosine_similarity(sum, embedding)))
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best {
+            Some((g, _)) => {
+                let (sum, members) = &mut groups[g];
+                sum.iter_mut().zip(embedding).for_each(|(s, x)| *s += x);
+                members.push(i);
+            }
+            None => groups.push((embedding.clone(), vec![i])),
+        }
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() >= min_size.max(1))
+        .map(|(centroid, members)| {
+            let cohesion = members
+                .iter()
+                .map(|&i| cosine_similarity(&centroid, &embeddings[i]))
+                .sum::<f32>()
+                / members.len() as f32;
+            Cluster { members, cohesion }
+        })
+        .collect();
+    clusters.sort_by(|a, b| b.members.len().cmp(&a.members.len()).then(b.cohesion.total_cmp(&a.cohesion)));
+    clusters
+}
+
+/// Index pairs of embeddings at least `threshold` similar, most similar first
+pub fn similar_pairs(embeddings: &[Vec<f32>], threshold: f32) -> Vec<(usize, usize, f32)> {
+    let mut pairs = Vec::new();
+    for i in 0..embeddings.len() {
+        for j in i + 1..embeddings.len() {
+            let similarity = cosine_similarity(&embeddings[i], &embeddings[j]);
+            if similarity >= threshold {
+                pairs.push((i, j, similarity));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+    pairs
+}
+
+/// The text a file is embedded from: its name with separators spaced out, plus a content snippet
+pub fn embedding_text(path: &Path, content: Option<&str>) -> String {
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().replace(['_', '-', '.'], " "))
+        .unwrap_or_default();
+    match content.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(content) => {
+            let snippet: String = content.chars().take(SNIPPET_CHARS).collect();
+            format!("{}\n{}", name, snippet)
+        }
+        None => name,
+    }
+}
+
+/// Tidy an AI-proposed folder name, rejecting ones that clash with an existing folder
+pub fn clean_folder_name(raw: &str, existing: &[String]) -> Option<String> {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') && !c.is_control())
+        .collect();
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let cleaned = cleaned.trim_matches('.').trim().to_string();
+
+    if cleaned.is_empty() || cleaned == "__UNSORTED__" {
+        return None;
+    }
+    if existing.iter().any(|folder| folder.eq_ignore_ascii_case(&cleaned)) {
+        return None;
+    }
+    Some(cleaned)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_groups_similar_embeddings() {
+        let embeddings = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.95, 0.1, 0.0],
+            vec![0.9, 0.05, 0.1],
+            vec![0.0, 0.0, 1.0],
+            vec![0.1, 0.9, 0.0],
+        ];
+
+        let clusters = cluster(&embeddings, 0.8, 2);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].members, vec![0, 2, 3]);
+        assert_eq!(clusters[1].members, vec![1, 5]);
+        assert!(clusters[0].cohesion > 0.9);
+
+        // Singletons are dropped once the minimum size is above one
+        assert_eq!(cluster(&embeddings, 0.8, 3).len(), 1);
+        assert!(cluster(&[], 0.8, 1).is_empty());
+
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_similar_pairs() {
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.99, 0.05], vec![1.0, 0.0]];
+        let pairs: Vec<(usize, usize)> = similar_pairs(&embeddings, 0.95).iter().map(|&(i, j, _)| (i, j)).collect();
+        assert_eq!(pairs[0], (0, 3));
+        assert_eq!(pairs.len(), 3);
+        assert!(!pairs.contains(&(0, 1)));
+        assert!(similar_pairs(&embeddings[..1], 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_embedding_text_and_folder_name() {
+        let path = Path::new("/Downloads/linear_algebra-ps2.pdf");
+        assert_eq!(embedding_text(path, None), "linear algebra ps2");
+        assert_eq!(embedding_text(path, Some("  ")), "linear algebra ps2");
+        let long = "x".repeat(1000);
+        assert_eq!(embedding_text(path, Some(&long)).len(), "linear algebra ps2\n".len() + SNIPPET_CHARS);
+
+        let existing = vec!["Calculus".to_string()];
+        assert_eq!(clean_folder_name(" Linear / Algebra. ", &existing), Some("Linear Algebra".to_string()));
+        assert_eq!(clean_folder_name("calculus", &existing), None);
+        assert_eq!(clean_folder_name("__UNSORTED__", &existing), None);
+        assert_eq!(clean_folder_name("???", &existing), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! Video metadata and poster frames
+//!
+//! Lecture recordings are hard to tell apart by filename alone. This module asks
+//! ffprobe for duration and resolution and has ffmpeg grab a single scaled-down
+//! frame to show as a thumbnail. Both are external tools (like tesseract for OCR);
+//! when they aren't installed the preview degrades to filename-only.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "mkv", "webm", "avi", "wmv"];
+
+// Poster frames are small: they're shown in the review list, not played
+const POSTER_WIDTH: u32 = 320;
+
+// Skip past title cards / black intro frames, but not too far into short clips
+const MAX_POSTER_OFFSET_SECS: f64 = 30.0;
+
+/// Duration and resolution of a video file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoInfo {
+    pub duration_secs: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+}
+
+/// Check whether a lowercase extension is a supported video container
+pub fn is_video_extension(ext: &str) -> bool {
+    VIDEO_EXTENSIONS.contains(&ext)
+}
+
+/// Read duration and resolution with ffprobe
+pub fn probe(path: &Path) -> Result<VideoInfo, String> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height:format=duration"])
+        .args(["-of", "json"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("ffprobe is not available: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    parse_probe_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse ffprobe's JSON output (split out so it can be tested without ffprobe installed)
+fn parse_probe_output(json: &str) -> Result<VideoInfo, String> {
+    let probe: ProbeOutput =
+        serde_json::from_str(json).map_err(|e| format!("Unexpected ffprobe output: {}", e))?;
+    let stream = probe.streams.first();
+
+    Ok(VideoInfo {
+        duration_secs: probe
+            .format
+            .and_then(|f| f.duration)
+            .and_then(|d| d.parse::<f64>().ok()),
+        width: stream.and_then(|s| s.width),
+        height: stream.and_then(|s| s.height),
+    })
+}
+
+/// Pick where to grab the poster frame: 10% in, capped at MAX_POSTER_OFFSET_SECS
+fn poster_offset(duration_secs: Option<f64>) -> f64 {
+    match duration_secs {
+        Some(d) if d > 0.0 => (d * 0.1).min(MAX_POSTER_OFFSET_SECS),
+        _ => 0.0,
+    }
+}
+
+/// Extract a single JPEG frame with ffmpeg, returned as raw bytes
+pub fn poster_frame(path: &Path, duration_secs: Option<f64>) -> Result<Vec<u8>, String> {
+    let offset = format!("{:.2}", poster_offset(duration_secs));
+    let scale = format!("scale={}:-2", POSTER_WIDTH);
+
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-ss", &offset, "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-vf", &scale])
+        .args(["-f", "image2pipe", "-vcodec", "mjpeg", "-"])
+        .output()
+        .map_err(|e| format!("ffmpeg is not available: {}", e))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!(
+            "ffmpeg could not extract a frame: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_output() {
+        let json = r#"{
+            "programs": [],
+            "streams": [{ "width": 1920, "height": 1080 }],
+            "format": { "duration": "3605.120000" }
+        }"#;
+        let info = parse_probe_output(json).unwrap();
+        assert_eq!(info.width, Some(1920));
+        assert_eq!(info.height, Some(1080));
+        assert!((info.duration_secs.unwrap() - 3605.12).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_parse_probe_output_audio_only() {
+        let info = parse_probe_output(r#"{ "format": { "duration": "N/A" } }"#).unwrap();
+        assert_eq!(
+            info,
+            VideoInfo { duration_secs: None, width: None, height: None }
+        );
+        assert!(parse_probe_output("not json").is_err());
+    }
+
+    #[test]
+    fn test_poster_offset() {
+        assert_eq!(poster_offset(None), 0.0);
+        assert_eq!(poster_offset(Some(20.0)), 2.0);
+        assert_eq!(poster_offset(Some(3600.0)), MAX_POSTER_OFFSET_SECS);
+    }
+}
@@ -0,0 +1,300 @@
+//! Destination folders on drives that come and go
+//!
+//! Course folders often live on an external drive or a network share. When it's unplugged,
+//! every move into it fails on its own, one error per file. Destinations are checked first
+//! instead: organize runs park the files bound for a missing drive as deferred session
+//! items, and applying a reviewed plan stops with one error naming the drives. The drives
+//! are then watched from a background thread, and the parked moves go ahead once they're back.
+//!
+//! Only folders on a removable or network volume are checked: a UNC share, or a Windows
+//! drive letter, /Volumes/..., /media/..., /run/media/... or /mnt/... that isn't a fixed
+//! disk. That's decided from the drive itself where it's connected (the drive type on
+//! Windows; on Linux the mount's filesystem and device, so WSL's /mnt/c stays a fixed
+//! disk), and a drive letter or mount folder that isn't there at all counts as unplugged.
+//! A folder on a fixed disk that's gone was deleted, not unplugged, and fails like it
+//! always did.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// How often the background thread looks for drives that were missing
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Volumes an organize run found missing, until they're back
+static WAITING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// The removable or network volume `path` is on, e.g. "E:\", "\\nas\courses",
+/// "/Volumes/USB" or "/media/me/USB" (None for folders on a fixed disk)
+pub fn volume_root(path: &Path) -> Option<PathBuf> {
+    #[cfg(target_os = "linux")]
+    if let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") {
+        let mounts = parse_mounts(&mounts);
+        if let Some(mount) = mount_of(&mounts, path) {
+            if let Some(removable) = removable_mount(mount, removable_device) {
+                return removable.then(|| mount.point.clone());
+            }
+        }
+    }
+    candidate_root(path).filter(|root| !is_fixed(root))
+}
+
+/// The volume `path` would be on if it were removable, going by the path alone (a drive
+/// letter other than C:, a share, or a folder where removable drives are mounted)
+fn candidate_root(path: &Path) -> Option<PathBuf> {
+    // Parsed by hand rather than with Path::components, so Windows paths read the same on every OS
+    let text = path.to_string_lossy();
+    let text = match text.strip_prefix("\\\\?\\UNC\\") {
+        Some(share) => format!("\\\\{}", share),
+        None => text.strip_prefix("\\\\?\\").unwrap_or(&text).to_string(),
+    };
+    let bytes = text.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return (!bytes[0].eq_ignore_ascii_case(&b'C')).then(|| PathBuf::from(format!("{}:\\", bytes[0] as char)));
+    }
+    if let Some(share) = text.strip_prefix("\\\\") {
+        let mut parts = share.split('\\').filter(|part| !part.is_empty());
+        let (server, name) = (parts.next()?, parts.next()?);
+        return Some(PathBuf::from(format!("\\\\{}\\{}", server, name)));
+    }
+
+    let parts: Vec<&str> = text.split('/').filter(|part| !part.is_empty()).collect();
+    let depth = match parts.as_slice() {
+        ["Volumes", ..] | ["mnt", ..] => 2,
+        ["media", ..] => 3,
+        ["run", "media", ..] => 4,
+        _ => return None,
+    };
+    (parts.len() >= depth).then(|| PathBuf::from(format!("/{}", parts[..depth].join("/"))))
+}
+
+// GetDriveTypeW's answer for a hard disk
+#[cfg(windows)]
+const DRIVE_FIXED: u32 = 3;
+
+/// Whether a connected drive letter is a hard disk (not a USB stick, card, disc or network drive)
+#[cfg(windows)]
+fn is_fixed(root: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+    }
+    if root.to_string_lossy().starts_with("\\") {
+        return false;
+    }
+    let wide: Vec<u16> = root.as_os_str().encode_wide().chain(Some(0)).collect();
+    // Safety: `wide` is a NUL-terminated path that outlives the call
+    unsafe { GetDriveTypeW(wide.as_ptr()) == DRIVE_FIXED }
+}
+
+/// Whether a mount folder is just a folder on the disk around it (it exists, and isn't a
+/// mount point) rather than where a drive is or was mounted
+#[cfg(unix)]
+fn is_fixed(root: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let device = |path: &Path| std::fs::metadata(path).map(|meta| meta.dev()).ok();
+    let Some(root_device) = device(root) else {
+        return false;
+    };
+    // macOS lists the startup disk in /Volumes too, as a link to /
+    root_device == device(Path::new("/")).unwrap_or_default()
+        || root.parent().and_then(device).is_some_and(|parent| parent == root_device)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_fixed(_root: &Path) -> bool {
+    false
+}
+
+// Filesystems on another computer
+const NETWORK_FILESYSTEMS: &[&str] =
+    &["nfs", "nfs4", "cifs", "smb3", "smbfs", "afpfs", "davfs", "fuse.sshfs", "fuse.rclone"];
+
+// How WSL mounts the Windows drives (/mnt/c): hard disks of the Windows side
+const WINDOWS_DRIVE_FILESYSTEMS: &[&str] = &["9p", "drvfs", "v9fs"];
+
+/// One line of /proc/self/mounts
+#[derive(Debug, Clone, PartialEq)]
+struct Mount {
+    device: String,
+    point: PathBuf,
+    fs_type: String,
+}
+
+fn parse_mounts(text: &str) -> Vec<Mount> {
+    // Spaces and tabs in mount points are written as octal escapes
+    let unescape = |field: &str| field.replace("\\040", " ").replace("\\011", "\t").replace("\\134", "\\");
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (device, point, fs_type) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(Mount { device: unescape(device), point: PathBuf::from(unescape(point)), fs_type: fs_type.to_string() })
+        })
+        .collect()
+}
+
+/// The innermost mount `path` is on, other than the root filesystem
+fn mount_of<'a>(mounts: &'a [Mount], path: &Path) -> Option<&'a Mount> {
+    mounts
+        .iter()
+        .filter(|mount| mount.point != Path::new("/") && path.starts_with(&mount.point))
+        .max_by_key(|mount| mount.point.components().count())
+}
+
+/// Whether a mount is a removable or network drive (None for what isn't a drive, like the
+/// tmpfs at /run, so the folder's own path decides)
+fn removable_mount(mount: &Mount, removable_device: impl Fn(&str) -> bool) -> Option<bool> {
+    if NETWORK_FILESYSTEMS.contains(&mount.fs_type.as_str()) {
+        return Some(true);
+    }
+    if WINDOWS_DRIVE_FILESYSTEMS.contains(&mount.fs_type.as_str()) {
+        return Some(false);
+    }
+    let name = Path::new(&mount.device).file_name()?.to_string_lossy().to_string();
+    mount.device.starts_with("/dev/").then(|| removable_device(&name))
+}
+
+/// Whether a block device (e.g. "sdb1") is on a removable disk, a USB bus or a card reader
+#[cfg(target_os = "linux")]
+fn removable_device(name: &str) -> bool {
+    let Ok(device) = std::fs::canonicalize(Path::new("/sys/class/block").join(name)) else {
+        return false;
+    };
+    // Many USB disks don't call themselves removable, so the bus they're on counts too
+    let sys_path = device.to_string_lossy();
+    if sys_path.contains("/usb") || sys_path.contains("/mmc") {
+        return true;
+    }
+    // A partition's flag is on its disk
+    let disk = if device.join("partition").exists() { device.parent() } else { Some(device.as_path()) };
+    disk.and_then(|disk| std::fs::read_to_string(disk.join("removable")).ok()).is_some_and(|flag| flag.trim() == "1")
+}
+
+/// The volumes of `folders` that aren't connected, each once and sorted
+pub fn unavailable<'a>(folders: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let missing: BTreeSet<String> = folders
+        .into_iter()
+        .filter_map(|folder| volume_root(Path::new(folder)))
+        .filter(|root| !root.is_dir())
+        .map(|root| root.to_string_lossy().to_string())
+        .collect();
+    missing.into_iter().collect()
+}
+
+/// Watch `volumes` until they're connected again
+pub fn wait_for(volumes: &[String]) {
+    let mut waiting = WAITING.lock().unwrap_or_else(|e| e.into_inner());
+    waiting.extend(volumes.iter().cloned());
+}
+
+/// Take the volumes in `waiting` that `connected` says are back
+pub fn reconnected(waiting: &mut BTreeSet<String>, connected: impl Fn(&Path) -> bool) -> Vec<String> {
+    let back: Vec<String> = waiting.iter().filter(|volume| connected(Path::new(volume))).cloned().collect();
+    for volume in &back {
+        waiting.remove(volume);
+    }
+    back
+}
+
+/// Check the volumes being waited for every CHECK_INTERVAL on a background thread and call
+/// `on_back` with the ones that have been connected again
+pub fn spawn_thread(on_back: impl Fn(Vec<String>) + Send + 'static) {
+    let spawned = std::thread::Builder::new()
+        .name("volume-watchdog".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let back = {
+                let mut waiting = WAITING.lock().unwrap_or_else(|e| e.into_inner());
+                reconnected(&mut waiting, Path::is_dir)
+            };
+            if !back.is_empty() {
+                println!("[VOLUMES] Connected again: {}", back.join(", "));
+                on_back(back);
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("[VOLUMES] Failed to start volume watchdog thread: {}", e);
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(path: &str) -> Option<String> {
+        candidate_root(Path::new(path)).map(|root| root.to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn test_candidate_root() {
+        assert_eq!(root("E:\\Uni\\ML"), Some("E:\\".to_string()));
+        assert_eq!(root("\\\\nas\\courses\\ML"), Some("\\\\nas\\courses".to_string()));
+        assert_eq!(root("\\\\?\\E:\\Uni"), Some("E:\\".to_string()));
+        assert_eq!(root("\\\\?\\UNC\\nas\\courses\\ML"), Some("\\\\nas\\courses".to_string()));
+        assert_eq!(root("/Volumes/USB/Uni/ML"), Some("/Volumes/USB".to_string()));
+        assert_eq!(root("/media/me/USB/Uni"), Some("/media/me/USB".to_string()));
+        assert_eq!(root("/run/media/me/USB/Uni"), Some("/run/media/me/USB".to_string()));
+        assert_eq!(root("/mnt/backup/Uni"), Some("/mnt/backup".to_string()));
+        // The system disk is never unplugged
+        assert_eq!(root("C:\\Users\\me\\Uni"), None);
+        assert_eq!(root("/home/me/Uni"), None);
+        assert_eq!(root("/Volumes"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_existing_folder_is_fixed() {
+        // A folder that's there without a drive mounted on it is on the disk around it
+        let dir = std::env::temp_dir().join("fileorg_test_volumes_fixed");
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(is_fixed(&dir));
+        assert!(!is_fixed(Path::new("/media/fileorg_test_unplugged")), "a missing mount folder was unplugged");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_removable_mounts() {
+        let mounts = parse_mounts(
+            "/dev/sda2 / ext4 rw,relatime 0 0\n\
+             tmpfs /run tmpfs rw,nosuid 0 0\n\
+             C:\\134 /mnt/c 9p rw,noatime 0 0\n\
+             /dev/sdb1 /mnt/data ext4 rw 0 0\n\
+             /dev/sdc1 /run/media/me/USB\\040Stick vfat rw 0 0\n\
+             nas:/courses /mnt/nas nfs4 rw 0 0\n",
+        );
+        assert_eq!(mounts.len(), 6);
+        assert_eq!(mounts[4].point, Path::new("/run/media/me/USB Stick"));
+
+        let usb = |name: &str| name == "sdc1";
+        let removable = |path: &str| mount_of(&mounts, Path::new(path)).and_then(|m| removable_mount(m, usb));
+        // WSL's Windows drives and other hard disks are fixed
+        assert_eq!(removable("/mnt/c/Users/me/Uni"), Some(false));
+        assert_eq!(removable("/mnt/data/Uni"), Some(false));
+        assert_eq!(removable("/run/media/me/USB Stick/Uni"), Some(true));
+        assert_eq!(removable("/mnt/nas/ML"), Some(true));
+        // An unplugged drive's folder is only under /run's tmpfs, so its path decides
+        assert_eq!(removable("/run/media/me/Other/Uni"), None);
+        assert_eq!(removable("/home/me/Uni"), None);
+    }
+
+    #[test]
+    fn test_unavailable_and_reconnected() {
+        let folders = vec![
+            "/Volumes/fileorg_test_unplugged/Uni/ML".to_string(),
+            "/Volumes/fileorg_test_unplugged/Uni/Econ".to_string(),
+            "/home/me/Uni/Stats".to_string(),
+        ];
+        assert_eq!(unavailable(&folders), vec!["/Volumes/fileorg_test_unplugged"]);
+
+        let mut waiting: BTreeSet<String> = ["E:\\".to_string(), "/Volumes/USB".to_string()].into();
+        assert_eq!(reconnected(&mut waiting, |volume| volume == Path::new("E:\\")), vec!["E:\\"]);
+        assert_eq!(waiting.into_iter().collect::<Vec<_>>(), vec!["/Volumes/USB"]);
+    }
+}
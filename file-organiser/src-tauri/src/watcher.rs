@@ -1,6 +1,8 @@
 // File watcher module for detecting new files in watched directories
 // Uses notify crate with debouncing to avoid duplicate events
+// Files in subfolders a watch profile excludes (node_modules, .git, ...) are ignored
 
+use notify::event::ModifyKind;
 use notify::{Event, EventKind, RecursiveMode, Watcher};
 use notify_debouncer_full::{new_debouncer, DebounceEventResult};
 use serde::{Deserialize, Serialize};
@@ -47,15 +49,15 @@ pub struct FileInfo {
 ///
 /// # Arguments
 /// * `app_handle` - Tauri app handle for emitting events
-/// * `watch_path` - Path to directory to watch
+/// * `watch_paths` - Directories to watch (the main watched folder plus any profile folders)
 ///
 /// # How it works:
 /// 1. Creates a debounced file watcher (waits 2 seconds after last change)
 /// 2. Watches for file creation events only (not modifications)
 /// 3. When file is stable (no changes for 2s), emits "file-detected" event
 /// 4. Frontend receives event and can display the file
-pub fn start_watcher(app_handle: AppHandle, watch_path: String) -> Result<(), String> {
-    println!("[WATCHER] Starting to watch: {}", watch_path);
+pub fn start_watcher(app_handle: AppHandle, watch_paths: Vec<String>) -> Result<(), String> {
+    println!("[WATCHER] Starting to watch: {}", watch_paths.join(", "));
 
     // Reset stop signal before starting
     reset_stop_signal();
@@ -93,13 +95,18 @@ pub fn start_watcher(app_handle: AppHandle, watch_path: String) -> Result<(), St
             }
         };
 
-        // Start watching the directory (non-recursive - only top level)
-        if let Err(e) = debouncer
-            .watcher()
-            .watch(&PathBuf::from(&watch_path), RecursiveMode::NonRecursive)
-        {
-            eprintln!("[WATCHER] Failed to watch directory: {}", e);
-            return;
+        // Start watching each directory (non-recursive - only top level)
+        for (i, watch_path) in watch_paths.iter().enumerate() {
+            if let Err(e) = debouncer
+                .watcher()
+                .watch(&PathBuf::from(watch_path), RecursiveMode::NonRecursive)
+            {
+                eprintln!("[WATCHER] Failed to watch directory {}: {}", watch_path, e);
+                // The main folder has to work; extra profile folders are skipped
+                if i == 0 {
+                    return;
+                }
+            }
         }
 
         println!("[WATCHER] Watching started successfully");
@@ -142,12 +149,19 @@ fn handle_file_event(app_handle: &AppHandle, event: &Event) {
         EventKind::Create(_) => {
             // File was created
             for path in &event.paths {
-                // Only process files, not directories
-                if path.is_file() {
+                // Only process files, not directories, and none in excluded subfolders
+                if path.is_file() && !crate::is_watch_excluded(path) {
                     process_new_file(app_handle, path);
                 }
             }
         }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+            // Keep the file index from counting files that were deleted or moved away
+            for path in event.paths.iter().filter(|p| !p.exists()) {
+                crate::unindex_file(path);
+                crate::unqueue_file(path);
+            }
+        }
         _ => {
             // Ignore modify, delete, access, etc.
         }
@@ -156,12 +170,13 @@ fn handle_file_event(app_handle: &AppHandle, event: &Event) {
 
 /// Process a newly detected file and emit event to frontend
 fn process_new_file(app_handle: &AppHandle, path: &PathBuf) {
-    // Extract filename
+    // Extract filename, composed (NFC) so macOS's decomposed names match rules and
+    // corrections typed elsewhere; the path stays byte-exact so the file can be found
     let filename = path
         .file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+        .map(crate::filename::nfc)
+        .unwrap_or_else(|| "unknown".to_string());
 
     // Get file size
     let size = std::fs::metadata(path)
@@ -176,6 +191,8 @@ fn process_new_file(app_handle: &AppHandle, path: &PathBuf) {
     };
 
     println!("[WATCHER] Detected file: {} ({} bytes)", filename, size);
+    crate::index_detected_file(path);
+    crate::queue_detected_file(&file_info);
 
     // Emit event to frontend
     if let Err(e) = app_handle.emit("file-detected", &file_info) {
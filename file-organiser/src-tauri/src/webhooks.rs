@@ -0,0 +1,170 @@
+//! Webhook notifications for organize events
+//!
+//! When a URL is configured, every automatic move and every finished organize session
+//! is POSTed to it as JSON, so activity can feed Discord, Slack or n8n automations.
+//! Payloads carry the event's fields plus a one-line summary under both `text` (Slack)
+//! and `content` (Discord), so chat webhooks show something readable without a relay.
+//! Delivery is best-effort: failures are logged and never block a move.
+
+use crate::db::{Database, DbError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const WEBHOOK_SETTING: &str = "webhook_settings";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Persisted webhook configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    pub enabled: bool,
+    pub url: String,
+}
+
+/// Something that happened, as sent to the webhook
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    FileMoved {
+        filename: String,
+        source_path: String,
+        dest_path: String,
+        confidence: Option<f32>,
+        origin: String, // "rule", "ai", "correction", "bucket", ...
+    },
+    SessionCompleted {
+        session_id: i64,
+        label: String,
+        moved: i64,
+        skipped: usize,
+        failed: usize,
+    },
+    Test,
+}
+
+impl WebhookEvent {
+    /// One-line human-readable description
+    pub fn summary(&self) -> String {
+        match self {
+            WebhookEvent::FileMoved { filename, dest_path, .. } => {
+                let folder = std::path::Path::new(dest_path)
+                    .parent()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                format!("Moved {} → {}", filename, folder)
+            }
+            WebhookEvent::SessionCompleted { label, moved, skipped, failed, .. } => {
+                let mut summary = format!("{}: {} moved", label, moved);
+                if *skipped > 0 {
+                    summary.push_str(&format!(", {} skipped", skipped));
+                }
+                if *failed > 0 {
+                    summary.push_str(&format!(", {} failed", failed));
+                }
+                summary
+            }
+            WebhookEvent::Test => "Test notification from File Organiser".to_string(),
+        }
+    }
+}
+
+/// Load the stored configuration (disabled if none has been saved, or it can't be parsed)
+pub fn load(db: &Database) -> Result<WebhookSettings, DbError> {
+    Ok(db
+        .get_setting(WEBHOOK_SETTING)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn save(db: &Database, settings: &WebhookSettings) -> Result<(), DbError> {
+    let json = serde_json::to_string(settings).map_err(|e| DbError::QueryFailed(e.to_string()))?;
+    db.set_setting(WEBHOOK_SETTING, &json)
+}
+
+/// Check a configuration before it's saved, returning it with the URL trimmed
+pub fn validate(mut settings: WebhookSettings) -> Result<WebhookSettings, String> {
+    settings.url = settings.url.trim().to_string();
+    if settings.enabled && settings.url.is_empty() {
+        return Err("Enter a webhook URL first".to_string());
+    }
+    if !settings.url.is_empty() && !settings.url.starts_with("https://") && !settings.url.starts_with("http://") {
+        return Err("The webhook URL must start with https://".to_string());
+    }
+    Ok(settings)
+}
+
+/// The JSON body for an event: its fields, when it happened and a readable summary
+pub fn payload(event: &WebhookEvent, timestamp: i64) -> serde_json::Value {
+    let mut body = serde_json::to_value(event).unwrap_or_else(|_| serde_json::json!({}));
+    if let Some(fields) = body.as_object_mut() {
+        let summary = event.summary();
+        fields.insert("timestamp".to_string(), timestamp.into());
+        fields.insert("text".to_string(), summary.clone().into());
+        fields.insert("content".to_string(), summary.into());
+    }
+    body
+}
+
+/// POST an event to `url`
+pub async fn send(url: &str, event: &WebhookEvent, timestamp: i64) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .post(url)
+        .json(&payload(event, timestamp))
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let moved = WebhookEvent::FileMoved {
+            filename: "PS2.pdf".to_string(),
+            source_path: "/Downloads/PS2.pdf".to_string(),
+            dest_path: "/Uni/Econ/PS2.pdf".to_string(),
+            confidence: Some(0.95),
+            origin: "ai".to_string(),
+        };
+        let body = payload(&moved, 1700000000000);
+        assert_eq!(body["event"], "file_moved");
+        assert_eq!(body["dest_path"], "/Uni/Econ/PS2.pdf");
+        assert_eq!(body["timestamp"], 1700000000000i64);
+        assert_eq!(body["text"], "Moved PS2.pdf → Econ");
+        assert_eq!(body["content"], body["text"]);
+
+        let session = WebhookEvent::SessionCompleted {
+            session_id: 3,
+            label: "Organize /Downloads (5 files)".to_string(),
+            moved: 3,
+            skipped: 2,
+            failed: 0,
+        };
+        assert_eq!(session.summary(), "Organize /Downloads (5 files): 3 moved, 2 skipped");
+        assert_eq!(payload(&WebhookEvent::Test, 0)["event"], "test");
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let settings = |enabled: bool, url: &str| WebhookSettings { enabled, url: url.to_string() };
+        assert_eq!(validate(settings(true, " https://hooks.slack.com/x ")).unwrap().url, "https://hooks.slack.com/x");
+        assert!(validate(settings(true, "")).is_err());
+        assert!(validate(settings(false, "")).is_ok());
+        assert!(validate(settings(false, "ftp://x")).is_err());
+    }
+}
@@ -14,6 +14,8 @@ export const STORAGE_KEYS = {
   autoMoveThreshold: "fileorg_auto_move_threshold",
   notificationsEnabled: "fileorg_notifications_enabled",
   lastScanFolder: "fileorg_last_scan_folder",
+  markdownExportFolder: "fileorg_markdown_export_folder",
+  openWithApp: "fileorg_open_with_app",
   windowState: "fileorg_window_state",
   theme: "fileorg_theme",
   apiKey: "fileorg_api_key",
@@ -37,6 +39,7 @@ export const CONFIDENCE_THRESHOLD = 0.7;
 // File type extensions
 export const IMAGE_EXTENSIONS = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
 export const CONTENT_EXTRACTABLE_EXTENSIONS = ["pdf", "txt", "md", "csv"];
+export const ARCHIVE_EXTENSIONS = ["zip"];
 
 // Retry delays for file-in-use scenarios (ms)
 export const QUICK_RETRY_DELAYS = [2000, 5000, 10000, 30000, 60000];
@@ -40,3 +40,18 @@ export function isDuplicateError(error) {
   return msg.includes("duplicateexists") || msg.includes("duplicate_exists") ||
     msg.includes("file already exists") || msg.includes("already exists");
 }
+
+// Raised when the user cancelled a large copy/move from the progress bar
+export function isCancelledError(error) {
+  return getErrorMessage(error).startsWith("cancelled ");
+}
+
+// Raised when undo finds the moved file was edited or swapped for another since the move
+export function isChangedSinceMoveError(error) {
+  return getErrorMessage(error).startsWith("changedsincemove ");
+}
+
+// Raised when the conflict policy left a file in place because the destination already has it
+export function isSkippedError(error) {
+  return getErrorMessage(error).startsWith("skipped ");
+}
@@ -1,8 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import { open } from "@tauri-apps/plugin-dialog";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { getCurrentWindow, LogicalSize, LogicalPosition } from "@tauri-apps/api/window";
-import { openPath } from "@tauri-apps/plugin-opener";
 
 // state.js documents all mutable state in one place for future refactoring
 // import state from "./state.js";
@@ -20,11 +19,27 @@ import {
 } from "./constants.js";
 import {
   formatFileSize,
+  formatDuration,
+  formatFileOpProgress,
+  summarizeFolderStats,
+  describeTimeSaved,
+  describeTruncation,
+  describeConfidence,
+  describeExplanation,
+  describeLink,
+  describeRestore,
+  describeLinkReport,
+  flattenDiskUsage,
+  summarizeOrganizeResult,
+  describeDeferredMoves,
+  describeFolderSuggestion,
+  describePaperId,
+  parseExifDate,
+  describeExif,
   escapeHtml,
   getFileExt,
   getFileTypeIcon,
   isImageFile,
-  isContentExtractable,
   isToday,
   shouldGroupAsBatch,
   validateModuleName,
@@ -32,10 +47,18 @@ import {
   filterNewFiles,
   getCachedClassification,
   matchRule,
+  findWatchProfile,
+  rulesForProfile,
+  getFolderPref,
+  getQuarantineReason,
+  applyNamingTemplate,
+  buildQuickSortItem,
+  buildAllowedRoots,
   pathJoin,
   pathBasename,
+  describeBudget,
 } from "./utils.js";
-import { getErrorMessage, isLockedFileError, isDuplicateError } from "./errors.js";
+import { getErrorMessage, isLockedFileError, isDuplicateError, isSkippedError, isCancelledError, isChangedSinceMoveError } from "./errors.js";
 import { showOnboardingScreen, initOnboarding } from "./onboarding.js";
 import { showSettingsScreen, initSettings } from "./settings.js";
 import {
@@ -45,8 +68,11 @@ import {
   getActivityLog as dbGetActivityLog,
   markActivityUndone as dbMarkActivityUndone,
   clearActivityLog as dbClearActivityLog,
+  startSession as dbStartSession,
+  endSession as dbEndSession,
   migrateFromLocalStorage,
   getRules as dbGetRules,
+  getFolderPrefs as dbGetFolderPrefs,
 } from "./storage.js";
 
 // ============================================================
@@ -72,6 +98,11 @@ let notificationsEnabled = false;
 let notificationApi = null;
 let darkModeEnabled = false;
 let classificationRules = [];
+let folderPrefs = [];
+let quarantineSettings = { folder: null, enabled: false };
+let classificationMode = { mode: "ai", bucket_folder: null };
+let localOnly = { enabled: false, provider: "openai", ai_available: true };
+let watchProfiles = [];
 
 function applyTheme() {
   document.documentElement.setAttribute("data-theme", darkModeEnabled ? "dark" : "light");
@@ -146,6 +177,7 @@ window.addEventListener("DOMContentLoaded", () => {
       onComplete() {
         userModules = onboardingState.userModules;
         basePath = onboardingState.basePath;
+        syncAllowedRoots();
         showAppScreen();
       }
     }));
@@ -188,6 +220,12 @@ async function loadSavedConfig() {
     correctionLog = await dbGetCorrections();
     activityLog = await dbGetActivityLog();
     try { classificationRules = await dbGetRules(); } catch (e) { classificationRules = []; }
+    folderPrefs = await dbGetFolderPrefs();
+    try { quarantineSettings = await invoke("get_quarantine_settings"); } catch (e) { console.warn("[QUARANTINE] Could not load settings:", e); }
+    try { classificationMode = await invoke("get_classification_mode"); } catch (e) { console.warn("[MODE] Could not load classification mode:", e); }
+    try { localOnly = await invoke("get_local_only"); } catch (e) { console.warn("[LOCAL] Could not load local-only mode:", e); }
+    try { watchProfiles = await invoke("list_watch_profiles"); } catch (e) { console.warn("[PROFILES] Could not load watch profiles:", e); }
+    await syncAllowedRoots();
 
     // Migrate API key from localStorage to secure Rust-side storage (one-time)
     const oldApiKey = localStorage.getItem(STORAGE_KEYS.apiKey);
@@ -205,6 +243,17 @@ async function loadSavedConfig() {
   }
 }
 
+// Keep Rust's allowed folders for moves, renames, trashing and previews in line with the config
+async function syncAllowedRoots() {
+  const roots = buildAllowedRoots({ watchPath, basePath, watchProfiles, quarantineSettings, classificationMode });
+  if (roots.length === 0) return;
+  try {
+    await invoke("set_allowed_roots", { roots });
+  } catch (e) {
+    console.warn("[ROOTS] Could not set allowed folders:", e);
+  }
+}
+
 // Initialize the notification API (dynamic import)
 async function initNotifications() {
   try {
@@ -250,9 +299,9 @@ function sendAppNotification(title, body) {
 }
 
 // Save a correction to the log (async, uses SQLite)
-async function logCorrection(filename, aiSuggested, userChose, type) {
-  // Save to SQLite database
-  await dbAddCorrection(filename, aiSuggested, userChose, type);
+async function logCorrection(filename, aiSuggested, userChose, type, confidence = null) {
+  // Save to SQLite database (the confidence feeds calibration of later suggestions)
+  await dbAddCorrection(filename, aiSuggested, userChose, type, confidence);
   // Update in-memory log
   correctionLog = await dbGetCorrections();
   console.log(`[CORRECTION] ${type}: "${filename}" | AI said "${aiSuggested}" → User chose "${userChose}"`);
@@ -261,17 +310,17 @@ async function logCorrection(filename, aiSuggested, userChose, type) {
 // buildCorrectionHistory is imported from utils.js
 
 // Save an activity log entry (async, uses SQLite)
-async function addActivityEntry(filename, fromFolder, toFolder, originalFilename = null) {
+async function addActivityEntry(filename, fromFolder, toFolder, originalFilename = null, sourcePath = null, sessionId = null, confidence = null) {
   // Save to SQLite database
-  const entry = await dbAddActivity(filename, fromFolder, toFolder, originalFilename);
+  const entry = await dbAddActivity(filename, fromFolder, toFolder, originalFilename, sourcePath, sessionId, confidence);
   // Update in-memory log
   activityLog = await dbGetActivityLog();
   return entry;
 }
 
-// Mark the most recent activity entry as undone (async, uses SQLite)
-async function markActivityUndone(timestamp) {
-  await dbMarkActivityUndone(timestamp);
+// Mark an activity entry as undone by id (async, uses SQLite)
+async function markActivityUndone(activityId) {
+  await dbMarkActivityUndone(activityId);
   // Update in-memory log
   activityLog = await dbGetActivityLog();
 }
@@ -279,6 +328,20 @@ async function markActivityUndone(timestamp) {
 // ============================================================
 // MAIN APP SCREEN
 // ============================================================
+// Unsorted-file count for the watched folder, read from the file index (no folder listing)
+async function refreshUnsortedCount() {
+  if (!watchPath) return;
+  try {
+    const [summary] = await invoke("get_file_index_summary", { folders: [watchPath] });
+    const el = document.querySelector("#config-unsorted");
+    if (el && summary && summary.unsorted > 0) {
+      el.textContent = ` (${summary.unsorted} unsorted)`;
+    }
+  } catch (e) {
+    console.error("Failed to read file index:", e);
+  }
+}
+
 function showAppScreen() {
   document.getElementById("onboarding-screen").style.display = "none";
   document.getElementById("settings-screen").style.display = "none";
@@ -294,7 +357,8 @@ function updateConfigSummary() {
   const watchDisplay = watchPath ? pathBasename(watchPath) : "Not set";
   const moduleCount = userModules.length;
   const autoMoveDisplay = autoMoveEnabled ? `Auto-move: ${Math.round(autoMoveThreshold * 100)}%+` : "Auto-move: off";
-  summary.innerHTML = `<strong>Watching:</strong> ${escapeHtml(watchDisplay)} &nbsp;|&nbsp; <strong>${moduleCount}</strong> modules &nbsp;|&nbsp; ${autoMoveDisplay}`;
+  summary.innerHTML = `<strong>Watching:</strong> ${escapeHtml(watchDisplay)}<span id="config-unsorted"></span> &nbsp;|&nbsp; <strong>${moduleCount}</strong> modules &nbsp;|&nbsp; ${autoMoveDisplay}`;
+  refreshUnsortedCount();
 
   // Update scan button state based on modules
   const scanFolderBtn = document.querySelector("#scan-folder-btn");
@@ -325,16 +389,28 @@ function initApp() {
   const activityList = document.querySelector("#activity-list");
   const activityCount = document.querySelector("#activity-count");
   const clearActivityBtn = document.querySelector("#clear-activity-btn");
+  const searchInput = document.querySelector("#search-input");
+  const searchBtn = document.querySelector("#search-btn");
+  const searchResults = document.querySelector("#search-results");
   const undoToast = document.querySelector("#undo-toast");
   const undoToastMsg = document.querySelector("#undo-toast-msg");
   const undoBtn = document.querySelector("#undo-btn");
   const undoCountdownEl = document.querySelector("#undo-countdown");
   const undoProgress = document.querySelector("#undo-progress");
   const scanFolderBtn = document.querySelector("#scan-folder-btn");
+  const reviewQuarantineBtn = document.querySelector("#review-quarantine-btn");
+  const suggestFoldersBtn = document.querySelector("#suggest-folders-btn");
+  const folderSuggestions = document.querySelector("#folder-suggestions");
+  const organizeFolderBtn = document.querySelector("#organize-folder-btn");
   const scanProgress = document.querySelector("#scan-progress");
   const scanProgressCount = document.querySelector("#scan-progress-count");
   const scanProgressFill = document.querySelector("#scan-progress-fill");
   const scanCancelBtn = document.querySelector("#scan-cancel-btn");
+  const fileOpProgress = document.querySelector("#file-op-progress");
+  const fileOpProgressText = document.querySelector("#file-op-progress-text");
+  const fileOpProgressFill = document.querySelector("#file-op-progress-fill");
+  const fileOpCancelBtn = document.querySelector("#file-op-cancel-btn");
+  let currentFileOpId = null;
   const scanLimitSelect = document.querySelector("#scan-limit");
   const scanLimitCustom = document.querySelector("#scan-limit-custom");
   let scanInProgress = false;
@@ -366,14 +442,220 @@ function initApp() {
     renderActivityLog();
   });
 
+  // Export the day's/week's moves as a Markdown note (e.g. into an Obsidian vault)
+  document.querySelector("#export-activity-md-btn").addEventListener("click", async () => {
+    const period = document.querySelector("#activity-export-period").value;
+    try {
+      const folder = await open({
+        directory: true,
+        multiple: false,
+        title: "Select notes folder (e.g. your Obsidian vault)",
+        defaultPath: localStorage.getItem(STORAGE_KEYS.markdownExportFolder) || undefined,
+      });
+      if (!folder) return;
+      localStorage.setItem(STORAGE_KEYS.markdownExportFolder, folder);
+      const notePath = await invoke("export_activity_markdown", { path: folder, period });
+      showStatus(`Activity written to ${pathBasename(notePath)}`, "success");
+    } catch (error) {
+      showStatus(`Markdown export failed: ${getErrorMessage(error)}`, "error");
+    }
+  });
+
+  // Save the whole activity history as a spreadsheet
+  document.querySelector("#export-activity-csv-btn").addEventListener("click", async () => {
+    try {
+      const path = await save({
+        title: "Export activity",
+        defaultPath: "file-organiser-activity.csv",
+        filters: [{ name: "CSV", extensions: ["csv"] }],
+      });
+      if (!path) return;
+      const rows = await invoke("export_activity_csv", { path });
+      showStatus(`Exported ${rows} move${rows === 1 ? "" : "s"} to ${pathBasename(path)}`, "success");
+    } catch (error) {
+      showStatus(`CSV export failed: ${getErrorMessage(error)}`, "error");
+    }
+  });
+
+  document.querySelector("#export-corrections-jsonl-btn").addEventListener("click", async () => {
+    try {
+      const path = await save({
+        title: "Export training data",
+        defaultPath: "file-organiser-corrections.jsonl",
+        filters: [{ name: "JSON Lines", extensions: ["jsonl"] }],
+      });
+      if (!path) return;
+      const examples = await invoke("export_corrections_jsonl", { path });
+      showStatus(`Exported ${examples} example${examples === 1 ? "" : "s"} to ${pathBasename(path)}`, "success");
+    } catch (error) {
+      showStatus(`Training data export failed: ${getErrorMessage(error)}`, "error");
+    }
+  });
+
+  // Recently deleted: files trashed by mistake (e.g. judged "not relevant") can be restored
+  const trashList = document.querySelector("#trash-list");
+  const showTrashBtn = document.querySelector("#show-trash-btn");
+  showTrashBtn.addEventListener("click", async () => {
+    if (trashList.style.display === "block") {
+      trashList.style.display = "none";
+      showTrashBtn.textContent = "Recently deleted";
+      return;
+    }
+    trashList.style.display = "block";
+    showTrashBtn.textContent = "Hide recently deleted";
+    await renderTrashList();
+  });
+
+  async function renderTrashList() {
+    trashList.innerHTML = '<p class="empty-msg">Loading...</p>';
+    let entries;
+    try {
+      entries = await invoke("list_trash");
+    } catch (error) {
+      trashList.innerHTML = `<p class="empty-msg">${escapeHtml(getErrorMessage(error))}</p>`;
+      return;
+    }
+    if (entries.length === 0) {
+      trashList.innerHTML = '<p class="empty-msg">Nothing the assistant deleted is left to restore.</p>';
+      return;
+    }
+
+    trashList.innerHTML = "";
+    for (const entry of entries) {
+      const item = document.createElement("label");
+      item.className = "activity-item trash-item";
+      const deleted = new Date(entry.deleted_at).toLocaleDateString([], { month: "short", day: "numeric" });
+      const where = entry.location === "app_trash" ? "app trash" : "recycle bin";
+      item.innerHTML = `
+        <input type="checkbox" class="trash-select" value="${escapeHtml(entry.id)}" />
+        <span class="activity-time">${deleted}</span>
+        <span class="activity-desc" title="${escapeHtml(entry.original_path)}">${escapeHtml(entry.name)}${entry.size != null ? ` · ${formatFileSize(entry.size)}` : ""}</span>
+        <span class="rename-badge">${where}</span>
+      `;
+      trashList.appendChild(item);
+    }
+
+    const restoreBtn = document.createElement("button");
+    restoreBtn.className = "activity-export-btn";
+    restoreBtn.textContent = "Restore selected";
+    restoreBtn.addEventListener("click", async () => {
+      const ids = [...trashList.querySelectorAll(".trash-select:checked")].map(box => box.value);
+      if (ids.length === 0) {
+        showStatus("Select the files to restore", "info");
+        return;
+      }
+      restoreBtn.disabled = true;
+      try {
+        const report = await invoke("restore_trash_items", { ids });
+        showStatus(describeRestore(report), report.failed.length === 0 ? "success" : "error");
+      } catch (error) {
+        showStatus(`Restore failed: ${getErrorMessage(error)}`, "error");
+      }
+      await renderTrashList();
+    });
+    trashList.appendChild(restoreBtn);
+  }
+
+  // Duplicates: the same file downloaded several times can share one copy's space
+  const duplicatesList = document.querySelector("#duplicates-list");
+  document.querySelector("#find-duplicates-btn").addEventListener("click", async () => {
+    const folder = await open({
+      directory: true,
+      multiple: false,
+      title: "Select folder to check for duplicates",
+      defaultPath: basePath || undefined,
+    });
+    if (!folder) return;
+    duplicatesList.style.display = "block";
+    await renderDuplicates(folder);
+  });
+
+  async function renderDuplicates(folder) {
+    duplicatesList.innerHTML = '<p class="empty-msg">Comparing files...</p>';
+    let groups;
+    try {
+      groups = await invoke("find_duplicates", { folder });
+    } catch (error) {
+      duplicatesList.innerHTML = `<p class="empty-msg">${escapeHtml(getErrorMessage(error))}</p>`;
+      return;
+    }
+    groups = groups.filter(g => g.reclaimable > 0);
+    if (groups.length === 0) {
+      duplicatesList.innerHTML = '<p class="empty-msg">No duplicate files found.</p>';
+      return;
+    }
+
+    duplicatesList.innerHTML = "";
+    for (const group of groups) {
+      const [keep, ...copies] = group.paths;
+      const item = document.createElement("div");
+      item.className = "activity-item";
+      item.innerHTML = `
+        <span class="activity-desc" title="${escapeHtml(group.paths.join("\n"))}">${escapeHtml(pathBasename(keep))} \u00d7 ${group.paths.length}</span>
+        <span class="rename-badge">${formatFileSize(group.reclaimable)}</span>
+      `;
+      const linkBtn = document.createElement("button");
+      linkBtn.className = "activity-export-btn";
+      linkBtn.textContent = "Link copies";
+      linkBtn.title = `Keep ${keep} and replace the other copies with hard links to it`;
+      linkBtn.addEventListener("click", async () => {
+        if (!confirm(`Replace ${copies.length} cop${copies.length === 1 ? "y" : "ies"} of ${pathBasename(keep)} with hard links?\n\nAll names will share one file, so editing one changes them all.`)) return;
+        linkBtn.disabled = true;
+        try {
+          const report = await invoke("link_duplicates", { keepPath: keep, copyPaths: copies });
+          showStatus(describeLinkReport(report), report.failed.length === 0 ? "success" : "error");
+        } catch (error) {
+          showStatus(`Linking failed: ${getErrorMessage(error)}`, "error");
+        }
+        await renderDuplicates(folder);
+      });
+      item.appendChild(linkBtn);
+      duplicatesList.appendChild(item);
+    }
+  }
+
+  // Disk usage: which course folder is taking up the space
+  const diskUsageList = document.querySelector("#disk-usage-list");
+  document.querySelector("#disk-usage-btn").addEventListener("click", async () => {
+    const folder = await open({
+      directory: true,
+      multiple: false,
+      title: "Select folder to measure",
+      defaultPath: basePath || undefined,
+    });
+    if (!folder) return;
+    diskUsageList.style.display = "block";
+    diskUsageList.innerHTML = '<p class="empty-msg">Measuring...</p>';
+    try {
+      const usage = await invoke("analyze_disk_usage", { path: folder, maxDepth: 2 });
+      const rows = flattenDiskUsage(usage);
+      diskUsageList.innerHTML = `<div class="folder-stats-row">
+          <span class="folder-stats-name">${escapeHtml(usage.name)}</span>
+          <span class="folder-stats-summary">${formatFileSize(usage.size)} \u00b7 ${usage.file_count} files</span>
+        </div>` + rows.map(r => `<div class="folder-stats-row" style="padding-left: ${(r.depth + 1) * 16}px;">
+          <span class="folder-stats-name" title="${escapeHtml(r.path)}">${escapeHtml(r.name)}</span>
+          <span class="folder-stats-summary">${formatFileSize(r.size)} \u00b7 ${Math.round(r.share * 100)}%</span>
+        </div>`).join("");
+    } catch (error) {
+      diskUsageList.innerHTML = `<p class="empty-msg">${escapeHtml(getErrorMessage(error))}</p>`;
+    }
+  });
+
   // Render existing activity log
   renderActivityLog();
 
+  // Wire up file search (watched folder + destination folders)
+  searchBtn.addEventListener("click", runSearch);
+  searchInput.addEventListener("keydown", (e) => {
+    if (e.key === "Enter") runSearch();
+  });
+
   // Settings button -> settings screen
   settingsBtn.addEventListener("click", () => {
     const settingsState = {
       basePath, watchPath, userModules, autoMoveEnabled, autoMoveThreshold,
       notificationsEnabled, darkModeEnabled, classificationRules, notificationApi,
+      quarantineSettings, classificationMode, watchProfiles, localOnly,
     };
     showSettingsScreen(() => initSettings(settingsState, {
       onClose() {
@@ -385,6 +667,11 @@ function initApp() {
         notificationsEnabled = settingsState.notificationsEnabled;
         darkModeEnabled = settingsState.darkModeEnabled;
         classificationRules = settingsState.classificationRules;
+        quarantineSettings = settingsState.quarantineSettings;
+        classificationMode = settingsState.classificationMode;
+        localOnly = settingsState.localOnly;
+        watchProfiles = settingsState.watchProfiles;
+        syncAllowedRoots();
         updateConfigSummary();
       },
       applyTheme() { applyTheme(); },
@@ -396,6 +683,9 @@ function initApp() {
   acceptAllHighBtn.addEventListener("click", acceptAllHighConfidence);
   dismissAllBtn.addEventListener("click", dismissAll);
   scanFolderBtn.addEventListener("click", startBulkScan);
+  reviewQuarantineBtn.addEventListener("click", reclassifyQuarantine);
+  suggestFoldersBtn.addEventListener("click", suggestNewFolders);
+  organizeFolderBtn.addEventListener("click", organizeFolder);
   scanCancelBtn.addEventListener("click", () => { scanCancelled = true; });
 
   // Show/hide custom input when "Custom" is selected
@@ -408,6 +698,12 @@ function initApp() {
 
   // Listen for file detection events from Rust
   setupFileListener();
+  restorePendingFiles();
+  offerToResumeSessions();
+
+  // Show progress for large copies/moves (e.g. recordings going to another drive)
+  setupFileOpProgressListener();
+  setupScheduledOrganizeListener();
 
   // Listen for tray hint notification
   setupTrayHintListener();
@@ -420,6 +716,7 @@ function initApp() {
 
   // Watch path must be configured by the user via Settings > Browse
   scanFolderBtn.disabled = userModules.length === 0;
+  organizeFolderBtn.disabled = userModules.length === 0;
   updateConfigSummary();
 
   // Check if watcher is already running (e.g. after navigating back from Settings)
@@ -430,16 +727,50 @@ function initApp() {
     return userModules.map(name => pathJoin(basePath, name));
   }
 
+  // Destination folders for a file: its watch profile's folders, or the modules
+  function getFoldersForFile(fileInfo) {
+    const profile = findWatchProfile(fileInfo.path, watchProfiles);
+    return profile && profile.folders.length > 0 ? profile.folders : getAvailableFolders();
+  }
+
   // --- Build folder select options ---
-  function buildFolderOptions() {
+  // extraFolders (full paths, e.g. a watch profile's folders) are listed after the modules
+  function buildFolderOptions(extraFolders = []) {
     let options = '<option value="">Choose destination...</option>';
+    const moduleFolders = getAvailableFolders();
     for (const name of userModules) {
       const fullPath = pathJoin(basePath, name);
       options += `<option value="${escapeHtml(fullPath)}">${escapeHtml(name)}</option>`;
     }
+    for (const folder of extraFolders) {
+      if (moduleFolders.includes(folder)) continue;
+      options += `<option value="${escapeHtml(folder)}">${escapeHtml(pathBasename(folder))}</option>`;
+    }
     return options;
   }
 
+  // Add a module, create its folder and refresh the folder dropdowns; returns the folder path
+  async function addModule(name) {
+    userModules.push(name);
+    localStorage.setItem(STORAGE_KEYS.modules, JSON.stringify(userModules));
+
+    // Create the folder
+    const fullPath = pathJoin(basePath, name);
+    try {
+      await invoke("create_folder", { path: fullPath });
+    } catch (e) {
+      console.error(`Failed to create folder for ${name}:`, e);
+    }
+
+    // Update all folder dropdowns on existing cards, keeping any watch-profile folders they offer
+    document.querySelectorAll(".folder-select").forEach(sel => {
+      const currentVal = sel.value;
+      sel.innerHTML = buildFolderOptions([...sel.options].map(o => o.value).filter(Boolean));
+      sel.value = currentVal;
+    });
+    return fullPath;
+  }
+
   // Quick-create a module from an unsorted file card (inline input, no prompt())
   function quickCreateModule(fileInfo, fileItem, classification) {
     const suggestionDiv = fileItem.querySelector(".ai-suggestion");
@@ -494,25 +825,7 @@ function initApp() {
         return;
       }
 
-      // Add module
-      userModules.push(trimmed);
-      localStorage.setItem(STORAGE_KEYS.modules, JSON.stringify(userModules));
-
-      // Create the folder
-      const fullPath = pathJoin(basePath, trimmed);
-      try {
-        await invoke("create_folder", { path: fullPath });
-      } catch (e) {
-        console.error(`Failed to create folder for ${trimmed}:`, e);
-      }
-
-      // Update all folder dropdowns on existing cards
-      const newOptions = buildFolderOptions();
-      document.querySelectorAll(".folder-select").forEach(sel => {
-        const currentVal = sel.value;
-        sel.innerHTML = newOptions;
-        sel.value = currentVal;
-      });
+      const fullPath = await addModule(trimmed);
 
       // Auto-select the new module in this card's dropdown
       const folderSelect = fileItem.querySelector(".folder-select");
@@ -526,7 +839,7 @@ function initApp() {
           <strong>New module:</strong> ${escapeHtml(trimmed)}
           <button class="accept-btn">Move Here</button>
         </div>
-        <div class="ai-reasoning">${escapeHtml(classification.reasoning)}</div>
+        <div class="ai-reasoning" title="${escapeHtml(classification.reasoning)}">${escapeHtml(describeExplanation(classification))}</div>
       `;
       suggestionDiv.querySelector(".accept-btn").addEventListener("click", function() {
         acceptAISuggestion(fileInfo.path, fullPath, this);
@@ -645,9 +958,29 @@ function initApp() {
       scanCancelled = false;
       scanFolderBtn.disabled = true;
 
-      // Get list of files from Rust
+      // Get list of files from Rust, counting them as the batches arrive
       showStatus(`Scanning ${selectedPath}...`, "info");
-      const files = await invoke("scan_files", { path: selectedPath });
+      const files = [];
+      let finishScan;
+      const scanDone = new Promise((resolve) => { finishScan = resolve; });
+      const unlistenBatch = await listen("scan-files-batch", (event) => {
+        if (event.payload.root !== selectedPath) return;
+        files.push(...event.payload.entries);
+        showStatus(`Scanning ${selectedPath}... ${files.length} files found`, "info");
+      });
+      // Sent after the last batch, so every batch has been received once it arrives
+      const unlistenComplete = await listen("scan-files-complete", (event) => {
+        if (event.payload.root === selectedPath) finishScan();
+      });
+      try {
+        await invoke("scan_files_stream", { path: selectedPath });
+        await scanDone;
+      } finally {
+        unlistenBatch();
+        unlistenComplete();
+      }
+      // Batches arrive in directory order; the scan limit keeps the newest files
+      files.sort((a, b) => b.modified - a.modified);
 
       if (files.length === 0) {
         showStatus("No files found in selected folder", "info");
@@ -746,6 +1079,151 @@ function initApp() {
     }
   }
 
+  // Organize a whole folder in one backend call (no per-file review); files below the
+  // auto-move threshold are left in place. Progress arrives as organize-progress events.
+  // Files bound for an unplugged drive are parked and moved once it's back (destinations-available).
+  async function organizeFolder() {
+    if (scanInProgress) {
+      showStatus("Scan already in progress", "info");
+      return;
+    }
+    if (userModules.length === 0) {
+      showStatus("Please add modules first in Settings", "error");
+      return;
+    }
+
+    const lastScanFolder = localStorage.getItem(STORAGE_KEYS.lastScanFolder) || undefined;
+    const selectedPath = await open({
+      directory: true,
+      title: "Select folder to organize",
+      defaultPath: lastScanFolder,
+    });
+    if (!selectedPath) return; // User cancelled
+    localStorage.setItem(STORAGE_KEYS.lastScanFolder, selectedPath);
+
+    scanInProgress = true;
+    scanFolderBtn.disabled = true;
+    organizeFolderBtn.disabled = true;
+    scanCancelBtn.style.display = "none"; // the backend run can't be interrupted
+    scanProgressFill.style.width = "0%";
+    scanProgressCount.textContent = "0/0";
+    scanProgress.style.display = "flex";
+    showStatus(`Organizing ${selectedPath}...`, "info");
+
+    const unlisten = await listen("organize-progress", (event) => {
+      const { index, total } = event.payload;
+      scanProgressFill.style.width = `${Math.round((index / total) * 100)}%`;
+      scanProgressCount.textContent = `${index}/${total}`;
+    });
+
+    try {
+      const result = await invoke("organize_folder", {
+        path: selectedPath,
+        options: {
+          available_folders: getAvailableFolders(),
+          correction_history: buildCorrectionHistory(correctionLog),
+          min_confidence: autoMoveThreshold,
+        },
+      });
+      showStatus(summarizeOrganizeResult(result), result.failed.length > 0 ? "error" : "success");
+      activityLog = await dbGetActivityLog();
+      renderActivityLog();
+    } catch (error) {
+      showStatus(`Organize failed: ${getErrorMessage(error)}`, "error");
+    } finally {
+      unlisten();
+      scanProgress.style.display = "none";
+      scanCancelBtn.style.display = "";
+      scanInProgress = false;
+      scanFolderBtn.disabled = false;
+      organizeFolderBtn.disabled = false;
+    }
+  }
+
+  // Re-run classification on everything in the quarantine folder (e.g. after adding modules)
+  async function reclassifyQuarantine() {
+    if (!quarantineSettings.folder) {
+      showStatus("Set a Needs Review folder in Settings first", "info");
+      return;
+    }
+    try {
+      const items = await invoke("list_quarantine");
+      const tracked = new Set(detectedFiles.map(f => f.path));
+      const pending = items.filter(item => !tracked.has(item.path));
+      if (pending.length === 0) {
+        showStatus("Nothing waiting in Needs Review", "info");
+        return;
+      }
+
+      // scan_files gives the size/modified info the file cards expect
+      const entries = await invoke("scan_files", { path: quarantineSettings.folder });
+      const byPath = new Map(entries.map(e => [e.path, e]));
+      showStatus(`Re-classifying ${pending.length} file(s) from Needs Review...`, "info");
+      for (const item of pending) {
+        const entry = byPath.get(item.path);
+        if (!entry) continue;
+        await addDetectedFile({ ...entry, timestamp: Date.now(), fromQuarantine: true });
+      }
+    } catch (error) {
+      showStatus(`Could not load Needs Review: ${getErrorMessage(error)}`, "error");
+    }
+  }
+
+  // Cluster unsorted files (cards here plus Needs Review) and propose new folders for them
+  async function suggestNewFolders() {
+    if (!basePath) {
+      showStatus("Set a destination folder in Settings first", "info");
+      return;
+    }
+    const files = detectedFiles.filter(f => f.unsorted).map(f => f.path);
+
+    suggestFoldersBtn.disabled = true;
+    showStatus("Looking for common topics among unsorted files...", "info");
+    try {
+      const suggestions = await invoke("suggest_new_folders", { files, availableFolders: getAvailableFolders() });
+      renderFolderSuggestions(suggestions);
+      // Say when the filenames went to the AI provider because the local model isn't ready
+      const embedding = await invoke("get_embedding_status").catch(() => null);
+      const viaProvider = embedding && embedding.state !== "ready" ? " (compared by the AI provider: the local model isn't ready yet)" : "";
+      if (suggestions.length === 0) {
+        showStatus(`No recurring topics found among unsorted files${viaProvider}`, "info");
+      } else {
+        showStatus(`Found ${suggestions.length} possible new folder(s)${viaProvider}`, "success");
+      }
+    } catch (error) {
+      showStatus(`Could not suggest folders: ${getErrorMessage(error)}`, "error");
+    } finally {
+      suggestFoldersBtn.disabled = false;
+    }
+  }
+
+  function renderFolderSuggestions(suggestions) {
+    folderSuggestions.innerHTML = "";
+    folderSuggestions.style.display = suggestions.length > 0 ? "" : "none";
+    for (const suggestion of suggestions) {
+      const item = document.createElement("div");
+      item.className = "activity-item";
+      item.innerHTML = `
+        <span class="activity-desc"><strong>${escapeHtml(suggestion.name)}</strong> <small>${escapeHtml(describeFolderSuggestion(suggestion))}</small></span>
+        <button class="accept-btn">Create</button>
+        <button class="secondary-btn">Dismiss</button>
+      `;
+      item.querySelector(".secondary-btn").addEventListener("click", () => item.remove());
+      item.querySelector(".accept-btn").addEventListener("click", async () => {
+        if (userModules.some(m => m.toLowerCase() === suggestion.name.toLowerCase())) {
+          showStatus("Module already exists", "error");
+          return;
+        }
+        await addModule(suggestion.name);
+        item.remove();
+        updateConfigSummary();
+        scanFolderBtn.disabled = false;
+        showStatus(`Module "${suggestion.name}" created. Re-run Needs Review to sort its files.`, "success");
+      });
+      folderSuggestions.appendChild(item);
+    }
+  }
+
   // Set up listener for tray hint (when window is minimized to tray)
   function setupTrayHintListener() {
     listen("tray-hint", (event) => {
@@ -775,6 +1253,139 @@ function initApp() {
     });
   }
 
+  // Bring back files that were detected but not reviewed before the app last closed
+  async function restorePendingFiles() {
+    let pending;
+    try {
+      pending = await invoke("list_pending_files");
+    } catch (error) {
+      console.error("[QUEUE] Failed to load pending files:", error);
+      return;
+    }
+    const tracked = new Set([...detectedFiles, ...pendingBatch].map(f => f.path));
+    const restored = pending.filter(f => !tracked.has(f.path));
+    if (restored.length === 0) return;
+
+    console.log(`[QUEUE] Restoring ${restored.length} pending file(s)`);
+    for (const file of restored) {
+      pendingBatch.push({
+        path: file.path,
+        name: file.name,
+        size: file.size,
+        timestamp: file.detected_at,
+        savedClassification: file.classification,
+      });
+    }
+    if (batchTimer) clearTimeout(batchTimer);
+    batchTimer = setTimeout(() => processBatch(), BATCH_WINDOW_MS);
+  }
+
+  // A bulk organize cut short by a crash leaves planned moves pending; offer to finish them
+  async function offerToResumeSessions() {
+    let sessions;
+    try {
+      sessions = await invoke("db_get_sessions");
+    } catch (error) {
+      console.error("[SESSION] Failed to load sessions:", error);
+      return;
+    }
+    for (const session of sessions.filter(s => s.pending_items > 0 && !s.undone)) {
+      const left = `${session.pending_items} file${session.pending_items === 1 ? "" : "s"}`;
+      if (!confirm(`"${session.label}" was interrupted with ${left} still to move.\n\nFinish it now?`)) continue;
+      try {
+        const result = await invoke("resume_session", { id: session.id });
+        const moved = result.moved + result.already_done;
+        const failed = result.failed.length;
+        showStatus(`Finished "${session.label}": ${moved} moved${failed ? `, ${failed} failed` : ""}`, failed ? "error" : "success");
+        activityLog = await dbGetActivityLog();
+        renderActivityLog();
+      } catch (error) {
+        showStatus(`Couldn't resume "${session.label}": ${error}`, "error");
+      }
+    }
+  }
+
+  // Progress bar + cancel for long-running copies reported by Rust
+  // Scheduled runs happen in the backend (possibly while hidden); refresh what they changed
+  function setupScheduledOrganizeListener() {
+    listen("scheduled-organize-finished", async (event) => {
+      showStatus(`Scheduled organize: ${event.payload}`, "info");
+      activityLog = await dbGetActivityLog();
+      renderActivityLog();
+    });
+    listen("shortcut-organize-finished", async (event) => {
+      showStatus(`Shortcut organize: ${event.payload}`, "info");
+      activityLog = await dbGetActivityLog();
+      renderActivityLog();
+    });
+    // A drive with deferred moves was plugged back in and they've been carried out
+    listen("destinations-available", async (event) => {
+      showStatus(describeDeferredMoves(event.payload), event.payload.failed.length > 0 ? "error" : "info");
+      activityLog = await dbGetActivityLog();
+      renderActivityLog();
+    });
+    // The watched folder grew past its size limit (also shown as a system notification)
+    listen("quota-warning", (event) => {
+      const { folder, size, max_bytes } = event.payload;
+      showStatus(`${pathBasename(folder)} is ${formatFileSize(size)} (limit ${formatFileSize(max_bytes)}) - time to organize`, "info");
+    });
+    // The month's AI budget ran out (also shown as a system notification)
+    listen("budget-exceeded", (event) => {
+      showStatus(describeBudget(event.payload), "info");
+    });
+    // Accept / Change in the quick-sort popup, carried out as if the card's buttons were clicked
+    listen("quick-sort-decision", (event) => {
+      const { path, action, folder } = event.payload;
+      const fileItem = [...fileList.querySelectorAll(".file-item")].find(item => item.getAttribute("data-file-path") === path);
+      const fileData = detectedFiles.find(f => f.path === path);
+      if (!fileItem || !fileData) return;
+      if (action === "accept" && fileData.classification?.suggested_folder) {
+        const button = fileItem.querySelector(".accept-btn") || fileItem.querySelector(".move-btn");
+        acceptAISuggestion(path, fileData.classification.suggested_folder, button);
+      } else if (action === "change" && folder) {
+        fileItem.querySelector(".folder-select").value = folder;
+        moveFile(path, fileItem.querySelector(".move-btn"));
+      }
+    });
+    // "Undo Last Auto-Move" in the tray undoes a move announced by a system notification
+    listen("auto-move-undone", async (event) => {
+      showStatus(`Undid auto-move: ${event.payload}`, "info");
+      activityLog = await dbGetActivityLog();
+      renderActivityLog();
+    });
+  }
+
+  function setupFileOpProgressListener() {
+    listen("file-op-progress", (event) => {
+      const progress = event.payload;
+      if (progress.done) {
+        if (progress.op_id === currentFileOpId) {
+          fileOpProgress.style.display = "none";
+          currentFileOpId = null;
+        }
+        return;
+      }
+      currentFileOpId = progress.op_id;
+      const pct = progress.total_bytes > 0 ? Math.round((progress.bytes_copied / progress.total_bytes) * 100) : 0;
+      fileOpProgressText.textContent = formatFileOpProgress(progress);
+      fileOpProgressFill.style.width = `${pct}%`;
+      fileOpCancelBtn.disabled = false;
+      fileOpProgress.style.display = "flex";
+    });
+
+    fileOpCancelBtn.addEventListener("click", async () => {
+      if (!currentFileOpId) return;
+      fileOpCancelBtn.disabled = true;
+      try {
+        await invoke("cancel_file_operation", { opId: currentFileOpId });
+      } catch (e) {
+        console.error("[FILE-OP] Cancel failed:", e);
+      }
+      fileOpProgress.style.display = "none";
+      currentFileOpId = null;
+    });
+  }
+
   // Set up drag & drop on app window
   function setupDragAndDrop() {
     const dropOverlay = document.getElementById("drop-overlay");
@@ -902,6 +1513,14 @@ function initApp() {
           }
           break;
         }
+        case "o": {
+          // Open in the default app
+          const openBtn = topCard.querySelector(".open-file-btn");
+          if (openBtn) {
+            openBtn.click();
+          }
+          break;
+        }
       }
     });
   }
@@ -965,113 +1584,72 @@ function initApp() {
 
   // File type helpers and CONFIDENCE_THRESHOLD imported from utils.js / constants.js
 
-  // Classification pipeline: Rules → Cache → API (two-pass)
+  // Classification pipeline: Rules → Cache → classify_auto (AI passes, or type buckets in bucket mode).
+  // Files in a watch profile's folder use that profile's rules, folders and mode.
   async function invokeClassify(fileInfo, statusCallback) {
+    const profile = findWatchProfile(fileInfo.path, watchProfiles);
+    const mode = profile ? profile.mode : classificationMode.mode;
+    const availableFolders = getFoldersForFile(fileInfo);
+
     // Check user-defined rules first (instant, no API call)
-    const ruleResult = matchRule(fileInfo.name, classificationRules);
+    const ruleResult = matchRule(fileInfo.name, rulesForProfile(classificationRules, profile ? profile.id : null));
     if (ruleResult) {
       console.log(`[RULES] ${fileInfo.name} matched rule → ${ruleResult.suggested_folder}`);
       return ruleResult;
     }
 
+    // Classified before the app was last closed
+    if (fileInfo.savedClassification) {
+      console.log(`[QUEUE] ${fileInfo.name}: reusing classification from before the restart`);
+      return fileInfo.savedClassification;
+    }
+
     // Check cache: reuse result if we've seen this exact filename before
     const cached = getCachedClassification(fileInfo.name, correctionLog, userModules, basePath);
-    if (cached) {
+    if (cached && availableFolders.includes(cached.suggested_folder)) {
       console.log(`[CACHE] ${fileInfo.name} → ${cached.suggested_folder}`);
       return cached;
     }
 
-    const availableFolders = getAvailableFolders();
-    const correctionHistory = buildCorrectionHistory(correctionLog);
-
-    // Pass 1: Filename-based classification
-    if (statusCallback) statusCallback("Analyzing filename...");
-    const firstPass = await invoke("classify_file", {
-      filename: fileInfo.name,
-      availableFolders: availableFolders,
-      correctionHistory: correctionHistory,
+    // Rust runs the rest: type buckets, or filename → OCR/vision, content or archive passes
+    const unlisten = await listen("classify-progress", (event) => {
+      if (statusCallback && event.payload.file_path === fileInfo.path) statusCallback(event.payload.message);
     });
-
-    console.log(`[PASS 1] ${fileInfo.name}: confidence=${firstPass.confidence}, relevant=${firstPass.is_relevant}, folder="${firstPass.suggested_folder}"`);
-
-    // Pass 2: Content-based fallback for low-confidence results
-    const canUseVision = isImageFile(fileInfo.name);
-    const canExtractText = isContentExtractable(fileInfo.name);
-
-    // For image files, ALWAYS use vision if filename is ambiguous (screenshots, generic names)
-    // because the filename alone can't determine if a screenshot contains academic content
-    if (canUseVision && firstPass.confidence < CONFIDENCE_THRESHOLD) {
-      // Skip the short-circuit — go straight to vision pass below
-    } else if (canExtractText) {
-      // Skip the short-circuit — ALWAYS extract content for PDFs/text files
-      // Filenames like "PS1_sol.pdf" are too generic; actual content is far more reliable
-    } else if (firstPass.confidence >= CONFIDENCE_THRESHOLD || (!firstPass.is_relevant && firstPass.confidence === 0)) {
-      // High confidence or clearly not relevant (and not an image/pdf) — use first pass
-      return firstPass;
-    }
-
-    if (canUseVision) {
-      // Try OCR first (free, local) — fall back to vision API if OCR gets too little text
-      console.log(`[PASS 2] Low confidence (${firstPass.confidence}), trying OCR for: ${fileInfo.name}`);
-      if (statusCallback) statusCallback("Filename unclear - extracting text from image (OCR)...");
-      try {
-        return await invoke("classify_image_with_ocr", {
-          filePath: fileInfo.path,
-          filename: fileInfo.name,
-          availableFolders: availableFolders,
-          correctionHistory: correctionHistory,
-        });
-      } catch (ocrError) {
-        const errMsg = typeof ocrError === "string" ? ocrError : (ocrError?.message || String(ocrError));
-        if (errMsg.includes("OCR_INSUFFICIENT_TEXT")) {
-          console.log(`[PASS 2] OCR extracted too little text, falling back to vision for: ${fileInfo.name}`);
-        } else {
-          console.warn(`[PASS 2] OCR failed (${errMsg}), falling back to vision for: ${fileInfo.name}`);
-        }
-        // Fall back to GPT-4o vision
-        if (statusCallback) statusCallback("OCR insufficient - analyzing with AI vision...");
-        try {
-          return await invoke("classify_image_file", {
-            filePath: fileInfo.path,
-            filename: fileInfo.name,
-            availableFolders: availableFolders,
-            correctionHistory: correctionHistory,
-          });
-        } catch (visionError) {
-          console.error("[PASS 2] Vision fallback also failed, using pass 1 result:", visionError);
-          return firstPass;
-        }
-      }
-    }
-
-    if (canExtractText) {
-      console.log(`[PASS 2] Extracting content for: ${fileInfo.name} (filename confidence: ${firstPass.confidence}, but content is more reliable)`);
-      if (statusCallback) statusCallback("Filename unclear - reading file content for better classification...");
-      try {
-        return await invoke("classify_with_content", {
-          filePath: fileInfo.path,
-          filename: fileInfo.name,
-          availableFolders: availableFolders,
-          correctionHistory: correctionHistory,
-        });
-      } catch (e) {
-        console.error("[PASS 2] Content extraction fallback failed, using pass 1 result:", e);
-        return firstPass;
-      }
+    try {
+      const result = await invoke("classify_auto", {
+        filePath: fileInfo.path,
+        availableFolders: availableFolders,
+        correctionHistory: buildCorrectionHistory(correctionLog),
+        mode: mode,
+      });
+      console.log(`[CLASSIFY] ${fileInfo.name}: ${result.pass} pass${result.tier ? ` (${result.tier})` : ""}, confidence=${result.confidence}, folder="${result.suggested_folder}"`);
+      return result;
+    } finally {
+      unlisten();
     }
-
-    // No content fallback available, return first pass as-is
-    return firstPass;
   }
 
   // Classify and render a file item (shared logic)
   // Returns true if file is relevant (shown in UI), false if skipped
+  // EXIF capture date of a photo, or null if it has none
+  async function getCaptureDate(filePath) {
+    try {
+      const meta = await invoke("get_file_metadata", { filePath });
+      return parseExifDate(meta.exif?.captured_at);
+    } catch (e) {
+      console.warn("[METADATA] Could not read metadata:", e);
+      return null;
+    }
+  }
+
   async function classifyAndRender(fileInfo, fileItem) {
     try {
       const loadingDiv = fileItem.querySelector(".ai-loading");
       const classification = await invokeClassify(fileInfo, (msg) => {
         if (loadingDiv) loadingDiv.textContent = msg;
       });
+      // Kept with the queued file so a restart doesn't classify it again
+      invoke("set_pending_classification", { path: fileInfo.path, classification }).catch(() => {});
 
       // Normalize suggested_folder: GPT sometimes returns just the folder name
       // (e.g. "Machine Learning") instead of the full path. Match it against
@@ -1079,7 +1657,7 @@ function initApp() {
       if (classification.suggested_folder &&
           classification.suggested_folder !== "__UNSORTED__" &&
           classification.is_relevant) {
-        const availableFolders = getAvailableFolders();
+        const availableFolders = getFoldersForFile(fileInfo);
         const sf = classification.suggested_folder;
         // Already a full path that matches an available folder — keep as-is
         if (!availableFolders.includes(sf)) {
@@ -1098,6 +1676,30 @@ function initApp() {
         }
       }
 
+      // Papers are named from their DOI/arXiv metadata ("Vaswani2017_Attention_Is_All_You_Need.pdf")
+      if (classification.is_relevant && getFileExt(fileInfo.name) === "pdf") {
+        if (loadingDiv) loadingDiv.textContent = "Looking up paper...";
+        try {
+          const paperName = await invoke("suggest_filename", { filePath: fileInfo.path });
+          if (paperName) classification.suggested_filename = paperName;
+        } catch (error) {
+          console.warn("[PAPER] Metadata lookup failed:", error);
+        }
+      }
+
+      // Apply the destination folder's preferences (naming template, manual confirm)
+      const folderPref = classification.is_relevant ? getFolderPref(classification.suggested_folder, folderPrefs) : null;
+      if (folderPref?.namingTemplate && !classification.suggested_filename) {
+        // For photos, {date} is when the picture was taken rather than today
+        let templateDate = new Date();
+        if (folderPref.namingTemplate.includes("{date}") && isImageFile(fileInfo.name)) {
+          const captured = await getCaptureDate(fileInfo.path);
+          if (captured) templateDate = captured;
+        }
+        classification.suggested_filename = applyNamingTemplate(folderPref.namingTemplate, fileInfo.name, classification.suggested_folder, templateDate);
+      }
+      const requiresConfirm = !!folderPref?.requireConfirm;
+
       // Two-stage: check if file is educational
       if (!classification.is_relevant) {
         // Not coursework - silently skip, remove from main UI
@@ -1120,24 +1722,31 @@ function initApp() {
 
       // Auto-move: if enabled and confidence meets threshold, move automatically
       // Skip auto-move for unsorted files (no matching module)
-      if (autoMoveEnabled && classification.is_relevant &&
-          classification.confidence >= autoMoveThreshold &&
+      // Skip auto-move for folders the user wants to confirm manually
+      const profileThreshold = findWatchProfile(fileInfo.path, watchProfiles)?.min_confidence;
+      if (autoMoveEnabled && classification.is_relevant && !requiresConfirm &&
+          classification.confidence >= (profileThreshold ?? autoMoveThreshold) &&
           classification.suggested_folder &&
           classification.suggested_folder !== "__UNSORTED__") {
         const suggestedModuleName = pathBasename(classification.suggested_folder);
         console.log(`[AUTO-MOVE] ${fileInfo.name} → ${suggestedModuleName} (${Math.round(classification.confidence * 100)}%)`);
 
         try {
-          await moveWithAutoRename(fileInfo.path, classification.suggested_folder);
+          await moveWithConflictPolicy(fileInfo.path, classification.suggested_folder);
 
           const filename = fileInfo.name;
           const moduleName = suggestedModuleName;
-          logCorrection(filename, moduleName, moduleName, "accepted");
+          logCorrection(filename, moduleName, moduleName, "accepted", classification.confidence);
 
           const movedDestPath = pathJoin(classification.suggested_folder, filename);
-          addActivityEntry(filename, watchPath, classification.suggested_folder);
+          const activity = await addActivityEntry(filename, watchPath, classification.suggested_folder, null, fileInfo.path, null, classification.confidence);
+          invoke("notify_file_auto_moved", {
+            sourcePath: fileInfo.path,
+            destPath: movedDestPath,
+            confidence: classification.confidence,
+          }).catch(e => console.warn("[WEBHOOK] Failed to report auto-move:", e));
           renderActivityLog();
-          showUndoToast(filename, movedDestPath, watchPath);
+          showUndoToast(filename, movedDestPath, watchPath, null, activity?.id);
 
           removeFileFromUI(fileInfo.path, fileItem);
           sendAppNotification("File auto-organized", `${filename} → ${moduleName}`);
@@ -1149,22 +1758,41 @@ function initApp() {
         }
       }
 
+      // Park unsorted / low-confidence files in the Needs Review folder (unless they're already there)
+      const quarantineReason = fileInfo.fromQuarantine ? null
+        : getQuarantineReason(classification, quarantineSettings, CONFIDENCE_THRESHOLD);
+      if (quarantineReason) {
+        try {
+          await invoke("quarantine_file", {
+            sourcePath: fileInfo.path,
+            reason: quarantineReason,
+            confidence: classification.confidence,
+            suggestedFolder: classification.suggested_folder || null,
+          });
+          removeFileFromUI(fileInfo.path, fileItem);
+          showStatus(`Moved to Needs Review: ${fileInfo.name}`, "info");
+          return true;
+        } catch (error) {
+          console.error("[QUARANTINE] Failed, showing file for manual review:", error);
+        }
+      }
+
       // File is educational - show classification
       const suggestionDiv = fileItem.querySelector(".ai-suggestion");
-      const confidencePercent = Math.round(classification.confidence * 100);
       const confidenceClass = classification.confidence > 0.8 ? "high" : classification.confidence > 0.5 ? "medium" : "low";
 
       const isUnsorted = classification.suggested_folder === "__UNSORTED__";
 
       if (isUnsorted) {
         // File is educational but doesn't match any configured module
+        fileInfo.unsorted = true; // picked up by "Suggest Folders"
         suggestionDiv.innerHTML = `
           <div class="ai-result low">
             <strong>No matching module</strong>
             <span class="confidence">Educational but doesn't fit current modules</span>
             <button class="create-module-btn">+ Create Module</button>
           </div>
-          <div class="ai-reasoning">${escapeHtml(classification.reasoning)}</div>
+          <div class="ai-reasoning" title="${escapeHtml(classification.reasoning)}">${escapeHtml(describeExplanation(classification))}</div>
         `;
         // Wire up create module button
         suggestionDiv.querySelector(".create-module-btn").addEventListener("click", () => {
@@ -1182,7 +1810,7 @@ function initApp() {
         suggestionDiv.innerHTML = `
           <div class="ai-result ${confidenceClass}">
             <strong>AI Suggests:</strong> ${escapeHtml(suggestedModuleName)}
-            <span class="confidence">${confidencePercent}% confident</span>
+            <span class="confidence">${escapeHtml(describeConfidence(classification))}</span>
             <button class="accept-btn">Accept</button>
           </div>
           ${hasSuggestedRename ? `
@@ -1193,9 +1821,18 @@ function initApp() {
             <button class="accept-rename-btn">Accept &amp; Rename</button>
           </div>
           ` : ""}
-          <div class="ai-reasoning">${escapeHtml(classification.reasoning)}</div>
+          <div class="ai-reasoning" title="${escapeHtml(classification.reasoning)}">${escapeHtml(describeExplanation(classification))}</div>
+          ${classification.truncation ? `<div class="ai-truncation">${escapeHtml(describeTruncation(classification.truncation))}</div>` : ""}
+          ${classification.cloud_placeholder ? `<div class="ai-truncation">Online-only file - classified by name without downloading it</div>` : ""}
+          ${classification.link ? `<div class="ai-link">${escapeHtml(describeLink(classification.link))}
+            ${classification.link.exists ? "" : `<button class="link-trash-btn">Delete shortcut</button>`}</div>` : ""}
         `;
 
+        // A shortcut whose target is gone goes to the recycle bin like any other file
+        suggestionDiv.querySelector(".link-trash-btn")?.addEventListener("click", () => {
+          fileItem.querySelector(".trash-btn")?.click();
+        });
+
         suggestionDiv.querySelector(".accept-btn").addEventListener("click", function() {
           acceptAISuggestion(fileInfo.path, classification.suggested_folder, this);
         });
@@ -1243,17 +1880,27 @@ function initApp() {
         });
       });
 
+      // Academic papers can also be handed to a reference manager
+      if (getFileExt(fileInfo.name) === "pdf") {
+        showPaperActions(fileInfo, suggestionDiv, isUnsorted ? null : classification.suggested_folder);
+      }
+
       const fileIndex = detectedFiles.findIndex(f => f.path === fileInfo.path);
       if (fileIndex > -1) {
         detectedFiles[fileIndex].classification = classification;
-        detectedFiles[fileIndex].isHighConfidence = classification.confidence > 0.8 && !isUnsorted;
+        detectedFiles[fileIndex].isHighConfidence = classification.confidence > 0.8 && !isUnsorted && !requiresConfirm;
       }
 
-      if (classification.confidence > 0.8 && !isUnsorted) {
+      // Folders that require manual confirm are left out of "Accept All High Confidence"
+      if (classification.confidence > 0.8 && !isUnsorted && !requiresConfirm) {
         fileItem.setAttribute("data-high-confidence", "true");
         fileItem.setAttribute("data-suggested-folder", classification.suggested_folder);
       }
 
+      // With the window hidden in the tray, Rust shows the file in the quick-sort popup instead
+      invoke("offer_quick_sort", { item: buildQuickSortItem(fileInfo, classification, getFoldersForFile(fileInfo)) })
+        .catch(e => console.warn("[QUICK-SORT] Failed to offer file:", e));
+
       updateBatchActions();
       return true;
     } catch (error) {
@@ -1264,6 +1911,50 @@ function initApp() {
     }
   }
 
+  // Offer "Send to Zotero" / "Save .bib" when a PDF has a DOI or arXiv ID
+  async function showPaperActions(fileInfo, suggestionDiv, destFolder) {
+    let paperId;
+    try {
+      paperId = await invoke("detect_paper", { filePath: fileInfo.path });
+    } catch (error) {
+      console.warn("[PAPER] Detection failed:", error);
+      return;
+    }
+    if (!paperId) return;
+
+    const actions = document.createElement("div");
+    actions.className = "paper-actions";
+    actions.innerHTML = `
+      <span>Paper: ${escapeHtml(describePaperId(paperId))}</span>
+      <button class="zotero-btn">Send to Zotero</button>
+      <button class="bibtex-btn" title="Save a BibTeX entry where this PDF is being filed">Save .bib</button>
+    `;
+    suggestionDiv.appendChild(actions);
+
+    actions.querySelector(".zotero-btn").addEventListener("click", async function() {
+      this.disabled = true;
+      try {
+        await invoke("send_paper_to_zotero", { filePath: fileInfo.path });
+        this.textContent = "Sent to Zotero";
+        showStatus(`Sent to Zotero: ${fileInfo.name}`, "success");
+      } catch (error) {
+        this.disabled = false;
+        showStatus(`Zotero: ${getErrorMessage(error)}`, "error");
+      }
+    });
+    actions.querySelector(".bibtex-btn").addEventListener("click", async function() {
+      this.disabled = true;
+      try {
+        const bibPath = await invoke("save_paper_bibtex", { filePath: fileInfo.path, destFolder });
+        this.textContent = "Saved .bib";
+        showStatus(`Saved ${pathBasename(bibPath)}`, "success");
+      } catch (error) {
+        this.disabled = false;
+        showStatus(`Couldn't save .bib: ${getErrorMessage(error)}`, "error");
+      }
+    });
+  }
+
   // Add a file to the skipped (non-educational) list
   function addToSkippedList(fileInfo, reasoning) {
     skippedFiles.push({ name: fileInfo.name, path: fileInfo.path, size: fileInfo.size, reasoning });
@@ -1319,7 +2010,6 @@ function initApp() {
       });
 
       const suggestionDiv = fileItem.querySelector(".ai-suggestion");
-      const confidencePercent = Math.round(classification.confidence * 100);
       const confidenceClass = classification.confidence > 0.8 ? "high" : classification.confidence > 0.5 ? "medium" : "low";
       const suggestedModuleName = pathBasename(classification.suggested_folder) || "Unknown";
 
@@ -1327,10 +2017,10 @@ function initApp() {
         suggestionDiv.innerHTML = `
           <div class="ai-result ${confidenceClass}">
             <strong>AI Suggests:</strong> ${escapeHtml(suggestedModuleName)}
-            <span class="confidence">${confidencePercent}% confident</span>
+            <span class="confidence">${escapeHtml(describeConfidence(classification))}</span>
             <button class="accept-btn">Accept</button>
           </div>
-          <div class="ai-reasoning">${escapeHtml(classification.reasoning)}</div>
+          <div class="ai-reasoning" title="${escapeHtml(classification.reasoning)}">${escapeHtml(describeExplanation(classification))}</div>
         `;
         suggestionDiv.querySelector(".accept-btn").addEventListener("click", function() {
           acceptAISuggestion(fileInfo.path, classification.suggested_folder, this);
@@ -1375,6 +2065,9 @@ function initApp() {
       </div>
       <div class="file-preview-toggle">
         <button class="preview-btn">Preview</button>
+        <button class="preview-btn open-file-btn" title="Open in its default app (O)">Open</button>
+        <button class="preview-btn open-with-btn" title="Open in another app">Open with...</button>
+        <select class="open-with-select" style="display: none;"></select>
       </div>
       <div class="file-preview" style="display: none;"></div>
       <div class="ai-suggestion">
@@ -1382,7 +2075,7 @@ function initApp() {
       </div>
       <div class="file-actions">
         <select class="folder-select">
-          ${buildFolderOptions()}
+          ${buildFolderOptions(findWatchProfile(fileInfo.path, watchProfiles)?.folders)}
         </select>
         <button class="move-btn">Move</button>
         <button class="ignore-btn">Ignore</button>
@@ -1411,9 +2104,9 @@ function initApp() {
       this.disabled = true;
       this.textContent = "Deleting...";
       try {
-        await invoke("trash_file", { filePath: fileInfo.path });
+        const outcome = await invoke("trash_file", { filePath: fileInfo.path });
         removeFileFromUI(fileInfo.path, fileItem);
-        showStatus(`Deleted: ${fileInfo.name} (sent to Recycle Bin)`, "success");
+        showStatus(`Deleted: ${fileInfo.name} (${outcome})`, "success");
       } catch (error) {
         showStatus(`Delete failed: ${error}`, "error");
         this.disabled = false;
@@ -1441,13 +2134,15 @@ function initApp() {
     });
 
     const doRename = async () => {
-      const newName = renameInput.value.trim();
-      if (!newName || newName === fileInfo.name) {
+      const typedName = renameInput.value.trim();
+      if (!typedName || typedName === fileInfo.name) {
         renameInputDiv.style.display = "none";
         return;
       }
       try {
-        const newPath = await invoke("rename_file", { filePath: fileInfo.path, newName });
+        const newPath = await invoke("rename_file", { filePath: fileInfo.path, newName: typedName });
+        // The name is sanitized Rust-side, so it may differ from what was typed
+        const newName = pathBasename(newPath);
         // Update fileInfo in place
         const oldName = fileInfo.name;
         fileInfo.name = newName;
@@ -1474,6 +2169,47 @@ function initApp() {
       if (e.key === "Escape") renameInputDiv.style.display = "none";
     });
 
+    // Bind open buttons: look at the file itself before approving where it goes
+    fileItem.querySelector(".open-file-btn").addEventListener("click", async () => {
+      try {
+        await invoke("open_file", { path: fileInfo.path });
+      } catch (error) {
+        showStatus(`Couldn't open ${fileInfo.name}: ${getErrorMessage(error)}`, "error");
+      }
+    });
+    // Only the known viewers and editors installed here are offered (the backend refuses others)
+    const openWithSelect = fileItem.querySelector(".open-with-select");
+    fileItem.querySelector(".open-with-btn").addEventListener("click", async () => {
+      if (openWithSelect.style.display !== "none") {
+        openWithSelect.style.display = "none";
+        return;
+      }
+      try {
+        const apps = await invoke("get_open_with_apps");
+        if (apps.length === 0) {
+          showStatus("No other apps to open files with were found", "error");
+          return;
+        }
+        const lastApp = localStorage.getItem(STORAGE_KEYS.openWithApp);
+        openWithSelect.innerHTML = `<option value="">Choose an app...</option>` +
+          apps.map(app => `<option value="${escapeHtml(app)}"${app === lastApp ? " selected" : ""}>${escapeHtml(app)}</option>`).join("");
+        openWithSelect.style.display = "inline-block";
+      } catch (error) {
+        showStatus(`Couldn't list apps: ${getErrorMessage(error)}`, "error");
+      }
+    });
+    openWithSelect.addEventListener("change", async () => {
+      const app = openWithSelect.value;
+      if (!app) return;
+      openWithSelect.style.display = "none";
+      localStorage.setItem(STORAGE_KEYS.openWithApp, app);
+      try {
+        await invoke("open_with", { path: fileInfo.path, app });
+      } catch (error) {
+        showStatus(`Couldn't open ${fileInfo.name}: ${getErrorMessage(error)}`, "error");
+      }
+    });
+
     // Bind preview button (lazy-load on first click)
     const previewBtn = fileItem.querySelector(".preview-btn");
     const previewDiv = fileItem.querySelector(".file-preview");
@@ -1497,6 +2233,21 @@ function initApp() {
           previewDiv.innerHTML = `<div class="preview-error">${escapeHtml(preview.error)}</div>`;
         } else if (preview.preview_type === "image") {
           previewDiv.innerHTML = `<img class="preview-image" src="${preview.content}" alt="Preview" />`;
+          const meta = await invoke("get_file_metadata", { filePath: fileInfo.path }).catch(() => null);
+          const details = describeExif(meta?.exif);
+          if (details) {
+            previewDiv.insertAdjacentHTML("beforeend", `<div class="preview-details">${escapeHtml(details)}</div>`);
+          }
+        } else if (preview.preview_type === "video") {
+          const info = preview.video || {};
+          const details = [
+            formatDuration(info.duration_secs),
+            info.width && info.height ? `${info.width}×${info.height}` : "",
+          ].filter(Boolean).join(" · ");
+          const poster = preview.content
+            ? `<img class="preview-image" src="${preview.content}" alt="Video frame" />`
+            : "";
+          previewDiv.innerHTML = `${poster}<div class="preview-details">${escapeHtml(details || "Video")}</div>`;
         } else if (preview.preview_type === "text" && preview.content) {
           previewDiv.innerHTML = `<pre class="preview-text">${escapeHtml(preview.content)}</pre>`;
         } else {
@@ -1600,6 +2351,7 @@ function initApp() {
     const fileData = detectedFiles.find(f => f.path === filePath);
     const filename = fileData?.name || pathBasename(filePath);
     const aiSuggested = fileData?.classification?.suggested_folder || "";
+    const aiConfidence = fileData?.classification?.confidence;
     const destModuleName = pathBasename(destFolder);
     const aiModuleName = pathBasename(aiSuggested);
 
@@ -1614,16 +2366,18 @@ function initApp() {
 
       // Log correction: did user agree with AI or pick a different folder?
       if (aiSuggested && destFolder === aiSuggested) {
-        logCorrection(filename, aiModuleName, destModuleName, "accepted");
+        logCorrection(filename, aiModuleName, destModuleName, "accepted", aiConfidence);
       } else if (aiSuggested) {
-        logCorrection(filename, aiModuleName, destModuleName, "corrected");
+        logCorrection(filename, aiModuleName, destModuleName, "corrected", aiConfidence);
       }
 
       // Build the full destination path for undo
       const movedDestPath = pathJoin(destFolder, filename);
-      addActivityEntry(filename, watchPath, destFolder);
+      // The AI's confidence only describes this move when the user kept its folder
+      const confidence = destFolder === aiSuggested ? aiConfidence : null;
+      const activity = await addActivityEntry(filename, watchPath, destFolder, null, filePath, null, confidence);
       renderActivityLog();
-      showUndoToast(filename, movedDestPath, watchPath);
+      showUndoToast(filename, movedDestPath, watchPath, null, activity?.id);
 
       removeFileFromUI(filePath, fileItem);
       sendAppNotification("File moved", `${filename} → ${destModuleName}`);
@@ -1636,17 +2390,22 @@ function initApp() {
         showStatus("File is in use - will auto-move when available", "info");
         retryMoveFile(filePath, destFolder, fileItem, 0, async () => {
           if (aiSuggested && destFolder === aiSuggested) {
-            await logCorrection(filename, aiModuleName, destModuleName, "accepted");
+            await logCorrection(filename, aiModuleName, destModuleName, "accepted", aiConfidence);
           } else if (aiSuggested) {
-            await logCorrection(filename, aiModuleName, destModuleName, "corrected");
+            await logCorrection(filename, aiModuleName, destModuleName, "corrected", aiConfidence);
           }
           const movedDestPath = pathJoin(destFolder, filename);
-          await addActivityEntry(filename, watchPath, destFolder);
+          const confidence = destFolder === aiSuggested ? aiConfidence : null;
+          const activity = await addActivityEntry(filename, watchPath, destFolder, null, filePath, null, confidence);
           renderActivityLog();
-          showUndoToast(filename, movedDestPath, watchPath);
+          showUndoToast(filename, movedDestPath, watchPath, null, activity?.id);
         });
       } else {
-        showStatus(`Failed to move file: ${getErrorMessage(error)}`, "error");
+        if (isCancelledError(error)) {
+          showStatus("Move cancelled - the file was left where it was", "info");
+        } else {
+          showStatus(`Failed to move file: ${getErrorMessage(error)}`, "error");
+        }
         buttonElement.disabled = false;
         buttonElement.textContent = "Move";
       }
@@ -1673,13 +2432,13 @@ function initApp() {
       }
 
       // Log as accepted - AI got it right
-      logCorrection(filename, moduleName, moduleName, "accepted");
+      logCorrection(filename, moduleName, moduleName, "accepted", fileData?.classification?.confidence);
 
       // Activity log and undo
       const movedDestPath = pathJoin(suggestedFolder, filename);
-      addActivityEntry(filename, watchPath, suggestedFolder);
+      const activity = await addActivityEntry(filename, watchPath, suggestedFolder, null, filePath, null, fileData?.classification?.confidence);
       renderActivityLog();
-      showUndoToast(filename, movedDestPath, watchPath);
+      showUndoToast(filename, movedDestPath, watchPath, null, activity?.id);
 
       removeFileFromUI(filePath, fileItem);
       const statusText = moveResult.renamed ? `${moveResult.result} (kept both)` : `${moveResult.result} (AI suggestion accepted)`;
@@ -1690,11 +2449,11 @@ function initApp() {
         buttonElement.textContent = "Waiting...";
         showStatus("File is in use - will auto-move when available", "info");
         retryMoveFile(filePath, suggestedFolder, fileItem, 0, async (result) => {
-          await logCorrection(filename, moduleName, moduleName, "accepted");
+          await logCorrection(filename, moduleName, moduleName, "accepted", fileData?.classification?.confidence);
           const movedDestPath = pathJoin(suggestedFolder, filename);
-          await addActivityEntry(filename, watchPath, suggestedFolder);
+          const activity = await addActivityEntry(filename, watchPath, suggestedFolder, null, filePath, null, fileData?.classification?.confidence);
           renderActivityLog();
-          showUndoToast(filename, movedDestPath, watchPath);
+          showUndoToast(filename, movedDestPath, watchPath, null, activity?.id);
         });
       } else {
         showStatus(`Failed to move file: ${getErrorMessage(error)}`, "error");
@@ -1729,18 +2488,20 @@ function initApp() {
     }
 
     try {
-      await invoke("rename_and_move_file", {
+      const finalPath = await invoke("rename_and_move_file", {
         filePath,
         newName,
         destFolder: suggestedFolder,
+        reasoning: fileData?.classification?.reasoning,
       });
+      newName = pathBasename(finalPath); // sanitized Rust-side
 
-      logCorrection(originalFilename, moduleName, moduleName, "accepted");
+      logCorrection(originalFilename, moduleName, moduleName, "accepted", fileData?.classification?.confidence);
 
       const movedDestPath = pathJoin(suggestedFolder, newName);
-      addActivityEntry(newName, watchPath, suggestedFolder, originalFilename);
+      const activity = await addActivityEntry(newName, watchPath, suggestedFolder, originalFilename, filePath, null, fileData?.classification?.confidence);
       renderActivityLog();
-      showUndoToast(newName, movedDestPath, watchPath, originalFilename);
+      showUndoToast(newName, movedDestPath, watchPath, originalFilename, activity?.id);
 
       removeFileFromUI(filePath, fileItem);
       showStatus(`Renamed & moved: ${originalFilename} → ${newName} → ${moduleName}`, "success");
@@ -1762,6 +2523,8 @@ function initApp() {
   function removeFileFromUI(filePath, fileItem) {
     const index = detectedFiles.findIndex(f => f.path === filePath);
     if (index > -1) detectedFiles.splice(index, 1);
+    invoke("dismiss_quick_sort", { path: filePath }).catch(() => {});
+    invoke("remove_pending_file", { path: filePath }).catch(() => {});
 
     fileCount.textContent = detectedFiles.length;
     updateBatchActions();
@@ -1803,7 +2566,7 @@ function initApp() {
     await new Promise(resolve => setTimeout(resolve, delay));
 
     try {
-      const result = await moveWithAutoRename(filePath, destFolder);
+      const result = await moveWithConflictPolicy(filePath, destFolder);
 
       if (onSuccess) await onSuccess(result);
       removeFileFromUI(filePath, fileItemElement);
@@ -1888,35 +2651,43 @@ function initApp() {
 
     let successCount = 0;
     let failCount = 0;
+    let skippedCount = 0;
+    const sessionId = await dbStartSession(`Accept all high confidence (${highConfidenceItems.length} files)`);
 
     for (const fileItem of highConfidenceItems) {
       const filePath = fileItem.getAttribute("data-file-path");
       const suggestedFolder = fileItem.getAttribute("data-suggested-folder");
 
       try {
-        await moveWithAutoRename(filePath, suggestedFolder);
+        await moveWithConflictPolicy(filePath, suggestedFolder);
 
         const index = detectedFiles.findIndex(f => f.path === filePath);
         const filename = index > -1 ? detectedFiles[index].name : pathBasename(filePath);
+        const confidence = index > -1 ? detectedFiles[index].classification?.confidence : null;
         if (index > -1) detectedFiles.splice(index, 1);
 
-        await addActivityEntry(filename, watchPath, suggestedFolder);
+        await addActivityEntry(filename, watchPath, suggestedFolder, null, filePath, sessionId, confidence);
 
         fileItem.style.opacity = "0";
         setTimeout(() => fileItem.remove(), FILE_REMOVE_ANIMATION_MS);
         successCount++;
       } catch (error) {
+        if (isSkippedError(error)) {
+          skippedCount++;
+          continue;
+        }
         console.error(`Failed to move ${filePath}:`, error);
         failCount++;
         if (isLockedFileError(error)) {
           const retryFilename = filename;
           retryMoveFile(filePath, suggestedFolder, fileItem, 0, async () => {
-            await addActivityEntry(retryFilename, watchPath, suggestedFolder);
+            await addActivityEntry(retryFilename, watchPath, suggestedFolder, null, filePath, sessionId);
             renderActivityLog();
           });
         }
       }
     }
+    if (sessionId != null) dbEndSession(sessionId);
 
     setTimeout(() => {
       fileCount.textContent = detectedFiles.length;
@@ -1925,10 +2696,11 @@ function initApp() {
       if (detectedFiles.length === 0) {
         fileList.innerHTML = '<p class="empty-msg">No files detected yet. Drop a file in your watched folder to test!</p>';
       }
+      const skippedNote = skippedCount > 0 ? `, ${skippedCount} skipped (already exist)` : "";
       if (failCount === 0) {
-        showStatus(`Successfully moved ${successCount} files`, "success");
+        showStatus(`Successfully moved ${successCount} files${skippedNote}`, "success");
       } else {
-        showStatus(`Moved ${successCount} files, ${failCount} files in retry queue`, "info");
+        showStatus(`Moved ${successCount} files, ${failCount} files in retry queue${skippedNote}`, "info");
       }
       acceptAllHighBtn.disabled = false;
       acceptAllHighBtn.textContent = "Accept All High Confidence (0)";
@@ -1950,35 +2722,43 @@ function initApp() {
 
     let successCount = 0;
     let failCount = 0;
+    let skippedCount = 0;
+    const sessionId = await dbStartSession(`Accept batch (${highConfidenceItems.length} files)`);
 
     for (const fileItem of highConfidenceItems) {
       const filePath = fileItem.getAttribute("data-file-path");
       const suggestedFolder = fileItem.getAttribute("data-suggested-folder");
 
       try {
-        await moveWithAutoRename(filePath, suggestedFolder);
+        await moveWithConflictPolicy(filePath, suggestedFolder);
 
         const index = detectedFiles.findIndex(f => f.path === filePath);
         const filename = index > -1 ? detectedFiles[index].name : pathBasename(filePath);
+        const confidence = index > -1 ? detectedFiles[index].classification?.confidence : null;
         if (index > -1) detectedFiles.splice(index, 1);
 
-        await addActivityEntry(filename, watchPath, suggestedFolder);
+        await addActivityEntry(filename, watchPath, suggestedFolder, null, filePath, sessionId, confidence);
 
         fileItem.style.opacity = "0";
         setTimeout(() => fileItem.remove(), FILE_REMOVE_ANIMATION_MS);
         successCount++;
       } catch (error) {
+        if (isSkippedError(error)) {
+          skippedCount++;
+          continue;
+        }
         console.error(`Failed to move ${filePath}:`, error);
         failCount++;
         if (isLockedFileError(error)) {
           const retryFilename = filename;
           retryMoveFile(filePath, suggestedFolder, fileItem, 0, async () => {
-            await addActivityEntry(retryFilename, watchPath, suggestedFolder);
+            await addActivityEntry(retryFilename, watchPath, suggestedFolder, null, filePath, sessionId);
             renderActivityLog();
           });
         }
       }
     }
+    if (sessionId != null) dbEndSession(sessionId);
 
     setTimeout(() => {
       fileCount.textContent = detectedFiles.length;
@@ -1992,10 +2772,11 @@ function initApp() {
       if (detectedFiles.length === 0) {
         fileList.innerHTML = '<p class="empty-msg">No files detected yet. Drop a file in your watched folder to test!</p>';
       }
+      const skippedNote = skippedCount > 0 ? `, ${skippedCount} skipped (already exist)` : "";
       if (failCount === 0) {
-        showStatus(`Batch: Successfully moved ${successCount} files`, "success");
+        showStatus(`Batch: Successfully moved ${successCount} files${skippedNote}`, "success");
       } else {
-        showStatus(`Batch: Moved ${successCount} files, ${failCount} files in retry queue`, "info");
+        showStatus(`Batch: Moved ${successCount} files, ${failCount} files in retry queue${skippedNote}`, "info");
       }
       batchBtn.disabled = false;
       batchBtn.textContent = "Accept All High Confidence in This Batch (0)";
@@ -2003,11 +2784,11 @@ function initApp() {
   }
 
   // Show undo toast after a successful move
-  function showUndoToast(filename, destPath, originalFolder, originalFilename = null) {
+  function showUndoToast(filename, destPath, originalFolder, originalFilename = null, activityId = null) {
     // Cancel any existing undo timer
     cancelUndo();
 
-    lastMove = { filename, destPath, originalFolder, originalFilename, timestamp: Date.now() };
+    lastMove = { filename, destPath, originalFolder, originalFilename, activityId };
     const msg = originalFilename && originalFilename !== filename
       ? `Renamed "${originalFilename}" → "${filename}" and moved`
       : `Moved "${filename}"`;
@@ -2047,7 +2828,7 @@ function initApp() {
   async function handleUndo() {
     if (!lastMove) return;
 
-    const { filename, destPath, originalFolder, originalFilename, timestamp } = lastMove;
+    const { filename, destPath, originalFolder, originalFilename, activityId } = lastMove;
     cancelUndo();
 
     try {
@@ -2055,6 +2836,7 @@ function initApp() {
       await invoke("undo_move", {
         filePath: destPath,
         originalFolder: originalFolder,
+        activityId: activityId,
       });
 
       // Step 2: If it was renamed, restore the original filename
@@ -2067,19 +2849,23 @@ function initApp() {
         } catch (renameError) {
           console.error("[UNDO] Rename-back failed:", renameError);
           showStatus(`File moved back but rename failed: ${getErrorMessage(renameError)}`, "error");
-          markActivityUndone(timestamp);
+          if (activityId != null) markActivityUndone(activityId);
           renderActivityLog();
           return;
         }
       }
 
-      markActivityUndone(timestamp);
+      if (activityId != null) markActivityUndone(activityId);
       renderActivityLog();
       const undoMsg = originalFilename && originalFilename !== filename
         ? `Undo: "${originalFilename}" restored (name and location)`
         : `Undo: "${filename}" restored`;
       showStatus(undoMsg, "success");
     } catch (error) {
+      if (isChangedSinceMoveError(error)) {
+        showStatus(`Not undone: "${filename}" was changed after it was moved, so moving it back could lose newer work`, "error");
+        return;
+      }
       showStatus(`Undo failed: ${getErrorMessage(error)}`, "error");
     }
   }
@@ -2116,14 +2902,14 @@ function initApp() {
         <span class="activity-desc">${wasRenamed ? `${escapeHtml(entry.originalFilename)} → ${escapeHtml(entry.filename)}` : escapeHtml(entry.filename)} → <strong>${escapeHtml(toName)}</strong></span>
         ${wasRenamed ? '<span class="rename-badge">renamed</span>' : ""}
         ${entry.undone ? '<span class="activity-undone-badge">undone</span>' : ""}
-        <button class="folder-link-btn" title="Open folder in Explorer" aria-label="Open folder in Explorer">&#128193;</button>
+        <button class="folder-link-btn" title="Show file in its folder" aria-label="Show file in its folder">&#128193;</button>
       `;
       item.querySelector(".folder-link-btn").addEventListener("click", async (e) => {
         e.stopPropagation();
         try {
-          await openPath(entry.to);
+          await invoke("open_file_location", { path: pathJoin(entry.to, entry.filename) });
         } catch (err) {
-          showStatus(`Failed to open folder: ${err}`, "error");
+          showStatus(`Failed to open folder: ${getErrorMessage(err)}`, "error");
         }
       });
       activityList.appendChild(item);
@@ -2141,6 +2927,47 @@ function initApp() {
 
   // isToday is imported from utils.js
 
+  // Search the watched folder and destination base for files by name
+  async function runSearch() {
+    const query = searchInput.value.trim();
+    const roots = [watchPath, basePath].filter(Boolean);
+    if (!query || roots.length === 0) {
+      searchResults.innerHTML = "";
+      return;
+    }
+
+    searchBtn.disabled = true;
+    try {
+      const results = await invoke("search_files", { query, filters: { roots } });
+      if (results.length === 0) {
+        searchResults.innerHTML = '<p class="empty-msg">No matching files.</p>';
+        return;
+      }
+      searchResults.innerHTML = "";
+      for (const result of results) {
+        const item = document.createElement("div");
+        item.className = "activity-item";
+        item.innerHTML = `
+          <span class="activity-desc">${escapeHtml(result.name)} <small>in <strong>${escapeHtml(pathBasename(result.folder))}</strong></small></span>
+          <span class="activity-time">${formatFileSize(result.size)}</span>
+          <button class="folder-link-btn" title="Show file in its folder" aria-label="Show file in its folder">&#128193;</button>
+        `;
+        item.querySelector(".folder-link-btn").addEventListener("click", async () => {
+          try {
+            await invoke("open_file_location", { path: result.path });
+          } catch (err) {
+            showStatus(`Failed to open folder: ${getErrorMessage(err)}`, "error");
+          }
+        });
+        searchResults.appendChild(item);
+      }
+    } catch (error) {
+      showStatus(`Search failed: ${getErrorMessage(error)}`, "error");
+    } finally {
+      searchBtn.disabled = false;
+    }
+  }
+
   // Render statistics dashboard
   function renderStats() {
     const statsSection = document.getElementById("stats-section");
@@ -2174,6 +3001,75 @@ function initApp() {
         ).join("")}</div></div>` : ""}
     `;
     statsSection.style.display = total > 0 || correctionLog.length > 0 ? "block" : "none";
+    if (statsSection.style.display === "block") {
+      refreshFolderStats();
+      refreshAccuracyReport();
+    }
+    refreshTimeSaved();
+  }
+
+  // Add a "you saved ~2.5 hours this month" card once this month's moves are counted
+  async function refreshTimeSaved() {
+    const statsGrid = document.getElementById("stats-grid");
+    try {
+      const saved = describeTimeSaved(await invoke("get_time_saved", { since: null }));
+      if (!statsGrid || !saved) return;
+      document.getElementById("time-saved-card")?.remove();
+      const card = document.createElement("div");
+      card.id = "time-saved-card";
+      card.className = "stat-card";
+      card.innerHTML = `<div class="stat-value">${escapeHtml(saved)}</div><div class="stat-label">Saved This Month</div>`;
+      statsGrid.prepend(card);
+    } catch (error) {
+      console.error("[STATS] Failed to load time saved:", error);
+    }
+  }
+
+  // Show the folders the AI most often gets wrong
+  async function refreshAccuracyReport() {
+    const list = document.getElementById("folder-accuracy-list");
+    if (!list) return;
+    try {
+      const report = await invoke("get_accuracy_report");
+      const folders = report.folders.filter(f => f.accuracy !== null && f.corrected > 0).slice(0, 5);
+      list.innerHTML = folders.map(f => `<div class="folder-stats-row">
+          <span class="folder-stats-name">${escapeHtml(f.folder)}</span>
+          <span class="folder-stats-summary">${Math.round(f.accuracy * 100)}% accurate \u00b7 ${f.corrected} corrected, ${f.accepted} accepted</span>
+        </div>`).join("");
+    } catch (error) {
+      console.error("[STATS] Failed to load accuracy report:", error);
+    }
+  }
+
+  // Show how each destination folder is growing (file count, size, last file added)
+  let folderStatsLoading = false;
+  async function refreshFolderStats() {
+    const list = document.getElementById("folder-stats-list");
+    const folders = getAvailableFolders();
+    if (!list || folderStatsLoading) return;
+    if (folders.length === 0) {
+      list.innerHTML = "";
+      return;
+    }
+
+    folderStatsLoading = true;
+    try {
+      const stats = await invoke("get_folder_stats", { paths: folders });
+      list.innerHTML = stats.map(s => {
+        const newest = s.newest
+          ? `<span class="folder-stats-newest">Last added ${new Date(s.newest.modified * 1000).toLocaleDateString([], { month: "short", day: "numeric" })}</span>`
+          : "";
+        return `<div class="folder-stats-row">
+          <span class="folder-stats-name">${escapeHtml(pathBasename(s.path))}</span>
+          <span class="folder-stats-summary">${escapeHtml(summarizeFolderStats(s))}</span>
+          ${newest}
+        </div>`;
+      }).join("");
+    } catch (error) {
+      console.error("[STATS] Failed to load folder stats:", error);
+    } finally {
+      folderStatsLoading = false;
+    }
   }
 
   // Show status message with dismiss button
@@ -2229,18 +3125,21 @@ function initApp() {
 
   // Move file with duplicate detection — interactive (shows dialog)
   async function moveWithDuplicateCheck(sourcePath, destFolder, fileData) {
+    // The AI's reasoning is kept as the file's provenance when its folder was accepted
+    const classification = fileData?.classification;
+    const reasoning = classification?.suggested_folder === destFolder ? classification.reasoning : null;
     try {
-      const result = await invoke("move_file", { sourcePath, destFolder });
+      const result = await invoke("move_file", { sourcePath, destFolder, reasoning });
       return { success: true, result };
     } catch (error) {
       if (isDuplicateError(error)) {
         const filename = fileData?.name || pathBasename(sourcePath);
         const action = await showDuplicateDialog(filename);
         if (action === "replace") {
-          const result = await invoke("replace_file", { sourcePath, destFolder });
+          const result = await invoke("replace_file", { sourcePath, destFolder, reasoning });
           return { success: true, result, replaced: true };
         } else if (action === "keep-both") {
-          const result = await invoke("move_file_with_rename", { sourcePath, destFolder });
+          const result = await invoke("move_file_with_rename", { sourcePath, destFolder, reasoning });
           return { success: true, result, renamed: true };
         }
         return { success: false, skipped: true };
@@ -2249,17 +3148,14 @@ function initApp() {
     }
   }
 
-  // Move file with silent auto-rename on duplicate (for auto-move / batch / retry)
-  async function moveWithAutoRename(sourcePath, destFolder) {
-    try {
-      const result = await invoke("move_file", { sourcePath, destFolder });
-      return result;
-    } catch (error) {
-      if (isDuplicateError(error)) {
-        return await invoke("move_file_with_rename", { sourcePath, destFolder });
-      }
-      throw error;
+  // Move file resolving duplicates with the user's conflict policy (for auto-move / batch / retry)
+  // Throws a "Skipped" error when the policy leaves the file where it is.
+  async function moveWithConflictPolicy(sourcePath, destFolder) {
+    const result = await invoke("move_file_with_policy", { sourcePath, destFolder });
+    if (result.outcome === "skipped") {
+      throw { type: "Skipped", message: `${pathBasename(sourcePath)} already exists in the destination` };
     }
+    return `Moved to ${result.dest_path}`;
   }
 }
 
@@ -3,11 +3,49 @@
 
 import {
   formatFileSize,
+  formatDuration,
+  formatFileOpProgress,
+  summarizeFolderStats,
+  summarizeOrganizeResult,
+  describeDeferredMoves,
+  describeFolderSuggestion,
+  describeTimetableCourses,
+  describeLmsSync,
+  describeProfileImport,
+  describeDataLocation,
+  describeBudget,
+  describeModelReliability,
+  describeEmbeddingStatus,
+  describePaperId,
+  buildScheduleSettings,
+  buildQuotaSettings,
+  describeTimeSaved,
+  describeTruncation,
+  describeConfidence,
+  describeExplanation,
+  describeLink,
+  describeRestore,
+  describeLinkReport,
+  flattenDiskUsage,
+  parseHeaderLines,
+  formatHeaderLines,
+  parseAliasLines,
+  formatAliasLines,
+  parseFallbackChain,
+  formatFallbackChain,
+  buildShortcutSettings,
+  buildQuickSortItem,
+  describeQuickSortItem,
+  summarizeDiagnostics,
+  buildAllowedRoots,
+  parseExifDate,
+  describeExif,
   escapeHtml,
   getFileExt,
   getFileTypeIcon,
   isImageFile,
   isContentExtractable,
+  isArchiveFile,
   isToday,
   shouldGroupAsBatch,
   validateModuleName,
@@ -15,6 +53,11 @@ import {
   filterNewFiles,
   getCachedClassification,
   matchRule,
+  findWatchProfile,
+  rulesForProfile,
+  getFolderPref,
+  getQuarantineReason,
+  applyNamingTemplate,
   pathJoin,
   pathBasename,
 } from "./utils.js";
@@ -57,8 +100,11 @@ function assertDeepEqual(actual, expected, message) {
 }
 
 // --- Activity log helpers (app-specific, not in utils.js) ---
+let nextActivityId = 1;
+
 function addActivityEntry(activityLog, filename, fromFolder, toFolder) {
   const entry = {
+    id: nextActivityId++,
     filename,
     from: fromFolder,
     to: toFolder,
@@ -69,8 +115,8 @@ function addActivityEntry(activityLog, filename, fromFolder, toFolder) {
   return entry;
 }
 
-function markActivityUndone(activityLog, timestamp) {
-  const entry = activityLog.find(e => e.timestamp === timestamp);
+function markActivityUndone(activityLog, id) {
+  const entry = activityLog.find(e => e.id === id);
   if (entry) {
     entry.undone = true;
   }
@@ -90,6 +136,446 @@ assertEqual(formatFileSize(1073741824), "1 GB", "1 GB");
 assertEqual(formatFileSize(1), "1 Bytes", "1 byte");
 assertEqual(formatFileSize(2048), "2 KB", "2 KB");
 
+console.log("\n=== formatDuration ===");
+assertEqual(formatDuration(0), "0:00", "zero");
+assertEqual(formatDuration(65), "1:05", "minutes and seconds");
+assertEqual(formatDuration(3605.4), "1:00:05", "over an hour");
+assertEqual(formatDuration(undefined), "", "missing duration");
+
+console.log("\n=== formatFileOpProgress ===");
+assertEqual(
+  formatFileOpProgress({ filename: "week3.mp4", bytes_copied: 1048576, total_bytes: 1073741824, bytes_per_sec: 2048 }),
+  "week3.mp4: 1 MB of 1 GB (2 KB/s)",
+  "progress with speed"
+);
+assertEqual(
+  formatFileOpProgress({ filename: "week3.mp4", bytes_copied: 0, total_bytes: 1024, bytes_per_sec: 0 }),
+  "week3.mp4: 0 Bytes of 1 KB",
+  "no speed yet"
+);
+
+console.log("\n=== summarizeFolderStats ===");
+{
+  const stats = (file_count, total_size, extensions) => ({ file_count, total_size, extensions, error: null });
+  assertEqual(
+    summarizeFolderStats(stats(3, 2048, [{ extension: "pdf", count: 2 }, { extension: "md", count: 1 }])),
+    "3 files, 2 KB, mostly PDF",
+    "majority extension"
+  );
+  assertEqual(
+    summarizeFolderStats(stats(2, 1024, [{ extension: "pdf", count: 1 }, { extension: "md", count: 1 }])),
+    "2 files, 1 KB",
+    "no majority extension"
+  );
+  assertEqual(summarizeFolderStats(stats(1, 1024, [{ extension: "", count: 1 }])), "1 file, 1 KB", "extensionless file");
+  assertEqual(summarizeFolderStats(stats(0, 0, [])), "Empty", "empty folder");
+  assertEqual(summarizeFolderStats({ error: "Not a folder" }), "Unavailable", "unreadable folder");
+}
+
+console.log("\n=== summarizeOrganizeResult ===");
+assertEqual(summarizeOrganizeResult({ moved: 5, skipped: [], failed: [] }), "Organized: 5 moved", "all moved");
+assertEqual(
+  summarizeOrganizeResult({ moved: 2, skipped: [{}, {}], failed: [{}] }),
+  "Organized: 2 moved, 2 left in place, 1 failed",
+  "mixed outcome"
+);
+assertEqual(
+  summarizeOrganizeResult({ moved: 1, deferred: 3, skipped: [], failed: [] }),
+  "Organized: 1 moved, 3 waiting for their drive",
+  "deferred for an unplugged drive"
+);
+
+console.log("\n=== describeDeferredMoves ===");
+assertEqual(
+  describeDeferredMoves({ volumes: ["E:\\"], moved: 3, skipped: 0, failed: [] }),
+  "E:\\ plugged back in: 3 moved",
+  "all moved"
+);
+assertEqual(
+  describeDeferredMoves({ volumes: ["/Volumes/USB", "/Volumes/NAS"], moved: 1, skipped: 1, failed: [{}] }),
+  "/Volumes/USB, /Volumes/NAS plugged back in: 1 moved, 1 left in place, 1 failed",
+  "mixed outcome"
+);
+
+console.log("\n=== describeFolderSuggestion ===");
+assertEqual(
+  describeFolderSuggestion({ name: "Linear Algebra", files: ["/dl/la_ps1.pdf", "/dl/la_ps2.pdf", "/dl/matrices.pdf"] }),
+  "3 files: la_ps1.pdf, la_ps2.pdf, matrices.pdf",
+  "lists every name when short"
+);
+assertEqual(
+  describeFolderSuggestion({ name: "Jobs", files: ["/a/cv.pdf", "/a/cover.docx", "/a/offer.pdf", "/a/ref.pdf"] }, 2),
+  "4 files: cv.pdf, cover.docx +2 more",
+  "truncates long lists"
+);
+
+console.log("\n=== describeTimetableCourses ===");
+assertEqual(
+  describeTimetableCourses([{ folder_name: "ECON202 Macro", sessions: 2, rule_pattern: "*ECON202*" }]),
+  "Create 1 course folder with filename rules?\n\n- ECON202 Macro (2 sessions, rule *ECON202*)",
+  "lists each course with its rule"
+);
+
+console.log("\n=== describePaperId ===");
+assertEqual(describePaperId({ kind: "doi", id: "10.1145/3290605.3300233" }), "DOI 10.1145/3290605.3300233", "DOI label");
+assertEqual(describePaperId({ kind: "arxiv", id: "1706.03762v7" }), "arXiv 1706.03762v7", "arXiv label");
+
+console.log("\n=== buildAllowedRoots ===");
+assertDeepEqual(
+  buildAllowedRoots({
+    watchPath: "C:\\Downloads",
+    basePath: "C:\\Uni",
+    watchProfiles: [{ watch_path: "C:\\Screenshots", folders: ["C:\\Notes", "C:\\Uni"] }],
+    quarantineSettings: { folder: "C:\\Review", enabled: true },
+    classificationMode: { mode: "ai", bucket_folder: null },
+  }),
+  ["C:\\Downloads", "C:\\Uni", "C:\\Screenshots", "C:\\Notes", "C:\\Review"],
+  "watched folders and destination trees, without duplicates or blanks"
+);
+assertDeepEqual(
+  buildAllowedRoots({ watchPath: null, basePath: "", watchProfiles: [], quarantineSettings: {}, classificationMode: {} }),
+  [],
+  "nothing configured yet"
+);
+
+console.log("\n=== summarizeDiagnostics ===");
+const okCheck = { name: "Database", status: "ok", detail: "Integrity check passed" };
+assertEqual(summarizeDiagnostics({ ok: true, checks: [okCheck, okCheck] }), "All 2 checks passed", "all passed");
+assertEqual(
+  summarizeDiagnostics({
+    ok: false,
+    checks: [
+      okCheck,
+      { name: "Tesseract OCR", status: "warning", detail: "Not found" },
+      { name: "Watch folder", status: "error", detail: "C:\\Downloads doesn't exist" },
+    ],
+  }),
+  "1 problem, 1 warning",
+  "problems and warnings"
+);
+
+console.log("\n=== describeLmsSync ===");
+assertEqual(describeLmsSync([]), "No active courses found", "no courses");
+assertEqual(
+  describeLmsSync([
+    { course: { name: "Machine Learning" }, folder: "C:\\Uni\\ML", created: false },
+    { course: { name: "Macroeconomics" }, folder: "C:\\Uni\\ECON202 Macroeconomics", created: true },
+    { course: { name: "Library Skills" }, folder: null, created: false },
+  ]),
+  "Synced 3 courses: 1 matched to folders, 1 new folder, 1 without a folder",
+  "counts matched, created and unmatched courses"
+);
+
+console.log("\n=== describeProfileImport ===");
+assertEqual(
+  describeProfileImport({ settings: 20, rules_added: 1, watch_profiles_added: 1, watch_profiles_updated: 1, folder_prefs: 3 }),
+  "Imported 20 settings, 1 new rule, 2 watch profiles, 3 folder preferences",
+  "lists everything imported"
+);
+assertEqual(
+  describeProfileImport({ settings: 1, rules_added: 0, watch_profiles_added: 0, watch_profiles_updated: 0, folder_prefs: 0 }),
+  "Imported 1 setting, 0 new rules",
+  "leaves out empty profile and folder counts"
+);
+
+console.log("\n=== describeEmbeddingStatus ===");
+{
+  const status = { state: "not_checked", downloaded: 0, total: 0, error: null, provider_fallback: true, provider_fallbacks: 0 };
+  assertEqual(
+    describeEmbeddingStatus({ ...status, state: "ready" }),
+    "Using the local model: filenames stay on this computer",
+    "local model in use"
+  );
+  assertEqual(
+    describeEmbeddingStatus({ ...status, state: "downloading", downloaded: 1024 * 1024, total: 4 * 1024 * 1024 }),
+    "Downloading the local model: 1 MB of 4 MB (the AI provider is used until it's ready)",
+    "shows download progress and the fallback"
+  );
+  assertEqual(
+    describeEmbeddingStatus({ ...status, state: "failed", error: "model.safetensors doesn't match its known checksum", provider_fallbacks: 2 }),
+    "The local model couldn't be set up (model.safetensors doesn't match its known checksum), so the AI provider is used. 2 requests went to the AI provider so far.",
+    "says why the AI provider is used, and how often it was"
+  );
+  assertEqual(
+    describeEmbeddingStatus({ ...status, state: "downloading", provider_fallback: false }),
+    "Downloading the local model (similarity features wait until it's ready)",
+    "without the AI provider, features wait for the model"
+  );
+  assertEqual(
+    describeEmbeddingStatus({ ...status, state: "missing", provider_fallback: false }),
+    "No local model, so similarity features are off.",
+    "nothing is sent to the AI provider unless allowed"
+  );
+  assertEqual(
+    describeEmbeddingStatus(status),
+    "The local model is set up the first time it's needed",
+    "not needed yet"
+  );
+}
+
+console.log("\n=== describeBudget ===");
+{
+  const settings = { monthly_limit_usd: 5, downgrade_model: "gpt-4o-mini", downgrade_at: 0.8 };
+  assertEqual(
+    describeBudget({ settings, month: "2026-10", spent_usd: 1.234, state: "normal" }),
+    "$1.23 of $5.00 spent in 2026-10",
+    "spending under the limit"
+  );
+  assertEqual(
+    describeBudget({ settings, month: "2026-10", spent_usd: 4.2, state: "downgraded" }),
+    "$4.20 of $5.00 spent in 2026-10 (using gpt-4o-mini)",
+    "names the cheaper model once downgraded"
+  );
+  assertEqual(
+    describeBudget({ settings, month: "2026-10", spent_usd: 5.01, state: "exceeded" }),
+    "$5.01 of $5.00 spent in 2026-10 (limit reached: free classifiers only)",
+    "says when the limit is reached"
+  );
+  assertEqual(
+    describeBudget({ settings: { ...settings, monthly_limit_usd: 0 }, month: "2026-10", spent_usd: 0.5, state: "normal" }),
+    "$0.50 spent in 2026-10 (no limit)",
+    "no limit set"
+  );
+}
+
+console.log("\n=== describeModelReliability ===");
+assertEqual(
+  describeModelReliability([
+    { model: "llama3.2", calls: 10, repairs: 2, repaired: 1 },
+    { model: "gpt-4o-mini", calls: 40, repairs: 0, repaired: 0 },
+  ]),
+  "Malformed replies: llama3.2 2 of 10 (1 fixed)",
+  "only models that needed repairs are named"
+);
+assertEqual(describeModelReliability([{ model: "gpt-4o-mini", calls: 3, repairs: 0, repaired: 0 }]), "", "nothing to report");
+
+console.log("\n=== describeDataLocation ===");
+assertEqual(
+  describeDataLocation({ database: "C:\\AppData\\file_organiser.db", portable: false, relocated: false }),
+  "Database: C:\\AppData\\file_organiser.db",
+  "app data folder needs no explanation"
+);
+assertEqual(
+  describeDataLocation({ database: "E:\\data\\file_organiser.db", portable: true, relocated: false }),
+  "Database: E:\\data\\file_organiser.db (portable mode)",
+  "says when portable mode is on"
+);
+assertEqual(
+  describeDataLocation({ database: "D:\\Sync\\file_organiser.db", portable: true, relocated: true }),
+  "Database: D:\\Sync\\file_organiser.db (moved there by you)",
+  "a chosen folder wins over portable mode"
+);
+assertEqual(
+  describeDataLocation({ database: "D:\\Dropbox\\file_organiser.db", portable: false, relocated: true, synced: "Dropbox" }),
+  "Database: D:\\Dropbox\\file_organiser.db (moved there by you; synced by Dropbox, saved in sync-safe mode)",
+  "mentions the sync client"
+);
+
+console.log("\n=== buildScheduleSettings ===");
+{
+  const base = { enabled: true, watchPath: "C:\\Downloads", basePath: "C:\\Uni", userModules: ["ML"], threshold: 0.9 };
+  const hourly = buildScheduleSettings({ ...base, frequency: "60" });
+  assertEqual(JSON.stringify(hourly.schedule), JSON.stringify({ kind: "interval", minutes: 60 }), "interval schedule");
+  assertEqual(hourly.folders[0], "C:\\Downloads", "watched folder is scheduled");
+  assertEqual(hourly.available_folders[0], "C:\\Uni\\ML", "destination folders from modules");
+  assertEqual(hourly.min_confidence, 0.9, "uses auto-move threshold");
+  const daily = buildScheduleSettings({ ...base, frequency: "daily", time: "07:45" });
+  assertEqual(JSON.stringify(daily.schedule), JSON.stringify({ kind: "daily", hour: 7, minute: 45 }), "daily schedule");
+  assertEqual(buildScheduleSettings({ ...base, watchPath: "", frequency: "60" }).folders.length, 0, "no watched folder");
+}
+
+console.log("\n=== buildQuotaSettings ===");
+{
+  const settings = buildQuotaSettings({ enabled: true, limitGb: "2.5", watchPath: "C:\\Downloads" });
+  assertDeepEqual(settings.quotas, [{ folder: "C:\\Downloads", max_bytes: 2684354560 }], "limit in bytes for the watched folder");
+  assertEqual(buildQuotaSettings({ enabled: true, limitGb: "", watchPath: "C:\\Downloads" }).quotas[0].max_bytes, 10737418240, "defaults to 10 GB");
+  assertEqual(buildQuotaSettings({ enabled: false, limitGb: "5", watchPath: "" }).quotas.length, 0, "no watched folder");
+}
+
+console.log("\n=== describeTimeSaved ===");
+{
+  assertEqual(describeTimeSaved({ manual_seconds_saved: 0 }), null, "nothing moved yet");
+  assertEqual(describeTimeSaved({ manual_seconds_saved: 45 }), "~45 sec");
+  assertEqual(describeTimeSaved({ manual_seconds_saved: 900 }), "~15 min");
+  assertEqual(describeTimeSaved({ manual_seconds_saved: 9000 }), "~2.5 hours");
+  assertEqual(describeTimeSaved({ manual_seconds_saved: 3600 }), "~1 hour");
+}
+
+console.log("\n=== describeTruncation ===");
+{
+  assertEqual(describeTruncation(null), null);
+  assertEqual(
+    describeTruncation({ budget: 12000, original_tokens: 15000, corrections_dropped: 1, content_tokens_dropped: 2500 }),
+    "Prompt trimmed to 12,000 tokens: left out 1 older correction and 2,500 tokens of file text"
+  );
+  assertEqual(
+    describeTruncation({ budget: 4000, original_tokens: 4100, corrections_dropped: 3, content_tokens_dropped: 0 }),
+    "Prompt trimmed to 4,000 tokens: left out 3 older corrections"
+  );
+}
+
+console.log("\n=== describeConfidence ===");
+{
+  assertEqual(describeConfidence({ confidence: 0.954 }), "95% confident");
+  assertEqual(describeConfidence({ confidence: 0.95, calibrated_confidence: null }), "95% confident");
+  assertEqual(describeConfidence({ confidence: 0.95, calibrated_confidence: 0.412 }), "95% confident (usually right 41%)");
+}
+
+console.log("\n=== describeExplanation ===");
+{
+  assertEqual(describeExplanation({ reasoning: "Lecture on ML" }), "Lecture on ML");
+  assertEqual(describeExplanation({ reasoning: "Lecture on ML", explanation: { keywords: [], content_clues: [] } }), "Lecture on ML");
+  assertEqual(
+    describeExplanation({
+      reasoning: "Lecture on ML",
+      explanation: { keywords: ["gradient descent", "loss"], content_clues: [], similar_correction: "ps3.pdf" },
+    }),
+    "Matched: 'gradient descent', 'loss'; similar to past correction \"ps3.pdf\""
+  );
+  assertEqual(
+    describeExplanation({ explanation: { keywords: [], content_clues: ["a loss curve"], similar_correction: null } }),
+    "Content shows a loss curve"
+  );
+}
+
+console.log("\n=== describeLink ===");
+{
+  assertEqual(describeLink(null), null);
+  assertEqual(describeLink({ target: "C:\\Uni\\ML\\week3.pdf", is_url: false, exists: true, title: null }), "Shortcut to C:\\Uni\\ML\\week3.pdf");
+  assertEqual(
+    describeLink({ target: "D:\\old.docx", is_url: false, exists: false, title: null }),
+    "Shortcut to D:\\old.docx, which no longer exists - delete the shortcut?"
+  );
+  assertEqual(
+    describeLink({ target: "https://canvas.example.edu", is_url: true, exists: true, title: "COMP4702" }),
+    "Shortcut to COMP4702 (https://canvas.example.edu)"
+  );
+}
+
+console.log("\n=== describeRestore ===");
+{
+  assertEqual(describeRestore({ restored: ["C:\\Downloads\\a.pdf"], failed: [] }), "Restored 1 file");
+  assertEqual(
+    describeRestore({ restored: [], failed: [{ path: "C:\\Downloads\\a.pdf", reason: "File exists" }] }),
+    "Couldn't restore 1 file (File exists)"
+  );
+  assertEqual(
+    describeRestore({ restored: ["a", "b"], failed: [{ path: "c", reason: "x" }, { path: "d", reason: "y" }] }),
+    "Restored 2 files, couldn't restore 2"
+  );
+}
+
+console.log("\n=== describeLinkReport ===");
+{
+  assertEqual(describeLinkReport({ linked: ["a", "b"], reclaimed: 2048, failed: [] }), `Linked 2 copies, freed ${formatFileSize(2048)}`);
+  assertEqual(
+    describeLinkReport({ linked: [], reclaimed: 0, failed: [{ path: "D:\\a.pdf", reason: "On a different drive than the kept file" }] }),
+    "Couldn't link 1 copy (On a different drive than the kept file)"
+  );
+  assertEqual(
+    describeLinkReport({ linked: ["a"], reclaimed: 10, failed: [{ path: "b", reason: "x" }, { path: "c", reason: "y" }] }),
+    `Linked 1 copy, freed ${formatFileSize(10)}; couldn't link 2`
+  );
+}
+
+console.log("\n=== flattenDiskUsage ===");
+{
+  const usage = {
+    name: "Year2", path: "/Year2", size: 400, children: [
+      { name: "ML", path: "/Year2/ML", size: 300, children: [
+        { name: "Lectures", path: "/Year2/ML/Lectures", size: 250, children: [] },
+      ] },
+      { name: "Physics", path: "/Year2/Physics", size: 100, children: [] },
+    ],
+  };
+  const rows = flattenDiskUsage(usage);
+  assertEqual(rows.map(r => r.name).join(","), "ML,Lectures,Physics", "parents before their subfolders");
+  assertEqual(rows.map(r => r.depth).join(","), "0,1,0");
+  assertEqual(rows[0].share, 0.75);
+  assertEqual(flattenDiskUsage({ name: "Empty", path: "/Empty", size: 0, children: [] }).length, 0);
+}
+
+console.log("\n=== parseHeaderLines ===");
+{
+  const headers = parseHeaderLines("X-Team: research\n\nAuthorization: Bearer abc:def\nnot a header");
+  assertDeepEqual(headers, { "X-Team": "research", Authorization: "Bearer abc:def" }, "splits on the first colon, skips the rest");
+  assertEqual(formatHeaderLines(headers), "X-Team: research\nAuthorization: Bearer abc:def");
+  assertDeepEqual(parseHeaderLines(""), {});
+}
+
+console.log("\n=== parseAliasLines ===");
+{
+  const aliases = parseAliasLines("Operations Research = OR\n\nMachine Learning=C:\\Uni\\ML\nno alias here");
+  assertDeepEqual(aliases, [
+    { alias: "Operations Research", folder: "OR" },
+    { alias: "Machine Learning", folder: "C:\\Uni\\ML" },
+  ], "splits on the first equals sign, skips the rest");
+  assertEqual(formatAliasLines(aliases), "Operations Research = OR\nMachine Learning = C:\\Uni\\ML");
+  assertDeepEqual(parseAliasLines(""), []);
+}
+
+console.log("\n=== parseFallbackChain ===");
+{
+  const chain = parseFallbackChain("OpenAI:gpt-4o-mini, openai:gpt-3.5-turbo,\nollama, mock,");
+  assertDeepEqual(chain, [
+    { provider: "openai", model: "gpt-4o-mini" },
+    { provider: "openai", model: "gpt-3.5-turbo" },
+    { provider: "ollama", model: null },
+    { provider: "mock", model: null },
+  ]);
+  assertEqual(formatFallbackChain(chain), "openai:gpt-4o-mini, openai:gpt-3.5-turbo, ollama, mock");
+  assertDeepEqual(parseFallbackChain(""), []);
+}
+
+console.log("\n=== buildQuickSortItem ===");
+{
+  const file = { path: "C:\\Downloads\\PS2.pdf", name: "PS2.pdf" };
+  const folders = ["C:\\Uni\\ML", "C:\\Uni\\Econ"];
+  const item = buildQuickSortItem(file, { suggested_folder: "C:\\Uni\\Econ", confidence: 0.72, reasoning: "Problem set" }, folders);
+  assertEqual(item.suggested_folder, "C:\\Uni\\Econ", "suggested folder kept");
+  assertEqual(item.folders.length, 2, "folders to change to");
+  assertEqual(describeQuickSortItem(item), "Move to Econ? (72% confident)", "describes suggestion");
+  const unsorted = buildQuickSortItem(file, { suggested_folder: "__UNSORTED__", confidence: 0.4 }, folders);
+  assertEqual(unsorted.suggested_folder, null, "unsorted has no suggestion");
+  assertEqual(unsorted.reasoning, "", "missing reasoning is empty");
+  assertEqual(describeQuickSortItem(unsorted), "No matching folder - choose one", "describes unsorted");
+}
+
+console.log("\n=== buildShortcutSettings ===");
+{
+  const base = { enabled: true, watchPath: "C:\\Downloads", basePath: "C:\\Uni", userModules: ["ML"], threshold: 0.9 };
+  const organize = buildShortcutSettings({ ...base, shortcut: " Ctrl+Shift+K ", action: "organize" });
+  assertEqual(organize.shortcut, "Ctrl+Shift+K", "shortcut trimmed");
+  assertEqual(organize.folder, "C:\\Downloads", "organizes the watched folder");
+  assertEqual(organize.available_folders[0], "C:\\Uni\\ML", "destination folders from modules");
+  const show = buildShortcutSettings({ ...base, watchPath: "", shortcut: "", action: "show_window" });
+  assertEqual(show.shortcut, "CmdOrCtrl+Alt+O", "default shortcut");
+  assertEqual(show.action, "show_window", "show window action");
+  assertEqual(show.folder, "", "no watched folder");
+}
+
+console.log("\n=== parseExifDate / describeExif ===");
+assertEqual(parseExifDate("2025-10-03T14:02:09").getDate(), 3, "capture date day");
+assertEqual(parseExifDate("2025-10-03T14:02:09").getHours(), 14, "capture date is local time");
+assertEqual(parseExifDate(null), null, "missing capture date");
+assertEqual(parseExifDate("garbage"), null, "invalid capture date");
+assertEqual(describeExif(null), "", "no exif");
+assertEqual(describeExif({ camera_make: "Canon", camera_model: "Canon EOS 80D" }), "Canon EOS 80D", "make not repeated");
+assertEqual(describeExif({ camera_make: "Apple", camera_model: "iPhone 13" }), "Apple iPhone 13", "make and model");
+assert(describeExif({ captured_at: "2025-10-03T14:02:09" }).startsWith("Taken "), "capture date only");
+
+console.log("\n=== getQuarantineReason ===");
+{
+  const on = { enabled: true, folder: "C:\\Needs Review" };
+  const relevant = (folder, confidence) => ({ is_relevant: true, suggested_folder: folder, confidence });
+  assertEqual(getQuarantineReason(relevant("__UNSORTED__", 0.9), on, 0.7), "unsorted", "unsorted file");
+  assertEqual(getQuarantineReason(relevant("C:\\ML", 0.5), on, 0.7), "low_confidence", "low confidence");
+  assertEqual(getQuarantineReason(relevant("C:\\ML", 0.7), on, 0.7), null, "at threshold");
+  assertEqual(getQuarantineReason({ is_relevant: false, suggested_folder: "", confidence: 0 }, on, 0.7), null, "irrelevant is skipped, not quarantined");
+  assertEqual(getQuarantineReason(relevant("__UNSORTED__", 0.9), { enabled: false, folder: "C:\\Needs Review" }, 0.7), null, "disabled");
+  assertEqual(getQuarantineReason(relevant("__UNSORTED__", 0.9), { enabled: true, folder: null }, 0.7), null, "no folder");
+}
+
 console.log("\n=== getFileExt ===");
 assertEqual(getFileExt("lecture.pdf"), "pdf", "simple pdf");
 assertEqual(getFileExt("photo.PNG"), "png", "uppercase ext");
@@ -119,6 +605,11 @@ assert(!isContentExtractable("photo.png"), "png is NOT extractable");
 assert(!isContentExtractable("app.exe"), "exe is NOT extractable");
 assert(!isContentExtractable("doc.docx"), "docx is NOT extractable");
 
+console.log("\n=== isArchiveFile ===");
+assert(isArchiveFile("week3.zip"), "zip is an archive");
+assert(isArchiveFile("Lectures.ZIP"), "extension check is case-insensitive");
+assert(!isArchiveFile("notes.pdf"), "pdf is NOT an archive");
+
 console.log("\n=== shouldGroupAsBatch ===");
 // Single file -> never batch
 assert(!shouldGroupAsBatch([{ timestamp: 1000 }]), "single file not batched");
@@ -187,12 +678,23 @@ console.log("\n=== Activity log ===");
   assert(entry.timestamp > 0, "entry has timestamp");
 
   // Mark as undone
-  markActivityUndone(log, entry.timestamp);
+  markActivityUndone(log, entry.id);
   assert(log[0].undone, "entry marked as undone");
 
-  // Mark non-existent timestamp (should be no-op)
+  // Mark non-existent id (should be no-op)
   markActivityUndone(log, 99999999);
-  assertEqual(log.length, 1, "no-op for non-existent timestamp");
+  assertEqual(log.length, 1, "no-op for non-existent id");
+}
+
+// Entries created in the same millisecond are undone independently
+{
+  const log = [];
+  const a = addActivityEntry(log, "a.pdf", "A", "B");
+  const b = addActivityEntry(log, "b.pdf", "A", "B");
+  b.timestamp = a.timestamp;
+  markActivityUndone(log, a.id);
+  assert(a.undone, "targeted entry undone");
+  assert(!b.undone, "entry with same timestamp untouched");
 }
 
 // Newest first ordering
@@ -435,6 +937,43 @@ console.log("\n=== matchRule ===");
   const r5 = matchRule("file.v2.pdf", dotRules);
   assert(r5 !== null, "dots in pattern handled correctly");
   assertEqual(matchRule("filexv2xpdf", dotRules), null, "dots are literal, not regex wildcard");
+
+  // Decomposed (NFD) names from macOS match composed patterns, and vice versa
+  const unicodeRules = [
+    { pattern: "Müller*", target_folder: "C:\\German" },
+    { pattern: "*\u304C\u304F\u305B\u3044*", target_folder: "C:\\Japanese" },
+  ];
+  assertEqual(matchRule("Mu\u0308ller_essay.pdf", unicodeRules)?.suggested_folder, "C:\\German", "NFD umlaut matches NFC pattern");
+  assertEqual(matchRule("\u304B\u3099\u304F\u305B\u3044.pdf", unicodeRules)?.suggested_folder, "C:\\Japanese", "NFD kana matches NFC pattern");
+  assertEqual(matchRule("Müller.pdf", [{ pattern: "Mu\u0308ller*", target_folder: "C:\\German" }])?.suggested_folder, "C:\\German", "NFD pattern matches NFC name");
+  assertEqual(matchRule("Muller.pdf", unicodeRules), null, "plain u doesn't match ü");
+}
+
+// ============================================================
+// Watch profiles
+// ============================================================
+
+console.log("\n=== findWatchProfile / rulesForProfile ===");
+{
+  const profiles = [
+    { id: 1, watch_path: "C:\\Users\\sam\\Downloads" },
+    { id: 2, watch_path: "C:\\Users\\sam\\Downloads\\Screenshots\\" },
+    { id: 3, watch_path: "/home/sam/Desktop" },
+  ];
+  assertEqual(findWatchProfile("C:\\Users\\sam\\Downloads\\ps3.pdf", profiles).id, 1, "file in watched folder");
+  assertEqual(findWatchProfile("C:\\Users\\sam\\Downloads\\Screenshots\\a.png", profiles).id, 2, "nearest profile wins");
+  assertEqual(findWatchProfile("/home/sam/Desktop/notes.md", profiles).id, 3, "unix paths");
+  assertEqual(findWatchProfile("C:\\Users\\sam\\Downloads2\\x.pdf", profiles), null, "sibling folder with same prefix");
+  assertEqual(findWatchProfile("/tmp/x", []), null, "no profiles");
+
+  const rules = [
+    { id: 1, pattern: "*.png", target_folder: "Images", profile_id: null },
+    { id: 2, pattern: "*.png", target_folder: "Notes", profile_id: 2 },
+    { id: 3, pattern: "*.pdf", target_folder: "Docs", profile_id: 1 },
+  ];
+  assertEqual(rulesForProfile(rules, 2).map(r => r.id).join(","), "2,1", "profile rules before global rules");
+  assertEqual(rulesForProfile(rules, null).map(r => r.id).join(","), "1", "global rules only without a profile");
+  assertEqual(matchRule("shot.png", rulesForProfile(rules, 2)).suggested_folder, "Notes", "profile rule wins");
 }
 
 // ============================================================
@@ -489,6 +1028,41 @@ console.log("\n=== pathBasename ===");
   assertEqual(pathBasename(""), "", "empty string");
 }
 
+// ============================================================
+// getFolderPref
+// ============================================================
+
+console.log("\n=== getFolderPref ===");
+{
+  const prefs = [
+    { folder: "C:\\Year2\\ML", neverSuggest: false, requireConfirm: true, namingTemplate: null },
+    { folder: "C:\\Year2\\Archive", neverSuggest: true, requireConfirm: false, namingTemplate: null },
+  ];
+
+  assertEqual(getFolderPref("C:\\Year2\\ML", prefs).requireConfirm, true, "finds folder by exact path");
+  assertEqual(getFolderPref("c:\\year2\\archive", prefs).neverSuggest, true, "path match is case-insensitive");
+  assertEqual(getFolderPref("C:\\Year2\\Physics", prefs), null, "folder without prefs returns null");
+  assertEqual(getFolderPref("", prefs), null, "empty folder returns null");
+  assertEqual(getFolderPref("C:\\Year2\\ML", []), null, "no prefs returns null");
+}
+
+// ============================================================
+// applyNamingTemplate
+// ============================================================
+
+console.log("\n=== applyNamingTemplate ===");
+{
+  const date = new Date(2025, 0, 7);
+
+  assertEqual(applyNamingTemplate("{date}_{name}", "notes.pdf", "C:\\Year2\\ML", date), "2025-01-07_notes.pdf", "date prefix keeps extension");
+  assertEqual(applyNamingTemplate("{folder} - {name}", "lecture3.pptx", "C:\\Year2\\ML", date), "ML - lecture3.pptx", "folder name placeholder");
+  assertEqual(applyNamingTemplate("{folder} - {name}", "slides.pdf", "/home/u/Year2/Physics", date), "Physics - slides.pdf", "folder name from forward-slash path");
+  assertEqual(applyNamingTemplate("{name}_{date}", "README", "C:\\Docs", date), "README_2025-01-07", "no extension");
+  assertEqual(applyNamingTemplate("{name}", "archive.tar.gz", "C:\\Docs", date), "archive.tar.gz", "only last extension is split off");
+  assertEqual(applyNamingTemplate("   ", "notes.pdf", "C:\\Docs", date), "notes.pdf", "blank template keeps original name");
+  assertEqual(applyNamingTemplate("{name}: final?", "notes.pdf", "C:\\Docs", date), "notes_ final_.pdf", "invalid characters replaced");
+}
+
 // ============================================================
 console.log("\n" + "=".repeat(50));
 console.log(`Results: ${passed} passed, ${failed} failed out of ${passed + failed} total`);
@@ -0,0 +1,75 @@
+// Quick-sort popup: shows one classified file at a time while the main window is hidden.
+// Choices go back through Rust to the main window, which does the actual move.
+
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { STORAGE_KEYS } from "./constants.js";
+import { describeQuickSortItem, pathBasename } from "./utils.js";
+
+const nameEl = document.getElementById("quick-sort-name");
+const countEl = document.getElementById("quick-sort-count");
+const suggestionEl = document.getElementById("quick-sort-suggestion");
+const reasoningEl = document.getElementById("quick-sort-reasoning");
+const changeRow = document.getElementById("quick-sort-change");
+const folderSelect = document.getElementById("quick-sort-folder");
+const acceptBtn = document.getElementById("quick-sort-accept-btn");
+const changeBtn = document.getElementById("quick-sort-change-btn");
+const skipBtn = document.getElementById("quick-sort-skip-btn");
+const moveBtn = document.getElementById("quick-sort-move-btn");
+
+let current = null;
+
+document.documentElement.setAttribute("data-theme", localStorage.getItem(STORAGE_KEYS.theme) === "dark" ? "dark" : "light");
+
+function render(status) {
+  current = status.item;
+  changeRow.style.display = "none";
+  if (!current) {
+    nameEl.textContent = "";
+    countEl.textContent = "";
+    suggestionEl.textContent = "Nothing to sort";
+    reasoningEl.textContent = "";
+    acceptBtn.disabled = changeBtn.disabled = skipBtn.disabled = true;
+    return;
+  }
+
+  nameEl.textContent = current.name;
+  nameEl.title = current.path;
+  countEl.textContent = status.queued > 1 ? `1 of ${status.queued}` : "";
+  suggestionEl.textContent = describeQuickSortItem(current);
+  reasoningEl.textContent = current.reasoning;
+  acceptBtn.disabled = !current.suggested_folder;
+  changeBtn.disabled = skipBtn.disabled = false;
+
+  folderSelect.innerHTML = "";
+  for (const folder of current.folders) {
+    const opt = document.createElement("option");
+    opt.value = folder;
+    opt.textContent = pathBasename(folder);
+    folderSelect.appendChild(opt);
+  }
+  if (current.suggested_folder) folderSelect.value = current.suggested_folder;
+}
+
+async function refresh() {
+  render(await invoke("get_quick_sort_item"));
+}
+
+async function decide(action, folder = null) {
+  if (!current) return;
+  try {
+    render(await invoke("decide_quick_sort", { path: current.path, action, folder }));
+  } catch (err) {
+    suggestionEl.textContent = String(err);
+  }
+}
+
+acceptBtn.addEventListener("click", () => decide("accept"));
+skipBtn.addEventListener("click", () => decide("skip"));
+changeBtn.addEventListener("click", () => {
+  changeRow.style.display = changeRow.style.display === "none" ? "flex" : "none";
+});
+moveBtn.addEventListener("click", () => decide("change", folderSelect.value || null));
+
+listen("quick-sort-updated", refresh);
+refresh();
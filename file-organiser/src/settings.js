@@ -3,11 +3,11 @@
 // ============================================================
 
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { enable as autostartEnable, disable as autostartDisable, isEnabled as autostartIsEnabled } from "@tauri-apps/plugin-autostart";
 import { STORAGE_KEYS, ONBOARDING_STATUS_TIMEOUT_MS } from "./constants.js";
-import { validateModuleName, escapeHtml, pathJoin, pathBasename } from "./utils.js";
-import { addRule as dbAddRule, deleteRule as dbDeleteRule } from "./storage.js";
+import { validateModuleName, escapeHtml, formatFileSize, pathJoin, pathBasename, buildScheduleSettings, buildQuotaSettings, buildShortcutSettings, parseHeaderLines, formatHeaderLines, parseFallbackChain, formatFallbackChain, summarizeDiagnostics, describeTimetableCourses, describeLmsSync, describeProfileImport, describeDataLocation, describeBudget, describeModelReliability, describeEmbeddingStatus, parseAliasLines, formatAliasLines } from "./utils.js";
+import { addRule as dbAddRule, deleteRule as dbDeleteRule, getRules as dbGetRules } from "./storage.js";
 
 export function showSettingsScreen(initFn) {
   document.getElementById("app-screen").style.display = "none";
@@ -17,7 +17,10 @@ export function showSettingsScreen(initFn) {
 }
 
 // state = { basePath, watchPath, userModules, autoMoveEnabled, autoMoveThreshold,
-//           notificationsEnabled, darkModeEnabled, classificationRules, notificationApi }
+//           notificationsEnabled, darkModeEnabled, classificationRules, notificationApi,
+//           quarantineSettings: { folder, enabled }, classificationMode: { mode, bucket_folder },
+//           watchProfiles: [{ id, name, watch_path, folders, mode, min_confidence, excluded }],
+//           localOnly: { enabled, provider, ai_available } }
 // callbacks = { onClose(), applyTheme() }
 export function initSettings(state, callbacks) {
   const backBtn = document.getElementById("settings-back-btn");
@@ -39,6 +42,30 @@ export function initSettings(state, callbacks) {
   const notificationsToggle = document.getElementById("settings-notifications-toggle");
   const notificationHint = document.getElementById("notification-permission-hint");
   const darkModeToggle = document.getElementById("settings-dark-mode-toggle");
+  const conflictPolicySelect = document.getElementById("settings-conflict-policy");
+  const deleteModeSelect = document.getElementById("settings-delete-mode");
+  const retentionGroup = document.getElementById("settings-retention-group");
+  const retentionInput = document.getElementById("settings-retention-days");
+  const deletedSummary = document.getElementById("settings-deleted-summary");
+  const quarantinePathInput = document.getElementById("settings-quarantine-path");
+  const browseQuarantineBtn = document.getElementById("settings-browse-quarantine-btn");
+  const quarantineToggle = document.getElementById("settings-quarantine-toggle");
+  const classificationModeSelect = document.getElementById("settings-classification-mode");
+  const bucketGroup = document.getElementById("settings-bucket-group");
+  const bucketPathInput = document.getElementById("settings-bucket-path");
+  const browseBucketBtn = document.getElementById("settings-browse-bucket-btn");
+  const clearBucketBtn = document.getElementById("settings-clear-bucket-btn");
+  const scheduleToggle = document.getElementById("settings-schedule-toggle");
+  const scheduleGroup = document.getElementById("settings-schedule-group");
+  const scheduleFrequency = document.getElementById("settings-schedule-frequency");
+  const scheduleTime = document.getElementById("settings-schedule-time");
+  const scheduleNext = document.getElementById("settings-schedule-next");
+  const quotaToggle = document.getElementById("settings-quota-toggle");
+  const quotaLimit = document.getElementById("settings-quota-limit");
+  const quotaUsage = document.getElementById("settings-quota-usage");
+  const shortcutKeys = document.getElementById("settings-shortcut-keys");
+  const shortcutAction = document.getElementById("settings-shortcut-action");
+  const shortcutToggle = document.getElementById("settings-shortcut-toggle");
   const apiKeyInput = document.getElementById("settings-api-key");
   const toggleKeyBtn = document.getElementById("settings-toggle-key-btn");
   const apiKeyStatus = document.getElementById("api-key-status");
@@ -121,6 +148,10 @@ export function initSettings(state, callbacks) {
 
   // Helper to close settings and return to main app
   function closeSettings() {
+    // Folders, modules and threshold may have changed since the schedule was saved
+    if (scheduleToggle.checked) saveSchedule();
+    if (quotaToggle.checked) saveQuota();
+    if (shortcutToggle.checked) saveShortcut();
     document.getElementById("settings-screen").style.display = "none";
     document.getElementById("app-screen").style.display = "block";
     callbacks.onClose();
@@ -227,6 +258,114 @@ export function initSettings(state, callbacks) {
     newScanBtn.textContent = "Scan existing course folders";
   });
 
+  // Import a timetable export: one folder and one filename rule per course
+  const importTimetableBtn = document.getElementById("settings-import-timetable-btn");
+  importTimetableBtn.onclick = async () => {
+    if (!state.basePath) {
+      showSettingsStatus("Please select your education folder first", "error");
+      return;
+    }
+    try {
+      const selected = await open({
+        multiple: false,
+        title: "Select timetable export",
+        filters: [{ name: "Calendar", extensions: ["ics"] }],
+      });
+      if (!selected) return;
+
+      const courses = await invoke("parse_timetable", { filePath: selected });
+      if (courses.length === 0) {
+        showSettingsStatus("No course codes found in that timetable", "info");
+        return;
+      }
+      if (!confirm(describeTimetableCourses(courses))) return;
+
+      const result = await invoke("import_timetable", { courses, baseFolder: state.basePath });
+      for (const name of result.folders) {
+        if (!state.userModules.some(m => m.toLowerCase() === name.toLowerCase())) state.userModules.push(name);
+      }
+      state.classificationRules.push(...result.rules);
+      renderSettingsModuleList();
+      renderRulesList();
+      autoSaveSettings();
+      showSettingsStatus(`Imported ${result.folders.length} courses (${result.created} new folders, ${result.rules.length} rules)`, "success");
+    } catch (error) {
+      showSettingsStatus(`Timetable import failed: ${error?.message || error}`, "error");
+    }
+  };
+
+  // Course sync: enrolled courses from Canvas/Moodle become folder descriptions (and new folders)
+  const lmsProvider = document.getElementById("settings-lms-provider");
+  const lmsUrl = document.getElementById("settings-lms-url");
+  const lmsToken = document.getElementById("settings-lms-token");
+  const lmsSyncBtn = document.getElementById("settings-lms-sync-btn");
+  const lmsStatus = document.getElementById("settings-lms-status");
+  (async () => {
+    try {
+      const lms = await invoke("get_lms_settings");
+      lmsProvider.value = lms.provider;
+      lmsUrl.value = lms.base_url;
+      lmsToken.value = lms.token;
+    } catch (err) {
+      console.error("Failed to load course sync settings:", err);
+    }
+  })();
+  async function saveLmsSettings() {
+    const settings = { provider: lmsProvider.value, base_url: lmsUrl.value, token: lmsToken.value };
+    const saved = await invoke("set_lms_settings", { settings });
+    lmsUrl.value = saved.base_url;
+  }
+  lmsProvider.onchange = lmsUrl.onchange = lmsToken.onchange = async () => {
+    try {
+      await saveLmsSettings();
+      lmsStatus.textContent = "";
+    } catch (err) {
+      lmsStatus.textContent = String(err);
+    }
+  };
+  lmsSyncBtn.onclick = async () => {
+    if (!state.basePath) {
+      showSettingsStatus("Please select your education folder first", "error");
+      return;
+    }
+    lmsSyncBtn.disabled = true;
+    lmsStatus.textContent = "Fetching courses...";
+    try {
+      await saveLmsSettings();
+      const synced = await invoke("sync_lms_courses", {
+        availableFolders: state.userModules.map(name => pathJoin(state.basePath, name)),
+        baseFolder: state.basePath,
+      });
+      for (const { folder, created } of synced) {
+        const name = folder && pathBasename(folder);
+        if (created && !state.userModules.some(m => m.toLowerCase() === name.toLowerCase())) state.userModules.push(name);
+      }
+      renderSettingsModuleList();
+      autoSaveSettings();
+      lmsStatus.textContent = describeLmsSync(synced);
+    } catch (err) {
+      lmsStatus.textContent = `Sync failed: ${err?.message || err}`;
+    }
+    lmsSyncBtn.disabled = false;
+  };
+
+  // Folder aliases: listed in prompts and resolved back to folders Rust-side
+  const folderAliases = document.getElementById("settings-folder-aliases");
+  (async () => {
+    try {
+      folderAliases.value = formatAliasLines(await invoke("get_folder_aliases"));
+    } catch (err) {
+      console.error("Failed to load folder aliases:", err);
+    }
+  })();
+  folderAliases.onchange = async () => {
+    try {
+      folderAliases.value = formatAliasLines(await invoke("set_folder_aliases", { aliases: parseAliasLines(folderAliases.value) }));
+    } catch (err) {
+      showSettingsStatus("Failed to save folder aliases: " + (err?.message || err), "error");
+    }
+  };
+
   // Add module
   const newAddBtn = addModuleBtn.cloneNode(true);
   addModuleBtn.parentNode.replaceChild(newAddBtn, addModuleBtn);
@@ -356,6 +495,847 @@ export function initSettings(state, callbacks) {
     autoSaveSettings();
   });
 
+  // Duplicate-file policy is stored Rust-side so batch moves use it too
+  (async () => {
+    try {
+      conflictPolicySelect.value = await invoke("get_conflict_policy");
+    } catch (err) {
+      console.error("Failed to load conflict policy:", err);
+    }
+  })();
+  conflictPolicySelect.onchange = async function() {
+    try {
+      await invoke("set_conflict_policy", { policy: this.value });
+    } catch (err) {
+      showSettingsStatus("Failed to save duplicate file setting: " + err, "error");
+    }
+  };
+
+  // Delete mode and app-trash retention are stored Rust-side (the purge runs there)
+  async function refreshDeletedSummary() {
+    try {
+      const deleted = await invoke("list_deleted");
+      const totalBytes = deleted.reduce((sum, f) => sum + f.size, 0);
+      deletedSummary.textContent = deleted.length === 0
+        ? "The app trash is empty"
+        : `${deleted.length} file(s) in the app trash (${formatFileSize(totalBytes)})`;
+    } catch (err) {
+      deletedSummary.textContent = "";
+    }
+  }
+  async function saveDeleteSettings() {
+    const days = Math.min(365, Math.max(1, parseInt(retentionInput.value, 10) || 30));
+    retentionInput.value = days;
+    retentionGroup.style.display = deleteModeSelect.value === "app_trash" ? "block" : "none";
+    try {
+      await invoke("set_delete_settings", { mode: deleteModeSelect.value, retentionDays: days });
+    } catch (err) {
+      showSettingsStatus("Failed to save delete setting: " + err, "error");
+    }
+  }
+  (async () => {
+    try {
+      const settings = await invoke("get_delete_settings");
+      deleteModeSelect.value = settings.mode;
+      retentionInput.value = settings.retention_days;
+      retentionGroup.style.display = settings.mode === "app_trash" ? "block" : "none";
+      if (settings.mode === "app_trash") refreshDeletedSummary();
+    } catch (err) {
+      console.error("Failed to load delete settings:", err);
+    }
+  })();
+  deleteModeSelect.onchange = async function() {
+    await saveDeleteSettings();
+    if (this.value === "app_trash") refreshDeletedSummary();
+  };
+  retentionInput.onchange = saveDeleteSettings;
+
+  // Classification mode is stored Rust-side so organize_folder and scheduled runs use it too
+  async function saveClassificationMode() {
+    bucketGroup.style.display = state.classificationMode.mode === "buckets" ? "block" : "none";
+    bucketPathInput.value = state.classificationMode.bucket_folder || "";
+    try {
+      await invoke("set_classification_mode", {
+        mode: state.classificationMode.mode,
+        bucketFolder: state.classificationMode.bucket_folder,
+      });
+    } catch (err) {
+      showSettingsStatus("Failed to save classification mode: " + err, "error");
+    }
+  }
+  classificationModeSelect.value = state.classificationMode.mode;
+  bucketGroup.style.display = state.classificationMode.mode === "buckets" ? "block" : "none";
+  bucketPathInput.value = state.classificationMode.bucket_folder || "";
+  classificationModeSelect.onchange = function() {
+    state.classificationMode = { ...state.classificationMode, mode: this.value };
+    saveClassificationMode();
+  };
+  browseBucketBtn.onclick = async () => {
+    try {
+      const selected = await open({ directory: true, multiple: false, title: "Select folder for type buckets" });
+      if (selected) {
+        state.classificationMode = { ...state.classificationMode, bucket_folder: selected };
+        await saveClassificationMode();
+      }
+    } catch (error) {
+      showSettingsStatus(`Error: ${error}`, "error");
+    }
+  };
+  clearBucketBtn.onclick = () => {
+    state.classificationMode = { ...state.classificationMode, bucket_folder: null };
+    saveClassificationMode();
+  };
+
+  // Needs Review (quarantine) folder is stored Rust-side alongside the other move settings
+  async function saveQuarantineSettings() {
+    try {
+      await invoke("set_quarantine_settings", {
+        folder: state.quarantineSettings.folder,
+        enabled: state.quarantineSettings.enabled,
+      });
+    } catch (err) {
+      showSettingsStatus("Failed to save Needs Review setting: " + err, "error");
+    }
+  }
+  quarantinePathInput.value = state.quarantineSettings.folder || "";
+  quarantineToggle.checked = state.quarantineSettings.enabled;
+
+  const newBrowseQuarantine = browseQuarantineBtn.cloneNode(true);
+  browseQuarantineBtn.parentNode.replaceChild(newBrowseQuarantine, browseQuarantineBtn);
+  newBrowseQuarantine.addEventListener("click", async () => {
+    try {
+      const selected = await open({ directory: true, multiple: false, title: "Select Needs Review folder" });
+      if (selected) {
+        state.quarantineSettings = { ...state.quarantineSettings, folder: selected };
+        quarantinePathInput.value = selected;
+        await saveQuarantineSettings();
+        showSettingsStatus("Needs Review folder saved", "success");
+      }
+    } catch (error) {
+      showSettingsStatus(`Error: ${error}`, "error");
+    }
+  });
+
+  const oldQuarantineSwitch = quarantineToggle.nextElementSibling;
+  const quarantineSwitch = oldQuarantineSwitch.cloneNode(true);
+  oldQuarantineSwitch.parentNode.replaceChild(quarantineSwitch, oldQuarantineSwitch);
+  quarantineSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    if (!state.quarantineSettings.folder && !quarantineToggle.checked) {
+      showSettingsStatus("Choose a Needs Review folder first", "error");
+      return;
+    }
+    quarantineToggle.checked = !quarantineToggle.checked;
+    state.quarantineSettings = { ...state.quarantineSettings, enabled: quarantineToggle.checked };
+    await saveQuarantineSettings();
+  });
+
+  // Privacy mode: filenames are redacted Rust-side when prompts are built
+  const privacyToggle = document.getElementById("settings-privacy-toggle");
+  (async () => {
+    try {
+      privacyToggle.checked = await invoke("get_privacy_mode");
+    } catch (err) {
+      console.error("Failed to load privacy mode:", err);
+    }
+  })();
+
+  const oldPrivacySwitch = privacyToggle.nextElementSibling;
+  const privacySwitch = oldPrivacySwitch.cloneNode(true);
+  oldPrivacySwitch.parentNode.replaceChild(privacySwitch, oldPrivacySwitch);
+  privacySwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    privacyToggle.checked = !privacyToggle.checked;
+    try {
+      await invoke("set_privacy_mode", { enabled: privacyToggle.checked });
+    } catch (err) {
+      privacyToggle.checked = !privacyToggle.checked;
+      showSettingsStatus("Failed to save privacy mode: " + err, "error");
+    }
+  });
+
+  // Sibling context: folder samples are added Rust-side when prompts are built
+  const siblingToggle = document.getElementById("settings-sibling-toggle");
+  (async () => {
+    try {
+      siblingToggle.checked = await invoke("get_sibling_context");
+    } catch (err) {
+      console.error("Failed to load sibling context:", err);
+    }
+  })();
+
+  const oldSiblingSwitch = siblingToggle.nextElementSibling;
+  const siblingSwitch = oldSiblingSwitch.cloneNode(true);
+  oldSiblingSwitch.parentNode.replaceChild(siblingSwitch, oldSiblingSwitch);
+  siblingSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    siblingToggle.checked = !siblingToggle.checked;
+    try {
+      await invoke("set_sibling_context", { enabled: siblingToggle.checked });
+    } catch (err) {
+      siblingToggle.checked = !siblingToggle.checked;
+      showSettingsStatus("Failed to save folder contents setting: " + err, "error");
+    }
+  });
+
+  // Subfolder suggestions: the course folders' subfolders are listed Rust-side when prompts are built
+  const subfoldersToggle = document.getElementById("settings-subfolders-toggle");
+  (async () => {
+    try {
+      subfoldersToggle.checked = await invoke("get_suggest_subfolders");
+    } catch (err) {
+      console.error("Failed to load subfolder suggestions:", err);
+    }
+  })();
+
+  const oldSubfoldersSwitch = subfoldersToggle.nextElementSibling;
+  const subfoldersSwitch = oldSubfoldersSwitch.cloneNode(true);
+  oldSubfoldersSwitch.parentNode.replaceChild(subfoldersSwitch, oldSubfoldersSwitch);
+  subfoldersSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    subfoldersToggle.checked = !subfoldersToggle.checked;
+    try {
+      await invoke("set_suggest_subfolders", { enabled: subfoldersToggle.checked });
+    } catch (err) {
+      subfoldersToggle.checked = !subfoldersToggle.checked;
+      showSettingsStatus("Failed to save subfolder suggestions: " + err, "error");
+    }
+  });
+
+  // Provenance always goes in the database; this also writes it onto moved files
+  const provenanceToggle = document.getElementById("settings-provenance-toggle");
+  (async () => {
+    try {
+      provenanceToggle.checked = await invoke("get_provenance_in_files");
+    } catch (err) {
+      console.error("Failed to load provenance setting:", err);
+    }
+  })();
+
+  const oldProvenanceSwitch = provenanceToggle.nextElementSibling;
+  const provenanceSwitch = oldProvenanceSwitch.cloneNode(true);
+  oldProvenanceSwitch.parentNode.replaceChild(provenanceSwitch, oldProvenanceSwitch);
+  provenanceSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    provenanceToggle.checked = !provenanceToggle.checked;
+    try {
+      await invoke("set_provenance_in_files", { enabled: provenanceToggle.checked });
+    } catch (err) {
+      provenanceToggle.checked = !provenanceToggle.checked;
+      showSettingsStatus("Failed to save provenance setting: " + err, "error");
+    }
+  });
+
+  // Online-only files: detection and skipping happen Rust-side
+  const placeholderToggle = document.getElementById("settings-placeholder-toggle");
+  (async () => {
+    try {
+      placeholderToggle.checked = await invoke("get_read_cloud_placeholders");
+    } catch (err) {
+      console.error("Failed to load cloud placeholder setting:", err);
+    }
+  })();
+
+  const oldPlaceholderSwitch = placeholderToggle.nextElementSibling;
+  const placeholderSwitch = oldPlaceholderSwitch.cloneNode(true);
+  oldPlaceholderSwitch.parentNode.replaceChild(placeholderSwitch, oldPlaceholderSwitch);
+  placeholderSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    placeholderToggle.checked = !placeholderToggle.checked;
+    try {
+      await invoke("set_read_cloud_placeholders", { enabled: placeholderToggle.checked });
+    } catch (err) {
+      placeholderToggle.checked = !placeholderToggle.checked;
+      showSettingsStatus("Failed to save online-only files setting: " + err, "error");
+    }
+  });
+
+  // Local-only mode is enforced Rust-side: the classifier refuses any request off this machine/network
+  const localOnlyToggle = document.getElementById("settings-local-only-toggle");
+  const providerSelect = document.getElementById("settings-ai-provider");
+  const localOnlyStatus = document.getElementById("settings-local-only-status");
+  function showLocalOnly() {
+    localOnlyToggle.checked = state.localOnly.enabled;
+    providerSelect.value = state.localOnly.provider;
+    localOnlyStatus.textContent = state.localOnly.ai_available
+      ? ""
+      : "No local AI: files are sorted by rules, past corrections and file type.";
+  }
+  async function saveLocalOnly(enabled, provider) {
+    try {
+      state.localOnly = await invoke("set_local_only", { enabled, provider });
+    } catch (err) {
+      showSettingsStatus("Failed to save local-only mode: " + err, "error");
+    }
+    showLocalOnly();
+  }
+  showLocalOnly();
+  providerSelect.onchange = () => saveLocalOnly(localOnlyToggle.checked, providerSelect.value);
+
+  const oldLocalOnlySwitch = localOnlyToggle.nextElementSibling;
+  const localOnlySwitch = oldLocalOnlySwitch.cloneNode(true);
+  oldLocalOnlySwitch.parentNode.replaceChild(localOnlySwitch, oldLocalOnlySwitch);
+  localOnlySwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    await saveLocalOnly(!localOnlyToggle.checked, providerSelect.value);
+  });
+
+  // Custom endpoint: OpenAI requests go to this base URL Rust-side
+  const endpointUrl = document.getElementById("settings-endpoint-url");
+  const endpointModel = document.getElementById("settings-endpoint-model");
+  const endpointHeaders = document.getElementById("settings-endpoint-headers");
+  (async () => {
+    try {
+      const endpoint = await invoke("get_ai_endpoint");
+      endpointUrl.value = endpoint.base_url || "";
+      endpointModel.value = endpoint.text_model || "";
+      endpointHeaders.value = formatHeaderLines(endpoint.headers);
+    } catch (err) {
+      console.error("Failed to load the API endpoint:", err);
+    }
+  })();
+  async function saveEndpoint() {
+    const endpoint = {
+      base_url: endpointUrl.value,
+      headers: parseHeaderLines(endpointHeaders.value),
+      text_model: endpointModel.value,
+      vision_model: null,
+    };
+    try {
+      await invoke("set_ai_endpoint", { endpoint });
+      state.localOnly = await invoke("get_local_only");
+      showLocalOnly();
+    } catch (err) {
+      showSettingsStatus("Failed to save API endpoint: " + err, "error");
+    }
+  }
+  endpointUrl.onchange = saveEndpoint;
+  endpointModel.onchange = saveEndpoint;
+  endpointHeaders.onchange = saveEndpoint;
+
+  // Model routing: filename-only, content and image requests can each use their own model
+  const routeInputs = {
+    filename: document.getElementById("settings-route-filename"),
+    content: document.getElementById("settings-route-content"),
+    vision: document.getElementById("settings-route-vision"),
+  };
+  function showRoutes(routes) {
+    for (const [kind, input] of Object.entries(routeInputs)) input.value = routes[kind] || "";
+  }
+  (async () => {
+    try {
+      showRoutes(await invoke("get_model_routes"));
+    } catch (err) {
+      console.error("Failed to load the model routes:", err);
+    }
+  })();
+  async function saveRoutes() {
+    const routes = {};
+    for (const [kind, input] of Object.entries(routeInputs)) routes[kind] = input.value || null;
+    try {
+      showRoutes(await invoke("set_model_routes", { routes }));
+    } catch (err) {
+      showSettingsStatus("Failed to save models: " + err, "error");
+    }
+  }
+  for (const input of Object.values(routeInputs)) input.onchange = saveRoutes;
+
+  // Fallback chain: Rust-side, classification moves down the list on auth/quota/timeout errors
+  const fallbackChain = document.getElementById("settings-fallback-chain");
+  (async () => {
+    try {
+      fallbackChain.value = formatFallbackChain(await invoke("get_fallback_chain"));
+    } catch (err) {
+      console.error("Failed to load the fallback chain:", err);
+    }
+  })();
+  fallbackChain.onchange = async () => {
+    try {
+      const chain = await invoke("set_fallback_chain", { chain: parseFallbackChain(fallbackChain.value) });
+      fallbackChain.value = formatFallbackChain(chain);
+      state.localOnly = await invoke("get_local_only");
+      showLocalOnly();
+    } catch (err) {
+      showSettingsStatus("Failed to save fallback chain: " + err, "error");
+    }
+  };
+
+  // ChatGPT conversations: Rust files them under this subfolder of their course
+  const chatSubfolder = document.getElementById("settings-chat-subfolder");
+  (async () => {
+    try {
+      chatSubfolder.value = await invoke("get_chat_export_subfolder");
+    } catch (err) {
+      console.error("Failed to load the AI notes subfolder:", err);
+    }
+  })();
+  chatSubfolder.onchange = async () => {
+    try {
+      chatSubfolder.value = await invoke("set_chat_export_subfolder", { subfolder: chatSubfolder.value });
+    } catch (err) {
+      showSettingsStatus("Failed to save AI notes subfolder: " + err, "error");
+    }
+  };
+
+  // Prompt size: Rust trims corrections and file text past this many tokens
+  const promptBudget = document.getElementById("settings-prompt-budget");
+  (async () => {
+    try {
+      promptBudget.value = await invoke("get_prompt_token_budget");
+    } catch (err) {
+      console.error("Failed to load the prompt size:", err);
+    }
+  })();
+  promptBudget.onchange = async () => {
+    const tokens = parseInt(promptBudget.value, 10);
+    if (!Number.isFinite(tokens) || tokens <= 0) return;
+    try {
+      promptBudget.value = await invoke("set_prompt_token_budget", { tokens });
+    } catch (err) {
+      showSettingsStatus("Failed to save prompt size: " + err, "error");
+    }
+  };
+
+  // Timeouts: per request kind, applied Rust-side
+  const timeoutInputs = {
+    text_secs: document.getElementById("settings-timeout-text"),
+    vision_secs: document.getElementById("settings-timeout-vision"),
+    ocr_secs: document.getElementById("settings-timeout-ocr"),
+  };
+  function showTimeouts(timeouts) {
+    for (const [key, input] of Object.entries(timeoutInputs)) input.value = timeouts[key];
+  }
+  (async () => {
+    try {
+      showTimeouts(await invoke("get_ai_timeouts"));
+    } catch (err) {
+      console.error("Failed to load the timeouts:", err);
+    }
+  })();
+  async function saveTimeouts() {
+    const timeouts = {};
+    for (const [key, input] of Object.entries(timeoutInputs)) timeouts[key] = parseInt(input.value, 10) || 0;
+    try {
+      showTimeouts(await invoke("set_ai_timeouts", { timeouts }));
+    } catch (err) {
+      showSettingsStatus("Failed to save timeouts: " + err, "error");
+    }
+  }
+  for (const input of Object.values(timeoutInputs)) input.onchange = saveTimeouts;
+
+  const maxConcurrent = document.getElementById("settings-max-concurrent");
+  (async () => {
+    try {
+      maxConcurrent.value = await invoke("get_max_concurrent_requests");
+    } catch (err) {
+      console.error("Failed to load the concurrency limit:", err);
+    }
+  })();
+  maxConcurrent.onchange = async () => {
+    const limit = parseInt(maxConcurrent.value, 10);
+    if (!Number.isFinite(limit)) return;
+    try {
+      maxConcurrent.value = await invoke("set_max_concurrent_requests", { limit });
+    } catch (err) {
+      showSettingsStatus("Failed to save concurrency limit: " + err, "error");
+    }
+  };
+
+  // Local embedding model: downloaded and checked Rust-side; refreshed while it downloads
+  const embeddingStatus = document.getElementById("settings-embedding-status");
+  const embeddingDownloadBtn = document.getElementById("settings-embedding-download-btn");
+  const embeddingFallbackToggle = document.getElementById("settings-embedding-fallback-toggle");
+  async function refreshEmbeddingStatus() {
+    try {
+      const status = await invoke("get_embedding_status");
+      embeddingFallbackToggle.checked = status.provider_fallback;
+      embeddingStatus.textContent = describeEmbeddingStatus(status);
+      embeddingDownloadBtn.style.display = status.state === "failed" ? "" : "none";
+      if (status.state === "downloading" && document.body.contains(embeddingStatus)) {
+        setTimeout(refreshEmbeddingStatus, 1000);
+      }
+    } catch (err) {
+      console.error("Failed to load the embedding model status:", err);
+    }
+  }
+  refreshEmbeddingStatus();
+
+  const oldEmbeddingFallbackSwitch = embeddingFallbackToggle.nextElementSibling;
+  const embeddingFallbackSwitch = oldEmbeddingFallbackSwitch.cloneNode(true);
+  oldEmbeddingFallbackSwitch.parentNode.replaceChild(embeddingFallbackSwitch, oldEmbeddingFallbackSwitch);
+  embeddingFallbackSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    embeddingFallbackToggle.checked = !embeddingFallbackToggle.checked;
+    try {
+      await invoke("set_embedding_fallback", { enabled: embeddingFallbackToggle.checked });
+      refreshEmbeddingStatus();
+    } catch (err) {
+      embeddingFallbackToggle.checked = !embeddingFallbackToggle.checked;
+      showSettingsStatus("Failed to save the similarity model setting: " + err, "error");
+    }
+  });
+  embeddingDownloadBtn.onclick = async () => {
+    embeddingDownloadBtn.disabled = true;
+    const download = invoke("download_embedding_model");
+    setTimeout(refreshEmbeddingStatus, 200);
+    try {
+      await download;
+      showSettingsStatus("Local model downloaded", "success");
+    } catch (err) {
+      showSettingsStatus("Model download failed: " + err, "error");
+    }
+    embeddingDownloadBtn.disabled = false;
+    refreshEmbeddingStatus();
+  };
+
+  // Monthly AI budget: spending is tracked and enforced Rust-side
+  const budgetLimit = document.getElementById("settings-budget-limit");
+  const budgetModel = document.getElementById("settings-budget-model");
+  const budgetDowngradeAt = document.getElementById("settings-budget-downgrade-at");
+  const budgetSpent = document.getElementById("settings-budget-spent");
+  function showBudget(status) {
+    budgetLimit.value = status.settings.monthly_limit_usd;
+    budgetModel.value = status.settings.downgrade_model;
+    budgetDowngradeAt.value = Math.round(status.settings.downgrade_at * 100);
+    budgetSpent.textContent = describeBudget(status);
+  }
+  (async () => {
+    try {
+      showBudget(await invoke("get_api_budget"));
+      document.getElementById("settings-model-reliability").textContent = describeModelReliability(
+        await invoke("get_model_reliability")
+      );
+    } catch (err) {
+      console.error("Failed to load the AI budget:", err);
+    }
+  })();
+  async function saveBudget() {
+    const settings = {
+      monthly_limit_usd: Math.max(0, parseFloat(budgetLimit.value) || 0),
+      downgrade_model: budgetModel.value.trim(),
+      downgrade_at: (parseInt(budgetDowngradeAt.value, 10) || 0) / 100,
+    };
+    try {
+      showBudget(await invoke("set_api_budget", { settings }));
+    } catch (err) {
+      showSettingsStatus("Failed to save the AI budget: " + (err?.message || err), "error");
+    }
+  }
+  for (const input of [budgetLimit, budgetModel, budgetDowngradeAt]) input.onchange = saveBudget;
+
+  // Webhook: auto-moves and finished sessions are POSTed Rust-side
+  const webhookUrl = document.getElementById("settings-webhook-url");
+  const webhookToggle = document.getElementById("settings-webhook-toggle");
+  const testWebhookBtn = document.getElementById("settings-test-webhook-btn");
+  async function saveWebhookSettings() {
+    const settings = { enabled: webhookToggle.checked, url: webhookUrl.value };
+    try {
+      const saved = await invoke("set_webhook_settings", { settings });
+      webhookUrl.value = saved.url;
+    } catch (err) {
+      webhookToggle.checked = false;
+      showSettingsStatus("Failed to save webhook: " + err, "error");
+    }
+  }
+  (async () => {
+    try {
+      const webhook = await invoke("get_webhook_settings");
+      webhookUrl.value = webhook.url;
+      webhookToggle.checked = webhook.enabled;
+    } catch (err) {
+      console.error("Failed to load webhook settings:", err);
+    }
+  })();
+  webhookUrl.onchange = saveWebhookSettings;
+  testWebhookBtn.onclick = async () => {
+    testWebhookBtn.disabled = true;
+    try {
+      await invoke("test_webhook", { url: webhookUrl.value });
+      showSettingsStatus("Test webhook sent", "success");
+    } catch (err) {
+      showSettingsStatus("Webhook test failed: " + err, "error");
+    }
+    testWebhookBtn.disabled = false;
+  };
+
+  const oldWebhookSwitch = webhookToggle.nextElementSibling;
+  const webhookSwitch = oldWebhookSwitch.cloneNode(true);
+  oldWebhookSwitch.parentNode.replaceChild(webhookSwitch, oldWebhookSwitch);
+  webhookSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    if (!webhookUrl.value.trim() && !webhookToggle.checked) {
+      showSettingsStatus("Enter a webhook URL first", "error");
+      return;
+    }
+    webhookToggle.checked = !webhookToggle.checked;
+    await saveWebhookSettings();
+  });
+
+  // Scheduled organizing is stored and run Rust-side, so it works while the window is hidden
+  function showScheduleStatus(status) {
+    scheduleGroup.style.display = scheduleToggle.checked ? "block" : "none";
+    scheduleTime.style.display = scheduleFrequency.value === "daily" ? "inline-block" : "none";
+    scheduleNext.textContent = status && status.next_run_at
+      ? `Next run: ${new Date(status.next_run_at).toLocaleString([], { weekday: "short", hour: "2-digit", minute: "2-digit" })}`
+      : "";
+  }
+  async function saveSchedule() {
+    const settings = buildScheduleSettings({
+      enabled: scheduleToggle.checked,
+      frequency: scheduleFrequency.value,
+      time: scheduleTime.value,
+      watchPath: state.watchPath,
+      basePath: state.basePath,
+      userModules: state.userModules,
+      threshold: state.autoMoveThreshold,
+    });
+    try {
+      showScheduleStatus(await invoke("set_schedule_settings", { settings }));
+    } catch (err) {
+      showSettingsStatus("Failed to save schedule: " + err, "error");
+    }
+  }
+  (async () => {
+    try {
+      const status = await invoke("get_schedule_settings");
+      const { enabled, schedule } = status.settings;
+      scheduleToggle.checked = enabled;
+      if (schedule.kind === "daily") {
+        scheduleFrequency.value = "daily";
+        scheduleTime.value = `${String(schedule.hour).padStart(2, "0")}:${String(schedule.minute).padStart(2, "0")}`;
+      } else {
+        scheduleFrequency.value = String(schedule.minutes);
+        if (scheduleFrequency.value !== String(schedule.minutes)) scheduleFrequency.value = "60";
+      }
+      showScheduleStatus(status);
+    } catch (err) {
+      console.error("Failed to load schedule:", err);
+    }
+  })();
+  scheduleFrequency.onchange = saveSchedule;
+  scheduleTime.onchange = saveSchedule;
+
+  const oldScheduleSwitch = scheduleToggle.nextElementSibling;
+  const scheduleSwitch = oldScheduleSwitch.cloneNode(true);
+  oldScheduleSwitch.parentNode.replaceChild(scheduleSwitch, oldScheduleSwitch);
+  scheduleSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    if (!state.watchPath && !scheduleToggle.checked) {
+      showSettingsStatus("Choose a watch folder first", "error");
+      return;
+    }
+    scheduleToggle.checked = !scheduleToggle.checked;
+    await saveSchedule();
+  });
+
+  // Folder size limits are checked Rust-side on a background thread
+  async function showQuotaUsage() {
+    if (!quotaToggle.checked) {
+      quotaUsage.textContent = "";
+      return;
+    }
+    try {
+      const [usage] = await invoke("check_folder_quotas");
+      quotaUsage.textContent = usage && !usage.error
+        ? `Currently ${formatFileSize(usage.size)}${usage.exceeded ? " - over the limit" : ""}`
+        : "";
+    } catch (err) {
+      console.error("Failed to check folder size:", err);
+    }
+  }
+  async function saveQuota() {
+    const settings = buildQuotaSettings({
+      enabled: quotaToggle.checked,
+      limitGb: quotaLimit.value,
+      watchPath: state.watchPath,
+    });
+    try {
+      await invoke("set_quota_settings", { settings });
+      showQuotaUsage();
+    } catch (err) {
+      showSettingsStatus("Failed to save folder size alert: " + err, "error");
+    }
+  }
+  (async () => {
+    try {
+      const settings = await invoke("get_quota_settings");
+      quotaToggle.checked = settings.enabled;
+      if (settings.quotas.length > 0) {
+        quotaLimit.value = String(Math.round(settings.quotas[0].max_bytes / (1024 * 1024 * 1024) * 10) / 10);
+      }
+      showQuotaUsage();
+    } catch (err) {
+      console.error("Failed to load folder size alert:", err);
+    }
+  })();
+  quotaLimit.onchange = saveQuota;
+
+  const oldQuotaSwitch = quotaToggle.nextElementSibling;
+  const quotaSwitch = oldQuotaSwitch.cloneNode(true);
+  oldQuotaSwitch.parentNode.replaceChild(quotaSwitch, oldQuotaSwitch);
+  quotaSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    if (!state.watchPath && !quotaToggle.checked) {
+      showSettingsStatus("Choose a watch folder first", "error");
+      return;
+    }
+    quotaToggle.checked = !quotaToggle.checked;
+    await saveQuota();
+  });
+
+  // The global shortcut is registered Rust-side so it works while the window is hidden
+  async function saveShortcut() {
+    const settings = buildShortcutSettings({
+      enabled: shortcutToggle.checked,
+      shortcut: shortcutKeys.value,
+      action: shortcutAction.value,
+      watchPath: state.watchPath,
+      basePath: state.basePath,
+      userModules: state.userModules,
+      threshold: state.autoMoveThreshold,
+    });
+    try {
+      const saved = await invoke("set_global_shortcut", { settings });
+      shortcutKeys.value = saved.shortcut;
+      return true;
+    } catch (err) {
+      showSettingsStatus("Failed to set shortcut: " + err, "error");
+      return false;
+    }
+  }
+  (async () => {
+    try {
+      const settings = await invoke("get_global_shortcut");
+      shortcutToggle.checked = settings.enabled;
+      shortcutKeys.value = settings.shortcut;
+      shortcutAction.value = settings.action;
+    } catch (err) {
+      console.error("Failed to load global shortcut:", err);
+    }
+  })();
+  shortcutKeys.onchange = saveShortcut;
+  shortcutAction.onchange = saveShortcut;
+
+  const oldShortcutSwitch = shortcutToggle.nextElementSibling;
+  const shortcutSwitch = oldShortcutSwitch.cloneNode(true);
+  oldShortcutSwitch.parentNode.replaceChild(shortcutSwitch, oldShortcutSwitch);
+  shortcutSwitch.addEventListener("click", async function(e) {
+    e.preventDefault();
+    e.stopPropagation();
+    if (!state.watchPath && !shortcutToggle.checked && shortcutAction.value === "organize") {
+      showSettingsStatus("Choose a watch folder first", "error");
+      return;
+    }
+    shortcutToggle.checked = !shortcutToggle.checked;
+    if (!(await saveShortcut())) shortcutToggle.checked = !shortcutToggle.checked;
+  });
+
+  // Share setup: settings, rules, watch profiles and folder preferences as one file
+  document.getElementById("settings-export-profile-btn").onclick = async () => {
+    try {
+      const path = await save({
+        title: "Export setup",
+        defaultPath: "file-organiser-profile.json",
+        filters: [{ name: "Profile", extensions: ["json"] }],
+      });
+      if (!path) return;
+      const profile = await invoke("export_profile", { path });
+      showSettingsStatus(`Exported ${profile.rules.length} rules and ${profile.watch_profiles.length} watch profiles (without your API key)`, "success");
+    } catch (err) {
+      showSettingsStatus("Export failed: " + (err?.message || err), "error");
+    }
+  };
+  document.getElementById("settings-import-profile-btn").onclick = async () => {
+    try {
+      const path = await open({
+        multiple: false,
+        title: "Import setup",
+        filters: [{ name: "Profile", extensions: ["json"] }],
+      });
+      if (!path) return;
+      const report = await invoke("import_profile", { path });
+      state.classificationRules = await dbGetRules();
+      state.watchProfiles = await invoke("list_watch_profiles");
+      renderRulesList();
+      renderProfilesList();
+      showSettingsStatus(describeProfileImport(report), "success");
+      // A custom endpoint would be sent the API key, so it's only used if the user says so
+      const endpoint = report.endpoint_to_confirm;
+      if (endpoint && confirm(`This setup sends AI requests, with your API key, to ${endpoint.base_url || "api.openai.com"}. Use it?`)) {
+        await invoke("set_ai_endpoint", { endpoint });
+      }
+    } catch (err) {
+      showSettingsStatus("Import failed: " + (err?.message || err), "error");
+    }
+  };
+
+  // Data location: portable mode, or a folder the user moved the database to
+  const dataLocationText = document.getElementById("settings-data-location");
+  invoke("get_data_location")
+    .then(location => { dataLocationText.textContent = describeDataLocation(location); })
+    .catch(err => { dataLocationText.textContent = "Database location unavailable: " + err; });
+  document.getElementById("settings-relocate-db-btn").onclick = async () => {
+    const newPath = await open({ directory: true, multiple: false, title: "Move the database to" });
+    if (!newPath) return;
+    if (!confirm(`Move the database to ${newPath}? The app will restart once running file operations finish.`)) return;
+    try {
+      const location = await invoke("relocate_database", { newPath });
+      showSettingsStatus(`Database moved to ${location.database}. Restarting...`, "success");
+    } catch (err) {
+      showSettingsStatus("Database not moved: " + (err?.message || err), "error");
+    }
+  };
+  document.getElementById("settings-use-existing-db-btn").onclick = async () => {
+    const path = await open({ directory: true, multiple: false, title: "Folder with the database to use" });
+    if (!path) return;
+    if (!confirm(`Switch to the database in ${path}? This computer's current database is kept but no longer used, and the app will restart.`)) return;
+    try {
+      const location = await invoke("use_existing_database", { path });
+      showSettingsStatus(`Switching to ${location.database}. Restarting...`, "success");
+    } catch (err) {
+      showSettingsStatus("Could not use that database: " + (err?.message || err), "error");
+    }
+  };
+
+  // Diagnostics: OCR, API key, database and folder write access
+  const diagnosticsList = document.getElementById("diagnostics-list");
+  const runDiagnosticsBtn = document.getElementById("settings-run-diagnostics-btn");
+  runDiagnosticsBtn.onclick = async () => {
+    runDiagnosticsBtn.disabled = true;
+    diagnosticsList.innerHTML = "";
+    try {
+      const report = await invoke("run_diagnostics", {
+        watchPath: state.watchPath || null,
+        destinationFolders: state.userModules.map(name => pathJoin(state.basePath, name)),
+      });
+      report.checks.forEach(check => {
+        const item = document.createElement("div");
+        item.className = `rule-item diagnostic-item diagnostic-${check.status}`;
+        item.innerHTML = `
+          <span class="diagnostic-status">${check.status === "ok" ? "OK" : check.status === "warning" ? "!" : "X"}</span>
+          <span class="rule-pattern">${escapeHtml(check.name)}</span>
+          <span class="rule-folder">${escapeHtml(check.detail)}</span>
+        `;
+        diagnosticsList.appendChild(item);
+      });
+      showSettingsStatus(summarizeDiagnostics(report), report.ok ? "success" : "error");
+    } catch (err) {
+      showSettingsStatus("Diagnostics failed: " + err, "error");
+    }
+    runDiagnosticsBtn.disabled = false;
+  };
+
   // Threshold slider - use oninput to replace any existing handler
   thresholdSlider.value = Math.round(state.autoMoveThreshold * 100);
   thresholdSlider.oninput = function() {
@@ -388,6 +1368,7 @@ export function initSettings(state, callbacks) {
   const addRuleForm = document.getElementById("add-rule-form");
   const rulePatternInput = document.getElementById("rule-pattern-input");
   const ruleFolderSelect = document.getElementById("rule-folder-select");
+  const ruleProfileSelect = document.getElementById("rule-profile-select");
   const ruleConfirmBtn = document.getElementById("rule-confirm-btn");
   const ruleCancelBtn = document.getElementById("rule-cancel-btn");
 
@@ -401,7 +1382,9 @@ export function initSettings(state, callbacks) {
       const item = document.createElement("div");
       item.className = "rule-item";
       const folderName = pathBasename(rule.target_folder);
+      const profile = state.watchProfiles.find(p => p.id === rule.profile_id);
       item.innerHTML = `
+        ${profile ? `<span class="rule-profile">${escapeHtml(profile.name)}</span>` : ""}
         <span class="rule-pattern">${escapeHtml(rule.pattern)}</span>
         <span class="rule-arrow">\u2192</span>
         <span class="rule-folder" title="${escapeHtml(rule.target_folder)}">${escapeHtml(folderName)}</span>
@@ -416,21 +1399,76 @@ export function initSettings(state, callbacks) {
     }
   }
 
+  // Rules can target a watch profile's own folders as well as the modules
   function populateRuleFolderSelect() {
     ruleFolderSelect.innerHTML = "";
-    for (const name of state.userModules) {
+    const profile = state.watchProfiles.find(p => String(p.id) === ruleProfileSelect.value);
+    const folders = state.userModules.map(name => pathJoin(state.basePath, name));
+    for (const folder of profile ? profile.folders : []) {
+      if (!folders.includes(folder)) folders.push(folder);
+    }
+    for (const folder of folders) {
       const opt = document.createElement("option");
-      opt.value = pathJoin(state.basePath, name);
-      opt.textContent = name;
+      opt.value = folder;
+      opt.textContent = pathBasename(folder);
       ruleFolderSelect.appendChild(opt);
     }
   }
 
+  function populateRuleProfileSelect() {
+    ruleProfileSelect.innerHTML = '<option value="">All folders</option>';
+    for (const profile of state.watchProfiles) {
+      const opt = document.createElement("option");
+      opt.value = String(profile.id);
+      opt.textContent = profile.name;
+      ruleProfileSelect.appendChild(opt);
+    }
+    ruleProfileSelect.style.display = state.watchProfiles.length > 0 ? "" : "none";
+  }
+  ruleProfileSelect.onchange = populateRuleFolderSelect;
+
   renderRulesList();
+  renderRuleSuggestions();
+
+  // Rules proposed from corrections the user keeps making the same way
+  async function renderRuleSuggestions() {
+    const suggestionsList = document.getElementById("rule-suggestions");
+    const availableFolders = state.userModules.map(name => pathJoin(state.basePath, name));
+    let insights = [];
+    try {
+      insights = await invoke("get_correction_insights", { availableFolders });
+    } catch (error) {
+      console.error("Failed to load rule suggestions:", error);
+    }
+    const suggestions = insights.filter(insight => insight.target_folder);
+    suggestionsList.innerHTML = "";
+    suggestionsList.style.display = suggestions.length > 0 ? "flex" : "none";
+    for (const insight of suggestions) {
+      const item = document.createElement("div");
+      item.className = "rule-item";
+      const conflicts = insight.conflicts > 0 ? ` (${insight.conflicts} went elsewhere)` : "";
+      item.innerHTML = `
+        <span class="rule-suggestion-msg" title="${escapeHtml(insight.examples.join("\n"))}">${escapeHtml(insight.message + conflicts)}</span>
+        <button class="rule-edit-btn">Create rule</button>
+      `;
+      item.querySelector(".rule-edit-btn").addEventListener("click", async () => {
+        const id = await dbAddRule(insight.pattern, insight.target_folder, null);
+        if (id) {
+          state.classificationRules.push({ id, pattern: insight.pattern, target_folder: insight.target_folder, profile_id: null });
+          renderRulesList();
+          showSettingsStatus(`Rule ${insight.pattern} \u2192 ${insight.user_chose} created`, "success");
+        }
+        item.remove();
+        if (!suggestionsList.children.length) suggestionsList.style.display = "none";
+      });
+      suggestionsList.appendChild(item);
+    }
+  }
 
   const newAddRuleBtn = addRuleBtn.cloneNode(true);
   addRuleBtn.parentNode.replaceChild(newAddRuleBtn, addRuleBtn);
   newAddRuleBtn.addEventListener("click", () => {
+    populateRuleProfileSelect();
     populateRuleFolderSelect();
     addRuleForm.style.display = "flex";
     newAddRuleBtn.style.display = "none";
@@ -451,9 +1489,10 @@ export function initSettings(state, callbacks) {
       showSettingsStatus("Please select a target folder", "error");
       return;
     }
-    const id = await dbAddRule(pattern, targetFolder);
+    const profileId = ruleProfileSelect.value ? Number(ruleProfileSelect.value) : null;
+    const id = await dbAddRule(pattern, targetFolder, profileId);
     if (id) {
-      state.classificationRules.push({ id, pattern, target_folder: targetFolder });
+      state.classificationRules.push({ id, pattern, target_folder: targetFolder, profile_id: profileId });
       renderRulesList();
     }
     addRuleForm.style.display = "none";
@@ -475,6 +1514,159 @@ export function initSettings(state, callbacks) {
     }
   };
 
+  // ---- Watch Profiles UI ----
+  const profilesList = document.getElementById("profiles-list");
+  const addProfileBtn = document.getElementById("add-profile-btn");
+  const addProfileForm = document.getElementById("add-profile-form");
+  const profileNameInput = document.getElementById("profile-name-input");
+  const profileWatchPath = document.getElementById("profile-watch-path");
+  const profileFoldersInput = document.getElementById("profile-folders");
+  const profileExcludedInput = document.getElementById("profile-excluded");
+  const profileModeSelect = document.getElementById("profile-mode-select");
+  const profileThresholdInput = document.getElementById("profile-threshold-input");
+  let editingProfile = null; // profile being added or edited in the form
+
+  function renderProfilesList() {
+    profilesList.innerHTML = "";
+    if (state.watchProfiles.length === 0) {
+      profilesList.innerHTML = '<p class="empty-msg" style="margin:0;font-size:12px;">No profiles yet</p>';
+      return;
+    }
+    for (const profile of state.watchProfiles) {
+      const item = document.createElement("div");
+      item.className = "rule-item";
+      const folders = profile.folders.length > 0 ? profile.folders.map(pathBasename).join(", ") : "your modules";
+      item.innerHTML = `
+        <span class="rule-pattern">${escapeHtml(profile.name)}</span>
+        <span class="rule-folder" title="${escapeHtml(profile.watch_path)}">${escapeHtml(pathBasename(profile.watch_path))}</span>
+        <span class="rule-arrow">\u2192</span>
+        <span class="rule-folder" title="${escapeHtml(folders)}">${escapeHtml(folders)}</span>
+        <span class="rule-profile">${escapeHtml(profile.mode)} \u00b7 ${Math.round(profile.min_confidence * 100)}%</span>
+        <button class="rule-edit-btn" title="Edit profile">Edit</button>
+        <button class="rule-delete-btn" title="Delete profile and its rules">&times;</button>
+      `;
+      item.querySelector(".rule-edit-btn").addEventListener("click", () => openProfileForm(profile));
+      item.querySelector(".rule-delete-btn").addEventListener("click", async () => {
+        try {
+          await invoke("delete_watch_profile", { id: profile.id });
+        } catch (err) {
+          showSettingsStatus("Failed to delete profile: " + err, "error");
+          return;
+        }
+        state.watchProfiles = state.watchProfiles.filter(p => p.id !== profile.id);
+        state.classificationRules = state.classificationRules.filter(r => r.profile_id !== profile.id);
+        renderProfilesList();
+        renderRulesList();
+      });
+      profilesList.appendChild(item);
+    }
+  }
+
+  function renderProfileForm() {
+    profileNameInput.value = editingProfile.name;
+    profileWatchPath.value = editingProfile.watch_path;
+    profileFoldersInput.value = editingProfile.folders.map(pathBasename).join(", ");
+    profileFoldersInput.title = editingProfile.folders.join("\n");
+    profileExcludedInput.value = editingProfile.excluded.join(", ");
+    profileModeSelect.value = editingProfile.mode;
+    profileThresholdInput.value = Math.round(editingProfile.min_confidence * 100);
+  }
+
+  // Subfolders typed into the form for the watcher and scans to skip
+  function typedExclusions() {
+    return profileExcludedInput.value.split(",").map(s => s.trim()).filter(Boolean);
+  }
+
+  function openProfileForm(profile) {
+    editingProfile = profile
+      ? { ...profile, folders: [...profile.folders], excluded: [...(profile.excluded || [])] }
+      : { id: null, name: "", watch_path: "", folders: [], mode: "ai", min_confidence: state.autoMoveThreshold, created_at: 0, excluded: [] };
+    renderProfileForm();
+    addProfileForm.style.display = "flex";
+    addProfileBtn.style.display = "none";
+    profileNameInput.focus();
+  }
+
+  function closeProfileForm() {
+    editingProfile = null;
+    addProfileForm.style.display = "none";
+    addProfileBtn.style.display = "block";
+  }
+
+  renderProfilesList();
+  closeProfileForm();
+  addProfileBtn.onclick = () => openProfileForm(null);
+  document.getElementById("profile-cancel-btn").onclick = closeProfileForm;
+  profileNameInput.onkeydown = (e) => e.stopPropagation(); // Don't trigger global shortcuts while typing
+  profileExcludedInput.onkeydown = (e) => e.stopPropagation();
+
+  document.getElementById("profile-browse-watch-btn").onclick = async () => {
+    try {
+      const selected = await open({ directory: true, multiple: false, title: "Select folder for this profile to watch" });
+      if (selected) {
+        editingProfile.watch_path = selected;
+        if (!profileNameInput.value.trim()) profileNameInput.value = pathBasename(selected);
+        profileWatchPath.value = selected;
+      }
+    } catch (error) {
+      showSettingsStatus(`Error: ${error}`, "error");
+    }
+  };
+  document.getElementById("profile-add-folder-btn").onclick = async () => {
+    try {
+      const selected = await open({ directory: true, multiple: true, title: "Select destination folders" });
+      const folders = Array.isArray(selected) ? selected : selected ? [selected] : [];
+      for (const folder of folders) {
+        if (!editingProfile.folders.includes(folder)) editingProfile.folders.push(folder);
+      }
+      editingProfile.name = profileNameInput.value;
+      editingProfile.excluded = typedExclusions();
+      renderProfileForm();
+    } catch (error) {
+      showSettingsStatus(`Error: ${error}`, "error");
+    }
+  };
+  document.getElementById("profile-clear-folders-btn").onclick = () => {
+    editingProfile.folders = [];
+    editingProfile.name = profileNameInput.value;
+    editingProfile.excluded = typedExclusions();
+    renderProfileForm();
+  };
+
+  document.getElementById("profile-confirm-btn").onclick = async () => {
+    const threshold = parseInt(profileThresholdInput.value, 10);
+    const profile = {
+      ...editingProfile,
+      name: profileNameInput.value.trim(),
+      mode: profileModeSelect.value,
+      min_confidence: Math.min(100, Math.max(50, isNaN(threshold) ? 90 : threshold)) / 100,
+      excluded: typedExclusions(),
+    };
+    if (!profile.name) {
+      showSettingsStatus("Please enter a profile name", "error");
+      return;
+    }
+    if (!profile.watch_path) {
+      showSettingsStatus("Please choose a folder to watch", "error");
+      return;
+    }
+    try {
+      const saved = await invoke("save_watch_profile", { profile });
+      const index = state.watchProfiles.findIndex(p => p.id === saved.id);
+      if (index > -1) {
+        state.watchProfiles[index] = saved;
+      } else {
+        state.watchProfiles.push(saved);
+      }
+      renderProfilesList();
+      renderRulesList();
+      closeProfileForm();
+      showSettingsStatus(`Profile "${saved.name}" saved`, "success");
+    } catch (err) {
+      showSettingsStatus("Failed to save profile: " + (err?.message || err), "error");
+    }
+  };
+
   function showSettingsStatus(message, type) {
     settingsStatus.textContent = message;
     settingsStatus.className = `status-msg ${type}`;
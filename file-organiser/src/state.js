@@ -42,6 +42,10 @@ const state = {
   autoMoveEnabled: false,
   autoMoveThreshold: 0.9,
 
+  // Needs Review (quarantine) settings, stored Rust-side
+  quarantineSettings: { folder: null, enabled: false },
+  classificationMode: { mode: "ai", bucket_folder: null }, // "ai" or "buckets" (no API calls)
+
   // Notification settings
   notificationsEnabled: false,
   notificationApi: null,
@@ -16,14 +16,16 @@ const MIGRATION_DONE_KEY = "fileorg_sqlite_migrated";
  * @param {string} aiSuggested - The folder AI suggested
  * @param {string} userChose - The folder user actually chose
  * @param {string} type - "accepted", "corrected", or "dismissed"
+ * @param {number|null} confidence - The AI's confidence in its suggestion, if known
  */
-export async function addCorrection(filename, aiSuggested, userChose, type) {
+export async function addCorrection(filename, aiSuggested, userChose, type, confidence = null) {
   try {
     await invoke("db_add_correction", {
       filename,
       aiSuggested,
       userChose,
       correctionType: type,
+      confidence,
     });
   } catch (e) {
     console.error("[Storage] Failed to add correction:", e);
@@ -72,24 +74,33 @@ export async function clearCorrections() {
  * @param {string} fromFolder - Source folder path
  * @param {string} toFolder - Destination folder path
  * @param {string|null} [originalFilename=null] - Original filename before rename (if renamed)
+ * @param {string|null} [sourcePath=null] - Full path the file was moved from (enables redo)
+ * @param {number|null} [sessionId=null] - Organize session this move belongs to
+ * @param {number|null} [confidence=null] - The classifier's confidence in the destination, if it chose it
  * @returns {Promise<Object|null>} The created entry or null on failure
  */
-export async function addActivity(filename, fromFolder, toFolder, originalFilename = null) {
+export async function addActivity(filename, fromFolder, toFolder, originalFilename = null, sourcePath = null, sessionId = null, confidence = null) {
   try {
-    await invoke("db_add_activity", {
+    const id = await invoke("db_add_activity", {
       filename,
       fromFolder,
       toFolder,
       originalFilename,
+      sourcePath,
+      sessionId,
+      confidence,
     });
     // Return the entry in the format expected by the frontend
     return {
+      id,
       filename,
       from: fromFolder,
       to: toFolder,
       timestamp: Date.now(),
       undone: false,
       originalFilename,
+      sourcePath,
+      sessionId,
     };
   } catch (e) {
     console.error("[Storage] Failed to add activity:", e);
@@ -106,12 +117,15 @@ export async function getActivityLog() {
     const entries = await invoke("db_get_activity_log");
     // Map from Rust struct format to JS format
     return entries.map((e) => ({
+      id: e.id,
       filename: e.filename,
       from: e.from_folder,
       to: e.to_folder,
       timestamp: e.created_at,
       undone: e.undone,
       originalFilename: e.original_filename || null,
+      sourcePath: e.source_path || null,
+      sessionId: e.session_id ?? null,
     }));
   } catch (e) {
     console.error("[Storage] Failed to get activity log:", e);
@@ -120,12 +134,12 @@ export async function getActivityLog() {
 }
 
 /**
- * Mark an activity entry as undone by timestamp
- * @param {number} timestamp - The timestamp of the entry to mark as undone
+ * Mark an activity entry as undone by id
+ * @param {number} id - The id of the entry to mark as undone
  */
-export async function markActivityUndone(timestamp) {
+export async function markActivityUndone(id) {
   try {
-    await invoke("db_mark_activity_undone", { timestamp });
+    await invoke("db_mark_activity_undone", { id });
   } catch (e) {
     console.error("[Storage] Failed to mark activity undone:", e);
   }
@@ -142,6 +156,36 @@ export async function clearActivityLog() {
   }
 }
 
+// ============================================================
+// ORGANIZE SESSIONS
+// ============================================================
+
+/**
+ * Start an organize session grouping the following moves
+ * @param {string} label - Human-readable label (e.g. "Accept all high confidence")
+ * @returns {Promise<number|null>} The session id or null on failure
+ */
+export async function startSession(label) {
+  try {
+    return await invoke("db_start_session", { label });
+  } catch (e) {
+    console.error("[Storage] Failed to start session:", e);
+    return null;
+  }
+}
+
+/**
+ * Mark an organize session as finished
+ * @param {number} id - The session id
+ */
+export async function endSession(id) {
+  try {
+    await invoke("db_end_session", { id });
+  } catch (e) {
+    console.error("[Storage] Failed to end session:", e);
+  }
+}
+
 // ============================================================
 // CLASSIFICATION RULES
 // ============================================================
@@ -150,11 +194,12 @@ export async function clearActivityLog() {
  * Add a classification rule
  * @param {string} pattern - Glob pattern (e.g. "*_ML_*", "Lecture*")
  * @param {string} targetFolder - Full path to target folder
+ * @param {number|null} profileId - Watch profile the rule is limited to (null: every folder)
  * @returns {Promise<number|null>} The rule id or null on failure
  */
-export async function addRule(pattern, targetFolder) {
+export async function addRule(pattern, targetFolder, profileId = null) {
   try {
-    return await invoke("db_add_rule", { pattern, targetFolder });
+    return await invoke("db_add_rule", { pattern, targetFolder, profileId });
   } catch (e) {
     console.error("[Storage] Failed to add rule:", e);
     return null;
@@ -163,7 +208,7 @@ export async function addRule(pattern, targetFolder) {
 
 /**
  * Get all classification rules
- * @returns {Promise<Array>} Array of rule objects { id, pattern, target_folder }
+ * @returns {Promise<Array>} Array of rule objects { id, pattern, target_folder, profile_id }
  */
 export async function getRules() {
   try {
@@ -172,6 +217,7 @@ export async function getRules() {
       id: r.id,
       pattern: r.pattern,
       target_folder: r.target_folder,
+      profile_id: r.profile_id ?? null,
     }));
   } catch (e) {
     console.error("[Storage] Failed to get rules:", e);
@@ -191,6 +237,104 @@ export async function deleteRule(id) {
   }
 }
 
+// ============================================================
+// FOLDER PREFERENCES
+// ============================================================
+
+/**
+ * Set preferences for a destination folder (replaces existing preferences)
+ * @param {string} folder - Full path to the destination folder
+ * @param {Object} prefs - { neverSuggest, requireConfirm, namingTemplate, description }
+ */
+export async function setFolderPref(folder, { neverSuggest = false, requireConfirm = false, namingTemplate = null, description = null } = {}) {
+  try {
+    await invoke("db_set_folder_pref", { folder, neverSuggest, requireConfirm, namingTemplate, description });
+  } catch (e) {
+    console.error("[Storage] Failed to set folder preferences:", e);
+  }
+}
+
+/**
+ * Get preferences for all folders that have any set
+ * @returns {Promise<Array>} Array of { folder, neverSuggest, requireConfirm, namingTemplate, description }
+ */
+export async function getFolderPrefs() {
+  try {
+    const prefs = await invoke("db_get_folder_prefs");
+    return prefs.map((p) => ({
+      folder: p.folder,
+      neverSuggest: p.never_suggest,
+      requireConfirm: p.require_confirm,
+      namingTemplate: p.naming_template,
+      description: p.description,
+    }));
+  } catch (e) {
+    console.error("[Storage] Failed to get folder preferences:", e);
+    return [];
+  }
+}
+
+/**
+ * Reset a folder's preferences to defaults
+ * @param {string} folder - Full path to the destination folder
+ */
+export async function deleteFolderPref(folder) {
+  try {
+    await invoke("db_delete_folder_pref", { folder });
+  } catch (e) {
+    console.error("[Storage] Failed to delete folder preferences:", e);
+  }
+}
+
+// ============================================================
+// MAINTENANCE
+// ============================================================
+
+/**
+ * Run an integrity check, VACUUM and WAL checkpoint on the database
+ * @returns {Promise<Object|null>} { integrityOk, integrityMessages, sizeBefore, sizeAfter } or null on failure
+ */
+export async function runMaintenance() {
+  try {
+    const r = await invoke("db_maintenance");
+    return {
+      integrityOk: r.integrity_ok,
+      integrityMessages: r.integrity_messages,
+      sizeBefore: r.size_before,
+      sizeAfter: r.size_after,
+    };
+  } catch (e) {
+    console.error("[Storage] Database maintenance failed:", e);
+    return null;
+  }
+}
+
+/**
+ * List activity and file index entries whose files were deleted outside the app
+ * @returns {Promise<Array>} [{ kind: "activity" | "index", id, path, recorded_at }]
+ */
+export async function getOrphanedEntries() {
+  try {
+    return await invoke("get_orphaned_entries");
+  } catch (e) {
+    console.error("[Storage] Failed to check for orphaned entries:", e);
+    return [];
+  }
+}
+
+/**
+ * Remove the activity and file index entries whose files no longer exist
+ * @returns {Promise<Object|null>} { activity, index } counts removed, or null on failure
+ */
+export async function purgeOrphanedEntries() {
+  try {
+    return await invoke("purge_orphaned_entries");
+  } catch (e) {
+    console.error("[Storage] Failed to purge orphaned entries:", e);
+    return null;
+  }
+}
+
 // ============================================================
 // MIGRATION FROM LOCALSTORAGE
 // ============================================================
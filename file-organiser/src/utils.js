@@ -7,6 +7,7 @@
 import {
   IMAGE_EXTENSIONS,
   CONTENT_EXTRACTABLE_EXTENSIONS,
+  ARCHIVE_EXTENSIONS,
   RAPID_WINDOW_MS,
   BATCH_WINDOW_MS,
   MIN_BATCH_SIZE,
@@ -22,6 +23,403 @@ export function formatFileSize(bytes) {
   return Math.round((bytes / Math.pow(k, i)) * 100) / 100 + " " + sizes[i];
 }
 
+// Format a duration in seconds as m:ss or h:mm:ss
+export function formatDuration(seconds) {
+  if (!Number.isFinite(seconds) || seconds < 0) return "";
+  const total = Math.round(seconds);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = String(total % 60).padStart(2, "0");
+  return h > 0 ? `${h}:${String(m).padStart(2, "0")}:${s}` : `${m}:${s}`;
+}
+
+// Parse a capture date from get_file_metadata ("YYYY-MM-DDTHH:MM:SS", camera local time).
+// Returns null if missing or invalid.
+export function parseExifDate(value) {
+  if (!value) return null;
+  const date = new Date(value);
+  return isNaN(date.getTime()) ? null : date;
+}
+
+// One-line description of a photo's EXIF info, e.g. "Taken 3 Oct 2025 · Apple iPhone 13"
+export function describeExif(exif) {
+  if (!exif) return "";
+  const parts = [];
+  const taken = parseExifDate(exif.captured_at);
+  if (taken) {
+    parts.push(`Taken ${taken.toLocaleDateString(undefined, { day: "numeric", month: "short", year: "numeric" })}`);
+  }
+  const { camera_make: make, camera_model: model } = exif;
+  if (make && model) {
+    parts.push(model.toLowerCase().startsWith(make.toLowerCase()) ? model : `${make} ${model}`);
+  } else if (make || model) {
+    parts.push(make || model);
+  }
+  return parts.join(" · ");
+}
+
+// Describe a file-op-progress event, e.g. "lecture.mp4: 1.2 GB of 3.4 GB (45 MB/s)"
+export function formatFileOpProgress(progress) {
+  const { filename, bytes_copied: copied, total_bytes: total, bytes_per_sec: rate } = progress;
+  const speed = rate > 0 ? ` (${formatFileSize(rate)}/s)` : "";
+  return `${filename}: ${formatFileSize(copied)} of ${formatFileSize(total)}${speed}`;
+}
+
+// One-line summary of a get_folder_stats entry, e.g. "42 files, 120 MB, mostly PDF"
+export function summarizeFolderStats(stats) {
+  if (stats.error) return "Unavailable";
+  if (stats.file_count === 0) return "Empty";
+  const files = `${stats.file_count} file${stats.file_count === 1 ? "" : "s"}`;
+  const top = stats.extensions[0];
+  const mostly = top && top.extension && top.count * 2 > stats.file_count
+    ? `, mostly ${top.extension.toUpperCase()}` : "";
+  return `${files}, ${formatFileSize(stats.total_size)}${mostly}`;
+}
+
+// Headline for a get_time_saved result, e.g. "~2.5 hours" (null when nothing was moved)
+export function describeTimeSaved(timeSaved) {
+  const seconds = timeSaved.manual_seconds_saved;
+  if (!seconds) return null;
+  if (seconds < 60) return `~${seconds} sec`;
+  if (seconds < 3600) return `~${Math.round(seconds / 60)} min`;
+  const hours = Math.round((seconds / 3600) * 10) / 10;
+  return `~${hours} hour${hours === 1 ? "" : "s"}`;
+}
+
+// Confidence label for a suggestion, e.g. "95% confident (usually right 40%)" once past
+// outcomes give a calibrated estimate
+export function describeConfidence(classification) {
+  const label = `${Math.round(classification.confidence * 100)}% confident`;
+  if (classification.calibrated_confidence == null) return label;
+  return `${label} (usually right ${Math.round(classification.calibrated_confidence * 100)}%)`;
+}
+
+// The evidence for a suggestion, e.g. "Matched: 'gradient descent'; similar to past correction
+// "ps3.pdf"", or the model's own reasoning when it didn't give any
+export function describeExplanation(classification) {
+  const explanation = classification.explanation;
+  const parts = [];
+  if (explanation?.keywords?.length) parts.push(`Matched: ${explanation.keywords.map((k) => `'${k}'`).join(", ")}`);
+  if (explanation?.content_clues?.length) parts.push(`content shows ${explanation.content_clues.join(", ")}`);
+  if (explanation?.similar_correction) parts.push(`similar to past correction "${explanation.similar_correction}"`);
+  if (parts.length === 0) return classification.reasoning || "";
+  const text = parts.join("; ");
+  return text[0].toUpperCase() + text.slice(1);
+}
+
+// Note under a shortcut's suggestion saying what it points at (null for other files)
+export function describeLink(link) {
+  if (!link) return null;
+  const target = link.is_url ? (link.title ? `${link.title} (${link.target})` : link.target) : link.target;
+  if (!link.exists) return `Shortcut to ${target}, which no longer exists - delete the shortcut?`;
+  return `Shortcut to ${target}`;
+}
+
+// Status line after restoring deleted files (restore_trash_items' report)
+export function describeRestore(report) {
+  const restored = report.restored.length;
+  const failed = report.failed.length;
+  const files = (n) => `${n} file${n === 1 ? "" : "s"}`;
+  if (failed === 0) return `Restored ${files(restored)}`;
+  const reason = failed === 1 ? ` (${report.failed[0].reason})` : "";
+  if (restored === 0) return `Couldn't restore ${files(failed)}${reason}`;
+  return `Restored ${files(restored)}, couldn't restore ${failed}${reason}`;
+}
+
+// Rows for analyze_disk_usage's tree: each subfolder with its depth and share of the total,
+// largest first within each parent (the root itself isn't a row)
+export function flattenDiskUsage(usage) {
+  const rows = [];
+  const walk = (node, depth) => {
+    for (const child of node.children) {
+      rows.push({
+        name: child.name,
+        path: child.path,
+        size: child.size,
+        depth,
+        share: usage.size > 0 ? child.size / usage.size : 0,
+      });
+      walk(child, depth + 1);
+    }
+  };
+  walk(usage, 0);
+  return rows;
+}
+
+// Status line after replacing duplicate copies with hard links (link_duplicates' report)
+export function describeLinkReport(report) {
+  const linked = report.linked.length;
+  const failed = report.failed.length;
+  const copies = (n) => `${n} cop${n === 1 ? "y" : "ies"}`;
+  const reason = failed === 1 ? ` (${report.failed[0].reason})` : "";
+  if (linked === 0) return `Couldn't link ${copies(failed)}${reason}`;
+  const done = `Linked ${copies(linked)}, freed ${formatFileSize(report.reclaimed)}`;
+  return failed === 0 ? done : `${done}; couldn't link ${failed}${reason}`;
+}
+
+// Note under a suggestion whose prompt was trimmed to the token budget (null if nothing was cut)
+export function describeTruncation(truncation) {
+  if (!truncation) return null;
+  const parts = [];
+  if (truncation.corrections_dropped > 0) {
+    parts.push(`${truncation.corrections_dropped} older correction${truncation.corrections_dropped === 1 ? "" : "s"}`);
+  }
+  if (truncation.content_tokens_dropped > 0) {
+    parts.push(`${truncation.content_tokens_dropped.toLocaleString("en-US")} tokens of file text`);
+  }
+  const left = parts.length > 0 ? `: left out ${parts.join(" and ")}` : "";
+  return `Prompt trimmed to ${truncation.budget.toLocaleString("en-US")} tokens${left}`;
+}
+
+// Status line for an organize_folder result, e.g. "Organized: 5 moved, 2 left in place"
+export function summarizeOrganizeResult(result) {
+  const parts = [`${result.moved} moved`];
+  if (result.deferred > 0) parts.push(`${result.deferred} waiting for their drive`);
+  if (result.skipped.length > 0) parts.push(`${result.skipped.length} left in place`);
+  if (result.failed.length > 0) parts.push(`${result.failed.length} failed`);
+  return `Organized: ${parts.join(", ")}`;
+}
+
+// Status line for the deferred moves carried out once their drive was plugged back in
+export function describeDeferredMoves(result) {
+  const parts = [`${result.moved} moved`];
+  if (result.skipped > 0) parts.push(`${result.skipped} left in place`);
+  if (result.failed.length > 0) parts.push(`${result.failed.length} failed`);
+  return `${result.volumes.join(", ")} plugged back in: ${parts.join(", ")}`;
+}
+
+// One-line description of a suggested new folder: file count and a few of the file names
+export function describeFolderSuggestion(suggestion, maxNames = 3) {
+  const names = suggestion.files.map(pathBasename);
+  const shown = names.slice(0, maxNames).join(", ");
+  const more = names.length > maxNames ? ` +${names.length - maxNames} more` : "";
+  return `${names.length} file${names.length === 1 ? "" : "s"}: ${shown}${more}`;
+}
+
+// Label for a paper identifier from detect_paper
+export function describePaperId(paperId) {
+  return paperId.kind === "arxiv" ? `arXiv ${paperId.id}` : `DOI ${paperId.id}`;
+}
+
+// Confirmation text listing the courses found in a timetable import
+export function describeTimetableCourses(courses) {
+  const lines = courses.map(c => {
+    const sessions = `${c.sessions} session${c.sessions === 1 ? "" : "s"}`;
+    return `- ${c.folder_name} (${sessions}, rule ${c.rule_pattern})`;
+  });
+  return `Create ${courses.length} course folder${courses.length === 1 ? "" : "s"} with filename rules?\n\n${lines.join("\n")}`;
+}
+
+// Summary of a sync_lms_courses result for the settings screen
+export function describeLmsSync(synced) {
+  if (synced.length === 0) return "No active courses found";
+  const created = synced.filter(s => s.created).length;
+  const matched = synced.filter(s => s.folder && !s.created).length;
+  const unmatched = synced.length - created - matched;
+  const parts = [`${matched} matched to folders`];
+  if (created > 0) parts.push(`${created} new folder${created === 1 ? "" : "s"}`);
+  if (unmatched > 0) parts.push(`${unmatched} without a folder`);
+  return `Synced ${synced.length} course${synced.length === 1 ? "" : "s"}: ${parts.join(", ")}`;
+}
+
+// Summary of an import_profile result for the settings screen
+export function describeProfileImport(report) {
+  const plural = (n, word) => `${n} ${word}${n === 1 ? "" : "s"}`;
+  const parts = [plural(report.settings, "setting"), `${plural(report.rules_added, "new rule")}`];
+  const profiles = report.watch_profiles_added + report.watch_profiles_updated;
+  if (profiles > 0) parts.push(plural(profiles, "watch profile"));
+  if (report.folder_prefs > 0) parts.push(plural(report.folder_prefs, "folder preference"));
+  return `Imported ${parts.join(", ")}`;
+}
+
+// This month's AI spending against the budget, e.g. "$3.20 of $5.00 spent in 2026-10 (using the cheaper model)"
+export function describeBudget(status) {
+  const spent = `$${status.spent_usd.toFixed(2)}`;
+  if (!(status.settings.monthly_limit_usd > 0)) return `${spent} spent in ${status.month} (no limit)`;
+  const line = `${spent} of $${status.settings.monthly_limit_usd.toFixed(2)} spent in ${status.month}`;
+  if (status.state === "exceeded") return `${line} (limit reached: free classifiers only)`;
+  if (status.state === "downgraded") return `${line} (using ${status.settings.downgrade_model})`;
+  return line;
+}
+
+// Where similarity features run, from get_embedding_status, e.g. "Downloading the local
+// model: 12 MB of 86.73 MB (the AI provider is used until it's ready)"
+export function describeEmbeddingStatus(status) {
+  const fallbacks = status.provider_fallbacks > 0
+    ? ` ${status.provider_fallbacks} request${status.provider_fallbacks === 1 ? "" : "s"} went to the AI provider so far.`
+    : "";
+  const meanwhile = status.provider_fallback ? "the AI provider is used" : "similarity features wait";
+  const instead = status.provider_fallback ? "the AI provider is used" : "similarity features are off";
+  switch (status.state) {
+    case "ready":
+      return "Using the local model: filenames stay on this computer";
+    case "downloading":
+      return status.total > 0
+        ? `Downloading the local model: ${formatFileSize(status.downloaded)} of ${formatFileSize(status.total)} (${meanwhile} until it's ready)`
+        : `Downloading the local model (${meanwhile} until it's ready)`;
+    case "failed":
+      return `The local model couldn't be set up (${status.error}), so ${instead}.${fallbacks}`;
+    case "missing":
+      return `No local model, so ${instead}.${fallbacks}`;
+    default:
+      return "The local model is set up the first time it's needed";
+  }
+}
+
+// One line naming the models whose replies needed repairing (not valid JSON), e.g.
+// "Malformed replies: llama3.2 2 of 10 (1 fixed)"; "" when every reply could be read
+export function describeModelReliability(stats) {
+  const flaky = stats.filter((s) => s.repairs > 0);
+  if (!flaky.length) return "";
+  const parts = flaky.map((s) => `${s.model} ${s.repairs} of ${s.calls} (${s.repaired} fixed)`);
+  return `Malformed replies: ${parts.join(", ")}`;
+}
+
+// One line for the settings screen saying where the database is and why it's there
+export function describeDataLocation(location) {
+  const notes = [];
+  if (location.relocated) notes.push("moved there by you");
+  else if (location.portable) notes.push("portable mode");
+  if (location.synced) notes.push(`synced by ${location.synced}, saved in sync-safe mode`);
+  return notes.length ? `Database: ${location.database} (${notes.join("; ")})` : `Database: ${location.database}`;
+}
+
+// Build the settings object for set_schedule_settings from the settings screen.
+// frequency is a number of minutes or "daily" (then time is "HH:MM", local time).
+export function buildScheduleSettings({ enabled, frequency, time, watchPath, basePath, userModules, threshold }) {
+  let schedule;
+  if (frequency === "daily") {
+    const [hour, minute] = (time || "18:00").split(":").map(n => parseInt(n, 10) || 0);
+    schedule = { kind: "daily", hour, minute };
+  } else {
+    schedule = { kind: "interval", minutes: parseInt(frequency, 10) || 60 };
+  }
+  return {
+    enabled,
+    schedule,
+    folders: watchPath ? [watchPath] : [],
+    available_folders: userModules.map(name => pathJoin(basePath, name)),
+    min_confidence: threshold,
+  };
+}
+
+// Build the settings object for set_quota_settings from the settings screen.
+// The limit applies to the watched folder and is entered in GB.
+export function buildQuotaSettings({ enabled, limitGb, watchPath }) {
+  const gb = parseFloat(limitGb);
+  const maxBytes = Math.round((gb > 0 ? gb : 10) * 1024 * 1024 * 1024);
+  return {
+    enabled,
+    quotas: watchPath ? [{ folder: watchPath, max_bytes: maxBytes }] : [],
+    interval_minutes: 60,
+  };
+}
+
+// Parse "Name: value" lines from the settings screen into a headers object (blank lines skipped)
+export function parseHeaderLines(text) {
+  const headers = {};
+  for (const line of (text || "").split("\n")) {
+    const colon = line.indexOf(":");
+    if (colon <= 0) continue;
+    headers[line.slice(0, colon).trim()] = line.slice(colon + 1).trim();
+  }
+  return headers;
+}
+
+// Headers object back to "Name: value" lines
+export function formatHeaderLines(headers) {
+  return Object.entries(headers || {}).map(([name, value]) => `${name}: ${value}`).join("\n");
+}
+
+// Parse "Operations Research = OR" lines into folder aliases for set_folder_aliases
+// (blank lines and lines without "=" skipped; the folder may itself contain "=")
+export function parseAliasLines(text) {
+  const aliases = [];
+  for (const line of (text || "").split("\n")) {
+    const equals = line.indexOf("=");
+    if (equals <= 0) continue;
+    aliases.push({ alias: line.slice(0, equals).trim(), folder: line.slice(equals + 1).trim() });
+  }
+  return aliases;
+}
+
+// Folder aliases back to "alias = folder" lines
+export function formatAliasLines(aliases) {
+  return (aliases || []).map(({ alias, folder }) => `${alias} = ${folder}`).join("\n");
+}
+
+// Parse "openai:gpt-4o-mini, ollama, mock" into fallback tiers for set_fallback_chain
+export function parseFallbackChain(text) {
+  return (text || "")
+    .split(/[,\n]/)
+    .map(step => step.trim())
+    .filter(Boolean)
+    .map(step => {
+      const colon = step.indexOf(":");
+      const provider = (colon < 0 ? step : step.slice(0, colon)).trim().toLowerCase();
+      const model = colon < 0 ? "" : step.slice(colon + 1).trim();
+      return { provider, model: model || null };
+    });
+}
+
+// Fallback tiers back to the comma-separated form
+export function formatFallbackChain(chain) {
+  return (chain || []).map(tier => (tier.model ? `${tier.provider}:${tier.model}` : tier.provider)).join(", ");
+}
+
+// Build the settings object for set_global_shortcut from the settings screen.
+// Organizing uses the same folders and threshold as scheduled runs.
+export function buildShortcutSettings({ enabled, shortcut, action, watchPath, basePath, userModules, threshold }) {
+  return {
+    enabled,
+    shortcut: (shortcut || "").trim() || "CmdOrCtrl+Alt+O",
+    action: action === "show_window" ? "show_window" : "organize",
+    folder: watchPath || "",
+    available_folders: userModules.map(name => pathJoin(basePath, name)),
+    min_confidence: threshold,
+  };
+}
+
+// Build the item invoke('offer_quick_sort') shows in the quick-sort popup
+export function buildQuickSortItem(fileInfo, classification, folders) {
+  const unsorted = !classification.suggested_folder || classification.suggested_folder === "__UNSORTED__";
+  return {
+    path: fileInfo.path,
+    name: fileInfo.name,
+    suggested_folder: unsorted ? null : classification.suggested_folder,
+    confidence: classification.confidence || 0,
+    reasoning: describeExplanation(classification),
+    folders,
+  };
+}
+
+// One-line suggestion for the quick-sort popup
+export function describeQuickSortItem(item) {
+  if (!item.suggested_folder) return "No matching folder - choose one";
+  return `Move to ${pathBasename(item.suggested_folder)}? (${Math.round(item.confidence * 100)}% confident)`;
+}
+
+// Folders Rust lets file operations touch (invoke('set_allowed_roots')): the watched
+// folders and destination trees, including watch profiles' and the Needs Review folder
+export function buildAllowedRoots({ watchPath, basePath, watchProfiles, quarantineSettings, classificationMode }) {
+  const roots = [watchPath, basePath];
+  for (const profile of watchProfiles) roots.push(profile.watch_path, ...profile.folders);
+  roots.push(quarantineSettings.folder, classificationMode.bucket_folder);
+  return [...new Set(roots.filter(Boolean))];
+}
+
+// One-line summary of a run_diagnostics report
+export function summarizeDiagnostics(report) {
+  const count = (status) => report.checks.filter(c => c.status === status).length;
+  const errors = count("error");
+  const warnings = count("warning");
+  if (errors === 0 && warnings === 0) return `All ${report.checks.length} checks passed`;
+  const parts = [];
+  if (errors > 0) parts.push(`${errors} problem${errors === 1 ? "" : "s"}`);
+  if (warnings > 0) parts.push(`${warnings} warning${warnings === 1 ? "" : "s"}`);
+  return parts.join(", ");
+}
+
 export function escapeHtml(str) {
   const map = { "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;", "'": "&#39;" };
   return String(str).replace(/[&<>"']/g, (c) => map[c]);
@@ -39,6 +437,10 @@ export function isContentExtractable(filename) {
   return CONTENT_EXTRACTABLE_EXTENSIONS.includes(getFileExt(filename));
 }
 
+export function isArchiveFile(filename) {
+  return ARCHIVE_EXTENSIONS.includes(getFileExt(filename));
+}
+
 export function isToday(date) {
   const now = new Date();
   return (
@@ -165,7 +567,7 @@ export function getFileTypeIcon(filename) {
 // return a synthetic classification result to skip the API call.
 export function getCachedClassification(filename, correctionLog, userModules, basePath) {
   const cached = correctionLog.find(
-    c => c.filename === filename && (c.type === "accepted" || c.type === "corrected")
+    c => c.filename.normalize("NFC") === filename.normalize("NFC") && (c.type === "accepted" || c.type === "corrected")
   );
   if (!cached) return null;
   const folder = userModules.find(m => m.toLowerCase() === cached.userChose.toLowerCase());
@@ -179,10 +581,12 @@ export function getCachedClassification(filename, correctionLog, userModules, ba
 }
 
 // Match a filename against user-defined rules (glob patterns).
+// Both sides are compared in NFC, so macOS's decomposed "ü" matches a typed "ü".
 // Returns a synthetic classification result or null.
 export function matchRule(filename, rules) {
+  filename = filename.normalize("NFC");
   for (const rule of rules) {
-    const regex = new RegExp("^" + rule.pattern.replace(/[.+^${}()|[\]]/g, "\\$&").replace(/\*/g, ".*") + "$", "i");
+    const regex = new RegExp("^" + rule.pattern.normalize("NFC").replace(/[.+^${}()|[\]]/g, "\\$&").replace(/\*/g, ".*") + "$", "i");
     if (regex.test(filename)) {
       return {
         is_relevant: true,
@@ -195,6 +599,66 @@ export function matchRule(filename, rules) {
   return null;
 }
 
+// Find the watch profile for a file: the one watching its folder, or the nearest watched ancestor.
+// Returns null when no profile covers the file (the global settings apply).
+export function findWatchProfile(filePath, profiles) {
+  let best = null;
+  for (const profile of profiles) {
+    const root = profile.watch_path.replace(/[\\/]+$/, "");
+    if (!root) continue;
+    const inside = filePath === root || filePath.startsWith(root + "/") || filePath.startsWith(root + "\\");
+    if (inside && (!best || root.length > best.watch_path.length)) best = profile;
+  }
+  return best;
+}
+
+// Rules that apply under a watch profile: its own rules first, then the global ones.
+// With profileId null only the global rules apply.
+export function rulesForProfile(rules, profileId) {
+  const own = profileId == null ? [] : rules.filter(r => r.profile_id === profileId);
+  return [...own, ...rules.filter(r => r.profile_id == null)];
+}
+
+// Look up the user's preferences for a destination folder (case-insensitive path match).
+// Returns null if the folder has no preferences set.
+export function getFolderPref(folder, folderPrefs) {
+  if (!folder) return null;
+  return folderPrefs.find(p => p.folder.toLowerCase() === folder.toLowerCase()) || null;
+}
+
+// Decide whether a classified file should go to the quarantine ("Needs Review") folder.
+// Returns "unsorted" or "low_confidence", or null to handle the file normally.
+// Irrelevant files are never quarantined — they're skipped as before.
+export function getQuarantineReason(classification, quarantineSettings, threshold) {
+  if (!quarantineSettings?.enabled || !quarantineSettings.folder) return null;
+  if (!classification?.is_relevant) return null;
+  if (classification.suggested_folder === "__UNSORTED__" || !classification.suggested_folder) return "unsorted";
+  if (classification.confidence < threshold) return "low_confidence";
+  return null;
+}
+
+// Build a filename from a folder's naming template.
+// Placeholders: {name} (original name without extension), {ext}, {date} (YYYY-MM-DD), {folder}.
+// The original extension is always kept so the file still opens with the same app.
+export function applyNamingTemplate(template, filename, folder, date = new Date()) {
+  const dot = filename.lastIndexOf(".");
+  const name = dot > 0 ? filename.slice(0, dot) : filename;
+  const ext = dot > 0 ? filename.slice(dot + 1) : "";
+  const pad = (n) => String(n).padStart(2, "0");
+  const dateStr = `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+
+  const stem = template
+    .replace(/\{name\}/g, name)
+    .replace(/\{ext\}/g, ext)
+    .replace(/\{date\}/g, dateStr)
+    .replace(/\{folder\}/g, pathBasename(folder || ""))
+    .replace(/[<>:"|?*\\/]/g, "_")
+    .trim();
+
+  if (!stem) return filename;
+  return ext ? `${stem}.${ext}` : stem;
+}
+
 // Cross-platform path join: detects separator from base path and joins parts.
 // On Windows, basePath from Tauri dialogs uses backslashes; on macOS/Linux, forward slashes.
 export function pathJoin(base, ...parts) {
@@ -11,6 +11,13 @@ export default defineConfig({
   // To access the Tauri environment variables set by the CLI with information about the current target
   envPrefix: ['VITE_', 'TAURI_PLATFORM', 'TAURI_ARCH', 'TAURI_FAMILY', 'TAURI_PLATFORM_VERSION', 'TAURI_PLATFORM_TYPE', 'TAURI_DEBUG'],
   build: {
+    // The quick-sort popup is a second page, opened by Rust as its own window
+    rollupOptions: {
+      input: {
+        main: 'index.html',
+        quickSort: 'quick-sort.html',
+      },
+    },
     // Tauri uses Chromium on Windows and WebKit on macOS and Linux
     target: process.env.TAURI_PLATFORM == 'windows' ? 'chrome105' : 'safari13',
     // Don't minify for debug builds